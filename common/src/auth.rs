@@ -1,4 +1,9 @@
+use crate::i18n::{ToTranslationKey, TranslationKey};
+use crate::search_direction::SearchDirection;
+use crate::theme::Theme;
 use fluent_templates::LanguageIdentifier;
+use serde::Deserialize;
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 use std::num::NonZeroU64;
 
@@ -8,10 +13,15 @@ pub struct User {
     pub username: String,
     pub permissions: Permissions,
     pub language: LanguageIdentifier,
+    pub theme: Theme,
+    pub search_direction: SearchDirection,
+    pub unread_notifications: u64,
+    pub csrf_token: Option<String>,
 }
 
 #[cfg_attr(feature = "server", derive(clap::ValueEnum))]
-#[derive(PartialEq, Eq, Ord, PartialOrd, Clone, Copy, Debug)]
+#[derive(Deserialize, PartialEq, Eq, Ord, PartialOrd, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
 pub enum Permissions {
     /// A regular user
     User,
@@ -33,7 +43,27 @@ impl Display for Permissions {
     }
 }
 
+impl ToTranslationKey for Permissions {
+    fn translation_key(&self) -> TranslationKey<'_> {
+        TranslationKey(Cow::Owned(format!("role.{}", self.name())))
+    }
+}
+
 impl Permissions {
+    pub const ALL: [Permissions; 3] = [
+        Permissions::User,
+        Permissions::Moderator,
+        Permissions::Administrator,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Permissions::User => "user",
+            Permissions::Moderator => "moderator",
+            Permissions::Administrator => "administrator",
+        }
+    }
+
     pub fn contains(&self, other: Permissions) -> bool {
         *self >= other
     }
@@ -89,4 +119,16 @@ impl Auth {
     pub fn user_id(&self) -> Option<NonZeroU64> {
         self.user().map(|user| user.user_id)
     }
+
+    // used in templates (macros.askama.html)
+    pub fn unread_notifications(&self) -> u64 {
+        self.user()
+            .map(|user| user.unread_notifications)
+            .unwrap_or_default()
+    }
+
+    // used in templates (macros.askama.html)
+    pub fn csrf_token(&self) -> Option<&str> {
+        self.user().and_then(|user| user.csrf_token.as_deref())
+    }
 }
@@ -1,11 +1,15 @@
-use crate::database::db_impl::DbImpl;
+use crate::database::db_impl::{DbImpl, ReadOnlyDbImpl};
 use crate::i18n::I18nInfo;
 use crate::language::{
     ConjunctionFollowedBy, NounClassExt, PartOfSpeech, Transitivity, WordLinkType,
 };
+use crate::morphology::{verb_extensions, VerbDerivation};
+use crate::search_direction::SearchDirection;
 use crate::serialization::{DiscrimOutOfRange, WithDeleteSentinel};
+use crate::theme::Theme;
 use crate::types::{
-    Dataset, ExistingExample, ExistingLinkedWord, ExistingWord, PublicUserInfo, WordHit,
+    Dataset, ExistingExample, ExistingLinkedWord, ExistingWord, LinkedWordSnippet,
+    LinkedWordSnippets, PublicUserInfo, ReferenceKind, WordHit, WordReference,
 };
 use anyhow::{Context, Result};
 use askama_warp::warp;
@@ -20,17 +24,34 @@ use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Value,
 use rusqlite::{params, Row};
 use rusqlite::{OptionalExtension, ToSql};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::num::NonZeroU64;
 use std::str::FromStr;
 use tracing::{instrument, Span};
 
 #[derive(Clone)]
-pub struct DbBase(pub Pool<SqliteConnectionManager>);
+pub struct DbBase {
+    pub primary: Pool<SqliteConnectionManager>,
+    /// A read-only copy of the database, which read-heavy pages are served from if set
+    pub replica: Option<Pool<SqliteConnectionManager>>,
+}
 
 impl DbBase {
-    pub fn new(pool: Pool<SqliteConnectionManager>) -> DbBase {
-        DbBase(pool)
+    pub fn new(primary: Pool<SqliteConnectionManager>) -> DbBase {
+        DbBase {
+            primary,
+            replica: None,
+        }
+    }
+
+    pub fn with_replica(self, replica: Option<Pool<SqliteConnectionManager>>) -> DbBase {
+        DbBase { replica, ..self }
+    }
+
+    /// The pool to use for reads which can tolerate lagging slightly behind the primary
+    pub fn read_pool(&self) -> &Pool<SqliteConnectionManager> {
+        self.replica.as_ref().unwrap_or(&self.primary)
     }
 }
 
@@ -51,6 +72,17 @@ pub mod db_impl {
     impl UserAccessDb for DbImpl {}
     impl ModeratorAccessDb for DbImpl {}
     impl AdministratorAccessDb for DbImpl {}
+
+    /// Access to the read replica (or the primary, if there is none). This only ever grants
+    /// public access, so that nothing can be written through it.
+    #[derive(Clone)]
+    pub struct ReadOnlyDbImpl(pub Pool<SqliteConnectionManager>);
+
+    impl PublicAccessDb for ReadOnlyDbImpl {
+        fn get(&self) -> Result<PooledConnection<SqliteConnectionManager>, r2d2::Error> {
+            self.0.get()
+        }
+    }
 }
 
 pub trait PublicAccessDb: Clone + Send + Sync + 'static {
@@ -65,7 +97,16 @@ pub fn with_public_db(
     db: DbBase,
 ) -> impl Filter<Extract = (impl PublicAccessDb,), Error = Infallible> + Clone {
     let db_clone = db.clone();
-    warp::any().map(move || DbImpl(db_clone.0.clone()))
+    warp::any().map(move || DbImpl(db_clone.primary.clone()))
+}
+
+/// Like [`with_public_db`], but reading from the replica if one is configured. Only use this where
+/// showing slightly stale data is fine.
+pub fn with_read_only_db(
+    db: DbBase,
+) -> impl Filter<Extract = (impl PublicAccessDb,), Error = Infallible> + Clone {
+    let read_pool = db.read_pool().clone();
+    warp::any().map(move || ReadOnlyDbImpl(read_pool.clone()))
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -164,11 +205,14 @@ impl ExistingExample {
         skip(db)
     )]
     pub fn fetch_all_for_word(db: &impl PublicAccessDb, word_id: u64) -> Vec<ExistingExample> {
-        const SELECT: &str =
-            "SELECT example_id, word_id, english, xhosa FROM examples WHERE word_id = ?1;";
+        const SELECT: &str = "
+            SELECT example_id, word_id, english, xhosa, source, alignment FROM examples
+                LEFT JOIN example_sources USING (example_id)
+                WHERE word_id = ?1;
+        ";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
         let rows = query.query(params![word_id]).unwrap();
 
         #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
@@ -189,13 +233,16 @@ impl ExistingExample {
         skip(db)
     )]
     pub fn fetch(db: &impl PublicAccessDb, example_id: u64) -> Option<ExistingExample> {
-        const SELECT: &str =
-            "SELECT example_id, word_id, english, xhosa FROM examples WHERE example_id = ?1;";
+        const SELECT: &str = "
+            SELECT example_id, word_id, english, xhosa, source, alignment FROM examples
+                LEFT JOIN example_sources USING (example_id)
+                WHERE example_id = ?1;
+        ";
 
         let conn = db.get().unwrap();
         #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
         let opt = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query_row(params![example_id], |row| ExistingExample::try_from(row))
             .optional()
@@ -216,6 +263,8 @@ impl TryFrom<&Row<'_>> for ExistingExample {
             word_id: row.get("word_id")?,
             english: row.get("english")?,
             xhosa: row.get("xhosa")?,
+            source: row.get("source")?,
+            alignment: row.get("alignment")?,
         })
     }
 }
@@ -234,7 +283,7 @@ impl ExistingLinkedWord {
         ";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
         let rows = query.query(params![word_id]).unwrap();
 
         let mut vec: Vec<ExistingLinkedWord> = rows
@@ -248,6 +297,55 @@ impl ExistingLinkedWord {
         vec
     }
 
+    /// Delete every link to or from the word, returning the other words which were linked to it.
+    /// Suggestions to edit or delete the links go with them.
+    #[instrument(name = "Delete all linked words for word", fields(results), skip(db))]
+    pub fn delete_all_for_word(db: &impl ModeratorAccessDb, word_id: u64) -> Vec<u64> {
+        const DELETE_SUGGESTIONS: &str = "
+            DELETE FROM linked_word_suggestions WHERE existing_linked_word_id IN (
+                SELECT link_id FROM linked_words WHERE first_word_id = ?1 OR second_word_id = ?1
+            );
+        ";
+        const DELETE_DELETION_SUGGESTIONS: &str = "
+            DELETE FROM linked_word_deletion_suggestions WHERE linked_word_id IN (
+                SELECT link_id FROM linked_words WHERE first_word_id = ?1 OR second_word_id = ?1
+            );
+        ";
+        const DELETE: &str = "
+            DELETE FROM linked_words WHERE first_word_id = ?1 OR second_word_id = ?1
+                RETURNING first_word_id, second_word_id;
+        ";
+
+        let conn = db.get().unwrap();
+
+        for statement in [DELETE_SUGGESTIONS, DELETE_DELETION_SUGGESTIONS] {
+            conn.prepare_cached(statement)
+                .unwrap()
+                .execute(params![word_id])
+                .unwrap();
+        }
+
+        let mut others: Vec<u64> = conn
+            .prepare_cached(DELETE)
+            .unwrap()
+            .query(params![word_id])
+            .unwrap()
+            .map(|row| {
+                let (first, second): (u64, u64) =
+                    (row.get("first_word_id")?, row.get("second_word_id")?);
+                Ok(if first == word_id { second } else { first })
+            })
+            .collect()
+            .unwrap();
+
+        others.sort_unstable();
+        others.dedup();
+
+        Span::current().record("results", others.len());
+
+        others
+    }
+
     #[instrument(
         level = "trace",
         name = "Fetch existing linked word",
@@ -267,7 +365,7 @@ impl ExistingLinkedWord {
 
         let conn = db.get().unwrap();
         let opt = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query_row(params![id], |row| {
                 ExistingLinkedWord::try_from_row_populate_other(row, db, skip_populating)
@@ -280,6 +378,75 @@ impl ExistingLinkedWord {
         opt
     }
 
+    /// The plural or singular counterpart and the first alternate use of each of the words, fetched
+    /// in one query so that they can be shown alongside search results
+    #[instrument(
+        level = "trace",
+        name = "Fetch linked word snippets for words",
+        fields(words = word_ids.len(), results),
+        skip_all
+    )]
+    pub fn fetch_snippets_for_words(
+        db: &impl PublicAccessDb,
+        word_ids: &[u64],
+    ) -> HashMap<u64, LinkedWordSnippets> {
+        if word_ids.is_empty() {
+            return HashMap::new();
+        }
+
+        let placeholders = (1..=word_ids.len())
+            .map(|i| format!("?{i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // Links are stored in one direction only, so look them up from both ends
+        let select = format!(
+            "
+            SELECT first_word_id AS word_id, link_type, words.word_id AS other_id, words.xhosa
+                FROM linked_words INNER JOIN words ON words.word_id = second_word_id
+                WHERE first_word_id IN ({placeholders}) AND link_type IN (?{plural}, ?{alternate})
+            UNION ALL
+            SELECT second_word_id AS word_id, link_type, words.word_id AS other_id, words.xhosa
+                FROM linked_words INNER JOIN words ON words.word_id = first_word_id
+                WHERE second_word_id IN ({placeholders}) AND link_type IN (?{plural}, ?{alternate})
+            ORDER BY word_id, link_type, other_id;
+            ",
+            plural = word_ids.len() + 1,
+            alternate = word_ids.len() + 2,
+        );
+
+        let params = word_ids.iter().map(|id| *id as i64).chain([
+            WordLinkType::PluralOrSingular as i64,
+            WordLinkType::AlternateUse as i64,
+        ]);
+
+        let conn = db.get().unwrap();
+        // Not cached, since the query differs with the number of words
+        let mut query = conn.prepare(&select).unwrap();
+        let mut rows = query.query(rusqlite::params_from_iter(params)).unwrap();
+
+        let mut snippets: HashMap<u64, LinkedWordSnippets> = HashMap::new();
+
+        while let Some(row) = rows.next().unwrap() {
+            let word_id: u64 = row.get("word_id").unwrap();
+            let link_type: WordLinkType = row.get("link_type").unwrap();
+            let linked = &mut snippets.entry(word_id).or_default().0;
+
+            // Only the first of each type is shown, to keep the results compact
+            if linked.iter().all(|l| l.link_type != link_type) {
+                linked.push(LinkedWordSnippet {
+                    link_type,
+                    word_id: row.get("other_id").unwrap(),
+                    xhosa: row.get("xhosa").unwrap(),
+                });
+            }
+        }
+
+        Span::current().record("results", snippets.len());
+
+        snippets
+    }
+
     #[instrument(name = "Populate existing linked word", fields(link_id), skip(row, db))]
     pub fn try_from_row_populate_other(
         row: &Row<'_>,
@@ -317,6 +484,7 @@ impl ExistingWord {
             word.linked_words = ExistingLinkedWord::fetch_all_for_word(db, id);
             word.contributors = PublicUserInfo::fetch_public_contributors_for_word(db, id);
             word.datasets = Dataset::fetch_all_for_word(db, id);
+            word.references = WordReference::fetch_all_for_word(db, id);
         }
 
         Span::current().record("found", word.is_some());
@@ -334,7 +502,7 @@ impl ExistingWord {
         const SELECT_ORIGINAL: &str = "
             SELECT
                 word_id, english, xhosa, part_of_speech, xhosa_tone_markings, infinitive, is_plural,
-                is_inchoative, is_informal, transitivity, followed_by, noun_class, note
+                is_inchoative, is_informal, transitivity, followed_by, noun_class, note, is_offensive
             FROM words
             WHERE word_id = ?1;
         ";
@@ -343,7 +511,7 @@ impl ExistingWord {
 
         #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
         let opt = conn
-            .prepare(SELECT_ORIGINAL)
+            .prepare_cached(SELECT_ORIGINAL)
             .unwrap()
             .query_row(params![id], |row| ExistingWord::try_from(row))
             .optional()
@@ -359,7 +527,11 @@ impl ExistingWord {
         const DELETE: &str = "DELETE FROM words WHERE word_id = ?1;";
 
         let conn = db.get().unwrap();
-        let modified_rows = conn.prepare(DELETE).unwrap().execute(params![id]).unwrap();
+        let modified_rows = conn
+            .prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![id])
+            .unwrap();
         let found = modified_rows == 1;
         Span::current().record("found", found);
         found
@@ -371,7 +543,7 @@ impl ExistingWord {
 
         let conn = db.get().unwrap();
         let count = conn
-            .prepare(COUNT)
+            .prepare_cached(COUNT)
             .unwrap()
             .query_row(params![], |row| row.get(0))
             .unwrap();
@@ -380,6 +552,92 @@ impl ExistingWord {
 
         count
     }
+
+    /// Find the verb with the given stem, whether it is written as the bare stem, with a leading
+    /// hyphen, or only has the stem in its infinitive
+    #[instrument(name = "Fetch verb id by stem", fields(found), skip(db))]
+    pub fn fetch_verb_id(db: &impl PublicAccessDb, stem: &str) -> Option<u64> {
+        const SELECT: &str = "
+            SELECT word_id FROM words
+            WHERE part_of_speech = ?1
+                AND (xhosa = ?2 OR xhosa = '-' || ?2 OR infinitive IN ('uku' || ?2, 'ukw' || ?2))
+            LIMIT 1;
+        ";
+
+        let id = db
+            .get()
+            .unwrap()
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![PartOfSpeech::Verb, stem], |row| row.get("word_id"))
+            .optional()
+            .unwrap();
+
+        Span::current().record("found", id.is_some());
+        id
+    }
+
+    #[instrument(name = "Fetch random existing word id", fields(found), skip(db))]
+    pub fn fetch_random_id(
+        db: &impl PublicAccessDb,
+        part_of_speech: Option<PartOfSpeech>,
+        noun_class: Option<NounClass>,
+        include_offensive: bool,
+    ) -> Option<u64> {
+        const SELECT: &str = "
+            SELECT word_id FROM words
+            WHERE (?1 IS NULL OR part_of_speech = ?1) AND (?2 IS NULL OR noun_class = ?2)
+                AND (?3 OR NOT is_offensive)
+            ORDER BY RANDOM()
+            LIMIT 1;
+        ";
+
+        let conn = db.get().unwrap();
+        let id = conn
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(
+                params![
+                    part_of_speech,
+                    noun_class.map(Into::<u8>::into),
+                    include_offensive
+                ],
+                |row| row.get("word_id"),
+            )
+            .optional()
+            .unwrap();
+
+        Span::current().record("found", id.is_some());
+
+        id
+    }
+}
+
+impl VerbDerivation {
+    /// Guess which root the verb is derived from, preferring roots which are in the dictionary
+    #[instrument(name = "Analyse verb derivation", fields(found_root), skip(db))]
+    pub fn analyze(db: &impl PublicAccessDb, verb: &str) -> Option<VerbDerivation> {
+        let derivations: Vec<VerbDerivation> = verb_extensions(verb)
+            .into_iter()
+            .map(|(extension, root)| VerbDerivation {
+                extension,
+                root_word_id: ExistingWord::fetch_verb_id(db, &root),
+                root,
+            })
+            .collect();
+
+        let found = derivations
+            .iter()
+            .find(|d| d.root_word_id.is_some())
+            .or(derivations.first())
+            .cloned();
+
+        Span::current().record(
+            "found_root",
+            found.as_ref().is_some_and(|d| d.root_word_id.is_some()),
+        );
+        found
+    }
 }
 
 impl WordHit {
@@ -435,7 +693,7 @@ impl WordHit {
         // WTF rustc?
         #[allow(clippy::redundant_closure)] // implementation of FnOnce is not general enough
         let v = conn
-            .prepare(stmt)
+            .prepare_cached(stmt)
             .unwrap()
             .query_row(params![id.inner()], |row| {
                 WordHit::try_from_row_and_id(row, id)
@@ -463,7 +721,7 @@ impl PublicUserInfo {
 
         let conn = db.get().unwrap();
 
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
 
         #[allow(clippy::redundant_closure)] // lifetime issue
         query
@@ -505,7 +763,7 @@ impl Dataset {
         ";
 
         let conn = db.get()?;
-        let mut query = conn.prepare(UPSERT)?;
+        let mut query = conn.prepare_cached(UPSERT)?;
 
         let params = params![
             id,
@@ -532,7 +790,7 @@ impl Dataset {
         ";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
 
         query
             .query_row(params![id], |row| Dataset::try_from(row))
@@ -544,7 +802,7 @@ impl Dataset {
         const SELECT: &str = "DELETE FROM datasets WHERE dataset_id = ?1;";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
 
         query.execute(params![id]).unwrap() == 1
     }
@@ -555,7 +813,7 @@ impl Dataset {
         ";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
 
         #[allow(clippy::redundant_closure)] // lifetime issue
         query
@@ -571,7 +829,7 @@ impl Dataset {
         const SELECT: &str = "SELECT icon FROM datasets WHERE dataset_id = ?1;";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
         query
             .query_row(params![dataset_id], |row| row.get("icon"))
             .unwrap()
@@ -588,7 +846,7 @@ impl Dataset {
         ";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
 
         #[allow(clippy::redundant_closure)] // lifetime issue
         query
@@ -600,6 +858,59 @@ impl Dataset {
     }
 }
 
+impl WordReference {
+    #[instrument(
+        level = "trace",
+        name = "Fetch all references for word",
+        fields(results),
+        skip(db)
+    )]
+    pub fn fetch_all_for_word(db: &impl PublicAccessDb, word_id: u64) -> Vec<WordReference> {
+        const SELECT: &str = "
+            SELECT
+                reference_id, word_id, kind, author, title, year, publisher, pages, url,
+                link_broken_since IS NOT NULL AS link_broken
+            FROM word_references
+            WHERE word_id = ?1
+            ORDER BY author, year, reference_id;
+        ";
+
+        let conn = db.get().unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
+
+        #[allow(clippy::redundant_closure)] // lifetime issue
+        let references: Vec<Self> = query
+            .query(params![word_id])
+            .unwrap()
+            .map(|row| WordReference::try_from(row))
+            .collect()
+            .unwrap();
+
+        Span::current().record("results", references.len());
+
+        references
+    }
+}
+
+impl TryFrom<&Row<'_>> for WordReference {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(WordReference {
+            reference_id: row.get("reference_id")?,
+            word_id: row.get("word_id")?,
+            kind: row.get("kind")?,
+            author: row.get("author")?,
+            title: row.get("title")?,
+            year: row.get("year")?,
+            publisher: row.get("publisher")?,
+            pages: row.get("pages")?,
+            url: row.get("url")?,
+            link_broken: row.get("link_broken")?,
+        })
+    }
+}
+
 impl TryFrom<&Row<'_>> for PublicUserInfo {
     type Error = rusqlite::Error;
 
@@ -647,10 +958,12 @@ impl TryFrom<&Row<'_>> for ExistingWord {
             noun_class: row.get_with_sentinel("noun_class")?,
             note: row.get("note")?,
             is_informal: row.get("is_informal")?,
+            is_offensive: row.get("is_offensive")?,
             examples: vec![],
             linked_words: vec![],
             contributors: vec![],
             datasets: vec![],
+            references: vec![],
         })
     }
 }
@@ -710,6 +1023,48 @@ impl ToSql for WordLinkType {
     }
 }
 
+impl FromSql for ReferenceKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let v = value.as_i64()?;
+        let err = || FromSqlError::Other(Box::new(DiscrimOutOfRange(v, "ReferenceKind")));
+        Self::try_from_primitive(v.try_into().map_err(|_| err())?).map_err(|_| err())
+    }
+}
+
+impl ToSql for ReferenceKind {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(ToSqlOutput::Owned(Value::Integer(*self as u8 as i64)))
+    }
+}
+
+impl FromSql for Theme {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let v = value.as_i64()?;
+        let err = || FromSqlError::Other(Box::new(DiscrimOutOfRange(v, "Theme")));
+        Self::try_from_primitive(v.try_into().map_err(|_| err())?).map_err(|_| err())
+    }
+}
+
+impl ToSql for Theme {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(ToSqlOutput::Owned(Value::Integer(*self as u8 as i64)))
+    }
+}
+
+impl FromSql for SearchDirection {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let v = value.as_i64()?;
+        let err = || FromSqlError::Other(Box::new(DiscrimOutOfRange(v, "SearchDirection")));
+        Self::try_from_primitive(v.try_into().map_err(|_| err())?).map_err(|_| err())
+    }
+}
+
+impl ToSql for SearchDirection {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(ToSqlOutput::Owned(Value::Integer(*self as u8 as i64)))
+    }
+}
+
 impl<T> FromSql for WithDeleteSentinel<T>
 where
     T: TryFromPrimitive,
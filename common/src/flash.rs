@@ -0,0 +1,141 @@
+//! Messages shown once, at the top of the next page, after a form has been submitted. The server
+//! keeps them in a cookie for the rest of the browser session until a page has shown them.
+
+use crate::format::{escape, DisplayHtml, HtmlFormatter};
+use crate::i18n::{I18nInfo, TranslationKey};
+use askama::{Html, MarkupDisplay};
+use fluent_templates::fluent_bundle::FluentValue;
+use fluent_templates::Loader;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashLevel {
+    Success,
+    Error,
+    Info,
+}
+
+impl FlashLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlashLevel::Success => "success",
+            FlashLevel::Error => "error",
+            FlashLevel::Info => "info",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashLinkText {
+    /// Translated when the message is shown
+    Key(String),
+    /// Shown as-is, e.g a word or a URL
+    Literal(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FlashLink {
+    pub href: String,
+    pub text: FlashLinkText,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FlashMessage {
+    pub level: FlashLevel,
+    /// The message's translation key. Messages are translated when they're shown, so that they
+    /// are in the language of the page.
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<(String, String)>,
+    /// Links shown after the message, e.g to the suggestion's status
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<FlashLink>,
+}
+
+impl FlashMessage {
+    fn new(level: FlashLevel, key: &str) -> FlashMessage {
+        FlashMessage {
+            level,
+            key: key.to_owned(),
+            args: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
+    pub fn success(key: &str) -> FlashMessage {
+        FlashMessage::new(FlashLevel::Success, key)
+    }
+
+    pub fn error(key: &str) -> FlashMessage {
+        FlashMessage::new(FlashLevel::Error, key)
+    }
+
+    pub fn info(key: &str) -> FlashMessage {
+        FlashMessage::new(FlashLevel::Info, key)
+    }
+
+    pub fn with_arg(mut self, name: &str, value: impl Into<String>) -> FlashMessage {
+        self.args.push((name.to_owned(), value.into()));
+        self
+    }
+
+    pub fn with_link(mut self, href: impl Into<String>, text: FlashLinkText) -> FlashMessage {
+        self.links.push(FlashLink {
+            href: href.into(),
+            text,
+        });
+        self
+    }
+
+    /// Renders the message to HTML, so that the shared navbar template can show it without every
+    /// template having to import [`DisplayHtml`]
+    pub fn render<L: Loader + 'static>(&self, i18n_info: &I18nInfo<L>) -> String {
+        self.to_html(i18n_info).to_string()
+    }
+}
+
+impl<L: Loader + 'static> DisplayHtml<L> for FlashMessage {
+    fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        // Arguments are escaped like with `i18n_args!`, but the messages themselves may contain
+        // markup such as links
+        let args: HashMap<String, FluentValue<'static>> = self
+            .args
+            .iter()
+            .map(|(name, value)| {
+                let escaped = MarkupDisplay::new_unsafe(value, Html).to_string();
+                (name.clone(), FluentValue::String(Cow::Owned(escaped)))
+            })
+            .collect();
+        let key = TranslationKey(Cow::Borrowed(&self.key));
+        let text = f.i18n_info.translate_with(&key, &args);
+
+        write!(
+            f.fmt,
+            "<p class=\"flash {}\" role=\"status\">{text}",
+            self.level.name()
+        )?;
+
+        for link in &self.links {
+            let text = match &link.text {
+                FlashLinkText::Key(key) => {
+                    f.i18n_info.translate(&TranslationKey(Cow::Borrowed(key)))
+                }
+                FlashLinkText::Literal(text) => text.clone(),
+            };
+
+            write!(
+                f.fmt,
+                " <a href=\"{}\">{}</a>",
+                escape(&link.href),
+                escape(&text)
+            )?;
+        }
+
+        f.write_unescaped_str("</p>")
+    }
+}
@@ -1,7 +1,10 @@
 use crate::i18n::{I18nInfo, TranslationKey};
-use crate::i18n_args;
-use crate::language::{NounClassExt, NounClassPrefixes};
-use crate::types::{PublicUserInfo, WordHit};
+use crate::language::{HeadwordMatcher, NounClassExt, NounClassPrefixes};
+use crate::types::{
+    ExistingExample, ExistingWord, LinkedWordSnippet, LinkedWordSnippets, PublicUserInfo,
+    ReferenceKind, WordHit, WordReference,
+};
+use crate::{i18n_args, i18n_args_unescaped};
 use askama::{Html, MarkupDisplay};
 use compact_str::CompactString;
 use fluent_templates::fluent_bundle::FluentValue;
@@ -180,6 +183,84 @@ impl<L: Loader + 'static> DisplayHtml<L> for PublicUserInfo {
     }
 }
 
+impl<L: Loader + 'static> DisplayHtml<L> for ReferenceKind {
+    fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        let key = match self {
+            ReferenceKind::Dictionary => "reference.kind-dictionary",
+            ReferenceKind::Textbook => "reference.kind-textbook",
+            ReferenceKind::Corpus => "reference.kind-corpus",
+            ReferenceKind::Other => "reference.kind-other",
+        };
+
+        f.write_text(&TranslationKey::new(key))
+    }
+}
+
+/// Formats the reference as a citation in roughly the APA style, e.g
+/// `Author (2001). Title. Publisher, pp. 12-14.`
+impl<L: Loader + 'static> DisplayHtml<L> for WordReference {
+    fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        f.write_raw_str(&self.author)?;
+        f.write_raw_str(" (")?;
+        match self.year {
+            Some(year) => f.write_raw_str(&year.to_string())?,
+            None => f.write_text(&TranslationKey::new("reference.no-date"))?,
+        }
+        f.write_raw_str("). ")?;
+
+        if !f.plain_text {
+            f.write_unescaped_str("<cite>")?;
+        }
+        f.write_raw_str(&self.title)?;
+        if !f.plain_text {
+            f.write_unescaped_str("</cite>")?;
+        }
+
+        if let Some(publisher) = &self.publisher {
+            f.write_raw_str(". ")?;
+            f.write_raw_str(publisher)?;
+        }
+
+        if let Some(pages) = &self.pages {
+            let key = if pages.contains(['-', '–', ',']) {
+                "reference.page-range"
+            } else {
+                "reference.page"
+            };
+
+            f.write_raw_str(", ")?;
+            f.write_text_with_args(
+                &TranslationKey::new(key),
+                &i18n_args_unescaped!("pages" => pages.clone()),
+            )?;
+        }
+
+        f.write_raw_str(".")?;
+
+        if let Some(url) = &self.url {
+            f.write_raw_str(" ")?;
+            if f.plain_text {
+                f.write_raw_str(url)?;
+            } else {
+                write!(f.fmt, "<a href=\"{url}\">{url}</a>", url = escape(url))?;
+            }
+
+            if self.link_broken {
+                f.write_raw_str(" ")?;
+                if !f.plain_text {
+                    f.write_unescaped_str("<span class=\"broken_link\">")?;
+                }
+                f.write_text(&TranslationKey::new("reference.link-broken"))?;
+                if !f.plain_text {
+                    f.write_unescaped_str("</span>")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl<L: Loader + 'static, T: DisplayHtml<L>> DisplayHtml<L> for Option<T> {
     fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
         if let Some(val) = self {
@@ -198,12 +279,22 @@ impl<L: Loader + 'static> DisplayHtml<L> for NounClass {
 
 impl<L: Loader + 'static> DisplayHtml<L> for NounClassPrefixes {
     fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        // Prefixes which didn't come from a NounClass (e.g deserialized ones) have no colour
+        let badge = !f.plain_text && !self.badge_class.is_empty();
+        if badge {
+            write!(f.fmt, "<span class=\"noun_class {}\">", self.badge_class)?;
+        }
+
         f.write_noun_class_prefix(&self.singular, self.selected_singular)?;
         if let Some(plural) = self.plural.as_ref() {
             f.write_raw_str("/")?;
             f.write_noun_class_prefix(plural, !self.selected_singular)?;
         }
 
+        if badge {
+            f.write_unescaped_str("</span>")?;
+        }
+
         Ok(())
     }
 }
@@ -240,6 +331,120 @@ impl<L: Loader + 'static> DisplayHtml<L> for HyperlinkWrapper<'_> {
     }
 }
 
+impl<L: Loader + 'static> DisplayHtml<L> for LinkedWordSnippet {
+    fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        self.link_type.fmt(f)?;
+        f.write_raw_str(": ")?;
+        f.write_unescaped_str("<span lang=\"")?;
+        f.write_text(&TranslationKey::new("target-language-code"))?;
+        f.write_unescaped_str("\">")?;
+        f.write_raw_str(&self.xhosa)?;
+        f.write_unescaped_str("</span>")
+    }
+}
+
+impl<L: Loader + 'static> DisplayHtml<L> for LinkedWordSnippets {
+    fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        f.write_unescaped_str("<span class=\"linked_words\">")?;
+        f.join_if_non_empty("; ", self.0.iter().map(|l| l as &dyn DisplayHtml<L>))?;
+        f.write_unescaped_str("</span>")
+    }
+}
+
+/// Which words of an English sentence and its isiXhosa translation are translations of each other,
+/// written as space-separated `english-xhosa` pairs of word indices counting from 0, e.g `0-1 2-0`.
+/// The words of a sentence are separated by whitespace.
+pub struct Alignment(Vec<(usize, usize)>);
+
+impl Alignment {
+    /// Returns `None` if the alignment is malformed or refers to words past the end of either
+    /// sentence
+    pub fn parse(alignment: &str, english: &str, xhosa: &str) -> Option<Alignment> {
+        let english_len = english.split_whitespace().count();
+        let xhosa_len = xhosa.split_whitespace().count();
+
+        let pairs = alignment
+            .split_whitespace()
+            .map(|pair| {
+                let (english, xhosa) = pair.split_once('-')?;
+                let (english, xhosa) = (english.parse().ok()?, xhosa.parse().ok()?);
+                (english < english_len && xhosa < xhosa_len).then_some((english, xhosa))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        (!pairs.is_empty()).then_some(Alignment(pairs))
+    }
+}
+
+/// A sentence with some of its words highlighted
+pub struct HighlightedSentence<'a>(Vec<(&'a str, bool)>);
+
+impl<L: Loader + 'static> DisplayHtml<L> for HighlightedSentence<'_> {
+    fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        for (i, (word, highlighted)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_raw_str(" ")?;
+            }
+
+            if *highlighted && !f.plain_text {
+                f.write_unescaped_str("<mark>")?;
+                f.write_raw_str(word)?;
+                f.write_unescaped_str("</mark>")?;
+            } else {
+                f.write_raw_str(word)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An example from a parallel corpus with the headword highlighted in the isiXhosa sentence, and
+/// the words aligned with it highlighted in the English sentence
+pub struct AlignedExample<'a> {
+    pub english: HighlightedSentence<'a>,
+    pub xhosa: HighlightedSentence<'a>,
+}
+
+impl ExistingExample {
+    /// Returns `None` unless the example has a valid alignment and the headword is found in it
+    pub fn aligned(&self, word: &ExistingWord) -> Option<AlignedExample<'_>> {
+        let alignment = Alignment::parse(self.alignment.as_deref()?, &self.english, &self.xhosa)?;
+        let matcher = HeadwordMatcher::new(&word.xhosa, word.part_of_speech)?;
+
+        let xhosa: Vec<(&str, bool)> = self
+            .xhosa
+            .split_whitespace()
+            .map(|w| (w, matcher.matches(w)))
+            .collect();
+
+        if !xhosa.iter().any(|(_, highlighted)| *highlighted) {
+            return None;
+        }
+
+        let mut english: Vec<(&str, bool)> = self
+            .english
+            .split_whitespace()
+            .map(|w| (w, false))
+            .collect();
+
+        for &(e, x) in &alignment.0 {
+            if xhosa[x].1 {
+                english[e].1 = true;
+            }
+        }
+
+        Some(AlignedExample {
+            english: HighlightedSentence(english),
+            xhosa: HighlightedSentence(xhosa),
+        })
+    }
+}
+
 fn is_not_isolator_or_whitespace(c: char) -> bool {
     !c.is_whitespace() && !('\u{2066}'..='\u{206f}').contains(&c)
 }
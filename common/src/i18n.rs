@@ -1,3 +1,7 @@
+use crate::flash::FlashMessage;
+use crate::language::LanguageProfile;
+use crate::search_direction::SearchDirection;
+use crate::theme::Theme;
 use fluent_templates::fluent_bundle::FluentValue;
 use fluent_templates::fs::langid;
 use fluent_templates::{LanguageIdentifier, Loader};
@@ -12,6 +16,9 @@ pub struct SiteContext<L> {
     pub site_i18n: L,
     pub supported_langs: &'static [&'static str],
     pub host: String,
+    pub language: LanguageProfile,
+    /// Whether school mode is on for everyone, rather than only for those who turn it on
+    pub school_mode: bool,
 }
 
 #[derive(Ord, PartialOrd, Eq, PartialEq)]
@@ -95,6 +102,16 @@ macro_rules! i18n_args_unescaped {
 
 pub struct I18nInfo<L> {
     pub user_language: LanguageIdentifier,
+    pub theme: Theme,
+    pub search_direction: SearchDirection,
+    /// Whether entries flagged as vulgar or offensive are hidden from search, browsing, and random
+    /// words, either for the whole site or for this browser session
+    pub school_mode: bool,
+    /// Messages left for the page by the form which was just submitted
+    pub flashes: Vec<FlashMessage>,
+    /// The nonce which inline scripts must carry to be allowed to run by the content security
+    /// policy. This is empty outside of a request, in which case no inline scripts are allowed.
+    pub csp_nonce: String,
     pub ctx: Arc<SiteContext<L>>,
 }
 
@@ -136,6 +153,11 @@ impl<L> Clone for I18nInfo<L> {
     fn clone(&self) -> Self {
         I18nInfo {
             user_language: self.user_language.clone(),
+            theme: self.theme,
+            search_direction: self.search_direction,
+            school_mode: self.school_mode,
+            flashes: self.flashes.clone(),
+            csp_nonce: self.csp_nonce.clone(),
             ctx: self.ctx.clone(),
         }
     }
@@ -163,6 +185,17 @@ impl<L: Loader + 'static> I18nInfo<L> {
             "preposition",
             "ideophone",
             "bound_morpheme",
+            "verb.capitalised",
+            "noun.capitalised",
+            "adjective.capitalised",
+            "adverb.capitalised",
+            "relative.capitalised",
+            "interjection.capitalised",
+            "conjunction.capitalised",
+            "preposition.capitalised",
+            "ideophone.capitalised",
+            "boundmorpheme.capitalised",
+            "search.no-part-of-speech",
             "linked-words.choose",
             "linked-words.search",
             "linked-words.plurality",
@@ -184,6 +217,9 @@ impl<L: Loader + 'static> Debug for I18nInfo<L> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("I18nInfo")
             .field("user_language", &self.user_language)
+            .field("theme", &self.theme)
+            .field("search_direction", &self.search_direction)
+            .field("flashes", &self.flashes)
             .finish()
     }
 }
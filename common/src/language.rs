@@ -24,6 +24,7 @@ use std::str::FromStr;
 )]
 #[repr(u8)]
 #[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub enum PartOfSpeech {
     Verb = 1,
     Noun = 2,
@@ -45,6 +46,19 @@ impl PartOfSpeech {
 }
 
 impl PartOfSpeech {
+    pub const ALL: [PartOfSpeech; 10] = [
+        PartOfSpeech::Verb,
+        PartOfSpeech::Noun,
+        PartOfSpeech::Adjective,
+        PartOfSpeech::Adverb,
+        PartOfSpeech::Relative,
+        PartOfSpeech::Interjection,
+        PartOfSpeech::Conjunction,
+        PartOfSpeech::Preposition,
+        PartOfSpeech::Ideophone,
+        PartOfSpeech::BoundMorpheme,
+    ];
+
     pub fn name(&self) -> String {
         match self {
             PartOfSpeech::BoundMorpheme => "bound_morpheme".to_owned(),
@@ -130,6 +144,7 @@ impl FromStr for ConjunctionFollowedBy {
 )]
 #[repr(u8)]
 #[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub enum Transitivity {
     Transitive,
     Intransitive,
@@ -210,10 +225,16 @@ impl FromStr for Transitivity {
 
 /// Noun class prefixes with singular and plural
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct NounClassPrefixes {
     pub selected_singular: bool,
+    #[cfg_attr(feature = "server", schema(value_type = String))]
     pub singular: Cow<'static, str>,
+    #[cfg_attr(feature = "server", schema(value_type = Option<String>))]
     pub plural: Option<Cow<'static, str>>,
+    /// The CSS class which the prefixes are coloured by. See [`NounClassExt::badge_class`].
+    #[serde(skip)]
+    pub badge_class: &'static str,
 }
 
 impl NounClassPrefixes {
@@ -226,6 +247,7 @@ impl NounClassPrefixes {
             selected_singular,
             singular: Cow::Borrowed(singular),
             plural: Some(Cow::Borrowed(plural)),
+            badge_class: "",
         }
     }
 
@@ -234,6 +256,344 @@ impl NounClassPrefixes {
             selected_singular: true,
             singular: Cow::Borrowed(singular),
             plural: None,
+            badge_class: "",
+        }
+    }
+}
+
+const NOUN_CLASSES: [NounClass; 15] = [
+    NounClass::Class1Um,
+    NounClass::Aba,
+    NounClass::U,
+    NounClass::Oo,
+    NounClass::Class3Um,
+    NounClass::Imi,
+    NounClass::Ili,
+    NounClass::Ama,
+    NounClass::Isi,
+    NounClass::Izi,
+    NounClass::In,
+    NounClass::Izin,
+    NounClass::Ulu,
+    NounClass::Ubu,
+    NounClass::Uku,
+];
+
+/// The parts of the dictionary which depend on the language it is for, so that the same server can
+/// run a dictionary for another Nguni language. Loaded from the site's config at startup.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LanguageProfile {
+    /// Whose rules are followed for noun prefixes, spelling, and searching. The search index has to
+    /// be rebuilt (e.g by deleting it) after this is changed on an existing site.
+    #[serde(default)]
+    pub rules: NguniLanguage,
+    /// Replaces the name of the site given in its translations, e.g `IsiXhosa.click Live Dictionary`
+    #[serde(default)]
+    pub site_name: Option<String>,
+    /// Replaces the short name of the site given in its translations, e.g `IsiXhosa.click`
+    #[serde(default)]
+    pub short_name: Option<String>,
+    /// Replaces the name of the language given in the site's translations, e.g `IsiXhosa`
+    #[serde(default)]
+    pub language_name: Option<String>,
+    /// Replaces the BCP 47 code of the language given in the site's translations, e.g `xh`
+    #[serde(default)]
+    pub language_code: Option<String>,
+    /// The noun classes which nouns can be put in, in the order they are offered
+    #[serde(default = "default_noun_classes")]
+    pub noun_classes: Vec<NounClass>,
+}
+
+fn default_noun_classes() -> Vec<NounClass> {
+    NOUN_CLASSES.to_vec()
+}
+
+impl Default for LanguageProfile {
+    fn default() -> Self {
+        LanguageProfile {
+            rules: NguniLanguage::default(),
+            site_name: None,
+            short_name: None,
+            language_name: None,
+            language_code: None,
+            noun_classes: default_noun_classes(),
+        }
+    }
+}
+
+impl LanguageProfile {
+    /// Guess the singular or plural of a noun, e.g umntu for abantu, by swapping the prefix of each
+    /// class which it could be in, or only of its class if that is known. The guesses are often
+    /// wrong, so they should only be used to look for existing words.
+    pub fn guess_other_number(&self, xhosa: &str, class: Option<NounClass>) -> Vec<String> {
+        let classes = match class {
+            Some(class) => vec![class],
+            None => self.noun_classes.clone(),
+        };
+
+        let xhosa = xhosa.trim().to_lowercase();
+        let mut guesses: Vec<String> = classes
+            .into_iter()
+            .flat_map(|class| {
+                [
+                    self.rules.pluralize(class, &xhosa),
+                    self.rules.singularize(class, &xhosa),
+                ]
+            })
+            .flatten()
+            .collect();
+
+        guesses.sort();
+        guesses.dedup();
+        guesses
+    }
+
+    /// Fluent messages replacing those in the site's translations with what this profile sets, so
+    /// that they are used everywhere the translations refer to them
+    pub fn fluent_overrides(&self) -> String {
+        // Braces are the only special characters in the text of a message, and it must fit on one line
+        let escape = |text: &str| {
+            text.trim()
+                .chars()
+                .map(|c| match c {
+                    '{' => "{\"{\"}".to_owned(),
+                    '}' => "{\"}\"}".to_owned(),
+                    '\n' | '\r' => " ".to_owned(),
+                    c => c.to_string(),
+                })
+                .collect::<String>()
+        };
+
+        [
+            ("-site-name", &self.site_name),
+            ("-site-short-name", &self.short_name),
+            ("target-language", &self.language_name),
+            ("target-language-code", &self.language_code),
+        ]
+        .into_iter()
+        .filter_map(|(id, value)| Some(format!("{id} = {}\n", escape(value.as_deref()?))))
+        .collect()
+    }
+
+    /// Whether nouns can be put in the class on this site
+    pub fn has_noun_class(&self, class: NounClass) -> bool {
+        self.noun_classes.contains(&class)
+    }
+}
+
+/// A language which a site's dictionary can be for
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NguniLanguage {
+    #[default]
+    Xhosa,
+    Zulu,
+}
+
+impl NguniLanguage {
+    /// Guess the plural of a singular noun in the class. See [`NounClassExt::pluralize`].
+    pub fn pluralize(&self, class: NounClass, singular: &str) -> Option<String> {
+        match self {
+            NguniLanguage::Xhosa => class.pluralize(singular),
+            NguniLanguage::Zulu => zulu::pluralize(class, singular),
+        }
+    }
+
+    /// Guess the singular of a plural noun in the class. See [`NounClassExt::singularize`].
+    pub fn singularize(&self, class: NounClass, plural: &str) -> Option<String> {
+        match self {
+            NguniLanguage::Xhosa => class.singularize(plural),
+            NguniLanguage::Zulu => zulu::singularize(class, plural),
+        }
+    }
+
+    /// How the class is labelled where it is chosen, e.g in the submit form
+    pub fn noun_class_label(&self, class: NounClass) -> &'static str {
+        match self {
+            NguniLanguage::Xhosa => class.form_label(),
+            NguniLanguage::Zulu => zulu::noun_class_label(class),
+        }
+    }
+
+    /// What a noun is indexed as for searching, so that it can be found without its prefix
+    pub fn noun_base(&self, noun: &str, class: Option<NounClass>) -> String {
+        match self {
+            NguniLanguage::Xhosa => isixhosa::noun::guess_noun_base(noun, class),
+            NguniLanguage::Zulu => zulu::noun_base(noun, class),
+        }
+    }
+
+    /// Whether a headword is written with only the letters of the language's orthography
+    pub fn is_valid_spelling(&self, headword: &str) -> bool {
+        match self {
+            // Entries in isiXhosa have long included older spellings and loanwords as they are
+            // written, so they aren't checked
+            NguniLanguage::Xhosa => true,
+            NguniLanguage::Zulu => zulu::is_valid_spelling(headword),
+        }
+    }
+}
+
+/// Where isiZulu differs from isiXhosa. The noun classes are the same, but some prefixes differ,
+/// e.g class 2a is o- rather than oo-, and class 10 is izin- rather than iin-.
+mod zulu {
+    use isixhosa::noun::NounClass;
+
+    /// Monosyllabic stems keep the full prefix, e.g umuntu but umfundi
+    fn is_monosyllabic(stem: &str) -> bool {
+        stem.chars().filter(|c| "aeiou".contains(*c)).count() <= 1
+    }
+
+    pub fn pluralize(class: NounClass, singular: &str) -> Option<String> {
+        use NounClass::*;
+
+        let word = singular.trim();
+        let swap = |from: &str, to: &str| word.strip_prefix(from).map(|stem| format!("{to}{stem}"));
+
+        match class {
+            Class1Um => swap("umu", "aba").or_else(|| swap("um", "aba")),
+            U => swap("u", "o"),
+            Class3Um => swap("umu", "imi").or_else(|| swap("um", "imi")),
+            Ili => swap("ili", "ama").or_else(|| swap("i", "ama")),
+            Isi => swap("is", "iz"),
+            // e.g inja -> izinja, imbuzi -> izimbuzi
+            In => swap("i", "izi"),
+            _ => None,
+        }
+        .filter(|plural| plural != word)
+    }
+
+    pub fn singularize(class: NounClass, plural: &str) -> Option<String> {
+        use NounClass::*;
+
+        let word = plural.trim();
+        let swap = |from: &str, to: &str| word.strip_prefix(from).map(|stem| format!("{to}{stem}"));
+        let by_syllables = |from: &str, long: &str, short: &str| {
+            let stem = word.strip_prefix(from)?;
+            let to = if is_monosyllabic(stem) { long } else { short };
+            Some(format!("{to}{stem}"))
+        };
+
+        match class {
+            // e.g abantu -> umuntu, abafundi -> umfundi
+            Aba => by_syllables("aba", "umu", "um"),
+            Oo => swap("o", "u"),
+            Imi => by_syllables("imi", "umu", "um"),
+            // e.g amazwe -> ilizwe, amagama -> igama
+            Ama => by_syllables("ama", "ili", "i"),
+            Izi => swap("iz", "is"),
+            Izin => swap("izi", "i"),
+            _ => None,
+        }
+        .filter(|singular| singular != word)
+    }
+
+    pub fn noun_class_label(class: NounClass) -> &'static str {
+        use NounClass::*;
+
+        match class {
+            Class1Um => "um(u) (aba)",
+            Aba => "aba",
+            U => "u",
+            Oo => "o",
+            Class3Um => "um(u) (imi)",
+            Imi => "imi",
+            Ili => "i(li)",
+            Ama => "ama",
+            Isi => "isi",
+            Izi => "izi",
+            In => "i(n)",
+            Izin => "izi(n)",
+            Ulu => "u(lu)",
+            Ubu => "u(bu)",
+            Uku => "uku",
+        }
+    }
+
+    /// The prefixes of each class, longest first so that the whole prefix is removed
+    fn prefixes(class: NounClass) -> &'static [&'static str] {
+        use NounClass::*;
+
+        match class {
+            Class1Um | Class3Um => &["umu", "um"],
+            Aba => &["aba", "abe"],
+            U => &["u"],
+            Oo => &["o"],
+            Imi => &["imi"],
+            Ili => &["ili", "i"],
+            Ama => &["ama", "ame"],
+            Isi => &["isi", "is"],
+            Izi => &["izi", "iz"],
+            In => &["in", "im", "i"],
+            Izin => &["izin", "izim", "izi"],
+            Ulu => &["ulu", "u"],
+            Ubu => &["ubu", "u"],
+            Uku => &["uku", "ukw"],
+        }
+    }
+
+    pub fn noun_base(noun: &str, class: Option<NounClass>) -> String {
+        let noun = noun.trim().to_lowercase();
+
+        // Without a class, guessing which prefix to remove does more harm than good
+        let Some(class) = class else {
+            return noun;
+        };
+
+        prefixes(class)
+            .iter()
+            .find_map(|prefix| noun.strip_prefix(prefix).filter(|stem| !stem.is_empty()))
+            .unwrap_or(&noun)
+            .to_owned()
+    }
+
+    /// IsiZulu is written with the letters of the Latin alphabet and no diacritics. Tone is marked
+    /// in a separate field.
+    pub fn is_valid_spelling(headword: &str) -> bool {
+        headword
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || matches!(c, ' ' | '-' | '\'' | '(' | ')'))
+    }
+}
+
+/// Picks out the words of an isiXhosa sentence which are forms of a headword
+#[derive(Clone, Debug)]
+pub struct HeadwordMatcher {
+    needle: String,
+    whole_word: bool,
+}
+
+impl HeadwordMatcher {
+    /// Returns `None` if the headword is too short to be found reliably
+    pub fn new(xhosa: &str, part_of_speech: Option<PartOfSpeech>) -> Option<HeadwordMatcher> {
+        let xhosa = xhosa.trim().to_lowercase();
+
+        // Verbs are conjugated, so only the stem is looked for, and it can be anywhere in a word
+        let (needle, whole_word) = match part_of_speech {
+            Some(PartOfSpeech::Verb) => (xhosa.strip_suffix('a').unwrap_or(&xhosa), false),
+            _ => (xhosa.as_str(), true),
+        };
+
+        if needle.chars().count() < 2 {
+            return None;
+        }
+
+        Some(HeadwordMatcher {
+            needle: needle.to_owned(),
+            whole_word,
+        })
+    }
+
+    /// Whether the word is a form of the headword, ignoring case and surrounding punctuation
+    pub fn matches(&self, word: &str) -> bool {
+        let word = word
+            .trim_matches(|c: char| !c.is_alphabetic())
+            .to_lowercase();
+
+        if self.whole_word {
+            word == self.needle
+        } else {
+            word.contains(&self.needle)
         }
     }
 }
@@ -241,6 +601,23 @@ impl NounClassPrefixes {
 pub trait NounClassExt {
     fn to_prefixes(&self) -> NounClassPrefixes;
     fn as_u8(&self) -> u8;
+    /// The plural class of a singular class, if it has one
+    fn plural(&self) -> Option<NounClass>;
+    /// Guess the plural of a singular noun in this class by swapping its prefix. Returns `None` if
+    /// the class has no plural or the noun doesn't start with the class's prefix.
+    fn pluralize(&self, singular: &str) -> Option<String>;
+    /// Guess the singular of a plural noun in this class by swapping its prefix. Returns `None` if
+    /// the class is singular or the noun doesn't start with the class's prefix.
+    fn singularize(&self, plural: &str) -> Option<String>;
+    /// The concords which words agreeing with a noun in this class take
+    fn concords(&self) -> concords::Concords;
+    /// The CSS class which gives the class its colour wherever it is shown. A singular class and
+    /// its plural share a colour, so that the pairs are easy to pick out.
+    fn badge_class(&self) -> &'static str;
+    /// The value which forms submit for the class
+    fn form_value(&self) -> &'static str;
+    /// How the class is labelled where it is chosen, e.g in the submit form
+    fn form_label(&self) -> &'static str;
 }
 
 impl NounClassExt for NounClass {
@@ -250,7 +627,7 @@ impl NounClassExt for NounClass {
         let both = NounClassPrefixes::from_singular_plural;
         let singular = NounClassPrefixes::singular_class;
 
-        match self {
+        let prefixes = match self {
             Class1Um | Aba => both(*self == Class1Um, "um", "aba"),
             U | Oo => both(*self == U, "u", "oo"),
             Class3Um | Imi => both(*self == Class3Um, "um", "imi"),
@@ -260,6 +637,11 @@ impl NounClassExt for NounClass {
             Ulu => singular("ulu"),
             Ubu => singular("ubu"),
             Uku => singular("uku"),
+        };
+
+        NounClassPrefixes {
+            badge_class: self.badge_class(),
+            ..prefixes
         }
     }
 
@@ -267,6 +649,185 @@ impl NounClassExt for NounClass {
     fn as_u8(&self) -> u8 {
         *self as u8
     }
+
+    fn plural(&self) -> Option<NounClass> {
+        use NounClass::*;
+
+        match self {
+            Class1Um => Some(Aba),
+            U => Some(Oo),
+            Class3Um => Some(Imi),
+            Ili => Some(Ama),
+            Isi => Some(Izi),
+            In => Some(Izin),
+            _ => None,
+        }
+    }
+
+    fn pluralize(&self, singular: &str) -> Option<String> {
+        use NounClass::*;
+
+        let word = singular.trim();
+        let swap = |from: &str, to: &str| word.strip_prefix(from).map(|stem| format!("{to}{stem}"));
+
+        match self {
+            Class1Um => swap("um", "aba"),
+            U => swap("u", "oo"),
+            Class3Um => swap("um", "imi"),
+            // The li is usually dropped in the singular, e.g igama -> amagama
+            Ili => swap("ili", "ama").or_else(|| swap("i", "ama")),
+            // Also covers is- before vowels, e.g isandla -> izandla
+            Isi => swap("is", "iz"),
+            In => {
+                let stem = word.strip_prefix('i')?;
+                let syllables = stem.chars().filter(|c| "aeiou".contains(*c)).count();
+
+                // Monosyllabic stems take izin, e.g inja -> izinja, but the rest take iin, e.g
+                // inkomo -> iinkomo
+                if syllables <= 1 {
+                    Some(format!("iz{word}"))
+                } else {
+                    Some(format!("i{word}"))
+                }
+            }
+            _ => None,
+        }
+        .filter(|plural| plural != word)
+    }
+
+    fn singularize(&self, plural: &str) -> Option<String> {
+        use NounClass::*;
+
+        let word = plural.trim();
+        let swap = |from: &str, to: &str| word.strip_prefix(from).map(|stem| format!("{to}{stem}"));
+
+        match self {
+            Aba => swap("aba", "um"),
+            Oo => swap("oo", "u"),
+            Imi => swap("imi", "um"),
+            Ama => swap("ama", "i"),
+            Izi => swap("iz", "is"),
+            // e.g izinja -> inja, iinkomo -> inkomo
+            Izin => swap("izi", "i").or_else(|| swap("ii", "i")),
+            _ => None,
+        }
+        .filter(|singular| singular != word)
+    }
+
+    fn concords(&self) -> concords::Concords {
+        concords::of(*self)
+    }
+
+    fn badge_class(&self) -> &'static str {
+        use NounClass::*;
+
+        match self {
+            Class1Um | Aba => "noun_class_1_2",
+            U | Oo => "noun_class_1a_2a",
+            Class3Um | Imi => "noun_class_3_4",
+            Ili | Ama => "noun_class_5_6",
+            Isi | Izi => "noun_class_7_8",
+            In | Izin => "noun_class_9_10",
+            Ulu => "noun_class_11",
+            Ubu => "noun_class_14",
+            Uku => "noun_class_15",
+        }
+    }
+
+    fn form_value(&self) -> &'static str {
+        use NounClass::*;
+
+        match self {
+            Class1Um => "Class1Um",
+            Aba => "Aba",
+            U => "U",
+            Oo => "Oo",
+            Class3Um => "Class3Um",
+            Imi => "Imi",
+            Ili => "Ili",
+            Ama => "Ama",
+            Isi => "Isi",
+            Izi => "Izi",
+            In => "In",
+            Izin => "Izin",
+            Ulu => "Ulu",
+            Ubu => "Ubu",
+            Uku => "Uku",
+        }
+    }
+
+    fn form_label(&self) -> &'static str {
+        use NounClass::*;
+
+        match self {
+            Class1Um => "um (aba)",
+            Aba => "aba",
+            U => "u",
+            Oo => "oo",
+            Class3Um => "um (imi)",
+            Imi => "imi",
+            Ili => "i(li)",
+            Ama => "ama",
+            Isi => "isi",
+            Izi => "izi",
+            In => "i(n)",
+            Izin => "i(z)i(n)",
+            Ulu => "ulu",
+            Ubu => "ubu",
+            Uku => "uku",
+        }
+    }
+}
+
+/// The agreement concords of each noun class. Verbs, possessives, and demonstratives agree with the
+/// noun they refer to by taking the concord of its class, e.g umntu uyahamba but abantu bayahamba.
+pub mod concords {
+    use isixhosa::noun::NounClass;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Copy, Clone, Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
+    pub struct Concords {
+        /// Prefixed to verbs of which the noun is the subject, e.g si- in sihamba
+        pub subject: &'static str,
+        /// Comes right before the verb stem when the noun is the object, e.g -si- in ndisibona
+        pub object: &'static str,
+        /// Prefixed to the possessor, e.g sa- in isitya sam
+        pub possessive: &'static str,
+        /// The demonstratives for this, that, and that over there, by distance from the speaker
+        pub demonstratives: [&'static str; 3],
+    }
+
+    const fn concords(
+        subject: &'static str,
+        object: &'static str,
+        possessive: &'static str,
+        demonstratives: [&'static str; 3],
+    ) -> Concords {
+        Concords {
+            subject,
+            object,
+            possessive,
+            demonstratives,
+        }
+    }
+
+    pub fn of(class: NounClass) -> Concords {
+        use NounClass::*;
+
+        match class {
+            Class1Um | U => concords("u", "m", "wa", ["lo", "lowo", "lowa"]),
+            Aba | Oo => concords("ba", "ba", "ba", ["aba", "abo", "abaya"]),
+            Class3Um => concords("u", "wu", "wa", ["lo", "lowo", "lowa"]),
+            Imi | In => concords("i", "yi", "ya", ["le", "leyo", "leya"]),
+            Ili => concords("li", "li", "la", ["eli", "elo", "eliya"]),
+            Ama => concords("a", "wa", "a", ["la", "lawo", "lawa"]),
+            Isi => concords("si", "si", "sa", ["esi", "eso", "esiya"]),
+            Izi | Izin => concords("zi", "zi", "za", ["ezi", "ezo", "eziya"]),
+            Ulu => concords("lu", "lu", "lwa", ["olu", "olo", "oluya"]),
+            Ubu => concords("bu", "bu", "ba", ["obu", "obo", "obuya"]),
+            Uku => concords("ku", "ku", "kwa", ["oku", "oko", "okuya"]),
+        }
+    }
 }
 
 #[derive(
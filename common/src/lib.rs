@@ -1,8 +1,13 @@
 pub mod auth;
+pub mod flash;
 pub mod format;
 pub mod language;
+pub mod morphology;
+pub mod normalize;
+pub mod search_direction;
 pub mod serialization;
 pub mod templates;
+pub mod theme;
 pub mod types;
 
 pub mod i18n;
@@ -0,0 +1,528 @@
+//! Rule-based analysis of isiXhosa word forms. This only guesses at the structure of a word from
+//! its shape, so results are shown as probable and never relied upon.
+
+use crate::format::{DisplayHtml, HtmlFormatter};
+use crate::i18n::TranslationKey;
+use fluent_templates::Loader;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::iter;
+
+/// The shortest a verb stem can be (not counting the final vowel) once an extension is removed.
+/// This keeps short roots such as -lwa (fight) from being mistaken for passives.
+const MIN_ROOT_LEN: usize = 2;
+
+/// A verb extension, which derives a new verb from a root by adding a suffix before the final
+/// vowel
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerbExtension {
+    /// -isa, e.g fundisa (teach) from funda (learn)
+    Causative,
+    /// -ela, e.g thengela (buy for) from thenga (buy)
+    Applicative,
+    /// -wa, e.g bonwa (be seen) from bona (see)
+    Passive,
+    /// -ana, e.g bonana (see each other) from bona (see)
+    Reciprocal,
+}
+
+impl VerbExtension {
+    /// The suffixes for the extension, including the final vowel, with the longest first
+    fn suffixes(&self) -> &'static [&'static str] {
+        match self {
+            VerbExtension::Causative => &["isa", "esa"],
+            VerbExtension::Applicative => &["ela", "ila"],
+            VerbExtension::Passive => &["iwa", "wa"],
+            VerbExtension::Reciprocal => &["ana"],
+        }
+    }
+}
+
+impl<L: Loader + 'static> DisplayHtml<L> for VerbExtension {
+    fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        let s = match self {
+            VerbExtension::Causative => TranslationKey::new("verb-extension.causative"),
+            VerbExtension::Applicative => TranslationKey::new("verb-extension.applicative"),
+            VerbExtension::Passive => TranslationKey::new("verb-extension.passive"),
+            VerbExtension::Reciprocal => TranslationKey::new("verb-extension.reciprocal"),
+        };
+
+        f.write_text(&s)
+    }
+}
+
+/// A verb which is probably derived from a root by an extension
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VerbDerivation {
+    pub extension: VerbExtension,
+    /// The probable root, as a bare stem ending in -a
+    pub root: String,
+    /// The root's entry in the dictionary, if there is one
+    pub root_word_id: Option<u64>,
+}
+
+/// Strips the infinitive prefix and any leading hyphen, so that ukuhamba, -hamba, and hamba are
+/// all treated as hamba
+pub fn verb_stem(verb: &str) -> &str {
+    let verb = verb.trim().trim_start_matches('-');
+
+    verb.strip_prefix("uku")
+        .or_else(|| verb.strip_prefix("ukw"))
+        .unwrap_or(verb)
+}
+
+/// The extensions which the verb might carry, each with the root it would be derived from. Verbs
+/// which only happen to end like an extension match too, so these are candidates to be checked
+/// against the dictionary rather than a definite analysis.
+pub fn verb_extensions(verb: &str) -> Vec<(VerbExtension, String)> {
+    use VerbExtension::*;
+
+    let stem = verb_stem(verb).to_lowercase();
+
+    [Causative, Applicative, Reciprocal, Passive]
+        .into_iter()
+        .filter_map(|extension| {
+            extension.suffixes().iter().find_map(|suffix| {
+                let root = stem.strip_suffix(suffix)?;
+                (root.chars().count() >= MIN_ROOT_LEN).then(|| (extension, format!("{root}a")))
+            })
+        })
+        .collect()
+}
+
+/// Subject concords, longest first so that e.g ndi is tried before i
+const SUBJECT_CONCORDS: &[&str] = &[
+    "ndi", "si", "ni", "ba", "li", "zi", "lu", "bu", "ku", "ka", "u", "i", "a",
+];
+
+/// Tense and aspect markers which come after the subject concord
+const TENSE_MARKERS: &[&str] = &["zaku", "yaku", "za", "zo", "ya", "sa", "be"];
+
+/// Object concords which come right before the verb stem
+const OBJECT_CONCORDS: &[&str] = &[
+    "ndi", "ku", "si", "ni", "ba", "wu", "yi", "li", "wa", "zi", "lu", "bu", "m",
+];
+
+/// The role of a morpheme found by [`analyze_verb`]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MorphemeKind {
+    /// The uku- of the infinitive
+    Infinitive,
+    /// The a- which begins most negative forms
+    Negative,
+    SubjectConcord,
+    /// A subject concord merged with the -a- of the remote past, e.g nda- in ndahamba
+    RemotePast,
+    TenseMarker,
+    ObjectConcord,
+    Root,
+    /// A suffix replacing the final -a, e.g the perfect -ile or the negative -i
+    Ending,
+}
+
+impl<L: Loader + 'static> DisplayHtml<L> for MorphemeKind {
+    fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        let s = match self {
+            MorphemeKind::Infinitive => TranslationKey::new("morpheme.infinitive"),
+            MorphemeKind::Negative => TranslationKey::new("morpheme.negative"),
+            MorphemeKind::SubjectConcord => TranslationKey::new("morpheme.subject-concord"),
+            MorphemeKind::RemotePast => TranslationKey::new("morpheme.remote-past"),
+            MorphemeKind::TenseMarker => TranslationKey::new("morpheme.tense-marker"),
+            MorphemeKind::ObjectConcord => TranslationKey::new("morpheme.object-concord"),
+            MorphemeKind::Root => TranslationKey::new("morpheme.root"),
+            MorphemeKind::Ending => TranslationKey::new("morpheme.ending"),
+        };
+
+        f.write_text(&s)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Morpheme {
+    pub kind: MorphemeKind,
+    /// The morpheme as it appears in the word
+    pub text: String,
+}
+
+impl Morpheme {
+    fn new(kind: MorphemeKind, text: &str) -> Morpheme {
+        Morpheme {
+            kind,
+            text: text.to_owned(),
+        }
+    }
+}
+
+/// One way of breaking a verb form into morphemes
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VerbAnalysis {
+    pub morphemes: Vec<Morpheme>,
+    /// The verb stem in its dictionary form, ending in -a
+    pub root: String,
+}
+
+/// The subject concord merged with the remote past -a-, e.g ndi -> nda and u -> wa
+fn remote_past(concord: &str) -> Option<String> {
+    match concord {
+        "u" => Some("wa".to_owned()),
+        "i" => Some("ya".to_owned()),
+        c if c.ends_with('i') => Some(format!("{}a", &c[..c.len() - 1])),
+        c if c.ends_with('u') => Some(format!("{}wa", &c[..c.len() - 1])),
+        _ => None,
+    }
+}
+
+/// Split off the ending of a verb stem and restore the final -a of its dictionary form
+fn split_ending(rest: &str, negative: bool) -> Option<(String, Option<&str>)> {
+    let endings: &[&str] = if negative {
+        &["anga", "i"]
+    } else {
+        &["ile", "e", ""]
+    };
+
+    endings.iter().find_map(|ending| {
+        let stem = match *ending {
+            "" => rest.strip_suffix('a')?,
+            ending => rest.strip_suffix(ending)?,
+        };
+
+        (stem.chars().count() >= MIN_ROOT_LEN)
+            .then(|| (stem.to_owned(), Some(*ending).filter(|e| !e.is_empty())))
+    })
+}
+
+/// An analysis of the rest of the word after the given prefixes, if it ends like a verb
+fn analysis(mut prefixes: Vec<Morpheme>, rest: &str, negative: bool) -> Option<VerbAnalysis> {
+    let (stem, ending) = split_ending(rest, negative)?;
+    prefixes.push(Morpheme::new(MorphemeKind::Root, &stem));
+    prefixes.extend(ending.map(|e| Morpheme::new(MorphemeKind::Ending, e)));
+
+    Some(VerbAnalysis {
+        morphemes: prefixes,
+        root: format!("{stem}a"),
+    })
+}
+
+/// Break a conjugated verb into probable morphemes, e.g ndiyahamba into ndi- (subject concord),
+/// -ya- (tense marker), and hamba (root). The rules only cover the most common prefixes, and a
+/// word can often be split more than one way, so every candidate is returned with those which
+/// account for more of the word first. Which is right can be checked by looking up the roots.
+pub fn analyze_verb(word: &str) -> Vec<VerbAnalysis> {
+    let word = word.trim().to_lowercase();
+    let mut analyses = Vec::new();
+
+    let mut starts = vec![(Vec::new(), word.as_str(), false)];
+    let negative_ending = word.ends_with('i') || word.ends_with("nga");
+    if let Some(rest) = word.strip_prefix('a').filter(|_| negative_ending) {
+        starts.push((vec![Morpheme::new(MorphemeKind::Negative, "a")], rest, true));
+    }
+
+    for (start, rest, negative) in starts {
+        for concord in SUBJECT_CONCORDS {
+            let subjects = [
+                (MorphemeKind::SubjectConcord, Some(concord.to_string())),
+                (
+                    MorphemeKind::RemotePast,
+                    remote_past(concord).filter(|_| !negative),
+                ),
+            ];
+
+            for (kind, subject) in subjects {
+                let Some(subject) = subject else { continue };
+                let Some(rest) = rest.strip_prefix(subject.as_str()) else {
+                    continue;
+                };
+
+                let mut with_subject = start.clone();
+                with_subject.push(Morpheme::new(kind, &subject));
+
+                // The remote past has no separate tense marker
+                let tenses = TENSE_MARKERS
+                    .iter()
+                    .filter(|_| kind == MorphemeKind::SubjectConcord)
+                    .filter_map(|tense| Some((Some(*tense), rest.strip_prefix(*tense)?)))
+                    .chain(iter::once((None, rest)));
+
+                for (tense, rest) in tenses {
+                    let mut with_tense = with_subject.clone();
+                    with_tense.extend(tense.map(|t| Morpheme::new(MorphemeKind::TenseMarker, t)));
+
+                    for object in OBJECT_CONCORDS {
+                        if let Some(rest) = rest.strip_prefix(object) {
+                            let mut with_object = with_tense.clone();
+                            with_object.push(Morpheme::new(MorphemeKind::ObjectConcord, object));
+                            analyses.extend(analysis(with_object, rest, negative));
+                        }
+                    }
+
+                    analyses.extend(analysis(with_tense, rest, negative));
+                }
+            }
+        }
+    }
+
+    // The shortest roots account for the most of the word, and where roots are the same the
+    // longest prefixes are more likely, e.g zaku- rather than za- followed by ku-
+    analyses.sort_by_key(|a| (a.root.len(), a.morphemes.len()));
+
+    // An infinitive could also be read as a subject concord followed by ku-, so it goes first
+    let stem = verb_stem(&word);
+    if stem != word {
+        let prefix = word[..word.len() - stem.len()].trim_start_matches('-');
+        let infinitive = Some(prefix)
+            .filter(|p| !p.is_empty())
+            .map(|p| Morpheme::new(MorphemeKind::Infinitive, p));
+
+        if let Some(analysis) = analysis(infinitive.into_iter().collect(), stem, false) {
+            analyses.insert(0, analysis);
+        }
+    }
+
+    let mut roots = HashSet::new();
+    analyses.retain(|a| roots.insert(a.root.clone()));
+    analyses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use MorphemeKind::*;
+
+    fn analysis(morphemes: &[(MorphemeKind, &str)], root: &str) -> VerbAnalysis {
+        VerbAnalysis {
+            morphemes: morphemes
+                .iter()
+                .map(|(kind, text)| Morpheme::new(*kind, text))
+                .collect(),
+            root: root.to_owned(),
+        }
+    }
+
+    /// Checks the most likely analysis of each word
+    fn assert_first(cases: &[(&str, VerbAnalysis)]) {
+        for (word, expected) in cases {
+            assert_eq!(analyze_verb(word).first(), Some(expected), "{word}");
+        }
+    }
+
+    #[test]
+    fn splits_present_tense() {
+        assert_first(&[(
+            "ndiyahamba",
+            analysis(
+                &[(SubjectConcord, "ndi"), (TenseMarker, "ya"), (Root, "hamb")],
+                "hamba",
+            ),
+        )]);
+    }
+
+    #[test]
+    fn splits_each_subject_concord() {
+        for concord in SUBJECT_CONCORDS {
+            let word = format!("{concord}yahamba");
+            let expected = analysis(
+                &[
+                    (SubjectConcord, *concord),
+                    (TenseMarker, "ya"),
+                    (Root, "hamb"),
+                ],
+                "hamba",
+            );
+            assert_first(&[(word.as_str(), expected)]);
+        }
+    }
+
+    #[test]
+    fn splits_each_tense_marker() {
+        for tense in TENSE_MARKERS {
+            let word = format!("ndi{tense}hamba");
+            let expected = analysis(
+                &[
+                    (SubjectConcord, "ndi"),
+                    (TenseMarker, *tense),
+                    (Root, "hamb"),
+                ],
+                "hamba",
+            );
+            assert_first(&[(word.as_str(), expected)]);
+        }
+    }
+
+    #[test]
+    fn splits_remote_past() {
+        assert_first(&[
+            (
+                "ndahamba",
+                analysis(&[(RemotePast, "nda"), (Root, "hamb")], "hamba"),
+            ),
+            (
+                "wahamba",
+                analysis(&[(RemotePast, "wa"), (Root, "hamb")], "hamba"),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn splits_object_concords() {
+        assert_first(&[
+            (
+                "ndiyambona",
+                analysis(
+                    &[
+                        (SubjectConcord, "ndi"),
+                        (TenseMarker, "ya"),
+                        (ObjectConcord, "m"),
+                        (Root, "bon"),
+                    ],
+                    "bona",
+                ),
+            ),
+            (
+                "ndiyabathanda",
+                analysis(
+                    &[
+                        (SubjectConcord, "ndi"),
+                        (TenseMarker, "ya"),
+                        (ObjectConcord, "ba"),
+                        (Root, "thand"),
+                    ],
+                    "thanda",
+                ),
+            ),
+            (
+                "ndiyazibona",
+                analysis(
+                    &[
+                        (SubjectConcord, "ndi"),
+                        (TenseMarker, "ya"),
+                        (ObjectConcord, "zi"),
+                        (Root, "bon"),
+                    ],
+                    "bona",
+                ),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn splits_negatives() {
+        assert_first(&[
+            (
+                "andihambi",
+                analysis(
+                    &[
+                        (Negative, "a"),
+                        (SubjectConcord, "ndi"),
+                        (Root, "hamb"),
+                        (Ending, "i"),
+                    ],
+                    "hamba",
+                ),
+            ),
+            (
+                "akahambi",
+                analysis(
+                    &[
+                        (Negative, "a"),
+                        (SubjectConcord, "ka"),
+                        (Root, "hamb"),
+                        (Ending, "i"),
+                    ],
+                    "hamba",
+                ),
+            ),
+            (
+                "andihambanga",
+                analysis(
+                    &[
+                        (Negative, "a"),
+                        (SubjectConcord, "ndi"),
+                        (Root, "hamb"),
+                        (Ending, "anga"),
+                    ],
+                    "hamba",
+                ),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn splits_perfect_ending() {
+        assert_first(&[(
+            "ndihambile",
+            analysis(
+                &[(SubjectConcord, "ndi"), (Root, "hamb"), (Ending, "ile")],
+                "hamba",
+            ),
+        )]);
+    }
+
+    #[test]
+    fn puts_infinitive_first() {
+        assert_first(&[
+            (
+                "ukuhamba",
+                analysis(&[(Infinitive, "uku"), (Root, "hamb")], "hamba"),
+            ),
+            (
+                "ukwenza",
+                analysis(&[(Infinitive, "ukw"), (Root, "enz")], "enza"),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn ignores_case_and_surrounding_whitespace() {
+        assert_first(&[(
+            "  NdiyaHamba ",
+            analysis(
+                &[(SubjectConcord, "ndi"), (TenseMarker, "ya"), (Root, "hamb")],
+                "hamba",
+            ),
+        )]);
+    }
+
+    #[test]
+    fn lists_each_root_once_longest_prefixes_first() {
+        let roots: Vec<String> = analyze_verb("ndizakuhamba")
+            .into_iter()
+            .map(|analysis| analysis.root)
+            .collect();
+
+        assert_eq!(roots, ["hamba", "kuhamba", "zakuhamba"]);
+    }
+
+    #[test]
+    fn does_not_split_words_without_prefixes() {
+        for word in [
+            "", "a", "ba", "ndi", "ndiya", "hamba", "hambi", "ndlela", "xyz",
+        ] {
+            assert_eq!(analyze_verb(word), Vec::new(), "{word:?}");
+        }
+    }
+
+    #[test]
+    fn finds_verb_extensions() {
+        let cases = [
+            ("fundisa", vec![(VerbExtension::Causative, "funda")]),
+            ("ukufundisa", vec![(VerbExtension::Causative, "funda")]),
+            ("thengela", vec![(VerbExtension::Applicative, "thenga")]),
+            ("bonwa", vec![(VerbExtension::Passive, "bona")]),
+            ("bonana", vec![(VerbExtension::Reciprocal, "bona")]),
+            // The root would be too short
+            ("ukulwa", vec![]),
+            ("hamba", vec![]),
+        ];
+
+        for (verb, expected) in cases {
+            let expected: Vec<(VerbExtension, String)> = expected
+                .into_iter()
+                .map(|(extension, root)| (extension, root.to_owned()))
+                .collect();
+            assert_eq!(verb_extensions(verb), expected, "{verb}");
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! How text is compared when searching and checking submissions, and how it is tidied up before
+//! it is saved. Text pasted from other sources often uses curly apostrophes or carries stray
+//! accents, and it shouldn't matter for finding a word whether e.g `ng’ombe` or `ng'ombe` was typed.
+
+use std::borrow::Cow;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters which are used in place of an apostrophe, e.g by word processors or keyboards
+const APOSTROPHE_LOOKALIKES: [char; 7] = [
+    '\u{2018}', // Left single quotation mark
+    '\u{2019}', // Right single quotation mark
+    '\u{02BC}', // Modifier letter apostrophe
+    '\u{02BB}', // Modifier letter turned comma
+    '\u{2032}', // Prime
+    '\u{00B4}', // Acute accent
+    '`',
+];
+
+/// Characters which are used in place of a hyphen. Dashes are left alone, as they are punctuation
+/// rather than part of a word.
+const HYPHEN_LOOKALIKES: [char; 4] = [
+    '\u{2010}', // Hyphen
+    '\u{2011}', // Non-breaking hyphen
+    '\u{2012}', // Figure dash
+    '\u{2212}', // Minus sign
+];
+
+/// Characters which are used in place of a straight double quote
+const DOUBLE_QUOTE_LOOKALIKES: [char; 4] = [
+    '\u{201C}', // Left double quotation mark
+    '\u{201D}', // Right double quotation mark
+    '\u{201E}', // Double low-9 quotation mark
+    '\u{2033}', // Double prime
+];
+
+fn normalize_apostrophe(c: char) -> char {
+    if APOSTROPHE_LOOKALIKES.contains(&c) {
+        '\''
+    } else {
+        c
+    }
+}
+
+fn normalize_punctuation(c: char) -> char {
+    if HYPHEN_LOOKALIKES.contains(&c) {
+        '-'
+    } else if DOUBLE_QUOTE_LOOKALIKES.contains(&c) {
+        '"'
+    } else {
+        normalize_apostrophe(c)
+    }
+}
+
+/// Replace apostrophe lookalikes with a plain apostrophe, leaving everything else as it is
+pub fn apostrophes(text: &str) -> Cow<'_, str> {
+    if text.contains(APOSTROPHE_LOOKALIKES) {
+        Cow::Owned(text.chars().map(normalize_apostrophe).collect())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// The form of text which is compared when matching: lowercase, without diacritics, and with plain
+/// apostrophes. Two pieces of text match exactly if they fold to the same string.
+pub fn fold(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(normalize_apostrophe)
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether two pieces of text are the same once folded
+pub fn matches(a: &str, b: &str) -> bool {
+    fold(a) == fold(b)
+}
+
+/// The form in which a single line of submitted text is saved: NFC normalised, with plain quotes
+/// and hyphens, and with runs of whitespace collapsed into one space. Without this, entries which
+/// look the same can differ in ways that can't be seen, and so aren't found as duplicates.
+pub fn canonicalize(text: &str) -> String {
+    let text: String = text.nfc().map(normalize_punctuation).collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Like [`canonicalize`], but for text such as notes where line breaks are kept. Each line is
+/// canonicalised on its own, and blank lines at the start and end are removed.
+pub fn canonicalize_multiline(text: &str) -> String {
+    text.lines()
+        .map(canonicalize)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composes_decomposed_text() {
+        // "é" as an "e" followed by a combining acute accent
+        let decomposed = "caf\u{0065}\u{0301}";
+        assert_eq!(canonicalize(decomposed), "caf\u{00E9}");
+    }
+
+    #[test]
+    fn replaces_quote_lookalikes() {
+        assert_eq!(canonicalize("ng\u{2019}ombe"), "ng'ombe");
+        assert_eq!(canonicalize("\u{2018}hamba\u{2019}"), "'hamba'");
+        assert_eq!(canonicalize("ng\u{02BC}ombe"), "ng'ombe");
+        assert_eq!(canonicalize("\u{201C}molo\u{201D}"), "\"molo\"");
+    }
+
+    #[test]
+    fn replaces_hyphen_lookalikes() {
+        for hyphen in HYPHEN_LOOKALIKES {
+            assert_eq!(
+                canonicalize(&format!("-ni{hyphen}na")),
+                "-ni-na",
+                "{hyphen:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn leaves_dashes_alone() {
+        assert_eq!(canonicalize("go \u{2013} walk"), "go \u{2013} walk");
+        assert_eq!(canonicalize("go \u{2014} walk"), "go \u{2014} walk");
+    }
+
+    #[test]
+    fn trims_and_collapses_whitespace() {
+        assert_eq!(canonicalize("  to   go\t away \u{00A0}"), "to go away");
+        assert_eq!(canonicalize("to\ngo"), "to go");
+        assert_eq!(canonicalize(" \t "), "");
+    }
+
+    #[test]
+    fn keeps_line_breaks_in_multiline_text() {
+        let note = "\n  Used  when\u{2019}s  polite \n\nfor elders\u{2019} \n\n";
+        assert_eq!(
+            canonicalize_multiline(note),
+            "Used when's polite\n\nfor elders'"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crate::i18n::{ToTranslationKey, TranslationKey};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Which language a user usually searches in, so that matches in that language can be ranked above
+/// matches in the other
+#[derive(
+    IntoPrimitive,
+    TryFromPrimitive,
+    Serialize,
+    Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Hash,
+    PartialEq,
+    Eq,
+)]
+#[repr(u8)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchDirection {
+    /// Rank matches in either language equally
+    #[default]
+    Both = 0,
+    /// Searching in English for isiXhosa words
+    EnglishToXhosa = 1,
+    /// Searching in isiXhosa for English translations
+    XhosaToEnglish = 2,
+}
+
+impl SearchDirection {
+    pub const ALL: [SearchDirection; 3] = [
+        SearchDirection::Both,
+        SearchDirection::EnglishToXhosa,
+        SearchDirection::XhosaToEnglish,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchDirection::Both => "both",
+            SearchDirection::EnglishToXhosa => "english_to_xhosa",
+            SearchDirection::XhosaToEnglish => "xhosa_to_english",
+        }
+    }
+
+    /// How much matches on the English and isiXhosa of a word count for respectively. Matches in
+    /// the other language are only weighted down a little, so that a close match in it still
+    /// ranks above a poor match in the preferred language.
+    pub fn weights(&self) -> (f32, f32) {
+        match self {
+            SearchDirection::Both => (1.0, 1.0),
+            SearchDirection::EnglishToXhosa => (1.0, 0.9),
+            SearchDirection::XhosaToEnglish => (0.9, 1.0),
+        }
+    }
+}
+
+impl Display for SearchDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SearchDirection {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SearchDirection::ALL
+            .into_iter()
+            .find(|direction| direction.name() == s)
+            .ok_or(())
+    }
+}
+
+impl ToTranslationKey for SearchDirection {
+    fn translation_key(&self) -> TranslationKey<'_> {
+        TranslationKey(Cow::Owned(format!("search-direction.{}", self.name())))
+    }
+}
@@ -2,6 +2,7 @@ use crate::auth::Auth;
 use crate::format::DisplayHtml;
 use crate::i18n::I18nInfo;
 use crate::language::*;
+use crate::morphology::VerbDerivation;
 use crate::types::{ExistingWord, WordHit};
 use askama::Template;
 use fluent_templates::Loader;
@@ -17,7 +18,50 @@ where
     pub auth: Auth,
     pub i18n_info: I18nInfo<L>,
     pub word: ExistingWord,
-    pub previous_success: Option<WordChangeMethod>,
+    /// Total page views of the word. Only shown to moderators.
+    pub views: Option<u64>,
+    /// Set when the word hasn't been submitted yet and is only being previewed
+    pub preview: Option<WordPreview>,
+    /// The code of the classroom session which the viewer is following, so that the page can move
+    /// on with the teacher
+    pub classroom: Option<String>,
+    /// The probable root of the word, if it is a derived verb
+    pub derivation: Option<VerbDerivation>,
+    /// Other entries spelled the same in isiXhosa but with different meanings
+    pub homographs: Vec<WordHit>,
+    pub neighbours: AlphabeticalNeighbours,
+}
+
+/// The words just before and after a word in alphabetical order of their isiXhosa
+#[derive(Default)]
+pub struct AlphabeticalNeighbours {
+    pub previous: Option<WordHit>,
+    pub next: Option<WordHit>,
+}
+
+/// A submission shown on the word details page before it is confirmed. The original form is sent
+/// again from hidden fields, either to submit it or to go back to editing it.
+pub struct WordPreview {
+    /// The route the form was originally submitted to
+    pub route: String,
+    pub fields: Vec<(String, String)>,
+    /// Set when a moderator is previewing a suggested word before accepting it, rather than a
+    /// user previewing their own submission
+    pub accept: Option<AcceptPreview>,
+}
+
+/// What accepting a suggested word will change besides what is shown on the word page
+pub struct AcceptPreview {
+    pub suggestion_id: u64,
+    /// The slug in the word's URL once it is accepted
+    pub slug: String,
+    /// The word as it will be indexed and shown in search results
+    pub search_hit: WordHit,
+    /// Homographs which will be linked to the word as confusable on accepting
+    pub new_homograph_links: Vec<WordHit>,
+    /// Whether the word can be accepted straight from the preview. Moderators who must tick a
+    /// checklist first have to go back to the suggestion instead.
+    pub can_accept: bool,
 }
 
 pub enum WordChangeMethod {
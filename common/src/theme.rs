@@ -0,0 +1,83 @@
+use crate::i18n::{ToTranslationKey, TranslationKey};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// The colour scheme a user would like the site to be shown in
+#[derive(
+    IntoPrimitive,
+    TryFromPrimitive,
+    Serialize,
+    Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Hash,
+    PartialEq,
+    Eq,
+)]
+#[repr(u8)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Follow the browser's or operating system's preference
+    #[default]
+    System = 0,
+    Light = 1,
+    Dark = 2,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    /// The value of the `color-scheme` meta tag, used by browsers to style form controls etc
+    pub fn color_scheme(&self) -> &'static str {
+        match self {
+            Theme::System => "light dark",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    /// The media query under which the dark stylesheet applies, if at all
+    pub fn dark_stylesheet_media(&self) -> Option<&'static str> {
+        match self {
+            Theme::System => Some("(prefers-color-scheme: dark)"),
+            Theme::Light => None,
+            Theme::Dark => Some("all"),
+        }
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Theme::ALL
+            .into_iter()
+            .find(|theme| theme.name() == s)
+            .ok_or(())
+    }
+}
+
+impl ToTranslationKey for Theme {
+    fn translation_key(&self) -> TranslationKey<'_> {
+        TranslationKey(Cow::Owned(format!("theme.{}", self.name())))
+    }
+}
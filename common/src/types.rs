@@ -2,6 +2,7 @@ use crate::language::{
     ConjunctionFollowedBy, NounClassPrefixes, PartOfSpeech, Transitivity, WordLinkType,
 };
 use isixhosa::noun::NounClass;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 use std::num::NonZeroU64;
@@ -13,6 +14,13 @@ pub struct ExistingExample {
 
     pub english: String,
     pub xhosa: String,
+    /// Where the example was taken from, if it was picked from a corpus
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Which words of the English and isiXhosa sentences are translations of each other, as given
+    /// by the corpus. See [`crate::format::Alignment`].
+    #[serde(default)]
+    pub alignment: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -24,7 +32,20 @@ pub struct ExistingLinkedWord {
     pub other: WordHit,
 }
 
-#[derive(Debug)]
+/// A word linked to a search result, shown alongside the result so that e.g. the plural can be
+/// seen without opening the word
+#[derive(Clone, Debug)]
+pub struct LinkedWordSnippet {
+    pub link_type: WordLinkType,
+    pub word_id: u64,
+    pub xhosa: String,
+}
+
+/// The linked words shown alongside a single search result
+#[derive(Clone, Debug, Default)]
+pub struct LinkedWordSnippets(pub Vec<LinkedWordSnippet>);
+
+#[derive(Debug, Serialize)]
 pub struct ExistingWord {
     pub word_id: u64,
 
@@ -42,11 +63,14 @@ pub struct ExistingWord {
     pub note: String,
 
     pub is_informal: bool,
+    /// Vulgar or offensive, so hidden on the word's page until the reader chooses to see it
+    pub is_offensive: bool,
 
     pub examples: Vec<ExistingExample>,
     pub linked_words: Vec<ExistingLinkedWord>,
     pub contributors: Vec<PublicUserInfo>,
     pub datasets: Vec<Dataset>,
+    pub references: Vec<WordReference>,
 }
 
 impl ExistingWord {
@@ -63,9 +87,60 @@ impl ExistingWord {
             || !self.note.is_empty()
             || self.is_informal
     }
+
+    pub fn completeness(&self) -> Completeness {
+        Completeness {
+            has_example: !self.examples.is_empty(),
+            has_link: !self.linked_words.is_empty(),
+            has_noun_class: (self.part_of_speech == Some(PartOfSpeech::Noun))
+                .then_some(self.noun_class.is_some()),
+        }
+    }
+}
+
+/// How complete an entry is. Shown on the word's page, and used to point contributors to entries
+/// which need work.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Completeness {
+    pub has_example: bool,
+    pub has_link: bool,
+    /// Whether a noun has its class given, or `None` if the word isn't a noun
+    pub has_noun_class: Option<bool>,
+}
+
+impl Completeness {
+    fn parts(&self) -> [(Option<bool>, &'static str); 3] {
+        [
+            (Some(self.has_example), "completeness.example"),
+            (Some(self.has_link), "completeness.link"),
+            (self.has_noun_class, "completeness.noun-class"),
+        ]
+    }
+
+    /// The percentage of the parts which apply to the word that it has
+    pub fn score(&self) -> u8 {
+        let applicable = self.parts().into_iter().filter_map(|(has, _)| has);
+        let (has, total) =
+            applicable.fold((0, 0), |(has, total), part| (has + part as u8, total + 1));
+        has * 100 / total
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.score() == 100
+    }
+
+    /// The translation keys of what the word is missing
+    pub fn missing(&self) -> Vec<&'static str> {
+        self.parts()
+            .into_iter()
+            .filter(|(has, _)| *has == Some(false))
+            .map(|(_, key)| key)
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct WordHit {
     pub id: u64,
     pub english: String,
@@ -127,7 +202,7 @@ pub struct PublicUserInfo {
 }
 
 /// An external dataset from which a word in the dictionary is sourced
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Dataset {
     pub id: u64,
     pub name: String,
@@ -137,3 +212,34 @@ pub struct Dataset {
     pub institution: Option<String>,
     pub url: Option<String>,
 }
+
+/// The kind of work which a [`WordReference`] cites
+#[derive(
+    IntoPrimitive, TryFromPrimitive, Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq,
+)]
+#[repr(u8)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    Dictionary = 1,
+    Textbook = 2,
+    Corpus = 3,
+    Other = 4,
+}
+
+/// A bibliographic reference to a published work in which the word is attested
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WordReference {
+    pub reference_id: u64,
+    pub word_id: u64,
+    pub kind: ReferenceKind,
+    pub author: String,
+    pub title: String,
+    pub year: Option<u32>,
+    pub publisher: Option<String>,
+    /// The page or range of pages cited, e.g `12` or `12-14`
+    pub pages: Option<String>,
+    pub url: Option<String>,
+    /// Whether the link has stopped working, as found by the link checker
+    #[serde(default)]
+    pub link_broken: bool,
+}
@@ -0,0 +1,139 @@
+//! Benchmarks of the hot paths of searching and browsing, against a generated dictionary the size of
+//! a well established site. Run with `cargo bench` from the server directory, since the site's
+//! translations are loaded from there.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::db_impl::DbImpl;
+use isixhosa_common::search_direction::SearchDirection;
+use isixhosa_common::theme::Theme;
+use isixhosa_common::types::WordHit;
+use isixhosa_server::bench::{
+    generate_fake_data, load_site_context, word, ClassroomViewer, FragmentCache, I18nInfo,
+    IncludeResults, SuggestedWord, TantivyClient, EN_ZA,
+};
+use isixhosa_server::{open_db_pool, Config};
+use rusqlite::params;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempdir::TempDir;
+use tokio::runtime::Runtime;
+use warp::Reply;
+
+const WORDS: usize = 50_000;
+const SEED: u64 = 0;
+const QUERIES: &[&str] = &["dog", "big house", "uku", "hamb", "quickly"];
+
+fn config(dir: &Path) -> Config {
+    Config {
+        database_path: dir.join("isixhosa_click.db"),
+        tantivy_path: dir.join("tantivy_data"),
+        // Otherwise repeated searches would only measure the cache
+        search_cache_size: 0,
+        server_source_path: PathBuf::from(env!("CARGO_MANIFEST_DIR")),
+        ..Config::default()
+    }
+}
+
+/// Suggests each generated word again as a new word, so that there are as many suggestions as words
+fn suggest_all_words(cfg: &Config) {
+    const INSERT_USER: &str = "
+        INSERT INTO users
+            (oidc_id, username, display_name, email, is_moderator, is_administrator, locked)
+        VALUES ('bench', 'bench', TRUE, 'bench@example.com', FALSE, FALSE, FALSE)
+        RETURNING user_id;
+    ";
+    const INSERT_SUGGESTIONS: &str = "
+        INSERT INTO word_suggestions (
+            suggesting_user, changes_summary, english, xhosa, part_of_speech, xhosa_tone_markings,
+            infinitive, is_plural, is_inchoative, is_informal, transitivity, followed_by,
+            noun_class, note, is_offensive
+        )
+        SELECT
+            ?1, 'Word added', english, xhosa, part_of_speech, xhosa_tone_markings, infinitive,
+            is_plural, is_inchoative, is_informal, transitivity, followed_by, noun_class, note,
+            is_offensive
+        FROM words;
+    ";
+
+    let conn = open_db_pool(cfg).unwrap().get().unwrap();
+    let user_id: u64 = conn
+        .query_row(INSERT_USER, params![], |row| row.get("user_id"))
+        .unwrap();
+    conn.execute(INSERT_SUGGESTIONS, params![user_id]).unwrap();
+}
+
+fn dictionary(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let dir = TempDir::new("isixhosa_click_bench").unwrap();
+
+    // Fake data can only be generated into an empty database, and it replaces the search index
+    std::fs::create_dir(dir.path().join("tantivy_data")).unwrap();
+    generate_fake_data(config(dir.path()), WORDS, SEED).unwrap();
+
+    let cfg = config(dir.path());
+    suggest_all_words(&cfg);
+
+    let pool = open_db_pool(&cfg).unwrap();
+    let fragments = Arc::new(FragmentCache::default());
+    let tantivy = runtime
+        .block_on(TantivyClient::start(&cfg, pool.clone(), fragments))
+        .unwrap();
+    let i18n_info = I18nInfo {
+        user_language: EN_ZA,
+        theme: Theme::default(),
+        search_direction: SearchDirection::default(),
+        school_mode: false,
+        flashes: Vec::new(),
+        csp_nonce: String::new(),
+        ctx: Arc::new(load_site_context("isixhosa".to_owned(), &cfg)),
+    };
+    let db = DbImpl(pool);
+
+    let mut search = c.benchmark_group("search");
+    for query in QUERIES {
+        search.bench_with_input(BenchmarkId::from_parameter(query), query, |b, query| {
+            b.to_async(&runtime).iter(|| {
+                tantivy.search::<WordHit>(
+                    query.to_string(),
+                    IncludeResults::AcceptedOnly,
+                    false,
+                    i18n_info.clone(),
+                )
+            })
+        });
+    }
+    search.finish();
+
+    let mut word_id = 0;
+    c.bench_function("word_details", |b| {
+        b.to_async(&runtime).iter(|| {
+            // Go through the words in turn, since some have many more examples and links
+            word_id = word_id % WORDS as u64 + 1;
+            let (word_id, i18n_info, db) = (word_id, i18n_info.clone(), db.clone());
+
+            async move {
+                word(
+                    word_id,
+                    ClassroomViewer::default(),
+                    Auth::default(),
+                    i18n_info,
+                    db,
+                )
+                .await
+                .unwrap()
+                .into_response()
+            }
+        })
+    });
+
+    let mut suggestions = c.benchmark_group("suggestions");
+    suggestions.sample_size(10);
+    suggestions.bench_function("fetch_all_full", |b| {
+        b.iter(|| SuggestedWord::fetch_all_full(&db, &i18n_info))
+    });
+    suggestions.finish();
+}
+
+criterion_group!(benches, dictionary);
+criterion_main!(benches);
@@ -18,6 +18,11 @@ fn get_last_changed(path: &str, name_pat: &str) -> String {
 
 fn main() {
     println!("cargo:rerun-if-changed=static/");
+    println!("cargo:rerun-if-changed=proto/");
+
+    // Use the vendored protoc so that it doesn't have to be installed to build the server
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
+    tonic_build::compile_protos("proto/lookup.proto").unwrap();
 
     let static_exts = ["png", "svg", "woff2", "ico", "pdf"]
         .map(|ext| format!("-name '*.{ext}'"))
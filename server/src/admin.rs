@@ -1,25 +1,175 @@
-use crate::auth::{with_administrator_auth, FullUser};
+use crate::auth::{with_administrator_auth, FullUser, StaySignedInToken};
+use crate::config::ReplicationConfig;
+use crate::csrf::{csrf_protected, csrf_protected_form, csrf_protected_multipart, CSRF_FIELD};
+use crate::database::api_keys::ApiKey;
+use crate::database::bans::{Ban, BanTarget, NewBanTarget};
+use crate::database::trainees;
+use crate::database::user_stats::UserStats;
+use crate::error::ServerError;
 use crate::i18n::{I18nInfo, SiteContext};
+use crate::scheduler::{JobInfo, Schedule, Scheduler};
+use crate::search::TantivyClient;
+use crate::serialization::{query_form, MultipartForm};
+use crate::{maintenance, replication, Config};
 use crate::{spawn_blocking_child, DebugBoxedExt};
 use anyhow::{bail, Context, Result};
 use askama::Template;
-use futures::StreamExt;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use image::{DynamicImage, ImageFormat, ImageReader};
 use isixhosa_click_macros::I18nTemplate;
-use isixhosa_common::auth::Auth;
+use isixhosa_common::auth::{Auth, Permissions};
 use isixhosa_common::database::{AdministratorAccessDb, DbBase};
 use isixhosa_common::types::Dataset;
-use std::io::{Cursor, Read};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use serde::Deserialize;
+use std::io::Cursor;
+use std::path::PathBuf;
 use std::sync::Arc;
-use warp::multipart::FormData;
-use warp::Buf;
+use tracing::instrument;
+use warp::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use warp::hyper::Body;
+use warp::reply::Response;
 use warp::{Filter, Rejection, Reply};
 
 pub fn admin(
     db: DbBase,
     site_ctx: Arc<SiteContext>,
+    cfg: &Config,
+    scheduler: Arc<Scheduler>,
+    tantivy: Arc<TantivyClient>,
 ) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let pool = db.primary.clone();
+    let tantivy_path = Arc::new(cfg.tantivy_path.clone());
     let base = with_administrator_auth(db, site_ctx);
+    let replication = cfg.replication.clone();
+    let upload_limit = cfg.body_limits.upload;
+    let config_values = Arc::new(cfg.redacted_values());
+    let with_config_values = warp::any().map(move || config_values.clone());
+
+    let panel = warp::path::end()
+        .and(warp::get())
+        .and(base.clone())
+        .and(with_config_values.clone())
+        .and(warp::any().map(|| None))
+        .and_then(reply_panel);
+
+    let reindex = warp::path!("reindex")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected())
+        .and(with_config_values.clone())
+        .and(warp::any().map(move || (tantivy.clone(), pool.clone())))
+        .and_then(reply_reindex);
+
+    let search_index_snapshot = warp::path!("search_index.tar.gz")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(base.clone())
+        .and(warp::any().map(move || tantivy_path.clone()))
+        .and_then(reply_search_index_snapshot);
+
+    let toggle_maintenance = warp::path!("maintenance")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected_form::<MaintenanceForm>())
+        .and(with_config_values)
+        .and_then(reply_toggle_maintenance);
+
+    let users_page = warp::path!("users")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(query_form())
+        .and(base.clone())
+        .and_then(reply_users);
+
+    let user_details = warp::path!("users" / u64)
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(base.clone())
+        .and(warp::any().map(|| None))
+        .and_then(reply_user_details);
+
+    let set_role = warp::path!("users" / u64 / "role")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected_form::<RoleForm>())
+        .and_then(reply_set_role);
+
+    let lock_user = warp::path!("users" / u64 / "lock")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected())
+        .and(warp::any().map(|| true))
+        .and_then(reply_set_locked);
+
+    let unlock_user = warp::path!("users" / u64 / "unlock")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected())
+        .and(warp::any().map(|| false))
+        .and_then(reply_set_locked);
+
+    let make_trainee = warp::path!("users" / u64 / "trainee")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected())
+        .and(warp::any().map(|| true))
+        .and_then(reply_set_trainee);
+
+    let end_training = warp::path!("users" / u64 / "end_training")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected())
+        .and(warp::any().map(|| false))
+        .and_then(reply_set_trainee);
+
+    let sign_out_user = warp::path!("users" / u64 / "sign_out")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected())
+        .and_then(reply_sign_out_user);
+
+    let set_api_key_quota = warp::path!("users" / u64 / "api_keys" / u64 / "quota")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected_form::<QuotaForm>())
+        .and_then(reply_set_api_key_quota);
+
+    let revoke_api_key = warp::path!("users" / u64 / "api_keys" / u64 / "revoke")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected())
+        .and_then(reply_revoke_api_key);
+
+    let panel = warp::path("admin")
+        .and(
+            panel
+                .or(reindex)
+                .or(search_index_snapshot)
+                .or(toggle_maintenance)
+                .or(users_page)
+                .or(user_details)
+                .or(set_role)
+                .or(lock_user)
+                .or(unlock_user)
+                .or(make_trainee)
+                .or(end_training)
+                .or(sign_out_user)
+                .or(set_api_key_quota)
+                .or(revoke_api_key),
+        )
+        .debug_boxed();
 
     let settings = warp::path::end()
         .and(base.clone())
@@ -39,7 +189,7 @@ pub fn admin(
     let add_dataset_submit = add_dataset_route
         .and(base.clone())
         .and(warp::post())
-        .and(warp::multipart::form().max_length(Some(16 * 1024 * 1024)))
+        .and(csrf_protected_multipart(upload_limit))
         .and_then(reply_add_dataset);
 
     let edit_dataset_form = warp::path!("dataset" / u64 / "edit")
@@ -52,23 +202,380 @@ pub fn admin(
         .and(base.clone())
         .and(warp::path::end())
         .and(warp::post())
+        .and(csrf_protected())
         .and_then(reply_delete_dataset);
 
+    let bans_route = warp::path("bans").and(warp::path::end());
+
+    let bans_page = bans_route
+        .and(warp::get())
+        .and(base.clone())
+        .and(warp::any().map(|| None))
+        .and_then(reply_bans);
+
+    let add_ban = bans_route
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected_form::<BanForm>())
+        .and_then(reply_add_ban);
+
+    let lift_ban = warp::path!("bans" / u64 / "lift")
+        .and(base.clone())
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(csrf_protected())
+        .and_then(reply_lift_ban);
+
+    let checkpoint = warp::path!("database" / "checkpoint")
+        .and(base.clone())
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(csrf_protected())
+        .and(warp::any().map(move || replication.clone()))
+        .and_then(reply_checkpoint);
+
+    let with_scheduler = warp::any().map(move || scheduler.clone());
+
+    let jobs_page = warp::path("jobs")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(base.clone())
+        .and(with_scheduler.clone())
+        .and(warp::any().map(|| None))
+        .and_then(reply_jobs);
+
+    let run_job = warp::path!("jobs" / String / "run")
+        .and(base.clone())
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(csrf_protected())
+        .and(with_scheduler)
+        .and_then(reply_run_job);
+
     warp::path!("admin" / "settings" / ..)
         .and(
             settings
                 .or(add_dataset_form)
                 .or(add_dataset_submit)
                 .or(edit_dataset_form)
-                .or(delete_dataset),
+                .or(delete_dataset)
+                .or(bans_page)
+                .or(add_ban)
+                .or(lift_ban)
+                .or(checkpoint)
+                .or(jobs_page)
+                .or(run_job),
         )
+        .or(panel)
         .debug_boxed()
 }
 
+enum PanelAction {
+    Reindex,
+    Maintenance,
+}
+
+#[derive(Deserialize, Debug)]
+struct MaintenanceForm {
+    enabled: bool,
+}
+
+enum UserAction {
+    SetRole,
+    SetLocked,
+    SetTrainee,
+    SignOut,
+    SetApiKeyQuota,
+    RevokeApiKey,
+}
+
+#[derive(Deserialize, Debug)]
+struct UserQuery {
+    /// Part of a username or email address
+    q: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RoleForm {
+    role: Permissions,
+}
+
+#[derive(Deserialize, Debug)]
+struct QuotaForm {
+    daily_quota: u64,
+}
+
+async fn reply_panel(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    _db: impl AdministratorAccessDb,
+    config_values: Arc<Vec<(String, String)>>,
+    previous_success: Option<Result<PanelAction, PanelAction>>,
+) -> Result<impl Reply, Rejection> {
+    Ok(AdminPanel {
+        auth: user.into(),
+        i18n_info,
+        maintenance: maintenance::is_enabled(),
+        config_values,
+        previous_success,
+    })
+}
+
+async fn reply_reindex(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    config_values: Arc<Vec<(String, String)>>,
+    (tantivy, pool): (Arc<TantivyClient>, Pool<SqliteConnectionManager>),
+) -> Result<impl Reply, Rejection> {
+    // Reindexing takes a while, so it isn't waited for
+    tokio::task::spawn(async move { tantivy.reindex_database(pool).await });
+
+    reply_panel(
+        user,
+        i18n_info,
+        db,
+        config_values,
+        Some(Ok(PanelAction::Reindex)),
+    )
+    .await
+}
+
+#[instrument(name = "Download search index snapshot", skip_all)]
+async fn reply_search_index_snapshot(
+    _user: FullUser,
+    _i18n_info: I18nInfo,
+    _db: impl AdministratorAccessDb,
+    tantivy_path: Arc<PathBuf>,
+) -> Result<Response, Rejection> {
+    let snapshot = spawn_blocking_child(move || {
+        let mut snapshot = Vec::new();
+        TantivyClient::export_snapshot(&tantivy_path, &mut snapshot).map(|()| snapshot)
+    })
+    .await
+    .map_err(ServerError::from)?
+    .map_err(ServerError::from)?;
+
+    let filename = format!("search_index_{}.tar.gz", Utc::now().format("%Y-%m-%d"));
+
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, "application/gzip")
+        .header(
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{filename}\""),
+        )
+        .body(Body::from(snapshot))
+        .unwrap())
+}
+
+async fn reply_toggle_maintenance(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    form: MaintenanceForm,
+    config_values: Arc<Vec<(String, String)>>,
+) -> Result<impl Reply, Rejection> {
+    maintenance::set_enabled(form.enabled);
+
+    reply_panel(
+        user,
+        i18n_info,
+        db,
+        config_values,
+        Some(Ok(PanelAction::Maintenance)),
+    )
+    .await
+}
+
+async fn reply_users(
+    query: UserQuery,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let search = query.q.unwrap_or_default();
+    let search_clone = search.clone();
+    let users = spawn_blocking_child(move || FullUser::search(&db, search_clone.trim()))
+        .await
+        .unwrap();
+
+    Ok(UsersPage {
+        auth: user.into(),
+        i18n_info,
+        search,
+        users,
+    })
+}
+
+async fn reply_user_details(
+    user_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    previous_success: Option<Result<UserAction, UserAction>>,
+) -> Result<impl Reply, Rejection> {
+    let (details, stats, api_keys, is_trainee) = spawn_blocking_child(move || {
+        let details = FullUser::fetch_by_id(&db, user_id)?;
+        let api_keys = ApiKey::fetch_for_user(&db, user_id);
+        let is_trainee = trainees::is_trainee(&db, user_id);
+        Some((
+            details,
+            UserStats::fetch(&db, user_id),
+            api_keys,
+            is_trainee,
+        ))
+    })
+    .await
+    .unwrap()
+    .ok_or_else(warp::reject::not_found)?;
+
+    let is_self = user_id == user.id.get();
+
+    Ok(UserDetails {
+        auth: user.into(),
+        i18n_info,
+        user: details,
+        stats,
+        api_keys,
+        is_trainee,
+        is_self,
+        previous_success,
+    })
+}
+
+async fn reply_set_role(
+    user_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    form: RoleForm,
+) -> Result<impl Reply, Rejection> {
+    // Administrators can't change their own role, so that they can't lock themselves out
+    let success = if user_id != user.id.get() {
+        let db = db.clone();
+        spawn_blocking_child(move || FullUser::set_role(&db, user_id, form.role))
+            .await
+            .unwrap()
+    } else {
+        false
+    };
+
+    let success = if success {
+        Ok(UserAction::SetRole)
+    } else {
+        Err(UserAction::SetRole)
+    };
+    reply_user_details(user_id, user, i18n_info, db, Some(success)).await
+}
+
+async fn reply_set_locked(
+    user_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    locked: bool,
+) -> Result<impl Reply, Rejection> {
+    let success = if user_id != user.id.get() {
+        let db = db.clone();
+        spawn_blocking_child(move || FullUser::set_locked(&db, user_id, locked))
+            .await
+            .unwrap()
+    } else {
+        false
+    };
+
+    let success = if success {
+        Ok(UserAction::SetLocked)
+    } else {
+        Err(UserAction::SetLocked)
+    };
+    reply_user_details(user_id, user, i18n_info, db, Some(success)).await
+}
+
+async fn reply_set_trainee(
+    user_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    trainee: bool,
+) -> Result<impl Reply, Rejection> {
+    let db_clone = db.clone();
+    let success = spawn_blocking_child(move || trainees::set_trainee(&db_clone, user_id, trainee))
+        .await
+        .unwrap();
+
+    let success = if success {
+        Ok(UserAction::SetTrainee)
+    } else {
+        Err(UserAction::SetTrainee)
+    };
+    reply_user_details(user_id, user, i18n_info, db, Some(success)).await
+}
+
+async fn reply_sign_out_user(
+    user_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let db_clone = db.clone();
+    spawn_blocking_child(move || StaySignedInToken::delete_all_for_user(&db_clone, user_id))
+        .await
+        .unwrap();
+
+    reply_user_details(user_id, user, i18n_info, db, Some(Ok(UserAction::SignOut))).await
+}
+
+async fn reply_set_api_key_quota(
+    user_id: u64,
+    key_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    form: QuotaForm,
+) -> Result<impl Reply, Rejection> {
+    let db_clone = db.clone();
+    let success = spawn_blocking_child(move || {
+        ApiKey::set_quota(&db_clone, user_id, key_id, form.daily_quota)
+    })
+    .await
+    .unwrap();
+
+    let success = if success {
+        Ok(UserAction::SetApiKeyQuota)
+    } else {
+        Err(UserAction::SetApiKeyQuota)
+    };
+
+    reply_user_details(user_id, user, i18n_info, db, Some(success)).await
+}
+
+async fn reply_revoke_api_key(
+    user_id: u64,
+    key_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let db_clone = db.clone();
+    let success = spawn_blocking_child(move || ApiKey::revoke(&db_clone, user_id, key_id))
+        .await
+        .unwrap();
+
+    let success = if success {
+        Ok(UserAction::RevokeApiKey)
+    } else {
+        Err(UserAction::RevokeApiKey)
+    };
+
+    reply_user_details(user_id, user, i18n_info, db, Some(success)).await
+}
+
 enum Action {
     None,
     AddDataset,
     DeleteDataset,
+    Checkpoint,
 }
 
 async fn reply_settings(
@@ -87,8 +594,103 @@ async fn reply_settings(
     })
 }
 
-async fn add_dataset_from_data(form: FormData, db: &impl AdministratorAccessDb) -> Result<()> {
-    let (dataset, icon) = DatasetForm::try_from_multipart(form).await?;
+enum BanAction {
+    Add,
+    Lift,
+}
+
+/// A new ban, from the form on the bans page
+#[derive(Deserialize, Debug)]
+struct BanForm {
+    /// A user ID, or an IP address or range
+    target: String,
+    reason: String,
+    /// `YYYY-MM-DD`, or empty if the ban never expires
+    expires: String,
+}
+
+impl BanForm {
+    fn parse(&self) -> Option<(NewBanTarget, Option<DateTime<Utc>>)> {
+        let target = match self.target.trim().parse::<u64>() {
+            Ok(user_id) => NewBanTarget::User(user_id),
+            Err(_) => NewBanTarget::IpRange(self.target.parse().ok()?),
+        };
+
+        let expires = match self.expires.trim() {
+            "" => None,
+            date => Some(
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .ok()?
+                    .and_time(NaiveTime::MIN)
+                    .and_utc(),
+            ),
+        };
+
+        Some((target, expires))
+    }
+}
+
+async fn reply_bans(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    previous_success: Option<Result<BanAction, BanAction>>,
+) -> Result<impl Reply, Rejection> {
+    Ok(BansPage {
+        auth: user.into(),
+        i18n_info,
+        bans: spawn_blocking_child(move || Ban::fetch_all_active(&db))
+            .await
+            .unwrap(),
+        previous_success,
+    })
+}
+
+async fn reply_add_ban(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    form: BanForm,
+) -> Result<impl Reply, Rejection> {
+    let success = match form.parse() {
+        Some((target, expires)) if !form.reason.trim().is_empty() => {
+            let (db, banned_by) = (db.clone(), user.id.get());
+            spawn_blocking_child(move || {
+                Ban::add(&db, target, form.reason.trim(), banned_by, expires)
+            })
+            .await
+            .unwrap();
+
+            Ok(BanAction::Add)
+        }
+        _ => Err(BanAction::Add),
+    };
+
+    reply_bans(user, i18n_info, db, Some(success)).await
+}
+
+async fn reply_lift_ban(
+    ban_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let db_clone = db.clone();
+    let success = spawn_blocking_child(move || Ban::lift(&db_clone, ban_id))
+        .await
+        .unwrap();
+
+    let success = if success {
+        Ok(BanAction::Lift)
+    } else {
+        Err(BanAction::Lift)
+    };
+
+    reply_bans(user, i18n_info, db, Some(success)).await
+}
+
+async fn add_dataset_from_data(form: MultipartForm, db: &impl AdministratorAccessDb) -> Result<()> {
+    let (dataset, icon) = DatasetForm::try_from_multipart(form)?;
     let icon_bytes = match icon {
         Some(icon) => {
             let mut vec = vec![];
@@ -125,7 +727,7 @@ async fn reply_add_dataset(
     user: FullUser,
     i18n_info: I18nInfo,
     db: impl AdministratorAccessDb,
-    form: FormData,
+    form: MultipartForm,
 ) -> Result<impl Reply, Rejection> {
     let success = match add_dataset_from_data(form, &db).await {
         Ok(_) => Ok(Action::AddDataset),
@@ -158,6 +760,60 @@ async fn reply_delete_dataset(
     reply_settings(user, i18n_info, db, success).await
 }
 
+async fn reply_checkpoint(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    cfg: ReplicationConfig,
+) -> Result<impl Reply, Rejection> {
+    let db_clone = db.clone();
+    let result =
+        spawn_blocking_child(move || replication::checkpoint_and_snapshot(&db_clone, &cfg))
+            .await
+            .unwrap();
+
+    let success = match result {
+        Ok(_) => Ok(Action::Checkpoint),
+        Err(error) => {
+            tracing::error!(?error, "Failed to checkpoint database");
+            Err(Action::Checkpoint)
+        }
+    };
+
+    reply_settings(user, i18n_info, db, success).await
+}
+
+async fn reply_jobs(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    _db: impl AdministratorAccessDb,
+    scheduler: Arc<Scheduler>,
+    previous_success: Option<Result<(), ()>>,
+) -> Result<impl Reply, Rejection> {
+    Ok(JobsPage {
+        auth: user.into(),
+        i18n_info,
+        jobs: scheduler.jobs(),
+        previous_success,
+    })
+}
+
+async fn reply_run_job(
+    name: String,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    scheduler: Arc<Scheduler>,
+) -> Result<impl Reply, Rejection> {
+    let success = if scheduler.run_now(&name) {
+        Ok(())
+    } else {
+        Err(())
+    };
+
+    reply_jobs(user, i18n_info, db, scheduler, Some(success)).await
+}
+
 async fn reply_edit_dataset_form(
     dataset_id: u64,
     user: FullUser,
@@ -189,46 +845,36 @@ struct DatasetForm {
 }
 
 impl DatasetForm {
-    async fn try_from_multipart(mut data: FormData) -> Result<(DatasetForm, Option<DynamicImage>)> {
+    fn try_from_multipart(data: MultipartForm) -> Result<(DatasetForm, Option<DynamicImage>)> {
         let mut form = DatasetForm::default();
-        let mut image = None;
 
-        let str = String::from_utf8;
-
-        while let Some(part) = data.next().await {
-            let part = part?;
-            #[allow(clippy::unnecessary_to_owned)] // We are copying it, basically
-            let name: &str = &part.name().to_owned();
-            let mut stream = part.stream();
-
-            let mut bytes = Vec::new();
-            while let Some(buf) = stream.next().await {
-                let mut rdr = buf?.reader();
-                rdr.read_to_end(&mut bytes)?;
-            }
-
-            match name {
-                "id" => form.id = Some(str(bytes)?.parse()?),
-                "name" => form.name = str(bytes)?,
-                "description" => form.description = str(bytes)?,
-                "author" => form.author = str(bytes)?,
-                "license" => form.license = str(bytes)?,
-                "institution" => form.institution = str(bytes)?,
-                "url" => form.url = str(bytes)?,
-                "icon" => {
-                    // This isn't a guard because we want to avoid the bail! branch
-                    if !bytes.is_empty() {
-                        image = Some(
-                            ImageReader::new(Cursor::new(bytes))
-                                .with_guessed_format()?
-                                .decode()?,
-                        )
-                    }
-                }
+        for (name, value) in data.fields.0 {
+            match name.as_str() {
+                "id" => form.id = Some(value.parse()?),
+                "name" => form.name = value,
+                "description" => form.description = value,
+                "author" => form.author = value,
+                "license" => form.license = value,
+                "institution" => form.institution = value,
+                "url" => form.url = value,
+                CSRF_FIELD => {}
                 name => bail!("Invalid field in dataset form {name}"),
             }
         }
 
+        if let Some((name, _)) = data.files.iter().find(|(name, _)| name != "icon") {
+            bail!("Invalid file in dataset form {name}");
+        }
+
+        let image = match data.file("icon") {
+            Some(icon) => Some(
+                ImageReader::new(Cursor::new(&icon.bytes))
+                    .with_guessed_format()?
+                    .decode()?,
+            ),
+            None => None,
+        };
+
         Ok((form, image))
     }
 }
@@ -247,6 +893,40 @@ impl From<Dataset> for DatasetForm {
     }
 }
 
+#[derive(I18nTemplate, Template)]
+#[template(path = "admin.askama.html")]
+struct AdminPanel {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    maintenance: bool,
+    config_values: Arc<Vec<(String, String)>>,
+    previous_success: Option<Result<PanelAction, PanelAction>>,
+}
+
+#[derive(I18nTemplate, Template)]
+#[template(path = "users.askama.html")]
+struct UsersPage {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    search: String,
+    users: Vec<FullUser>,
+}
+
+#[derive(I18nTemplate, Template)]
+#[template(path = "user_details.askama.html")]
+struct UserDetails {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    user: FullUser,
+    stats: UserStats,
+    api_keys: Vec<ApiKey>,
+    /// Whether the user must tick off a checklist before accepting suggested words
+    is_trainee: bool,
+    /// Administrators can't change their own role or lock themselves out
+    is_self: bool,
+    previous_success: Option<Result<UserAction, UserAction>>,
+}
+
 #[derive(I18nTemplate, Template)]
 #[template(path = "site_settings.askama.html")]
 struct SiteSettings {
@@ -263,3 +943,36 @@ struct AddDataset {
     i18n_info: I18nInfo,
     dataset: DatasetForm,
 }
+
+#[derive(I18nTemplate, Template)]
+#[template(path = "jobs.askama.html")]
+struct JobsPage {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    jobs: Vec<JobInfo>,
+    previous_success: Option<Result<(), ()>>,
+}
+
+impl JobsPage {
+    fn describe_schedule(&self, schedule: Schedule) -> String {
+        match schedule {
+            Schedule::Every(period) => self.t_with(
+                "jobs.every",
+                &crate::i18n_args!("minutes" => period.as_secs() / 60),
+            ),
+            Schedule::Daily(time) => self.t_with(
+                "jobs.daily",
+                &crate::i18n_args!("time" => time.format("%H:%M").to_string()),
+            ),
+        }
+    }
+}
+
+#[derive(I18nTemplate, Template)]
+#[template(path = "bans.askama.html")]
+struct BansPage {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    bans: Vec<Ban>,
+    previous_success: Option<Result<BanAction, BanAction>>,
+}
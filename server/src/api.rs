@@ -0,0 +1,200 @@
+//! The JSON API. Its OpenAPI description is generated from the annotations on the handlers below,
+//! served at `/api/openapi.json`, and browsable with Swagger UI at `/api/docs/`.
+
+use crate::api_keys::{
+    recover_api_key_rejection, with_api_key, ApiError, API_KEY_HEADER, QUOTA_REMAINING_HEADER,
+};
+use crate::auth::with_any_auth;
+use crate::details::RandomWordQuery;
+use crate::error::ServerError;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::search::{JsWordHit, TantivyClient};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use anyhow::anyhow;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::language::{NounClassPrefixes, PartOfSpeech, Transitivity};
+use isixhosa_common::types::WordHit;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::instrument;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{IntoParams, Modify, OpenApi};
+use utoipa_swagger_ui::Config as SwaggerConfig;
+use warp::http::Uri;
+use warp::path::{FullPath, Tail};
+use warp::reply::Response;
+use warp::{path, reply, Filter, Rejection, Reply};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "IsiXhosa.click API"),
+    paths(pattern_search, random_word),
+    components(schemas(
+        ApiError,
+        JsWordHit,
+        WordHit,
+        PartOfSpeech,
+        Transitivity,
+        NounClassPrefixes
+    )),
+    modifiers(&ApiKeySecurity)
+)]
+struct ApiDoc;
+
+/// Declares the API key scheme which the keyed endpoints refer to
+struct ApiKeySecurity;
+
+impl Modify for ApiKeySecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+        );
+    }
+}
+
+pub fn api(
+    db: DbBase,
+    tantivy: Arc<TantivyClient>,
+    site_ctx: Arc<SiteContext>,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let with_tantivy = warp::any().map(move || tantivy.clone());
+
+    let pattern = warp::get()
+        .and(warp::path("pattern"))
+        .and(path::end())
+        .and(warp::query())
+        .and(with_api_key(db.clone()))
+        .and(with_tantivy)
+        .and(with_any_auth(db.clone(), site_ctx.clone()))
+        .and_then(pattern_search);
+
+    let random = warp::get()
+        .and(warp::path("random"))
+        .and(path::end())
+        .and(warp::query())
+        .and(with_any_auth(db, site_ctx))
+        .and_then(random_word);
+
+    let spec = Arc::new(ApiDoc::openapi());
+    let openapi_json = warp::get()
+        .and(warp::path("openapi.json"))
+        .and(path::end())
+        .map(move || reply::json(&*spec));
+
+    let swagger_config = Arc::new(SwaggerConfig::from("/api/openapi.json"));
+    let docs = warp::get()
+        .and(warp::path("docs"))
+        .and(warp::path::full())
+        .and(warp::path::tail())
+        .and(warp::any().map(move || swagger_config.clone()))
+        .and_then(swagger_ui);
+
+    warp::path("api")
+        .and(pattern.or(random).or(openapi_json).or(docs))
+        .recover(recover_api_key_rejection)
+        .debug_boxed()
+}
+
+#[derive(Deserialize, IntoParams, Clone, Debug)]
+#[into_params(parameter_in = Query)]
+struct PatternQuery {
+    /// The pattern to match words against, where `*` matches any number of letters and `?` or `_`
+    /// matches exactly one. `q` is accepted as an alias.
+    #[serde(alias = "q")]
+    pattern: String,
+}
+
+/// Search for words matching a pattern. Requires an API key, and counts against its daily quota.
+#[utoipa::path(
+    get,
+    path = "/api/pattern",
+    params(PatternQuery),
+    responses(
+        (
+            status = 200,
+            description = "The words matching the pattern",
+            body = [JsWordHit],
+            headers(("x-quota-remaining" = u64, description = "Requests the key has left today"))
+        ),
+        (status = 401, description = "The API key is missing or invalid", body = ApiError),
+        (status = 429, description = "The API key has used its quota for today", body = ApiError),
+    ),
+    security(("api_key" = []))
+)]
+#[instrument(name = "Search with a pattern", fields(pattern = %query.pattern), skip_all)]
+async fn pattern_search(
+    query: PatternQuery,
+    quota_remaining: u64,
+    tantivy: Arc<TantivyClient>,
+    _auth: Auth,
+    i18n_info: I18nInfo,
+    _db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let results = tantivy
+        .search_pattern(query.pattern, i18n_info)
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(reply::with_header(
+        reply::json(&results),
+        QUOTA_REMAINING_HEADER,
+        quota_remaining,
+    ))
+}
+
+/// Fetch a random word from the dictionary
+#[utoipa::path(
+    get,
+    path = "/api/random",
+    params(RandomWordQuery),
+    responses(
+        (status = 200, description = "A random word matching the filters", body = WordHit),
+        (status = 404, description = "No word matches the filters"),
+    )
+)]
+#[instrument(name = "Fetch a random word", skip(_auth, i18n_info, db))]
+async fn random_word(
+    query: RandomWordQuery,
+    _auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let id = query
+        .fetch_random_id(db.clone(), i18n_info.school_mode)
+        .await?
+        .ok_or_else(warp::reject::not_found)?;
+
+    let hit =
+        spawn_blocking_child(move || WordHit::fetch_from_db(&db, WordOrSuggestionId::existing(id)))
+            .await
+            .map_err(ServerError::from)?
+            .ok_or_else(warp::reject::not_found)?;
+
+    Ok(reply::json(&hit))
+}
+
+/// Serve the Swagger UI's files, which are bundled into the binary
+async fn swagger_ui(
+    full_path: FullPath,
+    tail: Tail,
+    config: Arc<SwaggerConfig<'static>>,
+) -> Result<Response, Rejection> {
+    // Swagger UI's files are relative to the page, so it must be served from a directory
+    if full_path.as_str() == "/api/docs" {
+        return Ok(warp::redirect::found(Uri::from_static("/api/docs/")).into_response());
+    }
+
+    match utoipa_swagger_ui::serve(tail.as_str(), config) {
+        Ok(Some(file)) => {
+            Ok(
+                reply::with_header(file.bytes.into_owned(), "content-type", file.content_type)
+                    .into_response(),
+            )
+        }
+        Ok(None) => Err(warp::reject::not_found()),
+        Err(err) => Err(ServerError(anyhow!("Could not serve Swagger UI: {err}")).into()),
+    }
+}
@@ -0,0 +1,270 @@
+//! Issuing API keys from the settings page, and checking them on the high-volume API endpoints.
+//! Requests without a valid key, or with one which has used up its quota for the day, are
+//! answered with a JSON error rather than an error page.
+
+use crate::auth::{with_user_auth, FullUser};
+use crate::config::{ApiKeyConfig, BodyLimits};
+use crate::csrf::{csrf_protected, csrf_protected_form};
+use crate::database::api_keys::{ApiKey, KeyUse};
+use crate::error::ServerError;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{with_public_db, DbBase, PublicAccessDb, UserAccessDb};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+use tracing::{debug, instrument};
+use utoipa::ToSchema;
+use warp::http::StatusCode;
+use warp::{body, reply, Filter, Rejection, Reply};
+
+/// The header which API keys are sent in
+pub const API_KEY_HEADER: &str = "x-api-key";
+/// The header which tells API clients how many requests their key has left today
+pub const QUOTA_REMAINING_HEADER: &str = "x-quota-remaining";
+
+const MAX_NAME_LEN: usize = 64;
+
+/// A request to a keyed API endpoint was refused
+#[derive(Copy, Clone, Debug)]
+pub enum ApiKeyRejection {
+    Missing,
+    /// The key is malformed, unknown, or has been revoked
+    Invalid,
+    QuotaExceeded {
+        quota: u64,
+    },
+}
+
+impl warp::reject::Reject for ApiKeyRejection {}
+
+/// The body of an error response from the JSON API
+#[derive(Serialize, ToSchema)]
+pub struct ApiError {
+    /// What went wrong, in English
+    error: String,
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "api_keys.askama.html")]
+struct ApiKeysTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    keys: Vec<ApiKey>,
+    max_keys: usize,
+    daily_quota: u64,
+    /// A key which was just issued, shown only this once
+    new_key: Option<String>,
+    message: Option<ApiKeyMessage>,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum ApiKeyMessage {
+    Created,
+    Revoked,
+    /// The user already holds the most keys allowed
+    LimitReached,
+    /// The name was empty or too long
+    InvalidName,
+}
+
+impl Display for ApiKeyMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ApiKeyMessage::Created => "created",
+            ApiKeyMessage::Revoked => "revoked",
+            ApiKeyMessage::LimitReached => "limit-reached",
+            ApiKeyMessage::InvalidName => "invalid-name",
+        };
+
+        f.write_str(s)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct NewKeyForm {
+    name: String,
+}
+
+pub fn api_keys(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    cfg: ApiKeyConfig,
+    body_limits: BodyLimits,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let with_cfg = warp::any().map(move || cfg);
+
+    let page = warp::get()
+        .and(warp::path::end())
+        .and(with_cfg)
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and_then(api_keys_page);
+
+    let create = warp::post()
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(csrf_protected_form())
+        .and(with_cfg)
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and_then(create_key);
+
+    let revoke = warp::post()
+        .and(warp::path![u64 / "revoke"])
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(csrf_protected())
+        .and(with_cfg)
+        .and(with_user_auth(db, site_ctx))
+        .and_then(revoke_key);
+
+    warp::path!("settings" / "api-keys" / ..)
+        .and(page.or(create).or(revoke))
+        .debug_boxed()
+}
+
+/// Requires a valid API key in the [`API_KEY_HEADER`] with quota left for today, and counts the
+/// request against it. Extracts the number of requests the key has left today.
+pub fn with_api_key(db: DbBase) -> impl Filter<Extract = (u64,), Error = Rejection> + Clone {
+    warp::header::optional::<String>(API_KEY_HEADER)
+        .and(with_public_db(db))
+        .and_then(check_api_key)
+}
+
+#[instrument(name = "Check API key", skip_all)]
+async fn check_api_key(key: Option<String>, db: impl PublicAccessDb) -> Result<u64, Rejection> {
+    let key = key.ok_or_else(|| warp::reject::custom(ApiKeyRejection::Missing))?;
+
+    let key_use = spawn_blocking_child(move || ApiKey::record_use(&db, &key))
+        .await
+        .map_err(ServerError::from)?;
+
+    match key_use {
+        KeyUse::Allowed { remaining } => Ok(remaining),
+        KeyUse::QuotaExceeded { quota } => {
+            Err(warp::reject::custom(ApiKeyRejection::QuotaExceeded {
+                quota,
+            }))
+        }
+        KeyUse::Invalid => Err(warp::reject::custom(ApiKeyRejection::Invalid)),
+    }
+}
+
+/// Answer requests refused by [`with_api_key`] with a JSON error. Other rejections are passed on.
+pub async fn recover_api_key_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
+    let Some(rejection) = err.find::<ApiKeyRejection>() else {
+        return Err(err);
+    };
+
+    debug!(?rejection, "API request refused");
+
+    let (status, error) = match rejection {
+        ApiKeyRejection::Missing => (
+            StatusCode::UNAUTHORIZED,
+            format!("This endpoint requires an API key in the {API_KEY_HEADER} header"),
+        ),
+        ApiKeyRejection::Invalid => (
+            StatusCode::UNAUTHORIZED,
+            "The API key is invalid or has been revoked".to_owned(),
+        ),
+        ApiKeyRejection::QuotaExceeded { quota } => (
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("The API key has used its quota of {quota} requests for today"),
+        ),
+    };
+
+    Ok(reply::with_status(reply::json(&ApiError { error }), status))
+}
+
+async fn render_page(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+    cfg: ApiKeyConfig,
+    new_key: Option<String>,
+    message: Option<ApiKeyMessage>,
+) -> Result<ApiKeysTemplate, Rejection> {
+    let user_id = user.id.get();
+    let keys = spawn_blocking_child(move || ApiKey::fetch_for_user(&db, user_id))
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(ApiKeysTemplate {
+        auth: user.into(),
+        i18n_info,
+        keys,
+        max_keys: cfg.max_per_user,
+        daily_quota: cfg.daily_quota,
+        new_key,
+        message,
+    })
+}
+
+#[instrument(name = "Show API keys", skip_all)]
+async fn api_keys_page(
+    cfg: ApiKeyConfig,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Rejection> {
+    render_page(user, i18n_info, db, cfg, None, None).await
+}
+
+#[instrument(name = "Create API key", skip(cfg, user, i18n_info, db))]
+async fn create_key(
+    form: NewKeyForm,
+    cfg: ApiKeyConfig,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let name = form.name.trim().to_owned();
+
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        let message = Some(ApiKeyMessage::InvalidName);
+        return render_page(user, i18n_info, db, cfg, None, message).await;
+    }
+
+    let user_id = user.id.get();
+    let db_clone = db.clone();
+    let new_key = spawn_blocking_child(move || {
+        ApiKey::create(&db_clone, user_id, &name, cfg.daily_quota, cfg.max_per_user)
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    match new_key {
+        Some(new_key) => {
+            let message = Some(ApiKeyMessage::Created);
+            render_page(user, i18n_info, db, cfg, Some(new_key.key), message).await
+        }
+        None => {
+            let message = Some(ApiKeyMessage::LimitReached);
+            render_page(user, i18n_info, db, cfg, None, message).await
+        }
+    }
+}
+
+#[instrument(name = "Revoke API key", skip(cfg, user, i18n_info, db))]
+async fn revoke_key(
+    key_id: u64,
+    cfg: ApiKeyConfig,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let user_id = user.id.get();
+    let db_clone = db.clone();
+    let revoked = spawn_blocking_child(move || ApiKey::revoke(&db_clone, user_id, key_id))
+        .await
+        .map_err(ServerError::from)?;
+
+    if !revoked {
+        return Err(warp::reject::not_found());
+    }
+
+    let message = Some(ApiKeyMessage::Revoked);
+    render_page(user, i18n_info, db, cfg, None, message).await
+}
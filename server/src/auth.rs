@@ -1,5 +1,12 @@
+use crate::csrf::{csrf_protected, csrf_protected_form, CsrfToken};
+use crate::database::drafts::Draft;
+use crate::database::recovery::RecoveryIdentity;
+use crate::database::terms::record_acceptance;
+use crate::database::user::Refresh;
 use crate::i18n::{I18nInfo, SiteContext, EN_ZA};
-use crate::serialization::{deserialize_checkbox, false_fn, qs_form};
+use crate::serialization::{deserialize_checkbox, false_fn, form};
+use crate::terms::TERMS_VERSION;
+use crate::{flash, security_headers};
 use crate::{spawn_blocking_child, spawn_send_interval, Config, DebugBoxedExt, DebugExt};
 use askama::Template;
 use cookie::time::OffsetDateTime;
@@ -8,10 +15,13 @@ use dashmap::DashMap;
 use fluent_templates::LanguageIdentifier;
 use isixhosa_click_macros::I18nTemplate;
 use isixhosa_common::auth::{Auth, Permissions};
-use isixhosa_common::database::db_impl::DbImpl;
+use isixhosa_common::database::db_impl::{DbImpl, ReadOnlyDbImpl};
 use isixhosa_common::database::{
     AdministratorAccessDb, DbBase, ModeratorAccessDb, PublicAccessDb, UserAccessDb,
 };
+use isixhosa_common::flash::FlashMessage;
+use isixhosa_common::search_direction::SearchDirection;
+use isixhosa_common::theme::Theme;
 use openid::{Client, Discovered, DiscoveredClient, Options, StandardClaims, Token, Userinfo};
 use ordered_float::OrderedFloat;
 use rand::Rng;
@@ -27,8 +37,9 @@ use std::time::{Duration, Instant};
 use tabled::Tabled;
 use tracing::{debug, error, instrument, trace, Span};
 use url::Url;
-use warp::http::header::ACCEPT_LANGUAGE;
+use warp::http::header::{ACCEPT_LANGUAGE, SET_COOKIE};
 use warp::http::uri;
+use warp::http::HeaderValue;
 use warp::path::FullPath;
 use warp::{
     http::{Response, StatusCode},
@@ -143,7 +154,22 @@ impl Handler<RequestToken> for OidcActor {
     }
 }
 
-const STAY_LOGGED_IN_COOKIE: &str = "isixhosa_click_login_token";
+/// Holds the session token, which lasts until the browser is closed
+pub const STAY_LOGGED_IN_COOKIE: &str = "isixhosa_click_login_token";
+/// Holds the refresh token, which starts a new session once the browser is restarted, for users
+/// who chose to stay signed in on the device
+pub const REMEMBER_COOKIE: &str = "isixhosa_click_remember";
+/// How long a remembered session lasts without being used
+pub const REMEMBER_FOR: Duration = Duration::from_secs(60 * 60 * 24 * 31); // One month
+/// How recently a moderator must have signed in with their provider to accept suggestions, since
+/// a remembered session could be on a lost or shared device
+pub const SUDO_MODE_DURATION: Duration = Duration::from_secs(30 * 60); // 30 minutes
+/// Holds the theme of users who aren't signed in. Signed in users' themes are stored in the database.
+pub const THEME_COOKIE: &str = "isixhosa_click_theme";
+/// Holds the search direction of users who aren't signed in, like [`THEME_COOKIE`]
+pub const SEARCH_DIRECTION_COOKIE: &str = "isixhosa_click_search_direction";
+/// Turns on school mode for the rest of the browser session, for everyone whether signed in or not
+pub const SCHOOL_MODE_COOKIE: &str = "isixhosa_click_school_mode";
 const SIGN_IN_SESSION_ID: &str = "isixhosa_click_sign_in_session";
 
 async fn sweep_in_progress_sign_ins() {
@@ -185,6 +211,9 @@ impl FromStr for StaySignedInToken {
 #[derive(Deserialize, Debug)]
 pub struct LoginRedirectQuery {
     redirect: Option<String>,
+    /// Make the user sign in to their provider again, even if they are already signed in to it
+    #[serde(default)]
+    reauthenticate: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -193,11 +222,21 @@ pub struct OpenIdLoginQuery {
     pub state: Option<String>,
 }
 
+/// Which provider a sign in is through, and what it is for
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SignInProvider {
+    /// Google, which accounts are created with
+    Primary,
+    /// The recovery provider, either to sign in or to link it to the given user's account
+    Recovery { linking_user: Option<NonZeroU64> },
+}
+
 pub enum SignInState {
     WaitingForOpenIdResponse {
         state_change: Instant,
         csrf_token: String,
         nonce: String,
+        provider: SignInProvider,
     },
     WaitingForSignUp {
         userinfo: Box<Userinfo>,
@@ -214,6 +253,14 @@ impl SignInState {
     }
 }
 
+/// The provider which users can link to their account to sign in with if they lose access to
+/// their Google account
+pub struct RecoveryProvider {
+    client: OpenIDClient,
+    name: String,
+    issuer: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OpenIdState {
     pub redirect: Option<String>,
@@ -256,6 +303,28 @@ pub struct FullUser {
     pub locked: bool,
     #[tabled(rename = "Language")]
     pub language: LanguageIdentifier,
+    #[tabled(skip)]
+    pub theme: Theme,
+    #[tabled(skip)]
+    pub search_direction: SearchDirection,
+    #[tabled(skip)]
+    pub unread_notifications: u64,
+    /// Only present when the user was authenticated from their login cookie
+    #[tabled(skip)]
+    pub csrf_token: Option<CsrfToken>,
+    /// When the user last signed in with their provider rather than a remembered session. Only
+    /// present when the user was authenticated from their login cookie.
+    #[tabled(skip)]
+    pub authenticated: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl FullUser {
+    /// Whether the user signed in with their provider recently enough to take privileged actions
+    pub fn signed_in_recently(&self) -> bool {
+        let sudo_mode = chrono::Duration::from_std(SUDO_MODE_DURATION).unwrap();
+        self.authenticated
+            .is_some_and(|authenticated| chrono::Utc::now() - authenticated < sudo_mode)
+    }
 }
 
 impl From<FullUser> for isixhosa_common::auth::User {
@@ -265,6 +334,10 @@ impl From<FullUser> for isixhosa_common::auth::User {
             username: user.username,
             permissions: user.permissions,
             language: user.language,
+            theme: user.theme,
+            search_direction: user.search_direction,
+            unread_notifications: user.unread_notifications,
+            csrf_token: user.csrf_token.map(String::from),
         }
     }
 }
@@ -332,6 +405,43 @@ pub async fn auth(
         .map(move || (client.clone(), Config::host_builder(&host, https_port)))
         .untuple_one();
 
+    let recovery = match &cfg.recovery_provider {
+        Some(provider) => {
+            let redirect = Config::host_builder(&cfg.host, cfg.https_port)
+                .path_and_query("/login/oauth2/code/recovery")
+                .build()
+                .unwrap()
+                .to_string();
+
+            let client = OidcActor::new(
+                provider.client.clone(),
+                provider.secret.clone(),
+                redirect,
+                Url::parse(&provider.issuer).unwrap(),
+            )
+            .await;
+
+            Some(Arc::new(RecoveryProvider {
+                client: xtra::spawn_tokio(client, Mailbox::bounded(32)),
+                name: provider.name.clone(),
+                issuer: provider.issuer.clone(),
+            }))
+        }
+        None => None,
+    };
+
+    // Account recovery pages 404 if no recovery provider is configured
+    let with_recovery = warp::any().and_then(move || {
+        let recovery = recovery.clone();
+        async move { recovery.ok_or_else(reject::not_found) }
+    });
+
+    let (host, https_port) = (cfg.host.clone(), cfg.https_port);
+    let with_recovery_host = with_recovery
+        .clone()
+        .map(move |recovery| (recovery, Config::host_builder(&host, https_port)))
+        .untuple_one();
+
     tokio::task::spawn(sweep_in_progress_sign_ins());
 
     let login = warp::path!("login" / "oauth2" / "authorization" / "oidc")
@@ -358,22 +468,24 @@ pub async fn auth(
     let sign_up = warp::post()
         .and(warp::path("signup"))
         .and(warp::path::end())
-        .and(warp::body::content_length_limit(64 * 1024))
-        .and(qs_form())
+        .and(warp::body::content_length_limit(cfg.body_limits.form))
+        .and(form())
         .and(with_session())
         .and(with_client_host)
         .and(with_any_auth(db.clone(), site_ctx.clone()))
         .and_then(signup_form_submit);
 
+    let recovery_enabled = cfg.recovery_provider.is_some();
     let settings_base = warp::path("settings")
         .and(warp::path::end())
-        .and(with_user_auth(db.clone(), site_ctx));
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and(warp::any().map(move || recovery_enabled));
 
     let settings_page = warp::get().and(settings_base.clone()).and_then(settings);
 
     let settings_submit = warp::post()
         .and(settings_base.clone())
-        .and(qs_form())
+        .and(csrf_protected_form())
         .and_then(settings_form_submit);
 
     let settings_fail = warp::post()
@@ -385,11 +497,121 @@ pub async fn auth(
         .or(settings_fail)
         .debug_boxed();
 
+    let appearance_base = warp::path!("settings" / "appearance").and(warp::path::end());
+
+    let appearance_page = warp::get()
+        .and(appearance_base.clone())
+        .and(with_any_auth(db.clone(), site_ctx.clone()))
+        .and_then(appearance_settings);
+
+    let appearance_submit_user = warp::post()
+        .and(appearance_base.clone())
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and(csrf_protected_form())
+        .and_then(appearance_form_submit_user);
+
+    let appearance_submit_anon = warp::post()
+        .and(appearance_base)
+        .and(with_any_auth(db.clone(), site_ctx.clone()))
+        .and(form())
+        .and_then(appearance_form_submit_anon);
+
+    let school_mode = warp::path!("settings" / "school_mode")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(cfg.body_limits.form))
+        .and(with_any_auth(db.clone(), site_ctx.clone()))
+        .and(form())
+        .and_then(school_mode_form_submit);
+
+    let appearance = appearance_page
+        .or(appearance_submit_user)
+        .or(appearance_submit_anon)
+        .or(school_mode)
+        .debug_boxed();
+
+    let sessions_page = warp::path!("settings" / "sessions")
+        .and(warp::get())
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and(warp::cookie(STAY_LOGGED_IN_COOKIE))
+        .and_then(session_settings);
+
+    let sessions_remember = warp::path!("settings" / "sessions" / "remember")
+        .and(warp::post())
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and(warp::cookie(STAY_LOGGED_IN_COOKIE))
+        .and(csrf_protected_form())
+        .and_then(reply_set_remembered);
+
+    let sessions_sign_out = warp::path!("settings" / "sessions" / "sign_out_others")
+        .and(warp::post())
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and(warp::cookie(STAY_LOGGED_IN_COOKIE))
+        .and(csrf_protected())
+        .and_then(reply_sign_out_others);
+
+    let sessions = sessions_page
+        .or(sessions_remember)
+        .or(sessions_sign_out)
+        .debug_boxed();
+
+    let recovery_login = warp::path!("login" / "oauth2" / "authorization" / "recovery")
+        .and(warp::get())
+        .and(with_recovery_host.clone())
+        .and(warp::query::<LoginRedirectQuery>())
+        .and_then(reply_authorize_recovery);
+
+    let recovery_code = warp::path!("login" / "oauth2" / "code" / "recovery")
+        .and(warp::get())
+        .and(warp::query::<OpenIdLoginQuery>())
+        .and(with_session())
+        .and(with_recovery_host.clone())
+        .and(with_any_auth(db.clone(), site_ctx.clone()))
+        .and_then(reply_recovery_login);
+
+    let recovery_base = warp::path!("settings" / "recovery")
+        .and(warp::get())
+        .and(with_recovery.clone())
+        .and(warp::query::<RecoveryQuery>());
+
+    let recovery_page_user = recovery_base
+        .clone()
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and_then(recovery_settings);
+
+    let recovery_page_anon = recovery_base
+        .and(with_any_auth(db.clone(), site_ctx.clone()))
+        .and_then(recovery_settings_anon);
+
+    let recovery_link = warp::path!("settings" / "recovery" / "link")
+        .and(warp::post())
+        .and(with_recovery_host)
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and(csrf_protected())
+        .and_then(reply_link_recovery);
+
+    let recovery_unlink = warp::path!("settings" / "recovery" / "unlink")
+        .and(warp::post())
+        .and(with_recovery)
+        .and(with_user_auth(db, site_ctx))
+        .and(csrf_protected())
+        .and_then(reply_unlink_recovery);
+
+    let recovery = recovery_login
+        .or(recovery_code)
+        .or(recovery_page_user)
+        .or(recovery_page_anon)
+        .or(recovery_link)
+        .or(recovery_unlink)
+        .debug_boxed();
+
     login
         .or(oidc_code)
         .or(sign_up)
         .or(logout)
         .or(settings)
+        .or(appearance)
+        .or(sessions)
+        .or(recovery)
         .debug_boxed()
 }
 
@@ -419,8 +641,25 @@ async fn reply_authorize(
     host_uri_builder: uri::Builder,
     redirect: LoginRedirectQuery,
 ) -> Result<impl Reply, Infallible> {
+    Ok(begin_sign_in(
+        oidc_client,
+        host_uri_builder,
+        redirect.redirect,
+        SignInProvider::Primary,
+        redirect.reauthenticate,
+    )
+    .await)
+}
+
+/// Redirect to the provider's sign in page, starting a new sign in session
+async fn begin_sign_in(
+    oidc_client: OpenIDClient,
+    host_uri_builder: uri::Builder,
+    redirect: Option<String>,
+    provider: SignInProvider,
+    reauthenticate: bool,
+) -> Response<&'static str> {
     let redirect = redirect
-        .redirect
         .and_then(|path| host_uri_builder.path_and_query(path).build().ok())
         .map(|uri| uri.to_string());
     let state = OpenIdState {
@@ -436,7 +675,11 @@ async fn reply_authorize(
             scope: Some("openid email".into()),
             state: Some(serde_json::to_string(&state).unwrap()),
             nonce: Some(nonce.clone()),
-            max_age: Some(*MAX_OIDC_AGE),
+            max_age: Some(if reauthenticate {
+                chrono::Duration::zero()
+            } else {
+                *MAX_OIDC_AGE
+            }),
             ..Default::default()
         }))
         .await
@@ -457,21 +700,25 @@ async fn reply_authorize(
             state_change: Instant::now(),
             csrf_token: state.csrf_token,
             nonce,
+            provider,
         },
     );
 
-    Ok(Response::builder()
+    Response::builder()
         .status(StatusCode::FOUND)
         .header(warp::http::header::LOCATION, auth_url.to_string())
         .header(warp::http::header::SET_COOKIE, session_id_cookie)
         .body("")
-        .unwrap())
+        .unwrap()
 }
 
+/// Exchange the code from the provider for a token, checking that the session was started through
+/// the same kind of provider
 async fn request_token(
     oidc_client: OpenIDClient,
     session_id: &SignInSessionId,
     openid_query: &OpenIdLoginQuery,
+    recovery: bool,
 ) -> anyhow::Result<Option<(Token, Userinfo)>> {
     #[derive(Debug)]
     enum SignInInvalid {
@@ -490,8 +737,13 @@ async fn request_token(
 
     let (csrf_token, nonce) = match IN_PROGRESS_SIGN_INS.get(session_id).as_deref() {
         Some(SignInState::WaitingForOpenIdResponse {
-            csrf_token, nonce, ..
-        }) => (csrf_token.clone(), nonce.clone()),
+            csrf_token,
+            nonce,
+            provider,
+            ..
+        }) if matches!(provider, SignInProvider::Recovery { .. }) == recovery => {
+            (csrf_token.clone(), nonce.clone())
+        }
         Some(_) => return Err(SignInInvalid::SignInState.into()),
         None => return Err(SignInInvalid::SignInId.into()),
     };
@@ -525,7 +777,7 @@ async fn reply_login(
     i18n_info: I18nInfo,
     db: impl PublicAccessDb,
 ) -> Result<impl Reply, Infallible> {
-    let request_token = request_token(oidc_client, &session_id, &openid_query).await;
+    let request_token = request_token(oidc_client, &session_id, &openid_query, false).await;
     let mk_err = || {
         IN_PROGRESS_SIGN_INS.remove(&session_id);
 
@@ -606,8 +858,6 @@ async fn reply_insert_session(
     host_uri_builder: uri::Builder,
     redirect_url: Option<String>,
 ) -> impl Reply {
-    const ONE_MONTH: Duration = Duration::from_secs(60 * 60 * 24 * 31);
-
     let redirect_url = redirect_url.unwrap_or_else(|| {
         host_uri_builder
             .path_and_query("")
@@ -616,30 +866,168 @@ async fn reply_insert_session(
             .to_string()
     });
 
-    let token = spawn_blocking_child(move || StaySignedInToken::new(&db, user.id.get()))
-        .await
-        .unwrap();
-    let authorization_cookie = Cookie::build((
-        STAY_LOGGED_IN_COOKIE,
-        serde_json::to_string(&(token.token, token.token_id)).unwrap(),
-    ))
-    .path("/")
-    .http_only(true)
-    .secure(true)
-    .same_site(SameSite::Strict)
-    .expires(OffsetDateTime::now_utc() + ONE_MONTH)
-    .build()
-    .to_string();
+    // Sessions are remembered unless the user turns it off for the device in their settings
+    let (session, refresh) = spawn_blocking_child(move || {
+        let session = StaySignedInToken::new(&db, user.id.get());
+        let refresh = session.remember(&db);
+        (session, refresh)
+    })
+    .await
+    .unwrap();
 
     IN_PROGRESS_SIGN_INS.remove(&session_id);
 
-    warp::reply::with_header(
-        askama_warp::reply(&AuthRedirect {
-            redirect_url,
-            i18n_info,
-        }),
-        warp::http::header::SET_COOKIE,
-        authorization_cookie,
+    let mut response = askama_warp::reply(&AuthRedirect {
+        redirect_url,
+        i18n_info,
+    })
+    .into_response();
+
+    let headers = response.headers_mut();
+    headers.append(SET_COOKIE, session_cookie(&session));
+    headers.append(SET_COOKIE, remember_cookie(Some(&refresh)));
+    response
+}
+
+fn login_cookie(name: &'static str, value: String, expires: Expiration) -> HeaderValue {
+    let cookie = Cookie::build((name, value))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .expires(expires)
+        .build();
+
+    HeaderValue::from_str(&cookie.to_string()).unwrap()
+}
+
+/// The cookie holding the session token, which lasts until the browser is closed
+fn session_cookie(session: &StaySignedInToken) -> HeaderValue {
+    let value = serde_json::to_string(&(&session.token, session.token_id)).unwrap();
+    login_cookie(STAY_LOGGED_IN_COOKIE, value, Expiration::Session)
+}
+
+/// The cookie holding the refresh token, or one which deletes it if there is none
+fn remember_cookie(refresh: Option<&StaySignedInToken>) -> HeaderValue {
+    match refresh {
+        Some(refresh) => {
+            let value = serde_json::to_string(&(&refresh.token, refresh.token_id)).unwrap();
+            let expires = OffsetDateTime::now_utc() + REMEMBER_FOR;
+            login_cookie(REMEMBER_COOKIE, value, expires.into())
+        }
+        None => login_cookie(
+            REMEMBER_COOKIE,
+            String::new(),
+            OffsetDateTime::now_utc().into(),
+        ),
+    }
+}
+
+/// Starts a new session from the remember cookie once the browser has been restarted, and then
+/// redirects back to the same page with the new cookies. Only page loads are refreshed, so that
+/// the many requests for a page's resources don't all try to use the same refresh token.
+pub fn refresh_session(
+    db: DbBase,
+) -> impl Filter<Extract = (Response<&'static str>,), Error = Rejection> + Clone {
+    let session_cookie = warp::cookie::optional::<String>(STAY_LOGGED_IN_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify();
+    let remember = warp::cookie::optional::<String>(REMEMBER_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify();
+    let fetch_mode = warp::header::optional::<String>("sec-fetch-mode")
+        .or(warp::any().map(|| None))
+        .unify();
+    let query = warp::query::raw().or(warp::any().map(String::new)).unify();
+
+    warp::method()
+        .and(session_cookie)
+        .and(remember)
+        .and(fetch_mode)
+        .and(warp::path::full())
+        .and(query)
+        .and_then(
+            move |method,
+                  session: Option<String>,
+                  remember: Option<String>,
+                  fetch_mode: Option<String>,
+                  path: FullPath,
+                  query: String| {
+                let db = DbImpl(db.primary.clone());
+
+                async move {
+                    // Browsers which don't send `Sec-Fetch-Mode` are assumed to be loading a page
+                    let is_page_load = method == warp::http::Method::GET
+                        && fetch_mode.as_deref().unwrap_or("navigate") == "navigate";
+
+                    let remember = match remember {
+                        Some(remember) if session.is_none() && is_page_load => remember,
+                        _ => return Err(reject::not_found()),
+                    };
+
+                    let refresh = match StaySignedInToken::from_str(&remember) {
+                        Ok(refresh) => {
+                            let remember_for = chrono::Duration::from_std(REMEMBER_FOR).unwrap();
+                            spawn_blocking_child(move || {
+                                StaySignedInToken::refresh(&db, &refresh, remember_for)
+                            })
+                            .await
+                            .unwrap()
+                        }
+                        Err(_) => Refresh::Invalid,
+                    };
+
+                    let location = refreshed_location(path.as_str(), &query);
+                    let response = Response::builder()
+                        .status(StatusCode::FOUND)
+                        .header(warp::http::header::LOCATION, location);
+
+                    let response = match refresh {
+                        Refresh::Refreshed { session, refresh } => response
+                            .header(SET_COOKIE, session_cookie(&session))
+                            .header(SET_COOKIE, remember_cookie(Some(&refresh))),
+                        // Let the request through signed out, since the other request that
+                        // refreshed the session will set the new cookies
+                        Refresh::AlreadyRefreshed => return Err(reject::not_found()),
+                        Refresh::Invalid => {
+                            debug!("Invalid refresh token; deleting it");
+                            response.header(SET_COOKIE, remember_cookie(None))
+                        }
+                    };
+
+                    Ok(response.body("").unwrap())
+                }
+            },
+        )
+}
+
+/// The page to return to once the session has been refreshed. Leading slashes are collapsed into
+/// one, as e.g `//evil.example/` would otherwise be followed to another site.
+fn refreshed_location(path: &str, query: &str) -> String {
+    let path = format!("/{}", path.trim_start_matches(['/', '\\']));
+
+    if query.is_empty() {
+        path
+    } else {
+        format!("{path}?{query}")
+    }
+}
+
+/// Send a moderator to sign in with Google again before a privileged action, returning them to
+/// the given page afterwards
+pub fn reply_reauthenticate(return_to: &str) -> warp::reply::Response {
+    Response::builder()
+        .status(StatusCode::SEE_OTHER)
+        .header(warp::http::header::LOCATION, reauthenticate_url(return_to))
+        .body("")
+        .unwrap()
+        .into_response()
+}
+
+pub fn reauthenticate_url(return_to: &str) -> String {
+    format!(
+        "/login/oauth2/authorization/oidc?reauthenticate=true&redirect={}",
+        percent_encoding::utf8_percent_encode(return_to, percent_encoding::NON_ALPHANUMERIC)
     )
 }
 
@@ -701,7 +1089,7 @@ async fn signup_form_submit(
 
     let db_clone = db.clone();
     let user = spawn_blocking_child(move || {
-        FullUser::register(
+        let user = FullUser::register(
             &db,
             userinfo,
             form.username,
@@ -709,7 +1097,11 @@ async fn signup_form_submit(
             email,
             Permissions::User,
             form.language,
-        )
+        );
+
+        // The terms were agreed to above, so they needn't be accepted again before submitting
+        record_acceptance(&db, user.id.get(), TERMS_VERSION).unwrap();
+        user
     })
     .await
     .unwrap();
@@ -726,27 +1118,368 @@ async fn signup_form_submit(
     .into_response())
 }
 
+#[derive(Template, I18nTemplate)]
+#[template(path = "sessions.askama.html")]
+struct SessionSettings {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    /// Whether the current session is kept after the browser is restarted
+    remembered: bool,
+    message: Option<SessionMessage>,
+}
+
+enum SessionMessage {
+    Remembered,
+    Forgotten,
+    /// The given number of other sessions were ended
+    SignedOutOthers(usize),
+}
+
+#[derive(Deserialize, Debug)]
+struct RememberForm {
+    remember: bool,
+}
+
+async fn session_settings(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+    token: StaySignedInToken,
+) -> Result<impl Reply, Infallible> {
+    let remembered = spawn_blocking_child(move || token.is_remembered(&db))
+        .await
+        .unwrap();
+
+    Ok(SessionSettings {
+        auth: user.into(),
+        i18n_info,
+        remembered,
+        message: None,
+    })
+}
+
+async fn reply_set_remembered(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+    token: StaySignedInToken,
+    form: RememberForm,
+) -> Result<impl Reply, Infallible> {
+    let remember = form.remember;
+    let refresh = spawn_blocking_child(move || {
+        if remember {
+            Some(token.remember(&db))
+        } else {
+            token.forget(&db);
+            None
+        }
+    })
+    .await
+    .unwrap();
+
+    let page = SessionSettings {
+        auth: user.into(),
+        i18n_info,
+        remembered: remember,
+        message: Some(if remember {
+            SessionMessage::Remembered
+        } else {
+            SessionMessage::Forgotten
+        }),
+    };
+
+    Ok(warp::reply::with_header(
+        page,
+        SET_COOKIE,
+        remember_cookie(refresh.as_ref()),
+    ))
+}
+
+async fn reply_sign_out_others(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+    token: StaySignedInToken,
+) -> Result<impl Reply, Infallible> {
+    let user_id = user.id.get();
+    let (ended, remembered) =
+        spawn_blocking_child(move || (token.delete_others(&db, user_id), token.is_remembered(&db)))
+            .await
+            .unwrap();
+
+    Ok(SessionSettings {
+        auth: user.into(),
+        i18n_info,
+        remembered,
+        message: Some(SessionMessage::SignedOutOthers(ended)),
+    })
+}
+
+async fn reply_authorize_recovery(
+    recovery: Arc<RecoveryProvider>,
+    host_uri_builder: uri::Builder,
+    redirect: LoginRedirectQuery,
+) -> Result<impl Reply, Infallible> {
+    let provider = SignInProvider::Recovery { linking_user: None };
+
+    Ok(begin_sign_in(
+        recovery.client.clone(),
+        host_uri_builder,
+        redirect.redirect,
+        provider,
+        redirect.reauthenticate,
+    )
+    .await)
+}
+
+/// Either sign in with the account linked to the recovery provider, or link it to the account
+/// which started the sign in
+async fn reply_recovery_login(
+    openid_query: OpenIdLoginQuery,
+    session_id: SignInSessionId,
+    recovery: Arc<RecoveryProvider>,
+    host_builder: uri::Builder,
+    _: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Infallible> {
+    // The user's login cookie isn't sent on the redirect back from the provider, so the user who is
+    // linking the account is kept in the sign in session instead
+    let linking_user = match IN_PROGRESS_SIGN_INS.get(&session_id).as_deref() {
+        Some(SignInState::WaitingForOpenIdResponse {
+            provider: SignInProvider::Recovery { linking_user },
+            ..
+        }) => *linking_user,
+        _ => None,
+    };
+
+    let request_token = request_token(recovery.client.clone(), &session_id, &openid_query, true);
+    let mk_err = || {
+        IN_PROGRESS_SIGN_INS.remove(&session_id);
+
+        Ok(Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body("")
+            .unwrap()
+            .into_response())
+    };
+
+    let (token, oidc_id, userinfo) = match request_token.await {
+        Ok(Some((token, user_info))) => match user_info.sub {
+            Some(ref id) => (token, id.clone(), user_info),
+            None => {
+                error!("Error requesting recovery token: no remote `sub` id found");
+                return mk_err();
+            }
+        },
+        Ok(None) => {
+            error!("Error requesting token during recovery sign in: no id_token found");
+            return mk_err();
+        }
+        Err(err) => {
+            error!("Error requesting token during recovery sign in: {:#?}", err);
+            return mk_err();
+        }
+    };
+
+    let redirect_with_message = |message: RecoveryMessage| {
+        IN_PROGRESS_SIGN_INS.remove(&session_id);
+
+        let redirect_url = host_builder
+            .clone()
+            .path_and_query(format!("/settings/recovery?message={message}"))
+            .build()
+            .unwrap()
+            .to_string();
+
+        askama_warp::reply(&AuthRedirect {
+            redirect_url,
+            i18n_info: i18n_info.clone(),
+        })
+        .into_response()
+    };
+
+    let issuer = recovery.issuer.clone();
+
+    if let Some(user_id) = linking_user {
+        let linked = spawn_blocking_child(move || {
+            let email = userinfo.email.as_deref();
+            RecoveryIdentity::link(&db, token, user_id.get(), &issuer, &oidc_id, email)
+        })
+        .await
+        .unwrap();
+
+        let message = if linked {
+            RecoveryMessage::Linked
+        } else {
+            RecoveryMessage::AlreadyLinked
+        };
+
+        return Ok(redirect_with_message(message));
+    }
+
+    let db_clone = db.clone();
+    let user = spawn_blocking_child(move || {
+        RecoveryIdentity::user_for(&db_clone, &issuer, &oidc_id)
+            .and_then(|user_id| FullUser::fetch_by_id(&db_clone, user_id))
+    })
+    .await
+    .unwrap();
+
+    let Some(user) = user else {
+        return Ok(redirect_with_message(RecoveryMessage::NotLinked));
+    };
+
+    let state: OpenIdState = serde_json::from_str(openid_query.state.as_ref().unwrap()).unwrap();
+
+    Ok(reply_insert_session(
+        db,
+        i18n_info,
+        session_id,
+        user,
+        host_builder,
+        state.redirect,
+    )
+    .await
+    .into_response())
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "recovery.askama.html")]
+struct RecoverySettings {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    provider_name: String,
+    /// The account the user has linked, if they are signed in and have linked one
+    identity: Option<RecoveryIdentity>,
+    message: Option<RecoveryMessage>,
+}
+
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+enum RecoveryMessage {
+    Linked,
+    Unlinked,
+    /// The account is already linked to another user's account
+    AlreadyLinked,
+    /// Someone signed in with an account which isn't linked to any user's account
+    NotLinked,
+}
+
+// Used in recovery.askama.html
+impl Display for RecoveryMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            RecoveryMessage::Linked => "linked",
+            RecoveryMessage::Unlinked => "unlinked",
+            RecoveryMessage::AlreadyLinked => "already-linked",
+            RecoveryMessage::NotLinked => "not-linked",
+        };
+
+        f.write_str(s)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct RecoveryQuery {
+    message: Option<RecoveryMessage>,
+}
+
+async fn recovery_settings(
+    recovery: Arc<RecoveryProvider>,
+    query: RecoveryQuery,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Infallible> {
+    let (user_id, issuer) = (user.id.get(), recovery.issuer.clone());
+    let identity =
+        spawn_blocking_child(move || RecoveryIdentity::fetch_for_user(&db, user_id, &issuer))
+            .await
+            .unwrap();
+
+    Ok(RecoverySettings {
+        auth: user.into(),
+        i18n_info,
+        provider_name: recovery.name.clone(),
+        identity,
+        message: query.message,
+    })
+}
+
+async fn recovery_settings_anon(
+    recovery: Arc<RecoveryProvider>,
+    query: RecoveryQuery,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    _db: impl PublicAccessDb,
+) -> Result<impl Reply, Infallible> {
+    Ok(RecoverySettings {
+        auth,
+        i18n_info,
+        provider_name: recovery.name.clone(),
+        identity: None,
+        message: query.message,
+    })
+}
+
+async fn reply_link_recovery(
+    recovery: Arc<RecoveryProvider>,
+    host_uri_builder: uri::Builder,
+    user: FullUser,
+    _i18n_info: I18nInfo,
+    _db: impl UserAccessDb,
+) -> Result<impl Reply, Infallible> {
+    let provider = SignInProvider::Recovery {
+        linking_user: Some(user.id),
+    };
+
+    Ok(begin_sign_in(
+        recovery.client.clone(),
+        host_uri_builder,
+        None,
+        provider,
+        false,
+    )
+    .await)
+}
+
+async fn reply_unlink_recovery(
+    recovery: Arc<RecoveryProvider>,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Infallible> {
+    let user_id = user.id.get();
+    spawn_blocking_child(move || RecoveryIdentity::unlink(&db, user_id))
+        .await
+        .unwrap();
+
+    Ok(RecoverySettings {
+        auth: user.into(),
+        i18n_info,
+        provider_name: recovery.name.clone(),
+        identity: None,
+        message: Some(RecoveryMessage::Unlinked),
+    })
+}
+
 async fn reply_logout(
     _user: FullUser, // This _user is important as it implicitly validates the given token
     _i18n_info: I18nInfo,
     db: impl UserAccessDb,
     token: StaySignedInToken,
 ) -> Result<impl Reply, Infallible> {
-    let deleted_cookie = Cookie::build((STAY_LOGGED_IN_COOKIE, ""))
-        .path("/")
-        .http_only(true)
-        .secure(true)
-        .same_site(SameSite::Strict)
-        .expires(OffsetDateTime::now_utc())
-        .build()
-        .to_string();
+    let now = OffsetDateTime::now_utc().into();
+    let deleted_cookie = login_cookie(STAY_LOGGED_IN_COOKIE, String::new(), now);
 
     spawn_blocking_child(move || token.delete(&db));
 
     Ok(Response::builder()
         .status(StatusCode::FOUND)
         .header(warp::http::header::LOCATION, "/search")
-        .header(warp::http::header::SET_COOKIE, deleted_cookie)
+        .header(SET_COOKIE, deleted_cookie)
+        .header(SET_COOKIE, remember_cookie(None))
         .body("")
         .unwrap())
 }
@@ -758,18 +1491,29 @@ struct Settings {
     user: FullUser,
     i18n_info: I18nInfo,
     previous_success: Option<bool>,
+    drafts: Vec<Draft>,
+    /// Whether a recovery provider is configured, so that account recovery can be set up
+    recovery_enabled: bool,
 }
 
 async fn settings(
     user: FullUser,
     i18n_info: I18nInfo,
-    _db: impl UserAccessDb,
+    db: impl UserAccessDb,
+    recovery_enabled: bool,
 ) -> Result<impl Reply, Infallible> {
+    let user_id = user.id.get();
+    let drafts = spawn_blocking_child(move || Draft::fetch_all_for_user(&db, user_id))
+        .await
+        .unwrap();
+
     Ok(Settings {
         auth: user.clone().into(),
         user,
         i18n_info,
         previous_success: None,
+        drafts,
+        recovery_enabled,
     })
 }
 
@@ -787,13 +1531,21 @@ struct SettingsForm {
 async fn failed_to_submit_settings(
     user: FullUser,
     i18n_info: I18nInfo,
-    _db: impl UserAccessDb,
+    db: impl UserAccessDb,
+    recovery_enabled: bool,
 ) -> Result<impl Reply, Infallible> {
+    let user_id = user.id.get();
+    let drafts = spawn_blocking_child(move || Draft::fetch_all_for_user(&db, user_id))
+        .await
+        .unwrap();
+
     Ok(Settings {
         auth: user.clone().into(),
         user,
         i18n_info,
         previous_success: Some(false),
+        drafts,
+        recovery_enabled,
     })
 }
 
@@ -801,6 +1553,7 @@ async fn settings_form_submit(
     mut user: FullUser,
     mut i18n_info: I18nInfo,
     db: impl UserAccessDb,
+    recovery_enabled: bool,
     form: SettingsForm,
 ) -> Result<impl Reply, Infallible> {
     spawn_blocking_child(move || {
@@ -815,17 +1568,186 @@ async fn settings_form_submit(
             }
         };
 
+        let drafts = Draft::fetch_all_for_user(&db, user.id.get());
+
         Ok(Settings {
             auth: user.clone().into(),
             user,
             i18n_info,
             previous_success: Some(prev_success),
+            drafts,
+            recovery_enabled,
+        })
+    })
+    .await
+    .unwrap()
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "appearance_settings.askama.html")]
+struct AppearanceSettings {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    previous_success: Option<bool>,
+}
+
+// Used in appearance_settings.askama.html
+impl AppearanceSettings {
+    fn is_current(&self, theme: &Theme) -> bool {
+        *theme == self.i18n_info.theme
+    }
+
+    fn is_current_direction(&self, direction: &SearchDirection) -> bool {
+        *direction == self.i18n_info.search_direction
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct AppearanceForm {
+    theme: Theme,
+    search_direction: SearchDirection,
+}
+
+async fn appearance_settings(
+    auth: Auth,
+    i18n_info: I18nInfo,
+    _db: impl PublicAccessDb,
+) -> Result<impl Reply, Infallible> {
+    Ok(AppearanceSettings {
+        auth,
+        i18n_info,
+        previous_success: None,
+    })
+}
+
+async fn appearance_form_submit_user(
+    mut user: FullUser,
+    mut i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+    form: AppearanceForm,
+) -> Result<impl Reply, Infallible> {
+    spawn_blocking_child(move || {
+        let res = user
+            .update_theme(&db, form.theme)
+            .and_then(|()| user.update_search_direction(&db, form.search_direction));
+
+        let prev_success = match res {
+            Ok(()) => {
+                i18n_info.theme = form.theme;
+                i18n_info.search_direction = form.search_direction;
+                true
+            }
+            Err(err) => {
+                error!("Error updating user appearance settings: {err:#?}");
+                false
+            }
+        };
+
+        Ok(AppearanceSettings {
+            auth: user.into(),
+            i18n_info,
+            previous_success: Some(prev_success),
         })
     })
     .await
     .unwrap()
 }
 
+async fn appearance_form_submit_anon(
+    auth: Auth,
+    mut i18n_info: I18nInfo,
+    _db: impl PublicAccessDb,
+    form: AppearanceForm,
+) -> Result<impl Reply, Infallible> {
+    const ONE_YEAR: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+
+    // Signed in users only end up here if their CSRF token was invalid
+    if auth.user().is_some() {
+        return Ok(AppearanceSettings {
+            auth,
+            i18n_info,
+            previous_success: Some(false),
+        }
+        .into_response());
+    }
+
+    i18n_info.theme = form.theme;
+    i18n_info.search_direction = form.search_direction;
+
+    let preference_cookie = |name: &str, value: &str| {
+        Cookie::build((name, value))
+            .path("/")
+            .http_only(true)
+            .secure(true)
+            .same_site(SameSite::Lax)
+            .expires(OffsetDateTime::now_utc() + ONE_YEAR)
+            .build()
+            .to_string()
+    };
+
+    let mut response = AppearanceSettings {
+        auth,
+        i18n_info,
+        previous_success: Some(true),
+    }
+    .into_response();
+
+    let headers = response.headers_mut();
+    for cookie in [
+        preference_cookie(THEME_COOKIE, form.theme.name()),
+        preference_cookie(SEARCH_DIRECTION_COOKIE, form.search_direction.name()),
+    ] {
+        headers.append(SET_COOKIE, HeaderValue::from_str(&cookie).unwrap());
+    }
+
+    Ok(response)
+}
+
+#[derive(Deserialize, Debug)]
+struct SchoolModeForm {
+    #[serde(default = "false_fn")]
+    #[serde(deserialize_with = "deserialize_checkbox")]
+    school_mode: bool,
+}
+
+/// School mode is kept in a cookie which lasts until the browser is closed, so that it doesn't
+/// stay on for the next person to use a shared computer without them knowing
+async fn school_mode_form_submit(
+    auth: Auth,
+    mut i18n_info: I18nInfo,
+    _db: impl PublicAccessDb,
+    form: SchoolModeForm,
+) -> Result<impl Reply, Infallible> {
+    let mut cookie = Cookie::build((SCHOOL_MODE_COOKIE, "on"))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .expires(Expiration::Session)
+        .build();
+
+    if form.school_mode {
+        i18n_info.school_mode = true;
+    } else {
+        cookie.set_max_age(cookie::time::Duration::ZERO);
+        i18n_info.school_mode = i18n_info.ctx.school_mode;
+    }
+
+    let mut response = AppearanceSettings {
+        auth,
+        i18n_info,
+        previous_success: Some(true),
+    }
+    .into_response();
+
+    response.headers_mut().append(
+        SET_COOKIE,
+        HeaderValue::from_str(&cookie.to_string()).unwrap(),
+    );
+
+    Ok(response)
+}
+
 #[derive(Debug)]
 pub struct Unauthorized {
     pub reason: UnauthorizedReason,
@@ -858,8 +1780,12 @@ async fn extract_user(
         let span = Span::current();
 
         if let Some(stay_signed_in) = stay_signed_in {
-            if let Some(user) = stay_signed_in.verify_token(&db) {
-                let user = FullUser::fetch_by_id(&db, user).unwrap();
+            if let Some((user, authenticated)) = stay_signed_in.verify_token(&db) {
+                let user = FullUser {
+                    csrf_token: Some(CsrfToken::for_session(&stay_signed_in)),
+                    authenticated: Some(authenticated),
+                    ..FullUser::fetch_by_id(&db, user).unwrap()
+                };
 
                 span.record("id", user.id);
                 span.record("name", user.username.as_str());
@@ -897,22 +1823,156 @@ async fn extract_user(
     .unwrap()
 }
 
-async fn extract_i18n_from_auth(
-    auth: Auth,
-    ctx: Arc<SiteContext>,
-    db: impl PublicAccessDb,
+/// Everything needed to build the context shared by every page, i.e the [`Auth`] and [`I18nInfo`]
+/// given to each template, before anything has been looked up in the database. All of the auth
+/// filters are built on top of [`with_request_info`], so that this is worked out the same way
+/// everywhere.
+struct RequestInfo {
+    path: String,
+    stay_signed_in: Option<StaySignedInToken>,
     accept_lang: Option<String>,
-) -> Result<(Auth, I18nInfo, impl PublicAccessDb), Rejection> {
-    if let Some(user) = auth.user() {
-        let i18n = I18nInfo {
-            user_language: user.language.clone(),
+    theme_cookie: Option<String>,
+    search_direction_cookie: Option<String>,
+    school_mode_cookie: Option<String>,
+    flashes: Vec<FlashMessage>,
+    csp_nonce: String,
+}
+
+impl RequestInfo {
+    /// Signed in users' preferences are taken from their account, and everyone else's from their
+    /// browser. School mode is on if the whole site is in school mode, or if it was turned on for
+    /// this browser session.
+    fn i18n_info(
+        &self,
+        ctx: Arc<SiteContext>,
+        user: Option<(&LanguageIdentifier, Theme, SearchDirection)>,
+    ) -> I18nInfo {
+        let school_mode = ctx.school_mode || self.school_mode_cookie.as_deref() == Some("on");
+
+        match user {
+            Some((language, theme, search_direction)) => I18nInfo {
+                user_language: language.clone(),
+                theme,
+                search_direction,
+                school_mode,
+                flashes: self.flashes.clone(),
+                csp_nonce: self.csp_nonce.clone(),
+                ctx,
+            },
+            None => I18nInfo {
+                user_language: language_from_header(self.accept_lang.as_deref(), &ctx),
+                theme: self
+                    .theme_cookie
+                    .as_deref()
+                    .and_then(|theme| theme.parse().ok())
+                    .unwrap_or_default(),
+                search_direction: self
+                    .search_direction_cookie
+                    .as_deref()
+                    .and_then(|direction| direction.parse().ok())
+                    .unwrap_or_default(),
+                school_mode,
+                flashes: self.flashes.clone(),
+                csp_nonce: self.csp_nonce.clone(),
+                ctx,
+            },
+        }
+    }
+
+    /// Builds the page context for anyone, whether they're signed in or not
+    async fn resolve_any<DB>(mut self, db: DB, ctx: Arc<SiteContext>) -> (Auth, I18nInfo)
+    where
+        DB: PublicAccessDb,
+    {
+        let auth: Auth = extract_user(db, self.path.clone(), self.stay_signed_in.take())
+            .await
+            .map(Auth::from)
+            .unwrap_or_default();
+        let user = auth
+            .user()
+            .map(|user| (&user.language, user.theme, user.search_direction));
+        let i18n_info = self.i18n_info(ctx, user);
+        (auth, i18n_info)
+    }
+
+    /// Builds the page context for a signed in user, rejecting the request if they don't have the
+    /// given permissions
+    async fn resolve_user<DB>(
+        mut self,
+        db: DB,
+        ctx: Arc<SiteContext>,
+        permissions: Permissions,
+    ) -> Result<(FullUser, I18nInfo), Rejection>
+    where
+        DB: PublicAccessDb,
+    {
+        let user = extract_user(db, self.path.clone(), self.stay_signed_in.take()).await?;
+
+        if !user.permissions.contains(permissions) {
+            return Err(reject::custom(Unauthorized {
+                reason: UnauthorizedReason::NoPermissions,
+                redirect: self.path,
+            }));
+        }
+
+        let i18n_info = self.i18n_info(
             ctx,
-        };
-        return Ok((auth, i18n, db));
+            Some((&user.language, user.theme, user.search_direction)),
+        );
+        Ok((user, i18n_info))
     }
+}
+
+fn with_request_info() -> impl Filter<Extract = (RequestInfo,), Error = Infallible> + Clone {
+    let stay_signed_in = warp::cookie::optional(STAY_LOGGED_IN_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify();
+    let accept_lang = warp::header::optional(ACCEPT_LANGUAGE.as_str())
+        .or(warp::any().map(|| None))
+        .unify();
+    let theme_cookie = warp::cookie::optional(THEME_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify();
+    let search_direction_cookie = warp::cookie::optional(SEARCH_DIRECTION_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify();
+    let school_mode_cookie = warp::cookie::optional(SCHOOL_MODE_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify();
+
+    warp::path::full()
+        .map(|path: FullPath| path.as_str().to_owned())
+        .and(stay_signed_in)
+        .and(accept_lang)
+        .and(theme_cookie)
+        .and(search_direction_cookie)
+        .and(school_mode_cookie)
+        .and(flash::with_flashes())
+        .and(security_headers::csp_nonce())
+        .map(
+            |path,
+             stay_signed_in,
+             accept_lang,
+             theme_cookie,
+             search_direction_cookie,
+             school_mode_cookie,
+             flashes,
+             csp_nonce| RequestInfo {
+                path,
+                stay_signed_in,
+                accept_lang,
+                theme_cookie,
+                search_direction_cookie,
+                school_mode_cookie,
+                flashes,
+                csp_nonce,
+            },
+        )
+}
 
+fn language_from_header(accept_lang: Option<&str>, ctx: &SiteContext) -> LanguageIdentifier {
     let all = accept_language::intersection_with_quality(
-        accept_lang.as_deref().unwrap_or("en-ZA"),
+        accept_lang.unwrap_or("en-ZA"),
         ctx.supported_langs,
     );
 
@@ -922,44 +1982,66 @@ async fn extract_i18n_from_auth(
         .map(|(lang, _quality)| lang.as_str())
         .unwrap_or("en-ZA");
 
-    let user_language = best_lang.parse().unwrap_or(EN_ZA);
-    let i18n = I18nInfo { user_language, ctx };
-
-    Ok((auth, i18n, db))
+    best_lang.parse().unwrap_or(EN_ZA)
 }
 
-async fn extract_i18n_from_user<DB>(
-    user: FullUser,
+/// The request details needed to work out who the user is, without yet looking them up in the
+/// database. Used by the error pages, which only need this once a request has already failed.
+pub struct DeferredAuth {
+    db: DbImpl,
     ctx: Arc<SiteContext>,
-    db: DB,
-) -> Result<(FullUser, I18nInfo, DB), Rejection>
-where
-    DB: PublicAccessDb,
-{
-    let i18n = I18nInfo {
-        user_language: user.language.clone(),
-        ctx,
-    };
+    request: RequestInfo,
+}
 
-    Ok((user, i18n, db))
+impl DeferredAuth {
+    pub async fn resolve(self) -> (Auth, I18nInfo) {
+        self.request.resolve_any(self.db, self.ctx).await
+    }
+}
+
+pub fn with_deferred_auth(
+    db: DbBase,
+    ctx: Arc<SiteContext>,
+) -> impl Filter<Extract = (DeferredAuth,), Error = Infallible> + Clone {
+    with_request_info().map(move |request| DeferredAuth {
+        db: DbImpl(db.primary.clone()),
+        ctx: ctx.clone(),
+        request,
+    })
 }
 
 pub fn with_any_auth(
     db: DbBase,
     ctx: Arc<SiteContext>,
 ) -> impl Filter<Extract = (Auth, I18nInfo, impl PublicAccessDb), Error = Rejection> + Clone {
-    let db_clone = db.clone();
-    warp::path::full()
-        .map(|path: FullPath| path.as_str().to_owned())
-        .and(warp::cookie::optional(STAY_LOGGED_IN_COOKIE))
-        .and_then(move |path, cookie| extract_user(DbImpl(db.0.clone()), path, cookie))
-        .map(|user: FullUser| user.into())
-        .or(warp::any().map(Auth::default))
-        .unify()
-        .and(warp::any().map(move || DbImpl(db_clone.0.clone())))
-        .and(warp::header::optional(ACCEPT_LANGUAGE.as_str()))
-        .and_then(move |auth, db, accept_lang| {
-            extract_i18n_from_auth(auth, ctx.clone(), db, accept_lang)
+    with_request_info()
+        .and_then(move |request: RequestInfo| {
+            let (db, ctx) = (DbImpl(db.primary.clone()), ctx.clone());
+            async move {
+                let (auth, i18n_info) = request.resolve_any(db.clone(), ctx).await;
+                Ok::<_, Rejection>((auth, i18n_info, db))
+            }
+        })
+        .untuple_one()
+}
+
+/// Like [`with_any_auth`], but the database handed on reads from the replica if one is configured.
+/// The user themselves is still looked up on the primary, since they may have only just signed in.
+pub fn with_any_auth_read_only(
+    db: DbBase,
+    ctx: Arc<SiteContext>,
+) -> impl Filter<Extract = (Auth, I18nInfo, impl PublicAccessDb), Error = Rejection> + Clone {
+    with_request_info()
+        .and_then(move |request: RequestInfo| {
+            let (db, read_db, ctx) = (
+                DbImpl(db.primary.clone()),
+                ReadOnlyDbImpl(db.read_pool().clone()),
+                ctx.clone(),
+            );
+            async move {
+                let (auth, i18n_info) = request.resolve_any(db, ctx).await;
+                Ok::<_, Rejection>((auth, i18n_info, read_db))
+            }
         })
         .untuple_one()
 }
@@ -969,23 +2051,14 @@ fn with_permissioned_auth(
     ctx: Arc<SiteContext>,
     permissions: Permissions,
 ) -> impl Filter<Extract = (FullUser, I18nInfo, DbImpl), Error = Rejection> + Clone {
-    let db_clone = db.clone();
-    warp::path::full()
-        .map(|path: FullPath| path.as_str().to_owned())
-        .and(warp::cookie::optional(STAY_LOGGED_IN_COOKIE))
-        .and(warp::any().map(move || db.clone()))
-        .and_then(move |redirect: String, token, db: DbBase| async move {
-            match extract_user(DbImpl(db.0.clone()), redirect.clone(), token).await {
-                Ok(user) if user.permissions.contains(permissions) => Ok(user),
-                Ok(_unauthorized) => Err(reject::custom(Unauthorized {
-                    reason: UnauthorizedReason::NoPermissions,
-                    redirect,
-                })),
-                Err(e) => Err(e),
+    with_request_info()
+        .and_then(move |request: RequestInfo| {
+            let (db, ctx) = (DbImpl(db.primary.clone()), ctx.clone());
+            async move {
+                let (user, i18n_info) = request.resolve_user(db.clone(), ctx, permissions).await?;
+                Ok::<_, Rejection>((user, i18n_info, db))
             }
         })
-        .and(warp::any().map(move || DbImpl(db_clone.0.clone())))
-        .and_then(move |user, db| extract_i18n_from_user(user, ctx.clone(), db))
         .untuple_one()
 }
 
@@ -1011,3 +2084,39 @@ pub fn with_administrator_auth(
 {
     with_permissioned_auth(db, ctx, Permissions::Administrator)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestSite;
+    use warp::http::header::{COOKIE, LOCATION};
+
+    #[test]
+    fn refreshing_returns_to_the_same_page() {
+        assert_eq!(refreshed_location("/word/1", ""), "/word/1");
+        assert_eq!(
+            refreshed_location("/search", "query=hamba"),
+            "/search?query=hamba"
+        );
+        assert_eq!(refreshed_location("/\\evil.example/", ""), "/evil.example/");
+    }
+
+    #[tokio::test]
+    async fn refreshing_does_not_redirect_to_other_sites() {
+        let site = TestSite::start().await;
+
+        for (path, expected) in [
+            ("//evil.example/", "/evil.example/"),
+            ("///evil.example/word/1?a=b", "/evil.example/word/1?a=b"),
+        ] {
+            let response = warp::test::request()
+                .method("GET")
+                .path(path)
+                .header(COOKIE, format!("{REMEMBER_COOKIE}=invalid"))
+                .reply(&refresh_session(site.db.clone()))
+                .await;
+
+            assert_eq!(response.headers()[LOCATION], expected);
+        }
+    }
+}
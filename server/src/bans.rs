@@ -0,0 +1,176 @@
+//! Bans stop users, or anyone from a range of IP addresses, from submitting anything to the site.
+//! They are enforced by [`with_unbanned_user_auth`], which is used in place of
+//! [`with_user_auth`] on every route which writes. It also enforces maintenance mode and that the
+//! current terms have been accepted.
+
+use crate::auth::{with_user_auth, FullUser};
+use crate::database::bans::Ban;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::listener::PeerAddr;
+use crate::maintenance::check_not_under_maintenance;
+use crate::spawn_blocking_child;
+use crate::terms::with_terms_accepted_auth;
+use isixhosa_common::auth::Permissions;
+use isixhosa_common::database::{DbBase, UserAccessDb};
+use std::convert::Infallible;
+use std::fmt::{self, Display, Formatter};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+use warp::{Filter, Rejection};
+
+/// An IP address, or a range of them in CIDR notation
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+#[derive(Debug)]
+pub struct InvalidIpRange;
+
+impl IpRange {
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        fn masked(bits: u128, width: u8, prefix_len: u8) -> u128 {
+            bits.checked_shr((width - prefix_len) as u32).unwrap_or(0)
+        }
+
+        match (self.network, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                masked(u32::from(network) as u128, 32, self.prefix_len)
+                    == masked(u32::from(addr) as u128, 32, self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                masked(u128::from(network), 128, self.prefix_len)
+                    == masked(u128::from(addr), 128, self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V4(addr)) => {
+                // IPv4 clients may show up as IPv4-mapped IPv6 addresses, and vice versa
+                network.to_ipv4_mapped().is_some_and(|network| {
+                    IpRange {
+                        network: IpAddr::V4(network),
+                        prefix_len: self.prefix_len.saturating_sub(96),
+                    }
+                    .contains(IpAddr::V4(addr))
+                })
+            }
+            (IpAddr::V4(_), IpAddr::V6(addr)) => addr
+                .to_ipv4_mapped()
+                .is_some_and(|addr| self.contains(IpAddr::V4(addr))),
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = InvalidIpRange;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (network, prefix_len) = match s.trim().split_once('/') {
+            Some((network, prefix_len)) => (network, Some(prefix_len)),
+            None => (s.trim(), None),
+        };
+
+        let network: IpAddr = network.parse().map_err(|_| InvalidIpRange)?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+
+        let prefix_len = match prefix_len {
+            Some(len) => len.parse().map_err(|_| InvalidIpRange)?,
+            None => max_len,
+        };
+
+        if prefix_len > max_len {
+            return Err(InvalidIpRange);
+        }
+
+        Ok(IpRange {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl Display for IpRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let max_len = if self.network.is_ipv4() { 32 } else { 128 };
+
+        if self.prefix_len == max_len {
+            write!(f, "{}", self.network)
+        } else {
+            write!(f, "{}/{}", self.network, self.prefix_len)
+        }
+    }
+}
+
+/// The client is banned from submitting
+#[derive(Debug)]
+pub struct Banned(pub Ban);
+
+impl warp::reject::Reject for Banned {}
+
+/// The IP address of the client. When the connection comes from the same machine (or over a Unix
+/// socket, which has no address), the site is behind a reverse proxy, so the address which the
+/// proxy forwarded is used instead.
+///
+/// The proxy appends the address of whoever connected to it to `X-Forwarded-For`, so only the
+/// last entry can be trusted. Anything before it was sent by the client, who could have made it up
+/// to get around an IP ban.
+fn client_ip() -> impl Filter<Extract = (Option<IpAddr>,), Error = Infallible> + Clone {
+    warp::ext::optional::<PeerAddr>()
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .map(|peer: Option<PeerAddr>, forwarded: Option<String>| {
+            let remote = peer.map(|PeerAddr(addr)| addr.ip());
+            let forwarded = forwarded
+                .as_deref()
+                .and_then(|f| f.rsplit(',').next())
+                .and_then(|f| f.trim().parse().ok());
+
+            match remote {
+                Some(remote) if remote.is_loopback() => forwarded.or(Some(remote)),
+                None => forwarded,
+                remote => remote,
+            }
+        })
+}
+
+/// Like [`with_user_auth`], but rejects with [`Banned`] if the user or their IP address is banned.
+/// Moderators are never banned. Also rejects with
+/// [`UnderMaintenance`](crate::maintenance::UnderMaintenance) while the site is in maintenance mode,
+/// and with [`TermsNotAccepted`](crate::terms::TermsNotAccepted) if the user hasn't accepted the
+/// current terms.
+pub fn with_unbanned_user_auth(
+    db: DbBase,
+    ctx: Arc<SiteContext>,
+) -> impl Filter<Extract = (FullUser, I18nInfo, impl UserAccessDb), Error = Rejection> + Clone {
+    with_terms_accepted_auth(db, ctx)
+        .and(client_ip())
+        .and_then(check_not_banned)
+        .untuple_one()
+        .and_then(check_not_under_maintenance)
+        .untuple_one()
+}
+
+#[instrument(name = "Check whether user is banned", fields(user_id = %user.id), skip_all)]
+async fn check_not_banned<D: UserAccessDb>(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: D,
+    ip: Option<IpAddr>,
+) -> Result<(FullUser, I18nInfo, D), Rejection> {
+    if user.permissions.contains(Permissions::Moderator) {
+        return Ok((user, i18n_info, db));
+    }
+
+    let (db_clone, user_id) = (db.clone(), user.id.get());
+    let ban = spawn_blocking_child(move || Ban::fetch_active_for(&db_clone, user_id, ip))
+        .await
+        .unwrap();
+
+    match ban {
+        Some(ban) => {
+            debug!(ban_id = ban.ban_id, "User is banned");
+            Err(warp::reject::custom(Banned(ban)))
+        }
+        None => Ok((user, i18n_info, db)),
+    }
+}
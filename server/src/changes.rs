@@ -0,0 +1,84 @@
+use crate::auth::with_any_auth;
+use crate::database::revisions::{fetch_revisions, Revision, RevisionFilter};
+use crate::error::ServerError;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::serialization::query_form;
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, PublicAccessDb};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::instrument;
+use warp::{Filter, Rejection, Reply};
+
+const PAGE_SIZE: u64 = 50;
+
+#[derive(Deserialize, Debug)]
+struct ChangesQuery {
+    #[serde(default, rename = "type")]
+    filter: RevisionFilter,
+    #[serde(default)]
+    page: u64,
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "changes.askama.html")]
+struct ChangesTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    revisions: Vec<Revision>,
+    filter: RevisionFilter,
+    page: u64,
+    has_next: bool,
+}
+
+impl ChangesTemplate {
+    fn link(&self, filter: RevisionFilter, page: u64) -> String {
+        match (filter, page) {
+            (RevisionFilter::All, 0) => "/changes".to_owned(),
+            (RevisionFilter::All, page) => format!("/changes?page={page}"),
+            (filter, 0) => format!("/changes?type={}", filter.name()),
+            (filter, page) => format!("/changes?type={}&page={page}", filter.name()),
+        }
+    }
+}
+
+pub fn changes(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    warp::path!["changes"]
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(query_form())
+        .and(with_any_auth(db, site_ctx))
+        .and_then(changes_page)
+        .debug_boxed()
+}
+
+#[instrument(name = "Show changelog", skip(auth, i18n_info, db))]
+async fn changes_page(
+    query: ChangesQuery,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let ChangesQuery { filter, page } = query;
+    let mut revisions = spawn_blocking_child(move || fetch_revisions(&db, filter, page, PAGE_SIZE))
+        .await
+        .map_err(ServerError::from)?;
+
+    let has_next = revisions.len() as u64 > PAGE_SIZE;
+    revisions.truncate(PAGE_SIZE as usize);
+
+    Ok(ChangesTemplate {
+        auth,
+        i18n_info,
+        revisions,
+        filter,
+        page,
+        has_next,
+    })
+}
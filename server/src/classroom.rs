@@ -0,0 +1,435 @@
+//! Live classroom sessions, a teaching mode where a teacher starts a session and students join it
+//! with a short code. Whenever the teacher opens a word's page, everyone following the session is
+//! taken to it too over a WebSocket, so the class sees the same word page as the teacher. Sessions
+//! are only kept in memory, so they end if the server restarts.
+
+use crate::auth::{with_any_auth, with_user_auth, FullUser};
+use crate::config::ClassroomConfig;
+use crate::csrf::csrf_protected;
+use crate::flash;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::DebugBoxedExt;
+use askama::Template;
+use cookie::{Cookie, SameSite};
+use dashmap::DashMap;
+use futures::{SinkExt, StreamExt};
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, PublicAccessDb, UserAccessDb};
+use isixhosa_common::flash::FlashMessage;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{debug, instrument};
+use warp::http::header::SET_COOKIE;
+use warp::http::{HeaderValue, Uri};
+use warp::reply::Response;
+use warp::ws::{self, WebSocket};
+use warp::{Filter, Rejection, Reply};
+
+/// The code of the session which the browser is following
+const CLASSROOM_COOKIE: &str = "isixhosa_click_classroom";
+/// Letters and digits which are hard to mix up when read off a projector
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LEN: usize = 6;
+
+pub struct Classrooms {
+    sessions: DashMap<String, Arc<Classroom>>,
+    cfg: ClassroomConfig,
+}
+
+pub struct Classroom {
+    pub code: String,
+    teacher: NonZeroU64,
+    started: Instant,
+    /// The path of the word page which the teacher is on, once they've opened one
+    current: watch::Sender<Option<String>>,
+}
+
+impl Classroom {
+    /// The number of students with the session open
+    pub fn participants(&self) -> usize {
+        self.current.receiver_count()
+    }
+}
+
+impl Classrooms {
+    pub fn new(cfg: ClassroomConfig) -> Arc<Classrooms> {
+        Arc::new(Classrooms {
+            sessions: DashMap::new(),
+            cfg,
+        })
+    }
+
+    fn has_expired(&self, classroom: &Classroom) -> bool {
+        classroom.started.elapsed() > Duration::from_secs(self.cfg.session_hours * 60 * 60)
+    }
+
+    /// Start a session for the teacher, ending any session they were already teaching. Returns
+    /// `None` if there are too many sessions running already.
+    fn start(&self, teacher: NonZeroU64) -> Option<Arc<Classroom>> {
+        self.sessions
+            .retain(|_, classroom| classroom.teacher != teacher && !self.has_expired(classroom));
+
+        if self.sessions.len() >= self.cfg.max_sessions {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+        let code = loop {
+            let code: String = (0..CODE_LEN)
+                .map(|_| char::from(CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())]))
+                .collect();
+
+            if !self.sessions.contains_key(&code) {
+                break code;
+            }
+        };
+
+        let classroom = Arc::new(Classroom {
+            code: code.clone(),
+            teacher,
+            started: Instant::now(),
+            current: watch::channel(None).0,
+        });
+        self.sessions.insert(code, classroom.clone());
+        Some(classroom)
+    }
+
+    /// End the session, if it is the teacher's. Students following it are told that it ended once
+    /// the last reference to it is dropped.
+    fn end(&self, code: &str, teacher: NonZeroU64) -> bool {
+        self.sessions
+            .remove_if(code, |_, classroom| classroom.teacher == teacher)
+            .is_some()
+    }
+
+    pub fn find(&self, code: &str) -> Option<Arc<Classroom>> {
+        let classroom = self.sessions.get(code)?.clone();
+        (!self.has_expired(&classroom)).then_some(classroom)
+    }
+
+    fn teaching(&self, teacher: NonZeroU64) -> Option<Arc<Classroom>> {
+        self.sessions
+            .iter()
+            .find(|classroom| classroom.teacher == teacher && !self.has_expired(classroom))
+            .map(|classroom| classroom.clone())
+    }
+}
+
+/// How the viewer of a word page takes part in a classroom session, if at all
+#[derive(Clone, Default)]
+pub struct ClassroomViewer {
+    classrooms: Option<Arc<Classrooms>>,
+    /// The code of the session which the viewer joined, which may have ended since
+    following: Option<String>,
+}
+
+impl ClassroomViewer {
+    /// Take the class along to the word page if the viewer is teaching a session. Otherwise,
+    /// returns the code of the session they're following, if it's still running.
+    pub fn viewed(&self, user: Option<NonZeroU64>, path: String) -> Option<String> {
+        let classrooms = self.classrooms.as_ref()?;
+
+        if let Some(classroom) = user.and_then(|user| classrooms.teaching(user)) {
+            classroom.current.send_replace(Some(path));
+            return None;
+        }
+
+        let classroom = classrooms.find(self.following.as_deref()?)?;
+        Some(classroom.code.clone())
+    }
+}
+
+pub fn with_classroom_viewer(
+    classrooms: Option<Arc<Classrooms>>,
+) -> impl Filter<Extract = (ClassroomViewer,), Error = Infallible> + Clone {
+    warp::cookie::optional::<String>(CLASSROOM_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify()
+        .map(move |following: Option<String>| ClassroomViewer {
+            classrooms: classrooms.clone(),
+            following,
+        })
+}
+
+/// Where teachers start sessions and students join them
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "classroom.askama.html")]
+struct ClassroomTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+}
+
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "classroom_session.askama.html")]
+struct ClassroomSessionTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    code: String,
+    is_teacher: bool,
+    participants: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct JoinQuery {
+    code: String,
+}
+
+/// Sent to students over the WebSocket
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClassroomMessage<'a> {
+    /// Go to the word page which the teacher just opened
+    Show {
+        path: &'a str,
+    },
+    Ended,
+}
+
+pub fn classroom(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    classrooms: Option<Arc<Classrooms>>,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    // Every route is missing unless sessions are enabled
+    let with_classrooms = warp::any().and_then(move || {
+        let classrooms = classrooms.clone();
+        async move { classrooms.ok_or_else(warp::reject::not_found) }
+    });
+
+    let index = warp::get()
+        .and(warp::path::end())
+        .and(with_classrooms.clone())
+        .and(with_any_auth(db.clone(), site_ctx.clone()))
+        .map(|_, auth, i18n_info, _db| ClassroomTemplate { auth, i18n_info });
+
+    let start = warp::post()
+        .and(warp::path::end())
+        .and(csrf_protected())
+        .and(with_classrooms.clone())
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .map(start_classroom);
+
+    let join = warp::get()
+        .and(warp::path!("join"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(with_classrooms.clone())
+        .map(join_classroom);
+
+    let leave = warp::post()
+        .and(warp::path!("leave"))
+        .and(warp::path::end())
+        .and(with_classrooms.clone())
+        .map(|_| leave_classroom());
+
+    let session = warp::get()
+        .and(warp::path![String])
+        .and(warp::path::end())
+        .and(with_classrooms.clone())
+        .and(with_any_auth(db.clone(), site_ctx.clone()))
+        .and_then(classroom_session_page);
+
+    let end = warp::post()
+        .and(warp::path![String / "end"])
+        .and(warp::path::end())
+        .and(csrf_protected())
+        .and(with_classrooms.clone())
+        .and(with_user_auth(db, site_ctx))
+        .map(end_classroom);
+
+    let follow = warp::path![String / "ws"]
+        .and(warp::path::end())
+        .and(warp::ws())
+        .and(with_classrooms)
+        .and_then(follow_classroom);
+
+    warp::path("classroom")
+        .and(
+            index
+                .or(start)
+                .or(join)
+                .or(leave)
+                .or(follow)
+                .or(end)
+                .or(session),
+        )
+        .debug_boxed()
+}
+
+fn classroom_cookie(code: &str, clear: bool) -> HeaderValue {
+    let mut cookie = Cookie::build((CLASSROOM_COOKIE, code))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .build();
+
+    if clear {
+        cookie.set_max_age(cookie::time::Duration::ZERO);
+    }
+
+    HeaderValue::from_str(&cookie.to_string()).unwrap()
+}
+
+fn redirect(to: &str) -> Response {
+    warp::redirect::see_other(to.parse::<Uri>().unwrap()).into_response()
+}
+
+#[instrument(name = "Start classroom session", skip_all)]
+fn start_classroom(
+    classrooms: Arc<Classrooms>,
+    user: FullUser,
+    _i18n_info: I18nInfo,
+    _db: impl UserAccessDb,
+) -> Response {
+    match classrooms.start(user.id) {
+        Some(classroom) => redirect(&format!("/classroom/{}", classroom.code)),
+        None => flash::redirect(
+            "/classroom",
+            vec![FlashMessage::error("classroom.too-many")],
+        ),
+    }
+}
+
+#[instrument(name = "Join classroom session", skip(classrooms))]
+fn join_classroom(query: JoinQuery, classrooms: Arc<Classrooms>) -> Response {
+    let code = query.code.trim().to_uppercase();
+
+    match classrooms.find(&code) {
+        Some(classroom) => redirect(&format!("/classroom/{}", classroom.code)),
+        None => flash::redirect(
+            "/classroom",
+            vec![FlashMessage::error("classroom.not-found").with_arg("code", code)],
+        ),
+    }
+}
+
+fn leave_classroom() -> Response {
+    let mut response = flash::redirect("/classroom", vec![FlashMessage::info("classroom.left")]);
+    response
+        .headers_mut()
+        .append(SET_COOKIE, classroom_cookie("", true));
+    response
+}
+
+/// The teacher's view of the session, or the page students wait on until the teacher opens a word.
+/// Students start following the session from here.
+#[instrument(
+    name = "Display classroom session page",
+    skip(classrooms, auth, i18n_info, _db)
+)]
+async fn classroom_session_page(
+    code: String,
+    classrooms: Arc<Classrooms>,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    _db: impl PublicAccessDb,
+) -> Result<Response, Rejection> {
+    let classroom = classrooms.find(&code).ok_or_else(warp::reject::not_found)?;
+    let is_teacher = auth.user_id() == Some(classroom.teacher);
+
+    let template = ClassroomSessionTemplate {
+        auth,
+        i18n_info,
+        code: classroom.code.clone(),
+        is_teacher,
+        participants: classroom.participants(),
+    };
+
+    let mut response = template.into_response();
+    if !is_teacher {
+        response
+            .headers_mut()
+            .append(SET_COOKIE, classroom_cookie(&classroom.code, false));
+    }
+
+    Ok(response)
+}
+
+#[instrument(
+    name = "End classroom session",
+    skip(classrooms, user, _i18n_info, _db)
+)]
+fn end_classroom(
+    code: String,
+    classrooms: Arc<Classrooms>,
+    user: FullUser,
+    _i18n_info: I18nInfo,
+    _db: impl UserAccessDb,
+) -> Response {
+    let message = if classrooms.end(&code, user.id) {
+        FlashMessage::success("classroom.ended")
+    } else {
+        FlashMessage::error("classroom.not-found").with_arg("code", code)
+    };
+
+    flash::redirect("/classroom", vec![message])
+}
+
+#[instrument(name = "Begin classroom websocket connection", skip(ws, classrooms))]
+async fn follow_classroom(
+    code: String,
+    ws: warp::ws::Ws,
+    classrooms: Arc<Classrooms>,
+) -> Result<impl Reply, Rejection> {
+    let receiver = classrooms
+        .find(&code)
+        .ok_or_else(warp::reject::not_found)?
+        .current
+        .subscribe();
+
+    Ok(ws.on_upgrade(move |websocket| send_pages(websocket, receiver)))
+}
+
+/// Send the teacher's current word page to a student as it changes, until either of them leaves
+async fn send_pages(websocket: WebSocket, mut current: watch::Receiver<Option<String>>) {
+    let (mut sender, mut stream) = websocket.split();
+
+    let message = |path: Option<&str>| {
+        let message = match path {
+            Some(path) => ClassroomMessage::Show { path },
+            None => ClassroomMessage::Ended,
+        };
+        ws::Message::text(serde_json::to_string(&message).unwrap())
+    };
+
+    // Students who join partway through are taken to the page the teacher is on straight away
+    let path = current.borrow_and_update().clone();
+    if let Some(path) = path {
+        if sender.send(message(Some(&path))).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            changed = current.changed() => {
+                if changed.is_err() {
+                    let _ = sender.send(message(None)).await;
+                    break;
+                }
+
+                let path = current.borrow_and_update().clone();
+                if let Some(path) = path {
+                    if sender.send(message(Some(&path))).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            received = stream.next() => {
+                if !matches!(received, Some(Ok(_))) {
+                    break;
+                }
+            }
+        }
+    }
+
+    debug!("Classroom websocket connection closed");
+    let _ = sender.close().await;
+}
@@ -0,0 +1,81 @@
+//! Points contributors who want to help, but don't know where to start, to the entries which are
+//! missing the most
+
+use crate::auth::with_any_auth;
+use crate::database::completeness::{fetch_incomplete, IncompleteWord};
+use crate::error::ServerError;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::serialization::query_form;
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, PublicAccessDb};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::instrument;
+use warp::{Filter, Rejection, Reply};
+
+const PAGE_SIZE: u64 = 50;
+
+#[derive(Deserialize, Debug)]
+struct ImproveQuery {
+    #[serde(default)]
+    page: u64,
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "needs_improvement.askama.html")]
+struct NeedsImprovementTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    words: Vec<IncompleteWord>,
+    page: u64,
+    has_next: bool,
+}
+
+impl NeedsImprovementTemplate {
+    fn link(&self, page: u64) -> String {
+        match page {
+            0 => "/needs-improvement".to_owned(),
+            page => format!("/needs-improvement?page={page}"),
+        }
+    }
+}
+
+pub fn needs_improvement(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    warp::path!["needs-improvement"]
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(query_form())
+        .and(with_any_auth(db, site_ctx))
+        .and_then(needs_improvement_page)
+        .debug_boxed()
+}
+
+#[instrument(name = "Show words needing improvement", skip(auth, i18n_info, db))]
+async fn needs_improvement_page(
+    query: ImproveQuery,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let page = query.page;
+    let mut words = spawn_blocking_child(move || fetch_incomplete(&db, page, PAGE_SIZE))
+        .await
+        .map_err(ServerError::from)?;
+
+    let has_next = words.len() as u64 > PAGE_SIZE;
+    words.truncate(PAGE_SIZE as usize);
+
+    Ok(NeedsImprovementTemplate {
+        auth,
+        i18n_info,
+        words,
+        page,
+        has_next,
+    })
+}
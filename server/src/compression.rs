@@ -0,0 +1,139 @@
+//! Response compression negotiated from the request's `Accept-Encoding` header. Bodies are
+//! compressed as they are streamed out, so large responses (such as the exported dictionary files)
+//! never need to be buffered in memory.
+
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder};
+use async_compression::Level;
+use futures::TryStreamExt;
+use std::convert::Infallible;
+use std::io;
+use tokio_util::io::{ReaderStream, StreamReader};
+use warp::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, VARY};
+use warp::http::{HeaderValue, StatusCode};
+use warp::hyper::Body;
+use warp::reply::Response;
+use warp::{Filter, Reply};
+
+/// Brotli's default quality (11) is far too slow to compress on the fly
+const BROTLI_QUALITY: i32 = 4;
+
+/// Content types which are already compressed, so there's no point in compressing them again
+const INCOMPRESSIBLE: [&str; 8] = [
+    "image/png",
+    "image/jpeg",
+    "image/webp",
+    "font/woff2",
+    "application/zip",
+    "application/gzip",
+    "application/wasm",
+    "application/octet-stream",
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+    Identity,
+}
+
+impl Encoding {
+    fn from_accept_encoding(header: &str) -> Encoding {
+        let accepts = |encoding: &str| {
+            header.split(',').any(|accepted| {
+                let mut params = accepted.split(';');
+                let name = params.next().unwrap_or_default().trim();
+                let refused = params.any(|param| {
+                    let param = param.trim();
+                    param.starts_with("q=") && param[2..].parse::<f32>() == Ok(0.0)
+                });
+
+                name.eq_ignore_ascii_case(encoding) && !refused
+            })
+        };
+
+        if accepts("br") {
+            Encoding::Brotli
+        } else if accepts("gzip") {
+            Encoding::Gzip
+        } else {
+            Encoding::Identity
+        }
+    }
+
+    fn header_value(&self) -> Option<HeaderValue> {
+        match self {
+            Encoding::Brotli => Some(HeaderValue::from_static("br")),
+            Encoding::Gzip => Some(HeaderValue::from_static("gzip")),
+            Encoding::Identity => None,
+        }
+    }
+}
+
+/// Extracts the best encoding supported by the client
+pub fn accepted_encoding() -> impl Filter<Extract = (Encoding,), Error = Infallible> + Clone {
+    warp::header::optional::<String>(ACCEPT_ENCODING.as_str())
+        .map(|accepted: Option<String>| {
+            accepted
+                .as_deref()
+                .map(Encoding::from_accept_encoding)
+                .unwrap_or(Encoding::Identity)
+        })
+        .or(warp::any().map(|| Encoding::Identity))
+        .unify()
+}
+
+fn is_compressible(response: &Response) -> bool {
+    let no_body = matches!(
+        response.status(),
+        StatusCode::SWITCHING_PROTOCOLS | StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+    );
+
+    let mime = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|mime| mime.to_str().ok());
+    let incompressible =
+        mime.is_some_and(|mime| INCOMPRESSIBLE.iter().any(|pat| mime.starts_with(pat)));
+
+    // The encoders buffer their output, which would hold back server-sent events
+    let event_stream = mime.is_some_and(|mime| mime.starts_with("text/event-stream"));
+
+    !no_body
+        && !incompressible
+        && !event_stream
+        && !response.headers().contains_key(CONTENT_ENCODING)
+}
+
+pub fn compress<R: Reply>(encoding: Encoding, reply: R) -> Response {
+    let mut response = reply.into_response();
+
+    if !is_compressible(&response) {
+        return response;
+    }
+
+    response
+        .headers_mut()
+        .append(VARY, HeaderValue::from_static("accept-encoding"));
+
+    let content_encoding = match encoding.header_value() {
+        Some(value) => value,
+        None => return response,
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let reader = StreamReader::new(body.map_err(|err| io::Error::new(io::ErrorKind::Other, err)));
+
+    let body = match encoding {
+        Encoding::Brotli => Body::wrap_stream(ReaderStream::new(BrotliEncoder::with_quality(
+            reader,
+            Level::Precise(BROTLI_QUALITY),
+        ))),
+        Encoding::Gzip => Body::wrap_stream(ReaderStream::new(GzipEncoder::new(reader))),
+        Encoding::Identity => unreachable!(),
+    };
+
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(CONTENT_ENCODING, content_encoding);
+
+    Response::from_parts(parts, body)
+}
@@ -1,4 +1,6 @@
+use isixhosa_common::language::LanguageProfile;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
 use warp::host::Authority;
@@ -7,21 +9,577 @@ use warp::http::{uri, Uri};
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub database_path: PathBuf,
+    /// The maximum number of connections held open in the database connection pool
+    #[serde(default = "default_database_pool_size")]
+    pub database_pool_size: u32,
+    /// How long a connection waits for a lock on the database before failing with `SQLITE_BUSY`
+    #[serde(default = "default_database_busy_timeout_ms")]
+    pub database_busy_timeout_ms: u64,
+    /// The number of prepared statements each database connection keeps around for reuse
+    #[serde(default = "default_database_statement_cache_capacity")]
+    pub database_statement_cache_capacity: usize,
+    /// A read-only copy of the database (e.g a Litestream replica) which word pages and search
+    /// results are read from. Everything else, including all writes, uses `database_path`.
+    #[serde(default)]
+    pub database_replica_path: Option<PathBuf>,
     pub tantivy_path: PathBuf,
+    /// The memory budget of the search index writer, in bytes
+    #[serde(default = "default_tantivy_writer_heap_bytes")]
+    pub tantivy_writer_heap_bytes: usize,
+    /// The number of search actors, each of which serves one search at a time. Defaults to the
+    /// number of CPUs.
+    #[serde(default)]
+    pub tantivy_searchers: Option<usize>,
+    /// When searches start seeing words which were just written to the search index
+    #[serde(default)]
+    pub tantivy_reload_policy: SearchReloadPolicy,
+    /// How long cached search results are kept for, at most
+    #[serde(default = "default_search_cache_ttl_secs")]
+    pub search_cache_ttl_secs: u64,
+    /// The maximum number of queries with cached search results. Set to zero to disable caching.
+    #[serde(default = "default_search_cache_size")]
+    pub search_cache_size: usize,
     pub cert_path: Option<PathBuf>,
     pub key_path: Option<PathBuf>,
+    /// How often the certificate and key files are checked for changes, so that a renewed
+    /// certificate is used without restarting the server
+    #[serde(default = "default_tls_reload_interval_mins")]
+    pub tls_reload_interval_mins: u64,
     pub server_source_path: PathBuf,
     pub other_static_files: PathBuf,
     pub log_path: PathBuf,
     pub http_port: u16,
     pub https_port: u16,
+    /// A Unix domain socket to serve plain HTTP on instead of `http_port`. Only used when no
+    /// certificate is configured, i.e when the server is behind a reverse proxy.
+    #[serde(default)]
+    pub unix_socket: Option<UnixSocketConfig>,
     pub host: String,
     pub oidc_client: String,
     pub oidc_secret: String,
+    /// A second OpenID Connect provider which users can link to their account, so that they can
+    /// still sign in if they lose access to their Google account
+    #[serde(default)]
+    pub recovery_provider: Option<RecoveryProviderConfig>,
     pub plaintext_export_path: PathBuf,
+    /// Suggestions which have been pending for longer than this are highlighted on the moderation
+    /// page
+    #[serde(default = "default_moderation_overdue_days")]
+    pub moderation_overdue_days: u64,
+    /// The number of oldest pending suggestions listed in the daily summary sent to moderators
+    #[serde(default = "default_moderation_summary_size")]
+    pub moderation_summary_size: usize,
+    /// What trainee moderators must confirm that they have checked before accepting a suggested
+    /// word
+    #[serde(default = "default_trainee_checklist")]
+    pub trainee_checklist: Vec<ChecklistItem>,
+    /// The mail server which the daily summary is sent through. If unset, the summary is only
+    /// logged.
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+    /// An anti-abuse challenge which must be solved in the browser before a new word is submitted
+    #[serde(default)]
+    pub submission_challenge: SubmissionChallenge,
+    /// Coordination with external replication or backup tools such as Litestream
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+    /// The largest request bodies accepted by each kind of route
+    #[serde(default)]
+    pub body_limits: BodyLimits,
+    /// Settings for the connections which the site is served on
+    #[serde(default)]
+    pub connections: ConnectionTuning,
+    /// Keys which users issue themselves for the high-volume API endpoints
+    #[serde(default)]
+    pub api_keys: ApiKeyConfig,
+    /// A DICT protocol (RFC 2229) server so that terminal users can look words up with `dict`. If
+    /// unset, no DICT server is started.
+    #[serde(default)]
+    pub dict_server: Option<DictServerConfig>,
+    /// A gRPC lookup service for internal tools and the mobile app backend. If unset, no gRPC
+    /// server is started.
+    #[serde(default)]
+    pub grpc: Option<GrpcConfig>,
+    /// How many of the most viewed words are included in the offline bundle for the web app
+    #[serde(default = "default_pwa_bundle_words")]
+    pub pwa_bundle_words: usize,
+    /// A background job which suggests links between words whose English glosses overlap. If
+    /// unset, the job isn't run.
+    #[serde(default)]
+    pub link_suggestions: Option<LinkSuggestionConfig>,
+    /// A parallel corpus which moderators can pick example sentences from. If unset, no examples
+    /// are suggested.
+    #[serde(default)]
+    pub example_corpus: Option<ExampleCorpusConfig>,
+    /// Live classroom sessions, where students follow along with the word pages their teacher
+    /// opens. If unset, sessions can't be started.
+    #[serde(default)]
+    pub classrooms: Option<ClassroomConfig>,
+    /// Other dictionaries running this software, which are searched when a search finds nothing
+    /// here. If unset, only this dictionary is searched.
+    #[serde(default)]
+    pub federation: Option<FederationConfig>,
+    /// The language the dictionary is for, and what the site is called. Anything not set here is
+    /// taken from the site's translations.
+    #[serde(default)]
+    pub language: LanguageProfile,
+    /// Hide entries flagged as vulgar or offensive from search, browsing, and random words for
+    /// everyone, e.g for a deployment used in schools. Otherwise, each visitor can turn this on for
+    /// their own browser session.
+    #[serde(default)]
+    pub school_mode: bool,
+    /// Periodically check that the links given by references still work, and mark those which
+    /// don't. If unset, links aren't checked.
+    #[serde(default)]
+    pub link_checker: Option<LinkCheckerConfig>,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct BodyLimits {
+    /// Small forms such as moderation actions and sign up, in bytes
+    #[serde(default = "default_form_body_limit")]
+    pub form: u64,
+    /// Word submissions, edits and drafts, which can carry many examples and linked words, in
+    /// bytes
+    #[serde(default = "default_submission_body_limit")]
+    pub submission: u64,
+    /// Forms with file uploads such as dataset icons, in bytes
+    #[serde(default = "default_upload_body_limit")]
+    pub upload: u64,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        BodyLimits {
+            form: default_form_body_limit(),
+            submission: default_submission_body_limit(),
+            upload: default_upload_body_limit(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct ConnectionTuning {
+    /// Whether HTTP/1 connections are kept open for further requests
+    #[serde(default = "default_keep_alive")]
+    pub keep_alive: bool,
+    /// How long a client has to send a request's headers before the connection is closed, in
+    /// seconds
+    #[serde(default = "default_header_read_timeout_secs")]
+    pub header_read_timeout_secs: u64,
+    /// How often idle HTTP/2 connections are pinged, in seconds, so that they aren't dropped by
+    /// anything in between. If unset, connections aren't pinged.
+    #[serde(default = "default_http2_keep_alive_interval_secs")]
+    pub http2_keep_alive_interval_secs: Option<u64>,
+    /// How long to wait for the reply to a ping before closing the connection, in seconds
+    #[serde(default = "default_http2_keep_alive_timeout_secs")]
+    pub http2_keep_alive_timeout_secs: u64,
+    /// The most requests a client can make at once on one HTTP/2 connection
+    #[serde(default = "default_http2_max_concurrent_streams")]
+    pub http2_max_concurrent_streams: u32,
+    /// How long open connections are given to finish their requests when the server is stopped,
+    /// in seconds
+    #[serde(default = "default_shutdown_grace_period_secs")]
+    pub shutdown_grace_period_secs: u64,
+}
+
+impl Default for ConnectionTuning {
+    fn default() -> Self {
+        ConnectionTuning {
+            keep_alive: default_keep_alive(),
+            header_read_timeout_secs: default_header_read_timeout_secs(),
+            http2_keep_alive_interval_secs: default_http2_keep_alive_interval_secs(),
+            http2_keep_alive_timeout_secs: default_http2_keep_alive_timeout_secs(),
+            http2_max_concurrent_streams: default_http2_max_concurrent_streams(),
+            shutdown_grace_period_secs: default_shutdown_grace_period_secs(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct ApiKeyConfig {
+    /// The number of requests each new key can make per day (UTC). Administrators can change the
+    /// quota of individual keys afterwards.
+    #[serde(default = "default_api_key_daily_quota")]
+    pub daily_quota: u64,
+    /// The most keys one user can hold at once
+    #[serde(default = "default_api_keys_per_user")]
+    pub max_per_user: usize,
+}
+
+impl Default for ApiKeyConfig {
+    fn default() -> Self {
+        ApiKeyConfig {
+            daily_quota: default_api_key_daily_quota(),
+            max_per_user: default_api_keys_per_user(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ReplicationConfig {
+    /// How often the WAL is checkpointed and a snapshot taken. If unset, this is only done when
+    /// triggered from the site settings page.
+    #[serde(default)]
+    pub checkpoint_interval_mins: Option<u64>,
+    /// Where a consistent copy of the database is written after each checkpoint. If unset, only the
+    /// WAL is checkpointed.
+    #[serde(default)]
+    pub snapshot_path: Option<PathBuf>,
+    /// A program run once a snapshot has been written, with the snapshot's path as its argument
+    #[serde(default)]
+    pub snapshot_hook: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UnixSocketConfig {
+    pub path: PathBuf,
+    /// The permissions the socket file is given, e.g `0o660` so that only the server's user and
+    /// group (which the reverse proxy should be in) can connect
+    #[serde(default = "default_unix_socket_mode")]
+    pub mode: u32,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct DictServerConfig {
+    /// The port to listen on. 2628 is the port registered for DICT.
+    #[serde(default = "default_dict_port")]
+    pub port: u16,
+    /// The most clients connected at once. Clients over the limit are turned away.
+    #[serde(default = "default_dict_max_connections")]
+    pub max_connections: usize,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct GrpcConfig {
+    /// The address to listen on. The service has no authentication of its own, so this should
+    /// only be reachable by internal consumers.
+    #[serde(default = "default_grpc_address")]
+    pub address: SocketAddr,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct LinkSuggestionConfig {
+    /// The user which the suggested links are attributed to, e.g a bot account made for this
+    pub user_id: u64,
+    /// How often the words are analysed
+    #[serde(default = "default_link_suggestion_interval_hours")]
+    pub interval_hours: u64,
+    /// How much two glosses must overlap for the words to be linked, from 0 to 1. This is the
+    /// number of words shared by the glosses out of all of the words in either of them.
+    #[serde(default = "default_link_suggestion_min_overlap")]
+    pub min_overlap: f64,
+    /// The most links suggested in one run, so that the moderation queue isn't flooded
+    #[serde(default = "default_link_suggestions_per_run")]
+    pub max_per_run: usize,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct LinkCheckerConfig {
+    /// How often links are checked. Each link is checked at most once in this time.
+    #[serde(default = "default_link_checker_interval_hours")]
+    pub interval_hours: u64,
+    /// The most links checked in one run, so that a run doesn't take too long
+    #[serde(default = "default_links_per_run")]
+    pub max_per_run: usize,
+    /// How long to wait for a site to respond before counting the link as failed
+    #[serde(default = "default_link_checker_timeout_ms")]
+    pub timeout_ms: u64,
+    /// How many checks in a row a link must fail before it is marked as broken
+    #[serde(default = "default_link_failures_before_broken")]
+    pub failures_before_broken: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExampleCorpusConfig {
+    /// A file with one sentence pair per line, the English and then the isiXhosa separated by a tab.
+    /// A third column may give their word alignment as `english-xhosa` pairs of word indices, e.g
+    /// `0-1 2-0`, as output by aligners like fast_align.
+    pub path: PathBuf,
+    /// The name of the corpus, which examples picked from it are attributed to
+    pub name: String,
+    /// The most sentences suggested for one word
+    #[serde(default = "default_corpus_candidates")]
+    pub max_candidates: usize,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct ClassroomConfig {
+    /// The most sessions which can run at once, since they are kept in memory
+    #[serde(default = "default_max_classrooms")]
+    pub max_sessions: usize,
+    /// How long a session lasts before it ends by itself, in case the teacher doesn't end it
+    #[serde(default = "default_classroom_hours")]
+    pub session_hours: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FederationConfig {
+    pub peers: Vec<FederationPeer>,
+    /// How long to wait for a peer to answer before leaving its results out
+    #[serde(default = "default_federation_timeout_ms")]
+    pub timeout_ms: u64,
+    /// The most results shown from each peer
+    #[serde(default = "default_federation_max_results")]
+    pub max_results: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FederationPeer {
+    /// The peer's name as shown next to its results, e.g `IsiZulu.click`
+    pub name: String,
+    /// Where the peer is hosted, e.g `https://isizulu.click`
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecoveryProviderConfig {
+    /// The provider's name as shown to users, e.g `Microsoft`
+    pub name: String,
+    /// The issuer URL which the provider is discovered from
+    pub issuer: String,
+    pub client: String,
+    pub secret: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SmtpConfig {
+    pub server: String,
+    pub username: String,
+    pub password: String,
+    /// The address which emails are sent from, e.g `IsiXhosa.click <noreply@isixhosa.click>`
+    pub from: String,
+}
+
+fn default_database_pool_size() -> u32 {
+    16
+}
+
+fn default_database_busy_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_database_statement_cache_capacity() -> usize {
+    128
+}
+
+fn default_tantivy_writer_heap_bytes() -> usize {
+    128 * 1024 * 1024
+}
+
+fn default_search_cache_ttl_secs() -> u64 {
+    10 * 60
+}
+
+fn default_search_cache_size() -> usize {
+    1024
+}
+
+fn default_form_body_limit() -> u64 {
+    64 * 1024
+}
+
+fn default_submission_body_limit() -> u64 {
+    64 * 1024
+}
+
+fn default_upload_body_limit() -> u64 {
+    16 * 1024 * 1024
+}
+
+fn default_keep_alive() -> bool {
+    true
+}
+
+fn default_header_read_timeout_secs() -> u64 {
+    30
+}
+
+fn default_http2_keep_alive_interval_secs() -> Option<u64> {
+    Some(30)
+}
+
+fn default_http2_keep_alive_timeout_secs() -> u64 {
+    20
+}
+
+fn default_http2_max_concurrent_streams() -> u32 {
+    200
+}
+
+fn default_shutdown_grace_period_secs() -> u64 {
+    30
+}
+
+fn default_api_key_daily_quota() -> u64 {
+    1000
+}
+
+fn default_api_keys_per_user() -> usize {
+    5
+}
+
+fn default_unix_socket_mode() -> u32 {
+    0o660
+}
+
+fn default_dict_port() -> u16 {
+    2628
+}
+
+fn default_dict_max_connections() -> usize {
+    32
+}
+
+fn default_grpc_address() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 50051))
+}
+
+fn default_link_suggestion_interval_hours() -> u64 {
+    24
+}
+
+fn default_link_suggestion_min_overlap() -> f64 {
+    0.6
+}
+
+fn default_link_suggestions_per_run() -> usize {
+    20
+}
+
+fn default_link_checker_interval_hours() -> u64 {
+    24
+}
+
+fn default_links_per_run() -> usize {
+    200
+}
+
+fn default_link_checker_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_link_failures_before_broken() -> u64 {
+    2
+}
+
+fn default_tls_reload_interval_mins() -> u64 {
+    10
+}
+
+fn default_corpus_candidates() -> usize {
+    20
+}
+
+fn default_max_classrooms() -> usize {
+    100
+}
+
+fn default_classroom_hours() -> u64 {
+    4
+}
+
+fn default_federation_timeout_ms() -> u64 {
+    3_000
+}
+
+fn default_federation_max_results() -> usize {
+    5
+}
+
+fn default_moderation_overdue_days() -> u64 {
+    7
+}
+
+fn default_pwa_bundle_words() -> usize {
+    2000
+}
+
+fn default_moderation_summary_size() -> usize {
+    10
+}
+
+fn default_trainee_checklist() -> Vec<ChecklistItem> {
+    vec![
+        ChecklistItem::SpellingVerified,
+        ChecklistItem::ClassVerified,
+        ChecklistItem::NoDuplicate,
+    ]
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecklistItem {
+    /// The spelling of both the English and isiXhosa has been checked
+    SpellingVerified,
+    /// The part of speech and noun class are correct
+    ClassVerified,
+    /// The word is not already in the dictionary
+    NoDuplicate,
+}
+
+impl ChecklistItem {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChecklistItem::SpellingVerified => "spelling_verified",
+            ChecklistItem::ClassVerified => "class_verified",
+            ChecklistItem::NoDuplicate => "no_duplicate",
+        }
+    }
+
+    pub fn translation_key(&self) -> &'static str {
+        match self {
+            ChecklistItem::SpellingVerified => "moderation.checklist-spelling",
+            ChecklistItem::ClassVerified => "moderation.checklist-class",
+            ChecklistItem::NoDuplicate => "moderation.checklist-duplicate",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionChallenge {
+    #[default]
+    None,
+    /// Make the browser find a hash with this many leading zero bits. Each extra bit doubles the
+    /// expected work; 16 takes a few seconds on a phone.
+    ProofOfWork { difficulty_bits: u32 },
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchReloadPolicy {
+    /// Reload in the background shortly after a commit is noticed
+    #[default]
+    OnCommit,
+    /// Reload as soon as the writer commits, so that the next search sees the change
+    Immediate,
 }
 
 impl Config {
+    /// Each top-level setting and its value, with secrets hidden, for showing on the admin panel
+    pub fn redacted_values(&self) -> Vec<(String, String)> {
+        const REDACTED: &str = "(hidden)";
+
+        let mut value = serde_json::to_value(self).unwrap();
+        value["oidc_secret"] = REDACTED.into();
+
+        if let Some(smtp) = value["smtp"].as_object_mut() {
+            smtp.insert("password".to_owned(), REDACTED.into());
+        }
+
+        if let Some(provider) = value["recovery_provider"].as_object_mut() {
+            provider.insert("secret".to_owned(), REDACTED.into());
+        }
+
+        value
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.to_string()))
+            .collect()
+    }
+
     pub fn host_builder(host: &str, port: u16) -> uri::Builder {
         let authority = if port != 443 {
             format!("{}:{}", host, port)
@@ -38,18 +596,49 @@ impl Default for Config {
     fn default() -> Self {
         Config {
             database_path: PathBuf::from("isixhosa_click.db"),
+            database_pool_size: default_database_pool_size(),
+            database_busy_timeout_ms: default_database_busy_timeout_ms(),
+            database_statement_cache_capacity: default_database_statement_cache_capacity(),
+            database_replica_path: None,
             tantivy_path: PathBuf::from("tantivy_data/"),
+            tantivy_writer_heap_bytes: default_tantivy_writer_heap_bytes(),
+            tantivy_searchers: None,
+            tantivy_reload_policy: SearchReloadPolicy::default(),
+            search_cache_ttl_secs: default_search_cache_ttl_secs(),
+            search_cache_size: default_search_cache_size(),
             cert_path: Some(PathBuf::from("tls/cert.pem")),
             key_path: Some(PathBuf::from("tls/key.rsa")),
+            tls_reload_interval_mins: default_tls_reload_interval_mins(),
             server_source_path: PathBuf::from("./"),
             other_static_files: PathBuf::from("dummy_www/"),
             log_path: PathBuf::from("log/"),
             http_port: 8080,
             https_port: 8443,
+            unix_socket: None,
             host: "127.0.0.1".to_string(),
             oidc_client: "DUMMY_CLIENT".to_string(),
             oidc_secret: "DUMMY_SECRET".to_string(),
+            recovery_provider: None,
             plaintext_export_path: PathBuf::from("isixhosa_click_export/"),
+            moderation_overdue_days: default_moderation_overdue_days(),
+            moderation_summary_size: default_moderation_summary_size(),
+            trainee_checklist: default_trainee_checklist(),
+            smtp: None,
+            submission_challenge: SubmissionChallenge::default(),
+            replication: ReplicationConfig::default(),
+            body_limits: BodyLimits::default(),
+            connections: ConnectionTuning::default(),
+            api_keys: ApiKeyConfig::default(),
+            dict_server: None,
+            grpc: None,
+            pwa_bundle_words: default_pwa_bundle_words(),
+            link_suggestions: None,
+            example_corpus: None,
+            classrooms: None,
+            federation: None,
+            language: LanguageProfile::default(),
+            school_mode: false,
+            link_checker: None,
         }
     }
 }
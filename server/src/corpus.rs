@@ -0,0 +1,282 @@
+//! Example sentences for words, picked from a parallel corpus. Moderators are shown sentences from
+//! the corpus which use a word, and can attach one as an example in a click. The example is
+//! attributed to the corpus, and keeps the corpus's word alignment so that the word can be
+//! highlighted in both of its sentences.
+
+use crate::auth::{with_moderator_auth, FullUser};
+use crate::config::{BodyLimits, ExampleCorpusConfig};
+use crate::csrf::csrf_protected_form;
+use crate::database::example_sources::attach_example;
+use crate::error::{DbError, ServerError, WordNotFound};
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use anyhow::{Context, Result};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, ModeratorAccessDb};
+use isixhosa_common::format::Alignment;
+use isixhosa_common::language::HeadwordMatcher;
+use isixhosa_common::types::ExistingWord;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+use warp::http::Uri;
+use warp::{body, Filter, Rejection, Reply};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SentencePair {
+    pub english: String,
+    pub xhosa: String,
+    /// Which words of the two sentences are translations of each other, if the corpus says
+    #[serde(default)]
+    pub alignment: Option<String>,
+}
+
+pub struct Corpus {
+    name: String,
+    max_candidates: usize,
+    sentences: Vec<SentencePair>,
+}
+
+impl Corpus {
+    /// Read the whole corpus into memory. Each line is an English sentence, its isiXhosa
+    /// translation and optionally their word alignment, separated by tabs. Lines which aren't a
+    /// sentence pair are skipped, as are alignments which don't fit their sentences.
+    pub fn load(cfg: &ExampleCorpusConfig) -> Result<Corpus> {
+        let file = File::open(&cfg.path)
+            .with_context(|| format!("Couldn't open example corpus {:?}", cfg.path))?;
+
+        let mut sentences = Vec::new();
+        let mut skipped = 0;
+        let mut bad_alignments = 0;
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+
+            let mut columns = line.split('\t').map(str::trim);
+
+            match (columns.next(), columns.next(), columns.next()) {
+                (Some(english), Some(xhosa), alignment)
+                    if !english.is_empty() && !xhosa.is_empty() =>
+                {
+                    let alignment = alignment.filter(|a| !a.is_empty()).and_then(|a| {
+                        let valid = Alignment::parse(a, english, xhosa).is_some();
+                        if !valid {
+                            bad_alignments += 1;
+                        }
+                        valid.then(|| a.to_owned())
+                    });
+
+                    sentences.push(SentencePair {
+                        english: english.to_owned(),
+                        xhosa: xhosa.to_owned(),
+                        alignment,
+                    });
+                }
+                _ => skipped += 1,
+            }
+        }
+
+        if bad_alignments > 0 {
+            warn!(
+                "Ignored {bad_alignments} invalid alignments in example corpus {:?}",
+                cfg.path
+            );
+        }
+
+        if skipped > 0 {
+            warn!(
+                "Skipped {skipped} malformed lines in example corpus {:?}",
+                cfg.path
+            );
+        }
+
+        info!(
+            "Loaded {} sentence pairs from {}",
+            sentences.len(),
+            cfg.name
+        );
+
+        Ok(Corpus {
+            name: cfg.name.clone(),
+            max_candidates: cfg.max_candidates,
+            sentences,
+        })
+    }
+
+    /// Sentences which use the word and aren't already among its examples. Sentences whose English
+    /// also contains the word's translation are most likely to use it in the same sense, so they
+    /// come first, and shorter sentences make better examples than longer ones.
+    fn candidates(&self, word: &ExistingWord) -> Vec<SentencePair> {
+        let Some(matcher) = HeadwordMatcher::new(&word.xhosa, word.part_of_speech) else {
+            return Vec::new();
+        };
+
+        let english_words: Vec<String> = word
+            .english
+            .to_lowercase()
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|w| w.chars().count() > 2)
+            .map(str::to_owned)
+            .collect();
+        let existing: HashSet<String> = word
+            .examples
+            .iter()
+            .map(|ex| ex.xhosa.trim().to_lowercase())
+            .collect();
+
+        let mut candidates: Vec<(bool, &SentencePair)> = self
+            .sentences
+            .iter()
+            .filter(|pair| {
+                let lower = pair.xhosa.to_lowercase();
+                !existing.contains(&lower)
+                    && lower
+                        .split(|c: char| !c.is_alphabetic())
+                        .any(|w| matcher.matches(w))
+            })
+            .map(|pair| {
+                let english = pair.english.to_lowercase();
+                let translated = english_words.iter().any(|w| english.contains(w.as_str()));
+                (translated, pair)
+            })
+            .collect();
+
+        candidates.sort_by_key(|(translated, pair)| (!translated, pair.xhosa.len()));
+        candidates
+            .into_iter()
+            .take(self.max_candidates)
+            .map(|(_, pair)| pair.clone())
+            .collect()
+    }
+
+    fn contains(&self, pair: &SentencePair) -> bool {
+        self.sentences.iter().any(|p| {
+            p.english == pair.english && p.xhosa == pair.xhosa && p.alignment == pair.alignment
+        })
+    }
+}
+
+pub fn corpus(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    body_limits: BodyLimits,
+    corpus: Option<Arc<Corpus>>,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let with_corpus = warp::any().map(move || corpus.clone());
+
+    let show = warp::get()
+        .and(warp::path![u64 / "corpus_examples"])
+        .and(warp::path::end())
+        .and(with_corpus.clone())
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .and_then(corpus_examples_page);
+
+    let attach = warp::post()
+        .and(warp::path![u64 / "corpus_examples"])
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(csrf_protected_form())
+        .and(with_corpus)
+        .and(with_moderator_auth(db, site_ctx))
+        .and_then(attach_example_reply);
+
+    warp::path("word").and(show.or(attach)).debug_boxed()
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "corpus_examples.askama.html")]
+struct CorpusExamples {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    word: ExistingWord,
+    /// The corpus's name, or `None` if no corpus is configured
+    corpus_name: Option<String>,
+    candidates: Vec<SentencePair>,
+}
+
+#[instrument(name = "Show corpus examples", skip(corpus, user, i18n_info, db))]
+async fn corpus_examples_page(
+    word_id: u64,
+    corpus: Option<Arc<Corpus>>,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let corpus_name = corpus.as_ref().map(|corpus| corpus.name.clone());
+
+    let found = spawn_blocking_child(move || {
+        let word = ExistingWord::fetch_full(&db, word_id)?;
+        let candidates = corpus
+            .map(|corpus| corpus.candidates(&word))
+            .unwrap_or_default();
+        Some((word, candidates))
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    let Some((word, candidates)) = found else {
+        return Err(warp::reject::custom(WordNotFound(word_id)));
+    };
+
+    Ok(CorpusExamples {
+        auth: user.into(),
+        i18n_info,
+        word,
+        corpus_name,
+        candidates,
+    })
+}
+
+#[instrument(name = "Attach corpus example", skip(corpus, _user, _i18n_info, db))]
+async fn attach_example_reply(
+    word_id: u64,
+    pair: SentencePair,
+    corpus: Option<Arc<Corpus>>,
+    _user: FullUser,
+    _i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let Some(corpus) = corpus else {
+        return Err(warp::reject::not_found());
+    };
+
+    let attached = spawn_blocking_child(move || {
+        if ExistingWord::fetch_alone(&db, word_id).is_none() {
+            return Ok(None);
+        }
+
+        // Only sentences from the corpus can be attributed to it
+        if !corpus.contains(&pair) {
+            warn!("Attempted to attach an example which isn't in the corpus");
+            return Ok(Some(None));
+        }
+
+        let id = attach_example(
+            &db,
+            word_id,
+            &pair.english,
+            &pair.xhosa,
+            &corpus.name,
+            pair.alignment.as_deref(),
+        )?;
+        Ok::<_, DbError>(Some(Some(id)))
+    })
+    .await
+    .map_err(ServerError::from)?
+    .map_err(ServerError::from)?;
+
+    let anchor = match attached {
+        Some(Some(example_id)) => format!("example-{example_id}"),
+        Some(None) => "examples".to_owned(),
+        None => return Err(warp::reject::custom(WordNotFound(word_id))),
+    };
+
+    Ok(warp::redirect::see_other(
+        format!("/word/{word_id}#{anchor}").parse::<Uri>().unwrap(),
+    ))
+}
@@ -0,0 +1,122 @@
+//! Cross-site request forgery protection. Each signed in session has a CSRF token derived from its
+//! login token, which is embedded into forms as a hidden field and checked when they're submitted.
+//! Since another site can't read the login cookie, it can't forge a form submission with a valid
+//! token either.
+
+use crate::auth::{StaySignedInToken, STAY_LOGGED_IN_COOKIE};
+use crate::serialization::{
+    deserialize_form, form_bytes, multipart_form, FormFields, MultipartForm,
+};
+use serde::de::DeserializeOwned;
+use sha2::Digest;
+use std::convert::Infallible;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+use url::form_urlencoded;
+use warp::hyper::body::Bytes;
+use warp::{Filter, Rejection};
+
+/// The name of the hidden form field holding the token
+pub const CSRF_FIELD: &str = "csrf_token";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    pub fn for_session(session: &StaySignedInToken) -> CsrfToken {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update("csrf_");
+        hasher.update(session.token_id.to_le_bytes());
+        hasher.update(&session.token);
+        CsrfToken(format!("{:x}", hasher.finalize()))
+    }
+
+    fn verify(&self, submitted: &str) -> bool {
+        self.0.as_bytes().ct_eq(submitted.as_bytes()).into()
+    }
+}
+
+impl From<CsrfToken> for String {
+    fn from(token: CsrfToken) -> String {
+        token.0
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidCsrfToken;
+
+impl warp::reject::Reject for InvalidCsrfToken {}
+
+fn verify_form(session: Option<StaySignedInToken>, form: &[u8]) -> Result<(), Rejection> {
+    let submitted = form_urlencoded::parse(form)
+        .find(|(field, _)| field == CSRF_FIELD)
+        .map(|(_, token)| token);
+
+    verify_submitted(session, submitted.as_deref())
+}
+
+fn verify_submitted(
+    session: Option<StaySignedInToken>,
+    submitted: Option<&str>,
+) -> Result<(), Rejection> {
+    let expected = session.as_ref().map(CsrfToken::for_session);
+
+    match (expected, submitted) {
+        (Some(expected), Some(submitted)) if expected.verify(submitted) => Ok(()),
+        _ => {
+            warn!("Form submitted with a missing or invalid CSRF token");
+            Err(warp::reject::custom(InvalidCsrfToken))
+        }
+    }
+}
+
+fn with_session() -> impl Filter<Extract = (Option<StaySignedInToken>,), Error = Infallible> + Copy
+{
+    warp::cookie::optional(STAY_LOGGED_IN_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify()
+}
+
+/// Verifies the CSRF token of a url-encoded form whose other fields aren't needed
+pub fn csrf_protected() -> impl Filter<Extract = (), Error = Rejection> + Copy {
+    with_session()
+        .and(form_bytes())
+        .and_then(|session, bytes: Bytes| async move { verify_form(session, &bytes) })
+        .untuple_one()
+}
+
+/// Like [`form`](crate::serialization::form), but verifies the form's CSRF token first
+pub fn csrf_protected_form<T: DeserializeOwned + Send>(
+) -> impl Filter<Extract = (T,), Error = Rejection> + Copy {
+    with_session()
+        .and(form_bytes())
+        .and_then(|session, bytes: Bytes| async move {
+            verify_form(session, &bytes)?;
+            deserialize_form(&bytes)
+        })
+}
+
+/// Like [`csrf_protected_form`], but also extracts the raw fields of the form
+pub fn csrf_protected_form_with_fields<T: DeserializeOwned + Send>(
+) -> impl Filter<Extract = (T, FormFields), Error = Rejection> + Copy {
+    with_session()
+        .and(form_bytes())
+        .and_then(|session, bytes: Bytes| async move {
+            verify_form(session, &bytes)?;
+            let form = deserialize_form(&bytes)?;
+            Ok::<_, Rejection>((form, FormFields::parse(&bytes)))
+        })
+        .untuple_one()
+}
+
+/// Extracts a multipart form of at most `max_length` bytes, verifying its CSRF token
+pub fn csrf_protected_multipart(
+    max_length: u64,
+) -> impl Filter<Extract = (MultipartForm,), Error = Rejection> + Clone {
+    with_session().and(multipart_form(max_length)).and_then(
+        |session, form: MultipartForm| async move {
+            verify_submitted(session, form.fields.get(CSRF_FIELD))?;
+            Ok::<_, Rejection>(form)
+        },
+    )
+}
@@ -5,10 +5,38 @@ use isixhosa_common::types::PublicUserInfo;
 use rusqlite::params;
 use tracing::instrument;
 
+pub mod api_keys;
+pub mod bans;
+pub mod completeness;
 pub mod deletion;
+pub mod dependencies;
+pub mod drafts;
+pub mod example_sources;
+pub mod homepage;
+pub mod homographs;
+pub mod imports;
+pub mod link_suggestions;
+pub mod moderation_actions;
+pub mod neighbours;
+pub mod notifications;
+pub mod offline_bundle;
+pub mod pending;
+pub mod provenance;
+pub mod recovery;
+pub mod references;
+pub mod revisions;
+pub mod shortlinks;
+pub mod slugs;
+pub mod spam;
+pub mod status;
 pub mod submit;
 pub mod suggestion;
+pub mod tasks;
+pub mod terms;
+pub mod trainees;
 pub mod user;
+pub mod user_stats;
+pub mod views;
 
 #[instrument(name = "Add user attribution", skip(db))]
 pub fn add_user_attribution(db: &impl ModeratorAccessDb, user: &PublicUserInfo, word: WordId) {
@@ -17,7 +45,7 @@ pub fn add_user_attribution(db: &impl ModeratorAccessDb, user: &PublicUserInfo,
 
     db.get()
         .unwrap()
-        .prepare(INSERT)
+        .prepare_cached(INSERT)
         .unwrap()
         .execute(params![user.id.get(), word.0])
         .unwrap();
@@ -0,0 +1,237 @@
+//! Keys which users issue for themselves to use the high-volume API endpoints. Each key may make a
+//! limited number of requests per day, counted separately for each day (UTC) so that usage can be
+//! shown to the key's owner and to administrators.
+
+use super::user::{hash_token, verify_hash};
+use crate::auth::random_string_token;
+use chrono::{DateTime, Utc};
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{AdministratorAccessDb, PublicAccessDb, UserAccessDb};
+use rusqlite::{params, OptionalExtension, Row};
+use tracing::{instrument, Span};
+
+#[derive(Clone, Debug)]
+pub struct ApiKey {
+    pub key_id: u64,
+    pub name: String,
+    pub daily_quota: u64,
+    pub created: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+    /// The number of requests made with the key so far today
+    pub used_today: u64,
+    /// The number of requests made with the key since it was issued
+    pub used_total: u64,
+}
+
+impl TryFrom<&Row<'_>> for ApiKey {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(ApiKey {
+            key_id: row.get("key_id")?,
+            name: row.get("name")?,
+            daily_quota: row.get("daily_quota")?,
+            created: row.get("created")?,
+            last_used: row.get("last_used")?,
+            used_today: row.get("used_today")?,
+            used_total: row.get("used_total")?,
+        })
+    }
+}
+
+/// A key which was just issued. The secret is only known at this point, as only its hash is kept.
+pub struct NewApiKey {
+    pub key_id: u64,
+    /// The full key, which is sent in the `X-Api-Key` header
+    pub key: String,
+}
+
+/// The outcome of a request made with a key
+#[derive(Copy, Clone, Debug)]
+pub enum KeyUse {
+    /// The request was counted, and this many are left for today
+    Allowed { remaining: u64 },
+    /// The key has used up its quota for today
+    QuotaExceeded { quota: u64 },
+    /// The key is malformed, unknown, or has been revoked
+    Invalid,
+}
+
+impl ApiKey {
+    /// Fetch the user's keys, along with how much each has been used
+    #[instrument(name = "Fetch API keys", fields(results), skip(db))]
+    pub fn fetch_for_user(db: &impl UserAccessDb, user_id: u64) -> Vec<ApiKey> {
+        const SELECT: &str = "
+            SELECT
+                api_keys.key_id, name, daily_quota, created, last_used,
+                COALESCE(SUM(requests) FILTER (WHERE day = DATE(?2)), 0) AS used_today,
+                COALESCE(SUM(requests), 0) AS used_total
+            FROM api_keys
+            LEFT JOIN api_key_usage ON api_key_usage.key_id = api_keys.key_id
+            WHERE user_id = ?1
+            GROUP BY api_keys.key_id
+            ORDER BY created;
+        ";
+
+        let conn = db.get().unwrap();
+        let mut stmt = conn.prepare_cached(SELECT).unwrap();
+
+        #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+        let keys: Vec<ApiKey> = stmt
+            .query(params![user_id, Utc::now()])
+            .unwrap()
+            .map(|row| ApiKey::try_from(row))
+            .collect()
+            .unwrap();
+
+        Span::current().record("results", keys.len());
+        keys
+    }
+
+    /// Issue a new key to the user, unless they already hold the most keys allowed
+    #[instrument(name = "Create API key", skip(db))]
+    pub fn create(
+        db: &impl UserAccessDb,
+        user_id: u64,
+        name: &str,
+        daily_quota: u64,
+        max_per_user: usize,
+    ) -> Option<NewApiKey> {
+        const COUNT: &str = "SELECT COUNT(*) FROM api_keys WHERE user_id = ?1;";
+        const INSERT: &str = "
+            INSERT INTO api_keys (user_id, key_hash, name, daily_quota, created)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                RETURNING key_id;
+        ";
+
+        let mut conn = db.get().unwrap();
+        let tx = conn.transaction().unwrap();
+
+        let held: usize = tx
+            .prepare_cached(COUNT)
+            .unwrap()
+            .query_row(params![user_id], |row| row.get(0))
+            .unwrap();
+
+        if held >= max_per_user {
+            return None;
+        }
+
+        let secret = random_string_token();
+        let key_id: u64 = tx
+            .prepare_cached(INSERT)
+            .unwrap()
+            .query_row(
+                params![user_id, hash_token(&secret), name, daily_quota, Utc::now()],
+                |row| row.get("key_id"),
+            )
+            .unwrap();
+
+        tx.commit().unwrap();
+
+        Some(NewApiKey {
+            key_id,
+            key: format!("{key_id}_{secret}"),
+        })
+    }
+
+    /// Revoke one of the user's keys. Returns false if they don't hold the key.
+    #[instrument(name = "Revoke API key", skip(db))]
+    pub fn revoke(db: &impl UserAccessDb, user_id: u64, key_id: u64) -> bool {
+        const DELETE: &str = "DELETE FROM api_keys WHERE key_id = ?1 AND user_id = ?2;";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![key_id, user_id])
+            .unwrap()
+            > 0
+    }
+
+    /// Change how many requests one of the user's keys can make per day. Returns false if they
+    /// don't hold the key.
+    #[instrument(name = "Set API key quota", skip(db))]
+    pub fn set_quota(
+        db: &impl AdministratorAccessDb,
+        user_id: u64,
+        key_id: u64,
+        daily_quota: u64,
+    ) -> bool {
+        const UPDATE: &str =
+            "UPDATE api_keys SET daily_quota = ?3 WHERE key_id = ?1 AND user_id = ?2;";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![key_id, user_id, daily_quota])
+            .unwrap()
+            > 0
+    }
+
+    /// Check the key sent with a request and count the request against its quota for today.
+    /// Requests over the quota are not counted.
+    #[instrument(name = "Use API key", fields(key_id), skip_all)]
+    pub fn record_use(db: &impl PublicAccessDb, key: &str) -> KeyUse {
+        const SELECT: &str = "SELECT key_hash, daily_quota FROM api_keys WHERE key_id = ?1;";
+        const TOUCH: &str = "UPDATE api_keys SET last_used = ?2 WHERE key_id = ?1;";
+        // The update only happens while the key is under its quota, so no row is returned once it
+        // has been used up
+        const COUNT_USE: &str = "
+            INSERT INTO api_key_usage (key_id, day, requests) VALUES (?1, DATE(?2), 1)
+                ON CONFLICT (key_id, day) DO UPDATE SET requests = requests + 1
+                    WHERE requests < ?3
+                RETURNING requests;
+        ";
+
+        let Some((key_id, secret)) = key.split_once('_') else {
+            return KeyUse::Invalid;
+        };
+        let Ok(key_id) = key_id.parse::<u64>() else {
+            return KeyUse::Invalid;
+        };
+
+        Span::current().record("key_id", key_id);
+
+        let conn = db.get().unwrap();
+        let found: Option<(String, u64)> = conn
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![key_id], |row| {
+                Ok((row.get("key_hash")?, row.get("daily_quota")?))
+            })
+            .optional()
+            .unwrap();
+
+        let Some((key_hash, quota)) = found else {
+            return KeyUse::Invalid;
+        };
+
+        if !verify_hash(&key_hash, secret) {
+            return KeyUse::Invalid;
+        }
+
+        let now = Utc::now();
+        let used: Option<u64> = conn
+            .prepare_cached(COUNT_USE)
+            .unwrap()
+            .query_row(params![key_id, now, quota], |row| row.get("requests"))
+            .optional()
+            .unwrap();
+
+        match used {
+            Some(used) if used <= quota => {
+                conn.prepare_cached(TOUCH)
+                    .unwrap()
+                    .execute(params![key_id, now])
+                    .unwrap();
+
+                KeyUse::Allowed {
+                    remaining: quota - used,
+                }
+            }
+            _ => KeyUse::QuotaExceeded { quota },
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::bans::IpRange;
+use chrono::{DateTime, Utc};
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{AdministratorAccessDb, UserAccessDb};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{params, Row, ToSql};
+use std::net::IpAddr;
+use tracing::{instrument, Span};
+
+#[derive(Clone, Debug)]
+pub struct Ban {
+    pub ban_id: u64,
+    pub target: BanTarget,
+    pub reason: String,
+    pub banned_by: String,
+    pub created: DateTime<Utc>,
+    pub expires: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum BanTarget {
+    User { user_id: u64, username: String },
+    IpRange(IpRange),
+}
+
+impl ToSql for IpRange {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for IpRange {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|_| FromSqlError::Other("Invalid IP range".into()))
+    }
+}
+
+impl TryFrom<&Row<'_>> for Ban {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        let target = match row.get::<&str, Option<IpRange>>("ip_range")? {
+            Some(range) => BanTarget::IpRange(range),
+            None => BanTarget::User {
+                user_id: row.get("user_id")?,
+                username: row.get("username")?,
+            },
+        };
+
+        Ok(Ban {
+            ban_id: row.get("ban_id")?,
+            target,
+            reason: row.get("reason")?,
+            banned_by: row.get("banned_by_username")?,
+            created: row.get("created")?,
+            expires: row.get("expires")?,
+        })
+    }
+}
+
+const SELECT_ACTIVE: &str = "
+    SELECT bans.ban_id, bans.user_id, banned.username, bans.ip_range, bans.reason,
+           banned_by.username AS banned_by_username, bans.created, bans.expires
+        FROM bans
+        LEFT JOIN users AS banned ON bans.user_id = banned.user_id
+        INNER JOIN users AS banned_by ON bans.banned_by = banned_by.user_id
+";
+
+impl Ban {
+    #[instrument(name = "Add ban", fields(ban_id), skip(db, reason))]
+    pub fn add(
+        db: &impl AdministratorAccessDb,
+        target: NewBanTarget,
+        reason: &str,
+        banned_by: u64,
+        expires: Option<DateTime<Utc>>,
+    ) -> u64 {
+        const INSERT: &str = "
+            INSERT INTO bans (user_id, ip_range, reason, banned_by, created, expires)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                RETURNING ban_id;
+        ";
+
+        let (user_id, ip_range) = match target {
+            NewBanTarget::User(user_id) => (Some(user_id), None),
+            NewBanTarget::IpRange(range) => (None, Some(range)),
+        };
+
+        let conn = db.get().unwrap();
+        let ban_id = conn
+            .prepare_cached(INSERT)
+            .unwrap()
+            .query_row(
+                params![user_id, ip_range, reason, banned_by, Utc::now(), expires],
+                |row| row.get("ban_id"),
+            )
+            .unwrap();
+
+        Span::current().record("ban_id", ban_id);
+
+        ban_id
+    }
+
+    #[instrument(name = "Lift ban", fields(found), skip(db))]
+    pub fn lift(db: &impl AdministratorAccessDb, ban_id: u64) -> bool {
+        const DELETE: &str = "DELETE FROM bans WHERE ban_id = ?1;";
+
+        let conn = db.get().unwrap();
+        let modified_rows = conn
+            .prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![ban_id])
+            .unwrap();
+        let found = modified_rows == 1;
+        Span::current().record("found", found);
+        found
+    }
+
+    /// All bans which have not yet expired, newest first
+    #[instrument(name = "Fetch all active bans", fields(results), skip(db))]
+    pub fn fetch_all_active(db: &impl AdministratorAccessDb) -> Vec<Ban> {
+        let select = format!(
+            "{SELECT_ACTIVE} WHERE bans.expires IS NULL OR bans.expires > ?1 ORDER BY bans.ban_id DESC;"
+        );
+
+        let conn = db.get().unwrap();
+        #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+        let bans: Vec<Ban> = conn
+            .prepare_cached(&select)
+            .unwrap()
+            .query(params![Utc::now()])
+            .unwrap()
+            .map(|row| Ban::try_from(row))
+            .collect()
+            .unwrap();
+
+        Span::current().record("results", bans.len());
+
+        bans
+    }
+
+    /// The active ban, if any, on the user or on a range which includes the IP address
+    #[instrument(
+        level = "trace",
+        name = "Fetch active ban for user",
+        fields(found),
+        skip(db)
+    )]
+    pub fn fetch_active_for(
+        db: &impl UserAccessDb,
+        user_id: u64,
+        ip: Option<IpAddr>,
+    ) -> Option<Ban> {
+        let select = format!(
+            "{SELECT_ACTIVE}
+                WHERE (bans.user_id = ?1 OR bans.ip_range IS NOT NULL) AND
+                      (bans.expires IS NULL OR bans.expires > ?2);"
+        );
+
+        let conn = db.get().unwrap();
+        #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+        let ban = conn
+            .prepare_cached(&select)
+            .unwrap()
+            .query(params![user_id, Utc::now()])
+            .unwrap()
+            .map(|row| Ban::try_from(row))
+            .find(|ban| {
+                Ok(match &ban.target {
+                    BanTarget::User { .. } => true,
+                    BanTarget::IpRange(range) => ip.is_some_and(|ip| range.contains(ip)),
+                })
+            })
+            .unwrap();
+
+        Span::current().record("found", ban.is_some());
+
+        ban
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum NewBanTarget {
+    User(u64),
+    IpRange(IpRange),
+}
@@ -0,0 +1,71 @@
+//! Entries which are missing parts, for contributors looking for something to work on
+
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::language::PartOfSpeech;
+use isixhosa_common::types::{Completeness, WordHit};
+use rusqlite::params;
+use tracing::{instrument, Span};
+
+#[derive(Debug)]
+pub struct IncompleteWord {
+    pub hit: WordHit,
+    pub completeness: Completeness,
+}
+
+/// Fetch one page of the words which are missing parts, with the least complete first. One more
+/// word than `page_size` is fetched so that the caller can tell whether there is a next page.
+#[instrument(name = "Fetch incomplete words", fields(results), skip(db))]
+pub fn fetch_incomplete(
+    db: &impl PublicAccessDb,
+    page: u64,
+    page_size: u64,
+) -> Vec<IncompleteWord> {
+    const SELECT: &str = "
+        WITH parts AS (
+            SELECT
+                word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+                transitivity, noun_class,
+                EXISTS(SELECT 1 FROM examples WHERE examples.word_id = words.word_id)
+                    AS has_example,
+                EXISTS(
+                    SELECT 1 FROM linked_words
+                    WHERE first_word_id = words.word_id OR second_word_id = words.word_id
+                ) AS has_link,
+                CASE WHEN part_of_speech = ?1 THEN noun_class IS NOT NULL END AS has_noun_class
+            FROM words
+        )
+        SELECT * FROM parts
+        WHERE NOT has_example OR NOT has_link OR NOT IFNULL(has_noun_class, 1)
+        ORDER BY has_example + has_link + IFNULL(has_noun_class, 1), word_id
+        LIMIT ?2 OFFSET ?3;
+    ";
+
+    let conn = db.get().unwrap();
+    let words: Vec<IncompleteWord> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![
+            PartOfSpeech::Noun,
+            page_size + 1,
+            page.saturating_mul(page_size)
+        ])
+        .unwrap()
+        .map(|row| {
+            let id = row.get::<&str, i64>("word_id")? as u64;
+            Ok(IncompleteWord {
+                hit: WordHit::try_from_row_and_id(row, WordOrSuggestionId::existing(id))?,
+                completeness: Completeness {
+                    has_example: row.get("has_example")?,
+                    has_link: row.get("has_link")?,
+                    has_noun_class: row.get("has_noun_class")?,
+                },
+            })
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", words.len());
+
+    words
+}
@@ -39,7 +39,7 @@ impl WordDeletionSuggestion {
 
         // thanks rustc for forcing this `let x = ...; x` very cool
         let x: Vec<Self> = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query(params![])
             .unwrap()
@@ -74,7 +74,7 @@ impl WordDeletionSuggestion {
 
         let conn = db.get().unwrap();
         let word_id = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query_row(params![suggestion], |row| row.get("word_id"))
             .unwrap();
@@ -89,7 +89,7 @@ impl WordDeletionSuggestion {
         const DELETE: &str = "DELETE FROM word_deletion_suggestions WHERE suggestion_id = ?1;";
 
         let conn = db.get().unwrap();
-        conn.prepare(DELETE)
+        conn.prepare_cached(DELETE)
             .unwrap()
             .execute(params![suggestion])
             .unwrap();
@@ -126,15 +126,17 @@ impl ExampleDeletionSuggestion {
     pub fn fetch_all(db: &impl ModeratorAccessDb) -> impl Iterator<Item = (WordId, Vec<Self>)> {
         const SELECT: &str =
             "SELECT examples.example_id, examples.word_id, examples.xhosa, examples.english,
+                    example_sources.source, example_sources.alignment,
                     example_deletion_suggestions.suggestion_id, example_deletion_suggestions.reason,
                     users.username, users.display_name, example_deletion_suggestions.suggesting_user
             FROM examples
+            LEFT JOIN example_sources ON examples.example_id = example_sources.example_id
             INNER JOIN users ON example_deletion_suggestions.suggesting_user = users.user_id
             INNER JOIN example_deletion_suggestions
                 ON examples.example_id = example_deletion_suggestions.example_id;";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
         let deletions = query.query(params![]).unwrap();
 
         let mut map: HashMap<WordId, Vec<Self>> = HashMap::new();
@@ -170,7 +172,7 @@ impl ExampleDeletionSuggestion {
 
         let conn = db.get().unwrap();
         let example_id = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query_row(params![suggestion], |row| row.get("example_id"))
             .unwrap();
@@ -186,7 +188,7 @@ impl ExampleDeletionSuggestion {
 
         let to_delete = Self::fetch_example_id_for_suggestion(db, suggestion);
         let conn = db.get().unwrap();
-        conn.prepare(DELETE_EXAMPLE)
+        conn.prepare_cached(DELETE_EXAMPLE)
             .unwrap()
             .execute(params![to_delete])
             .unwrap();
@@ -198,7 +200,7 @@ impl ExampleDeletionSuggestion {
         const DELETE: &str = "DELETE FROM example_deletion_suggestions WHERE suggestion_id = ?1;";
 
         let conn = db.get().unwrap();
-        conn.prepare(DELETE)
+        conn.prepare_cached(DELETE)
             .unwrap()
             .execute(params![suggestion])
             .unwrap();
@@ -257,7 +259,7 @@ impl LinkedWordDeletionSuggestion {
                 ON linked_words.link_id = linked_word_deletion_suggestions.linked_word_id;";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
         let deletions = query.query(params![]).unwrap();
 
         let mut map: HashMap<WordId, Vec<Self>> = HashMap::new();
@@ -300,7 +302,7 @@ impl LinkedWordDeletionSuggestion {
 
         let conn = db.get().unwrap();
         let link_id = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query_row(params![suggestion], |row| row.get("linked_word_id"))
             .unwrap();
@@ -316,7 +318,7 @@ impl LinkedWordDeletionSuggestion {
 
         let to_delete = Self::fetch_link_id_for_suggestion(db, suggestion);
         let conn = db.get().unwrap();
-        conn.prepare(DELETE)
+        conn.prepare_cached(DELETE)
             .unwrap()
             .execute(params![to_delete])
             .unwrap();
@@ -329,7 +331,7 @@ impl LinkedWordDeletionSuggestion {
             "DELETE FROM linked_word_deletion_suggestions WHERE suggestion_id = ?1";
 
         let conn = db.get().unwrap();
-        conn.prepare(DELETE)
+        conn.prepare_cached(DELETE)
             .unwrap()
             .execute(params![suggestion])
             .unwrap();
@@ -0,0 +1,175 @@
+//! Suggested words can be linked to other suggested words which are still pending, e.g a new plural
+//! linked to its new singular. The links can only be added once both words are accepted, so each
+//! suggestion depends on the other. Accepting one prompts the moderator to review the others, and
+//! rejecting one flags the suggestions which depended on it.
+
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{ModeratorAccessDb, WordOrSuggestionId};
+use isixhosa_common::types::WordHit;
+use rusqlite::{params, Params};
+use std::collections::HashMap;
+use tracing::{instrument, Span};
+
+/// Pairs of suggested words which are linked to each other, in both directions
+const DEPENDENCIES: &str = "
+    SELECT suggested_word_id AS this_id, second_suggested_word_id AS other_id
+        FROM linked_word_suggestions
+    UNION
+    SELECT second_suggested_word_id, suggested_word_id
+        FROM linked_word_suggestions
+";
+
+/// A suggested word which another suggestion was linked to, but which was rejected
+#[derive(Clone, Debug)]
+pub struct RejectedDependency {
+    pub english: String,
+    pub xhosa: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Dependencies {
+    /// Other pending suggested words which the suggestion is linked to
+    pub pending: Vec<WordHit>,
+    /// Suggested words which the suggestion was linked to, but which have since been rejected
+    pub rejected: Vec<RejectedDependency>,
+}
+
+impl Dependencies {
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.rejected.is_empty()
+    }
+}
+
+fn fetch_pending_with_filter(
+    db: &impl ModeratorAccessDb,
+    where_clause: &str,
+    params: impl Params,
+) -> HashMap<u64, Vec<WordHit>> {
+    let select = format!(
+        "
+        SELECT
+            dependencies.this_id, word_suggestions.suggestion_id, english, xhosa, part_of_speech,
+            is_plural, is_inchoative, is_informal, transitivity, noun_class
+        FROM ({DEPENDENCIES}) AS dependencies
+        INNER JOIN word_suggestions ON word_suggestions.suggestion_id = dependencies.other_id
+        WHERE dependencies.this_id IS NOT NULL {where_clause}
+        ORDER BY word_suggestions.suggestion_id;
+        "
+    );
+
+    let conn = db.get().unwrap();
+    let mut query = conn.prepare_cached(&select).unwrap();
+    let mut map: HashMap<u64, Vec<WordHit>> = HashMap::new();
+
+    query
+        .query(params)
+        .unwrap()
+        .for_each(|row| {
+            let id = WordOrSuggestionId::suggested(row.get("suggestion_id")?);
+            map.entry(row.get("this_id")?)
+                .or_default()
+                .push(WordHit::try_from_row_and_id(row, id)?);
+            Ok(())
+        })
+        .unwrap();
+
+    map
+}
+
+fn fetch_rejected_with_filter(
+    db: &impl ModeratorAccessDb,
+    where_clause: &str,
+    params: impl Params,
+) -> HashMap<u64, Vec<RejectedDependency>> {
+    let select = format!(
+        "
+        SELECT suggestion_id, english, xhosa FROM rejected_dependencies
+        {where_clause}
+        ORDER BY rowid;
+        "
+    );
+
+    let conn = db.get().unwrap();
+    let mut query = conn.prepare_cached(&select).unwrap();
+    let mut map: HashMap<u64, Vec<RejectedDependency>> = HashMap::new();
+
+    query
+        .query(params)
+        .unwrap()
+        .for_each(|row| {
+            map.entry(row.get("suggestion_id")?)
+                .or_default()
+                .push(RejectedDependency {
+                    english: row.get("english")?,
+                    xhosa: row.get("xhosa")?,
+                });
+            Ok(())
+        })
+        .unwrap();
+
+    map
+}
+
+/// The dependencies of every suggested word which has any, by suggestion ID
+#[instrument(name = "Fetch all suggestion dependencies", fields(results), skip(db))]
+pub fn fetch_all(db: &impl ModeratorAccessDb) -> HashMap<u64, Dependencies> {
+    let mut all: HashMap<u64, Dependencies> = HashMap::new();
+
+    for (id, pending) in fetch_pending_with_filter(db, "", params![]) {
+        all.entry(id).or_default().pending = pending;
+    }
+
+    for (id, rejected) in fetch_rejected_with_filter(db, "", params![]) {
+        all.entry(id).or_default().rejected = rejected;
+    }
+
+    Span::current().record("results", all.len());
+
+    all
+}
+
+#[instrument(name = "Fetch suggestion dependencies", skip(db))]
+pub fn fetch_for_suggestion(db: &impl ModeratorAccessDb, suggestion_id: u64) -> Dependencies {
+    let pending =
+        fetch_pending_with_filter(db, "AND dependencies.this_id = ?1", params![suggestion_id]);
+    let rejected =
+        fetch_rejected_with_filter(db, "WHERE suggestion_id = ?1", params![suggestion_id]);
+
+    Dependencies {
+        pending: pending.into_values().next().unwrap_or_default(),
+        rejected: rejected.into_values().next().unwrap_or_default(),
+    }
+}
+
+/// Flag every pending suggested word which is linked to the given one as depending on a rejected
+/// suggestion. This must be done before the suggestion is deleted, since its links are deleted with
+/// it. Returns the number of suggestions flagged.
+#[instrument(
+    name = "Flag dependents of rejected suggestion",
+    fields(flagged),
+    skip(db)
+)]
+pub fn flag_dependents(db: &impl ModeratorAccessDb, rejected_id: u64) -> usize {
+    let insert = format!(
+        "
+        INSERT INTO rejected_dependencies (suggestion_id, english, xhosa)
+            SELECT DISTINCT dependencies.other_id, rejected.english, rejected.xhosa
+            FROM ({DEPENDENCIES}) AS dependencies
+            INNER JOIN word_suggestions AS rejected
+                ON rejected.suggestion_id = dependencies.this_id
+            WHERE dependencies.this_id = ?1 AND dependencies.other_id IS NOT NULL;
+        "
+    );
+
+    let flagged = db
+        .get()
+        .unwrap()
+        .prepare_cached(&insert)
+        .unwrap()
+        .execute(params![rejected_id])
+        .unwrap();
+
+    Span::current().record("flagged", flagged);
+
+    flagged
+}
@@ -0,0 +1,136 @@
+//! Word submissions which a user has saved to finish later. The form is stored exactly as it was
+//! submitted, since an incomplete submission can't be stored as a suggestion.
+
+use chrono::{DateTime, Utc};
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::UserAccessDb;
+use rusqlite::{params, OptionalExtension, Row};
+use tracing::{instrument, Span};
+
+#[derive(Clone, Debug)]
+pub struct Draft {
+    pub draft_id: u64,
+    pub english: String,
+    pub xhosa: String,
+    /// The url-encoded form, without its CSRF token
+    pub form: String,
+    pub last_saved: DateTime<Utc>,
+}
+
+impl TryFrom<&Row<'_>> for Draft {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Draft {
+            draft_id: row.get("draft_id")?,
+            english: row.get("english")?,
+            xhosa: row.get("xhosa")?,
+            form: row.get("form")?,
+            last_saved: row.get("last_saved")?,
+        })
+    }
+}
+
+impl Draft {
+    /// Save the draft, overwriting the given draft if the user owns it. Returns the ID of the draft.
+    #[instrument(name = "Save draft", fields(draft_id), skip(db, english, xhosa, form))]
+    pub fn save(
+        db: &impl UserAccessDb,
+        user_id: u64,
+        draft_id: Option<u64>,
+        english: &str,
+        xhosa: &str,
+        form: &str,
+    ) -> u64 {
+        const UPDATE: &str = "
+            UPDATE drafts SET english = ?3, xhosa = ?4, form = ?5, last_saved = ?6
+                WHERE draft_id = ?1 AND user_id = ?2;
+        ";
+        const INSERT: &str = "
+            INSERT INTO drafts (user_id, english, xhosa, form, last_saved)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                RETURNING draft_id;
+        ";
+
+        let conn = db.get().unwrap();
+        let now = Utc::now();
+
+        let updated = draft_id.filter(|id| {
+            conn.prepare_cached(UPDATE)
+                .unwrap()
+                .execute(params![id, user_id, english, xhosa, form, now])
+                .unwrap()
+                > 0
+        });
+
+        let draft_id = updated.unwrap_or_else(|| {
+            conn.prepare_cached(INSERT)
+                .unwrap()
+                .query_row(params![user_id, english, xhosa, form, now], |row| {
+                    row.get("draft_id")
+                })
+                .unwrap()
+        });
+
+        Span::current().record("draft_id", draft_id);
+
+        draft_id
+    }
+
+    #[instrument(name = "Fetch draft", fields(found), skip(db))]
+    pub fn fetch(db: &impl UserAccessDb, user_id: u64, draft_id: u64) -> Option<Draft> {
+        const SELECT: &str = "
+            SELECT draft_id, english, xhosa, form, last_saved FROM drafts
+                WHERE draft_id = ?1 AND user_id = ?2;
+        ";
+
+        let conn = db.get().unwrap();
+        let draft = conn
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![draft_id, user_id], |row| Draft::try_from(row))
+            .optional()
+            .unwrap();
+
+        Span::current().record("found", draft.is_some());
+
+        draft
+    }
+
+    #[instrument(name = "Fetch all drafts for user", fields(results), skip(db))]
+    pub fn fetch_all_for_user(db: &impl UserAccessDb, user_id: u64) -> Vec<Draft> {
+        const SELECT: &str = "
+            SELECT draft_id, english, xhosa, form, last_saved FROM drafts
+                WHERE user_id = ?1
+                ORDER BY last_saved DESC;
+        ";
+
+        let conn = db.get().unwrap();
+
+        #[allow(clippy::redundant_closure)] // lifetime issue
+        let drafts: Vec<Draft> = conn
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query(params![user_id])
+            .unwrap()
+            .map(|row| Draft::try_from(row))
+            .collect()
+            .unwrap();
+
+        Span::current().record("results", drafts.len());
+
+        drafts
+    }
+
+    #[instrument(name = "Delete draft", skip(db))]
+    pub fn delete(db: &impl UserAccessDb, user_id: u64, draft_id: u64) {
+        const DELETE: &str = "DELETE FROM drafts WHERE draft_id = ?1 AND user_id = ?2;";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![draft_id, user_id])
+            .unwrap();
+    }
+}
@@ -0,0 +1,39 @@
+//! Examples which moderators pick from a corpus and attach to words directly, rather than going
+//! through suggestions, along with the corpus they came from and its word alignment for them.
+
+use crate::error::DbError;
+use isixhosa_common::database::ModeratorAccessDb;
+use rusqlite::params;
+use tracing::instrument;
+
+#[instrument(name = "Attach example from corpus", skip(db))]
+pub fn attach_example(
+    db: &impl ModeratorAccessDb,
+    word_id: u64,
+    english: &str,
+    xhosa: &str,
+    source: &str,
+    alignment: Option<&str>,
+) -> Result<u64, DbError> {
+    const INSERT_EXAMPLE: &str = "
+        INSERT INTO examples (word_id, english, xhosa) VALUES (?1, ?2, ?3)
+            RETURNING example_id;
+    ";
+    const INSERT_SOURCE: &str = "
+        INSERT INTO example_sources (example_id, source, alignment) VALUES (?1, ?2, ?3);
+    ";
+
+    let mut conn = db.get()?;
+    let tx = conn.transaction()?;
+
+    let example_id: u64 = tx
+        .prepare_cached(INSERT_EXAMPLE)?
+        .query_row(params![word_id, english.trim(), xhosa.trim()], |row| {
+            row.get("example_id")
+        })?;
+    tx.prepare_cached(INSERT_SOURCE)?
+        .execute(params![example_id, source, alignment])?;
+
+    tx.commit()?;
+    Ok(example_id)
+}
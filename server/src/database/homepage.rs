@@ -0,0 +1,65 @@
+//! Word counts and the featured word for the homepage
+
+use chrono::{Datelike, NaiveDate};
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::language::PartOfSpeech;
+use isixhosa_common::types::WordHit;
+use rusqlite::{params, OptionalExtension};
+use tracing::{instrument, Span};
+
+/// The number of words for each part of speech, with the most common first. Words without a part
+/// of speech are counted under `None`.
+#[instrument(name = "Count words by part of speech", skip(db))]
+pub fn count_by_part_of_speech(db: &impl PublicAccessDb) -> Vec<(Option<PartOfSpeech>, u64)> {
+    const SELECT: &str = "
+        SELECT part_of_speech, COUNT(1) AS count FROM words
+            GROUP BY part_of_speech
+            ORDER BY count DESC;
+    ";
+
+    db.get()
+        .unwrap()
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![])
+        .unwrap()
+        .map(|row| Ok((row.get("part_of_speech")?, row.get("count")?)))
+        .collect()
+        .unwrap()
+}
+
+/// The word featured on the given day, which rotates through all of the words except offensive ones
+#[instrument(name = "Fetch featured word", fields(found), skip(db))]
+pub fn fetch_featured(db: &impl PublicAccessDb, day: NaiveDate) -> Option<WordHit> {
+    const SELECT: &str = "
+        SELECT
+            word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM words
+        WHERE NOT is_offensive
+        ORDER BY word_id
+        LIMIT 1 OFFSET ?1 % MAX((SELECT COUNT(1) FROM words WHERE NOT is_offensive), 1);
+    ";
+
+    // Step through the words by a large prime rather than in order, so that words added together,
+    // which are often related, aren't featured one after another. Since it's prime, every word is
+    // still featured before any is repeated unless the number of words is a multiple of it.
+    let offset = (day.num_days_from_ce() as i64).wrapping_mul(7919);
+
+    let word = db
+        .get()
+        .unwrap()
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query_row(params![offset], |row| {
+            let id = row.get::<&str, i64>("word_id")? as u64;
+            WordHit::try_from_row_and_id(row, WordOrSuggestionId::existing(id))
+        })
+        .optional()
+        .unwrap();
+
+    Span::current().record("found", word.is_some());
+
+    word
+}
@@ -0,0 +1,113 @@
+//! Homographs are entries which are spelled the same in isiXhosa but have different meanings. They
+//! are easily mixed up, so they are linked to each other as confusable when accepted, and each of
+//! their pages lists the others.
+
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{ModeratorAccessDb, PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::language::WordLinkType;
+use isixhosa_common::types::WordHit;
+use rusqlite::params;
+use tracing::{instrument, Span};
+
+/// The other entries spelled the same way in isiXhosa as the given one, ignoring case, but with a
+/// different English meaning
+#[instrument(name = "Fetch homographs", fields(results), skip(db))]
+pub fn fetch_homographs(
+    db: &impl PublicAccessDb,
+    word_id: u64,
+    xhosa: &str,
+    english: &str,
+) -> Vec<WordHit> {
+    const SELECT: &str = "
+        SELECT
+            word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM words
+        WHERE
+            word_id != ?1 AND
+            LOWER(TRIM(xhosa)) = LOWER(TRIM(?2)) AND
+            LOWER(TRIM(english)) != LOWER(TRIM(?3))
+        ORDER BY word_id;
+    ";
+
+    let conn = db.get().unwrap();
+    let mut query = conn.prepare_cached(SELECT).unwrap();
+
+    let words: Vec<WordHit> = query
+        .query(params![word_id, xhosa, english])
+        .unwrap()
+        .map(|row| {
+            let id = row.get::<&str, i64>("word_id")? as u64;
+            WordHit::try_from_row_and_id(row, WordOrSuggestionId::existing(id))
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", words.len());
+
+    words
+}
+
+/// Every entry spelled the given way in isiXhosa, ignoring case
+#[instrument(name = "Fetch words with spelling", fields(results), skip(db))]
+pub fn fetch_with_spelling(db: &impl PublicAccessDb, xhosa: &str) -> Vec<WordHit> {
+    const SELECT: &str = "
+        SELECT
+            word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM words
+        WHERE LOWER(TRIM(xhosa)) = LOWER(TRIM(?1))
+        ORDER BY word_id;
+    ";
+
+    let conn = db.get().unwrap();
+    let mut query = conn.prepare_cached(SELECT).unwrap();
+
+    let words: Vec<WordHit> = query
+        .query(params![xhosa])
+        .unwrap()
+        .map(|row| {
+            let id = row.get::<&str, i64>("word_id")? as u64;
+            WordHit::try_from_row_and_id(row, WordOrSuggestionId::existing(id))
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", words.len());
+
+    words
+}
+
+/// Link the word to each of its homographs as confusable. Homographs which are already linked to
+/// the word in any way, e.g as alternate uses, are left as they are. Returns the number of links
+/// added.
+#[instrument(name = "Link homographs", fields(linked), skip(db))]
+pub fn link_homographs(db: &impl ModeratorAccessDb, word_id: u64) -> usize {
+    const INSERT: &str = "
+        INSERT INTO linked_words (link_type, first_word_id, second_word_id)
+            SELECT ?2, word.word_id, other.word_id
+            FROM words AS word
+            INNER JOIN words AS other ON
+                other.word_id != word.word_id AND
+                LOWER(TRIM(other.xhosa)) = LOWER(TRIM(word.xhosa)) AND
+                LOWER(TRIM(other.english)) != LOWER(TRIM(word.english))
+            WHERE word.word_id = ?1 AND NOT EXISTS (
+                SELECT 1 FROM linked_words
+                WHERE
+                    (first_word_id = word.word_id AND second_word_id = other.word_id) OR
+                    (first_word_id = other.word_id AND second_word_id = word.word_id)
+            );
+    ";
+
+    let linked = db
+        .get()
+        .unwrap()
+        .prepare_cached(INSERT)
+        .unwrap()
+        .execute(params![word_id, WordLinkType::Confusable])
+        .unwrap();
+
+    Span::current().record("linked", linked);
+
+    linked
+}
@@ -0,0 +1,239 @@
+//! Batches of suggestions imported from CSV files by administrators. Each suggestion in a batch
+//! records the batch as its [`Provenance`](super::provenance::Provenance), as does the revision made
+//! when it is accepted, so that a batch can be traced after its suggestions have been reviewed.
+
+use crate::database::revisions::WordSnapshot;
+use crate::error::DbError;
+use chrono::{DateTime, Utc};
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::AdministratorAccessDb;
+use rusqlite::{params, OptionalExtension, Row};
+use tracing::{instrument, Span};
+
+/// An import batch, as listed on the imports page
+#[derive(Debug)]
+pub struct ImportBatch {
+    pub batch_id: u64,
+    pub filename: String,
+    /// The administrator who uploaded the file, unless their account has been deleted
+    pub imported_by: Option<String>,
+    pub imported: DateTime<Utc>,
+    /// Suggestions from the batch which are still waiting to be reviewed
+    pub pending: u64,
+    /// Words added or edited by accepting suggestions from the batch
+    pub accepted: u64,
+    pub rolled_back: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<&Row<'_>> for ImportBatch {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(ImportBatch {
+            batch_id: row.get("batch_id")?,
+            filename: row.get("filename")?,
+            imported_by: row.get("username")?,
+            imported: row.get("imported")?,
+            pending: row.get("pending")?,
+            accepted: row.get("accepted")?,
+            rolled_back: row.get("rolled_back")?,
+        })
+    }
+}
+
+const SELECT_BATCHES: &str = "
+    SELECT
+        batch_id, filename, username, imported, rolled_back,
+        (SELECT COUNT(*) FROM word_suggestions
+            WHERE word_suggestions.import_batch = import_batches.batch_id) AS pending,
+        (SELECT COUNT(*) FROM word_revisions
+            WHERE word_revisions.import_batch = import_batches.batch_id) AS accepted
+    FROM import_batches
+    LEFT OUTER JOIN users ON users.user_id = import_batches.user_id";
+
+#[instrument(name = "Create import batch", fields(batch_id), skip(db))]
+pub fn create_batch(
+    db: &impl AdministratorAccessDb,
+    user_id: u64,
+    filename: &str,
+) -> Result<u64, DbError> {
+    const INSERT: &str = "
+        INSERT INTO import_batches (user_id, filename, imported) VALUES (?1, ?2, ?3)
+            RETURNING batch_id;
+    ";
+
+    let batch_id = db
+        .get()?
+        .prepare_cached(INSERT)?
+        .query_row(params![user_id, filename, Utc::now()], |row| row.get(0))?;
+
+    Span::current().record("batch_id", batch_id);
+    Ok(batch_id)
+}
+
+/// All import batches, newest first
+#[instrument(name = "Fetch import batches", fields(results), skip(db))]
+pub fn fetch_batches(db: &impl AdministratorAccessDb) -> Result<Vec<ImportBatch>, DbError> {
+    let select = format!("{SELECT_BATCHES} ORDER BY batch_id DESC;");
+
+    let conn = db.get()?;
+    #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+    let batches = conn
+        .prepare_cached(&select)?
+        .query(params![])?
+        .map(|row| ImportBatch::try_from(row))
+        .collect::<Vec<_>>()?;
+
+    Span::current().record("results", batches.len());
+    Ok(batches)
+}
+
+#[instrument(name = "Fetch import batch", fields(found), skip(db))]
+pub fn fetch_batch(
+    db: &impl AdministratorAccessDb,
+    batch_id: u64,
+) -> Result<Option<ImportBatch>, DbError> {
+    let select = format!("{SELECT_BATCHES} WHERE batch_id = ?1;");
+
+    #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+    let batch = db
+        .get()?
+        .prepare_cached(&select)?
+        .query_row(params![batch_id], |row| ImportBatch::try_from(row))
+        .optional()?;
+
+    Span::current().record("found", batch.is_some());
+    Ok(batch)
+}
+
+/// A word or suggestion affected by rolling back a batch
+#[derive(Debug)]
+pub struct RollbackItem {
+    /// The suggestion ID for pending suggestions, and the word ID otherwise
+    pub id: u64,
+    pub english: String,
+    pub xhosa: String,
+}
+
+/// What rolling back an import batch would do
+#[derive(Debug, Default)]
+pub struct RollbackPlan {
+    /// Suggestions which haven't been reviewed yet, to be rejected
+    pub pending: Vec<RollbackItem>,
+    /// Words which the batch added, to be deleted
+    pub added: Vec<RollbackItem>,
+    /// Words which the batch edited, to be put back as they were before
+    pub edited: Vec<(RollbackItem, WordSnapshot)>,
+    /// Words which have been edited since the batch changed them, or whose previous state wasn't
+    /// kept. These are left alone, so that later work isn't lost.
+    pub conflicts: Vec<RollbackItem>,
+}
+
+impl RollbackPlan {
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+            && self.added.is_empty()
+            && self.edited.is_empty()
+            && self.conflicts.is_empty()
+    }
+}
+
+/// Work out what rolling back the batch would do, without changing anything
+#[instrument(
+    name = "Fetch import batch rollback plan",
+    fields(pending, added, edited, conflicts),
+    skip(db)
+)]
+pub fn fetch_rollback_plan(
+    db: &impl AdministratorAccessDb,
+    batch_id: u64,
+) -> Result<RollbackPlan, DbError> {
+    // Suggestions which edit a word only hold the fields which they change
+    const SELECT_PENDING: &str = "
+        SELECT
+            suggestion_id,
+            COALESCE(word_suggestions.english, words.english, '') AS english,
+            COALESCE(word_suggestions.xhosa, words.xhosa, '') AS xhosa
+        FROM word_suggestions
+        LEFT OUTER JOIN words ON words.word_id = word_suggestions.existing_word_id
+        WHERE import_batch = ?1
+        ORDER BY suggestion_id;
+    ";
+
+    // Only the earliest revision from the batch is needed for each word, as it holds the word as
+    // it was before the batch changed it
+    const SELECT_REVISIONS: &str = "
+        SELECT
+            revisions.word_id, english, xhosa, added, previous,
+            EXISTS(
+                SELECT 1 FROM word_revisions AS later
+                WHERE later.word_id = revisions.word_id
+                    AND later.revision_id > revisions.revision_id
+                    AND later.import_batch IS NOT ?1
+            ) AS changed_since
+        FROM word_revisions AS revisions
+        INNER JOIN words ON words.word_id = revisions.word_id
+        WHERE revisions.import_batch = ?1 AND revisions.revision_id = (
+            SELECT MIN(first.revision_id) FROM word_revisions AS first
+            WHERE first.word_id = revisions.word_id AND first.import_batch = ?1
+        )
+        ORDER BY revisions.revision_id;
+    ";
+
+    let conn = db.get()?;
+
+    let pending = conn
+        .prepare_cached(SELECT_PENDING)?
+        .query(params![batch_id])?
+        .map(|row| {
+            Ok(RollbackItem {
+                id: row.get("suggestion_id")?,
+                english: row.get("english")?,
+                xhosa: row.get("xhosa")?,
+            })
+        })
+        .collect::<Vec<_>>()?;
+    let mut plan = RollbackPlan {
+        pending,
+        ..Default::default()
+    };
+
+    let mut stmt = conn.prepare_cached(SELECT_REVISIONS)?;
+    let mut rows = stmt.query(params![batch_id])?;
+
+    while let Some(row) = rows.next()? {
+        let item = RollbackItem {
+            id: row.get("word_id")?,
+            english: row.get("english")?,
+            xhosa: row.get("xhosa")?,
+        };
+        let previous = row
+            .get::<&str, Option<String>>("previous")?
+            .and_then(|previous| serde_json::from_str(&previous).ok());
+
+        match (row.get("changed_since")?, row.get("added")?, previous) {
+            (true, _, _) => plan.conflicts.push(item),
+            (false, true, _) => plan.added.push(item),
+            (false, false, Some(previous)) => plan.edited.push((item, previous)),
+            (false, false, None) => plan.conflicts.push(item),
+        }
+    }
+
+    let span = Span::current();
+    span.record("pending", plan.pending.len());
+    span.record("added", plan.added.len());
+    span.record("edited", plan.edited.len());
+    span.record("conflicts", plan.conflicts.len());
+
+    Ok(plan)
+}
+
+#[instrument(name = "Mark import batch rolled back", skip(db))]
+pub fn mark_rolled_back(db: &impl AdministratorAccessDb, batch_id: u64) -> Result<(), DbError> {
+    const UPDATE: &str = "UPDATE import_batches SET rolled_back = ?2 WHERE batch_id = ?1;";
+
+    db.get()?
+        .prepare_cached(UPDATE)?
+        .execute(params![batch_id, Utc::now()])?;
+    Ok(())
+}
@@ -0,0 +1,155 @@
+//! Links between existing words which were suggested automatically because their English glosses
+//! overlap, rather than by a user. Each pair of words is only ever suggested once, so a rejected
+//! suggestion isn't suggested again on the next run.
+
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::ModeratorAccessDb;
+use isixhosa_common::language::WordLinkType;
+use rusqlite::params;
+use std::collections::{HashMap, HashSet};
+use tracing::{instrument, Span};
+
+/// A word and its English gloss, as analysed for overlap
+#[derive(Clone, Debug)]
+pub struct Gloss {
+    pub word_id: u64,
+    pub english: String,
+    pub xhosa: String,
+}
+
+#[instrument(name = "Fetch all glosses", fields(results), skip(db))]
+pub fn fetch_all_glosses(db: &impl ModeratorAccessDb) -> Vec<Gloss> {
+    const SELECT: &str = "SELECT word_id, english, xhosa FROM words ORDER BY word_id;";
+
+    let conn = db.get().unwrap();
+    let glosses: Vec<Gloss> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![])
+        .unwrap()
+        .map(|row| {
+            Ok(Gloss {
+                word_id: row.get("word_id")?,
+                english: row.get("english")?,
+                xhosa: row.get("xhosa")?,
+            })
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", glosses.len());
+
+    glosses
+}
+
+/// Every pair of words which shouldn't be suggested, because they are already linked, a link
+/// between them is pending, or they were suggested before. The smaller ID is first in each pair.
+#[instrument(name = "Fetch known word pairs", fields(results), skip(db))]
+pub fn fetch_known_pairs(db: &impl ModeratorAccessDb) -> HashSet<(u64, u64)> {
+    const SELECT: &str = "
+        SELECT first_word_id AS first, second_word_id AS second FROM linked_words
+        UNION
+        SELECT first_existing_word_id, second_existing_word_id FROM linked_word_suggestions
+            WHERE first_existing_word_id IS NOT NULL AND second_existing_word_id IS NOT NULL
+        UNION
+        SELECT first_word_id, second_word_id FROM automatic_link_suggestions;
+    ";
+
+    let conn = db.get().unwrap();
+    let pairs: HashSet<(u64, u64)> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![])
+        .unwrap()
+        .map(|row| {
+            let (first, second): (u64, u64) = (row.get("first")?, row.get("second")?);
+            Ok((first.min(second), first.max(second)))
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", pairs.len());
+
+    pairs
+}
+
+/// Suggest linking the words as related, attributed to the given user. Returns the ID of the
+/// linked word suggestion.
+#[instrument(name = "Suggest automatic link", skip(db))]
+pub fn suggest_link(
+    db: &impl ModeratorAccessDb,
+    user_id: u64,
+    first_word_id: u64,
+    second_word_id: u64,
+    overlap: f64,
+) -> u64 {
+    const INSERT_SUGGESTION: &str = "
+        INSERT INTO linked_word_suggestions (
+            suggesting_user, changes_summary, link_type, first_existing_word_id,
+            second_existing_word_id
+        ) VALUES (?1, ?2, ?3, ?4, ?5)
+            RETURNING suggestion_id;
+    ";
+    const INSERT_AUTOMATIC: &str = "
+        INSERT INTO automatic_link_suggestions (
+            first_word_id, second_word_id, suggestion_id, overlap
+        ) VALUES (?1, ?2, ?3, ?4);
+    ";
+
+    let conn = db.get().unwrap();
+    let changes_summary = format!(
+        "Suggested automatically, since the English glosses overlap by {:.0}%",
+        overlap * 100.0
+    );
+
+    let suggestion_id: u64 = conn
+        .prepare_cached(INSERT_SUGGESTION)
+        .unwrap()
+        .query_row(
+            params![
+                user_id,
+                changes_summary,
+                WordLinkType::Related,
+                first_word_id,
+                second_word_id,
+            ],
+            |row| row.get("suggestion_id"),
+        )
+        .unwrap();
+
+    conn.prepare_cached(INSERT_AUTOMATIC)
+        .unwrap()
+        .execute(params![
+            first_word_id,
+            second_word_id,
+            suggestion_id,
+            overlap
+        ])
+        .unwrap();
+
+    suggestion_id
+}
+
+/// How much the glosses overlapped for each pending automatically suggested link, by linked word
+/// suggestion ID
+#[instrument(name = "Fetch pending automatic links", fields(results), skip(db))]
+pub fn fetch_pending(db: &impl ModeratorAccessDb) -> HashMap<u64, f64> {
+    const SELECT: &str = "
+        SELECT suggestion_id, overlap FROM automatic_link_suggestions
+            WHERE suggestion_id IS NOT NULL;
+    ";
+
+    let conn = db.get().unwrap();
+    let pending: HashMap<u64, f64> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![])
+        .unwrap()
+        .map(|row| Ok((row.get("suggestion_id")?, row.get("overlap")?)))
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", pending.len());
+
+    pending
+}
@@ -0,0 +1,45 @@
+//! A log of which moderator accepted or rejected suggestions, for the per-user stats on the admin
+//! panel. Only the outcome is kept, not what the suggestion was, along with the checklist which a
+//! trainee moderator ticked when accepting.
+
+use crate::config::ChecklistItem;
+use chrono::Utc;
+use isixhosa_common::database::ModeratorAccessDb;
+use rusqlite::params;
+use tracing::instrument;
+
+/// Record the outcome of a moderation action, returning the ID of the action
+#[instrument(level = "trace", name = "Record moderation action", skip(db))]
+pub fn record(db: &impl ModeratorAccessDb, moderator_id: u64, accepted: bool) -> u64 {
+    const INSERT: &str = "
+        INSERT INTO moderation_actions (moderator_id, accepted, time) VALUES (?1, ?2, ?3)
+            RETURNING action_id;
+    ";
+
+    db.get()
+        .unwrap()
+        .prepare_cached(INSERT)
+        .unwrap()
+        .query_row(params![moderator_id, accepted, Utc::now()], |row| {
+            row.get("action_id")
+        })
+        .unwrap()
+}
+
+#[instrument(level = "trace", name = "Record moderation checklist", skip(db))]
+pub fn record_checklist(db: &impl ModeratorAccessDb, action_id: u64, items: &[ChecklistItem]) {
+    const INSERT: &str = "INSERT INTO moderation_checklists (action_id, items) VALUES (?1, ?2);";
+
+    let items = items
+        .iter()
+        .map(ChecklistItem::name)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    db.get()
+        .unwrap()
+        .prepare_cached(INSERT)
+        .unwrap()
+        .execute(params![action_id, items])
+        .unwrap();
+}
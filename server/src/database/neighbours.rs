@@ -0,0 +1,54 @@
+//! The words just before and after a word in alphabetical order of their isiXhosa, so that the
+//! dictionary can be browsed page by page like a paper one. Entries spelled the same are ordered
+//! by ID.
+
+use isixhosa_common::database::{PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::templates::AlphabeticalNeighbours;
+use isixhosa_common::types::WordHit;
+use rusqlite::{params, OptionalExtension};
+use tracing::instrument;
+
+#[instrument(name = "Fetch alphabetical neighbours", skip(db))]
+pub fn fetch_neighbours(
+    db: &impl PublicAccessDb,
+    word_id: u64,
+    xhosa: &str,
+    include_offensive: bool,
+) -> AlphabeticalNeighbours {
+    // Both use the words_alphabetical index
+    const SELECT_PREVIOUS: &str = "
+        SELECT
+            word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM words
+        WHERE (xhosa COLLATE NOCASE, word_id) < (?1, ?2) AND (?3 OR NOT is_offensive)
+        ORDER BY xhosa COLLATE NOCASE DESC, word_id DESC
+        LIMIT 1;
+    ";
+    const SELECT_NEXT: &str = "
+        SELECT
+            word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM words
+        WHERE (xhosa COLLATE NOCASE, word_id) > (?1, ?2) AND (?3 OR NOT is_offensive)
+        ORDER BY xhosa COLLATE NOCASE, word_id
+        LIMIT 1;
+    ";
+
+    let conn = db.get().unwrap();
+    let fetch = |query: &str| {
+        conn.prepare_cached(query)
+            .unwrap()
+            .query_row(params![xhosa, word_id, include_offensive], |row| {
+                let id = row.get::<&str, i64>("word_id")? as u64;
+                WordHit::try_from_row_and_id(row, WordOrSuggestionId::existing(id))
+            })
+            .optional()
+            .unwrap()
+    };
+
+    AlphabeticalNeighbours {
+        previous: fetch(SELECT_PREVIOUS),
+        next: fetch(SELECT_NEXT),
+    }
+}
@@ -0,0 +1,158 @@
+//! Notifications shown to users in their inbox on the site, e.g when a moderator has reviewed one
+//! of their suggestions.
+
+use chrono::{DateTime, Utc};
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{ModeratorAccessDb, UserAccessDb};
+use rusqlite::{params, Row};
+use tracing::{instrument, Span};
+
+#[derive(Debug)]
+pub struct Notification {
+    pub notification_id: u64,
+    pub kind: NotificationKind,
+    pub english: String,
+    pub xhosa: String,
+    /// The word is `None` if it has since been deleted
+    pub word_id: Option<u64>,
+    pub created: DateTime<Utc>,
+    pub read: bool,
+}
+
+#[derive(Debug)]
+pub enum NotificationKind {
+    SuggestionAccepted,
+    SuggestionRejected { reason: Option<String> },
+}
+
+impl TryFrom<&Row<'_>> for Notification {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        let kind = match row.get::<&str, u8>("kind")? {
+            1 => NotificationKind::SuggestionAccepted,
+            _ => NotificationKind::SuggestionRejected {
+                reason: row.get("reason")?,
+            },
+        };
+
+        Ok(Notification {
+            notification_id: row.get("notification_id")?,
+            kind,
+            english: row.get("english")?,
+            xhosa: row.get("xhosa")?,
+            word_id: row.get("word_id")?,
+            created: row.get("created")?,
+            read: row.get("read")?,
+        })
+    }
+}
+
+impl Notification {
+    #[instrument(name = "Notify suggestion accepted", skip(db, english, xhosa))]
+    pub fn suggestion_accepted(
+        db: &impl ModeratorAccessDb,
+        user_id: u64,
+        word_id: u64,
+        english: &str,
+        xhosa: &str,
+    ) {
+        const INSERT: &str = "
+            INSERT INTO notifications (user_id, kind, english, xhosa, word_id, created)
+                VALUES (?1, 1, ?2, ?3, ?4, ?5);
+        ";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(INSERT)
+            .unwrap()
+            .execute(params![user_id, english, xhosa, word_id, Utc::now()])
+            .unwrap();
+    }
+
+    /// Must be called before the suggestion is deleted. Suggested edits only store the fields
+    /// which were changed, so the rest are taken from the word being edited.
+    #[instrument(name = "Notify suggestion rejected", skip(db))]
+    pub fn suggestion_rejected(
+        db: &impl ModeratorAccessDb,
+        suggestion_id: u64,
+        reason: Option<&str>,
+    ) {
+        const INSERT: &str = "
+            INSERT INTO notifications (user_id, kind, english, xhosa, word_id, reason, created)
+                SELECT
+                    word_suggestions.suggesting_user, 2,
+                    COALESCE(word_suggestions.english, words.english, ''),
+                    COALESCE(word_suggestions.xhosa, words.xhosa, ''),
+                    word_suggestions.existing_word_id, ?2, ?3
+                FROM word_suggestions
+                LEFT JOIN words ON word_suggestions.existing_word_id = words.word_id
+                WHERE word_suggestions.suggestion_id = ?1;
+        ";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(INSERT)
+            .unwrap()
+            .execute(params![suggestion_id, reason, Utc::now()])
+            .unwrap();
+    }
+
+    #[instrument(name = "Fetch notifications", fields(results), skip(db))]
+    pub fn fetch_recent(db: &impl UserAccessDb, user_id: u64, limit: u64) -> Vec<Notification> {
+        const SELECT: &str = "
+            SELECT notification_id, kind, english, xhosa, word_id, reason, created, read
+                FROM notifications
+                WHERE user_id = ?1
+                ORDER BY created DESC
+                LIMIT ?2;
+        ";
+
+        #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+        let notifications: Vec<Notification> = db
+            .get()
+            .unwrap()
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query(params![user_id, limit])
+            .unwrap()
+            .map(|row| Notification::try_from(row))
+            .collect()
+            .unwrap();
+
+        Span::current().record("results", notifications.len());
+
+        notifications
+    }
+
+    #[instrument(name = "Mark notification read", fields(found), skip(db))]
+    pub fn mark_read(db: &impl UserAccessDb, user_id: u64, notification_id: u64) -> bool {
+        const UPDATE: &str =
+            "UPDATE notifications SET read = TRUE WHERE notification_id = ?1 AND user_id = ?2;";
+
+        let found = db
+            .get()
+            .unwrap()
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![notification_id, user_id])
+            .unwrap()
+            == 1;
+
+        Span::current().record("found", found);
+
+        found
+    }
+
+    #[instrument(name = "Mark all notifications read", skip(db))]
+    pub fn mark_all_read(db: &impl UserAccessDb, user_id: u64) {
+        const UPDATE: &str = "UPDATE notifications SET read = TRUE WHERE user_id = ?1;";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![user_id])
+            .unwrap();
+    }
+}
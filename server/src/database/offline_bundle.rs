@@ -0,0 +1,125 @@
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::types::WordHit;
+use rusqlite::{params, params_from_iter, Row};
+use tracing::{instrument, Span};
+
+/// What the words in the dictionary looked like at a point in time. Any addition, edit, or deletion
+/// changes at least one of these, so bundles built from equal states hold the same words.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WordsState {
+    pub last_revision: u64,
+    pub word_count: u64,
+    pub last_word_id: u64,
+}
+
+fn hit_from_row(row: &Row<'_>) -> Result<WordHit, rusqlite::Error> {
+    let id = row.get::<&str, i64>("word_id")? as u64;
+    WordHit::try_from_row_and_id(row, WordOrSuggestionId::existing(id))
+}
+
+#[instrument(name = "Fetch words state", skip(db))]
+pub fn fetch_words_state(db: &impl PublicAccessDb) -> WordsState {
+    const SELECT: &str = "
+        SELECT
+            (SELECT COALESCE(MAX(revision_id), 0) FROM word_revisions) AS last_revision,
+            COUNT(*) AS word_count,
+            COALESCE(MAX(word_id), 0) AS last_word_id
+        FROM words;
+    ";
+
+    db.get()
+        .unwrap()
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query_row(params![], |row| {
+            Ok(WordsState {
+                last_revision: row.get("last_revision")?,
+                word_count: row.get("word_count")?,
+                last_word_id: row.get("last_word_id")?,
+            })
+        })
+        .unwrap()
+}
+
+/// The most viewed words of all time, most viewed first
+#[instrument(name = "Fetch most viewed words", fields(results), skip(db))]
+pub fn fetch_most_viewed(
+    db: &impl PublicAccessDb,
+    limit: usize,
+    include_offensive: bool,
+) -> Vec<WordHit> {
+    const SELECT: &str = "
+        SELECT
+            words.word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM word_views
+        INNER JOIN words ON words.word_id = word_views.word_id
+        WHERE ?2 OR NOT is_offensive
+        GROUP BY words.word_id
+        ORDER BY SUM(views) DESC, words.word_id
+        LIMIT ?1;
+    ";
+
+    let conn = db.get().unwrap();
+    let mut query = conn.prepare_cached(SELECT).unwrap();
+
+    let words: Vec<WordHit> = query
+        .query(params![limit, include_offensive])
+        .unwrap()
+        .map(hit_from_row)
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", words.len());
+
+    words
+}
+
+/// Every word sourced from any of the given datasets
+#[instrument(name = "Fetch words in datasets", fields(results), skip(db))]
+pub fn fetch_in_datasets(
+    db: &impl PublicAccessDb,
+    datasets: &[u64],
+    include_offensive: bool,
+) -> Vec<WordHit> {
+    if datasets.is_empty() {
+        return Vec::new();
+    }
+
+    let placeholders = (1..=datasets.len())
+        .map(|i| format!("?{i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let select = format!(
+        "
+        SELECT DISTINCT
+            words.word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM dataset_attributions
+        INNER JOIN words ON words.word_id = dataset_attributions.word_id
+        WHERE dataset_id IN ({placeholders}) AND (?{include_offensive} OR NOT is_offensive)
+        ORDER BY words.word_id;
+        ",
+        include_offensive = datasets.len() + 1,
+    );
+
+    let conn = db.get().unwrap();
+    let mut query = conn.prepare(&select).unwrap();
+
+    let words: Vec<WordHit> = query
+        .query(params_from_iter(
+            datasets
+                .iter()
+                .map(|id| *id as i64)
+                .chain([include_offensive as i64]),
+        ))
+        .unwrap()
+        .map(hit_from_row)
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", words.len());
+
+    words
+}
@@ -0,0 +1,131 @@
+//! How long suggested words have been waiting for a moderator
+
+use chrono::{DateTime, Utc};
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{ModeratorAccessDb, UserAccessDb};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use tracing::{instrument, Span};
+
+/// Record when the suggestion was submitted, unless it was submitted before
+#[instrument(level = "trace", name = "Record suggestion submission time", skip(db))]
+pub fn record_submitted(db: &impl UserAccessDb, suggestion_id: u64) {
+    const INSERT: &str = "
+        INSERT INTO word_suggestion_times (suggestion_id, submitted) VALUES (?1, ?2)
+            ON CONFLICT(suggestion_id) DO NOTHING;
+    ";
+
+    let conn = db.get().unwrap();
+    conn.prepare_cached(INSERT)
+        .unwrap()
+        .execute(params![suggestion_id, Utc::now()])
+        .unwrap();
+}
+
+/// When each pending suggested word was submitted, keyed by suggestion
+#[instrument(name = "Fetch suggestion submission times", fields(results), skip_all)]
+pub fn fetch_all_submitted(db: &impl ModeratorAccessDb) -> HashMap<u64, DateTime<Utc>> {
+    const SELECT: &str = "SELECT suggestion_id, submitted FROM word_suggestion_times;";
+
+    let conn = db.get().unwrap();
+    let times: HashMap<u64, DateTime<Utc>> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![])
+        .unwrap()
+        .map(|row| Ok((row.get("suggestion_id")?, row.get("submitted")?)))
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", times.len());
+
+    times
+}
+
+/// Suggestions from before submission times were recorded are counted as submitted now
+#[instrument(
+    name = "Add missing suggestion submission times",
+    fields(added),
+    skip_all
+)]
+pub fn add_missing_submission_times(conn: &Connection) -> rusqlite::Result<()> {
+    const INSERT: &str = "
+        INSERT INTO word_suggestion_times (suggestion_id, submitted)
+            SELECT suggestion_id, ?1 FROM word_suggestions WHERE true
+            ON CONFLICT(suggestion_id) DO NOTHING;
+    ";
+
+    let added = conn.execute(INSERT, params![Utc::now()])?;
+    Span::current().record("added", added);
+
+    Ok(())
+}
+
+/// What is waiting in the moderation queue, so that open moderation pages can tell when it has
+/// changed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueueState {
+    /// Suggested words in the main queue, i.e not flagged as likely spam, in ascending order
+    pub suggested_words: Vec<u64>,
+    /// The number of suggestions of any kind in the main queue
+    pub pending: u64,
+}
+
+impl QueueState {
+    /// The newest suggested word in the main queue, or zero if it's empty
+    pub fn newest(&self) -> u64 {
+        self.suggested_words.last().copied().unwrap_or(0)
+    }
+
+    /// The number of suggested words submitted after the given one. Suggestions are numbered in
+    /// order of submission.
+    pub fn new_since(&self, since: u64) -> usize {
+        self.suggested_words
+            .iter()
+            .filter(|id| **id > since)
+            .count()
+    }
+}
+
+#[instrument(name = "Fetch moderation queue state", fields(pending), skip_all)]
+pub fn fetch_queue_state(db: &impl ModeratorAccessDb) -> QueueState {
+    const SELECT_WORDS: &str = "
+        SELECT suggestion_id FROM word_suggestions
+            WHERE suggestion_id NOT IN (SELECT suggestion_id FROM likely_spam_suggestions)
+            ORDER BY suggestion_id;
+    ";
+
+    const COUNT_OTHERS: &str = "
+        SELECT
+            (SELECT COUNT(*) FROM word_deletion_suggestions)
+            + (SELECT COUNT(*) FROM example_suggestions)
+            + (SELECT COUNT(*) FROM example_deletion_suggestions)
+            + (SELECT COUNT(*) FROM linked_word_suggestions)
+            + (SELECT COUNT(*) FROM linked_word_deletion_suggestions)
+            + (SELECT COUNT(*) FROM dataset_attribution_suggestions);
+    ";
+
+    let conn = db.get().unwrap();
+    let suggested_words: Vec<u64> = conn
+        .prepare_cached(SELECT_WORDS)
+        .unwrap()
+        .query(params![])
+        .unwrap()
+        .map(|row| row.get("suggestion_id"))
+        .collect()
+        .unwrap();
+
+    let others: u64 = conn
+        .prepare_cached(COUNT_OTHERS)
+        .unwrap()
+        .query_row(params![], |row| row.get(0))
+        .unwrap();
+
+    let pending = suggested_words.len() as u64 + others;
+    Span::current().record("pending", pending);
+
+    QueueState {
+        suggested_words,
+        pending,
+    }
+}
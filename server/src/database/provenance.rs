@@ -0,0 +1,75 @@
+//! How a suggestion arrived on the site, so that moderators can treat suggestions from bulk imports
+//! differently to those typed in by hand, and trace bad suggestions back to the batch they came in.
+
+use isixhosa_common::i18n::{ToTranslationKey, TranslationKey};
+use rusqlite::Row;
+use std::borrow::Cow;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Provenance {
+    WebForm,
+    /// Uploaded by an administrator as part of a CSV file
+    Import {
+        batch_id: u64,
+    },
+    /// Submitted by an API client. The key is forgotten if it is later deleted.
+    Api {
+        key_id: Option<u64>,
+    },
+    Anonymous,
+}
+
+impl Provenance {
+    /// Reads the `source`, `import_batch`, and `api_key_id` columns of a suggestion
+    pub fn from_row(row: &Row<'_>) -> rusqlite::Result<Provenance> {
+        Ok(match row.get::<&str, u8>("source")? {
+            1 => Provenance::Import {
+                batch_id: row.get("import_batch")?,
+            },
+            2 => Provenance::Api {
+                key_id: row.get("api_key_id")?,
+            },
+            3 => Provenance::Anonymous,
+            _ => Provenance::WebForm,
+        })
+    }
+
+    /// The value of the `source` column
+    pub fn source(&self) -> u8 {
+        match self {
+            Provenance::WebForm => 0,
+            Provenance::Import { .. } => 1,
+            Provenance::Api { .. } => 2,
+            Provenance::Anonymous => 3,
+        }
+    }
+
+    pub fn import_batch(&self) -> Option<u64> {
+        match self {
+            Provenance::Import { batch_id } => Some(*batch_id),
+            _ => None,
+        }
+    }
+
+    pub fn api_key_id(&self) -> Option<u64> {
+        match self {
+            Provenance::Api { key_id } => *key_id,
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Provenance::WebForm => "web-form",
+            Provenance::Import { .. } => "import",
+            Provenance::Api { .. } => "api",
+            Provenance::Anonymous => "anonymous",
+        }
+    }
+}
+
+impl ToTranslationKey for Provenance {
+    fn translation_key(&self) -> TranslationKey<'_> {
+        TranslationKey(Cow::Owned(format!("provenance.{}", self.name())))
+    }
+}
@@ -0,0 +1,134 @@
+//! Accounts on the recovery OpenID Connect provider which users have linked to their account, so
+//! that they can still sign in if they lose access to their Google account.
+
+use chrono::{DateTime, Utc};
+use isixhosa_common::database::{PublicAccessDb, UserAccessDb};
+use openid::Token;
+use rusqlite::{params, OptionalExtension, Row};
+use tracing::{instrument, Span};
+
+#[derive(Clone, Debug)]
+pub struct RecoveryIdentity {
+    /// The email of the linked account, if the provider shared it
+    pub email: Option<String>,
+    pub linked: DateTime<Utc>,
+}
+
+impl TryFrom<&Row<'_>> for RecoveryIdentity {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(RecoveryIdentity {
+            email: row.get("email")?,
+            linked: row.get("linked")?,
+        })
+    }
+}
+
+impl RecoveryIdentity {
+    /// Fetch the identity linked to the user's account from the given issuer, if there is one
+    #[instrument(
+        level = "trace",
+        name = "Fetch recovery identity",
+        fields(found),
+        skip(db)
+    )]
+    pub fn fetch_for_user(
+        db: &impl UserAccessDb,
+        user_id: u64,
+        issuer: &str,
+    ) -> Option<RecoveryIdentity> {
+        const SELECT: &str = "
+            SELECT email, linked FROM recovery_identities WHERE user_id = ?1 AND issuer = ?2;
+        ";
+
+        #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+        let identity = db
+            .get()
+            .unwrap()
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![user_id, issuer], |row| {
+                RecoveryIdentity::try_from(row)
+            })
+            .optional()
+            .unwrap();
+
+        Span::current().record("found", identity.is_some());
+        identity
+    }
+
+    /// Find which user has linked the given account, if any
+    #[instrument(
+        level = "trace",
+        name = "Fetch user for recovery identity",
+        fields(found),
+        skip_all
+    )]
+    pub fn user_for(db: &impl PublicAccessDb, issuer: &str, oidc_id: &str) -> Option<u64> {
+        const SELECT: &str = "
+            SELECT user_id FROM recovery_identities WHERE issuer = ?1 AND oidc_id = ?2;
+        ";
+
+        let user_id = db
+            .get()
+            .unwrap()
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![issuer, oidc_id], |row| row.get("user_id"))
+            .optional()
+            .unwrap();
+
+        Span::current().record("found", user_id.is_some());
+        user_id
+    }
+
+    /// Link the account to the user's, replacing any account they had linked before. Returns false
+    /// if the account is already linked to someone else's.
+    #[instrument(name = "Link recovery identity", skip(db, _proof, oidc_id, email))]
+    pub fn link(
+        db: &impl PublicAccessDb,
+        _proof: Token, // Make sure this is not called from the wrong context
+        user_id: u64,
+        issuer: &str,
+        oidc_id: &str,
+        email: Option<&str>,
+    ) -> bool {
+        const UPSERT: &str = "
+            INSERT INTO recovery_identities (user_id, issuer, oidc_id, email, linked)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(user_id) DO UPDATE SET
+                    issuer = excluded.issuer,
+                    oidc_id = excluded.oidc_id,
+                    email = excluded.email,
+                    linked = excluded.linked;
+        ";
+
+        match Self::user_for(db, issuer, oidc_id) {
+            Some(other) if other != user_id => return false,
+            _ => {}
+        }
+
+        db.get()
+            .unwrap()
+            .prepare_cached(UPSERT)
+            .unwrap()
+            .execute(params![user_id, issuer, oidc_id, email, Utc::now()])
+            .unwrap();
+
+        true
+    }
+
+    #[instrument(name = "Unlink recovery identity", skip(db))]
+    pub fn unlink(db: &impl UserAccessDb, user_id: u64) -> bool {
+        const DELETE: &str = "DELETE FROM recovery_identities WHERE user_id = ?1;";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![user_id])
+            .unwrap()
+            > 0
+    }
+}
@@ -0,0 +1,249 @@
+//! Bibliographic references attesting to words, added and removed directly by moderators rather
+//! than going through suggestions. The links which references give are checked periodically, and
+//! those which stop working are marked as broken.
+
+use crate::error::DbError;
+use chrono::{DateTime, Utc};
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::ModeratorAccessDb;
+use isixhosa_common::types::ReferenceKind;
+use rusqlite::params;
+use serde::Deserialize;
+use serde_with::{serde_as, NoneAsEmptyString};
+use tracing::{instrument, Span};
+
+pub const MAX_REFERENCE_FIELD_LENGTH: usize = 512;
+
+#[serde_as]
+#[derive(Deserialize, Clone, Debug)]
+pub struct NewReference {
+    pub kind: ReferenceKind,
+    pub author: String,
+    pub title: String,
+    #[serde_as(as = "NoneAsEmptyString")]
+    pub year: Option<u32>,
+    #[serde_as(as = "NoneAsEmptyString")]
+    pub publisher: Option<String>,
+    #[serde_as(as = "NoneAsEmptyString")]
+    pub pages: Option<String>,
+    #[serde_as(as = "NoneAsEmptyString")]
+    pub url: Option<String>,
+}
+
+impl NewReference {
+    /// Returns `true` if the author and title are given and no field is too long
+    pub fn is_valid(&self) -> bool {
+        let fields = [
+            Some(&self.author),
+            Some(&self.title),
+            self.publisher.as_ref(),
+            self.pages.as_ref(),
+            self.url.as_ref(),
+        ];
+
+        !self.author.trim().is_empty()
+            && !self.title.trim().is_empty()
+            && fields
+                .into_iter()
+                .flatten()
+                .all(|field| field.chars().count() <= MAX_REFERENCE_FIELD_LENGTH)
+    }
+}
+
+#[instrument(name = "Add reference", skip(db))]
+pub fn add_reference(
+    db: &impl ModeratorAccessDb,
+    word_id: u64,
+    reference: NewReference,
+) -> Result<u64, DbError> {
+    const INSERT: &str = "
+        INSERT INTO word_references (word_id, kind, author, title, year, publisher, pages, url)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            RETURNING reference_id;
+    ";
+
+    let trim = |s: Option<String>| s.map(|s| s.trim().to_owned());
+
+    let reference_id = db.get()?.prepare_cached(INSERT)?.query_row(
+        params![
+            word_id,
+            reference.kind,
+            reference.author.trim(),
+            reference.title.trim(),
+            reference.year,
+            trim(reference.publisher),
+            trim(reference.pages),
+            trim(reference.url),
+        ],
+        |row| row.get("reference_id"),
+    )?;
+
+    Ok(reference_id)
+}
+
+#[instrument(name = "Delete reference", fields(found), skip(db))]
+pub fn delete_reference(
+    db: &impl ModeratorAccessDb,
+    word_id: u64,
+    reference_id: u64,
+) -> Result<bool, DbError> {
+    const DELETE: &str = "DELETE FROM word_references WHERE word_id = ?1 AND reference_id = ?2;";
+
+    let found = db
+        .get()?
+        .prepare_cached(DELETE)?
+        .execute(params![word_id, reference_id])?
+        == 1;
+
+    Span::current().record("found", found);
+    Ok(found)
+}
+
+/// A reference's link which is due to be checked
+#[derive(Debug)]
+pub struct LinkToCheck {
+    pub reference_id: u64,
+    pub url: String,
+}
+
+/// Links which haven't been checked since the given time, those checked longest ago first. Only
+/// web links are checked.
+#[instrument(name = "Fetch links to check", fields(results), skip(db))]
+pub fn fetch_links_to_check(
+    db: &impl ModeratorAccessDb,
+    checked_before: DateTime<Utc>,
+    limit: usize,
+) -> Result<Vec<LinkToCheck>, DbError> {
+    const SELECT: &str = "
+        SELECT reference_id, url FROM word_references
+        WHERE (url LIKE 'http://%' OR url LIKE 'https://%')
+            AND (link_checked IS NULL OR link_checked < ?1)
+        ORDER BY link_checked, reference_id
+        LIMIT ?2;
+    ";
+
+    let conn = db.get()?;
+    let links: Vec<LinkToCheck> = conn
+        .prepare_cached(SELECT)?
+        .query(params![checked_before, limit])?
+        .map(|row| {
+            Ok(LinkToCheck {
+                reference_id: row.get("reference_id")?,
+                url: row.get("url")?,
+            })
+        })
+        .collect()?;
+
+    Span::current().record("results", links.len());
+    Ok(links)
+}
+
+/// Record whether a link worked, along with why it didn't if not. A link is only marked as broken
+/// once it has failed the given number of checks in a row, so that a site being down for a moment
+/// doesn't mark it.
+#[instrument(name = "Record link check", skip(db))]
+pub fn record_link_check(
+    db: &impl ModeratorAccessDb,
+    reference_id: u64,
+    error: Option<&str>,
+    failures_before_broken: u64,
+) -> Result<(), DbError> {
+    const WORKING: &str = "
+        UPDATE word_references
+            SET link_checked = ?2, link_failures = 0, link_broken_since = NULL, link_error = NULL
+            WHERE reference_id = ?1;
+    ";
+    const FAILED: &str = "
+        UPDATE word_references
+            SET
+                link_checked = ?2,
+                link_failures = link_failures + 1,
+                link_error = ?3,
+                link_broken_since = CASE
+                    WHEN link_failures + 1 >= ?4 THEN IFNULL(link_broken_since, ?2)
+                    ELSE link_broken_since
+                END
+            WHERE reference_id = ?1;
+    ";
+
+    let conn = db.get()?;
+    let now = Utc::now();
+
+    match error {
+        None => conn
+            .prepare_cached(WORKING)?
+            .execute(params![reference_id, now])?,
+        Some(error) => conn.prepare_cached(FAILED)?.execute(params![
+            reference_id,
+            now,
+            error,
+            failures_before_broken
+        ])?,
+    };
+
+    Ok(())
+}
+
+/// A reference whose link is marked as broken, as listed for moderators
+#[derive(Debug)]
+pub struct BrokenLink {
+    pub reference_id: u64,
+    pub word_id: u64,
+    pub english: String,
+    pub xhosa: String,
+    pub title: String,
+    pub url: String,
+    pub broken_since: DateTime<Utc>,
+    /// Why the last check failed
+    pub error: Option<String>,
+}
+
+/// All references with broken links, those broken the longest first
+#[instrument(name = "Fetch broken links", fields(results), skip(db))]
+pub fn fetch_broken_links(db: &impl ModeratorAccessDb) -> Vec<BrokenLink> {
+    const SELECT: &str = "
+        SELECT
+            reference_id, word_references.word_id, english, xhosa, title, url, link_broken_since,
+            link_error
+        FROM word_references
+        INNER JOIN words ON words.word_id = word_references.word_id
+        WHERE link_broken_since IS NOT NULL
+        ORDER BY link_broken_since, reference_id;
+    ";
+
+    let conn = db.get().unwrap();
+    let links: Vec<BrokenLink> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![])
+        .unwrap()
+        .map(|row| {
+            Ok(BrokenLink {
+                reference_id: row.get("reference_id")?,
+                word_id: row.get("word_id")?,
+                english: row.get("english")?,
+                xhosa: row.get("xhosa")?,
+                title: row.get("title")?,
+                url: row.get("url")?,
+                broken_since: row.get("link_broken_since")?,
+                error: row.get("link_error")?,
+            })
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", links.len());
+    links
+}
+
+#[instrument(name = "Count broken links", skip(db))]
+pub fn count_broken_links(db: &impl ModeratorAccessDb) -> u64 {
+    const COUNT: &str = "SELECT COUNT(1) FROM word_references WHERE link_broken_since IS NOT NULL;";
+
+    db.get()
+        .unwrap()
+        .prepare_cached(COUNT)
+        .unwrap()
+        .query_row(params![], |row| row.get(0))
+        .unwrap()
+}
@@ -0,0 +1,288 @@
+//! When words were added or edited. Revisions of a word are deleted along with it.
+
+use chrono::{DateTime, Utc};
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{ModeratorAccessDb, PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::i18n::{ToTranslationKey, TranslationKey};
+use isixhosa_common::types::WordHit;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use tracing::{instrument, Span};
+
+/// A word being added or edited, as shown in the public changelog
+#[derive(Clone, Debug)]
+pub struct Revision {
+    pub word_id: u64,
+    pub english: String,
+    pub xhosa: String,
+    pub added: bool,
+    pub time: DateTime<Utc>,
+    /// The user whose suggestion was accepted, if they have chosen to be credited publicly
+    pub author: Option<String>,
+}
+
+impl TryFrom<&Row<'_>> for Revision {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Revision {
+            word_id: row.get("word_id")?,
+            english: row.get("english")?,
+            xhosa: row.get("xhosa")?,
+            added: row.get("added")?,
+            time: row.get("time")?,
+            author: row.get("author")?,
+        })
+    }
+}
+
+/// Which kinds of revisions to show in the changelog
+#[derive(Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionFilter {
+    #[default]
+    All,
+    Added,
+    Edited,
+}
+
+impl RevisionFilter {
+    pub const ALL: [RevisionFilter; 3] = [
+        RevisionFilter::All,
+        RevisionFilter::Added,
+        RevisionFilter::Edited,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RevisionFilter::All => "all",
+            RevisionFilter::Added => "added",
+            RevisionFilter::Edited => "edited",
+        }
+    }
+
+    fn added(&self) -> Option<bool> {
+        match self {
+            RevisionFilter::All => None,
+            RevisionFilter::Added => Some(true),
+            RevisionFilter::Edited => Some(false),
+        }
+    }
+}
+
+impl ToTranslationKey for RevisionFilter {
+    fn translation_key(&self) -> TranslationKey<'_> {
+        TranslationKey(Cow::Owned(format!("changes.filter-{}", self.name())))
+    }
+}
+
+/// A word's own fields as they were stored before it was edited. These are kept for edits from
+/// import batches, so that the edit can be undone if the batch is rolled back.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WordSnapshot {
+    english: String,
+    xhosa: String,
+    part_of_speech: Option<i64>,
+    xhosa_tone_markings: String,
+    infinitive: String,
+    is_plural: bool,
+    is_inchoative: bool,
+    is_informal: bool,
+    transitivity: Option<i64>,
+    followed_by: String,
+    noun_class: Option<i64>,
+    note: String,
+    is_offensive: bool,
+}
+
+impl WordSnapshot {
+    #[instrument(level = "trace", name = "Fetch word snapshot", fields(found), skip(db))]
+    pub fn fetch(db: &impl ModeratorAccessDb, word_id: u64) -> Option<WordSnapshot> {
+        const SELECT: &str = "
+            SELECT
+                english, xhosa, part_of_speech, xhosa_tone_markings, infinitive, is_plural,
+                is_inchoative, is_informal, transitivity, followed_by, noun_class, note, is_offensive
+            FROM words
+            WHERE word_id = ?1;
+        ";
+
+        let snapshot = db
+            .get()
+            .unwrap()
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![word_id], |row| {
+                Ok(WordSnapshot {
+                    english: row.get("english")?,
+                    xhosa: row.get("xhosa")?,
+                    part_of_speech: row.get("part_of_speech")?,
+                    xhosa_tone_markings: row.get("xhosa_tone_markings")?,
+                    infinitive: row.get("infinitive")?,
+                    is_plural: row.get("is_plural")?,
+                    is_inchoative: row.get("is_inchoative")?,
+                    is_informal: row.get("is_informal")?,
+                    transitivity: row.get("transitivity")?,
+                    followed_by: row.get("followed_by")?,
+                    noun_class: row.get("noun_class")?,
+                    note: row.get("note")?,
+                    is_offensive: row.get("is_offensive")?,
+                })
+            })
+            .optional()
+            .unwrap();
+
+        Span::current().record("found", snapshot.is_some());
+        snapshot
+    }
+
+    /// Put the word back as it was when the snapshot was taken
+    #[instrument(level = "trace", name = "Restore word snapshot", skip(self, db))]
+    pub fn restore(&self, db: &impl ModeratorAccessDb, word_id: u64) {
+        const UPDATE: &str = "
+            UPDATE words SET
+                english = ?2, xhosa = ?3, part_of_speech = ?4, xhosa_tone_markings = ?5,
+                infinitive = ?6, is_plural = ?7, is_inchoative = ?8, is_informal = ?9,
+                transitivity = ?10, followed_by = ?11, noun_class = ?12, note = ?13,
+                is_offensive = ?14
+            WHERE word_id = ?1;
+        ";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![
+                word_id,
+                self.english,
+                self.xhosa,
+                self.part_of_speech,
+                self.xhosa_tone_markings,
+                self.infinitive,
+                self.is_plural,
+                self.is_inchoative,
+                self.is_informal,
+                self.transitivity,
+                self.followed_by,
+                self.noun_class,
+                self.note,
+                self.is_offensive,
+            ])
+            .unwrap();
+    }
+
+    pub fn xhosa(&self) -> &str {
+        &self.xhosa
+    }
+}
+
+/// Record that a word was added or edited, i.e that a suggestion for it was accepted, along with the
+/// import batch which the suggestion came from and, for edits from a batch, the word as it was
+/// before
+#[instrument(level = "trace", name = "Record word revision", skip(db, previous))]
+pub fn record_revision(
+    db: &impl ModeratorAccessDb,
+    word_id: u64,
+    user_id: u64,
+    added: bool,
+    import_batch: Option<u64>,
+    previous: Option<&WordSnapshot>,
+) {
+    const INSERT: &str = "
+        INSERT INTO word_revisions (word_id, user_id, added, time, import_batch, previous)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6);
+    ";
+
+    let previous = previous.map(|previous| serde_json::to_string(previous).unwrap());
+
+    db.get()
+        .unwrap()
+        .prepare_cached(INSERT)
+        .unwrap()
+        .execute(params![
+            word_id,
+            user_id,
+            added,
+            Utc::now(),
+            import_batch,
+            previous
+        ])
+        .unwrap();
+}
+
+/// Fetch one page of the changelog, newest first. One more revision than `page_size` is fetched
+/// so that the caller can tell whether there is a next page.
+#[instrument(name = "Fetch revisions", fields(results), skip(db))]
+pub fn fetch_revisions(
+    db: &impl PublicAccessDb,
+    filter: RevisionFilter,
+    page: u64,
+    page_size: u64,
+) -> Vec<Revision> {
+    const SELECT: &str = "
+        SELECT
+            word_revisions.word_id, words.english, words.xhosa, word_revisions.added,
+            word_revisions.time,
+            CASE WHEN users.display_name THEN users.username END AS author
+        FROM word_revisions
+        INNER JOIN words ON words.word_id = word_revisions.word_id
+        LEFT JOIN users ON users.user_id = word_revisions.user_id
+        WHERE ?1 IS NULL OR word_revisions.added = ?1
+        ORDER BY word_revisions.time DESC, word_revisions.revision_id DESC
+        LIMIT ?2 OFFSET ?3;
+    ";
+
+    let conn = db.get().unwrap();
+    #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+    let revisions: Vec<Revision> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![
+            filter.added(),
+            page_size + 1,
+            page.saturating_mul(page_size)
+        ])
+        .unwrap()
+        .map(|row| Revision::try_from(row))
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", revisions.len());
+
+    revisions
+}
+
+#[instrument(name = "Fetch recently added words", fields(results), skip(db))]
+pub fn fetch_recently_added(
+    db: &impl PublicAccessDb,
+    limit: u64,
+    include_offensive: bool,
+) -> Vec<WordHit> {
+    const SELECT: &str = "
+        SELECT
+            words.word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM word_revisions
+        INNER JOIN words ON words.word_id = word_revisions.word_id
+        WHERE added = 1 AND (?2 OR NOT is_offensive)
+        ORDER BY time DESC
+        LIMIT ?1;
+    ";
+
+    let conn = db.get().unwrap();
+    let words: Vec<WordHit> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![limit, include_offensive])
+        .unwrap()
+        .map(|row| {
+            let id = row.get::<&str, i64>("word_id")? as u64;
+            WordHit::try_from_row_and_id(row, WordOrSuggestionId::existing(id))
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", words.len());
+
+    words
+}
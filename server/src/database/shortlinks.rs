@@ -0,0 +1,61 @@
+//! Short codes for searches, so that a search with its filters can be shared as e.g `/s/x7Kq2mPa`.
+//! Each search only ever gets one code, so sharing the same search twice gives the same link.
+
+use crate::error::DbError;
+use chrono::Utc;
+use isixhosa_common::database::{PublicAccessDb, UserAccessDb};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rusqlite::{params, OptionalExtension};
+use tracing::{instrument, Span};
+
+const SHORT_LEN: usize = 8;
+
+/// The search page's query string for the code, if there is one
+#[instrument(name = "Fetch search shortlink", fields(found), skip(db))]
+pub fn fetch_query(db: &impl PublicAccessDb, short: &str) -> Result<Option<String>, DbError> {
+    const SELECT: &str = "SELECT query FROM search_shortlinks WHERE short = ?1;";
+
+    let conn = db.get()?;
+    let query = conn
+        .prepare_cached(SELECT)?
+        .query_row(params![short], |row| row.get("query"))
+        .optional()?;
+
+    Span::current().record("found", query.is_some());
+    Ok(query)
+}
+
+/// The code for the search, making one up if it doesn't have one yet
+#[instrument(name = "Shorten search", fields(short), skip(db))]
+pub fn shorten(db: &impl UserAccessDb, query: &str) -> Result<String, DbError> {
+    const INSERT: &str = "
+        INSERT OR IGNORE INTO search_shortlinks (short, query, created) VALUES (?1, ?2, ?3);
+    ";
+    const SELECT: &str = "SELECT short FROM search_shortlinks WHERE query = ?1;";
+
+    let conn = db.get()?;
+
+    // The insert is ignored if the search already has a code, or, very rarely, if the new code is
+    // taken, in which case another is tried
+    loop {
+        let short: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(SHORT_LEN)
+            .map(char::from)
+            .collect();
+
+        conn.prepare_cached(INSERT)?
+            .execute(params![short, query, Utc::now()])?;
+
+        let found: Option<String> = conn
+            .prepare_cached(SELECT)?
+            .query_row(params![query], |row| row.get("short"))
+            .optional()?;
+
+        if let Some(short) = found {
+            Span::current().record("short", short.as_str());
+            return Ok(short);
+        }
+    }
+}
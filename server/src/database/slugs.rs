@@ -0,0 +1,212 @@
+//! Human-readable names for words in their URLs, e.g `/word/umntu`. A word keeps its old slugs
+//! when its isiXhosa changes so that old links keep working, but only its newest slug is canonical.
+
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{ModeratorAccessDb, PublicAccessDb};
+use rusqlite::{params, Connection, OptionalExtension};
+use tracing::{instrument, Span};
+
+/// Slugs which would clash with other routes under `/word/`
+const RESERVED: &[&str] = &["random"];
+
+pub fn slugify(xhosa: &str) -> String {
+    let mut slug = String::with_capacity(xhosa.len());
+
+    for c in xhosa.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if c == '(' || c == ')' {
+            // Latent i verbs, e.g (i)bona
+            continue;
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        "word".to_owned()
+    } else if slug.chars().all(|c| c.is_ascii_digit()) || RESERVED.contains(&slug) {
+        // Numeric slugs would be mistaken for word IDs
+        format!("word-{slug}")
+    } else {
+        slug.to_owned()
+    }
+}
+
+/// Whether the slug was generated from the given base, possibly with a suffix to make it unique
+fn has_base(slug: &str, base: &str) -> bool {
+    match slug.strip_prefix(base) {
+        Some("") => true,
+        Some(suffix) => suffix
+            .strip_prefix('-')
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())),
+        None => false,
+    }
+}
+
+/// Make sure the word's canonical slug matches its isiXhosa, adding a new slug if it doesn't.
+/// Returns the canonical slug.
+#[instrument(name = "Update word slug", fields(slug), skip(db, xhosa))]
+pub fn update_slug(db: &impl ModeratorAccessDb, word_id: u64, xhosa: &str) -> String {
+    let slug = update_slug_with_conn(&db.get().unwrap(), word_id, xhosa);
+    Span::current().record("slug", slug.as_str());
+    slug
+}
+
+fn update_slug_with_conn(conn: &Connection, word_id: u64, xhosa: &str) -> String {
+    const SELECT_OWNER: &str = "SELECT word_id FROM word_slugs WHERE slug = ?1;";
+    const DELETE: &str = "DELETE FROM word_slugs WHERE slug = ?1;";
+    const INSERT: &str = "INSERT INTO word_slugs (slug, word_id) VALUES (?1, ?2);";
+
+    let base = slugify(xhosa);
+
+    if let Some(current) = fetch_slug_with_conn(conn, word_id) {
+        if has_base(&current, &base) {
+            return current;
+        }
+    }
+
+    for n in 1.. {
+        let candidate = match n {
+            1 => base.clone(),
+            n => format!("{base}-{n}"),
+        };
+
+        let owner: Option<u64> = conn
+            .prepare_cached(SELECT_OWNER)
+            .unwrap()
+            .query_row(params![candidate], |row| row.get("word_id"))
+            .optional()
+            .unwrap();
+
+        match owner {
+            Some(owner) if owner != word_id => continue,
+            Some(_) => {
+                // The word had this slug before, so reinsert it to make it the newest again
+                conn.prepare_cached(DELETE)
+                    .unwrap()
+                    .execute(params![candidate])
+                    .unwrap();
+            }
+            None => {}
+        }
+
+        conn.prepare_cached(INSERT)
+            .unwrap()
+            .execute(params![candidate, word_id])
+            .unwrap();
+
+        return candidate;
+    }
+
+    unreachable!()
+}
+
+/// The canonical slug which the word would have after updating it for the given isiXhosa, without
+/// saving anything. `word_id` is `None` for words which haven't been added yet.
+#[instrument(name = "Preview word slug", fields(slug), skip(db, xhosa))]
+pub fn preview_slug(db: &impl PublicAccessDb, word_id: Option<u64>, xhosa: &str) -> String {
+    const SELECT_OWNER: &str = "SELECT word_id FROM word_slugs WHERE slug = ?1;";
+
+    let conn = db.get().unwrap();
+    let base = slugify(xhosa);
+
+    let current = word_id.and_then(|id| fetch_slug_with_conn(&conn, id));
+    let slug = match current {
+        Some(current) if has_base(&current, &base) => current,
+        _ => (1..)
+            .map(|n| match n {
+                1 => base.clone(),
+                n => format!("{base}-{n}"),
+            })
+            .find(|candidate| {
+                let owner: Option<u64> = conn
+                    .prepare_cached(SELECT_OWNER)
+                    .unwrap()
+                    .query_row(params![candidate], |row| row.get("word_id"))
+                    .optional()
+                    .unwrap();
+
+                owner.is_none() || owner == word_id
+            })
+            .unwrap(),
+    };
+
+    Span::current().record("slug", slug.as_str());
+    slug
+}
+
+/// The canonical slug of the word
+#[instrument(level = "trace", name = "Fetch word slug", fields(found), skip(db))]
+pub fn fetch_slug(db: &impl PublicAccessDb, word_id: u64) -> Option<String> {
+    let slug = fetch_slug_with_conn(&db.get().unwrap(), word_id);
+    Span::current().record("found", slug.is_some());
+    slug
+}
+
+fn fetch_slug_with_conn(conn: &Connection, word_id: u64) -> Option<String> {
+    const SELECT: &str =
+        "SELECT slug FROM word_slugs WHERE word_id = ?1 ORDER BY rowid DESC LIMIT 1;";
+
+    conn.prepare_cached(SELECT)
+        .unwrap()
+        .query_row(params![word_id], |row| row.get("slug"))
+        .optional()
+        .unwrap()
+}
+
+/// The word which the slug belongs to, and the word's canonical slug
+#[instrument(name = "Fetch word for slug", fields(found), skip(db))]
+pub fn fetch_word_for_slug(db: &impl PublicAccessDb, slug: &str) -> Option<(u64, String)> {
+    const SELECT: &str = "
+        SELECT word_id, (
+            SELECT canonical.slug FROM word_slugs AS canonical
+                WHERE canonical.word_id = word_slugs.word_id
+                ORDER BY canonical.rowid DESC LIMIT 1
+        ) AS canonical
+        FROM word_slugs WHERE slug = ?1;
+    ";
+
+    let found = db
+        .get()
+        .unwrap()
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query_row(params![slug], |row| {
+            Ok((row.get("word_id")?, row.get("canonical")?))
+        })
+        .optional()
+        .unwrap();
+
+    Span::current().record("found", found.is_some());
+
+    found
+}
+
+/// Give a slug to every word which doesn't have one yet, e.g words from before slugs existed or
+/// which were imported
+#[instrument(name = "Add missing word slugs", fields(added), skip_all)]
+pub fn add_missing_slugs(conn: &Connection) -> rusqlite::Result<()> {
+    const SELECT: &str = "
+        SELECT word_id, xhosa FROM words
+            WHERE NOT EXISTS (SELECT 1 FROM word_slugs WHERE word_slugs.word_id = words.word_id);
+    ";
+
+    let missing: Vec<(u64, String)> = conn
+        .prepare(SELECT)?
+        .query(params![])?
+        .map(|row| Ok((row.get("word_id")?, row.get("xhosa")?)))
+        .collect()?;
+
+    let tx = conn.unchecked_transaction()?;
+    for (word_id, xhosa) in &missing {
+        update_slug_with_conn(&tx, *word_id, xhosa);
+    }
+    tx.commit()?;
+
+    Span::current().record("added", missing.len());
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! Heuristics for spotting spam submissions. Suggested words which look like spam are flagged so
+//! that they are shown in their own tab on the moderation page rather than in the main queue.
+
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{ModeratorAccessDb, UserAccessDb};
+use rusqlite::params;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::{instrument, Span};
+
+/// Text shorter than this is too short to judge whether it is nonsense
+const MIN_NONSENSE_CHECK_LEN: usize = 12;
+/// The proportion of unusual characters above which text is considered nonsense
+const MAX_UNUSUAL_CHAR_RATIO: f64 = 0.3;
+/// Longer runs of consonants than this don't occur in either language
+const MAX_CONSONANT_RUN: usize = 6;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SpamReason {
+    ContainsUrl,
+    RepeatedSubmission,
+    Nonsense,
+}
+
+impl SpamReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SpamReason::ContainsUrl => "contains_url",
+            SpamReason::RepeatedSubmission => "repeated_submission",
+            SpamReason::Nonsense => "nonsense",
+        }
+    }
+
+    // used in templates (moderation_spam.askama.html)
+    pub fn translation_key(&self) -> &'static str {
+        match self {
+            SpamReason::ContainsUrl => "moderation.spam-reason-url",
+            SpamReason::RepeatedSubmission => "moderation.spam-reason-repeated",
+            SpamReason::Nonsense => "moderation.spam-reason-nonsense",
+        }
+    }
+}
+
+impl FromStr for SpamReason {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "contains_url" => SpamReason::ContainsUrl,
+            "repeated_submission" => SpamReason::RepeatedSubmission,
+            "nonsense" => SpamReason::Nonsense,
+            _ => return Err(()),
+        })
+    }
+}
+
+fn contains_url(text: &str) -> bool {
+    let text = text.to_lowercase();
+    ["http://", "https://", "www.", "://"]
+        .iter()
+        .any(|pattern| text.contains(pattern))
+}
+
+fn is_nonsense(text: &str) -> bool {
+    let len = text.chars().count();
+    if len < MIN_NONSENSE_CHECK_LEN {
+        return false;
+    }
+
+    let unusual = text
+        .chars()
+        .filter(|c| !(c.is_alphabetic() || c.is_whitespace() || "'-,.()!?;:\"".contains(*c)))
+        .count();
+
+    if unusual as f64 / len as f64 > MAX_UNUSUAL_CHAR_RATIO {
+        return true;
+    }
+
+    let mut run = 0;
+    for c in text.chars() {
+        if c.is_alphabetic() && !"aeiouy".contains(c.to_ascii_lowercase()) {
+            run += 1;
+
+            if run > MAX_CONSONANT_RUN {
+                return true;
+            }
+        } else {
+            run = 0;
+        }
+    }
+
+    false
+}
+
+/// Why the submitted text looks like spam, if it does. `repeated` is whether the user has already
+/// submitted the same word.
+pub fn spam_reasons(texts: &[&str], repeated: bool) -> Vec<SpamReason> {
+    let mut reasons = Vec::new();
+
+    if texts.iter().any(|text| contains_url(text)) {
+        reasons.push(SpamReason::ContainsUrl);
+    }
+
+    if repeated {
+        reasons.push(SpamReason::RepeatedSubmission);
+    }
+
+    if texts.iter().any(|text| is_nonsense(text)) {
+        reasons.push(SpamReason::Nonsense);
+    }
+
+    reasons
+}
+
+/// Whether the user already has another pending suggestion with the same English and isiXhosa
+#[instrument(level = "trace", name = "Check for repeated submission", skip(db))]
+pub fn is_repeated_submission(
+    db: &impl UserAccessDb,
+    user_id: u64,
+    suggestion_id: Option<u64>,
+    english: &str,
+    xhosa: &str,
+) -> bool {
+    const SELECT: &str = "
+        SELECT EXISTS(
+            SELECT 1 FROM word_suggestions
+                WHERE suggesting_user = ?1 AND suggestion_id IS NOT ?2 AND english = ?3 AND xhosa = ?4
+        );
+    ";
+
+    let conn = db.get().unwrap();
+    conn.prepare_cached(SELECT)
+        .unwrap()
+        .query_row(params![user_id, suggestion_id, english, xhosa], |row| {
+            row.get(0)
+        })
+        .unwrap()
+}
+
+#[instrument(level = "trace", name = "Flag suggestion as likely spam", skip(db))]
+pub fn flag_suggestion(db: &impl UserAccessDb, suggestion_id: u64, reasons: &[SpamReason]) {
+    const INSERT: &str = "
+        INSERT INTO likely_spam_suggestions (suggestion_id, reasons) VALUES (?1, ?2)
+            ON CONFLICT(suggestion_id) DO UPDATE SET reasons = excluded.reasons;
+    ";
+
+    let reasons = reasons
+        .iter()
+        .map(SpamReason::as_str)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let conn = db.get().unwrap();
+    conn.prepare_cached(INSERT)
+        .unwrap()
+        .execute(params![suggestion_id, reasons])
+        .unwrap();
+}
+
+/// The reasons that each suggested word flagged as likely spam was flagged, keyed by suggestion
+#[instrument(name = "Fetch likely spam suggestions", fields(results), skip_all)]
+pub fn fetch_flagged(db: &impl ModeratorAccessDb) -> HashMap<u64, Vec<SpamReason>> {
+    const SELECT: &str = "SELECT suggestion_id, reasons FROM likely_spam_suggestions;";
+
+    let conn = db.get().unwrap();
+    let flagged: HashMap<u64, Vec<SpamReason>> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![])
+        .unwrap()
+        .map(|row| {
+            let reasons: String = row.get("reasons")?;
+            let reasons = reasons.split(',').filter_map(|r| r.parse().ok()).collect();
+            Ok((row.get("suggestion_id")?, reasons))
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", flagged.len());
+
+    flagged
+}
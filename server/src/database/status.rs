@@ -0,0 +1,141 @@
+//! Lets contributors check on what happened to a suggested word with a token given to them when
+//! they submitted it. The status outlives the suggestion itself, which is deleted once reviewed.
+
+use crate::auth::random_string_token;
+use chrono::{DateTime, Utc};
+use isixhosa_common::database::{ModeratorAccessDb, PublicAccessDb, UserAccessDb};
+use rusqlite::{params, OptionalExtension, Row};
+use tracing::{instrument, Span};
+
+#[derive(Debug)]
+pub struct SuggestionStatus {
+    pub english: String,
+    pub xhosa: String,
+    pub state: ReviewState,
+    pub updated: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub enum ReviewState {
+    Pending,
+    /// The word is `None` if it has since been deleted
+    Accepted {
+        word_id: Option<u64>,
+    },
+    Rejected {
+        reason: Option<String>,
+    },
+}
+
+impl TryFrom<&Row<'_>> for SuggestionStatus {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        let state = match row.get::<&str, u8>("status")? {
+            1 => ReviewState::Accepted {
+                word_id: row.get("word_id")?,
+            },
+            2 => ReviewState::Rejected {
+                reason: row.get("reason")?,
+            },
+            _ => ReviewState::Pending,
+        };
+
+        Ok(SuggestionStatus {
+            english: row.get("english")?,
+            xhosa: row.get("xhosa")?,
+            state,
+            updated: row.get("updated")?,
+        })
+    }
+}
+
+impl SuggestionStatus {
+    /// Returns the status token for the suggestion, reusing the existing one if the suggestion has
+    /// been submitted before, e.g when a moderator edits it.
+    #[instrument(name = "Create suggestion status token", skip(db, english, xhosa))]
+    pub fn create_token(
+        db: &impl UserAccessDb,
+        suggestion_id: u64,
+        english: &str,
+        xhosa: &str,
+    ) -> String {
+        const UPSERT: &str = "
+            INSERT INTO suggestion_statuses (token, suggestion_id, english, xhosa, updated)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(suggestion_id) DO UPDATE SET
+                    english = excluded.english,
+                    xhosa = excluded.xhosa,
+                    updated = excluded.updated
+                RETURNING token;
+        ";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(UPSERT)
+            .unwrap()
+            .query_row(
+                params![
+                    random_string_token(),
+                    suggestion_id,
+                    english,
+                    xhosa,
+                    Utc::now()
+                ],
+                |row| row.get("token"),
+            )
+            .unwrap()
+    }
+
+    #[instrument(name = "Mark suggestion accepted", skip(db))]
+    pub fn mark_accepted(db: &impl ModeratorAccessDb, suggestion_id: u64, word_id: u64) {
+        const UPDATE: &str = "
+            UPDATE suggestion_statuses SET status = 1, word_id = ?2, updated = ?3
+                WHERE suggestion_id = ?1;
+        ";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![suggestion_id, word_id, Utc::now()])
+            .unwrap();
+    }
+
+    #[instrument(name = "Mark suggestion rejected", skip(db))]
+    pub fn mark_rejected(db: &impl ModeratorAccessDb, suggestion_id: u64, reason: Option<&str>) {
+        const UPDATE: &str = "
+            UPDATE suggestion_statuses SET status = 2, reason = ?2, updated = ?3
+                WHERE suggestion_id = ?1;
+        ";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![suggestion_id, reason, Utc::now()])
+            .unwrap();
+    }
+
+    #[instrument(name = "Fetch suggestion status", fields(found), skip_all)]
+    pub fn fetch(db: &impl PublicAccessDb, token: &str) -> Option<SuggestionStatus> {
+        const SELECT: &str = "
+            SELECT english, xhosa, status, word_id, reason, updated FROM suggestion_statuses
+                WHERE token = ?1;
+        ";
+
+        #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+        let status = db
+            .get()
+            .unwrap()
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![token], |row| SuggestionStatus::try_from(row))
+            .optional()
+            .unwrap();
+
+        Span::current().record("found", status.is_some());
+
+        status
+    }
+}
@@ -1,19 +1,33 @@
 use crate::auth::FullUser;
+use crate::database::drafts::Draft;
+use crate::database::pending;
+use crate::database::provenance::Provenance;
+use crate::database::spam;
+use crate::database::status::SuggestionStatus;
 use crate::database::suggestion::{
     DatasetAttributionSuggestion, SuggestedExample, SuggestedLinkedWord, SuggestedWord,
 };
 use crate::database::WordId;
 use crate::database::WordOrSuggestionId;
+use crate::error::ServerResult;
 use crate::i18n::{FromWithI18n, I18nInfo};
 use crate::search::{TantivyClient, WordDocument};
 use crate::serialization::{deserialize_checkbox, false_fn};
 use crate::spawn_blocking_child;
+use crate::validation::{FieldError, ValidationErrors};
 use futures::executor::block_on;
 use isixhosa::noun::NounClass;
+use isixhosa_common::auth::Permissions;
 use isixhosa_common::database::UserAccessDb;
 use isixhosa_common::format::DisplayHtml;
-use isixhosa_common::language::{ConjunctionFollowedBy, PartOfSpeech, Transitivity, WordLinkType};
-use isixhosa_common::types::{Dataset, ExistingExample, ExistingLinkedWord, ExistingWord, WordHit};
+use isixhosa_common::language::{
+    ConjunctionFollowedBy, LanguageProfile, NounClassExt, PartOfSpeech, Transitivity, WordLinkType,
+};
+use isixhosa_common::normalize;
+use isixhosa_common::types::{
+    Dataset, ExistingExample, ExistingLinkedWord, ExistingWord, PublicUserInfo, WordHit,
+    WordReference,
+};
 use rusqlite::types::{ToSqlOutput, Value};
 use rusqlite::{params, ToSql};
 use serde::{Deserialize, Deserializer, Serialize};
@@ -22,7 +36,12 @@ use std::collections::HashSet;
 use std::iter;
 use std::num::NonZeroU64;
 use std::sync::Arc;
-use tracing::{debug_span, instrument, Span};
+use tracing::{debug_span, instrument, warn, Span};
+
+const MAX_WORD_LENGTH: usize = 256;
+const MAX_NOTE_LENGTH: usize = 2048;
+const MAX_EXAMPLE_LENGTH: usize = 1024;
+const MAX_CHANGES_SUMMARY_LENGTH: usize = 1024;
 
 fn diff<T: PartialEq + Eq>(value: T, template: &T, override_use_value: bool) -> Option<T> {
     if override_use_value || &value != template {
@@ -69,7 +88,7 @@ pub async fn suggest_word_deletion(
 
     spawn_blocking_child(move || {
         let conn = db.get().unwrap();
-        conn.prepare(STATEMENT)
+        conn.prepare_cached(STATEMENT)
             .unwrap()
             .execute(params![word_id.0, "No reason given", user_id])
             .unwrap();
@@ -78,9 +97,20 @@ pub async fn suggest_word_deletion(
     .unwrap()
 }
 
+/// A suggested word which was saved
+#[derive(Clone, Debug)]
+pub struct SubmittedSuggestion {
+    pub suggestion_id: u64,
+    /// The token which the suggestion's status can be checked with
+    pub status_token: String,
+}
+
+/// Saves the submission as a suggestion. Returns the suggestion and its status token, if a
+/// suggested word was saved. The provenance is only recorded for new suggestions, so editing a
+/// suggestion keeps how it originally arrived.
 #[instrument(
     name = "Process word submission",
-    fields(suggestion_id, changes),
+    fields(suggestion_id, changes, likely_spam),
     skip_all
 )]
 pub async fn submit_suggestion(
@@ -89,14 +119,18 @@ pub async fn submit_suggestion(
     suggesting_user: &FullUser,
     db: &impl UserAccessDb,
     i18n_info: I18nInfo,
-) {
+    provenance: Provenance,
+) -> ServerResult<Option<SubmittedSuggestion>> {
     // Intentionally suggesting_user is not set to excluded
     const INSERT_SUGGESTION: &str = "
         INSERT INTO word_suggestions (
             suggestion_id, suggesting_user, existing_word_id, changes_summary, english, xhosa,
             part_of_speech, xhosa_tone_markings, infinitive, is_plural, is_inchoative, is_informal,
-            transitivity, followed_by, noun_class, note
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+            transitivity, followed_by, noun_class, note, is_offensive, source, import_batch,
+            api_key_id
+        ) VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20
+        )
             ON CONFLICT(suggestion_id) DO UPDATE SET
                 existing_word_id = excluded.existing_word_id,
                 changes_summary = excluded.changes_summary,
@@ -111,19 +145,30 @@ pub async fn submit_suggestion(
                 transitivity = excluded.transitivity,
                 followed_by = excluded.followed_by,
                 noun_class = excluded.noun_class,
-                note = excluded.note
+                note = excluded.note,
+                is_offensive = excluded.is_offensive
             RETURNING suggestion_id;
         ";
 
     let db = db.clone();
     let mut w = word;
+    // Moderators' submissions are trusted
+    let check_spam = !suggesting_user.permissions.contains(Permissions::Moderator);
     let suggesting_user = suggesting_user.id;
 
     if w.infinitive.starts_with('U') {
         w.infinitive = w.infinitive.replacen('U', "u", 1);
     }
 
-    spawn_blocking_child(move || {
+    let submitted = spawn_blocking_child(move || {
+        if !w.drop_dangling_references(&db) {
+            warn!(
+                existing_id = w.existing_id,
+                "Submission edits a word which doesn't exist"
+            );
+            return None;
+        }
+
         let conn = db.get().unwrap();
 
         let orig = WordFormTemplate::fetch_from_db(&db, &i18n_info, w.existing_id, None)
@@ -160,7 +205,11 @@ pub async fn submit_suggestion(
             diff_with_sentinel(w.transitivity, orig.transitivity),
             diff(w.followed_by.clone(), &orig.followed_by, use_submitted),
             diff_with_sentinel(w.noun_class, orig.noun_class),
-            diff(w.note.clone(), &orig.note, use_submitted)
+            diff(w.note.clone(), &orig.note, use_submitted),
+            diff(w.is_offensive, &orig.is_offensive, use_submitted),
+            provenance.source(),
+            provenance.import_batch(),
+            provenance.api_key_id()
         ];
 
         let orig_suggestion =
@@ -171,13 +220,27 @@ pub async fn submit_suggestion(
             None => w.has_any_changes_in_word(&orig),
         };
 
+        let spam_reasons = if check_spam {
+            let repeated = spam::is_repeated_submission(
+                &db,
+                suggesting_user.get(),
+                w.suggestion_id,
+                &w.english,
+                &w.xhosa,
+            );
+            spam::spam_reasons(&w.free_text(), repeated)
+        } else {
+            Vec::new()
+        };
+
         let suggested_word_id = if any_changes {
             let _g = debug_span!("Insert word suggestion").entered();
             let suggested_word_id: i64 = conn
-                .prepare(INSERT_SUGGESTION)
+                .prepare_cached(INSERT_SUGGESTION)
                 .unwrap()
                 .query_row(params, |row| row.get("suggestion_id"))
                 .unwrap();
+            pending::record_submitted(&db, suggested_word_id as u64);
             Some(suggested_word_id)
         } else {
             w.suggestion_id.map(|id| id as i64)
@@ -186,6 +249,11 @@ pub async fn submit_suggestion(
         let span = Span::current();
         span.record("changes", any_changes);
         span.record("suggestion_id", suggested_word_id);
+        span.record("likely_spam", !spam_reasons.is_empty());
+
+        if let Some(suggested_word_id) = suggested_word_id.filter(|_| !spam_reasons.is_empty()) {
+            spam::flag_suggestion(&db, suggested_word_id as u64, &spam_reasons);
+        }
 
         let suggested_word_id_if_new = suggested_word_id.filter(|_| w.existing_id.is_none());
 
@@ -202,6 +270,7 @@ pub async fn submit_suggestion(
                 suggesting_user: Some(suggesting_user),
                 noun_class: w.noun_class,
                 is_informal: w.is_informal,
+                is_offensive: w.is_offensive,
             };
 
             if orig_suggestion.is_none() {
@@ -232,10 +301,20 @@ pub async fn submit_suggestion(
             suggesting_user,
             suggested_word_id_if_new,
             &changes_summary,
-        )
+        );
+
+        if let Some(draft_id) = w.draft_id {
+            Draft::delete(&db, suggesting_user.get(), draft_id);
+        }
+
+        suggested_word_id.map(|id| SubmittedSuggestion {
+            suggestion_id: id as u64,
+            status_token: SuggestionStatus::create_token(&db, id as u64, &w.english, &w.xhosa),
+        })
     })
-    .await
-    .unwrap();
+    .await?;
+
+    Ok(submitted)
 }
 
 #[instrument(
@@ -284,9 +363,9 @@ fn process_linked_words(
     let use_submitted = w.existing_id.is_none() && w.suggestion_id.is_none();
 
     let conn = db.get().unwrap();
-    let mut upsert_suggested_link = conn.prepare(INSERT_LINKED_WORD_SUGGESTION).unwrap();
-    let mut delete_suggested_link = conn.prepare(DELETE_LINKED_WORD_SUGGESTION).unwrap();
-    let mut suggest_link_deletion = conn.prepare(SUGGEST_LINKED_WORD_DELETION).unwrap();
+    let mut upsert_suggested_link = conn.prepare_cached(INSERT_LINKED_WORD_SUGGESTION).unwrap();
+    let mut delete_suggested_link = conn.prepare_cached(DELETE_LINKED_WORD_SUGGESTION).unwrap();
+    let mut suggest_link_deletion = conn.prepare_cached(SUGGEST_LINKED_WORD_DELETION).unwrap();
 
     let existing_word_id = w.existing_id;
     let suggestion_id = w.suggestion_id;
@@ -364,16 +443,9 @@ fn process_linked_words(
                     .iter()
                     .position(|new| new.suggestion_id == Some(prev.suggestion_id))
                 {
+                    // A new suggested word can't have any existing links yet
                     let new = linked_words.remove(i);
-                    let old = new.existing_id.and_then(|id| {
-                        ExistingLinkedWord::fetch(
-                            db,
-                            i18n_info.clone(),
-                            id,
-                            existing_word_id.unwrap(),
-                        )
-                    });
-                    maybe_insert_link(new, old);
+                    maybe_insert_link(new, None);
                 } else {
                     deleted += 1;
                     delete_suggested_link
@@ -475,9 +547,9 @@ fn process_examples(
         ";
 
     let conn = db.get().unwrap();
-    let mut upsert_example = conn.prepare(INSERT_EXAMPLE_SUGGESTION).unwrap();
-    let mut delete_suggested_example = conn.prepare(DELETE_EXAMPLE_SUGGESTION).unwrap();
-    let mut suggest_example_deletion = conn.prepare(SUGGEST_EXAMPLE_DELETION).unwrap();
+    let mut upsert_example = conn.prepare_cached(INSERT_EXAMPLE_SUGGESTION).unwrap();
+    let mut delete_suggested_example = conn.prepare_cached(DELETE_EXAMPLE_SUGGESTION).unwrap();
+    let mut suggest_example_deletion = conn.prepare_cached(SUGGEST_EXAMPLE_DELETION).unwrap();
 
     let use_submitted = w.existing_id.is_none() && w.suggestion_id.is_none();
     let existing_id = w.existing_id;
@@ -622,8 +694,8 @@ pub fn process_datasets(
     let suggested_datasets: HashSet<u64> = w.datasets.iter().copied().collect();
 
     let conn = db.get().unwrap();
-    let mut insert_suggestion = conn.prepare(INSERT_SUGGESTION).unwrap();
-    let mut delete_suggestion = conn.prepare(DELETE_SUGGESTION).unwrap();
+    let mut insert_suggestion = conn.prepare_cached(INSERT_SUGGESTION).unwrap();
+    let mut delete_suggestion = conn.prepare_cached(DELETE_SUGGESTION).unwrap();
 
     match suggested_word_id_if_new {
         // This is part of a suggestion for a new word
@@ -708,9 +780,13 @@ pub struct WordFormTemplate {
     pub noun_class: Option<NounClass>,
     pub note: String,
     pub is_informal: bool,
+    pub is_offensive: bool,
     pub examples: Vec<ExampleTemplate>,
     pub linked_words: Vec<LinkedWordTemplate>,
     pub datasets: Vec<u64>,
+    /// Only filled in when the form is shown again after failing validation
+    pub changes_summary: String,
+    pub draft_id: Option<u64>,
 }
 
 impl WordFormTemplate {
@@ -750,6 +826,102 @@ impl WordFormTemplate {
     }
 }
 
+impl WordFormTemplate {
+    /// A form for the plural of the user's suggested singular noun, with the plural guessed from
+    /// its noun class and linked back to the singular so that both are linked once accepted
+    #[instrument(name = "Fetch plural word form template", skip(db, i18n))]
+    pub fn plural_of(
+        db: &impl UserAccessDb,
+        i18n: &I18nInfo,
+        suggestion_id: u64,
+        suggesting_user: NonZeroU64,
+    ) -> Option<Self> {
+        let singular = SuggestedWord::fetch_alone(db, suggestion_id)?;
+
+        let is_singular_noun = *singular.part_of_speech.current() == Some(PartOfSpeech::Noun)
+            && !*singular.is_plural.current();
+
+        if singular.suggesting_user.id != suggesting_user || !is_singular_noun {
+            return None;
+        }
+
+        let class = (*singular.noun_class.current())?;
+        let xhosa = i18n
+            .ctx
+            .language
+            .rules
+            .pluralize(class, singular.xhosa.current())?;
+        let other = WordHit::fetch_from_db(db, WordOrSuggestionId::suggested(suggestion_id))?;
+
+        Some(WordFormTemplate {
+            english: singular.english.current().clone(),
+            xhosa,
+            part_of_speech: Some(PartOfSpeech::Noun),
+            is_plural: true,
+            noun_class: class.plural(),
+            linked_words: vec![LinkedWordTemplate {
+                suggestion_id: None,
+                existing_id: None,
+                link_type: WordLinkType::PluralOrSingular,
+                other_rendered_plaintext: other.to_plaintext(i18n).to_string(),
+                other,
+            }],
+            ..Default::default()
+        })
+    }
+
+    /// Rebuild the form from a submission which failed validation, so that none of the user's
+    /// input is lost
+    #[instrument(name = "Rebuild word form from submission", skip_all)]
+    pub fn from_submission(db: &impl UserAccessDb, i18n: &I18nInfo, w: WordSubmission) -> Self {
+        let linked_words = w
+            .linked_words
+            .0
+            .into_iter()
+            .filter_map(|link| {
+                let other = WordHit::fetch_from_db(db, link.other)?;
+                Some(LinkedWordTemplate {
+                    suggestion_id: link.suggestion_id,
+                    existing_id: link.existing_id,
+                    link_type: link.link_type,
+                    other_rendered_plaintext: other.to_plaintext(i18n).to_string(),
+                    other,
+                })
+            })
+            .collect();
+
+        WordFormTemplate {
+            english: w.english,
+            xhosa: w.xhosa,
+            part_of_speech: w.part_of_speech,
+            xhosa_tone_markings: w.xhosa_tone_markings,
+            infinitive: w.infinitive,
+            is_plural: w.is_plural,
+            is_inchoative: w.is_inchoative,
+            transitivity: w.transitivity,
+            followed_by: w.followed_by,
+            noun_class: w.noun_class,
+            note: w.note,
+            is_informal: w.is_informal,
+            is_offensive: w.is_offensive,
+            examples: w
+                .examples
+                .into_iter()
+                .map(|ex| ExampleTemplate {
+                    suggestion_id: ex.suggestion_id,
+                    existing_id: ex.existing_id,
+                    english: ex.english,
+                    xhosa: ex.xhosa,
+                })
+                .collect(),
+            linked_words,
+            datasets: w.datasets,
+            changes_summary: w.changes_summary.unwrap_or_default(),
+            draft_id: w.draft_id,
+        }
+    }
+}
+
 impl FromWithI18n<SuggestedWord> for WordFormTemplate {
     fn from_with_i18n(w: SuggestedWord, i18n: &I18nInfo) -> Self {
         let this_id = w.this_id();
@@ -766,6 +938,7 @@ impl FromWithI18n<SuggestedWord> for WordFormTemplate {
             noun_class: *w.noun_class.current(),
             note: w.note.current().clone(),
             is_informal: *w.is_informal.current(),
+            is_offensive: *w.is_offensive.current(),
             examples: w.examples.into_iter().map(Into::into).collect(),
             linked_words: w
                 .linked_words
@@ -773,6 +946,8 @@ impl FromWithI18n<SuggestedWord> for WordFormTemplate {
                 .map(|s| LinkedWordTemplate::from_suggested(s, this_id, i18n))
                 .collect(),
             datasets: w.datasets.into_iter().map(|d| d.dataset.id).collect(),
+            changes_summary: String::new(),
+            draft_id: None,
         }
     }
 }
@@ -792,6 +967,7 @@ impl FromWithI18n<ExistingWord> for WordFormTemplate {
             noun_class: w.noun_class,
             note: w.note,
             is_informal: w.is_informal,
+            is_offensive: w.is_offensive,
             examples: w.examples.into_iter().map(Into::into).collect(),
             linked_words: w
                 .linked_words
@@ -799,6 +975,8 @@ impl FromWithI18n<ExistingWord> for WordFormTemplate {
                 .map(|e| LinkedWordTemplate::from_with_i18n(e, i18n))
                 .collect(),
             datasets: w.datasets.into_iter().map(|d| d.id).collect(),
+            changes_summary: String::new(),
+            draft_id: None,
         }
     }
 }
@@ -946,6 +1124,20 @@ impl<'de> Deserialize<'de> for LinkedWordList {
     }
 }
 
+/// What to do with a submitted word form
+#[derive(Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmissionAction {
+    #[default]
+    Submit,
+    /// Show the word as it would appear once accepted, without saving it
+    Preview,
+    /// Go back to the form from a preview
+    Edit,
+    /// Save the form as a draft without checking it, to be finished later
+    Draft,
+}
+
 #[serde_as]
 #[derive(Deserialize, Clone, Debug)]
 pub struct WordSubmission {
@@ -978,6 +1170,10 @@ pub struct WordSubmission {
     #[serde(default = "false_fn")]
     #[serde(deserialize_with = "deserialize_checkbox")]
     pub is_informal: bool,
+    /// Vulgar or offensive, so hidden until the reader chooses to see it
+    #[serde(default = "false_fn")]
+    #[serde(deserialize_with = "deserialize_checkbox")]
+    pub is_offensive: bool,
 
     #[serde(default)]
     examples: Vec<ExampleSubmission>,
@@ -985,9 +1181,258 @@ pub struct WordSubmission {
     linked_words: LinkedWordList,
     #[serde(default)]
     datasets: Vec<u64>,
+
+    #[serde(default)]
+    pub action: SubmissionAction,
+    /// Set when the form was resumed from a draft, which is deleted once the word is submitted
+    #[serde(default)]
+    pub draft_id: Option<u64>,
 }
 
 impl WordSubmission {
+    /// Tidies up the submitted text so that it's saved and indexed in one consistent form. This is
+    /// done before validation, so that the lengths checked are those of what will be saved.
+    pub fn canonicalize(&mut self) {
+        for field in [
+            &mut self.english,
+            &mut self.xhosa,
+            &mut self.xhosa_tone_markings,
+            &mut self.infinitive,
+        ] {
+            *field = normalize::canonicalize(field);
+        }
+
+        self.note = normalize::canonicalize_multiline(&self.note);
+
+        if let Some(changes_summary) = &mut self.changes_summary {
+            *changes_summary = normalize::canonicalize_multiline(changes_summary);
+        }
+
+        if let Some(ConjunctionFollowedBy::Custom(followed_by)) = &mut self.followed_by {
+            *followed_by = normalize::canonicalize(followed_by);
+        }
+
+        for example in &mut self.examples {
+            example.english = normalize::canonicalize(&example.english);
+            example.xhosa = normalize::canonicalize(&example.xhosa);
+        }
+    }
+
+    /// Checks the submission before anything is written, so that the form can be shown to the
+    /// user again with what they need to fix
+    pub fn validate(&self, language: &LanguageProfile) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+
+        errors.require_text("english", &self.english);
+        errors.max_length("english", &self.english, MAX_WORD_LENGTH);
+        errors.require_text("xhosa", &self.xhosa);
+        errors.max_length("xhosa", &self.xhosa, MAX_WORD_LENGTH);
+
+        // Apostrophes pasted from elsewhere are often curly, which shouldn't count as misspelt
+        if !language
+            .rules
+            .is_valid_spelling(&normalize::apostrophes(&self.xhosa))
+        {
+            errors.add("xhosa", FieldError::Spelling);
+        }
+        errors.max_length(
+            "xhosa_tone_markings",
+            &self.xhosa_tone_markings,
+            MAX_WORD_LENGTH,
+        );
+        errors.max_length("infinitive", &self.infinitive, MAX_WORD_LENGTH);
+        errors.max_length("note", &self.note, MAX_NOTE_LENGTH);
+
+        if let Some(ConjunctionFollowedBy::Custom(followed_by)) = &self.followed_by {
+            errors.max_length("followed_by", followed_by, MAX_WORD_LENGTH);
+        }
+
+        if let Some(changes_summary) = &self.changes_summary {
+            errors.max_length(
+                "changes_summary",
+                changes_summary,
+                MAX_CHANGES_SUMMARY_LENGTH,
+            );
+        }
+
+        errors.require("part_of_speech", self.part_of_speech.is_some());
+
+        if self.part_of_speech == Some(PartOfSpeech::Noun) {
+            // Only the classes which the site's language has can be chosen
+            errors.require(
+                "noun_class",
+                self.noun_class
+                    .is_some_and(|class| language.has_noun_class(class)),
+            );
+        }
+
+        // Examples with both halves left empty are removed rather than submitted
+        for example in &self.examples {
+            if example.english.trim().is_empty() && example.xhosa.trim().is_empty() {
+                continue;
+            }
+
+            errors.require_text("examples", &example.english);
+            errors.require_text("examples", &example.xhosa);
+            errors.max_length("examples", &example.english, MAX_EXAMPLE_LENGTH);
+            errors.max_length("examples", &example.xhosa, MAX_EXAMPLE_LENGTH);
+        }
+
+        errors.into_result()
+    }
+
+    /// Drop references to words, links, examples, and datasets which don't exist or don't belong
+    /// to the word being edited. These only come from stale or tampered-with forms, and would
+    /// otherwise fail foreign key constraints once written. Returns `false` if the word being
+    /// edited doesn't exist, in which case nothing should be written at all.
+    #[instrument(
+        name = "Drop dangling references in submission",
+        fields(dropped),
+        skip_all
+    )]
+    pub fn drop_dangling_references(&mut self, db: &impl UserAccessDb) -> bool {
+        let exists = |id| WordHit::fetch_from_db(db, id).is_some();
+
+        if let Some(existing_id) = self.existing_id {
+            if !exists(WordOrSuggestionId::existing(existing_id)) {
+                return false;
+            }
+        }
+
+        let (link_ids, example_ids): (HashSet<u64>, HashSet<u64>) = match self.existing_id {
+            Some(id) => (
+                ExistingLinkedWord::fetch_all_for_word(db, id)
+                    .into_iter()
+                    .map(|link| link.link_id)
+                    .collect(),
+                ExistingExample::fetch_all_for_word(db, id)
+                    .into_iter()
+                    .map(|example| example.example_id)
+                    .collect(),
+            ),
+            None => Default::default(),
+        };
+        let dataset_ids: HashSet<u64> = Dataset::fetch_all(db).into_iter().map(|d| d.id).collect();
+
+        let before = self.linked_words.0.len() + self.datasets.len();
+
+        self.linked_words.0.retain(|link| exists(link.other));
+        self.datasets.retain(|id| dataset_ids.contains(id));
+
+        for link in &mut self.linked_words.0 {
+            link.existing_id = link.existing_id.filter(|id| link_ids.contains(id));
+        }
+
+        for example in &mut self.examples {
+            example.existing_id = example.existing_id.filter(|id| example_ids.contains(id));
+        }
+
+        let dropped = before - (self.linked_words.0.len() + self.datasets.len());
+        Span::current().record("dropped", dropped);
+
+        true
+    }
+
+    /// Build the word as it would appear once the submission is accepted, for previewing it
+    #[instrument(name = "Build word preview from submission", skip_all)]
+    pub fn into_preview(self, db: &impl UserAccessDb, user: &FullUser) -> ExistingWord {
+        let word_id = self.existing_id.unwrap_or_default();
+
+        let examples = self
+            .examples
+            .into_iter()
+            .filter(|ex| !ex.english.trim().is_empty() || !ex.xhosa.trim().is_empty())
+            .map(|ex| ExistingExample {
+                example_id: ex.existing_id.unwrap_or_default(),
+                word_id,
+                english: ex.english,
+                xhosa: ex.xhosa,
+                source: None,
+                alignment: None,
+            })
+            .collect();
+
+        let linked_words = self
+            .linked_words
+            .0
+            .into_iter()
+            .filter_map(|link| {
+                let other = WordHit::fetch_from_db(db, link.other)?;
+                Some(ExistingLinkedWord {
+                    link_id: link.existing_id.unwrap_or_default(),
+                    first_word_id: word_id,
+                    second_word_id: other.id,
+                    link_type: link.link_type,
+                    other,
+                })
+            })
+            .collect();
+
+        let mut contributors = self
+            .existing_id
+            .map(|id| PublicUserInfo::fetch_public_contributors_for_word(db, id))
+            .unwrap_or_default();
+
+        if user.display_name && contributors.iter().all(|c| c.id != user.id) {
+            contributors.push(PublicUserInfo {
+                id: user.id,
+                username: user.username.clone(),
+                display_name: user.display_name,
+            });
+        }
+
+        let datasets = Dataset::fetch_all(db)
+            .into_iter()
+            .filter(|dataset| self.datasets.contains(&dataset.id))
+            .collect();
+
+        // References aren't part of submissions, so the existing word's are kept as they are
+        let references = self
+            .existing_id
+            .map(|id| WordReference::fetch_all_for_word(db, id))
+            .unwrap_or_default();
+
+        ExistingWord {
+            word_id,
+            english: self.english,
+            xhosa: self.xhosa,
+            part_of_speech: self.part_of_speech,
+            xhosa_tone_markings: self.xhosa_tone_markings,
+            infinitive: self.infinitive,
+            is_plural: self.is_plural,
+            is_inchoative: self.is_inchoative,
+            transitivity: self.transitivity,
+            followed_by: self.followed_by,
+            noun_class: self.noun_class,
+            note: self.note,
+            is_informal: self.is_informal,
+            is_offensive: self.is_offensive,
+            examples,
+            linked_words,
+            contributors,
+            datasets,
+            references,
+        }
+    }
+
+    /// All of the free text in the submission, for checking whether it looks like spam
+    fn free_text(&self) -> Vec<&str> {
+        let mut text = vec![
+            self.english.as_str(),
+            self.xhosa.as_str(),
+            self.note.as_str(),
+            self.xhosa_tone_markings.as_str(),
+            self.infinitive.as_str(),
+        ];
+        text.extend(self.changes_summary.as_deref());
+
+        for example in &self.examples {
+            text.extend([example.english.as_str(), example.xhosa.as_str()]);
+        }
+
+        text
+    }
+
     fn has_any_changes_in_word(&self, o: &WordFormTemplate) -> bool {
         self.english != o.english
             || self.xhosa != o.xhosa
@@ -997,6 +1442,7 @@ impl WordSubmission {
             || self.is_plural != o.is_plural
             || self.is_inchoative != o.is_inchoative
             || self.is_informal != o.is_informal
+            || self.is_offensive != o.is_offensive
             || self.transitivity != o.transitivity
             || self.noun_class != o.noun_class
             || self.part_of_speech != o.part_of_speech
@@ -1019,3 +1465,179 @@ impl ExampleSubmission {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialization::deserialize_form;
+    use proptest::prelude::*;
+    use url::form_urlencoded;
+
+    /// The fields of a word form as a browser would submit them
+    #[derive(Clone, Debug)]
+    struct WordForm {
+        english: String,
+        xhosa: String,
+        part_of_speech: Option<PartOfSpeech>,
+        xhosa_tone_markings: String,
+        infinitive: String,
+        note: String,
+        is_plural: bool,
+        is_inchoative: bool,
+        is_informal: bool,
+        is_offensive: bool,
+        transitivity: Option<Transitivity>,
+        examples: Vec<(String, String)>,
+        action: SubmissionAction,
+    }
+
+    impl WordForm {
+        fn to_urlencoded(&self) -> String {
+            let mut form = form_urlencoded::Serializer::new(String::new());
+            form.append_pair("english", &self.english)
+                .append_pair("xhosa", &self.xhosa)
+                .append_pair("xhosa_tone_markings", &self.xhosa_tone_markings)
+                .append_pair("infinitive", &self.infinitive)
+                .append_pair("note", &self.note)
+                .append_pair("followed_by", "")
+                .append_pair(
+                    "transitivity",
+                    self.transitivity.as_ref().map_or("", Transitivity::name),
+                );
+
+            if let Some(part_of_speech) = self.part_of_speech {
+                form.append_pair("part_of_speech", &part_of_speech.name());
+            }
+
+            // Unticked checkboxes aren't submitted at all
+            for (field, ticked) in [
+                ("is_plural", self.is_plural),
+                ("is_inchoative", self.is_inchoative),
+                ("is_informal", self.is_informal),
+                ("is_offensive", self.is_offensive),
+            ] {
+                if ticked {
+                    form.append_pair(field, "on");
+                }
+            }
+
+            for (i, (english, xhosa)) in self.examples.iter().enumerate() {
+                form.append_pair(&format!("examples[{i}][english]"), english)
+                    .append_pair(&format!("examples[{i}][xhosa]"), xhosa);
+            }
+
+            let action = match self.action {
+                SubmissionAction::Submit => "submit",
+                SubmissionAction::Preview => "preview",
+                SubmissionAction::Edit => "edit",
+                SubmissionAction::Draft => "draft",
+            };
+            form.append_pair("action", action);
+
+            form.finish()
+        }
+    }
+
+    fn text() -> impl Strategy<Value = String> {
+        "[\\w ,.'’!?-]{0,40}"
+    }
+
+    fn word_form() -> impl Strategy<Value = WordForm> {
+        let part_of_speech =
+            proptest::option::of(proptest::sample::select(PartOfSpeech::ALL.to_vec()));
+        let transitivity = proptest::option::of(proptest::sample::select(vec![
+            Transitivity::Transitive,
+            Transitivity::Intransitive,
+            Transitivity::Ambitransitive,
+        ]));
+        let action = proptest::sample::select(vec![
+            SubmissionAction::Submit,
+            SubmissionAction::Preview,
+            SubmissionAction::Edit,
+            SubmissionAction::Draft,
+        ]);
+        let examples = proptest::collection::vec((text(), text()), 0..4);
+
+        (
+            (text(), text(), part_of_speech, text(), text(), text()),
+            any::<[bool; 4]>(),
+            (transitivity, examples, action),
+        )
+            .prop_map(
+                |(
+                    (english, xhosa, part_of_speech, xhosa_tone_markings, infinitive, note),
+                    [is_plural, is_inchoative, is_informal, is_offensive],
+                    (transitivity, examples, action),
+                )| WordForm {
+                    english,
+                    xhosa,
+                    part_of_speech,
+                    xhosa_tone_markings,
+                    infinitive,
+                    note,
+                    is_plural,
+                    is_inchoative,
+                    is_informal,
+                    is_offensive,
+                    transitivity,
+                    examples,
+                    action,
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn word_forms_round_trip(form in word_form()) {
+            let word: WordSubmission = deserialize_form(form.to_urlencoded().as_bytes()).unwrap();
+
+            prop_assert_eq!(&word.english, &form.english);
+            prop_assert_eq!(&word.xhosa, &form.xhosa);
+            prop_assert_eq!(word.part_of_speech, form.part_of_speech);
+            prop_assert_eq!(&word.xhosa_tone_markings, &form.xhosa_tone_markings);
+            prop_assert_eq!(&word.infinitive, &form.infinitive);
+            prop_assert_eq!(&word.note, &form.note);
+            prop_assert_eq!(word.is_plural, form.is_plural);
+            prop_assert_eq!(word.is_inchoative, form.is_inchoative);
+            prop_assert_eq!(word.is_informal, form.is_informal);
+            prop_assert_eq!(word.is_offensive, form.is_offensive);
+            prop_assert_eq!(word.transitivity, form.transitivity);
+            prop_assert_eq!(word.followed_by, None);
+            prop_assert_eq!(word.action, form.action);
+
+            let examples: Vec<(String, String)> = word
+                .examples
+                .into_iter()
+                .map(|example| (example.english, example.xhosa))
+                .collect();
+            prop_assert_eq!(examples, form.examples);
+        }
+
+        #[test]
+        fn checking_submitted_words_does_not_panic(form in word_form()) {
+            let mut word: WordSubmission =
+                deserialize_form(form.to_urlencoded().as_bytes()).unwrap();
+            word.canonicalize();
+            let _ = word.validate(&LanguageProfile::default());
+        }
+
+        #[test]
+        fn truncated_word_forms_do_not_panic(
+            form in word_form(),
+            cut in any::<prop::sample::Index>(),
+        ) {
+            let form = form.to_urlencoded();
+            let truncated = &form[..cut.index(form.len() + 1)];
+
+            if let Ok(mut word) = deserialize_form::<WordSubmission>(truncated.as_bytes()) {
+                word.canonicalize();
+                let _ = word.validate(&LanguageProfile::default());
+            }
+        }
+
+        #[test]
+        fn arbitrary_bytes_do_not_panic(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let _ = deserialize_form::<WordSubmission>(&bytes);
+        }
+    }
+}
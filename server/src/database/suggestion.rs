@@ -1,3 +1,9 @@
+use crate::database::homographs::{fetch_homographs, link_homographs};
+use crate::database::notifications::Notification;
+use crate::database::provenance::Provenance;
+use crate::database::revisions::{self, WordSnapshot};
+use crate::database::slugs;
+use crate::database::status::SuggestionStatus;
 use crate::database::WordId;
 use crate::database::{add_user_attribution, WordOrSuggestionId};
 use crate::i18n::I18nInfo;
@@ -12,15 +18,18 @@ use isixhosa_common::format::{DisplayHtml, HtmlFormatter, HyperlinkWrapper, Noun
 use isixhosa_common::i18n::TranslationKey;
 use isixhosa_common::language::{ConjunctionFollowedBy, PartOfSpeech, Transitivity, WordLinkType};
 use isixhosa_common::serialization::WithDeleteSentinel;
-use isixhosa_common::types::{Dataset, ExistingExample, ExistingWord, PublicUserInfo, WordHit};
+use isixhosa_common::types::{
+    Dataset, ExistingExample, ExistingLinkedWord, ExistingWord, PublicUserInfo, WordHit,
+};
 use num_enum::TryFromPrimitive;
 use rusqlite::types::FromSql;
 use rusqlite::{params, OptionalExtension, Params, Row};
+use similar::{ChangeTag, TextDiff};
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::{self, Debug};
 use std::sync::Arc;
-use tracing::{instrument, Span};
+use tracing::{instrument, warn, Span};
 
 #[derive(Clone, Debug)]
 pub struct SuggestedWord {
@@ -29,6 +38,7 @@ pub struct SuggestedWord {
     pub word_id: Option<u64>,
 
     pub changes_summary: String,
+    pub provenance: Provenance,
 
     // TODO(translations): a TargetLanguage vs SourceLanguage string could be cool
     pub english: MaybeEdited<String>,
@@ -45,6 +55,7 @@ pub struct SuggestedWord {
     pub note: MaybeEdited<String>,
 
     pub is_informal: MaybeEdited<bool>,
+    pub is_offensive: MaybeEdited<bool>,
 
     pub examples: Vec<SuggestedExample>,
     pub linked_words: Vec<SuggestedLinkedWord>,
@@ -71,24 +82,40 @@ impl SuggestedWord {
             SELECT
                 suggestion_id, suggesting_user, existing_word_id, changes_summary,
                 english, xhosa, part_of_speech, xhosa_tone_markings, infinitive, is_plural,
-                is_inchoative, is_informal, transitivity, followed_by, noun_class, note, username, display_name
+                is_inchoative, is_informal, transitivity, followed_by, noun_class, note, is_offensive,
+                source, import_batch, api_key_id, username, display_name
             FROM word_suggestions
             INNER JOIN users ON word_suggestions.suggesting_user = users.user_id
             ORDER BY suggestion_id;";
 
+        // Everything associated with the suggestions is loaded up front, a query per kind, rather
+        // than a few queries per suggestion
+        let originals = SuggestedWord::fetch_all_originals(db);
+        let mut examples = SuggestedExample::fetch_all_for_all_suggestions(db);
+        let mut linked_words = SuggestedLinkedWord::fetch_all_for_all_suggestions(db, i18n_info);
+        let mut datasets: HashMap<u64, Vec<DatasetAttributionSuggestion>> =
+            DatasetAttributionSuggestion::fetch_with_filter(
+                db,
+                "WHERE suggested_word_id IS NOT NULL",
+                params![],
+            )
+            .filter_map(|(id, suggestions)| Some((id.into_suggested()?, suggestions)))
+            .collect();
+
         let conn = db.get().unwrap();
 
-        let mut query = conn.prepare(SELECT_SUGGESTIONS).unwrap();
+        let mut query = conn.prepare_cached(SELECT_SUGGESTIONS).unwrap();
         let suggestions = query.query(params![]).unwrap();
 
         let results: Vec<_> = suggestions
             .map(|row| {
-                let mut w = SuggestedWord::from_row_fetch_original(row, db);
-                w.examples = SuggestedExample::fetch_all_for_suggestion(db, w.suggestion_id);
-                w.linked_words =
-                    SuggestedLinkedWord::fetch_all_for_suggestion(db, i18n_info, w.suggestion_id);
-                w.datasets =
-                    DatasetAttributionSuggestion::fetch_all_for_suggestion(db, w.suggestion_id);
+                let existing_id = row.get::<&str, Option<u64>>("existing_word_id")?;
+                let original = existing_id.and_then(|id| originals.get(&id));
+
+                let mut w = SuggestedWord::from_row_with_original(row, original);
+                w.examples = examples.remove(&w.suggestion_id).unwrap_or_default();
+                w.linked_words = linked_words.remove(&w.suggestion_id).unwrap_or_default();
+                w.datasets = datasets.remove(&w.suggestion_id).unwrap_or_default();
                 Ok(w)
             })
             .collect()
@@ -111,7 +138,8 @@ impl SuggestedWord {
             SELECT
                 suggestion_id, existing_word_id, changes_summary, english, xhosa, part_of_speech,
                 xhosa_tone_markings, infinitive, is_plural, is_inchoative, is_informal, transitivity,
-                followed_by, noun_class, note, username, display_name, suggesting_user
+                followed_by, noun_class, note, is_offensive, source, import_batch, api_key_id,
+                username, display_name, suggesting_user
             FROM word_suggestions
             INNER JOIN users ON word_suggestions.suggesting_user = users.user_id
             WHERE suggestion_id = ?1;
@@ -120,7 +148,7 @@ impl SuggestedWord {
         let conn = db.get().unwrap();
 
         let word = conn
-            .prepare(SELECT_SUGGESTION)
+            .prepare_cached(SELECT_SUGGESTION)
             .unwrap()
             .query_row(params![id], |row| {
                 Ok(SuggestedWord::from_row_fetch_original(row, db))
@@ -167,8 +195,8 @@ impl SuggestedWord {
         const INSERT: &str = "
             INSERT INTO words (
                 word_id, english, xhosa, part_of_speech, xhosa_tone_markings, infinitive, is_plural,
-                is_inchoative, is_informal, transitivity, followed_by, noun_class, note
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+                is_inchoative, is_informal, transitivity, followed_by, noun_class, note, is_offensive
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
                 ON CONFLICT(word_id) DO UPDATE SET
                     english = excluded.english,
                     xhosa = excluded.xhosa,
@@ -181,10 +209,17 @@ impl SuggestedWord {
                     is_informal = excluded.is_informal,
                     transitivity = excluded.transitivity,
                     followed_by = excluded.followed_by,
-                    note = excluded.note
+                    note = excluded.note,
+                    is_offensive = excluded.is_offensive
                 RETURNING word_id;
         ";
 
+        // Kept so that the edit can be undone if the import batch is rolled back
+        let previous = self
+            .word_id
+            .filter(|_| self.provenance.import_batch().is_some())
+            .and_then(|word_id| WordSnapshot::fetch(db, word_id));
+
         let conn = db.get().unwrap();
         let params = params![
             self.word_id,
@@ -200,16 +235,27 @@ impl SuggestedWord {
             self.followed_by.current().clone().unwrap_or_default(),
             self.noun_class.current().map(|x| x as u8),
             self.note.current(),
+            self.is_offensive.current(),
         ];
 
         let id: i64 = conn
-            .prepare(INSERT)
+            .prepare_cached(INSERT)
             .unwrap()
             .query_row(params, |row| row.get("word_id"))
             .unwrap();
         let id = id as u64;
 
         add_user_attribution(db, &self.suggesting_user, WordId(id));
+        slugs::update_slug(db, id, self.xhosa.current());
+        revisions::record_revision(
+            db,
+            id,
+            self.suggesting_user.id.get(),
+            self.word_id.is_none(),
+            self.provenance.import_batch(),
+            previous.as_ref(),
+        );
+        link_homographs(db, id);
 
         Span::current().record("accepted_id", id);
 
@@ -224,6 +270,14 @@ impl SuggestedWord {
     ) {
         let word_suggestion_id = self.suggestion_id;
         let new_word_id = self.accept_just_word_suggestion(db);
+        SuggestionStatus::mark_accepted(db, word_suggestion_id, new_word_id);
+        Notification::suggestion_accepted(
+            db,
+            self.suggesting_user.id.get(),
+            new_word_id,
+            self.english.current(),
+            self.xhosa.current(),
+        );
 
         for mut example in self.examples.into_iter() {
             example.word_or_suggested_id = WordOrSuggestionId::existing(new_word_id);
@@ -246,14 +300,31 @@ impl SuggestedWord {
             }
 
             if l.first.current().0.is_existing() && l.second.current().0.is_existing() {
-                l.accept(db);
+                // An invalid link is left for the moderator to reject
+                if let Err(error) = l.accept(db) {
+                    warn!(suggestion_id = l.suggestion_id, %error, "Could not accept suggested linked word");
+                }
             } else {
                 l.update_first_and_second(db);
             }
         }
 
-        let document = WordDocument {
-            id: WordOrSuggestionId::existing(new_word_id),
+        let document = self.accepted_document(new_word_id);
+
+        let tantivy_clone = tantivy.clone();
+        SuggestedWord::delete(db, tantivy_clone, word_suggestion_id);
+
+        if self.word_id.is_none() {
+            block_on(async move { tantivy.add_new_word(document).await });
+        } else {
+            block_on(async move { tantivy.edit_word(document).await });
+        }
+    }
+
+    /// The search index document for the word once the suggestion is accepted with the given ID
+    pub fn accepted_document(&self, word_id: u64) -> WordDocument {
+        WordDocument {
+            id: WordOrSuggestionId::existing(word_id),
             english: self.english.current().clone(),
             xhosa: self.xhosa.current().clone(),
             part_of_speech: *self.part_of_speech.current(),
@@ -263,16 +334,146 @@ impl SuggestedWord {
             suggesting_user: None,
             noun_class: *self.noun_class.current(),
             is_informal: *self.is_informal.current(),
-        };
+            is_offensive: *self.is_offensive.current(),
+        }
+    }
 
-        let tantivy_clone = tantivy.clone();
-        SuggestedWord::delete(db, tantivy_clone, word_suggestion_id);
+    /// Build the word as it would appear once the whole suggestion is accepted, without changing
+    /// anything. New words are given the ID 0. Along with the word, the homographs which would be
+    /// linked to it as confusable on accepting are returned, which are also among its linked words.
+    #[instrument(
+        name = "Build accept preview",
+        fields(suggestion_id = self.suggestion_id),
+        skip_all
+    )]
+    pub fn preview_accept(&self, db: &impl ModeratorAccessDb) -> (ExistingWord, Vec<WordHit>) {
+        let word_id = self.word_id.unwrap_or_default();
+        let this_id = self.this_id();
+        let existing = self.word_id.and_then(|id| ExistingWord::fetch_full(db, id));
+
+        let (mut examples, mut linked_words, mut contributors, mut datasets, references) =
+            match existing {
+                Some(word) => (
+                    word.examples,
+                    word.linked_words,
+                    word.contributors,
+                    word.datasets,
+                    word.references,
+                ),
+                None => Default::default(),
+            };
+
+        for suggested in &self.examples {
+            let example = ExistingExample {
+                example_id: suggested.existing_example_id.unwrap_or_default(),
+                word_id,
+                english: suggested.english.current().clone(),
+                xhosa: suggested.xhosa.current().clone(),
+                source: None,
+                alignment: None,
+            };
+
+            match examples
+                .iter_mut()
+                .find(|ex| Some(ex.example_id) == suggested.existing_example_id)
+            {
+                Some(ex) => {
+                    // The alignment is only right for the sentences it was made for
+                    let unchanged = ex.english == example.english && ex.xhosa == example.xhosa;
+
+                    *ex = ExistingExample {
+                        source: ex.source.take(),
+                        alignment: ex.alignment.take().filter(|_| unchanged),
+                        ..example
+                    }
+                }
+                None => examples.push(example),
+            }
+        }
 
-        if self.word_id.is_none() {
-            block_on(async move { tantivy.add_new_word(document).await });
-        } else {
-            block_on(async move { tantivy.edit_word(document).await });
+        for suggested in &self.linked_words {
+            let (first, second) = (suggested.first.current(), suggested.second.current());
+            let (other_id, other) = if first.0 == this_id { second } else { first };
+
+            // Links to other suggested words are only added once those are accepted too
+            let Some(other_id) = other_id.into_existing() else {
+                continue;
+            };
+
+            if other_id == word_id {
+                continue;
+            }
+
+            let link_type = *suggested.link_type.current();
+            linked_words.retain(|link| {
+                Some(link.link_id) != suggested.existing_linked_word_id
+                    && !(link.other.id == other_id && link.link_type == link_type)
+            });
+            linked_words.push(ExistingLinkedWord {
+                link_id: suggested.existing_linked_word_id.unwrap_or_default(),
+                first_word_id: word_id,
+                second_word_id: other_id,
+                link_type,
+                other: other.clone(),
+            });
         }
+
+        let homographs: Vec<WordHit> =
+            fetch_homographs(db, word_id, self.xhosa.current(), self.english.current())
+                .into_iter()
+                .filter(|hit| linked_words.iter().all(|link| link.other.id != hit.id))
+                .collect();
+
+        linked_words.extend(homographs.iter().map(|hit| ExistingLinkedWord {
+            link_id: 0,
+            first_word_id: word_id,
+            second_word_id: hit.id,
+            link_type: WordLinkType::Confusable,
+            other: hit.clone(),
+        }));
+
+        for suggested in &self.datasets {
+            datasets.retain(|dataset| dataset.id != suggested.dataset.id);
+
+            if !suggested.is_delete {
+                datasets.push(suggested.dataset.clone());
+            }
+        }
+
+        let suggesters = std::iter::once(&self.suggesting_user)
+            .chain(self.examples.iter().map(|ex| &ex.suggesting_user))
+            .chain(self.linked_words.iter().map(|l| &l.suggesting_user))
+            .chain(self.datasets.iter().map(|d| &d.suggesting_user));
+
+        for user in suggesters {
+            if user.display_name && contributors.iter().all(|c| c.id != user.id) {
+                contributors.push(user.clone());
+            }
+        }
+
+        let word = ExistingWord {
+            word_id,
+            english: self.english.current().clone(),
+            xhosa: self.xhosa.current().clone(),
+            part_of_speech: *self.part_of_speech.current(),
+            xhosa_tone_markings: self.xhosa_tone_markings.current().clone(),
+            infinitive: self.infinitive.current().clone(),
+            is_plural: *self.is_plural.current(),
+            is_inchoative: *self.is_inchoative.current(),
+            transitivity: *self.transitivity.current(),
+            followed_by: self.followed_by.current().clone(),
+            noun_class: *self.noun_class.current(),
+            note: self.note.current().clone(),
+            is_informal: *self.is_informal.current(),
+            is_offensive: *self.is_offensive.current(),
+            examples,
+            linked_words,
+            contributors,
+            datasets,
+            references,
+        };
+
+        (word, homographs)
     }
 
     #[instrument(name = "Delete word suggestion", fields(found), skip(db, tantivy))]
@@ -282,7 +483,11 @@ impl SuggestedWord {
         block_on(async move { tantivy.delete_word(WordOrSuggestionId::suggested(id)).await });
 
         let conn = db.get().unwrap();
-        let modified_rows = conn.prepare(DELETE).unwrap().execute(params![id]).unwrap();
+        let modified_rows = conn
+            .prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![id])
+            .unwrap();
         let found = modified_rows == 1;
 
         Span::current().record("found", found);
@@ -290,11 +495,47 @@ impl SuggestedWord {
         found
     }
 
+    /// The words which suggestions edit, as they are now
+    #[instrument(
+        level = "trace",
+        name = "Fetch all originals of suggested words",
+        fields(results),
+        skip(db)
+    )]
+    fn fetch_all_originals(db: &impl UserAccessDb) -> HashMap<u64, ExistingWord> {
+        const SELECT_ORIGINALS: &str = "
+            SELECT
+                word_id, english, xhosa, part_of_speech, xhosa_tone_markings, infinitive, is_plural,
+                is_inchoative, is_informal, transitivity, followed_by, noun_class, note, is_offensive
+            FROM words
+            WHERE word_id IN (SELECT existing_word_id FROM word_suggestions);
+        ";
+
+        let conn = db.get().unwrap();
+        let mut query = conn.prepare_cached(SELECT_ORIGINALS).unwrap();
+
+        let originals: HashMap<u64, ExistingWord> = query
+            .query(params![])
+            .unwrap()
+            .map(|row| {
+                let word = ExistingWord::try_from(row)?;
+                Ok((word.word_id, word))
+            })
+            .collect()
+            .unwrap();
+
+        Span::current().record("results", originals.len());
+
+        originals
+    }
+
     fn from_row_fetch_original(row: &Row<'_>, db: &impl UserAccessDb) -> Self {
         let existing_id = row.get::<&str, Option<i64>>("existing_word_id").unwrap();
         let e = existing_id.and_then(|id| ExistingWord::fetch_alone(db, id as u64));
-        let e = e.as_ref();
+        SuggestedWord::from_row_with_original(row, e.as_ref())
+    }
 
+    fn from_row_with_original(row: &Row<'_>, e: Option<&ExistingWord>) -> Self {
         let val = row.get::<&str, Option<String>>("followed_by").unwrap();
         let old = e.and_then(|e| e.followed_by.clone());
         let followed_by = val.map(|x| x.parse().ok());
@@ -309,6 +550,7 @@ impl SuggestedWord {
             suggestion_id: row.get("suggestion_id").unwrap(),
             word_id: row.get("existing_word_id").unwrap(),
             changes_summary: row.get("changes_summary").unwrap(),
+            provenance: Provenance::from_row(row).unwrap(),
             english: MaybeEdited::from_row("english", row, e.map(|e| e.english.clone())),
             xhosa: MaybeEdited::from_row("xhosa", row, e.map(|e| e.xhosa.clone())),
             part_of_speech: MaybeEdited::from_row(
@@ -337,6 +579,7 @@ impl SuggestedWord {
             ),
             note: MaybeEdited::from_row("note", row, e.map(|e| e.note.clone())),
             is_informal: MaybeEdited::from_row("is_informal", row, e.map(|e| e.is_plural)),
+            is_offensive: MaybeEdited::from_row("is_offensive", row, e.map(|e| e.is_offensive)),
             examples: vec![],
             linked_words: vec![],
             datasets: vec![],
@@ -358,7 +601,7 @@ impl SuggestedWord {
 
         let conn = db.get().unwrap();
         let word_id: Option<u64> = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query_row(params![suggestion], |row| row.get("existing_word_id"))
             .unwrap();
@@ -404,7 +647,7 @@ impl SuggestedExample {
         ";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
         let examples = query.query(params![]).unwrap();
 
         let mut map: HashMap<WordId, Vec<SuggestedExample>> = HashMap::new();
@@ -449,7 +692,7 @@ impl SuggestedExample {
         ";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT_SUGGESTION).unwrap();
+        let mut query = conn.prepare_cached(SELECT_SUGGESTION).unwrap();
         let examples = query.query(params![suggested_word_id]).unwrap();
 
         let examples: Vec<_> = examples
@@ -462,6 +705,71 @@ impl SuggestedExample {
         examples
     }
 
+    /// Fetch the suggested examples of all suggested words, keyed by the suggested word
+    #[instrument(
+        level = "trace",
+        name = "Fetch all suggested examples for all suggested words",
+        fields(results),
+        skip(db)
+    )]
+    pub fn fetch_all_for_all_suggestions(
+        db: &impl UserAccessDb,
+    ) -> HashMap<u64, Vec<SuggestedExample>> {
+        const SELECT_ORIGINALS: &str = "
+            SELECT example_id, word_id, english, xhosa, source, alignment FROM examples
+                LEFT JOIN example_sources USING (example_id)
+                WHERE example_id IN (
+                    SELECT existing_example_id FROM example_suggestions
+                        WHERE suggested_word_id IS NOT NULL
+                );
+        ";
+        const SELECT_SUGGESTIONS: &str = "
+            SELECT
+                suggestion_id, existing_word_id, suggested_word_id, existing_example_id,
+                changes_summary, xhosa, english, username, display_name, suggesting_user
+            FROM example_suggestions
+            INNER JOIN users ON example_suggestions.suggesting_user = users.user_id
+            WHERE suggested_word_id IS NOT NULL
+            ORDER BY suggestion_id;
+        ";
+
+        let conn = db.get().unwrap();
+
+        let originals: HashMap<u64, ExistingExample> = conn
+            .prepare_cached(SELECT_ORIGINALS)
+            .unwrap()
+            .query(params![])
+            .unwrap()
+            .map(|row| {
+                let example = ExistingExample::try_from(row)?;
+                Ok((example.example_id, example))
+            })
+            .collect()
+            .unwrap();
+
+        let mut query = conn.prepare_cached(SELECT_SUGGESTIONS).unwrap();
+        let mut map: HashMap<u64, Vec<SuggestedExample>> = HashMap::new();
+
+        query
+            .query(params![])
+            .unwrap()
+            .for_each(|row| {
+                let suggested_word_id: u64 = row.get("suggested_word_id")?;
+                let existing_id = row.get::<&str, Option<u64>>("existing_example_id")?;
+                let original = existing_id.and_then(|id| originals.get(&id));
+
+                map.entry(suggested_word_id)
+                    .or_default()
+                    .push(SuggestedExample::from_row_with_original(row, original));
+                Ok(())
+            })
+            .unwrap();
+
+        Span::current().record("results", map.len());
+
+        map
+    }
+
     #[instrument(name = "Fetch suggested example", fields(found), skip(db))]
     pub fn fetch(db: &impl UserAccessDb, suggestion_id: u64) -> Option<SuggestedExample> {
         const SELECT: &str = "
@@ -475,7 +783,7 @@ impl SuggestedExample {
 
         let conn = db.get().unwrap();
         let ex = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query_row(params![suggestion_id], |row| {
                 Ok(Self::from_row_fetch_original(row, db))
@@ -506,6 +814,10 @@ impl SuggestedExample {
                 RETURNING example_id;
         ";
 
+        // An alignment from the example's corpus doesn't fit the sentences once they are edited
+        const CLEAR_ALIGNMENT: &str =
+            "UPDATE example_sources SET alignment = NULL WHERE example_id = ?1;";
+
         let conn = db.get().unwrap();
         let word = match self.word_or_suggested_id {
             WordOrSuggestionId::ExistingWord { existing_id } => existing_id,
@@ -518,12 +830,21 @@ impl SuggestedExample {
             self.xhosa.current()
         ];
 
-        let id = conn
-            .prepare(INSERT)
+        let id: i64 = conn
+            .prepare_cached(INSERT)
             .unwrap()
             .query_row(params, |row| row.get("example_id"))
             .unwrap();
 
+        let edited = matches!(self.english, MaybeEdited::Edited { .. })
+            || matches!(self.xhosa, MaybeEdited::Edited { .. });
+        if edited {
+            conn.prepare_cached(CLEAR_ALIGNMENT)
+                .unwrap()
+                .execute(params![id])
+                .unwrap();
+        }
+
         add_user_attribution(db, &self.suggesting_user, WordId(word));
         SuggestedExample::delete(db, self.suggestion_id);
 
@@ -537,7 +858,11 @@ impl SuggestedExample {
         const DELETE: &str = "DELETE FROM example_suggestions WHERE suggestion_id = ?1;";
 
         let conn = db.get().unwrap();
-        let modified_rows = conn.prepare(DELETE).unwrap().execute(params![id]).unwrap();
+        let modified_rows = conn
+            .prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![id])
+            .unwrap();
         let found = modified_rows == 1;
         Span::current().record("found", found);
         found
@@ -546,8 +871,10 @@ impl SuggestedExample {
     fn from_row_fetch_original(row: &Row<'_>, db: &impl UserAccessDb) -> Self {
         let existing_id = row.get::<&str, Option<i64>>("existing_example_id").unwrap();
         let e = existing_id.and_then(|id| ExistingExample::fetch(db, id as u64));
-        let e = e.as_ref();
+        SuggestedExample::from_row_with_original(row, e.as_ref())
+    }
 
+    fn from_row_with_original(row: &Row<'_>, e: Option<&ExistingExample>) -> Self {
         SuggestedExample {
             suggesting_user: PublicUserInfo::try_from(row).unwrap(),
             changes_summary: row.get("changes_summary").unwrap(),
@@ -560,6 +887,25 @@ impl SuggestedExample {
     }
 }
 
+/// Why a suggested linked word could not be accepted
+#[derive(Copy, Clone, Debug)]
+pub enum InvalidLinkedWord {
+    /// One of the words does not exist, e.g because it has since been deleted
+    WordNotFound(u64),
+    LinkedToItself(u64),
+}
+
+impl fmt::Display for InvalidLinkedWord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidLinkedWord::WordNotFound(id) => write!(f, "linked word {id} does not exist"),
+            InvalidLinkedWord::LinkedToItself(id) => write!(f, "word {id} is linked to itself"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidLinkedWord {}
+
 #[derive(Clone, Debug)]
 pub struct SuggestedLinkedWord {
     pub changes_summary: String,
@@ -595,7 +941,7 @@ impl SuggestedLinkedWord {
 
         let conn = db.get().unwrap();
         let s = conn
-            .prepare(SELECT_SUGGESTION)
+            .prepare_cached(SELECT_SUGGESTION)
             .unwrap()
             .query_row(params![suggestion], |row| {
                 Ok(SuggestedLinkedWord::from_row_populate_both(row, db))
@@ -626,7 +972,7 @@ impl SuggestedLinkedWord {
         ";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT_SUGGESTION).unwrap();
+        let mut query = conn.prepare_cached(SELECT_SUGGESTION).unwrap();
         let rows = query.query(params![suggested_word_id]).unwrap();
 
         let mut vec: Vec<SuggestedLinkedWord> = rows
@@ -639,6 +985,61 @@ impl SuggestedLinkedWord {
         vec
     }
 
+    /// Fetch the suggested linked words of all suggested words, keyed by the suggested word. As
+    /// with [`SuggestedLinkedWord::fetch_all_for_suggestion`], a link between two suggested words
+    /// shows up for both of them.
+    #[instrument(
+        level = "trace",
+        name = "Fetch all suggested linked words for all suggested words",
+        fields(results),
+        skip(db, _i18n_info)
+    )]
+    pub fn fetch_all_for_all_suggestions(
+        db: &impl UserAccessDb,
+        _i18n_info: &I18nInfo,
+    ) -> HashMap<u64, Vec<SuggestedLinkedWord>> {
+        const SELECT_SUGGESTIONS: &str = "
+            SELECT suggestion_id, link_type, changes_summary, existing_linked_word_id,
+                first_existing_word_id, second_existing_word_id, suggested_word_id,
+                second_suggested_word_id, username, display_name, suggesting_user
+            FROM linked_word_suggestions
+            INNER JOIN users ON linked_word_suggestions.suggesting_user = users.user_id
+            WHERE suggested_word_id IS NOT NULL OR second_suggested_word_id IS NOT NULL;
+        ";
+
+        let conn = db.get().unwrap();
+        let mut query = conn.prepare_cached(SELECT_SUGGESTIONS).unwrap();
+        let mut map: HashMap<u64, Vec<SuggestedLinkedWord>> = HashMap::new();
+
+        query
+            .query(params![])
+            .unwrap()
+            .for_each(|row| {
+                let first = row.get::<&str, Option<u64>>("suggested_word_id")?;
+                let second = row.get::<&str, Option<u64>>("second_suggested_word_id")?;
+                let link = SuggestedLinkedWord::from_row_populate_both(row, db);
+
+                if let Some(second) = second.filter(|second| Some(*second) != first) {
+                    map.entry(second).or_default().push(link.clone());
+                }
+
+                if let Some(first) = first {
+                    map.entry(first).or_default().push(link);
+                }
+
+                Ok(())
+            })
+            .unwrap();
+
+        for links in map.values_mut() {
+            links.sort_by_key(|link| *link.link_type.current());
+        }
+
+        Span::current().record("results", map.len());
+
+        map
+    }
+
     /// Each link shows up once and only once.
     #[instrument(
         name = "Fetch all suggested linked words for existing words",
@@ -676,7 +1077,7 @@ impl SuggestedLinkedWord {
         ";
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(SELECT).unwrap();
+        let mut query = conn.prepare_cached(SELECT).unwrap();
         let examples = query.query(params![]).unwrap();
 
         let mut map: HashMap<WordId, Vec<SuggestedLinkedWord>> = HashMap::new();
@@ -709,7 +1110,9 @@ impl SuggestedLinkedWord {
         map.into_iter()
     }
 
-    // TODO(error handling)
+    /// Accept the link, after checking that both words still exist. Links are read from both
+    /// ends, so a single row is already reciprocal; if the same link already exists in either
+    /// direction it is reused rather than being shown twice.
     #[instrument(
         name = "Accept suggested linked word",
         fields(
@@ -719,7 +1122,17 @@ impl SuggestedLinkedWord {
         )
         skip_all,
     )]
-    pub fn accept(&self, db: &impl ModeratorAccessDb) -> i64 {
+    pub fn accept(&self, db: &impl ModeratorAccessDb) -> Result<i64, InvalidLinkedWord> {
+        const WORD_EXISTS: &str = "SELECT EXISTS(SELECT 1 FROM words WHERE word_id = ?1);";
+        const SELECT_SAME_LINK: &str = "
+            SELECT link_id FROM linked_words
+                WHERE link_type = ?1 AND link_id IS NOT ?4 AND (
+                    (first_word_id = ?2 AND second_word_id = ?3) OR
+                    (first_word_id = ?3 AND second_word_id = ?2)
+                )
+                LIMIT 1;
+        ";
+        const DELETE_LINK: &str = "DELETE FROM linked_words WHERE link_id = ?1;";
         const INSERT: &str = "
             INSERT INTO linked_words (link_id, link_type, first_word_id, second_word_id)
                 VALUES (?1, ?2, ?3, ?4)
@@ -737,26 +1150,62 @@ impl SuggestedLinkedWord {
 
         let (first, second) = (get_existing(&self.first), get_existing(&self.second));
 
-        let params = params![
-            self.existing_linked_word_id,
-            self.link_type.current(),
-            first,
-            second,
-        ];
+        if first == second {
+            return Err(InvalidLinkedWord::LinkedToItself(first));
+        }
 
-        let id = conn
-            .prepare(INSERT)
+        for word in [first, second] {
+            let exists: bool = conn
+                .prepare_cached(WORD_EXISTS)
+                .unwrap()
+                .query_row(params![word], |row| row.get(0))
+                .unwrap();
+
+            if !exists {
+                return Err(InvalidLinkedWord::WordNotFound(word));
+            }
+        }
+
+        let link_type = self.link_type.current();
+        let same_link: Option<i64> = conn
+            .prepare_cached(SELECT_SAME_LINK)
             .unwrap()
-            .query_row(params, |row| row.get("link_id"))
+            .query_row(
+                params![link_type, first, second, self.existing_linked_word_id],
+                |row| row.get("link_id"),
+            )
+            .optional()
             .unwrap();
 
+        let id = match same_link {
+            Some(same_link) => {
+                // Merge an edited link into the one it now duplicates
+                if let Some(existing) = self.existing_linked_word_id {
+                    conn.prepare_cached(DELETE_LINK)
+                        .unwrap()
+                        .execute(params![existing])
+                        .unwrap();
+                }
+
+                same_link
+            }
+            None => {
+                let params = params![self.existing_linked_word_id, link_type, first, second];
+
+                conn.prepare_cached(INSERT)
+                    .unwrap()
+                    .query_row(params, |row| row.get("link_id"))
+                    .unwrap()
+            }
+        };
+
         add_user_attribution(db, &self.suggesting_user, WordId(first));
         add_user_attribution(db, &self.suggesting_user, WordId(second));
         SuggestedLinkedWord::delete(db, self.suggestion_id);
 
         Span::current().record("accepted_id", id);
 
-        id
+        Ok(id)
     }
 
     #[instrument(
@@ -783,7 +1232,10 @@ impl SuggestedLinkedWord {
         ];
 
         let conn = db.get().unwrap();
-        conn.prepare(UPDATE).unwrap().execute(params).unwrap();
+        conn.prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params)
+            .unwrap();
     }
 
     #[instrument(name = "Delete suggested linked word", fields(found), skip(db))]
@@ -791,7 +1243,11 @@ impl SuggestedLinkedWord {
         const DELETE: &str = "DELETE FROM linked_word_suggestions WHERE suggestion_id = ?1;";
 
         let conn = db.get().unwrap();
-        let modified_rows = conn.prepare(DELETE).unwrap().execute(params![id]).unwrap();
+        let modified_rows = conn
+            .prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![id])
+            .unwrap();
         let found = modified_rows == 1;
         Span::current().record("found", found);
         found
@@ -813,7 +1269,7 @@ impl SuggestedLinkedWord {
             .unwrap();
         let (other_type, other_first, other_second) = if let Some(id) = existing_id {
             let trio = conn
-                .prepare(SELECT)
+                .prepare_cached(SELECT)
                 .unwrap()
                 .query_row(params![id], |r| {
                     Ok((
@@ -949,7 +1405,7 @@ impl DatasetAttributionSuggestion {
         );
 
         let conn = db.get().unwrap();
-        let mut query = conn.prepare(&select).unwrap();
+        let mut query = conn.prepare_cached(&select).unwrap();
         let suggestions = query.query(params).unwrap();
 
         let mut map: HashMap<WordOrSuggestionId, Vec<Self>> = HashMap::new();
@@ -1038,7 +1494,7 @@ impl DatasetAttributionSuggestion {
 
         db.get()
             .unwrap()
-            .prepare(sql)
+            .prepare_cached(sql)
             .unwrap()
             .execute(params![self.dataset.id, word_id])
             .unwrap();
@@ -1053,7 +1509,11 @@ impl DatasetAttributionSuggestion {
             "DELETE FROM dataset_attribution_suggestions WHERE suggestion_id = ?1;";
 
         let conn = db.get().unwrap();
-        let modified_rows = conn.prepare(DELETE).unwrap().execute(params![id]).unwrap();
+        let modified_rows = conn
+            .prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![id])
+            .unwrap();
         let found = modified_rows == 1;
         Span::current().record("found", found);
         found
@@ -1127,6 +1587,13 @@ impl<T: Debug> MaybeEdited<Option<T>> {
     }
 }
 
+impl MaybeEdited<String> {
+    /// Render the old and new text side by side, highlighting the characters which changed
+    pub fn diffed(&self) -> DiffedText<'_> {
+        DiffedText(self)
+    }
+}
+
 impl MaybeEdited<WordHit> {
     pub fn hyperlinked(&self) -> MaybeEdited<HyperlinkWrapper<'_>> {
         self.map(HyperlinkWrapper)
@@ -1204,6 +1671,48 @@ impl<L: Loader + 'static, T: DisplayHtml<L>> DisplayHtml<L> for MaybeEdited<T> {
     }
 }
 
+pub struct DiffedText<'a>(&'a MaybeEdited<String>);
+
+impl DiffedText<'_> {
+    fn fmt_side<L: Loader + 'static>(
+        f: &mut HtmlFormatter<L>,
+        diff: &TextDiff<'_, '_, '_, str>,
+        changed: ChangeTag,
+        (open, close): (&str, &str),
+    ) -> fmt::Result {
+        for change in diff.iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Equal => f.write_raw_str(change.value())?,
+                t if t == changed => {
+                    f.write_unescaped_str(open)?;
+                    f.write_raw_str(change.value())?;
+                    f.write_unescaped_str(close)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<L: Loader + 'static> DisplayHtml<L> for DiffedText<'_> {
+    fn fmt(&self, f: &mut HtmlFormatter<L>) -> fmt::Result {
+        let (old, new) = match self.0 {
+            MaybeEdited::Edited { old, new } if !old.is_empty() && !new.is_empty() => (old, new),
+            other => return other.fmt(f),
+        };
+
+        let diff = TextDiff::from_chars(old.as_str(), new.as_str());
+
+        f.write_unescaped_str("<span class=\"diff\"><span class=\"diff_old\">")?;
+        Self::fmt_side(f, &diff, ChangeTag::Delete, ("<del>", "</del>"))?;
+        f.write_unescaped_str("</span> <span class=\"diff_new\">")?;
+        Self::fmt_side(f, &diff, ChangeTag::Insert, ("<ins>", "</ins>"))?;
+        f.write_unescaped_str("</span></span>")
+    }
+}
+
 trait TextIfBoolIn {
     fn into_maybe_edited(self) -> MaybeEdited<bool>;
 }
@@ -1243,14 +1752,14 @@ impl<L: Loader + 'static> DisplayHtml<L> for SuggestedWord {
         f.write_unescaped_str("<span lang=\"")?;
         f.write_text(&TranslationKey::new("source-language-code"))?;
         f.write_unescaped_str("\">")?;
-        DisplayHtml::fmt(&self.english, f)?;
+        DisplayHtml::fmt(&self.english.diffed(), f)?;
         f.write_unescaped_str("</span>")?;
 
         f.write_unescaped_str(" - <span lang=\"")?;
         f.write_text(&TranslationKey::new("target-language-code"))?;
         f.write_unescaped_str("\">")?;
 
-        DisplayHtml::fmt(&self.xhosa, f)?;
+        DisplayHtml::fmt(&self.xhosa.diffed(), f)?;
         f.write_unescaped_str("</span> (")?;
 
         f.join_if_non_empty(
@@ -1262,6 +1771,12 @@ impl<L: Loader + 'static> DisplayHtml<L> for SuggestedWord {
                     self.is_informal,
                     false,
                 ),
+                &text_if_bool(
+                    TranslationKey::new("offensive.in-word-result"),
+                    TranslationKey::new("offensive.non"),
+                    self.is_offensive,
+                    false,
+                ),
                 &text_if_bool(
                     TranslationKey::new("inchoative.in-word-result"),
                     TranslationKey::new("inchoative.non"),
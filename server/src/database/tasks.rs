@@ -0,0 +1,159 @@
+//! Small, specific things contributors can do to improve the dictionary, such as giving a verb its
+//! transitivity or adding a word which people searched for but couldn't find
+
+use crate::error::DbError;
+use chrono::Utc;
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::language::PartOfSpeech;
+use isixhosa_common::types::WordHit;
+use rusqlite::params;
+use tracing::{instrument, Span};
+
+/// A kind of task on an existing word, each of which is fixed on the word's edit page
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WordTask {
+    MissingExample,
+    NounMissingClass,
+    VerbMissingTransitivity,
+}
+
+impl WordTask {
+    pub const ALL: [WordTask; 3] = [
+        WordTask::MissingExample,
+        WordTask::NounMissingClass,
+        WordTask::VerbMissingTransitivity,
+    ];
+
+    /// The part of the edit form which the task is done in
+    pub fn anchor(&self) -> &'static str {
+        match self {
+            WordTask::MissingExample => "examples",
+            WordTask::NounMissingClass => "noun_class",
+            WordTask::VerbMissingTransitivity => "transitivity",
+        }
+    }
+
+    pub fn translation_key(&self) -> &'static str {
+        match self {
+            WordTask::MissingExample => "tasks.missing-example",
+            WordTask::NounMissingClass => "tasks.noun-missing-class",
+            WordTask::VerbMissingTransitivity => "tasks.verb-missing-transitivity",
+        }
+    }
+
+    fn condition(&self) -> &'static str {
+        match self {
+            WordTask::MissingExample => {
+                "NOT EXISTS (SELECT 1 FROM examples WHERE examples.word_id = words.word_id)"
+            }
+            WordTask::NounMissingClass => "part_of_speech = ?2 AND noun_class IS NULL",
+            WordTask::VerbMissingTransitivity => "part_of_speech = ?2 AND transitivity IS NULL",
+        }
+    }
+
+    fn part_of_speech(&self) -> Option<PartOfSpeech> {
+        match self {
+            WordTask::MissingExample => None,
+            WordTask::NounMissingClass => Some(PartOfSpeech::Noun),
+            WordTask::VerbMissingTransitivity => Some(PartOfSpeech::Verb),
+        }
+    }
+}
+
+/// Words which the task needs doing on, the most viewed first so that work goes where it's seen
+#[instrument(name = "Fetch words for task", fields(results), skip(db))]
+pub fn fetch_word_tasks(db: &impl PublicAccessDb, task: WordTask, limit: u64) -> Vec<WordHit> {
+    let select = format!(
+        "
+        SELECT
+            word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM words
+        WHERE {}
+        ORDER BY IFNULL(
+            (SELECT SUM(views) FROM word_views WHERE word_views.word_id = words.word_id), 0
+        ) DESC, word_id
+        LIMIT ?1;
+    ",
+        task.condition()
+    );
+
+    let conn = db.get().unwrap();
+    let mut stmt = conn.prepare_cached(&select).unwrap();
+    let rows = match task.part_of_speech() {
+        Some(part_of_speech) => stmt.query(params![limit, part_of_speech]),
+        None => stmt.query(params![limit]),
+    };
+
+    let words: Vec<WordHit> = rows
+        .unwrap()
+        .map(|row| {
+            let id = row.get::<&str, i64>("word_id")? as u64;
+            WordHit::try_from_row_and_id(row, WordOrSuggestionId::existing(id))
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", words.len());
+
+    words
+}
+
+/// Something which was searched for but not found
+#[derive(Debug)]
+pub struct MissedSearch {
+    pub query: String,
+    pub times: u64,
+}
+
+/// Note that a search found nothing, so that the word can be suggested to contributors
+#[instrument(name = "Record missed search", skip(db))]
+pub fn record_missed_search(db: &impl PublicAccessDb, query: &str) -> Result<(), DbError> {
+    const UPSERT: &str = "
+        INSERT INTO missed_searches (query, times, last_searched) VALUES (?1, 1, ?2)
+            ON CONFLICT(query) DO UPDATE SET
+                times = times + 1,
+                last_searched = excluded.last_searched;
+    ";
+
+    let query = query.trim().to_lowercase();
+    db.get()?
+        .prepare_cached(UPSERT)?
+        .execute(params![query, Utc::now()])?;
+    Ok(())
+}
+
+/// The most searched for words which couldn't be found, leaving out any which have since been added
+#[instrument(name = "Fetch missed searches", fields(results), skip(db))]
+pub fn fetch_missed_searches(db: &impl PublicAccessDb, limit: u64) -> Vec<MissedSearch> {
+    const SELECT: &str = "
+        SELECT query, times FROM missed_searches
+        WHERE NOT EXISTS (
+            SELECT 1 FROM words
+            WHERE LOWER(words.english) = missed_searches.query
+                OR LOWER(words.xhosa) = missed_searches.query
+        )
+        ORDER BY times DESC, last_searched DESC
+        LIMIT ?1;
+    ";
+
+    let conn = db.get().unwrap();
+    let searches: Vec<MissedSearch> = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query(params![limit])
+        .unwrap()
+        .map(|row| {
+            Ok(MissedSearch {
+                query: row.get("query")?,
+                times: row.get("times")?,
+            })
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", searches.len());
+
+    searches
+}
@@ -0,0 +1,41 @@
+//! Which versions of the terms of use and contributor license each user has accepted, and when, so
+//! that it can be shown under which terms their contributions were made
+
+use crate::error::DbError;
+use chrono::Utc;
+use isixhosa_common::database::PublicAccessDb;
+use rusqlite::params;
+use tracing::{instrument, Span};
+
+#[instrument(name = "Check whether user accepted terms", fields(accepted), skip(db))]
+pub fn has_accepted(db: &impl PublicAccessDb, user_id: u64, version: u32) -> Result<bool, DbError> {
+    const SELECT: &str =
+        "SELECT EXISTS(SELECT 1 FROM terms_acceptances WHERE user_id = ?1 AND version = ?2);";
+
+    let accepted = db
+        .get()?
+        .prepare_cached(SELECT)?
+        .query_row(params![user_id, version], |row| row.get(0))?;
+
+    Span::current().record("accepted", accepted);
+    Ok(accepted)
+}
+
+/// Record that the user accepted the given version of the terms. Accepting it again keeps the time
+/// it was first accepted.
+#[instrument(name = "Record terms acceptance", skip(db))]
+pub fn record_acceptance(
+    db: &impl PublicAccessDb,
+    user_id: u64,
+    version: u32,
+) -> Result<(), DbError> {
+    const INSERT: &str = "
+        INSERT INTO terms_acceptances (user_id, version, accepted) VALUES (?1, ?2, ?3)
+            ON CONFLICT(user_id, version) DO NOTHING;
+    ";
+
+    db.get()?
+        .prepare_cached(INSERT)?
+        .execute(params![user_id, version, Utc::now()])?;
+    Ok(())
+}
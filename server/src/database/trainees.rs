@@ -0,0 +1,33 @@
+//! Trainee moderators can do everything that other moderators can, but must tick off the
+//! configured checklist before accepting a suggested word.
+
+use isixhosa_common::database::{AdministratorAccessDb, ModeratorAccessDb};
+use rusqlite::{params, OptionalExtension};
+use tracing::instrument;
+
+#[instrument(level = "trace", name = "Check if moderator is trainee", skip(db))]
+pub fn is_trainee(db: &impl ModeratorAccessDb, user_id: u64) -> bool {
+    const SELECT: &str = "SELECT 1 FROM trainee_moderators WHERE user_id = ?1;";
+
+    db.get()
+        .unwrap()
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query_row(params![user_id], |_| Ok(()))
+        .optional()
+        .unwrap()
+        .is_some()
+}
+
+#[instrument(level = "info", name = "Set moderator trainee status", skip(db))]
+pub fn set_trainee(db: &impl AdministratorAccessDb, user_id: u64, trainee: bool) -> bool {
+    const INSERT: &str = "INSERT OR IGNORE INTO trainee_moderators (user_id) VALUES (?1);";
+    const DELETE: &str = "DELETE FROM trainee_moderators WHERE user_id = ?1;";
+
+    db.get()
+        .unwrap()
+        .prepare_cached(if trainee { INSERT } else { DELETE })
+        .unwrap()
+        .execute(params![user_id])
+        .is_ok()
+}
@@ -1,10 +1,14 @@
 use crate::auth::{random_string_token, FullUser, StaySignedInToken};
 use anyhow::anyhow;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use fallible_iterator::FallibleIterator;
 use fluent_templates::LanguageIdentifier;
 use isixhosa_common::auth::Permissions;
-use isixhosa_common::database::{ModeratorAccessDb, PublicAccessDb, UserAccessDb};
+use isixhosa_common::database::{
+    AdministratorAccessDb, ModeratorAccessDb, PublicAccessDb, UserAccessDb,
+};
+use isixhosa_common::search_direction::SearchDirection;
+use isixhosa_common::theme::Theme;
 use openid::{Token, Userinfo};
 use r2d2_sqlite::rusqlite::Row;
 use rusqlite::{params, OptionalExtension};
@@ -12,7 +16,7 @@ use sha2::Digest;
 use std::convert::TryFrom;
 use std::num::NonZeroU64;
 use subtle::ConstantTimeEq;
-use tracing::{debug_span, instrument, Span};
+use tracing::{debug_span, instrument, warn, Span};
 
 impl TryFrom<&Row<'_>> for FullUser {
     type Error = rusqlite::Error;
@@ -33,6 +37,11 @@ impl TryFrom<&Row<'_>> for FullUser {
             },
             locked: row.get("locked")?,
             language: row.get::<&str, String>("language")?.parse().unwrap(),
+            theme: row.get("theme")?,
+            search_direction: row.get("search_direction")?,
+            unread_notifications: row.get("unread_notifications")?,
+            csrf_token: None,
+            authenticated: None,
         })
     }
 }
@@ -42,17 +51,24 @@ impl FullUser {
     pub fn fetch_by_id(db: &impl PublicAccessDb, id: u64) -> Option<FullUser> {
         const SELECT: &str = "
             SELECT
-                user_id, username, display_name, email, is_moderator, is_administrator, locked,
-                language
+                users.user_id, username, display_name, email, is_moderator, is_administrator,
+                locked, language, COALESCE(user_preferences.theme, 0) AS theme,
+                COALESCE(search_preferences.direction, 0) AS search_direction,
+                (
+                    SELECT COUNT(*) FROM notifications
+                        WHERE notifications.user_id = users.user_id AND NOT read
+                ) AS unread_notifications
             FROM users
-            WHERE user_id = ?1;
+            LEFT JOIN user_preferences ON user_preferences.user_id = users.user_id
+            LEFT JOIN search_preferences ON search_preferences.user_id = users.user_id
+            WHERE users.user_id = ?1;
         ";
 
         let conn = db.get().unwrap();
 
         #[allow(clippy::redundant_closure)] // lifetime issue
         let user = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query_row(params![id], |row| FullUser::try_from(row))
             .optional()
@@ -71,9 +87,16 @@ impl FullUser {
     ) -> Option<FullUser> {
         const SELECT: &str = "
             SELECT
-                user_id, username, display_name, email, is_moderator, is_administrator, locked,
-                language
+                users.user_id, username, display_name, email, is_moderator, is_administrator,
+                locked, language, COALESCE(user_preferences.theme, 0) AS theme,
+                COALESCE(search_preferences.direction, 0) AS search_direction,
+                (
+                    SELECT COUNT(*) FROM notifications
+                        WHERE notifications.user_id = users.user_id AND NOT read
+                ) AS unread_notifications
             FROM users
+            LEFT JOIN user_preferences ON user_preferences.user_id = users.user_id
+            LEFT JOIN search_preferences ON search_preferences.user_id = users.user_id
             WHERE oidc_id = ?1;
         ";
 
@@ -81,7 +104,7 @@ impl FullUser {
 
         #[allow(clippy::redundant_closure)] // lifetime issue
         let user = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query_row(params![oidc_id], |row| FullUser::try_from(row))
             .optional()
@@ -96,16 +119,23 @@ impl FullUser {
     pub fn fetch_all(db: &impl ModeratorAccessDb) -> Vec<FullUser> {
         const SELECT: &str = "
             SELECT
-                user_id, username, display_name, email, is_moderator, is_administrator, locked,
-                language
-            FROM users;
+                users.user_id, username, display_name, email, is_moderator, is_administrator,
+                locked, language, COALESCE(user_preferences.theme, 0) AS theme,
+                COALESCE(search_preferences.direction, 0) AS search_direction,
+                (
+                    SELECT COUNT(*) FROM notifications
+                        WHERE notifications.user_id = users.user_id AND NOT read
+                ) AS unread_notifications
+            FROM users
+            LEFT JOIN user_preferences ON user_preferences.user_id = users.user_id
+            LEFT JOIN search_preferences ON search_preferences.user_id = users.user_id;
         ";
 
         let conn = db.get().unwrap();
 
         #[allow(clippy::redundant_closure)] // lifetime issue
         let users = conn
-            .prepare(SELECT)
+            .prepare_cached(SELECT)
             .unwrap()
             .query(params!())
             .unwrap()
@@ -116,6 +146,42 @@ impl FullUser {
         users
     }
 
+    /// Users whose username or email address contains the query
+    #[instrument(level = "info", name = "Search users", fields(results), skip(db))]
+    pub fn search(db: &impl AdministratorAccessDb, query: &str) -> Vec<FullUser> {
+        const SELECT: &str = "
+            SELECT
+                users.user_id, username, display_name, email, is_moderator, is_administrator,
+                locked, language, COALESCE(user_preferences.theme, 0) AS theme,
+                COALESCE(search_preferences.direction, 0) AS search_direction,
+                (
+                    SELECT COUNT(*) FROM notifications
+                        WHERE notifications.user_id = users.user_id AND NOT read
+                ) AS unread_notifications
+            FROM users
+            LEFT JOIN user_preferences ON user_preferences.user_id = users.user_id
+            LEFT JOIN search_preferences ON search_preferences.user_id = users.user_id
+            WHERE instr(lower(username), lower(?1)) > 0 OR instr(lower(email), lower(?1)) > 0
+            ORDER BY users.user_id;
+        ";
+
+        let conn = db.get().unwrap();
+
+        #[allow(clippy::redundant_closure)] // lifetime issue
+        let users: Vec<FullUser> = conn
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query(params![query])
+            .unwrap()
+            .map(|row| FullUser::try_from(row))
+            .collect()
+            .unwrap();
+
+        Span::current().record("results", users.len());
+
+        users
+    }
+
     #[instrument(level = "info", name = "Set user role", skip(db))]
     pub fn set_role_by_email(
         db: &impl ModeratorAccessDb,
@@ -135,7 +201,7 @@ impl FullUser {
 
         #[allow(clippy::redundant_closure)] // lifetime issue
         let changed = conn
-            .prepare(UPDATE)
+            .prepare_cached(UPDATE)
             .unwrap()
             .execute(params![is_mod, is_admin, email])
             .unwrap();
@@ -151,7 +217,7 @@ impl FullUser {
 
         #[allow(clippy::redundant_closure)] // lifetime issue
         let changed = conn
-            .prepare(UPDATE)
+            .prepare_cached(UPDATE)
             .unwrap()
             .execute(params![locked, email])
             .unwrap();
@@ -159,6 +225,61 @@ impl FullUser {
         changed != 0
     }
 
+    #[instrument(level = "info", name = "Set user role", skip(db))]
+    pub fn set_role(db: &impl AdministratorAccessDb, user_id: u64, role: Permissions) -> bool {
+        const UPDATE: &str =
+            "UPDATE users SET is_moderator = ?1, is_administrator = ?2 WHERE user_id = ?3;";
+
+        let (is_mod, is_admin) = match role {
+            Permissions::User => (false, false),
+            Permissions::Moderator => (true, false),
+            Permissions::Administrator => (true, true),
+        };
+
+        let conn = db.get().unwrap();
+        let changed = conn
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![is_mod, is_admin, user_id])
+            .unwrap();
+
+        changed != 0
+    }
+
+    #[instrument(level = "info", name = "Set user to locked", skip(db))]
+    pub fn set_locked(db: &impl AdministratorAccessDb, user_id: u64, locked: bool) -> bool {
+        const UPDATE: &str = "UPDATE users SET locked = ?1 WHERE user_id = ?2;";
+
+        let conn = db.get().unwrap();
+        let changed = conn
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![locked, user_id])
+            .unwrap();
+
+        changed != 0
+    }
+
+    /// Lock the account of a user who submitted spam. Moderators can't be locked this way.
+    #[instrument(level = "info", name = "Lock spam submitter", fields(found), skip(db))]
+    pub fn lock_spam_submitter(db: &impl ModeratorAccessDb, user_id: u64) -> bool {
+        const UPDATE: &str = "
+            UPDATE users SET locked = TRUE
+                WHERE user_id = ?1 AND NOT is_moderator AND NOT is_administrator;
+        ";
+
+        let conn = db.get().unwrap();
+        let changed = conn
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![user_id])
+            .unwrap();
+
+        let found = changed != 0;
+        Span::current().record("found", found);
+        found
+    }
+
     #[instrument(level = "info", name = "Update user settings", skip(db))]
     pub fn update_settings(
         &mut self,
@@ -179,7 +300,7 @@ impl FullUser {
 
         let conn = db.get().unwrap();
 
-        let changed = conn.prepare(UPDATE)?.execute(params![
+        let changed = conn.prepare_cached(UPDATE)?.execute(params![
             self.display_name,
             self.username,
             self.language.to_string(),
@@ -193,6 +314,42 @@ impl FullUser {
         }
     }
 
+    #[instrument(name = "Update user theme", skip(db))]
+    pub fn update_theme(&mut self, db: &impl UserAccessDb, theme: Theme) -> anyhow::Result<()> {
+        const UPSERT: &str = "
+            INSERT INTO user_preferences (user_id, theme) VALUES (?1, ?2)
+                ON CONFLICT(user_id) DO UPDATE SET theme = excluded.theme;
+        ";
+
+        self.theme = theme;
+
+        let conn = db.get().unwrap();
+        conn.prepare_cached(UPSERT)?
+            .execute(params![self.id.get(), self.theme])?;
+
+        Ok(())
+    }
+
+    #[instrument(name = "Update user search direction", skip(db))]
+    pub fn update_search_direction(
+        &mut self,
+        db: &impl UserAccessDb,
+        direction: SearchDirection,
+    ) -> anyhow::Result<()> {
+        const UPSERT: &str = "
+            INSERT INTO search_preferences (user_id, direction) VALUES (?1, ?2)
+                ON CONFLICT(user_id) DO UPDATE SET direction = excluded.direction;
+        ";
+
+        self.search_direction = direction;
+
+        let conn = db.get().unwrap();
+        conn.prepare_cached(UPSERT)?
+            .execute(params![self.id.get(), self.search_direction])?;
+
+        Ok(())
+    }
+
     #[instrument(name = "Register user", skip(db, userinfo))]
     pub fn register(
         db: &impl PublicAccessDb,
@@ -210,7 +367,7 @@ impl FullUser {
         ";
 
         let conn = db.get().unwrap();
-        let mut stmt = conn.prepare(INSERT).unwrap();
+        let mut stmt = conn.prepare_cached(INSERT).unwrap();
         let params = params![
             userinfo.sub.unwrap(),
             username.trim(),
@@ -232,32 +389,67 @@ impl FullUser {
             permissions,
             locked: false,
             language,
+            theme: Theme::default(),
+            search_direction: SearchDirection::default(),
+            unread_notifications: 0,
+            csrf_token: None,
+            authenticated: None,
         }
     }
 }
 
+/// Hash a token with a random salt, encoded as `salt_hash`
+pub(super) fn hash_token(token: &str) -> String {
+    let salt = random_string_token();
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(&salt);
+    hasher.update(token);
+    format!("{salt}_{:x}", hasher.finalize())
+}
+
+/// Check a token against a hash from [`hash_token`] in constant time
+pub(super) fn verify_hash(encoded: &str, token: &str) -> bool {
+    let Some((salt, hash)) = encoded.split_once('_') else {
+        return false;
+    };
+
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(salt);
+    hasher.update(token);
+    let our_hash = format!("{:x}", hasher.finalize());
+
+    our_hash.as_bytes().ct_eq(hash.as_bytes()).into()
+}
+
+/// The outcome of using a refresh token to start a new session
+pub enum Refresh {
+    /// The refresh token was valid, and it has been replaced along with the session token
+    Refreshed {
+        session: StaySignedInToken,
+        refresh: StaySignedInToken,
+    },
+    /// The refresh token was replaced moments ago by another request from the same browser
+    AlreadyRefreshed,
+    /// The refresh token is unknown, expired, or has been revoked
+    Invalid,
+}
+
 impl StaySignedInToken {
+    /// Start a new session for a user who has just signed in with their provider
     pub fn new(db: &impl PublicAccessDb, user_id: u64) -> Self {
         const INSERT: &str = "
-            INSERT INTO login_tokens (token_hash, user_id, last_used)
-            VALUES (?1, ?2, ?3)
+            INSERT INTO login_tokens (token_hash, user_id, last_used, authenticated)
+            VALUES (?1, ?2, ?3, ?3)
             RETURNING token_id;
         ";
 
-        let mut hasher = sha2::Sha256::new();
         let token = random_string_token();
-        let salt = random_string_token();
-        hasher.update(&salt);
-        hasher.update(&token);
-
-        let token_hash = format!("{:x}", hasher.finalize());
-        let token_encoded = format!("{salt}_{token_hash}");
 
         let conn = db.get().unwrap();
         let token_id: i64 = conn
-            .prepare(INSERT)
+            .prepare_cached(INSERT)
             .unwrap()
-            .query_row(params![token_encoded, user_id, Utc::now()], |row| {
+            .query_row(params![hash_token(&token), user_id, Utc::now()], |row| {
                 row.get("token_id")
             })
             .unwrap();
@@ -268,12 +460,203 @@ impl StaySignedInToken {
         }
     }
 
+    /// Keep the session going after the browser is restarted. Returns the refresh token which
+    /// starts the next session.
+    #[instrument(name = "Remember session", fields(token_id = self.token_id), skip_all)]
+    pub fn remember(&self, db: &impl PublicAccessDb) -> StaySignedInToken {
+        const UPDATE: &str = "
+            UPDATE login_tokens
+                SET refresh_hash = ?2, previous_refresh_hash = NULL, refreshed = ?3
+                WHERE token_id = ?1;
+        ";
+
+        let token = random_string_token();
+
+        db.get()
+            .unwrap()
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![self.token_id, hash_token(&token), Utc::now()])
+            .unwrap();
+
+        StaySignedInToken {
+            token,
+            token_id: self.token_id,
+        }
+    }
+
+    /// Stop the session from outliving the browser, revoking its refresh token
+    #[instrument(name = "Forget session", fields(token_id = self.token_id), skip_all)]
+    pub fn forget(&self, db: &impl UserAccessDb) {
+        const UPDATE: &str = "
+            UPDATE login_tokens SET refresh_hash = NULL, previous_refresh_hash = NULL
+                WHERE token_id = ?1;
+        ";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(UPDATE)
+            .unwrap()
+            .execute(params![self.token_id])
+            .unwrap();
+    }
+
+    #[instrument(name = "Check if session is remembered", fields(token_id = self.token_id), skip_all)]
+    pub fn is_remembered(&self, db: &impl UserAccessDb) -> bool {
+        const SELECT: &str =
+            "SELECT refresh_hash IS NOT NULL FROM login_tokens WHERE token_id = ?1;";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![self.token_id], |row| row.get(0))
+            .optional()
+            .unwrap()
+            .unwrap_or(false)
+    }
+
+    /// Start a new session from a refresh token, replacing both it and the session token. An old
+    /// refresh token being used again means that it was likely stolen, so the session is revoked.
+    #[instrument(name = "Refresh session", fields(token_id = refresh.token_id), skip_all)]
+    pub fn refresh(
+        db: &impl PublicAccessDb,
+        refresh: &StaySignedInToken,
+        remember_for: chrono::Duration,
+    ) -> Refresh {
+        const SELECT: &str = "
+            SELECT refresh_hash, previous_refresh_hash, refreshed, last_used
+                FROM login_tokens WHERE token_id = ?1;
+        ";
+        const UPDATE: &str = "
+            UPDATE login_tokens
+                SET token_hash = ?2, refresh_hash = ?3, previous_refresh_hash = refresh_hash,
+                    refreshed = ?4, last_used = ?4
+                WHERE token_id = ?1 AND refresh_hash = ?5;
+        ";
+        const DELETE: &str = "DELETE FROM login_tokens WHERE token_id = ?1;";
+        /// How long a replaced refresh token is still accepted for, since the browser may have
+        /// sent it with several requests at once
+        const REUSE_GRACE_SECS: i64 = 60;
+
+        type Hashes = (
+            Option<String>,
+            Option<String>,
+            Option<DateTime<Utc>>,
+            DateTime<Utc>,
+        );
+
+        let conn = db.get().unwrap();
+        let hashes: Option<Hashes> = conn
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![refresh.token_id], |row| {
+                Ok((
+                    row.get("refresh_hash")?,
+                    row.get("previous_refresh_hash")?,
+                    row.get("refreshed")?,
+                    row.get("last_used")?,
+                ))
+            })
+            .optional()
+            .unwrap();
+
+        let Some((current, previous, refreshed, last_used)) = hashes else {
+            return Refresh::Invalid;
+        };
+
+        let now = Utc::now();
+
+        match current {
+            Some(current) if verify_hash(&current, &refresh.token) => {
+                if now - last_used > remember_for {
+                    return Refresh::Invalid;
+                }
+
+                let (session_token, refresh_token) = (random_string_token(), random_string_token());
+                let params = params![
+                    refresh.token_id,
+                    hash_token(&session_token),
+                    hash_token(&refresh_token),
+                    now,
+                    current
+                ];
+
+                // Another request may have refreshed the session between the select and now
+                if conn
+                    .prepare_cached(UPDATE)
+                    .unwrap()
+                    .execute(params)
+                    .unwrap()
+                    == 0
+                {
+                    return Refresh::AlreadyRefreshed;
+                }
+
+                Refresh::Refreshed {
+                    session: StaySignedInToken {
+                        token: session_token,
+                        token_id: refresh.token_id,
+                    },
+                    refresh: StaySignedInToken {
+                        token: refresh_token,
+                        token_id: refresh.token_id,
+                    },
+                }
+            }
+            _ if previous.is_some_and(|previous| verify_hash(&previous, &refresh.token)) => {
+                let grace = chrono::Duration::seconds(REUSE_GRACE_SECS);
+
+                if refreshed.is_some_and(|refreshed| now - refreshed < grace) {
+                    Refresh::AlreadyRefreshed
+                } else {
+                    warn!("Replaced refresh token was used again; revoking the session");
+
+                    conn.prepare_cached(DELETE)
+                        .unwrap()
+                        .execute(params![refresh.token_id])
+                        .unwrap();
+
+                    Refresh::Invalid
+                }
+            }
+            _ => Refresh::Invalid,
+        }
+    }
+
     #[instrument(name = "Delete all stay-signed-in tokens", skip_all)]
     pub fn delete_all(db: &impl ModeratorAccessDb) {
         const DELETE: &str = "DELETE FROM login_tokens;";
 
         let conn = db.get().unwrap();
-        conn.prepare(DELETE).unwrap().execute(params![]).unwrap();
+        conn.prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![])
+            .unwrap();
+    }
+
+    /// Sign the user out on every device they stayed signed in on
+    #[instrument(name = "Delete user's stay-signed-in tokens", skip(db))]
+    pub fn delete_all_for_user(db: &impl AdministratorAccessDb, user_id: u64) {
+        const DELETE: &str = "DELETE FROM login_tokens WHERE user_id = ?1;";
+
+        let conn = db.get().unwrap();
+        conn.prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![user_id])
+            .unwrap();
+    }
+
+    /// Sign the user out on every device but this one. Returns the number of sessions ended.
+    #[instrument(name = "Delete user's other stay-signed-in tokens", fields(token_id = self.token_id), skip(self, db))]
+    pub fn delete_others(&self, db: &impl UserAccessDb, user_id: u64) -> usize {
+        const DELETE: &str = "DELETE FROM login_tokens WHERE user_id = ?1 AND token_id != ?2;";
+
+        let conn = db.get().unwrap();
+        conn.prepare_cached(DELETE)
+            .unwrap()
+            .execute(params![user_id, self.token_id])
+            .unwrap()
     }
 
     #[instrument(name = "Delete stay-signed-in token", fields(token_id = self.token_id), skip_all)]
@@ -281,53 +664,47 @@ impl StaySignedInToken {
         const DELETE: &str = "DELETE FROM login_tokens WHERE token_id = ?1;";
 
         let conn = db.get().unwrap();
-        conn.prepare(DELETE)
+        conn.prepare_cached(DELETE)
             .unwrap()
             .execute(params![self.token_id])
             .unwrap();
     }
 
-    /// Verifies the hash, returning the user id if successful
+    /// Verifies the hash, returning the user id and when they last signed in with their provider
+    /// if successful
     #[instrument(name = "Verify a user login token", fields(token_id = self.token_id), skip_all)]
-    pub fn verify_token(&self, db: &impl PublicAccessDb) -> Option<u64> {
-        const SELECT: &str = "SELECT token_hash, user_id FROM login_tokens WHERE token_id = ?1;";
+    pub fn verify_token(&self, db: &impl PublicAccessDb) -> Option<(u64, DateTime<Utc>)> {
+        const SELECT: &str = "
+            SELECT token_hash, user_id, authenticated FROM login_tokens WHERE token_id = ?1;
+        ";
         const UPDATE: &str = "UPDATE login_tokens SET last_used = ?1 WHERE token_id = ?2;";
 
         let conn = db.get().unwrap();
-        let (token_hash, user_id): (String, i64) =
+        let (token_hash, user_id, authenticated): (String, i64, DateTime<Utc>) =
             debug_span!("Fetch token hash").in_scope(|| {
-                conn.prepare(SELECT)
+                conn.prepare_cached(SELECT)
                     .unwrap()
                     .query_row(params![self.token_id], |row| {
-                        Ok((row.get("token_hash")?, row.get("user_id")?))
+                        Ok((
+                            row.get("token_hash")?,
+                            row.get("user_id")?,
+                            row.get("authenticated")?,
+                        ))
                     })
                     .optional()
                     .unwrap()
             })?;
 
-        let verified = debug_span!("Verify hash").in_scope(|| {
-            let (salt, hash) = token_hash.split_once('_')?;
-            let mut hasher = sha2::Sha256::new();
-            hasher.update(salt);
-            hasher.update(&self.token);
-            let our_hash = format!("{:x}", hasher.finalize());
-
-            let verified = our_hash.as_bytes().ct_eq(hash.as_bytes()).into();
-
-            if verified {
-                Some(())
-            } else {
-                None
-            }
-        });
+        let verified =
+            debug_span!("Verify hash").in_scope(|| verify_hash(&token_hash, &self.token));
 
-        verified.map(|_| {
+        verified.then(|| {
             debug_span!("Update last used time of token").in_scope(|| {
-                conn.prepare(UPDATE)
+                conn.prepare_cached(UPDATE)
                     .unwrap()
-                    .execute(params![self.token_id, Utc::now()])
+                    .execute(params![Utc::now(), self.token_id])
                     .unwrap();
-                user_id as u64
+                (user_id as u64, authenticated)
             })
         })
     }
@@ -0,0 +1,62 @@
+//! Counts of what a user has contributed and moderated, shown on their admin panel page
+
+use isixhosa_common::database::AdministratorAccessDb;
+use rusqlite::params;
+use tracing::instrument;
+
+#[derive(Clone, Debug, Default)]
+pub struct UserStats {
+    /// Words which the user is credited for
+    pub attributed_words: u64,
+    pub words_added: u64,
+    pub words_edited: u64,
+    pub pending_suggestions: u64,
+    pub suggestions_rejected: u64,
+    pub moderation_accepted: u64,
+    pub moderation_rejected: u64,
+    /// Devices on which the user has stayed signed in
+    pub signed_in_devices: u64,
+}
+
+impl UserStats {
+    #[instrument(name = "Fetch user stats", skip(db))]
+    pub fn fetch(db: &impl AdministratorAccessDb, user_id: u64) -> UserStats {
+        const SELECT: &str = "
+            SELECT
+                (SELECT COUNT(*) FROM user_attributions WHERE user_id = ?1)
+                    AS attributed_words,
+                (SELECT COUNT(*) FROM word_revisions WHERE user_id = ?1 AND added)
+                    AS words_added,
+                (SELECT COUNT(*) FROM word_revisions WHERE user_id = ?1 AND NOT added)
+                    AS words_edited,
+                (SELECT COUNT(*) FROM word_suggestions WHERE suggesting_user = ?1)
+                    AS pending_suggestions,
+                (SELECT COUNT(*) FROM notifications WHERE user_id = ?1 AND kind = 2)
+                    AS suggestions_rejected,
+                (SELECT COUNT(*) FROM moderation_actions WHERE moderator_id = ?1 AND accepted)
+                    AS moderation_accepted,
+                (SELECT COUNT(*) FROM moderation_actions WHERE moderator_id = ?1 AND NOT accepted)
+                    AS moderation_rejected,
+                (SELECT COUNT(*) FROM login_tokens WHERE user_id = ?1)
+                    AS signed_in_devices;
+        ";
+
+        db.get()
+            .unwrap()
+            .prepare_cached(SELECT)
+            .unwrap()
+            .query_row(params![user_id], |row| {
+                Ok(UserStats {
+                    attributed_words: row.get("attributed_words")?,
+                    words_added: row.get("words_added")?,
+                    words_edited: row.get("words_edited")?,
+                    pending_suggestions: row.get("pending_suggestions")?,
+                    suggestions_rejected: row.get("suggestions_rejected")?,
+                    moderation_accepted: row.get("moderation_accepted")?,
+                    moderation_rejected: row.get("moderation_rejected")?,
+                    signed_in_devices: row.get("signed_in_devices")?,
+                })
+            })
+            .unwrap()
+    }
+}
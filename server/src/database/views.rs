@@ -0,0 +1,81 @@
+use fallible_iterator::FallibleIterator;
+use isixhosa_common::database::{PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::types::WordHit;
+use rusqlite::params;
+use std::collections::HashMap;
+use tracing::{instrument, Span};
+
+/// Add the given number of views to each word for today. Words which have been deleted since
+/// being viewed are skipped.
+#[instrument(name = "Record word views", fields(words = views.len()), skip_all)]
+pub fn record_views(db: &impl PublicAccessDb, views: HashMap<u64, u64>) {
+    const UPSERT: &str = "
+        INSERT INTO word_views (word_id, day, views)
+            SELECT ?1, date('now'), ?2 WHERE EXISTS (SELECT 1 FROM words WHERE word_id = ?1)
+        ON CONFLICT(word_id, day) DO UPDATE SET views = views + excluded.views;
+    ";
+
+    let mut conn = db.get().unwrap();
+    let tx = conn.transaction().unwrap();
+
+    {
+        let mut stmt = tx.prepare_cached(UPSERT).unwrap();
+        for (word_id, views) in views {
+            stmt.execute(params![word_id, views]).unwrap();
+        }
+    }
+
+    tx.commit().unwrap();
+}
+
+#[instrument(name = "Count total views for word", fields(views), skip(db))]
+pub fn count_views(db: &impl PublicAccessDb, word_id: u64) -> u64 {
+    const SELECT: &str = "SELECT COALESCE(SUM(views), 0) FROM word_views WHERE word_id = ?1;";
+
+    let conn = db.get().unwrap();
+    let views = conn
+        .prepare_cached(SELECT)
+        .unwrap()
+        .query_row(params![word_id], |row| row.get(0))
+        .unwrap();
+
+    Span::current().record("views", views);
+
+    views
+}
+
+#[instrument(name = "Fetch trending words", fields(results), skip(db))]
+pub fn fetch_trending(
+    db: &impl PublicAccessDb,
+    limit: u64,
+    include_offensive: bool,
+) -> Vec<WordHit> {
+    const SELECT: &str = "
+        SELECT
+            words.word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal,
+            transitivity, noun_class
+        FROM word_views
+        INNER JOIN words ON words.word_id = word_views.word_id
+        WHERE day >= date('now', '-7 days') AND (?2 OR NOT is_offensive)
+        GROUP BY words.word_id
+        ORDER BY SUM(views) DESC
+        LIMIT ?1;
+    ";
+
+    let conn = db.get().unwrap();
+    let mut query = conn.prepare_cached(SELECT).unwrap();
+
+    let words: Vec<WordHit> = query
+        .query(params![limit, include_offensive])
+        .unwrap()
+        .map(|row| {
+            let id = row.get::<&str, i64>("word_id")? as u64;
+            WordHit::try_from_row_and_id(row, WordOrSuggestionId::existing(id))
+        })
+        .collect()
+        .unwrap();
+
+    Span::current().record("results", words.len());
+
+    words
+}
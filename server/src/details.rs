@@ -1,48 +1,377 @@
-use crate::auth::with_any_auth;
+use crate::auth::with_any_auth_read_only;
+use crate::classroom::{with_classroom_viewer, ClassroomViewer, Classrooms};
+use crate::database::homographs::{fetch_homographs, fetch_with_spelling};
+use crate::database::neighbours::fetch_neighbours;
+use crate::database::slugs;
+use crate::database::views::count_views;
+use crate::error::{ServerError, ServerResult, WordNotFound};
 use crate::i18n::I18nInfo;
 use crate::i18n::SiteContext;
-use crate::{spawn_blocking_child, DebugBoxedExt, NotFound};
+use crate::views::{counts_as_view, record_view, ViewCounter};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa::noun::NounClass;
+use isixhosa_click_macros::I18nTemplate;
 use isixhosa_common::auth::Auth;
 use isixhosa_common::database::{DbBase, PublicAccessDb};
-use isixhosa_common::templates::{WordChangeMethod, WordDetails};
-use isixhosa_common::types::ExistingWord;
+use isixhosa_common::language::PartOfSpeech;
+use isixhosa_common::morphology::VerbDerivation;
+use isixhosa_common::templates::{AlphabeticalNeighbours, WordDetails};
+use isixhosa_common::types::{ExistingWord, WordHit};
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::sync::Arc;
 use tracing::instrument;
+use utoipa::IntoParams;
+use warp::http::Uri;
+use warp::reply::Response;
 use warp::{Filter, Rejection, Reply};
+use xtra::Address;
 
 pub fn details(
     db: DbBase,
     site_ctx: Arc<SiteContext>,
+    views: Address<ViewCounter>,
+    classrooms: Option<Arc<Classrooms>>,
 ) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
-    warp::path!["word" / u64]
+    let random = warp::path!["word" / "random"]
         .and(warp::path::end())
         .and(warp::get())
-        .and(warp::any().map(|| None)) // previous_success is None
-        .and(with_any_auth(db, site_ctx))
-        .and_then(word)
-        .debug_boxed()
+        .and(warp::query())
+        .and(with_any_auth_read_only(db.clone(), site_ctx.clone()))
+        .and_then(random_word);
+
+    // Views are only recorded for requests which count as one
+    let with_views = counts_as_view()
+        .map(move |counts_as_view: bool| Some(views.clone()).filter(|_| counts_as_view));
+
+    let details = warp::path!["word" / u64]
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(wants_json())
+        .and(with_views.clone())
+        .and(with_classroom_viewer(classrooms.clone()))
+        .and(with_any_auth_read_only(db.clone(), site_ctx.clone()))
+        .and_then(view_word_by_id);
+
+    let by_slug = warp::path!["word" / String]
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(wants_json())
+        .and(with_views)
+        .and(with_classroom_viewer(classrooms))
+        .and(with_any_auth_read_only(db.clone(), site_ctx.clone()))
+        .and_then(view_word_by_slug);
+
+    let spelling = warp::path!["word" / "spelling" / String]
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_any_auth_read_only(db, site_ctx))
+        .and_then(spelling);
+
+    random.or(spelling).or(details).or(by_slug).debug_boxed()
 }
 
-#[instrument(name = "Display word details page", skip(auth, db, previous_success))]
-pub async fn word(
-    word_id: u64,
-    previous_success: Option<WordChangeMethod>,
-    auth: Auth,
+/// Filters for picking a random word, used by both `/word/random` and `/api/random`
+#[derive(Deserialize, IntoParams, Clone, Debug)]
+#[into_params(parameter_in = Query)]
+pub struct RandomWordQuery {
+    pub part_of_speech: Option<PartOfSpeech>,
+    /// Only pick nouns of this class
+    #[param(value_type = Option<String>)]
+    pub noun_class: Option<NounClass>,
+    /// Whether vulgar or offensive words can be picked. They are left out unless asked for, and
+    /// always in school mode.
+    #[serde(default)]
+    pub include_offensive: bool,
+}
+
+impl RandomWordQuery {
+    pub async fn fetch_random_id(
+        self,
+        db: impl PublicAccessDb,
+        school_mode: bool,
+    ) -> ServerResult<Option<u64>> {
+        spawn_blocking_child(move || {
+            ExistingWord::fetch_random_id(
+                &db,
+                self.part_of_speech,
+                self.noun_class,
+                self.include_offensive && !school_mode,
+            )
+        })
+        .await
+        .map_err(ServerError::from)
+    }
+}
+
+#[instrument(name = "Redirect to a random word", skip(_auth, i18n_info, db))]
+async fn random_word(
+    query: RandomWordQuery,
+    _auth: Auth,
     i18n_info: I18nInfo,
     db: impl PublicAccessDb,
 ) -> Result<impl Reply, Rejection> {
-    let db = db.clone();
-    let word = spawn_blocking_child(move || ExistingWord::fetch_full(&db, word_id))
+    match query.fetch_random_id(db, i18n_info.school_mode).await? {
+        Some(id) => Ok(warp::redirect::temporary(
+            format!("/word/{id}").parse::<Uri>().unwrap(),
+        )),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+/// Whether the client asked for JSON rather than HTML, i.e it accepts JSON but not HTML
+fn wants_json() -> impl Filter<Extract = (bool,), Error = Infallible> + Clone {
+    fn accepts_only_json(accept: &str) -> bool {
+        let media_types = || {
+            accept
+                .split(',')
+                .map(|t| t.split(';').next().unwrap().trim())
+        };
+        media_types().any(|t| t == "application/json")
+            && !media_types().any(|t| t == "text/html" || t == "*/*")
+    }
+
+    warp::header::optional::<String>("accept")
+        .map(|accept: Option<String>| accept.as_deref().is_some_and(accepts_only_json))
+        .or(warp::any().map(|| false))
+        .unify()
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "spelling.askama.html")]
+struct Spelling {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    spelling: String,
+    words: Vec<WordHit>,
+}
+
+/// A stable link to every entry spelled a certain way in isiXhosa. A spelling with only one entry
+/// is redirected to it, but only temporarily, since another entry with the spelling may be added
+/// later.
+#[instrument(name = "Display words with spelling", skip(auth, i18n_info, db))]
+async fn spelling(
+    spelling: String,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<Response, Rejection> {
+    let spelling = percent_decode_str(&spelling)
+        .decode_utf8()
+        .map_err(|_| warp::reject::not_found())?
+        .trim()
+        .to_owned();
+
+    let spelling_clone = spelling.clone();
+    let mut words = spawn_blocking_child(move || fetch_with_spelling(&db, &spelling_clone))
         .await
-        .unwrap();
-    Ok(match word {
-        Some(word) => WordDetails {
+        .map_err(ServerError::from)?;
+
+    match words.len() {
+        0 => Err(warp::reject::not_found()),
+        1 => {
+            let id = words.remove(0).id;
+            Ok(
+                warp::redirect::temporary(format!("/word/{id}").parse::<Uri>().unwrap())
+                    .into_response(),
+            )
+        }
+        _ => Ok(Spelling {
             auth,
             i18n_info,
-            word,
-            previous_success,
+            spelling,
+            words,
         }
-        .into_response(),
-        None => NotFound { auth, i18n_info }.into_response(),
+        .into_response()),
+    }
+}
+
+/// The JSON representation of a word, with the same data as the word details page
+#[derive(Serialize)]
+struct WordJson {
+    #[serde(flatten)]
+    word: ExistingWord,
+    /// Only included for moderators
+    #[serde(skip_serializing_if = "Option::is_none")]
+    views: Option<u64>,
+}
+
+/// The path of the word's page, by its slug if it has one so that the page isn't redirected to
+pub async fn word_path(word_id: u64, db: impl PublicAccessDb) -> ServerResult<String> {
+    let slug = spawn_blocking_child(move || slugs::fetch_slug(&db, word_id)).await?;
+
+    Ok(match slug {
+        Some(slug) => format!("/word/{slug}"),
+        None => format!("/word/{word_id}"),
     })
 }
+
+fn redirect_to_slug(slug: &str) -> Response {
+    warp::redirect::permanent(format!("/word/{slug}").parse::<Uri>().unwrap()).into_response()
+}
+
+/// Links by ID are redirected to the word's slug, except for JSON requests which are served as-is
+async fn view_word_by_id(
+    word_id: u64,
+    json: bool,
+    views: Option<Address<ViewCounter>>,
+    classroom: ClassroomViewer,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<Response, Rejection> {
+    if !json {
+        let db = db.clone();
+        let slug = spawn_blocking_child(move || slugs::fetch_slug(&db, word_id))
+            .await
+            .map_err(ServerError::from)?;
+
+        if let Some(slug) = slug {
+            return Ok(redirect_to_slug(&slug));
+        }
+    }
+
+    view_word(word_id, json, views, classroom, auth, i18n_info, db).await
+}
+
+/// Old slugs are redirected to the word's canonical slug
+async fn view_word_by_slug(
+    slug: String,
+    json: bool,
+    views: Option<Address<ViewCounter>>,
+    classroom: ClassroomViewer,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<Response, Rejection> {
+    let db_clone = db.clone();
+    let found = spawn_blocking_child(move || {
+        slugs::fetch_word_for_slug(&db_clone, &slug).map(|f| (slug, f))
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    match found {
+        Some((slug, (_, canonical))) if slug != canonical => Ok(redirect_to_slug(&canonical)),
+        Some((_, (word_id, _))) => {
+            view_word(word_id, json, views, classroom, auth, i18n_info, db).await
+        }
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+async fn view_word(
+    word_id: u64,
+    json: bool,
+    views: Option<Address<ViewCounter>>,
+    classroom: ClassroomViewer,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<Response, Rejection> {
+    let reply = if json {
+        let (word, views) = fetch_word(word_id, &auth, db).await?;
+        warp::reply::json(&WordJson { word, views }).into_response()
+    } else {
+        let reply = word(word_id, classroom, auth, i18n_info, db).await?;
+
+        // Only now is it known that the word exists
+        if let Some(views) = views {
+            record_view(&views, word_id);
+        }
+
+        reply.into_response()
+    };
+
+    // The same URL serves both, so caches must not mix them up
+    Ok(warp::reply::with_header(reply, "Vary", "Accept").into_response())
+}
+
+async fn fetch_word(
+    word_id: u64,
+    auth: &Auth,
+    db: impl PublicAccessDb,
+) -> Result<(ExistingWord, Option<u64>), Rejection> {
+    let is_moderator = auth.has_moderator_permissions();
+    let (word, views) = spawn_blocking_child(move || {
+        let word = ExistingWord::fetch_full(&db, word_id);
+        let views = Some(word_id)
+            .filter(|_| is_moderator && word.is_some())
+            .map(|id| count_views(&db, id));
+        (word, views)
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    match word {
+        Some(word) => Ok((word, views)),
+        None => Err(warp::reject::custom(WordNotFound(word_id))),
+    }
+}
+
+#[instrument(name = "Display word details page", skip(classroom, auth, db))]
+pub async fn word(
+    word_id: u64,
+    classroom: ClassroomViewer,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let (word, views) = fetch_word(word_id, &auth, db.clone()).await?;
+    let classroom = classroom.viewed(auth.user_id(), format!("/word/{word_id}"));
+    let derivation = verb_derivation(&word, db.clone()).await?;
+    let homographs = homographs(&word, db.clone()).await?;
+    let neighbours = neighbours(&word, i18n_info.school_mode, db).await?;
+
+    Ok(WordDetails {
+        auth,
+        i18n_info,
+        word,
+        views,
+        preview: None,
+        classroom,
+        derivation,
+        homographs,
+        neighbours,
+    })
+}
+
+/// The words before and after this one alphabetically, for browsing the dictionary in order
+async fn neighbours(
+    word: &ExistingWord,
+    school_mode: bool,
+    db: impl PublicAccessDb,
+) -> ServerResult<AlphabeticalNeighbours> {
+    let (id, xhosa) = (word.word_id, word.xhosa.clone());
+    spawn_blocking_child(move || fetch_neighbours(&db, id, &xhosa, !school_mode))
+        .await
+        .map_err(ServerError::from)
+}
+
+/// The other entries spelled the same in isiXhosa, which are shown so that they aren't mixed up
+pub async fn homographs(
+    word: &ExistingWord,
+    db: impl PublicAccessDb,
+) -> ServerResult<Vec<WordHit>> {
+    let (id, xhosa, english) = (word.word_id, word.xhosa.clone(), word.english.clone());
+    spawn_blocking_child(move || fetch_homographs(&db, id, &xhosa, &english))
+        .await
+        .map_err(ServerError::from)
+}
+
+/// The probable root of the word, if it is a verb derived with an extension
+pub async fn verb_derivation(
+    word: &ExistingWord,
+    db: impl PublicAccessDb,
+) -> ServerResult<Option<VerbDerivation>> {
+    if word.part_of_speech != Some(PartOfSpeech::Verb) {
+        return Ok(None);
+    }
+
+    let xhosa = word.xhosa.clone();
+    spawn_blocking_child(move || VerbDerivation::analyze(&db, &xhosa))
+        .await
+        .map_err(ServerError::from)
+}
@@ -0,0 +1,529 @@
+//! A server for the DICT protocol (RFC 2229), so that the dictionary can be looked up from the
+//! terminal with standard clients, e.g `dict -h isixhosa.click hamba`. The whole dictionary is
+//! served as a single database, and lookups go through the same search index as the site.
+
+use crate::config::DictServerConfig;
+use crate::i18n::{I18nInfo, SiteContext, ToTranslationKey, EN_ZA};
+use crate::search::{IncludeResults, TantivyClient};
+use crate::spawn_blocking_child;
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use isixhosa_common::database::db_impl::ReadOnlyDbImpl;
+use isixhosa_common::database::DbBase;
+use isixhosa_common::i18n::TranslationKey;
+use isixhosa_common::language::NounClassExt;
+use isixhosa_common::normalize;
+use isixhosa_common::search_direction::SearchDirection;
+use isixhosa_common::theme::Theme;
+use isixhosa_common::types::{ExistingWord, WordHit};
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tokio_util::codec::{FramedRead, LinesCodec};
+use tracing::{debug, info, instrument, warn};
+
+/// The name of the only database, as given to `dict -d`
+const DATABASE: &str = "isixhosa";
+/// RFC 2229 limits command lines to 1024 octets including the line ending
+const MAX_LINE_LEN: usize = 1022;
+/// Clients which send nothing for this long are disconnected
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// The most definitions or matches returned for one word
+const MAX_RESULTS: usize = 20;
+
+/// The strategies which MATCH supports, and their descriptions
+const STRATEGIES: [(&str, &str); 3] = [
+    ("search", "Search as on the website (the default)"),
+    ("exact", "Match words exactly"),
+    ("prefix", "Match the start of words"),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Strategy {
+    Search,
+    Exact,
+    Prefix,
+}
+
+impl Strategy {
+    fn parse(s: &str) -> Option<Strategy> {
+        match s {
+            "search" | "." => Some(Strategy::Search),
+            "exact" => Some(Strategy::Exact),
+            "prefix" => Some(Strategy::Prefix),
+            _ => None,
+        }
+    }
+
+    /// The side of the hit which matches the word, if either does
+    fn headword<'a>(&self, hit: &'a WordHit, word: &str) -> Option<&'a str> {
+        let word = normalize::fold(word);
+        let matches = |s: &str| match self {
+            Strategy::Search => true,
+            Strategy::Exact => normalize::fold(s) == word,
+            Strategy::Prefix => normalize::fold(s).starts_with(&word),
+        };
+
+        [hit.xhosa.as_str(), hit.english.as_str()]
+            .into_iter()
+            .find(|s| matches(s))
+    }
+}
+
+struct DictContext {
+    db: ReadOnlyDbImpl,
+    tantivy: Arc<TantivyClient>,
+    i18n_info: I18nInfo,
+    host: String,
+    connections: Semaphore,
+}
+
+impl DictContext {
+    fn t(&self, key: &str) -> String {
+        self.i18n_info.t(&TranslationKey::new(key))
+    }
+
+    async fn search(&self, word: &str, strategy: Strategy) -> Result<Vec<(WordHit, String)>> {
+        let hits: Vec<WordHit> = self
+            .tantivy
+            .search(
+                word.to_owned(),
+                IncludeResults::AcceptedOnly,
+                false,
+                self.i18n_info.clone(),
+            )
+            .await?;
+
+        Ok(hits
+            .into_iter()
+            .filter_map(|hit| {
+                let headword = strategy.headword(&hit, word)?.to_owned();
+                Some((hit, headword))
+            })
+            .take(MAX_RESULTS)
+            .collect())
+    }
+}
+
+/// Start listening for DICT clients in the background
+pub async fn start(
+    cfg: DictServerConfig,
+    db: DbBase,
+    tantivy: Arc<TantivyClient>,
+    site_ctx: Arc<SiteContext>,
+) -> Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", cfg.port))
+        .await
+        .with_context(|| format!("Binding the DICT server to port {}", cfg.port))?;
+
+    let ctx = Arc::new(DictContext {
+        db: ReadOnlyDbImpl(db.read_pool().clone()),
+        tantivy,
+        host: site_ctx.host.clone(),
+        i18n_info: I18nInfo {
+            user_language: EN_ZA,
+            theme: Theme::default(),
+            search_direction: SearchDirection::default(),
+            school_mode: site_ctx.school_mode,
+            flashes: Vec::new(),
+            csp_nonce: String::new(),
+            ctx: site_ctx,
+        },
+        connections: Semaphore::new(cfg.max_connections),
+    });
+
+    info!(port = cfg.port, "DICT server listening");
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, addr) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(error) => {
+                    warn!(?error, "Error accepting DICT connection");
+                    continue;
+                }
+            };
+
+            let ctx = ctx.clone();
+            tokio::spawn(async move {
+                if let Err(error) = handle_connection(stream, ctx).await {
+                    debug!(%addr, ?error, "DICT connection ended with an error");
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+#[instrument(name = "DICT connection", skip_all)]
+async fn handle_connection(stream: TcpStream, ctx: Arc<DictContext>) -> Result<()> {
+    let (read, write) = stream.into_split();
+    let mut conn = Connection { write, mime: false };
+
+    let Ok(_permit) = ctx.connections.try_acquire() else {
+        conn.status(420, "server temporarily unavailable").await?;
+        return Ok(());
+    };
+
+    let msg_id = crate::auth::random_string_token();
+    conn.status(
+        220,
+        &format!(
+            "{} dict server <mime> <{}@{}>",
+            ctx.host,
+            &msg_id[..16],
+            ctx.host
+        ),
+    )
+    .await?;
+
+    let mut lines = FramedRead::new(read, LinesCodec::new_with_max_length(MAX_LINE_LEN));
+
+    loop {
+        let line = match tokio::time::timeout(IDLE_TIMEOUT, lines.next()).await {
+            Ok(Some(Ok(line))) => line,
+            Ok(Some(Err(_))) => {
+                conn.status(500, "line too long").await?;
+                return Ok(());
+            }
+            Ok(None) => return Ok(()),
+            Err(_) => {
+                conn.status(421, "idle timeout").await?;
+                return Ok(());
+            }
+        };
+
+        if !conn.command(&ctx, &line).await? {
+            return Ok(());
+        }
+    }
+}
+
+struct Connection {
+    write: OwnedWriteHalf,
+    /// Whether the client asked for MIME headers before each text response
+    mime: bool,
+}
+
+impl Connection {
+    async fn status(&mut self, code: u16, text: &str) -> Result<()> {
+        self.write
+            .write_all(format!("{code} {text}\r\n").as_bytes())
+            .await?;
+        Ok(())
+    }
+
+    /// Send a body of text, ended by a line containing only a dot. Lines which start with a dot
+    /// have it doubled so that they aren't mistaken for the end.
+    async fn text(&mut self, text: &str) -> Result<()> {
+        let mut out = String::new();
+
+        if self.mime {
+            out.push_str("Content-type: text/plain; charset=utf-8\r\n\r\n");
+        }
+
+        for line in text.lines() {
+            if line.starts_with('.') {
+                out.push('.');
+            }
+            out.push_str(line);
+            out.push_str("\r\n");
+        }
+
+        out.push_str(".\r\n");
+        self.write.write_all(out.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Handle one command. Returns false once the client has quit.
+    async fn command(&mut self, ctx: &DictContext, line: &str) -> Result<bool> {
+        let Some(args) = parse_command(line) else {
+            self.status(501, "syntax error, illegal parameters").await?;
+            return Ok(true);
+        };
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let Some((command, args)) = args.split_first() else {
+            return Ok(true);
+        };
+
+        match (command.to_ascii_uppercase().as_str(), args) {
+            ("DEFINE", [database, word]) => self.define(ctx, database, word).await?,
+            ("MATCH", [database, strategy, word]) => {
+                self.match_words(ctx, database, strategy, word).await?
+            }
+            ("SHOW", [what, rest @ ..]) => self.show(ctx, what, rest).await?,
+            ("CLIENT", _) => self.status(250, "ok").await?,
+            ("OPTION", [option]) if option.eq_ignore_ascii_case("MIME") => {
+                self.mime = true;
+                self.status(250, "ok - using MIME headers").await?;
+            }
+            ("STATUS", []) => self.status(210, "up").await?,
+            ("HELP", []) => {
+                self.status(113, "help text follows").await?;
+                self.text(HELP).await?;
+                self.status(250, "ok").await?;
+            }
+            ("QUIT", []) => {
+                self.status(221, "bye").await?;
+                return Ok(false);
+            }
+            ("AUTH" | "SASLAUTH" | "OPTION", _) => {
+                self.status(502, "command not implemented").await?
+            }
+            ("DEFINE" | "MATCH" | "SHOW" | "STATUS" | "HELP" | "QUIT", _) => {
+                self.status(501, "syntax error, illegal parameters").await?
+            }
+            _ => self.status(500, "unknown command").await?,
+        }
+
+        Ok(true)
+    }
+
+    async fn define(&mut self, ctx: &DictContext, database: &str, word: &str) -> Result<()> {
+        if !is_our_database(database) {
+            return self
+                .status(550, "invalid database, use \"SHOW DB\" for list")
+                .await;
+        }
+
+        let hits = ctx.search(word, Strategy::Exact).await?;
+        let db = ctx.db.clone();
+        let words: Vec<ExistingWord> = spawn_blocking_child(move || {
+            hits.iter()
+                .filter_map(|(hit, _)| ExistingWord::fetch_full(&db, hit.id))
+                .collect()
+        })
+        .await?;
+
+        if words.is_empty() {
+            return self.status(552, "no match").await;
+        }
+
+        self.status(150, &format!("{} definitions retrieved", words.len()))
+            .await?;
+
+        let description = database_description(ctx);
+        for word in &words {
+            self.status(
+                151,
+                &format!("\"{}\" {DATABASE} \"{description}\"", word.xhosa),
+            )
+            .await?;
+            self.text(&format_definition(ctx, word)).await?;
+        }
+
+        self.status(250, "ok").await
+    }
+
+    async fn match_words(
+        &mut self,
+        ctx: &DictContext,
+        database: &str,
+        strategy: &str,
+        word: &str,
+    ) -> Result<()> {
+        if !is_our_database(database) {
+            return self
+                .status(550, "invalid database, use \"SHOW DB\" for list")
+                .await;
+        }
+
+        let Some(strategy) = Strategy::parse(strategy) else {
+            return self
+                .status(551, "invalid strategy, use \"SHOW STRAT\" for list")
+                .await;
+        };
+
+        let mut headwords: Vec<String> = Vec::new();
+        for (_, headword) in ctx.search(word, strategy).await? {
+            if !headwords.contains(&headword) {
+                headwords.push(headword);
+            }
+        }
+
+        if headwords.is_empty() {
+            return self.status(552, "no match").await;
+        }
+
+        self.status(152, &format!("{} matches found", headwords.len()))
+            .await?;
+
+        let list = headwords
+            .iter()
+            .map(|w| format!("{DATABASE} \"{}\"\n", w.replace('"', "")))
+            .collect::<String>();
+        self.text(&list).await?;
+
+        self.status(250, "ok").await
+    }
+
+    async fn show(&mut self, ctx: &DictContext, what: &str, args: &[&str]) -> Result<()> {
+        match (what.to_ascii_uppercase().as_str(), args) {
+            ("DB" | "DATABASES", []) => {
+                self.status(110, "1 databases present").await?;
+                self.text(&format!("{DATABASE} \"{}\"", database_description(ctx)))
+                    .await?;
+            }
+            ("STRAT" | "STRATEGIES", []) => {
+                self.status(111, &format!("{} strategies present", STRATEGIES.len()))
+                    .await?;
+
+                let list = STRATEGIES
+                    .iter()
+                    .map(|(name, description)| format!("{name} \"{description}\"\n"))
+                    .collect::<String>();
+                self.text(&list).await?;
+            }
+            ("INFO", [database]) if is_our_database(database) => {
+                self.status(112, "database information follows").await?;
+                self.text(&format!(
+                    "{}\n\nThe full dictionary is at https://{}/",
+                    database_description(ctx),
+                    ctx.host
+                ))
+                .await?;
+            }
+            ("INFO", [_]) => {
+                return self
+                    .status(550, "invalid database, use \"SHOW DB\" for list")
+                    .await;
+            }
+            ("SERVER", []) => {
+                self.status(114, "server information follows").await?;
+                self.text(&format!("{} dict server", ctx.host)).await?;
+            }
+            _ => return self.status(501, "syntax error, illegal parameters").await,
+        }
+
+        self.status(250, "ok").await
+    }
+}
+
+const HELP: &str = "\
+DEFINE database word         -- look up word in database
+MATCH database strategy word -- match word in database using strategy
+SHOW DB                      -- list all accessible databases
+SHOW STRAT                   -- list available matching strategies
+SHOW INFO database           -- provide information about the database
+SHOW SERVER                  -- provide site-specific information
+OPTION MIME                  -- use MIME headers
+CLIENT info                  -- identify client to server
+STATUS                       -- display timing information
+HELP                         -- display this help information
+QUIT                         -- terminate connection";
+
+/// `*` means every database and `!` means the first with a match, which are the same thing when
+/// there is only one
+fn is_our_database(database: &str) -> bool {
+    matches!(database, DATABASE | "*" | "!")
+}
+
+fn database_description(ctx: &DictContext) -> String {
+    format!("{} isiXhosa-English dictionary", ctx.t("site.short-name"))
+}
+
+/// Split a command line into words, where words can be quoted with single or double quotes and
+/// characters can be escaped with a backslash. Returns `None` if a quote is left open.
+fn parse_command(line: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', _) => {
+                word.push(chars.next()?);
+                in_word = true;
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (c, Some(_)) => word.push(c),
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (c, None) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (c, None) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return None;
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Some(words)
+}
+
+fn format_definition(ctx: &DictContext, word: &ExistingWord) -> String {
+    let mut out = format!("{} - {}\n", word.xhosa, word.english);
+
+    let mut grammar = Vec::new();
+    if let Some(pos) = word.part_of_speech {
+        grammar.push(ctx.i18n_info.t(&pos.translation_key()));
+    }
+    if let Some(transitivity) = word.transitivity {
+        grammar.push(ctx.i18n_info.t(&transitivity.translation_key()));
+    }
+    if let Some(class) = word.noun_class {
+        let prefixes = class.to_prefixes();
+        let class = match prefixes.plural {
+            Some(plural) => format!("{}-/{plural}-", prefixes.singular),
+            None => format!("{}-", prefixes.singular),
+        };
+        grammar.push(format!("{} {class}", ctx.t("noun-class")));
+    }
+    if word.is_plural {
+        grammar.push(ctx.t("plurality.plural"));
+    }
+    if word.is_informal {
+        grammar.push(ctx.t("informal"));
+    }
+
+    if !grammar.is_empty() {
+        let _ = writeln!(out, "{}", grammar.join(", "));
+    }
+
+    if !word.infinitive.is_empty() {
+        let _ = writeln!(out, "{}: {}", ctx.t("infinitive"), word.infinitive);
+    }
+
+    if !word.note.is_empty() {
+        let _ = writeln!(out, "{}: {}", ctx.t("note"), word.note);
+    }
+
+    if !word.examples.is_empty() {
+        let _ = writeln!(out, "\n{}:", ctx.t("examples"));
+        for example in &word.examples {
+            let _ = writeln!(out, "  {}\n    {}", example.xhosa, example.english);
+        }
+    }
+
+    if !word.linked_words.is_empty() {
+        let _ = writeln!(out, "\n{}:", ctx.t("linked-words"));
+        for link in &word.linked_words {
+            let _ = writeln!(out, "  {} - {}", link.other.xhosa, link.other.english);
+        }
+    }
+
+    let _ = writeln!(out, "\nhttps://{}/word/{}", ctx.host, word.word_id);
+    out
+}
@@ -0,0 +1,74 @@
+use crate::auth::{with_user_auth, FullUser};
+use crate::config::BodyLimits;
+use crate::csrf::csrf_protected;
+use crate::database::drafts::Draft;
+use crate::database::submit::WordSubmission;
+use crate::error::ServerError;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::serialization::deserialize_form;
+use crate::submit::submission_form_page;
+use crate::validation::ValidationErrors;
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use isixhosa_common::database::{DbBase, UserAccessDb};
+use std::sync::Arc;
+use tracing::instrument;
+use warp::http::Uri;
+use warp::reply::Response;
+use warp::{body, Filter, Rejection, Reply};
+
+pub fn drafts(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    body_limits: BodyLimits,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let resume = warp::get()
+        .and(warp::path![u64])
+        .and(warp::path::end())
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and_then(resume_draft);
+
+    let delete = warp::post()
+        .and(warp::path![u64 / "delete"])
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.submission))
+        .and(csrf_protected())
+        .and(with_user_auth(db, site_ctx))
+        .and_then(delete_draft);
+
+    warp::path("drafts").and(resume.or(delete)).debug_boxed()
+}
+
+#[instrument(name = "Resume draft", skip(user, i18n_info, db))]
+async fn resume_draft(
+    draft_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<Response, Rejection> {
+    let user_id = user.id.get();
+    let db_clone = db.clone();
+    let draft = spawn_blocking_child(move || Draft::fetch(&db_clone, user_id, draft_id))
+        .await
+        .map_err(ServerError::from)?
+        .ok_or_else(warp::reject::not_found)?;
+
+    let mut submission: WordSubmission = deserialize_form(draft.form.as_bytes())?;
+    submission.draft_id = Some(draft.draft_id);
+
+    submission_form_page(submission, ValidationErrors::default(), user, i18n_info, db).await
+}
+
+#[instrument(name = "Delete draft", skip(user, _i18n_info, db))]
+async fn delete_draft(
+    draft_id: u64,
+    user: FullUser,
+    _i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let user_id = user.id.get();
+    spawn_blocking_child(move || Draft::delete(&db, user_id, draft_id))
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(warp::redirect::see_other(Uri::from_static("/settings")))
+}
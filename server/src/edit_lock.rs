@@ -0,0 +1,48 @@
+//! Advisory locks on suggestions which a moderator is editing. Nothing stops a second moderator from
+//! editing a locked suggestion, but they are warned that someone else is already editing it, so
+//! that neither silently overwrites the other's changes.
+
+use crate::auth::FullUser;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::num::NonZeroU64;
+use std::time::{Duration, Instant};
+
+/// Locks expire on their own so that abandoned edits don't lock a suggestion forever
+const LOCK_DURATION: Duration = Duration::from_secs(15 * 60);
+
+lazy_static::lazy_static! {
+    static ref EDIT_LOCKS: DashMap<u64, EditLock> = DashMap::new();
+}
+
+struct EditLock {
+    user_id: NonZeroU64,
+    username: String,
+    acquired: Instant,
+}
+
+/// Lock the suggestion for the user, or refresh their lock if they already hold it. If another
+/// moderator holds the lock, it is left alone and their username is returned.
+pub fn lock_suggestion(suggestion_id: u64, user: &FullUser) -> Option<String> {
+    let now = Instant::now();
+    EDIT_LOCKS.retain(|_, lock| now - lock.acquired < LOCK_DURATION);
+
+    match EDIT_LOCKS.entry(suggestion_id) {
+        Entry::Occupied(entry) if entry.get().user_id != user.id => {
+            Some(entry.get().username.clone())
+        }
+        entry => {
+            entry.insert(EditLock {
+                user_id: user.id,
+                username: user.username.clone(),
+                acquired: now,
+            });
+            None
+        }
+    }
+}
+
+/// Release the user's lock on the suggestion, if they hold it
+pub fn unlock_suggestion(suggestion_id: u64, user_id: NonZeroU64) {
+    EDIT_LOCKS.remove_if(&suggestion_id, |_, lock| lock.user_id == user_id);
+}
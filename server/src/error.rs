@@ -0,0 +1,256 @@
+//! Errors which occur while handling a request. Rather than panicking the request or falling
+//! through to warp's plain-text rejections, handlers reject and the rejection is rendered as a
+//! branded error page.
+
+use crate::auth::{with_deferred_auth, DeferredAuth};
+use crate::bans::Banned;
+use crate::csrf::InvalidCsrfToken;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::maintenance::UnderMaintenance;
+use crate::serialization::{InvalidForm, InvalidMultipart};
+use askama::Template;
+use async_graphql_warp::GraphQLBadRequest;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::DbBase;
+use std::convert::Infallible;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+use tracing::{debug, error, Span};
+use warp::body::BodyDeserializeError;
+use warp::http::StatusCode;
+use warp::reject::{InvalidHeader, InvalidQuery, MissingHeader, PayloadTooLarge};
+use warp::reply::Response;
+use warp::{reply, Filter, Rejection, Reply};
+
+/// Error responses carry the request's ID in this header too, for clients which don't show the page
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+pub struct ServerError(pub anyhow::Error);
+
+pub type ServerResult<T> = Result<T, ServerError>;
+
+impl Debug for ServerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl warp::reject::Reject for ServerError {}
+
+impl<E: Into<anyhow::Error>> From<E> for ServerError {
+    fn from(err: E) -> Self {
+        ServerError(err.into())
+    }
+}
+
+impl From<ServerError> for Rejection {
+    fn from(err: ServerError) -> Self {
+        warp::reject::custom(err)
+    }
+}
+
+/// A database query which couldn't be made. These are carried in a [`ServerError`] like any other
+/// error, but a busy connection pool is shown as the site being temporarily unavailable rather than
+/// broken.
+#[derive(thiserror::Error, Debug)]
+pub enum DbError {
+    #[error("couldn't get a database connection: {0}")]
+    Pool(#[from] r2d2::Error),
+    #[error("database query failed: {0}")]
+    Query(#[from] rusqlite::Error),
+}
+
+/// A search which couldn't be made, because the search actors have stopped
+#[derive(thiserror::Error, Debug)]
+pub enum SearchError {
+    #[error("search index is unavailable: {0}")]
+    Unavailable(#[from] xtra::Error),
+}
+
+impl ServerError {
+    /// Whether the error is likely to go away by itself, e.g the connection pool was exhausted
+    fn is_temporary(&self) -> bool {
+        self.0.chain().any(|err| {
+            matches!(err.downcast_ref::<DbError>(), Some(DbError::Pool(_)))
+                || err.downcast_ref::<SearchError>().is_some()
+        })
+    }
+}
+
+/// The word with the given ID does not exist (or has since been deleted)
+#[derive(Copy, Clone, Debug)]
+pub struct WordNotFound(pub u64);
+
+impl warp::reject::Reject for WordNotFound {}
+
+#[derive(Template, I18nTemplate, Clone, Debug)]
+#[template(path = "404.askama.html")]
+pub struct NotFound {
+    pub auth: Auth,
+    pub i18n_info: I18nInfo,
+    pub word_id: Option<u64>,
+}
+
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "400.askama.html")]
+struct BadRequest {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    /// The form field which was missing or invalid, if it is known
+    field: Option<String>,
+}
+
+/// Shown to users who are banned from submitting
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "403.askama.html")]
+struct BannedPage {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    reason: String,
+    /// The date on which the ban expires, if it ever does
+    expires: Option<String>,
+}
+
+/// Shown when submitting while the site is in maintenance mode
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "503.askama.html")]
+struct MaintenancePage {
+    auth: Auth,
+    i18n_info: I18nInfo,
+}
+
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "500.askama.html")]
+struct InternalServerError {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    /// The request's ID, so that a user reporting the error can be matched up with its logs
+    reference_code: String,
+}
+
+/// Identifies a request in the logs, and is shown on error pages as a reference code
+#[derive(Clone, Debug)]
+pub struct RequestId(String);
+
+/// Gives the request an ID and records it on the request's span, so that everything logged while
+/// handling the request carries it. The span must have an empty `request_id` field.
+fn with_request_id() -> impl Filter<Extract = (RequestId,), Error = Infallible> + Clone {
+    warp::any().map(|| {
+        let id = format!("{:08X}", rand::random::<u32>());
+        Span::current().record("request_id", id.as_str());
+        RequestId(id)
+    })
+}
+
+fn is_bad_request(err: &Rejection) -> bool {
+    err.find::<InvalidForm>().is_some()
+        || err.find::<InvalidCsrfToken>().is_some()
+        || err.find::<InvalidMultipart>().is_some()
+        || err.find::<InvalidQuery>().is_some()
+        || err.find::<BodyDeserializeError>().is_some()
+        || err.find::<InvalidHeader>().is_some()
+        || err.find::<MissingHeader>().is_some()
+        || err.find::<GraphQLBadRequest>().is_some()
+}
+
+async fn render_error_page(
+    err: Rejection,
+    auth: DeferredAuth,
+    RequestId(request_id): RequestId,
+) -> Result<Response, Rejection> {
+    let status = if let Some(server_error) = err.find::<ServerError>() {
+        error!("Internal server error: {:?}", server_error.0);
+
+        if server_error.is_temporary() {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    } else if err.find::<WordNotFound>().is_some() || err.is_not_found() {
+        StatusCode::NOT_FOUND
+    } else if err.find::<Banned>().is_some() {
+        StatusCode::FORBIDDEN
+    } else if err.find::<UnderMaintenance>().is_some() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else if err.find::<PayloadTooLarge>().is_some() {
+        debug!("Request body too large: {:?}", err);
+        StatusCode::PAYLOAD_TOO_LARGE
+    } else if is_bad_request(&err) {
+        debug!("Bad request: {:?}", err);
+        StatusCode::BAD_REQUEST
+    } else {
+        return Err(err);
+    };
+
+    let (auth, i18n_info) = auth.resolve().await;
+
+    let page = match status {
+        StatusCode::SERVICE_UNAVAILABLE if err.find::<UnderMaintenance>().is_some() => {
+            MaintenancePage { auth, i18n_info }.into_response()
+        }
+        StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
+            InternalServerError {
+                auth,
+                i18n_info,
+                reference_code: request_id.clone(),
+            }
+            .into_response()
+        }
+        StatusCode::NOT_FOUND => NotFound {
+            auth,
+            i18n_info,
+            word_id: err.find::<WordNotFound>().map(|WordNotFound(id)| *id),
+        }
+        .into_response(),
+        StatusCode::FORBIDDEN => {
+            let Banned(ban) = err.find::<Banned>().unwrap();
+            BannedPage {
+                auth,
+                i18n_info,
+                reason: ban.reason.clone(),
+                expires: ban.expires.map(|e| e.format("%Y-%m-%d").to_string()),
+            }
+            .into_response()
+        }
+        _ => BadRequest {
+            auth,
+            i18n_info,
+            field: err.find::<InvalidForm>().and_then(|err| err.field.clone()),
+        }
+        .into_response(),
+    };
+
+    let page = reply::with_status(page, status);
+    Ok(reply::with_header(page, REQUEST_ID_HEADER, request_id).into_response())
+}
+
+/// Render rejections from the filter as error pages: 404 for anything not found, 400 for
+/// malformed forms and queries, 413 for bodies over the route's limit, 403 for banned users, 503
+/// while in maintenance mode or when a [`ServerError`] is likely temporary, and 500 for any other
+/// [`ServerError`]. Other rejections are passed through untouched.
+pub fn recover_error_pages<F, R>(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    filter: F,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    let filter = filter
+        .map(|reply: R| Ok::<_, Rejection>(reply.into_response()))
+        .or_else(|err| async move { Ok::<_, Infallible>((Err(err),)) });
+
+    with_deferred_auth(db, site_ctx)
+        .and(with_request_id())
+        .and(filter)
+        .and_then(
+            |auth, request_id, res: Result<Response, Rejection>| async move {
+                match res {
+                    Ok(response) => Ok(response),
+                    Err(err) => render_error_page(err, auth, request_id).await,
+                }
+            },
+        )
+}
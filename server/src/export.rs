@@ -1,7 +1,9 @@
-//! This script is called daily to back up the database and sweep unused login tokens.
+//! This script is called daily to back up the database, sweep unused login tokens, and send
+//! moderators a summary of the oldest pending suggestions.
 
 use crate::i18n::{I18nInfo, EN_ZA};
-use crate::{set_up_db, CliArgs, Config};
+use crate::reminders;
+use crate::{open_db_connection, set_up_db, CliArgs, Config};
 use anyhow::Result;
 use chrono::Utc;
 use fallible_iterator::FallibleIterator;
@@ -9,7 +11,9 @@ use genanki_rs::{Deck, Field, Model, ModelType, Note, Template};
 use isixhosa::noun::NounClass;
 use isixhosa_common::format::DisplayHtml;
 use isixhosa_common::language::{ConjunctionFollowedBy, PartOfSpeech, Transitivity, WordLinkType};
-use isixhosa_common::types::{ExistingExample, ExistingWord};
+use isixhosa_common::search_direction::SearchDirection;
+use isixhosa_common::theme::Theme;
+use isixhosa_common::types::{ExistingExample, ExistingWord, WordReference};
 use rusqlite::backup::Backup;
 use rusqlite::{params, OptionalExtension};
 use rusqlite::{Connection, Row};
@@ -23,16 +27,18 @@ use std::sync::Arc;
 use std::time::Duration;
 use std::{fs, io};
 use tempdir::TempDir;
+use tracing::error;
 
 // TODO(restore users, datasets)
 pub fn restore(cfg: Config) -> Result<()> {
-    let conn = Connection::open(&cfg.database_path)?;
+    let conn = open_db_connection(&cfg)?;
 
     set_up_db(&conn)?;
     restore_words(&cfg, &conn)?;
     restore_examples(&cfg, &conn)?;
     restore_linked_words(&cfg, &conn)?;
     restore_contributions(&cfg, &conn)?;
+    restore_references(&cfg, &conn)?;
 
     // Force reindex on next start
     std::fs::remove_dir_all(&cfg.tantivy_path)?;
@@ -42,8 +48,13 @@ pub fn restore(cfg: Config) -> Result<()> {
 }
 
 pub fn run_daily_tasks(cfg: &Config, args: &CliArgs) -> Result<()> {
-    let conn = Connection::open(&cfg.database_path)?;
+    let conn = open_db_connection(cfg)?;
     sweep_tokens(&conn)?;
+
+    if let Err(e) = reminders::send_pending_summary(cfg, &conn) {
+        error!("Error sending pending suggestion summary: {e:?}");
+    }
+
     export(cfg, &args.site, &conn)
 }
 
@@ -64,6 +75,7 @@ fn export(cfg: &Config, site: &str, src: &Connection) -> Result<()> {
     write_contributions(cfg, &dest)?;
     write_datasets(cfg, &dest)?;
     write_dataset_attributions(cfg, &dest)?;
+    write_references(cfg, &dest)?;
 
     let output = Command::new("git")
         .current_dir(&cfg.plaintext_export_path)
@@ -109,6 +121,9 @@ pub struct WordRecord {
     pub followed_by: Option<ConjunctionFollowedBy>,
     pub noun_class: Option<NounClass>,
     pub note: String,
+    /// Missing from backups made before words could be flagged as offensive
+    #[serde(default)]
+    pub is_offensive: bool,
 }
 
 impl WordRecord {
@@ -314,6 +329,7 @@ impl From<ExistingWord> for WordRecord {
             followed_by: w.followed_by,
             noun_class: w.noun_class,
             note: w.note,
+            is_offensive: w.is_offensive,
         }
     }
 }
@@ -437,7 +453,7 @@ fn write_words(cfg: &Config, site: &str, conn: &Connection) -> Result<()> {
     const SELECT_WORDS: &str = "
         SELECT
             word_id, english, xhosa, part_of_speech, xhosa_tone_markings, infinitive, is_plural,
-            is_inchoative, is_informal, transitivity, followed_by, noun_class, note
+            is_inchoative, is_informal, transitivity, followed_by, noun_class, note, is_offensive
         FROM words
         ORDER BY word_id;
     ";
@@ -474,6 +490,11 @@ fn write_words(cfg: &Config, site: &str, conn: &Connection) -> Result<()> {
     let ctx = crate::i18n::load(site.to_owned(), cfg);
     let i18n_info = I18nInfo {
         user_language: EN_ZA,
+        theme: Theme::default(),
+        search_direction: SearchDirection::default(),
+        school_mode: false,
+        flashes: Vec::new(),
+        csp_nonce: String::new(),
         ctx: Arc::new(ctx),
     };
 
@@ -508,8 +529,8 @@ fn restore_words(cfg: &Config, conn: &Connection) -> Result<()> {
     const INSERT: &str = "
         INSERT INTO words (
             word_id, english, xhosa, part_of_speech, xhosa_tone_markings, infinitive, is_plural,
-            is_inchoative, is_informal, transitivity, followed_by, noun_class, note
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13);
+            is_inchoative, is_informal, transitivity, followed_by, noun_class, note, is_offensive
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14);
     ";
 
     let mut csv = csv_reader(cfg, "words.csv")?;
@@ -531,7 +552,8 @@ fn restore_words(cfg: &Config, conn: &Connection) -> Result<()> {
             w.transitivity,
             w.followed_by.unwrap_or_default(),
             w.noun_class.map(|x| x as u8),
-            w.note
+            w.note,
+            w.is_offensive
         ])?;
     }
 
@@ -541,8 +563,9 @@ fn restore_words(cfg: &Config, conn: &Connection) -> Result<()> {
 #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
 fn write_examples(cfg: &Config, conn: &Connection) -> Result<()> {
     const SELECT: &str = "
-        SELECT example_id, word_id, english, xhosa
+        SELECT example_id, word_id, english, xhosa, source, alignment
         FROM examples
+        LEFT JOIN example_sources USING (example_id)
         ORDER BY example_id;
     ";
 
@@ -561,12 +584,21 @@ fn restore_examples(cfg: &Config, conn: &Connection) -> Result<()> {
         INSERT INTO examples (example_id, word_id, english, xhosa) VALUES (?1, ?2, ?3, ?4);
     ";
 
+    const INSERT_SOURCE: &str = "
+        INSERT INTO example_sources (example_id, source, alignment) VALUES (?1, ?2, ?3);
+    ";
+
     let mut csv = csv_reader(cfg, "examples.csv")?;
     let mut insert = conn.prepare(INSERT)?;
+    let mut insert_source = conn.prepare(INSERT_SOURCE)?;
 
     for res in csv.deserialize() {
         let e: ExistingExample = res?;
         insert.execute(params![e.example_id, e.word_id, e.english, e.xhosa])?;
+
+        if let Some(source) = e.source {
+            insert_source.execute(params![e.example_id, source, e.alignment])?;
+        }
     }
 
     Ok(())
@@ -713,6 +745,58 @@ fn write_dataset_attributions(cfg: &Config, conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+#[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+fn write_references(cfg: &Config, conn: &Connection) -> Result<()> {
+    const SELECT: &str = "
+        SELECT
+            reference_id, word_id, kind, author, title, year, publisher, pages, url,
+            link_broken_since IS NOT NULL AS link_broken
+        FROM word_references
+        ORDER BY reference_id;
+    ";
+
+    let mut csv = csv_writer(cfg, "references.csv")?;
+
+    conn.prepare(SELECT)?
+        .query(params![])?
+        .map(|row| WordReference::try_from(row))
+        .map_err(|e| -> anyhow::Error { e.into() })
+        .for_each(|reference| csv.serialize(reference).map_err(Into::into))
+}
+
+fn restore_references(cfg: &Config, conn: &Connection) -> Result<()> {
+    const INSERT: &str = "
+        INSERT INTO word_references
+            (reference_id, word_id, kind, author, title, year, publisher, pages, url)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9);
+    ";
+
+    // Backups from before references were added don't have them
+    if !cfg.plaintext_export_path.join("references.csv").exists() {
+        return Ok(());
+    }
+
+    let mut csv = csv_reader(cfg, "references.csv")?;
+    let mut insert = conn.prepare(INSERT)?;
+
+    for res in csv.deserialize() {
+        let r: WordReference = res?;
+        insert.execute(params![
+            r.reference_id,
+            r.word_id,
+            r.kind,
+            r.author,
+            r.title,
+            r.year,
+            r.publisher,
+            r.pages,
+            r.url
+        ])?;
+    }
+
+    Ok(())
+}
+
 fn sweep_tokens(conn: &Connection) -> Result<()> {
     const DELETE: &str =
         "DELETE FROM login_tokens WHERE JULIANDAY(?1) - JULIANDAY(last_used) > ?2;";
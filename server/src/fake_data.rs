@@ -0,0 +1,280 @@
+//! Fake dictionary entries for running the site locally without a copy of the production database.
+//! The isiXhosa is made up, but it is shaped like the real thing: nouns take the prefix of their
+//! class and may have a plural linked to them, and verbs end in -a.
+
+use crate::{open_db_connection, set_up_db, Config};
+use anyhow::{bail, Context, Result};
+use isixhosa::noun::NounClass;
+use isixhosa_common::language::{NounClassExt, PartOfSpeech, Transitivity, WordLinkType};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rusqlite::{params, Connection};
+use std::ops::RangeInclusive;
+
+const ONSETS: &[&str] = &[
+    "b", "bh", "c", "d", "dl", "f", "g", "gq", "hl", "j", "k", "kh", "l", "m", "n", "ng", "nk",
+    "ny", "p", "ph", "q", "qh", "s", "sh", "t", "th", "tsh", "v", "w", "x", "xh", "y", "z",
+];
+
+const VOWELS: &[&str] = &["a", "e", "i", "o", "u"];
+
+/// Singular classes along with the prefix which nouns in them are given
+const NOUN_CLASSES: &[(NounClass, &str)] = &[
+    (NounClass::Class1Um, "um"),
+    (NounClass::U, "u"),
+    (NounClass::Class3Um, "um"),
+    (NounClass::Ili, "i"),
+    (NounClass::Isi, "isi"),
+    (NounClass::In, "in"),
+    (NounClass::Ulu, "ulu"),
+    (NounClass::Ubu, "ubu"),
+];
+
+const NOUNS: &[&str] = &[
+    "person", "child", "house", "river", "mountain", "dog", "cow", "tree", "book", "road", "cloud",
+    "stone", "friend", "teacher", "song", "field", "bird", "door", "knife", "basket", "fire",
+    "blanket", "village", "chair", "horse", "star", "bridge", "garden", "pot", "spoon",
+];
+
+const VERBS: &[&str] = &[
+    "walk", "see", "eat", "sing", "build", "run", "speak", "laugh", "cook", "write", "read",
+    "carry", "dance", "listen", "plant", "sleep", "call", "wash", "buy", "teach",
+];
+
+const MODIFIERS: &[&str] = &[
+    "big", "small", "old", "new", "red", "long", "short", "quiet", "bright", "heavy", "wild",
+    "sweet", "cold", "warm", "young",
+];
+
+const ADVERBS: &[&str] = &[
+    "quickly", "slowly", "today", "tomorrow", "outside", "nearby", "again", "together", "loudly",
+];
+
+/// Fill an empty database with `words` fake entries, along with examples and links between them.
+/// The same seed always generates the same entries.
+pub fn generate(cfg: Config, words: usize, seed: u64) -> Result<()> {
+    let conn = open_db_connection(&cfg)?;
+
+    set_up_db(&conn)?;
+
+    let existing: u64 =
+        conn.query_row("SELECT COUNT(*) FROM words;", params![], |row| row.get(0))?;
+    if existing > 0 {
+        bail!("Fake data can only be added to an empty database, but it has {existing} words");
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let tx = conn.unchecked_transaction()?;
+    let mut ids = Vec::with_capacity(words);
+
+    while ids.len() < words {
+        let id = match rng.gen_range(0..10) {
+            0..=4 => insert_noun(&tx, &mut rng, &mut ids),
+            5..=7 => insert_verb(&tx, &mut rng),
+            8 => insert_modifier(&tx, &mut rng),
+            _ => insert_adverb(&tx, &mut rng),
+        };
+
+        for _ in 0..rng.gen_range(0..=2) {
+            insert_example(&tx, &mut rng, id);
+        }
+
+        ids.push(id);
+    }
+
+    for _ in 0..words / 10 {
+        let pair: Vec<&i64> = ids.choose_multiple(&mut rng, 2).collect();
+        if let [first, second] = pair[..] {
+            let link_type = *[WordLinkType::Related, WordLinkType::Antonym]
+                .choose(&mut rng)
+                .unwrap();
+            insert_link(&tx, link_type, *first, *second);
+        }
+    }
+
+    tx.commit()?;
+
+    // Force reindex on next start
+    std::fs::remove_dir_all(&cfg.tantivy_path).context("Couldn't delete tantivy data directory")?;
+    std::fs::create_dir_all(&cfg.tantivy_path).context("Couldn't create tantivy data directory")?;
+
+    println!("Added {} fake words", ids.len());
+
+    Ok(())
+}
+
+/// A stem of consonant-vowel syllables, e.g `gqobo`
+fn stem(rng: &mut StdRng, syllables: RangeInclusive<usize>) -> String {
+    (0..rng.gen_range(syllables))
+        .map(|_| {
+            let onset = ONSETS.choose(rng).unwrap();
+            let vowel = VOWELS.choose(rng).unwrap();
+            format!("{onset}{vowel}")
+        })
+        .collect()
+}
+
+fn pick<'a>(rng: &mut StdRng, words: &[&'a str]) -> &'a str {
+    words.choose(rng).unwrap()
+}
+
+/// Inserts a singular noun, and sometimes its plural too. Returns the ID of the singular.
+fn insert_noun(conn: &Connection, rng: &mut StdRng, ids: &mut Vec<i64>) -> i64 {
+    let (class, prefix) = *NOUN_CLASSES.choose(rng).unwrap();
+    let stem = stem(rng, 1..=3);
+
+    // Assimilation of the nasal, e.g impuku rather than inpuku
+    let prefix = match (prefix, stem.chars().next()) {
+        ("in", Some('b' | 'p' | 'f' | 'v')) => "im",
+        _ => prefix,
+    };
+    let xhosa = format!("{prefix}{stem}");
+    let english = format!("{} {}", pick(rng, MODIFIERS), pick(rng, NOUNS));
+
+    let singular = insert_word(
+        conn,
+        &english,
+        &xhosa,
+        PartOfSpeech::Noun,
+        false,
+        None,
+        Some(class),
+    );
+
+    if let (Some(plural_class), Some(plural)) = (class.plural(), class.pluralize(&xhosa)) {
+        if rng.gen_bool(0.5) {
+            let plural = insert_word(
+                conn,
+                &format!("{english}s"),
+                &plural,
+                PartOfSpeech::Noun,
+                true,
+                None,
+                Some(plural_class),
+            );
+            insert_link(conn, WordLinkType::PluralOrSingular, singular, plural);
+            ids.push(plural);
+        }
+    }
+
+    singular
+}
+
+fn insert_verb(conn: &Connection, rng: &mut StdRng) -> i64 {
+    let xhosa = format!("{}a", stem(rng, 1..=2));
+    let english = format!("to {}", pick(rng, VERBS));
+    let transitivity = *[
+        Transitivity::Transitive,
+        Transitivity::Intransitive,
+        Transitivity::Ambitransitive,
+    ]
+    .choose(rng)
+    .unwrap();
+
+    insert_word(
+        conn,
+        &english,
+        &xhosa,
+        PartOfSpeech::Verb,
+        false,
+        Some(transitivity),
+        None,
+    )
+}
+
+fn insert_modifier(conn: &Connection, rng: &mut StdRng) -> i64 {
+    let xhosa = stem(rng, 1..=2);
+    let part_of_speech = *[PartOfSpeech::Adjective, PartOfSpeech::Relative]
+        .choose(rng)
+        .unwrap();
+
+    insert_word(
+        conn,
+        pick(rng, MODIFIERS),
+        &xhosa,
+        part_of_speech,
+        false,
+        None,
+        None,
+    )
+}
+
+fn insert_adverb(conn: &Connection, rng: &mut StdRng) -> i64 {
+    let xhosa = format!("ka{}", stem(rng, 1..=2));
+
+    insert_word(
+        conn,
+        pick(rng, ADVERBS),
+        &xhosa,
+        PartOfSpeech::Adverb,
+        false,
+        None,
+        None,
+    )
+}
+
+fn insert_example(conn: &Connection, rng: &mut StdRng, word_id: i64) {
+    const INSERT: &str = "INSERT INTO examples (word_id, english, xhosa) VALUES (?1, ?2, ?3);";
+
+    let words: Vec<String> = (0..rng.gen_range(3..=6))
+        .map(|_| stem(rng, 1..=3))
+        .collect();
+    let mut xhosa = words.join(" ");
+    xhosa[..1].make_ascii_uppercase();
+    xhosa.push('.');
+
+    let english = format!(
+        "The {} will {} the {} {}.",
+        pick(rng, NOUNS),
+        pick(rng, VERBS),
+        pick(rng, NOUNS),
+        pick(rng, ADVERBS),
+    );
+
+    conn.prepare_cached(INSERT)
+        .unwrap()
+        .execute(params![word_id, english, xhosa])
+        .unwrap();
+}
+
+fn insert_word(
+    conn: &Connection,
+    english: &str,
+    xhosa: &str,
+    part_of_speech: PartOfSpeech,
+    is_plural: bool,
+    transitivity: Option<Transitivity>,
+    noun_class: Option<NounClass>,
+) -> i64 {
+    const INSERT: &str = "INSERT INTO words
+        (
+            english, xhosa, part_of_speech, xhosa_tone_markings, infinitive, is_plural,
+            is_inchoative, is_informal, transitivity, followed_by, noun_class, note
+        )
+        VALUES (?1, ?2, ?3, '', '', ?4, FALSE, FALSE, ?5, '', ?6, '');";
+
+    conn.prepare_cached(INSERT)
+        .unwrap()
+        .execute(params![
+            english,
+            xhosa,
+            part_of_speech,
+            is_plural,
+            transitivity,
+            noun_class.map(|class| class as u8),
+        ])
+        .unwrap();
+    conn.last_insert_rowid()
+}
+
+fn insert_link(conn: &Connection, link_type: WordLinkType, first: i64, second: i64) {
+    const INSERT: &str = "INSERT INTO linked_words
+            (link_type, first_word_id, second_word_id)
+        VALUES (?1, ?2, ?3)";
+
+    conn.prepare_cached(INSERT)
+        .unwrap()
+        .execute(params![link_type, first, second])
+        .unwrap();
+}
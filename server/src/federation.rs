@@ -0,0 +1,93 @@
+//! Searches other dictionaries running this software when a search finds nothing here, so that
+//! e.g a word missing from isiXhosa.click can still be found on a sister site. Peers are searched
+//! through the same JSON results which the search page's `raw` mode returns, and their results are
+//! always shown apart from this dictionary's, labelled with where they came from.
+
+use crate::config::{FederationConfig, FederationPeer};
+use anyhow::Result;
+use futures::future;
+use isixhosa_common::types::WordHit;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{instrument, warn};
+
+pub struct FederationClient {
+    client: reqwest::Client,
+    peers: Vec<FederationPeer>,
+    max_results: usize,
+}
+
+/// The results which one peer found
+#[derive(Debug)]
+pub struct ExternalHits {
+    /// The peer's name, shown next to its results
+    pub peer: String,
+    url: String,
+    pub hits: Vec<WordHit>,
+}
+
+impl ExternalHits {
+    /// The word's page on the peer's site
+    pub fn word_url(&self, hit: &WordHit) -> String {
+        format!("{}/word/{}", self.url, hit.id)
+    }
+}
+
+impl FederationClient {
+    pub fn new(cfg: FederationConfig) -> Result<Arc<FederationClient>> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(cfg.timeout_ms))
+            .user_agent(concat!("isixhosa_click/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Arc::new(FederationClient {
+            client,
+            peers: cfg.peers,
+            max_results: cfg.max_results,
+        }))
+    }
+
+    /// Search every peer at once. Peers which fail or take too long are left out, since their
+    /// results are only ever a fallback.
+    #[instrument(name = "Search peer dictionaries", skip(self))]
+    pub async fn search(&self, query: &str) -> Vec<ExternalHits> {
+        let searches = self.peers.iter().map(|peer| async move {
+            match self.search_peer(peer, query).await {
+                Ok(hits) => Some(ExternalHits {
+                    peer: peer.name.clone(),
+                    url: peer.url.trim_end_matches('/').to_owned(),
+                    hits,
+                }),
+                Err(e) => {
+                    warn!("Failed to search peer dictionary {}: {e:?}", peer.name);
+                    None
+                }
+            }
+        });
+
+        future::join_all(searches)
+            .await
+            .into_iter()
+            .flatten()
+            .filter(|external| !external.hits.is_empty())
+            .collect()
+    }
+
+    async fn search_peer(&self, peer: &FederationPeer, query: &str) -> Result<Vec<WordHit>> {
+        let url = format!("{}/search", peer.url.trim_end_matches('/'));
+        let mut hits: Vec<WordHit> = self
+            .client
+            .get(url)
+            .query(&[("query", query), ("raw", "true")])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // Only accepted words have pages which can be linked to
+        hits.retain(|hit| !hit.is_suggestion);
+        hits.truncate(self.max_results);
+        Ok(hits)
+    }
+}
@@ -0,0 +1,104 @@
+//! Messages shown once after a form has been submitted. Rather than rendering the outcome from the
+//! POST handler, which the browser would submit again on refresh, form handlers redirect with
+//! 303 See Other to the page which shows it. The messages are carried over in a session cookie, and
+//! are given to every template through [`I18nInfo::flashes`](isixhosa_common::i18n::I18nInfo). The
+//! cookie is cleared once a page has been rendered with them.
+
+use crate::i18n::I18nInfo;
+use cookie::time::Duration;
+use cookie::{Cookie, SameSite};
+use isixhosa_common::flash::{FlashLinkText, FlashMessage};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use std::convert::Infallible;
+use warp::http::header::{CONTENT_TYPE, SET_COOKIE};
+use warp::http::{HeaderValue, Uri};
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+const FLASH_COOKIE: &str = "flash";
+
+fn flash_cookie(value: String, clear: bool) -> HeaderValue {
+    let mut cookie = Cookie::build((FLASH_COOKIE, value))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .build();
+
+    // Otherwise it's a session cookie, so that messages which never got shown don't turn up on a
+    // much later visit
+    if clear {
+        cookie.set_max_age(Duration::ZERO);
+    }
+
+    HeaderValue::from_str(&cookie.to_string()).unwrap()
+}
+
+/// Redirect to the page at `to`, showing `messages` at the top of it
+pub fn redirect(to: &str, messages: Vec<FlashMessage>) -> Response {
+    let json = serde_json::to_string(&messages).unwrap();
+    let value = utf8_percent_encode(&json, NON_ALPHANUMERIC).to_string();
+
+    let mut response = warp::redirect::see_other(to.parse::<Uri>().unwrap()).into_response();
+    response
+        .headers_mut()
+        .append(SET_COOKIE, flash_cookie(value, false));
+    response
+}
+
+/// Where to check on a suggestion's status, shown after it has been submitted
+pub fn status_link(token: &str, i18n_info: &I18nInfo) -> FlashMessage {
+    let href = format!("/suggestion-status/{token}");
+    let url = format!("https://{}{href}", i18n_info.ctx.host);
+    FlashMessage::info("suggestion-status.link").with_link(href, FlashLinkText::Literal(url))
+}
+
+fn parse_flashes(cookie: &str) -> Option<Vec<FlashMessage>> {
+    let json = percent_decode_str(cookie).decode_utf8().ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Extracts the messages waiting to be shown, if there are any
+pub fn with_flashes() -> impl Filter<Extract = (Vec<FlashMessage>,), Error = Infallible> + Clone {
+    warp::cookie::optional::<String>(FLASH_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify()
+        .map(|cookie: Option<String>| {
+            cookie
+                .as_deref()
+                .and_then(parse_flashes)
+                .unwrap_or_default()
+        })
+}
+
+/// Clears the messages once a page has been rendered with them, so that they are only shown once.
+/// Redirects and anything other than an HTML page (e.g a stylesheet or a JSON response) are left
+/// alone, so that the messages are kept until they reach the page they're for.
+pub fn clear_shown<F>(filter: F) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (Response,), Error = Rejection> + Clone + Send + Sync + 'static,
+{
+    let had_flash = warp::cookie::optional::<String>(FLASH_COOKIE)
+        .or(warp::any().map(|| None))
+        .unify()
+        .map(|cookie: Option<String>| cookie.is_some_and(|cookie| !cookie.is_empty()));
+
+    had_flash
+        .and(filter)
+        .map(|had_flash: bool, mut response: Response| {
+            let is_page = response.status().is_success()
+                && response
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|ty| ty.to_str().ok())
+                    .is_some_and(|ty| ty.starts_with("text/html"));
+
+            if had_flash && is_page {
+                response
+                    .headers_mut()
+                    .append(SET_COOKIE, flash_cookie(String::new(), true));
+            }
+
+            response
+        })
+}
@@ -0,0 +1,117 @@
+//! Rendered HTML for parts of pages which are expensive to build but rarely change, such as the
+//! list of all words. Fragments are cached per language, and separately for school mode since it
+//! leaves out offensive words. They are tagged with the versions of the data they were built from,
+//! so a fragment is only rebuilt the first time it is needed after that data changes.
+
+use anyhow::Result;
+use chrono::{Datelike, Utc};
+use dashmap::DashMap;
+use fluent_templates::LanguageIdentifier;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{instrument, Span};
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Fragment {
+    /// The list on the all words page
+    AllWords,
+    /// The trending words on the search page
+    Trending,
+    /// Recently added words, word counts, and the featured word on the homepage
+    Homepage,
+}
+
+#[derive(Default)]
+pub struct FragmentCache {
+    /// Bumped whenever a word is added, edited, or deleted
+    words_version: AtomicU64,
+    /// Bumped whenever buffered word views are written to the database
+    views_version: AtomicU64,
+    fragments: DashMap<(Fragment, LanguageIdentifier, bool), CachedFragment>,
+}
+
+struct CachedFragment {
+    version: DataVersion,
+    html: String,
+}
+
+/// The versions of the data which a fragment was built from. Data which the fragment doesn't depend
+/// on is left at zero so that changes to it don't invalidate the fragment.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct DataVersion {
+    words: u64,
+    views: u64,
+    /// For fragments which change daily, e.g the featured word
+    day: i32,
+}
+
+impl FragmentCache {
+    pub fn words_changed(&self) {
+        self.words_version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn views_changed(&self) {
+        self.views_version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn version_for(&self, fragment: Fragment) -> DataVersion {
+        let words = self.words_version.load(Ordering::Relaxed);
+
+        match fragment {
+            Fragment::AllWords => DataVersion {
+                words,
+                views: 0,
+                day: 0,
+            },
+            Fragment::Trending => DataVersion {
+                words,
+                views: self.views_version.load(Ordering::Relaxed),
+                day: 0,
+            },
+            Fragment::Homepage => DataVersion {
+                words,
+                views: 0,
+                day: Utc::now().num_days_from_ce(),
+            },
+        }
+    }
+
+    /// Returns the cached fragment if the data it was built from hasn't changed since, or renders
+    /// and caches it otherwise.
+    #[instrument(name = "Get cached fragment", fields(hit), skip(self, render))]
+    pub async fn get_or_render<F, Fut>(
+        &self,
+        fragment: Fragment,
+        lang: &LanguageIdentifier,
+        school_mode: bool,
+        render: F,
+    ) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        // Read before rendering so that a change while rendering causes a rebuild next time
+        let version = self.version_for(fragment);
+        let key = (fragment, lang.clone(), school_mode);
+
+        if let Some(cached) = self.fragments.get(&key) {
+            if cached.version == version {
+                Span::current().record("hit", true);
+                return Ok(cached.html.clone());
+            }
+        }
+
+        Span::current().record("hit", false);
+
+        let html = render().await?;
+        self.fragments.insert(
+            key,
+            CachedFragment {
+                version,
+                html: html.clone(),
+            },
+        );
+
+        Ok(html)
+    }
+}
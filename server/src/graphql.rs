@@ -0,0 +1,325 @@
+//! A GraphQL endpoint at `/graphql`, so that apps can fetch exactly the parts of words they need in
+//! one request. Words are only fetched once they are asked for, and the examples and linked words
+//! of a word are only fetched if they are selected.
+
+use crate::auth::with_any_auth_read_only;
+use crate::config::BodyLimits;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::search::{IncludeResults, TantivyClient};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, Object, Result, Schema, SimpleObject,
+};
+use async_graphql_warp::GraphQLResponse;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::db_impl::ReadOnlyDbImpl;
+use isixhosa_common::database::{DbBase, PublicAccessDb, WordOrSuggestionId};
+use isixhosa_common::language::NounClassExt;
+use isixhosa_common::types::{ExistingExample, ExistingLinkedWord, ExistingWord, WordHit};
+use std::convert::Infallible;
+use std::sync::Arc;
+use warp::{body, Filter, Rejection, Reply};
+
+/// The most search results returned at once
+const MAX_SEARCH_RESULTS: usize = 50;
+const DEFAULT_SEARCH_RESULTS: usize = 10;
+
+/// Queries nested deeper than this are refused, since each level of linked words can fetch many
+/// more words
+const MAX_DEPTH: usize = 8;
+const MAX_COMPLEXITY: usize = 500;
+
+pub type DictionarySchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn graphql(
+    db: DbBase,
+    tantivy: Arc<TantivyClient>,
+    site_ctx: Arc<SiteContext>,
+    body_limits: BodyLimits,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(ReadOnlyDbImpl(db.read_pool().clone()))
+        .data(tantivy)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish();
+
+    // GET requests have no body, so only POST requests are limited
+    let post = warp::post()
+        .and(body::content_length_limit(body_limits.form))
+        .and(async_graphql_warp::graphql(schema.clone()));
+    let get = warp::get().and(async_graphql_warp::graphql(schema));
+
+    warp::path("graphql")
+        .and(warp::path::end())
+        .and(post.or(get).unify())
+        .and(with_any_auth_read_only(db, site_ctx))
+        .and_then(execute)
+        .debug_boxed()
+}
+
+async fn execute(
+    (schema, request): (DictionarySchema, async_graphql::Request),
+    _auth: Auth,
+    i18n_info: I18nInfo,
+    _db: impl PublicAccessDb,
+) -> Result<GraphQLResponse, Infallible> {
+    Ok(schema.execute(request.data(i18n_info)).await.into())
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(remote = "isixhosa_common::language::PartOfSpeech")]
+enum PartOfSpeech {
+    Verb,
+    Noun,
+    Adjective,
+    Adverb,
+    Relative,
+    Interjection,
+    Conjunction,
+    Preposition,
+    Ideophone,
+    BoundMorpheme,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(remote = "isixhosa_common::language::Transitivity")]
+enum Transitivity {
+    Transitive,
+    Intransitive,
+    Ambitransitive,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(remote = "isixhosa_common::language::WordLinkType")]
+enum WordLinkType {
+    PluralOrSingular,
+    AlternateUse,
+    Antonym,
+    Related,
+    Confusable,
+}
+
+/// The prefixes of a noun's class
+#[derive(SimpleObject)]
+struct NounClass {
+    singular_prefix: String,
+    plural_prefix: Option<String>,
+    /// Whether the noun is in the singular half of the class
+    is_singular: bool,
+}
+
+impl From<isixhosa::noun::NounClass> for NounClass {
+    fn from(class: isixhosa::noun::NounClass) -> Self {
+        let prefixes = class.to_prefixes();
+
+        NounClass {
+            singular_prefix: prefixes.singular.into_owned(),
+            plural_prefix: prefixes.plural.map(|p| p.into_owned()),
+            is_singular: prefixes.selected_singular,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+struct Example {
+    id: u64,
+    english: String,
+    xhosa: String,
+}
+
+impl From<ExistingExample> for Example {
+    fn from(example: ExistingExample) -> Self {
+        Example {
+            id: example.example_id,
+            english: example.english,
+            xhosa: example.xhosa,
+        }
+    }
+}
+
+/// A link between two words, seen from one of them
+struct LinkedWord(ExistingLinkedWord);
+
+#[Object]
+impl LinkedWord {
+    async fn link_type(&self) -> WordLinkType {
+        self.0.link_type.into()
+    }
+
+    /// The word on the other end of the link
+    async fn word(&self) -> WordSummary {
+        WordSummary(self.0.other.clone())
+    }
+}
+
+/// The headline details of a word, as shown in search results
+struct WordSummary(WordHit);
+
+#[Object]
+impl WordSummary {
+    async fn id(&self) -> u64 {
+        self.0.id
+    }
+
+    async fn english(&self) -> &str {
+        &self.0.english
+    }
+
+    async fn xhosa(&self) -> &str {
+        &self.0.xhosa
+    }
+
+    async fn part_of_speech(&self) -> Option<PartOfSpeech> {
+        self.0.part_of_speech.map(Into::into)
+    }
+
+    async fn is_plural(&self) -> bool {
+        self.0.is_plural
+    }
+
+    /// The full entry for the word
+    async fn details(&self, ctx: &Context<'_>) -> Result<Option<Word>> {
+        fetch_word(ctx, self.0.id).await
+    }
+}
+
+/// A dictionary entry. Each entry has one meaning; the other meanings of the same word are
+/// separate entries, listed as its senses.
+struct Word(ExistingWord);
+
+#[Object]
+impl Word {
+    async fn id(&self) -> u64 {
+        self.0.word_id
+    }
+
+    async fn english(&self) -> &str {
+        &self.0.english
+    }
+
+    async fn xhosa(&self) -> &str {
+        &self.0.xhosa
+    }
+
+    async fn part_of_speech(&self) -> Option<PartOfSpeech> {
+        self.0.part_of_speech.map(Into::into)
+    }
+
+    async fn xhosa_tone_markings(&self) -> &str {
+        &self.0.xhosa_tone_markings
+    }
+
+    async fn infinitive(&self) -> &str {
+        &self.0.infinitive
+    }
+
+    async fn is_plural(&self) -> bool {
+        self.0.is_plural
+    }
+
+    async fn is_inchoative(&self) -> bool {
+        self.0.is_inchoative
+    }
+
+    async fn is_informal(&self) -> bool {
+        self.0.is_informal
+    }
+
+    async fn transitivity(&self) -> Option<Transitivity> {
+        self.0.transitivity.map(Into::into)
+    }
+
+    async fn noun_class(&self) -> Option<NounClass> {
+        self.0.noun_class.map(Into::into)
+    }
+
+    async fn note(&self) -> &str {
+        &self.0.note
+    }
+
+    async fn examples(&self, ctx: &Context<'_>) -> Result<Vec<Example>> {
+        let db = ctx.data::<ReadOnlyDbImpl>()?.clone();
+        let id = self.0.word_id;
+        let examples =
+            spawn_blocking_child(move || ExistingExample::fetch_all_for_word(&db, id)).await?;
+
+        Ok(examples.into_iter().map(Into::into).collect())
+    }
+
+    /// Every word linked to this one, e.g its plural or antonyms
+    async fn linked_words(&self, ctx: &Context<'_>) -> Result<Vec<LinkedWord>> {
+        fetch_links(ctx, self.0.word_id, None).await
+    }
+
+    /// The other meanings of this word, which are linked to it as alternate uses
+    async fn senses(&self, ctx: &Context<'_>) -> Result<Vec<WordSummary>> {
+        let links = fetch_links(ctx, self.0.word_id, Some(WordLinkType::AlternateUse)).await?;
+        Ok(links.into_iter().map(|l| WordSummary(l.0.other)).collect())
+    }
+}
+
+async fn fetch_word(ctx: &Context<'_>, id: u64) -> Result<Option<Word>> {
+    let db = ctx.data::<ReadOnlyDbImpl>()?.clone();
+    let word = spawn_blocking_child(move || ExistingWord::fetch_alone(&db, id)).await?;
+    Ok(word.map(Word))
+}
+
+async fn fetch_links(
+    ctx: &Context<'_>,
+    word_id: u64,
+    link_type: Option<WordLinkType>,
+) -> Result<Vec<LinkedWord>> {
+    let db = ctx.data::<ReadOnlyDbImpl>()?.clone();
+    let links =
+        spawn_blocking_child(move || ExistingLinkedWord::fetch_all_for_word(&db, word_id)).await?;
+
+    Ok(links
+        .into_iter()
+        .filter(|link| link_type.map_or(true, |t| WordLinkType::from(link.link_type) == t))
+        .map(LinkedWord)
+        .collect())
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Fetch a word by its ID
+    async fn word(&self, ctx: &Context<'_>, id: u64) -> Result<Option<Word>> {
+        fetch_word(ctx, id).await
+    }
+
+    /// Fetch a word which appears in search results, by its ID
+    async fn word_summary(&self, ctx: &Context<'_>, id: u64) -> Result<Option<WordSummary>> {
+        let db = ctx.data::<ReadOnlyDbImpl>()?.clone();
+        let hit = spawn_blocking_child(move || {
+            WordHit::fetch_from_db(&db, WordOrSuggestionId::existing(id))
+        })
+        .await?;
+
+        Ok(hit.map(WordSummary))
+    }
+
+    /// Search for words in either language, as on the search page. Only accepted words are
+    /// included.
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        query: String,
+        #[graphql(default_with = "DEFAULT_SEARCH_RESULTS")] limit: usize,
+    ) -> Result<Vec<WordSummary>> {
+        let tantivy = ctx.data::<Arc<TantivyClient>>()?;
+        let i18n_info = ctx.data::<I18nInfo>()?.clone();
+
+        let hits: Vec<WordHit> = tantivy
+            .search(query, IncludeResults::AcceptedOnly, false, i18n_info)
+            .await?;
+
+        Ok(hits
+            .into_iter()
+            .take(limit.min(MAX_SEARCH_RESULTS))
+            .map(WordSummary)
+            .collect())
+    }
+}
@@ -0,0 +1,279 @@
+//! A gRPC lookup service for internal tools and the mobile app backend, described in
+//! `proto/lookup.proto`. It reads from the same database and search index as the site, and only
+//! ever returns accepted words.
+
+use crate::config::GrpcConfig;
+use crate::i18n::{I18nInfo, SiteContext, EN_ZA};
+use crate::search::{IncludeResults, TantivyClient};
+use crate::spawn_blocking_child;
+use isixhosa_common::database::db_impl::ReadOnlyDbImpl;
+use isixhosa_common::database::DbBase;
+use isixhosa_common::language::{NounClassExt, NounClassPrefixes};
+use isixhosa_common::normalize;
+use isixhosa_common::search_direction::SearchDirection;
+use isixhosa_common::theme::Theme;
+use isixhosa_common::types::{ExistingWord, WordHit};
+use proto::dictionary_server::{Dictionary, DictionaryServer};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use tracing::{error, info, instrument};
+
+pub mod proto {
+    tonic::include_proto!("isixhosa.lookup.v1");
+}
+
+/// The most search results returned at once
+const MAX_SEARCH_RESULTS: usize = 50;
+const DEFAULT_SEARCH_RESULTS: usize = 10;
+/// The most words returned by a lookup
+const MAX_LOOKUP_RESULTS: usize = 20;
+
+struct DictionaryService {
+    db: ReadOnlyDbImpl,
+    tantivy: Arc<TantivyClient>,
+    i18n_info: I18nInfo,
+}
+
+/// Start serving the gRPC lookup service in the background
+pub fn start(cfg: GrpcConfig, db: DbBase, tantivy: Arc<TantivyClient>, site_ctx: Arc<SiteContext>) {
+    let service = DictionaryService {
+        db: ReadOnlyDbImpl(db.read_pool().clone()),
+        tantivy,
+        i18n_info: I18nInfo {
+            user_language: EN_ZA,
+            theme: Theme::default(),
+            search_direction: SearchDirection::default(),
+            school_mode: site_ctx.school_mode,
+            flashes: Vec::new(),
+            csp_nonce: String::new(),
+            ctx: site_ctx,
+        },
+    };
+
+    info!(address = %cfg.address, "gRPC server listening");
+
+    tokio::spawn(async move {
+        let res = tonic::transport::Server::builder()
+            .add_service(DictionaryServer::new(service))
+            .serve(cfg.address)
+            .await;
+
+        if let Err(error) = res {
+            error!(?error, "gRPC server stopped");
+        }
+    });
+}
+
+impl DictionaryService {
+    async fn search_accepted(&self, query: String) -> Result<Vec<WordHit>, Status> {
+        self.tantivy
+            .search(
+                query,
+                IncludeResults::AcceptedOnly,
+                false,
+                self.i18n_info.clone(),
+            )
+            .await
+            .map_err(internal)
+    }
+}
+
+#[tonic::async_trait]
+impl Dictionary for DictionaryService {
+    #[instrument(name = "gRPC lookup", skip_all, fields(text = %request.get_ref().text))]
+    async fn lookup(
+        &self,
+        request: Request<proto::LookupRequest>,
+    ) -> Result<Response<proto::LookupResponse>, Status> {
+        let text = normalize::fold(request.into_inner().text.trim());
+
+        let ids: Vec<u64> = self
+            .search_accepted(text.clone())
+            .await?
+            .into_iter()
+            .filter(|hit| {
+                normalize::matches(&hit.xhosa, &text) || normalize::matches(&hit.english, &text)
+            })
+            .map(|hit| hit.id)
+            .take(MAX_LOOKUP_RESULTS)
+            .collect();
+
+        let db = self.db.clone();
+        let words = spawn_blocking_child(move || {
+            ids.into_iter()
+                .filter_map(|id| ExistingWord::fetch_full(&db, id))
+                .map(Into::into)
+                .collect()
+        })
+        .await
+        .map_err(internal)?;
+
+        Ok(Response::new(proto::LookupResponse { words }))
+    }
+
+    #[instrument(name = "gRPC search", skip_all, fields(query = %request.get_ref().query))]
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let request = request.into_inner();
+        let limit = match request.limit {
+            0 => DEFAULT_SEARCH_RESULTS,
+            limit => (limit as usize).min(MAX_SEARCH_RESULTS),
+        };
+
+        let results = self
+            .search_accepted(request.query)
+            .await?
+            .into_iter()
+            .take(limit)
+            .map(Into::into)
+            .collect();
+
+        Ok(Response::new(proto::SearchResponse { results }))
+    }
+
+    #[instrument(name = "gRPC get word", skip_all, fields(id = request.get_ref().id))]
+    async fn get_word(
+        &self,
+        request: Request<proto::GetWordRequest>,
+    ) -> Result<Response<proto::Word>, Status> {
+        let id = request.into_inner().id;
+        let db = self.db.clone();
+        let word = spawn_blocking_child(move || ExistingWord::fetch_full(&db, id))
+            .await
+            .map_err(internal)?;
+
+        // Offensive words are left out of search results in school mode, so they can't be
+        // looked up directly either
+        let school_mode = self.i18n_info.school_mode;
+        match word.filter(|word| !(school_mode && word.is_offensive)) {
+            Some(word) => Ok(Response::new(word.into())),
+            None => Err(Status::not_found(format!("No word with ID {id}"))),
+        }
+    }
+}
+
+fn internal(error: impl std::fmt::Debug) -> Status {
+    error!(?error, "Error in gRPC request");
+    Status::internal("Internal server error")
+}
+
+impl From<isixhosa_common::language::PartOfSpeech> for proto::PartOfSpeech {
+    fn from(pos: isixhosa_common::language::PartOfSpeech) -> Self {
+        use isixhosa_common::language::PartOfSpeech::*;
+
+        match pos {
+            Verb => proto::PartOfSpeech::Verb,
+            Noun => proto::PartOfSpeech::Noun,
+            Adjective => proto::PartOfSpeech::Adjective,
+            Adverb => proto::PartOfSpeech::Adverb,
+            Relative => proto::PartOfSpeech::Relative,
+            Interjection => proto::PartOfSpeech::Interjection,
+            Conjunction => proto::PartOfSpeech::Conjunction,
+            Preposition => proto::PartOfSpeech::Preposition,
+            Ideophone => proto::PartOfSpeech::Ideophone,
+            BoundMorpheme => proto::PartOfSpeech::BoundMorpheme,
+        }
+    }
+}
+
+impl From<isixhosa_common::language::Transitivity> for proto::Transitivity {
+    fn from(transitivity: isixhosa_common::language::Transitivity) -> Self {
+        use isixhosa_common::language::Transitivity::*;
+
+        match transitivity {
+            Transitive => proto::Transitivity::Transitive,
+            Intransitive => proto::Transitivity::Intransitive,
+            Ambitransitive => proto::Transitivity::Ambitransitive,
+        }
+    }
+}
+
+impl From<isixhosa_common::language::WordLinkType> for proto::WordLinkType {
+    fn from(link_type: isixhosa_common::language::WordLinkType) -> Self {
+        use isixhosa_common::language::WordLinkType::*;
+
+        match link_type {
+            PluralOrSingular => proto::WordLinkType::PluralOrSingular,
+            AlternateUse => proto::WordLinkType::AlternateUse,
+            Antonym => proto::WordLinkType::Antonym,
+            Related => proto::WordLinkType::Related,
+            Confusable => proto::WordLinkType::Confusable,
+        }
+    }
+}
+
+impl From<NounClassPrefixes> for proto::NounClass {
+    fn from(prefixes: NounClassPrefixes) -> Self {
+        proto::NounClass {
+            singular_prefix: prefixes.singular.into_owned(),
+            plural_prefix: prefixes.plural.map(|p| p.into_owned()),
+            is_singular: prefixes.selected_singular,
+        }
+    }
+}
+
+impl From<WordHit> for proto::WordSummary {
+    fn from(hit: WordHit) -> Self {
+        proto::WordSummary {
+            id: hit.id,
+            english: hit.english,
+            xhosa: hit.xhosa,
+            part_of_speech: hit
+                .part_of_speech
+                .map_or(proto::PartOfSpeech::Unspecified, Into::into)
+                .into(),
+            is_plural: hit.is_plural,
+            is_inchoative: hit.is_inchoative,
+            is_informal: hit.is_informal,
+            transitivity: hit
+                .transitivity
+                .map_or(proto::Transitivity::Unspecified, Into::into)
+                .into(),
+            noun_class: hit.noun_class.map(Into::into),
+        }
+    }
+}
+
+impl From<ExistingWord> for proto::Word {
+    fn from(word: ExistingWord) -> Self {
+        proto::Word {
+            id: word.word_id,
+            english: word.english,
+            xhosa: word.xhosa,
+            part_of_speech: word
+                .part_of_speech
+                .map_or(proto::PartOfSpeech::Unspecified, Into::into)
+                .into(),
+            xhosa_tone_markings: word.xhosa_tone_markings,
+            infinitive: word.infinitive,
+            is_plural: word.is_plural,
+            is_inchoative: word.is_inchoative,
+            is_informal: word.is_informal,
+            transitivity: word
+                .transitivity
+                .map_or(proto::Transitivity::Unspecified, Into::into)
+                .into(),
+            noun_class: word.noun_class.map(|class| class.to_prefixes().into()),
+            note: word.note,
+            examples: word
+                .examples
+                .into_iter()
+                .map(|example| proto::Example {
+                    id: example.example_id,
+                    english: example.english,
+                    xhosa: example.xhosa,
+                })
+                .collect(),
+            linked_words: word
+                .linked_words
+                .into_iter()
+                .map(|link| proto::LinkedWord {
+                    link_type: proto::WordLinkType::from(link.link_type).into(),
+                    word: Some(link.other.into()),
+                })
+                .collect(),
+        }
+    }
+}
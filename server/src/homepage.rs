@@ -0,0 +1,97 @@
+use crate::auth::with_any_auth;
+use crate::database::homepage::{count_by_part_of_speech, fetch_featured};
+use crate::database::revisions::fetch_recently_added;
+use crate::error::ServerError;
+use crate::fragment_cache::{Fragment, FragmentCache};
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use chrono::Utc;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, PublicAccessDb};
+use isixhosa_common::format::DisplayHtml;
+use isixhosa_common::language::PartOfSpeech;
+use isixhosa_common::types::WordHit;
+use std::sync::Arc;
+use tracing::instrument;
+use warp::{path, Filter, Rejection, Reply};
+
+const RECENT_WORDS: u64 = 10;
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "homepage.askama.html")]
+struct Homepage {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    /// Rendered from [`HomepageSections`]
+    sections: String,
+}
+
+/// Inner part of the [`Homepage`] template which is regenerated only as needed
+#[derive(Template, I18nTemplate)]
+#[template(path = "homepage.sections.askama.html")]
+struct HomepageSections {
+    i18n_info: I18nInfo,
+    featured: Option<WordHit>,
+    recently_added: Vec<WordHit>,
+    total_words: u64,
+    counts_by_part_of_speech: Vec<(Option<PartOfSpeech>, u64)>,
+}
+
+impl HomepageSections {
+    fn fetch(db: &impl PublicAccessDb, i18n_info: I18nInfo) -> HomepageSections {
+        let counts_by_part_of_speech = count_by_part_of_speech(db);
+        let recently_added = fetch_recently_added(db, RECENT_WORDS, !i18n_info.school_mode);
+
+        HomepageSections {
+            i18n_info,
+            featured: fetch_featured(db, Utc::now().date_naive()),
+            recently_added,
+            total_words: counts_by_part_of_speech.iter().map(|(_, n)| n).sum(),
+            counts_by_part_of_speech,
+        }
+    }
+}
+
+pub fn homepage(
+    db: DbBase,
+    fragments: Arc<FragmentCache>,
+    site_ctx: Arc<SiteContext>,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    warp::get()
+        .and(path::end())
+        .and(warp::any().map(move || fragments.clone()))
+        .and(with_any_auth(db, site_ctx))
+        .and_then(homepage_page)
+        .debug_boxed()
+}
+
+#[instrument(name = "Show homepage", skip_all)]
+async fn homepage_page(
+    fragments: Arc<FragmentCache>,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let i18n_clone = i18n_info.clone();
+    let sections = fragments
+        .get_or_render(
+            Fragment::Homepage,
+            &i18n_info.user_language,
+            i18n_info.school_mode,
+            || async move {
+                let sections =
+                    spawn_blocking_child(move || HomepageSections::fetch(&db, i18n_clone)).await?;
+                Ok(sections.render()?)
+            },
+        )
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(Homepage {
+        auth,
+        i18n_info,
+        sections,
+    })
+}
@@ -25,6 +25,9 @@ pub fn load(site: String, config: &Config) -> SiteContext {
     site_specific_shared.push(&site);
     site_specific_shared.push("shared.ftl");
 
+    let language = config.language.clone();
+    let overrides = language.fluent_overrides();
+
     let loader = ArcLoader::builder(&base, EN_ZA)
         .shared_resources(Some(&[
             ["translations", "locales", "shared.ftl"].iter().collect(),
@@ -57,6 +60,11 @@ pub fn load(site: String, config: &Config) -> SiteContext {
             let site_resource = FluentResource::try_new(site_ftl)
                 .expect("Couldn't parse site-specific fluent file");
             bundle.add_resource(Arc::new(site_resource)).unwrap();
+
+            // The names set in the config take precedence over those in the translations
+            let overrides = FluentResource::try_new(overrides.clone())
+                .expect("Couldn't parse the names set in the language config");
+            bundle.add_resource_overriding(Arc::new(overrides));
         })
         .build()
         .expect("Error loading fluent resources");
@@ -73,6 +81,8 @@ pub fn load(site: String, config: &Config) -> SiteContext {
         site_i18n: loader,
         supported_langs: supported,
         host: config.host.clone(),
+        language,
+        school_mode: config.school_mode,
     }
 }
 
@@ -1,4 +1,4 @@
-use crate::{set_up_db, Config};
+use crate::{open_db_connection, set_up_db, Config};
 use anyhow::{Context, Result};
 use isixhosa_common::language::WordLinkType;
 use itertools::Itertools;
@@ -8,7 +8,7 @@ use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 pub fn import_zulu_lsp(cfg: Config, path: &Path) -> Result<()> {
-    let conn = Connection::open(&cfg.database_path)?;
+    let conn = open_db_connection(&cfg)?;
 
     set_up_db(&conn)?;
 
@@ -0,0 +1,541 @@
+//! Importing suggestions in bulk from a CSV file. Each row is read like a submission of the word
+//! form, with a column for each of the form's fields, and is saved as a suggestion for moderators
+//! to review. The suggestions from one file form an import batch, which they can be traced back to.
+//!
+//! A bad batch can be rolled back as a whole: its pending suggestions are rejected, the words it
+//! added are deleted, and the words it edited are put back as they were. Words which have been
+//! edited again since are left alone. What would happen is shown before anything is changed.
+
+use crate::auth::{reply_reauthenticate, with_administrator_auth, FullUser};
+use crate::config::BodyLimits;
+use crate::csrf::{csrf_protected, csrf_protected_multipart};
+use crate::database::homographs::link_homographs;
+use crate::database::imports::{
+    create_batch, fetch_batch, fetch_batches, fetch_rollback_plan, mark_rolled_back, ImportBatch,
+    RollbackPlan,
+};
+use crate::database::provenance::Provenance;
+use crate::database::revisions::record_revision;
+use crate::database::slugs::update_slug;
+use crate::database::submit::{submit_suggestion, WordSubmission};
+use crate::error::{DbError, ServerError};
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::moderation::reject_suggested_word;
+use crate::search::TantivyClient;
+use crate::serialization::{deserialize_form, FormFields, InvalidForm, MultipartForm};
+use crate::{flash, spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{AdministratorAccessDb, DbBase, WordOrSuggestionId};
+use isixhosa_common::flash::FlashMessage;
+use isixhosa_common::language::LanguageProfile;
+use isixhosa_common::types::{ExistingLinkedWord, ExistingWord};
+use std::sync::Arc;
+use tracing::{info, instrument};
+use warp::{Filter, Rejection, Reply};
+
+/// The multipart field which the CSV file is uploaded through
+const FILE_FIELD: &str = "file";
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "imports.askama.html")]
+struct ImportsTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    batches: Vec<ImportBatch>,
+    report: Option<ImportReport>,
+}
+
+/// What happened to the rows of an uploaded file
+#[derive(Debug, Default)]
+struct ImportReport {
+    /// The batch which was created, unless no rows could be imported
+    batch_id: Option<u64>,
+    imported: usize,
+    skipped: Vec<SkippedRow>,
+}
+
+/// A row which wasn't imported. Lines are counted from 1, including the header.
+#[derive(Debug)]
+struct SkippedRow {
+    line: u64,
+    error: RowError,
+}
+
+#[derive(Debug)]
+enum RowError {
+    /// The row couldn't be read as a submission, e.g because a column is missing or a value isn't
+    /// one which the form would send
+    Unreadable { field: Option<String> },
+    /// The row was read, but these fields aren't valid
+    Invalid { fields: Vec<&'static str> },
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "import_rollback.askama.html")]
+struct RollbackTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    batch: ImportBatch,
+    plan: RollbackPlan,
+}
+
+pub fn imports(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    tantivy: Arc<TantivyClient>,
+    body_limits: BodyLimits,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let with_tantivy = warp::any().map(move || tantivy.clone());
+    let base = with_administrator_auth(db, site_ctx);
+
+    let page = warp::path::end()
+        .and(warp::get())
+        .and(base.clone())
+        .and_then(imports_page);
+
+    let upload = warp::path::end()
+        .and(warp::post())
+        .and(base.clone())
+        .and(csrf_protected_multipart(body_limits.upload))
+        .and(with_tantivy.clone())
+        .and_then(import_file);
+
+    let rollback_page = warp::path!(u64 / "rollback")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(base.clone())
+        .and_then(rollback_page);
+
+    let rollback = warp::path!(u64 / "rollback")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(base)
+        .and(csrf_protected())
+        .and(with_tantivy)
+        .and_then(rollback_batch);
+
+    warp::path!("admin" / "settings" / "imports" / ..)
+        .and(page.or(upload).or(rollback_page).or(rollback))
+        .debug_boxed()
+}
+
+async fn imports_template(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    report: Option<ImportReport>,
+) -> Result<ImportsTemplate, Rejection> {
+    let batches = spawn_blocking_child(move || fetch_batches(&db))
+        .await
+        .map_err(ServerError::from)?
+        .map_err(ServerError::from)?;
+
+    Ok(ImportsTemplate {
+        auth: user.into(),
+        i18n_info,
+        batches,
+        report,
+    })
+}
+
+#[instrument(name = "Show imports page", skip_all)]
+async fn imports_page(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    imports_template(user, i18n_info, db, None).await
+}
+
+/// Read the rows of the file, with the line each is on. A file which isn't valid CSV at all is
+/// reported as an unreadable first line.
+fn read_rows(
+    bytes: &[u8],
+    language: &LanguageProfile,
+) -> Vec<(u64, Result<WordSubmission, RowError>)> {
+    let mut reader = csv::Reader::from_reader(bytes);
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(_) => return vec![(1, Err(RowError::Unreadable { field: None }))],
+    };
+
+    reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let line = record
+                .as_ref()
+                .ok()
+                .and_then(|record| record.position())
+                .map_or(i as u64 + 2, |pos| pos.line());
+            (line, read_row(&headers, record, language))
+        })
+        .collect()
+}
+
+fn read_row(
+    headers: &csv::StringRecord,
+    record: csv::Result<csv::StringRecord>,
+    language: &LanguageProfile,
+) -> Result<WordSubmission, RowError> {
+    let record = record.map_err(|_| RowError::Unreadable { field: None })?;
+    let fields = FormFields(
+        headers
+            .iter()
+            .zip(record.iter())
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect(),
+    );
+
+    let mut submission: WordSubmission = deserialize_form(fields.to_urlencoded().as_bytes())
+        .map_err(|rejection| RowError::Unreadable {
+            field: rejection
+                .find::<InvalidForm>()
+                .and_then(|err| err.field.clone()),
+        })?;
+
+    // Imported rows are always new suggestions, even if the file was exported from a form
+    submission.suggestion_id = None;
+    submission.draft_id = None;
+    submission.canonicalize();
+
+    submission
+        .validate(language)
+        .map_err(|errors| RowError::Invalid {
+            fields: errors.fields().collect(),
+        })?;
+
+    Ok(submission)
+}
+
+#[instrument(name = "Import suggestions", fields(user_id = %user.id), skip_all)]
+async fn import_file(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    form: MultipartForm,
+    tantivy: Arc<TantivyClient>,
+) -> Result<impl Reply, Rejection> {
+    let (filename, rows) = match form.file(FILE_FIELD) {
+        Some(file) => (
+            file.filename.clone(),
+            read_rows(&file.bytes, &i18n_info.ctx.language),
+        ),
+        None => (String::new(), Vec::new()),
+    };
+
+    let mut report = ImportReport::default();
+    let mut submissions = Vec::new();
+
+    for (line, row) in rows {
+        match row {
+            Ok(submission) => submissions.push(submission),
+            Err(error) => report.skipped.push(SkippedRow { line, error }),
+        }
+    }
+
+    if !submissions.is_empty() {
+        let (db_clone, user_id) = (db.clone(), user.id.get());
+        let batch_id = spawn_blocking_child(move || create_batch(&db_clone, user_id, &filename))
+            .await
+            .map_err(ServerError::from)?
+            .map_err(ServerError::from)?;
+
+        for submission in submissions {
+            let submitted = submit_suggestion(
+                submission,
+                tantivy.clone(),
+                &user,
+                &db,
+                i18n_info.clone(),
+                Provenance::Import { batch_id },
+            )
+            .await?;
+
+            if submitted.is_some() {
+                report.imported += 1;
+            }
+        }
+
+        report.batch_id = Some(batch_id);
+        info!(
+            batch_id,
+            imported = report.imported,
+            skipped = report.skipped.len(),
+            "Imported suggestions"
+        );
+    }
+
+    imports_template(user, i18n_info, db, Some(report)).await
+}
+
+async fn fetch_batch_and_plan(
+    db: &impl AdministratorAccessDb,
+    batch_id: u64,
+) -> Result<(ImportBatch, RollbackPlan), Rejection> {
+    let db = db.clone();
+    let (batch, plan) = spawn_blocking_child(move || {
+        Ok::<_, DbError>((
+            fetch_batch(&db, batch_id)?,
+            fetch_rollback_plan(&db, batch_id)?,
+        ))
+    })
+    .await
+    .map_err(ServerError::from)?
+    .map_err(ServerError::from)?;
+
+    match batch {
+        Some(batch) => Ok((batch, plan)),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+/// Shows what rolling back the batch would do, so that it can be checked before confirming
+#[instrument(name = "Show import batch rollback", skip(user, i18n_info, db))]
+async fn rollback_page(
+    batch_id: u64,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let (batch, plan) = fetch_batch_and_plan(&db, batch_id).await?;
+
+    Ok(RollbackTemplate {
+        auth: user.into(),
+        i18n_info,
+        batch,
+        plan,
+    })
+}
+
+#[instrument(
+    name = "Roll back import batch",
+    fields(user_id = %user.id),
+    skip(user, _i18n_info, db, tantivy)
+)]
+async fn rollback_batch(
+    batch_id: u64,
+    user: FullUser,
+    _i18n_info: I18nInfo,
+    db: impl AdministratorAccessDb,
+    tantivy: Arc<TantivyClient>,
+) -> Result<impl Reply, Rejection> {
+    let page = format!("/admin/settings/imports/{batch_id}/rollback");
+
+    if !user.signed_in_recently() {
+        return Ok(reply_reauthenticate(&page));
+    }
+
+    // The plan is worked out again, in case the batch's words have changed since it was shown
+    let (batch, plan) = fetch_batch_and_plan(&db, batch_id).await?;
+
+    if batch.rolled_back.is_some() {
+        return Ok(flash::redirect(
+            &page,
+            vec![FlashMessage::error("import-rollback.already-rolled-back")],
+        ));
+    }
+
+    let reason = format!("Import batch #{batch_id} was rolled back");
+    for suggestion in &plan.pending {
+        reject_suggested_word(&db, tantivy.clone(), suggestion.id, Some(reason.clone())).await?;
+    }
+
+    let RollbackPlan {
+        pending,
+        added,
+        edited,
+        conflicts,
+    } = plan;
+
+    let restored = edited.len();
+    let (db_clone, user_id) = (db.clone(), user.id.get());
+    let (deleted, to_reindex) = spawn_blocking_child(move || {
+        let db = db_clone;
+        let mut to_reindex = Vec::new();
+
+        let deleted: Vec<u64> = added.iter().map(|word| word.id).collect();
+        for &word_id in &deleted {
+            to_reindex.extend(ExistingLinkedWord::delete_all_for_word(&db, word_id));
+            ExistingWord::delete(&db, word_id);
+        }
+
+        for (word, previous) in &edited {
+            previous.restore(&db, word.id);
+            update_slug(&db, word.id, previous.xhosa());
+            link_homographs(&db, word.id);
+            record_revision(&db, word.id, user_id, false, None, None);
+            to_reindex.push(word.id);
+        }
+
+        to_reindex.retain(|word_id| !deleted.contains(word_id));
+        mark_rolled_back(&db, batch_id)?;
+
+        Ok::<_, DbError>((deleted, to_reindex))
+    })
+    .await
+    .map_err(ServerError::from)?
+    .map_err(ServerError::from)?;
+
+    for &word_id in &deleted {
+        tantivy
+            .delete_word(WordOrSuggestionId::existing(word_id))
+            .await;
+    }
+
+    tantivy.reindex_words(db, to_reindex).await;
+
+    info!(
+        batch_id,
+        rejected = pending.len(),
+        deleted = deleted.len(),
+        restored,
+        skipped = conflicts.len(),
+        "Rolled back import batch"
+    );
+
+    let mut messages =
+        vec![FlashMessage::success("import-rollback.success")
+            .with_arg("batch", batch_id.to_string())];
+    if !conflicts.is_empty() {
+        messages.push(
+            FlashMessage::info("import-rollback.skipped")
+                .with_arg("count", conflicts.len().to_string()),
+        );
+    }
+
+    Ok(flash::redirect("/admin/settings/imports", messages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::imports::RollbackItem;
+    use crate::test_support::{assert_redirects, Session, TestSite, HAMBA};
+    use isixhosa_common::auth::Permissions;
+    use isixhosa_common::database::db_impl::DbImpl;
+
+    const IMPORTS: &str = "/admin/settings/imports";
+    const HEADERS: &str = "english,xhosa,part_of_speech,note,xhosa_tone_markings,infinitive,\
+                           transitivity,followed_by";
+
+    /// Import the rows as a batch, returning the batch's ID
+    async fn import(site: &TestSite, admin: &Session, rows: &str) -> u64 {
+        let uploaded = site
+            .upload(IMPORTS, admin, FILE_FIELD, "words.csv", rows)
+            .await;
+        assert!(uploaded.status().is_success(), "{uploaded:?}");
+
+        let batches = fetch_batches(&DbImpl(site.db.primary.clone())).unwrap();
+        batches.first().unwrap().batch_id
+    }
+
+    async fn accept_all(site: &TestSite, moderator: &Session) {
+        for id in site.pending_suggestions() {
+            let accepted = site.moderate(moderator, id, "accept").await;
+            assert_redirects(&accepted, "/moderation");
+        }
+    }
+
+    fn plan(site: &TestSite, batch_id: u64) -> RollbackPlan {
+        fetch_rollback_plan(&DbImpl(site.db.primary.clone()), batch_id).unwrap()
+    }
+
+    fn ids(items: &[RollbackItem]) -> Vec<u64> {
+        items.iter().map(|item| item.id).collect()
+    }
+
+    #[tokio::test]
+    async fn added_words_are_deleted() {
+        let site = TestSite::start().await;
+        let admin = site.sign_in_as(Permissions::Administrator);
+        let moderator = site.sign_in_as(Permissions::Moderator);
+
+        let batch_id = import(&site, &admin, &format!("{HEADERS}\ngo,hamba,verb,,,,,\n")).await;
+        assert_eq!(plan(&site, batch_id).pending.len(), 1);
+
+        accept_all(&site, &moderator).await;
+        let plan = plan(&site, batch_id);
+
+        assert!(plan.pending.is_empty());
+        assert_eq!(ids(&plan.added), vec![site.word_id("hamba").unwrap()]);
+        assert!(plan.edited.is_empty());
+        assert!(plan.conflicts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn edited_words_are_restored() {
+        let site = TestSite::start().await;
+        let user = site.sign_in_as(Permissions::User);
+        let admin = site.sign_in_as(Permissions::Administrator);
+        let moderator = site.sign_in_as(Permissions::Moderator);
+
+        site.post_form("/submit", &user, HAMBA).await;
+        accept_all(&site, &moderator).await;
+        let word_id = site.word_id("hamba").unwrap();
+
+        let rows = format!("existing_id,{HEADERS}\n{word_id},walk,hambahamba,verb,,,,,\n");
+        let batch_id = import(&site, &admin, &rows).await;
+        accept_all(&site, &moderator).await;
+        let plan = plan(&site, batch_id);
+
+        assert!(plan.added.is_empty());
+        assert!(plan.conflicts.is_empty());
+        assert_eq!(plan.edited.len(), 1);
+
+        let (word, previous) = &plan.edited[0];
+        assert_eq!(word.id, word_id);
+        assert_eq!(
+            (word.english.as_str(), word.xhosa.as_str()),
+            ("walk", "hambahamba")
+        );
+        assert_eq!(previous.xhosa(), "hamba");
+    }
+
+    #[tokio::test]
+    async fn words_edited_since_are_conflicts() {
+        let site = TestSite::start().await;
+        let user = site.sign_in_as(Permissions::User);
+        let admin = site.sign_in_as(Permissions::Administrator);
+        let moderator = site.sign_in_as(Permissions::Moderator);
+
+        let batch_id = import(&site, &admin, &format!("{HEADERS}\ngo,hamba,verb,,,,,\n")).await;
+        accept_all(&site, &moderator).await;
+        let word_id = site.word_id("hamba").unwrap();
+
+        let id = word_id.to_string();
+        let mut edit = vec![("existing_id", id.as_str())];
+        edit.extend(HAMBA.iter().filter(|(field, _)| *field != "english"));
+        edit.push(("english", "walk"));
+        let edited = site
+            .post_form(&format!("/word/{word_id}"), &user, &edit)
+            .await;
+        assert!(edited.status().is_redirection(), "{edited:?}");
+        accept_all(&site, &moderator).await;
+        let plan = plan(&site, batch_id);
+
+        assert!(plan.added.is_empty());
+        assert!(plan.edited.is_empty());
+        assert_eq!(ids(&plan.conflicts), vec![word_id]);
+    }
+
+    #[tokio::test]
+    async fn batches_are_only_rolled_back_once() {
+        let site = TestSite::start().await;
+        let admin = site.sign_in_as(Permissions::Administrator);
+        let moderator = site.sign_in_as(Permissions::Moderator);
+
+        let batch_id = import(&site, &admin, &format!("{HEADERS}\ngo,hamba,verb,,,,,\n")).await;
+        accept_all(&site, &moderator).await;
+
+        let rollback = format!("{IMPORTS}/{batch_id}/rollback");
+        let first = site.post_form(&rollback, &admin, &[]).await;
+        assert_redirects(&first, IMPORTS);
+        assert_eq!(site.word_id("hamba"), None);
+
+        let second = site.post_form(&rollback, &admin, &[]).await;
+        assert_redirects(&second, &rollback);
+    }
+}
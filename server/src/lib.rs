@@ -0,0 +1,1845 @@
+// Soon after launch, perhaps before:
+// - informal/archaic meanings
+// - standalone example & linked word suggestion editing
+// - forum for xhosa questions
+// - error handling - dont crash always probably & on panic, always crash (viz. tokio workers)!
+// - better search engine optimisation
+// - cache control headers/etags
+// - attributions - references
+// - learn page with additional resources/links page
+
+// Well after launch:
+// - rate limiting
+// - integration testing
+// - tracing for logging over log: open telemetry/ELK stack or similar?
+// - conjugation tables
+// - user profiles showing statistics (for mods primarily but maybe can publicise it?)
+// - semantic fields/categories linking related words to browse all at once
+// - grammar notes
+// - embedded blog (static site generator?) for transparency
+
+#![recursion_limit = "256"] // Warp does warp things
+use crate::auth::*;
+use crate::corpus::{corpus, Corpus};
+use crate::database::pending;
+use crate::database::slugs;
+use crate::database::suggestion::SuggestedWord;
+use crate::database::views::fetch_trending;
+use crate::error::{SearchError, ServerError};
+use crate::federation::{ExternalHits, FederationClient};
+use crate::fragment_cache::{Fragment, FragmentCache};
+use crate::queue_events::QueueWatcher;
+use crate::scheduler::Scheduler;
+use crate::search::{HitGroup, IncludeResults, JsWordHit, TantivyClient};
+use crate::serialization::{false_fn, is_default, query_form};
+use crate::session::LiveSearchSession;
+use crate::views::ViewCounter;
+use anyhow::{Context, Result};
+use askama::Template;
+use auth::auth;
+use changes::changes;
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use classroom::{classroom, Classrooms};
+use completeness::needs_improvement;
+use details::details;
+use drafts::drafts;
+use edit::edit;
+use fluent_templates::Loader;
+use futures::StreamExt;
+use homepage::homepage;
+use imports::imports;
+use isixhosa::noun::NounClass;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::{Auth, Permissions};
+use isixhosa_common::database::{with_public_db, DbBase, ModeratorAccessDb, PublicAccessDb};
+use isixhosa_common::format::DisplayHtml;
+use isixhosa_common::i18n::TranslationKey;
+use isixhosa_common::language::PartOfSpeech;
+use isixhosa_common::morphology::{analyze_verb, VerbAnalysis};
+use isixhosa_common::normalize;
+use isixhosa_common::types::{
+    Dataset, ExistingLinkedWord, ExistingWord, LinkedWordSnippets, WordHit,
+};
+use moderation::moderation;
+use notifications::notifications;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_sdk::Resource;
+use percent_encoding::NON_ALPHANUMERIC;
+use proof_of_work::proof_of_work;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use references::references;
+use rusqlite::{params, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, NoneAsEmptyString};
+use shortlinks::shortlinks;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use submit::submit;
+use suggestion_status::suggestion_status;
+use tasks::tasks;
+use terms::{terms, TermsNotAccepted};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, instrument, warn, Span};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, EnvFilter, Layer, Registry};
+use walkdir::DirEntry;
+#[cfg(debug_assertions)]
+use warp::filters::BoxedFilter;
+use warp::http::header::{CACHE_CONTROL, CONTENT_TYPE, LAST_MODIFIED};
+use warp::http::{HeaderValue, StatusCode, Uri};
+use warp::hyper::Body;
+use warp::path::FullPath;
+use warp::reject::MethodNotAllowed;
+use warp::reply::Response;
+use warp::{path, reply, Filter, Rejection, Reply};
+use warp_reverse_proxy as proxy;
+use xtra::{Handler, Mailbox, WeakAddress};
+
+pub use isixhosa_common::{i18n_args, icon};
+
+mod admin;
+mod api;
+mod api_keys;
+mod auth;
+mod bans;
+mod changes;
+mod classroom;
+mod completeness;
+mod compression;
+mod config;
+mod corpus;
+mod csrf;
+mod database;
+mod details;
+mod dict;
+mod drafts;
+mod edit;
+mod edit_lock;
+mod error;
+mod export;
+mod fake_data;
+mod federation;
+mod flash;
+mod fragment_cache;
+mod graphql;
+mod grpc;
+mod homepage;
+mod i18n;
+mod import_zulu;
+mod imports;
+mod link_checker;
+mod link_suggestions;
+mod listener;
+mod maintenance;
+mod moderation;
+mod notifications;
+mod proof_of_work;
+mod pwa;
+mod queue_events;
+mod references;
+mod reminders;
+mod replication;
+mod scheduler;
+mod search;
+mod security_headers;
+mod serialization;
+mod session;
+mod shortlinks;
+mod submit;
+mod suggestion_status;
+mod tasks;
+mod terms;
+#[cfg(test)]
+mod test_support;
+mod tls;
+mod unix_socket;
+mod user_management;
+mod validation;
+mod views;
+
+/// What the benchmarks in `benches/` measure. This isn't meant to be used by anything else.
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::classroom::ClassroomViewer;
+    pub use crate::database::suggestion::SuggestedWord;
+    pub use crate::details::word;
+    pub use crate::fake_data::generate as generate_fake_data;
+    pub use crate::fragment_cache::FragmentCache;
+    pub use crate::i18n::{load as load_site_context, I18nInfo, EN_ZA};
+    pub use crate::search::{IncludeResults, TantivyClient};
+}
+
+#[doc(hidden)]
+pub mod fuzz {
+    use crate::database::submit::WordSubmission;
+    use crate::moderation::Action;
+    use crate::serialization::deserialize_form;
+    use isixhosa_common::language::LanguageProfile;
+
+    /// Parses the bytes as each of the forms which can be submitted, checking what is parsed as it
+    /// would be before being written
+    pub fn forms(bytes: &[u8]) {
+        if let Ok(mut word) = deserialize_form::<WordSubmission>(bytes) {
+            word.canonicalize();
+            let _ = word.validate(&LanguageProfile::default());
+        }
+
+        let _ = deserialize_form::<Action>(bytes);
+    }
+}
+
+use crate::admin::admin;
+use crate::api::api;
+use crate::api_keys::api_keys;
+use crate::graphql::graphql;
+use crate::i18n::I18nInfo;
+use crate::i18n::SiteContext;
+use crate::i18n::ToTranslationKey;
+use crate::i18n::EN_ZA;
+use crate::pwa::pwa;
+pub use config::Config;
+use isixhosa_common::templates::AllWords;
+
+const STATIC_LAST_CHANGED: &str = env!("STATIC_LAST_CHANGED");
+const STATIC_BIN_FILES_LAST_CHANGED: &str = env!("STATIC_BIN_FILES_LAST_CHANGED");
+
+#[derive(Parser)]
+#[command(name = "IsiXhosa.click")]
+#[command(about = "Online, live dictionary software", long_about = None)]
+struct CliArgs {
+    /// The site. Each site has a distinct database, export directory, and config.toml file.
+    #[arg(short, long, required = true)]
+    site: String,
+    /// Whether to enable OpenTelemetry protocol (OTLP) trace exporting.
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    with_otlp: bool,
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Run the server for the site
+    Run,
+    /// Run the backup for the site. The directory of the exported files is specified in the site's
+    /// configuration file.
+    Backup,
+    /// Restore from the backup. The directory of the files to restore from are specified in the
+    /// site's configuration file.
+    Restore,
+    /// Import a dictionary file in the format of the isiZulu LSP
+    ImportZuluLSP {
+        /// The path of the dictionary file
+        path: PathBuf,
+    },
+    /// Export a snapshot of the search index, which can be imported into another instance instead
+    /// of rebuilding its index from its database
+    ExportSearchIndex {
+        /// The path of the gzipped tarball to write
+        path: PathBuf,
+    },
+    /// Replace the search index with a snapshot exported from another instance. The server must be
+    /// stopped, and its database should be a copy of the other instance's.
+    ImportSearchIndex {
+        /// The path of the gzipped tarball to read
+        path: PathBuf,
+    },
+    /// Fill an empty database with fake words for local development
+    GenerateFakeData {
+        /// The number of words to generate
+        #[arg(long, default_value_t = 500)]
+        words: usize,
+        /// The seed to generate the words from. The same seed always gives the same words.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+    /// Commands relating to user management
+    User(UserCommandArgs),
+}
+
+#[derive(Parser)]
+struct UserCommandArgs {
+    #[command(subcommand)]
+    command: UserCommand,
+}
+
+#[derive(Subcommand, Clone)]
+enum UserCommand {
+    /// Set a user's permissions
+    SetRole {
+        /// The user's email
+        user: String,
+        /// The user's new role
+        role: Permissions,
+    },
+    /// Lock a user so that they cannot log in - this amounts to a ban but is not necessarily
+    /// because of bad behaviour (e.g., the user could have disabled their account voluntarily).
+    Lock {
+        /// The user's email
+        user: String,
+    },
+    /// Unlock a user so they can log in again.
+    Unlock {
+        /// The user's email
+        user: String,
+    },
+    /// List all users
+    List,
+    /// Logs out all users
+    LogoutAll,
+}
+
+/// Runs the command given on the command line
+pub fn run() -> Result<()> {
+    let cli = CliArgs::parse();
+    let cfg: Config = confy::load("isixhosa_click", Some(cli.site.as_ref()))?;
+
+    match cli.command {
+        Commands::Run => tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?
+            .block_on(server(cfg, cli)),
+        Commands::Backup => export::run_daily_tasks(&cfg, &cli),
+        Commands::Restore => export::restore(cfg),
+        Commands::ImportZuluLSP { path } => import_zulu::import_zulu_lsp(cfg, &path),
+        Commands::ExportSearchIndex { path } => {
+            let file = File::create(&path)
+                .with_context(|| format!("Failed to create snapshot file {:?}", path))?;
+            TantivyClient::export_snapshot(&cfg.tantivy_path, BufWriter::new(file))
+        }
+        Commands::ImportSearchIndex { path } => {
+            let file = File::open(&path)
+                .with_context(|| format!("Failed to open snapshot file {:?}", path))?;
+            TantivyClient::import_snapshot(&cfg.tantivy_path, BufReader::new(file))
+        }
+        Commands::GenerateFakeData { words, seed } => fake_data::generate(cfg, words, seed),
+        Commands::User(command) => user_management::run_command(cfg, command.command),
+    }
+}
+
+pub fn spawn_blocking_child<F, R>(f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let span = Span::current();
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        f()
+    })
+}
+
+pub trait DebugBoxedExt: Filter {
+    #[cfg(debug_assertions)]
+    fn debug_boxed(self) -> BoxedFilter<Self::Extract>;
+
+    #[cfg(not(debug_assertions))]
+    fn debug_boxed(self) -> Self;
+}
+
+impl<F> DebugBoxedExt for F
+where
+    F: Filter + Send + Sync + 'static,
+    F::Extract: Send,
+    F::Error: Into<Rejection>,
+{
+    #[cfg(debug_assertions)]
+    fn debug_boxed(self) -> BoxedFilter<Self::Extract> {
+        self.boxed()
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn debug_boxed(self) -> Self {
+        self
+    }
+}
+
+pub trait DebugExt {
+    fn to_debug(&self) -> String;
+}
+
+impl<T: Debug> DebugExt for T {
+    fn to_debug(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+fn init_tracing(cli: &CliArgs) -> Result<()> {
+    global::set_text_map_propagator(opentelemetry_jaeger_propagator::Propagator::new());
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+        .with_trace_config(
+            opentelemetry_sdk::trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                opentelemetry_semantic_conventions::resource::SERVICE_NAME,
+                format!("isixhosa-click-{}", cli.site),
+            )])),
+        )
+        .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+
+    let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
+    let fmt_layer = tracing_subscriber::fmt::layer().compact().with_filter(
+        EnvFilter::builder()
+            .with_default_directive(LevelFilter::INFO.into())
+            .from_env()?
+            .add_directive("h2=warn".parse()?)
+            .add_directive("isixhosa_common=debug".parse()?)
+            .add_directive("isixhosa_server=debug".parse()?),
+    );
+
+    let registry = Registry::default().with(LevelFilter::DEBUG).with(fmt_layer);
+
+    if cli.with_otlp {
+        registry.with(telemetry).init();
+    } else {
+        registry.init();
+    }
+
+    Ok(())
+}
+
+#[instrument(
+    name = "Minify outgoing data",
+    fields(unminified, minified, saving),
+    skip_all
+)]
+async fn process_body<F, E>(response: Response, minify: F) -> Result<Response, Rejection>
+where
+    F: FnOnce(&str) -> Result<String, E>,
+    E: Debug,
+{
+    let span = Span::current();
+    let (parts, body) = response.into_parts();
+    let bytes = warp::hyper::body::to_bytes(body).await.unwrap();
+    let unminified = std::str::from_utf8(&bytes).unwrap();
+    let minified = minify(unminified).unwrap();
+
+    span.record("unminified", unminified.len());
+    span.record("minified", minified.len());
+
+    let saving = if !unminified.is_empty() {
+        (1.0 - (minified.len() as f64 / unminified.len() as f64)) * 100.0
+    } else {
+        0.0
+    };
+
+    span.record("saving", format!("{:.2}%", saving));
+
+    Ok(Response::from_parts(parts, minified.into()))
+}
+
+async fn minify_and_cache<R: Reply>(reply: R) -> Result<impl Reply, Rejection> {
+    let response = reply.into_response();
+
+    fn starts_with(mime: &str, pats: &[&str]) -> bool {
+        pats.iter().any(|pat| mime.starts_with(pat))
+    }
+
+    if let Some(content_type) = response.headers().get(CONTENT_TYPE) {
+        let mime = &content_type.to_str().unwrap().to_owned();
+
+        // TODO: we can't use minifier as it breaks the WASM bindgen wrapper:
+        // https://github.com/GuillaumeGomez/minifier-rs/issues/108
+        let mut response = if mime.starts_with("text/html") {
+            #[allow(clippy::redundant_closure)] // lifetime issue
+            process_body(response, |s| html_minifier::minify(s)).await?
+        } else if mime.starts_with("text/css") {
+            process_body(response, |s| {
+                minifier::css::minify(s).map(|s| s.to_string())
+            })
+            .await?
+        } else {
+            response
+        };
+
+        if starts_with(mime, &["text", "application/javascript"])
+            && !mime.to_lowercase().contains("charset=utf-8")
+        {
+            let new_content_type =
+                HeaderValue::from_str(&format!("{}; charset=UTF-8", mime)).unwrap();
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, new_content_type);
+        }
+
+        if mime.starts_with("font/woff2") {
+            response.headers_mut().insert(
+                CACHE_CONTROL,
+                HeaderValue::from_static("public, max-age=31536000"),
+            );
+        }
+
+        Ok(response)
+    } else {
+        Ok(response)
+    }
+}
+
+#[instrument(name = "Handle errors")]
+async fn handle_error(err: Rejection) -> Result<Response, Rejection> {
+    if let Some(unauthorized) = err.find::<Unauthorized>() {
+        let redirect_to = |to| {
+            warp::http::Response::builder()
+                .status(StatusCode::FOUND)
+                .header(warp::http::header::LOCATION, to)
+                .body("")
+                .unwrap()
+                .into_response()
+        };
+
+        match unauthorized.reason {
+            UnauthorizedReason::NotLoggedIn => {
+                debug!("User was not logged in; redirecting");
+                let login = format!(
+                    "/login/oauth2/authorization/oidc?redirect={}",
+                    percent_encoding::utf8_percent_encode(
+                        unauthorized.redirect.as_str(),
+                        NON_ALPHANUMERIC
+                    ),
+                );
+
+                Ok(redirect_to(login))
+            }
+            UnauthorizedReason::NoPermissions | UnauthorizedReason::Locked => {
+                debug!("User has insufficient permissions");
+                Ok(reply::with_status(warp::reply(), StatusCode::FORBIDDEN).into_response())
+            }
+            UnauthorizedReason::InvalidCookie => {
+                debug!("User has invalid cookie; redirecting");
+                Ok(redirect_to("/login/oauth2/authorization/oidc".to_owned()))
+            }
+        }
+    } else if let Some(not_accepted) = err.find::<TermsNotAccepted>() {
+        debug!("User has not accepted the current terms; redirecting");
+        Ok(
+            warp::redirect::see_other(not_accepted.accept_url().parse::<Uri>().unwrap())
+                .into_response(),
+        )
+    } else if err.find::<MethodNotAllowed>().is_some() {
+        Err(warp::reject::not_found())
+    } else {
+        Err(err)
+    }
+}
+
+#[instrument("Set up database PRAGMAs and tables", skip_all)]
+pub fn set_up_db(conn: &Connection) -> Result<()> {
+    const CREATIONS: [&str; 41] = [
+        include_str!("sql/users.sql"),
+        include_str!("sql/words.sql"),
+        include_str!("sql/user_attributions.sql"),
+        include_str!("sql/word_suggestions.sql"),
+        include_str!("sql/word_deletion_suggestions.sql"),
+        include_str!("sql/examples.sql"),
+        include_str!("sql/example_suggestions.sql"),
+        include_str!("sql/example_deletion_suggestions.sql"),
+        include_str!("sql/linked_words.sql"),
+        include_str!("sql/linked_word_suggestions.sql"),
+        include_str!("sql/linked_word_deletion_suggestions.sql"),
+        include_str!("sql/login_tokens.sql"),
+        include_str!("sql/datasets.sql"),
+        include_str!("sql/dataset_attributions.sql"),
+        include_str!("sql/dataset_attribution_suggestions.sql"),
+        include_str!("sql/word_views.sql"),
+        include_str!("sql/drafts.sql"),
+        include_str!("sql/word_slugs.sql"),
+        include_str!("sql/likely_spam_suggestions.sql"),
+        include_str!("sql/bans.sql"),
+        include_str!("sql/word_suggestion_times.sql"),
+        include_str!("sql/suggestion_statuses.sql"),
+        include_str!("sql/word_revisions.sql"),
+        include_str!("sql/user_preferences.sql"),
+        include_str!("sql/notifications.sql"),
+        include_str!("sql/moderation_actions.sql"),
+        include_str!("sql/recovery_identities.sql"),
+        include_str!("sql/api_keys.sql"),
+        include_str!("sql/api_key_usage.sql"),
+        include_str!("sql/word_references.sql"),
+        include_str!("sql/trainee_moderators.sql"),
+        include_str!("sql/moderation_checklists.sql"),
+        include_str!("sql/rejected_dependencies.sql"),
+        include_str!("sql/automatic_link_suggestions.sql"),
+        include_str!("sql/example_sources.sql"),
+        include_str!("sql/search_preferences.sql"),
+        include_str!("sql/words_alphabetical_index.sql"),
+        include_str!("sql/search_shortlinks.sql"),
+        include_str!("sql/missed_searches.sql"),
+        include_str!("sql/terms_acceptances.sql"),
+        include_str!("sql/import_batches.sql"),
+    ];
+
+    // See https://github.com/the-lean-crate/criner/discussions/5
+    // The journal mode is persisted in the database file, so this only needs to be done once.
+    conn.execute_batch(
+        "
+        PRAGMA journal_mode = WAL;
+        PRAGMA wal_autocheckpoint = 1000;
+        PRAGMA wal_checkpoint(TRUNCATE);
+    ",
+    )?;
+
+    for creation in &CREATIONS {
+        conn.execute(creation, params![])?;
+    }
+
+    add_missing_columns(conn)?;
+
+    Ok(())
+}
+
+/// Add columns which were added to tables after they were first created, since `CREATE TABLE IF
+/// NOT EXISTS` leaves tables in existing databases as they are
+fn add_missing_columns(conn: &Connection) -> Result<()> {
+    /// The table, the column, its definition, and what to fill it in with in existing rows
+    /// afterwards, if anything
+    const ADDED_COLUMNS: [(&str, &str, &str, Option<&str>); 16] = [
+        // SQLite can only add a NOT NULL column with a default, but every row is filled in straight
+        // after, so the default is never used
+        (
+            "login_tokens",
+            "authenticated",
+            "TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT '1970-01-01 00:00:00+00:00'",
+            Some("UPDATE login_tokens SET authenticated = last_used;"),
+        ),
+        ("login_tokens", "refresh_hash", "TEXT", None),
+        ("login_tokens", "previous_refresh_hash", "TEXT", None),
+        (
+            "login_tokens",
+            "refreshed",
+            "TIMESTAMP WITH TIME ZONE",
+            None,
+        ),
+        (
+            "words",
+            "is_offensive",
+            "BOOLEAN NOT NULL DEFAULT FALSE",
+            None,
+        ),
+        // Suggested new words must have every field filled in
+        (
+            "word_suggestions",
+            "is_offensive",
+            "BOOLEAN",
+            Some(
+                "UPDATE word_suggestions SET is_offensive = FALSE WHERE existing_word_id IS NULL;",
+            ),
+        ),
+        ("word_references", "link_checked", "TIMESTAMP", None),
+        (
+            "word_references",
+            "link_failures",
+            "INTEGER NOT NULL DEFAULT 0",
+            None,
+        ),
+        ("word_references", "link_broken_since", "TIMESTAMP", None),
+        ("word_references", "link_error", "TEXT", None),
+        (
+            "word_suggestions",
+            "source",
+            "INTEGER NOT NULL DEFAULT 0",
+            None,
+        ),
+        (
+            "word_suggestions",
+            "import_batch",
+            "INTEGER REFERENCES import_batches(batch_id)",
+            None,
+        ),
+        (
+            "word_suggestions",
+            "api_key_id",
+            "INTEGER REFERENCES api_keys(key_id) ON DELETE SET NULL",
+            None,
+        ),
+        (
+            "word_revisions",
+            "import_batch",
+            "INTEGER REFERENCES import_batches(batch_id)",
+            None,
+        ),
+        ("word_revisions", "previous", "TEXT", None),
+        (
+            "import_batches",
+            "rolled_back",
+            "TIMESTAMP WITH TIME ZONE",
+            None,
+        ),
+    ];
+    const EXISTS: &str = "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2);";
+
+    for (table, column, definition, backfill) in ADDED_COLUMNS {
+        let exists: bool = conn.query_row(EXISTS, params![table, column], |row| row.get(0))?;
+
+        if !exists {
+            info!("Adding column {column} to table {table}");
+            conn.execute(
+                &format!("ALTER TABLE {table} ADD COLUMN {column} {definition};"),
+                params![],
+            )?;
+
+            if let Some(backfill) = backfill {
+                conn.execute(backfill, params![])?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Set the PRAGMAs which only apply to a single connection. This must be called on every
+/// connection opened to the database.
+fn configure_connection(
+    conn: &Connection,
+    busy_timeout: Duration,
+    statement_cache_capacity: usize,
+) -> rusqlite::Result<()> {
+    conn.busy_timeout(busy_timeout)?;
+    conn.set_prepared_statement_cache_capacity(statement_cache_capacity);
+    conn.execute_batch(
+        "
+        PRAGMA synchronous = NORMAL;
+    ",
+    )
+}
+
+pub fn open_db_connection(cfg: &Config) -> Result<Connection> {
+    let conn = Connection::open(&cfg.database_path)?;
+    configure_connection(
+        &conn,
+        Duration::from_millis(cfg.database_busy_timeout_ms),
+        cfg.database_statement_cache_capacity,
+    )?;
+    Ok(conn)
+}
+
+pub fn open_db_pool(cfg: &Config) -> Result<Pool<SqliteConnectionManager>> {
+    let busy_timeout = Duration::from_millis(cfg.database_busy_timeout_ms);
+    let statement_cache_capacity = cfg.database_statement_cache_capacity;
+    let manager = SqliteConnectionManager::file(&cfg.database_path)
+        .with_init(move |conn| configure_connection(conn, busy_timeout, statement_cache_capacity));
+
+    let pool = Pool::builder()
+        .max_size(cfg.database_pool_size)
+        .build(manager)?;
+    Ok(pool)
+}
+
+/// Open a pool of read-only connections to the database replica, if one is configured
+pub fn open_db_replica_pool(cfg: &Config) -> Result<Option<Pool<SqliteConnectionManager>>> {
+    let Some(path) = &cfg.database_replica_path else {
+        return Ok(None);
+    };
+
+    let busy_timeout = Duration::from_millis(cfg.database_busy_timeout_ms);
+    let statement_cache_capacity = cfg.database_statement_cache_capacity;
+    let manager = SqliteConnectionManager::file(path)
+        .with_flags(
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_init(move |conn| configure_connection(conn, busy_timeout, statement_cache_capacity));
+
+    let pool = Pool::builder()
+        .max_size(cfg.database_pool_size)
+        .build(manager)?;
+    Ok(Some(pool))
+}
+
+// I cannot be bothered trying to find the right type
+macro_rules! wrap_filter {
+    ($content_lang:expr, $f:expr) => {
+        compression::accepted_encoding()
+            .and(
+                $f.and_then(minify_and_cache)
+                    .and(security_headers::csp_nonce())
+                    .and_then(security_headers::add_security_headers),
+            )
+            .map(compression::compress)
+            .with(warp::trace(|info| {
+                tracing::info_span!(
+                    "HTTPS request",
+                    method = %info.method(),
+                    path = %info.path(),
+                    request_id = tracing::field::Empty,
+                )
+            }))
+            .with(warp::reply::with::header(warp::http::header::X_FRAME_OPTIONS, "Deny"))
+            .with(warp::reply::with::header(warp::http::header::CONTENT_LANGUAGE, $content_lang))
+    }
+}
+
+fn walk_dir(dir: &Path) -> impl Iterator<Item = DirEntry> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+}
+
+fn walk_static_files(
+    src_static: &Path,
+    site_translation_files: &Path,
+) -> impl Iterator<Item = DirEntry> {
+    walk_dir(src_static).chain(walk_dir(site_translation_files))
+}
+
+async fn server(cfg: Config, args: CliArgs) -> Result<()> {
+    init_tracing(&args)?;
+    info!("IsiXhosa server startup");
+
+    let pool = open_db_pool(&cfg)?;
+    let pool_clone = pool.clone();
+    spawn_blocking_child(move || {
+        let conn = pool_clone.get()?;
+        set_up_db(&conn)?;
+        slugs::add_missing_slugs(&conn)?;
+        pending::add_missing_submission_times(&conn)?;
+        Ok::<_, anyhow::Error>(())
+    })
+    .await??;
+
+    let fragments = Arc::new(FragmentCache::default());
+    let tantivy = TantivyClient::start(&cfg, pool.clone(), fragments.clone()).await?;
+
+    let db = DbBase::new(pool).with_replica(open_db_replica_pool(&cfg)?);
+    let site_ctx = Arc::new(i18n::load(args.site.clone(), &cfg));
+
+    if let Some(dict_cfg) = cfg.dict_server {
+        dict::start(dict_cfg, db.clone(), tantivy.clone(), site_ctx.clone()).await?;
+    }
+
+    if let Some(grpc_cfg) = cfg.grpc {
+        grpc::start(grpc_cfg, db.clone(), tantivy.clone(), site_ctx.clone());
+    }
+
+    let cert_resolver = match (&cfg.cert_path, &cfg.key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            Some(Arc::new(tls::CertResolver::load(cert_path, key_path)?))
+        }
+        _ => None,
+    };
+
+    let mut scheduler = Scheduler::default();
+    if let Some(resolver) = &cert_resolver {
+        tls::register(
+            &mut scheduler,
+            resolver.clone(),
+            cfg.tls_reload_interval_mins,
+        );
+    }
+    replication::register(&mut scheduler, db.clone(), cfg.replication.clone());
+    link_suggestions::register(&mut scheduler, db.clone(), cfg.link_suggestions);
+    link_checker::register(&mut scheduler, db.clone(), cfg.link_checker)?;
+    let scheduler = scheduler.start();
+
+    let jaeger_proxy = {
+        let base = warp::path!("admin" / "jaeger" / ..);
+        let forward_url = "http://127.0.0.1:16686".to_owned();
+        let forward =
+            proxy::reverse_proxy_filter(String::new(), forward_url).with(warp::trace(|_info| {
+                tracing::info_span!("Forward jaeger request")
+            }));
+        let proxy = with_administrator_auth(db.clone(), site_ctx.clone())
+            .map(|_, _, _| ())
+            .untuple_one()
+            .and(forward)
+            .recover(handle_error)
+            .with(warp::trace(|info| {
+                tracing::info_span!(
+                    "Jaeger reverse proxy request",
+                    method = %info.method(),
+                    path = %info.path(),
+                )
+            }));
+
+        base.and(proxy).debug_boxed()
+    };
+
+    let sign_in = auth(db.clone(), &cfg, site_ctx.clone()).await;
+    let routes = routes(
+        &cfg,
+        &args.site,
+        db.clone(),
+        tantivy,
+        fragments,
+        site_ctx.clone(),
+        scheduler,
+        sign_in,
+    )
+    .await?;
+
+    info!("Visit https://127.0.0.1:{}/", cfg.https_port);
+
+    let http_redirect = path::full()
+        .map(move |path: FullPath| {
+            let to = Uri::builder()
+                .scheme("https")
+                .authority("isixhosa.click")
+                .path_and_query(path.as_str())
+                .build()
+                .unwrap();
+            warp::redirect(to)
+        })
+        .with(warp::trace(|info| {
+            tracing::info_span!(
+                    "HTTP redirect",
+                    method = %info.method(),
+                    path = %info.path(),
+            )
+        }));
+
+    if cert_resolver.is_some() {
+        let http_redirect = warp::serve(http_redirect);
+        tokio::spawn(http_redirect.run(([0, 0, 0, 0], cfg.http_port)));
+    }
+
+    let content_lang = site_ctx.site_i18n.lookup(&EN_ZA, "source-language-code");
+
+    // Add post filters such as minification, logging, security headers, and compression
+    let serve = jaeger_proxy.or(wrap_filter!(content_lang, routes));
+
+    let service = warp::service(serve);
+
+    match cert_resolver {
+        None => match &cfg.unix_socket {
+            Some(socket) => {
+                let listener = unix_socket::bind(socket)?;
+                listener::serve(listener, service, cfg.connections).await?;
+            }
+            None => {
+                let listener = TcpListener::bind(([0, 0, 0, 0], cfg.http_port)).await?;
+                listener::serve(listener, service, cfg.connections).await?;
+            }
+        },
+        Some(resolver) => {
+            if cfg.unix_socket.is_some() {
+                warn!("A certificate is configured, so the Unix socket is not used");
+            }
+
+            let listener = tls::TlsListener::bind(cfg.https_port, resolver).await?;
+            listener::serve(listener, service, cfg.connections).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Everything the site serves apart from the jaeger proxy, without the post filters such as
+/// compression and security headers. The sign in routes are passed in, since building them
+/// discovers the OIDC provider over the network.
+#[allow(clippy::too_many_arguments)]
+async fn routes<A>(
+    cfg: &Config,
+    site: &str,
+    db: DbBase,
+    tantivy: Arc<TantivyClient>,
+    fragments: Arc<FragmentCache>,
+    site_ctx: Arc<SiteContext>,
+    scheduler: Arc<Scheduler>,
+    sign_in: A,
+) -> Result<impl Filter<Extract = (Response,), Error = Rejection> + Clone>
+where
+    A: Filter<Error = Rejection> + Clone + Send + Sync + 'static,
+    A::Extract: Reply + Send,
+{
+    let tantivy_cloned = tantivy.clone();
+    let with_tantivy = warp::any().map(move || tantivy_cloned.clone());
+    let view_counter = ViewCounter::start(db.clone(), fragments.clone());
+    let queue_watcher = QueueWatcher::start(db.clone());
+    let classrooms = cfg.classrooms.map(Classrooms::new);
+    let federation = cfg
+        .federation
+        .clone()
+        .map(FederationClient::new)
+        .transpose()?;
+
+    let example_corpus = match cfg.example_corpus.clone() {
+        Some(corpus_cfg) => {
+            let corpus = spawn_blocking_child(move || Corpus::load(&corpus_cfg)).await??;
+            Some(Arc::new(corpus))
+        }
+        None => None,
+    };
+
+    let homepage = homepage(db.clone(), fragments.clone(), site_ctx.clone());
+
+    let search = {
+        let search_page = warp::any()
+            .map(move || fragments.clone())
+            .and(with_any_auth(db.clone(), site_ctx.clone()))
+            .and_then(show_search_page);
+
+        let query_search = path::end()
+            .and(query_form())
+            .and(with_tantivy.clone())
+            .and(warp::any().map(move || federation.clone()))
+            .and(with_any_auth_read_only(db.clone(), site_ctx.clone()))
+            .and(with_public_db(db.clone()))
+            .and_then(query_search);
+        let live_search = path::end()
+            .and(warp::ws())
+            .and(with_tantivy.clone())
+            .and(warp::query())
+            .and(with_any_auth(db.clone(), site_ctx.clone()))
+            .map(live_search);
+        let duplicate_search = warp::path("duplicates")
+            .and(path::end())
+            .and(warp::query())
+            .and(with_tantivy.clone())
+            .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+            .and_then(duplicate_search);
+        let other_number_search = warp::path("other_number")
+            .and(path::end())
+            .and(warp::query())
+            .and(with_tantivy.clone())
+            .and(with_any_auth_read_only(db.clone(), site_ctx.clone()))
+            .and_then(other_number_search);
+
+        warp::path("search")
+            .and(
+                duplicate_search
+                    .or(other_number_search)
+                    .or(live_search)
+                    .or(query_search)
+                    .or(search_page),
+            )
+            .debug_boxed()
+    };
+
+    let src_static = cfg.server_source_path.join("static");
+    let site_translation_files = cfg
+        .server_source_path
+        .join("translations")
+        .join("site-specific")
+        .join(site);
+
+    let simple_templates = {
+        let terms_of_use = warp::path("terms_of_use")
+            .and(path::end())
+            .and(with_any_auth(db.clone(), site_ctx.clone()))
+            .map(|auth, i18n_info, _| TermsOfUse { auth, i18n_info });
+        let style_guide = warp::path("style_guide")
+            .and(path::end())
+            .and(with_any_auth(db.clone(), site_ctx.clone()))
+            .map(|auth, i18n_info, _| StyleGuide { auth, i18n_info });
+        let wordle = warp::path("wordle")
+            .and(path::end())
+            .and(with_any_auth(db.clone(), site_ctx.clone()))
+            .map(|auth, i18n_info, _| Wordle { auth, i18n_info });
+        let offline = warp::get()
+            .and(warp::path("offline"))
+            .and(path::end())
+            .and(with_any_auth(db.clone(), site_ctx.clone()))
+            .map(|_auth, i18n_info, _db| Offline { i18n_info });
+
+        let about = warp::get()
+            .and(warp::path("about"))
+            .and(path::end())
+            .and(with_any_auth(db.clone(), site_ctx.clone()))
+            .and_then(|auth, i18n_info, db| async move {
+                Ok::<About, Infallible>(About {
+                    i18n_info,
+                    auth,
+                    word_count: spawn_blocking_child(move || ExistingWord::count_all(&db))
+                        .await
+                        .unwrap(),
+                })
+            });
+
+        fn ends_with(entry: &str, pats: &[&str]) -> bool {
+            pats.iter().any(|pat| entry.ends_with(pat))
+        }
+
+        let (bin_files, static_files) = walk_static_files(&src_static, &site_translation_files)
+            .map(|entry| {
+                let relative_to_src = entry.path().strip_prefix(&cfg.server_source_path).unwrap();
+
+                // It's either a static file or a translation file
+                let relative_to_web_root = relative_to_src
+                    .strip_prefix("static")
+                    .map(ToOwned::to_owned)
+                    .unwrap_or_else(|_| {
+                        let relative_to_site = relative_to_src
+                            .strip_prefix(&format!("translations/site-specific/{}/", site))
+                            .expect("Couldn't find site-specific translations");
+                        Path::new("translations").join(relative_to_site)
+                    });
+
+                relative_to_web_root.to_str().unwrap().to_owned()
+            })
+            .filter(|entry: &String| !entry.contains("LICENSE"))
+            .partition::<Vec<_>, _>(|entry| ends_with(entry, &["png", "svg", "woff2", "ico"]));
+
+        let last_modified_static = walk_static_files(&src_static, &site_translation_files)
+            .filter_map(|entry| entry.metadata().ok())
+            .filter_map(|meta| meta.modified().or(meta.created()).ok())
+            .max()
+            .unwrap();
+
+        let last_modified_static = DateTime::<Utc>::from(last_modified_static);
+        let last_modified_js = Utc::now(); // server boot time
+        let last_modified = std::cmp::max(last_modified_static, last_modified_js);
+        let last_modified = last_modified.format("%a, %d %m %Y %H:%M:%S GMT");
+        let last_modified = HeaderValue::from_str(&last_modified.to_string())?;
+
+        let service_worker = warp::get()
+            .and(warp::path("service_worker.js"))
+            .and(path::end())
+            .map(move || {
+                let template = ServiceWorker {
+                    static_files: static_files.clone(),
+                    static_bin_files: bin_files.clone(),
+                };
+                warp::http::Response::builder()
+                    .header(CONTENT_TYPE, HeaderValue::from_static("text/javascript"))
+                    .header(LAST_MODIFIED, last_modified.clone())
+                    .body(template.render().unwrap())
+                    .unwrap()
+            });
+        terms_of_use
+            .or(about)
+            .or(style_guide)
+            .or(wordle)
+            .or(offline)
+            .or(service_worker)
+            .debug_boxed()
+    };
+
+    let all_words = warp::get()
+        .and(warp::path("all"))
+        .and(path::end())
+        .and(with_tantivy)
+        .and(with_any_auth(db.clone(), site_ctx.clone()))
+        .and_then(all_words);
+
+    let dataset_icons = warp::get()
+        .and(warp::path!["dataset" / u64 / "icon.png"])
+        .and(path::end())
+        .and(with_public_db(db.clone()))
+        .and_then(serve_dataset_icon);
+
+    let redirects = {
+        let favico_redirect = warp::get()
+            .and(warp::path("favicon.ico"))
+            .map(|| warp::redirect(Uri::from_static("/icons/favicon.ico")));
+
+        favico_redirect.debug_boxed()
+    };
+
+    let static_files = warp::fs::dir(src_static).or(warp::fs::dir(cfg.other_static_files.clone()));
+
+    let langs = site_ctx.supported_langs;
+    let translations = warp::path("translations").and(
+        warp::fs::dir(site_translation_files).or(path::end().map(move || reply::json(&langs))),
+    );
+
+    let routes = refresh_session(db.clone())
+        .or(search)
+        .or(all_words)
+        .or(simple_templates)
+        .or(redirects)
+        .or(homepage)
+        .debug_boxed()
+        .or(submit(
+            db.clone(),
+            tantivy.clone(),
+            site_ctx.clone(),
+            cfg.submission_challenge,
+            cfg.body_limits,
+        ))
+        .or(moderation(
+            db.clone(),
+            tantivy.clone(),
+            site_ctx.clone(),
+            queue_watcher,
+            &cfg,
+        ))
+        .or(admin(
+            db.clone(),
+            site_ctx.clone(),
+            &cfg,
+            scheduler,
+            tantivy.clone(),
+        ))
+        .or(imports(
+            db.clone(),
+            site_ctx.clone(),
+            tantivy.clone(),
+            cfg.body_limits,
+        ))
+        .or(details(
+            db.clone(),
+            site_ctx.clone(),
+            view_counter,
+            classrooms.clone(),
+        ))
+        .or(edit(
+            db.clone(),
+            tantivy.clone(),
+            site_ctx.clone(),
+            cfg.body_limits,
+        ))
+        .or(references(db.clone(), site_ctx.clone(), cfg.body_limits))
+        .or(corpus(
+            db.clone(),
+            site_ctx.clone(),
+            cfg.body_limits,
+            example_corpus,
+        ))
+        .or(drafts(db.clone(), site_ctx.clone(), cfg.body_limits))
+        .or(suggestion_status(db.clone(), site_ctx.clone()))
+        .or(notifications(db.clone(), site_ctx.clone(), cfg.body_limits))
+        .or(changes(db.clone(), site_ctx.clone()))
+        .or(needs_improvement(db.clone(), site_ctx.clone()))
+        .or(tasks(db.clone(), site_ctx.clone()))
+        .or(terms(db.clone(), site_ctx.clone(), cfg.body_limits))
+        .or(shortlinks(db.clone(), site_ctx.clone(), cfg.body_limits))
+        .or(classroom(db.clone(), site_ctx.clone(), classrooms))
+        .or(proof_of_work(cfg.submission_challenge))
+        .or(api(db.clone(), tantivy.clone(), site_ctx.clone()))
+        .or(graphql(
+            db.clone(),
+            tantivy,
+            site_ctx.clone(),
+            cfg.body_limits,
+        ))
+        .or(api_keys(
+            db.clone(),
+            site_ctx.clone(),
+            cfg.api_keys,
+            cfg.body_limits,
+        ))
+        .or(pwa(db.clone(), cfg.pwa_bundle_words, site_ctx.school_mode))
+        .or(sign_in)
+        .debug_boxed()
+        .or(dataset_icons)
+        .or(static_files)
+        .or(translations)
+        .recover(handle_error)
+        .debug_boxed();
+    let routes = error::recover_error_pages(db, site_ctx, routes);
+    Ok(flash::clear_shown(routes))
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct LiveSearchParams {
+    include_own_suggestions: Option<bool>,
+}
+
+/// Everything about a search which is kept in its URL, so that a filtered view of the results can be
+/// bookmarked or shared
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct SearchQuery {
+    #[serde(alias = "q")]
+    query: String,
+    #[serde(default = "false_fn", skip_serializing)]
+    raw: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    mode: SearchMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    part_of_speech: Option<PartOfSpeech>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    sort: SearchSort,
+    #[serde(default, skip_serializing_if = "is_default")]
+    page: usize,
+    /// The shortlink which was just made for this search, so that it can be shown to be copied
+    #[serde(default, skip_serializing)]
+    shared: Option<String>,
+}
+
+impl SearchQuery {
+    /// The query string which leads back to this search, leaving out anything only relevant to
+    /// this request
+    fn to_query_string(&self) -> String {
+        serde_qs::to_string(self).unwrap()
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum SearchSort {
+    /// Best matches first
+    #[default]
+    Relevance,
+    /// Alphabetically by isiXhosa
+    Xhosa,
+    /// Alphabetically by English
+    English,
+}
+
+impl SearchSort {
+    const ALL: [SearchSort; 3] = [
+        SearchSort::Relevance,
+        SearchSort::Xhosa,
+        SearchSort::English,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            SearchSort::Relevance => "relevance",
+            SearchSort::Xhosa => "xhosa",
+            SearchSort::English => "english",
+        }
+    }
+
+    fn sort(&self, hits: &mut [WordHit]) {
+        match self {
+            SearchSort::Relevance => {}
+            SearchSort::Xhosa => hits.sort_by_cached_key(|hit| hit.xhosa.to_lowercase()),
+            SearchSort::English => hits.sort_by_cached_key(|hit| hit.english.to_lowercase()),
+        }
+    }
+}
+
+impl ToTranslationKey for SearchSort {
+    fn translation_key(&self) -> TranslationKey<'_> {
+        TranslationKey(Cow::Owned(format!("search.sort-{}", self.name())))
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum SearchMode {
+    #[default]
+    Words,
+    /// Break the query into morphemes and search for the verb root, so that e.g ndiyahamba finds
+    /// ukuhamba
+    Morphemes,
+}
+
+#[derive(Template, I18nTemplate, Clone, Debug)]
+#[template(path = "about.askama.html")]
+struct About {
+    i18n_info: I18nInfo,
+    auth: Auth,
+    word_count: u64,
+}
+
+#[derive(Template, I18nTemplate, Clone, Debug)]
+#[template(path = "terms_of_use.askama.html")]
+struct TermsOfUse {
+    auth: Auth,
+    i18n_info: I18nInfo,
+}
+
+#[derive(Template, I18nTemplate, Clone, Debug)]
+#[template(path = "style_guide.askama.html")]
+struct StyleGuide {
+    auth: Auth,
+    i18n_info: I18nInfo,
+}
+
+#[derive(Template, I18nTemplate, Clone, Debug)]
+#[template(path = "wordle.askama.html")]
+struct Wordle {
+    auth: Auth,
+    i18n_info: I18nInfo,
+}
+
+#[derive(Template, Clone, Debug)]
+#[template(path = "service_worker.askama.js", escape = "none", syntax = "js")]
+struct ServiceWorker {
+    static_files: Vec<String>,
+    static_bin_files: Vec<String>,
+}
+
+#[derive(Template, I18nTemplate, Clone, Debug)]
+#[template(path = "offline.askama.html")]
+struct Offline {
+    i18n_info: I18nInfo,
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "search.askama.html")]
+struct Search {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    /// The results, grouped by part of speech
+    groups: Vec<HitGroup>,
+    linked_words: HashMap<u64, LinkedWordSnippets>,
+    search: SearchQuery,
+    /// Whether there are more results after this page
+    has_next: bool,
+    /// How the query was broken down when searching by morphemes, if it could be
+    analysis: Option<VerbAnalysis>,
+    /// Results from other dictionaries, if nothing was found here
+    external: Vec<ExternalHits>,
+    /// The trending words section, rendered from [`TrendingList`]
+    trending: String,
+}
+
+/// Inner part of the [`Search`] template which is regenerated only as needed
+#[derive(Template, I18nTemplate)]
+#[template(path = "search.trending.askama.html")]
+struct TrendingList {
+    i18n_info: I18nInfo,
+    trending: Vec<WordHit>,
+}
+
+impl Search {
+    fn linked_words(&self, word_id: u64) -> Option<&LinkedWordSnippets> {
+        self.linked_words.get(&word_id)
+    }
+
+    fn part_of_speech_name(&self, part_of_speech: Option<PartOfSpeech>) -> String {
+        // The message for bound morphemes is named without the underscore
+        let key = match part_of_speech {
+            Some(pos) => format!("{}.capitalised", pos.translation_key().0.replace('_', "")),
+            None => "search.no-part-of-speech".to_owned(),
+        };
+
+        self.t(key.as_str())
+    }
+
+    fn group_heading(&self, group: &HitGroup) -> String {
+        self.t_with(
+            "search.group",
+            &crate::i18n_args!(
+                "part-of-speech" => self.part_of_speech_name(group.part_of_speech),
+                "count" => group.hits.len()
+            ),
+        )
+    }
+
+    fn is_filtered_to(&self, part_of_speech: &PartOfSpeech) -> bool {
+        self.search.part_of_speech == Some(*part_of_speech)
+    }
+
+    fn is_sorted_by(&self, sort: &SearchSort) -> bool {
+        self.search.sort == *sort
+    }
+
+    fn page_link(&self, page: usize) -> String {
+        let search = SearchQuery {
+            page,
+            ..self.search.clone()
+        };
+
+        format!("/search?{}", search.to_query_string())
+    }
+
+    /// The live search runs again on the search box's contents, so it's given the root which was
+    /// searched for rather than the whole word
+    fn search_box_query(&self) -> &str {
+        match &self.analysis {
+            Some(analysis) => &analysis.root,
+            None => &self.search.query,
+        }
+    }
+}
+
+const TRENDING_WORDS: u64 = 10;
+/// How many search results are shown on each page
+const SEARCH_PAGE_SIZE: usize = 10;
+/// The most search results which can be filtered and paged through
+const MAX_SEARCH_RESULTS: usize = 100;
+
+#[instrument(name = "Show search page", skip_all)]
+async fn show_search_page(
+    fragments: Arc<FragmentCache>,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let i18n_clone = i18n_info.clone();
+    let trending = fragments
+        .get_or_render(
+            Fragment::Trending,
+            &i18n_info.user_language,
+            i18n_info.school_mode,
+            || async move {
+                let include_offensive = !i18n_clone.school_mode;
+                let trending = spawn_blocking_child(move || {
+                    fetch_trending(&db, TRENDING_WORDS, include_offensive)
+                })
+                .await?;
+                let list = TrendingList {
+                    i18n_info: i18n_clone,
+                    trending,
+                };
+                Ok(list.render()?)
+            },
+        )
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(Search {
+        auth,
+        i18n_info,
+        groups: Default::default(),
+        linked_words: Default::default(),
+        search: Default::default(),
+        has_next: false,
+        analysis: None,
+        external: Vec::new(),
+        trending,
+    })
+}
+
+#[instrument(
+    name = "Search with a query string",
+    fields(
+        query = %query.query,
+        raw = %query.raw,
+        mode = ?query.mode,
+        part_of_speech = ?query.part_of_speech,
+        sort = ?query.sort,
+        page = %query.page,
+    ),
+    skip_all,
+)]
+async fn query_search(
+    mut query: SearchQuery,
+    tantivy: Arc<TantivyClient>,
+    federation: Option<Arc<FederationClient>>,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+    primary_db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let analysis = match query.mode {
+        SearchMode::Words => None,
+        SearchMode::Morphemes => {
+            let (word, db) = (query.query.clone(), db.clone());
+            spawn_blocking_child(move || {
+                analyze_verb(&word)
+                    .into_iter()
+                    .find(|analysis| ExistingWord::fetch_verb_id(&db, &analysis.root).is_some())
+            })
+            .await
+            .map_err(ServerError::from)?
+        }
+    };
+
+    let search_for = analysis
+        .as_ref()
+        .map_or_else(|| query.query.clone(), |a| a.root.clone());
+
+    let mut hits = tantivy
+        .search_many(search_for, MAX_SEARCH_RESULTS, i18n_info.clone())
+        .await
+        .map_err(ServerError::from)?;
+
+    if let Some(part_of_speech) = query.part_of_speech {
+        hits.retain(|hit| hit.part_of_speech == Some(part_of_speech));
+    }
+
+    query.sort.sort(&mut hits);
+
+    let start = query.page.saturating_mul(SEARCH_PAGE_SIZE);
+    let has_next = hits.len() > start.saturating_add(SEARCH_PAGE_SIZE);
+    let hits: Vec<WordHit> = hits
+        .into_iter()
+        .skip(start)
+        .take(SEARCH_PAGE_SIZE)
+        .collect();
+
+    if query.raw {
+        return Ok(reply::json(&hits).into_response());
+    }
+
+    // Plain searches which find nothing are listed for contributors as words which are wanted. Only
+    // a failed search for the whole query counts, since a filter may be what left nothing.
+    let missed = hits.is_empty()
+        && query.page == 0
+        && query.part_of_speech.is_none()
+        && query.mode == SearchMode::Words
+        && !query.query.trim().is_empty();
+
+    if missed {
+        let (missed_query, db) = (query.query.clone(), primary_db.clone());
+        let recorded =
+            spawn_blocking_child(move || database::tasks::record_missed_search(&db, &missed_query))
+                .await
+                .map_err(ServerError::from)?;
+
+        if let Err(e) = recorded {
+            warn!("Failed to record missed search: {e:?}");
+        }
+    }
+
+    // Raw searches are what peers make, so they are never passed on to avoid searches going around
+    // in circles between peers. Peers aren't searched in school mode, since there's no telling
+    // which of their words are offensive.
+    let external = match federation {
+        Some(federation)
+            if hits.is_empty()
+                && query.page == 0
+                && !query.query.is_empty()
+                && !i18n_info.school_mode =>
+        {
+            federation.search(&query.query).await
+        }
+        _ => Vec::new(),
+    };
+
+    // Only show a shortlink which really leads to this search, so that one can't be passed off as
+    // leading somewhere else. It has only just been made, so it may not be on the replica yet.
+    if let Some(short) = query.shared.clone() {
+        let shared =
+            spawn_blocking_child(move || database::shortlinks::fetch_query(&primary_db, &short))
+                .await
+                .map_err(ServerError::from)?
+                .map_err(ServerError::from)?;
+
+        if shared.as_deref() != Some(query.to_query_string().as_str()) {
+            query.shared = None;
+        }
+    }
+
+    let word_ids: Vec<u64> = hits
+        .iter()
+        .filter(|hit| !hit.is_suggestion)
+        .map(|hit| hit.id)
+        .collect();
+    let linked_words =
+        spawn_blocking_child(move || ExistingLinkedWord::fetch_snippets_for_words(&db, &word_ids))
+            .await
+            .unwrap();
+
+    let template = Search {
+        auth,
+        i18n_info,
+        groups: HitGroup::group(hits),
+        linked_words,
+        search: query,
+        has_next,
+        analysis,
+        external,
+        trending: String::new(),
+    };
+
+    Ok(askama_warp::reply(&template))
+}
+
+#[derive(Deserialize, Debug)]
+struct DuplicateQuery {
+    suggestion: NonZeroU64,
+}
+
+#[derive(Serialize, Debug)]
+struct Duplicates {
+    duplicates: HashSet<JsWordHit>,
+    /// Existing words which may be the singular or plural of the suggestion, which it should
+    /// perhaps be linked to rather than added
+    other_number: HashSet<JsWordHit>,
+}
+
+/// Nouns whose isiXhosa is a guess at the singular or plural of `xhosa`
+async fn search_other_number(
+    tantivy: &TantivyClient,
+    xhosa: &str,
+    noun_class: Option<NounClass>,
+    include: IncludeResults,
+    i18n: &I18nInfo,
+) -> Result<HashSet<JsWordHit>, SearchError> {
+    let mut results = HashSet::new();
+
+    for guess in i18n.ctx.language.guess_other_number(xhosa, noun_class) {
+        let hits: Vec<JsWordHit> = tantivy
+            .search(guess.clone(), include, true, i18n.clone())
+            .await?;
+
+        results.extend(hits.into_iter().filter(|hit| {
+            hit.part_of_speech == Some(PartOfSpeech::Noun) && normalize::matches(&hit.xhosa, &guess)
+        }));
+    }
+
+    Ok(results)
+}
+
+#[instrument(
+    name = "Search for duplicates of a suggestion",
+    fields(suggestion_id = %query.suggestion),
+    skip_all,
+)]
+async fn duplicate_search(
+    query: DuplicateQuery,
+    tantivy: Arc<TantivyClient>,
+    _user: FullUser,
+    i18n: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let suggestion = SuggestedWord::fetch_alone(&db, query.suggestion.get());
+
+    let include = IncludeResults::AcceptedAndAllSuggestions;
+    let res = match suggestion.filter(|w| w.word_id.is_none()) {
+        Some(w) => {
+            let english = tantivy
+                .search(w.english.current().clone(), include, true, i18n.clone())
+                .await
+                .map_err(ServerError::from)?;
+            let xhosa = tantivy
+                .search(w.xhosa.current().clone(), include, true, i18n.clone())
+                .await
+                .map_err(ServerError::from)?;
+
+            let mut other_number = HashSet::new();
+            if *w.part_of_speech.current() == Some(PartOfSpeech::Noun) {
+                other_number = search_other_number(
+                    &tantivy,
+                    w.xhosa.current(),
+                    *w.noun_class.current(),
+                    include,
+                    &i18n,
+                )
+                .await
+                .map_err(ServerError::from)?;
+            }
+
+            let mut results: HashSet<JsWordHit> =
+                HashSet::with_capacity(english.len() + xhosa.len());
+            results.extend(english);
+            results.extend(xhosa);
+            // Exclude this suggestion and the original of this suggestion (the word being edited)
+            results.retain(|res| {
+                let is_this_suggestion = res.id == query.suggestion.get() && res.is_suggestion;
+                let is_original = Some(res.id) == w.word_id && !res.is_suggestion;
+                !(is_this_suggestion || is_original)
+            });
+            other_number.retain(|res| !(res.id == query.suggestion.get() && res.is_suggestion));
+
+            Duplicates {
+                duplicates: results,
+                other_number,
+            }
+        }
+        None => Duplicates {
+            duplicates: HashSet::new(),
+            other_number: HashSet::new(),
+        },
+    };
+
+    Ok(reply::json(&res))
+}
+
+#[serde_as]
+#[derive(Deserialize, Debug)]
+struct OtherNumberQuery {
+    xhosa: String,
+    #[serde(default)]
+    #[serde_as(as = "NoneAsEmptyString")]
+    noun_class: Option<NounClass>,
+}
+
+/// Used on the submit form to point out when a new noun may be the singular or plural of an
+/// existing one
+#[instrument(
+    name = "Search for the singular or plural of a noun",
+    fields(xhosa = %query.xhosa),
+    skip_all,
+)]
+async fn other_number_search(
+    query: OtherNumberQuery,
+    tantivy: Arc<TantivyClient>,
+    auth: Auth,
+    i18n: I18nInfo,
+    _db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let include = match auth.user_id() {
+        Some(user) => IncludeResults::AcceptedAndSuggestionsFrom(user),
+        None => IncludeResults::AcceptedOnly,
+    };
+
+    let results = search_other_number(&tantivy, &query.xhosa, query.noun_class, include, &i18n)
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(reply::json(&results))
+}
+
+#[instrument(
+    name = "Begin live search websocket connection",
+    fields(include_own_suggestions = %params.include_own_suggestions.unwrap_or_default()),
+    skip_all,
+)]
+fn live_search(
+    ws: warp::ws::Ws,
+    tantivy: Arc<TantivyClient>,
+    params: LiveSearchParams,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> impl Reply {
+    ws.on_upgrade(move |websocket| {
+        let (sender, stream) = websocket.split();
+        let include_suggestions_from_user = if params.include_own_suggestions.unwrap_or(false) {
+            auth.user_id()
+        } else {
+            None
+        };
+
+        let actor = LiveSearchSession::new(
+            sender,
+            tantivy,
+            db,
+            include_suggestions_from_user,
+            auth.has_permissions(Permissions::Moderator),
+            i18n_info,
+        );
+
+        let addr = xtra::spawn_tokio(actor, Mailbox::bounded(4));
+
+        tokio::spawn(stream.map(Ok).forward(addr.into_sink()));
+        futures::future::ready(())
+    })
+}
+#[instrument(name = "Show all words", skip_all)]
+async fn all_words(
+    tantivy: Arc<TantivyClient>,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    _db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    Ok(AllWords {
+        auth,
+        all_words: tantivy
+            .get_all_words_html(i18n_info.clone())
+            .await
+            .expect("Failed to get all words cached HTML"),
+        i18n_info,
+    })
+}
+
+async fn serve_dataset_icon(
+    dataset_id: u64,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    match Dataset::fetch_icon(&db, dataset_id) {
+        Some(data) => Ok(warp::http::Response::builder()
+            .status(200)
+            .header("Content-Type", "image/png")
+            .header("Cache-Control", "public, max-age=29030400")
+            .body(Body::from(data))
+            .unwrap()),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+fn spawn_send_interval<A, M>(addr: WeakAddress<A>, interval: Duration, msg: M)
+where
+    A: Handler<M>,
+    M: Clone + Send + Sync + 'static,
+{
+    let addr_clone = addr.clone();
+    let fut = async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            if addr.send(msg.clone()).await.is_err() {
+                return;
+            }
+        }
+    };
+    tokio::spawn(xtra::scoped(&addr_clone, fut));
+}
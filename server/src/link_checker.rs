@@ -0,0 +1,120 @@
+//! A background job which checks that the links given by references still work, so that moderators
+//! can find a new link for those which have broken. Links which have failed a few checks in a row
+//! are marked as broken on the word's page, and listed on the broken links page.
+
+use crate::config::LinkCheckerConfig;
+use crate::database::references::{fetch_links_to_check, record_link_check, LinkToCheck};
+use crate::scheduler::{Schedule, Scheduler};
+use crate::spawn_blocking_child;
+use anyhow::Result;
+use chrono::Utc;
+use futures::StreamExt;
+use isixhosa_common::database::db_impl::DbImpl;
+use isixhosa_common::database::DbBase;
+use reqwest::StatusCode;
+use std::time::Duration;
+use tracing::{info, instrument, Span};
+
+/// How many links are checked at once
+const CONCURRENT_CHECKS: usize = 8;
+
+/// Check a link, returning why it doesn't work if it doesn't
+async fn check_link(client: &reqwest::Client, url: &str) -> Option<String> {
+    match client.get(url).send().await {
+        // Sites often refuse requests which don't come from a browser, but that still shows that
+        // the page is there
+        Ok(response)
+            if response.status().is_success()
+                || matches!(
+                    response.status(),
+                    StatusCode::UNAUTHORIZED
+                        | StatusCode::FORBIDDEN
+                        | StatusCode::TOO_MANY_REQUESTS
+                ) =>
+        {
+            None
+        }
+        Ok(response) => Some(response.status().to_string()),
+        Err(e) if e.is_timeout() => Some("timed out".to_owned()),
+        Err(e) => Some(e.to_string()),
+    }
+}
+
+/// Check the links which are due to be checked. Returns the number checked and the number which
+/// failed.
+#[instrument(name = "Check reference links", fields(checked, failed), skip_all)]
+pub async fn check_links(
+    db: DbImpl,
+    client: &reqwest::Client,
+    cfg: LinkCheckerConfig,
+) -> Result<(usize, usize)> {
+    let interval = chrono::Duration::hours(cfg.interval_hours as i64);
+    let checked_before = Utc::now() - interval;
+
+    let db_clone = db.clone();
+    let links = spawn_blocking_child(move || {
+        fetch_links_to_check(&db_clone, checked_before, cfg.max_per_run)
+    })
+    .await??;
+
+    let results: Vec<(LinkToCheck, Option<String>)> = futures::stream::iter(links)
+        .map(|link| async move {
+            let error = check_link(client, &link.url).await;
+            (link, error)
+        })
+        .buffer_unordered(CONCURRENT_CHECKS)
+        .collect()
+        .await;
+
+    let checked = results.len();
+    let failed = results.iter().filter(|(_, error)| error.is_some()).count();
+
+    spawn_blocking_child(move || {
+        for (link, error) in results {
+            record_link_check(
+                &db,
+                link.reference_id,
+                error.as_deref(),
+                cfg.failures_before_broken,
+            )?;
+        }
+
+        Ok::<_, anyhow::Error>(())
+    })
+    .await??;
+
+    Span::current().record("checked", checked);
+    Span::current().record("failed", failed);
+
+    Ok((checked, failed))
+}
+
+/// Check links periodically, if configured
+pub fn register(
+    scheduler: &mut Scheduler,
+    db: DbBase,
+    cfg: Option<LinkCheckerConfig>,
+) -> Result<()> {
+    let Some(cfg) = cfg else {
+        return Ok(());
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(cfg.timeout_ms))
+        .user_agent(concat!("isixhosa_click/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+
+    let db = DbImpl(db.primary);
+    let schedule = Schedule::Every(Duration::from_secs(cfg.interval_hours * 60 * 60));
+
+    scheduler.register("link-checker", schedule, move || {
+        let (db, client) = (db.clone(), client.clone());
+        async move {
+            let (checked, failed) = check_links(db, &client, cfg).await?;
+            info!(checked, failed, "Checked the links given by references");
+            Ok(())
+        }
+    });
+
+    Ok(())
+}
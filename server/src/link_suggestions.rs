@@ -0,0 +1,147 @@
+//! A background job which suggests linking existing words whose English glosses share many words,
+//! e.g "to run quickly" and "to run fast". The suggestions are queued for moderators like any other
+//! suggested link, but they are listed after the links suggested by users.
+
+use crate::auth::FullUser;
+use crate::config::LinkSuggestionConfig;
+use crate::database::link_suggestions::{
+    fetch_all_glosses, fetch_known_pairs, suggest_link, Gloss,
+};
+use crate::scheduler::{Schedule, Scheduler};
+use crate::spawn_blocking_child;
+use anyhow::{Context, Result};
+use isixhosa_common::database::db_impl::DbImpl;
+use isixhosa_common::database::{DbBase, ModeratorAccessDb};
+use isixhosa_common::normalize;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tracing::{info, instrument, Span};
+
+/// Words which say little about the meaning of a gloss, so are ignored when comparing glosses
+const STOP_WORDS: &[&str] = &[
+    "a",
+    "an",
+    "and",
+    "as",
+    "at",
+    "be",
+    "become",
+    "by",
+    "for",
+    "from",
+    "in",
+    "into",
+    "is",
+    "it",
+    "of",
+    "on",
+    "one",
+    "or",
+    "someone",
+    "something",
+    "the",
+    "to",
+    "with",
+];
+
+fn tokens(gloss: &str) -> HashSet<String> {
+    gloss
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty() && !STOP_WORDS.contains(token))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// The number of tokens shared by both glosses out of all of the tokens in either of them
+fn gloss_overlap(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+
+    if total == 0 {
+        0.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+fn is_same_spelling(a: &Gloss, b: &Gloss) -> bool {
+    normalize::matches(a.xhosa.trim(), b.xhosa.trim())
+}
+
+/// Find pairs of words whose glosses overlap enough, and suggest linking the ones which overlap the
+/// most. Returns the number of links suggested.
+#[instrument(name = "Suggest links from gloss overlap", fields(suggested), skip_all)]
+pub fn suggest_links(db: &impl ModeratorAccessDb, cfg: &LinkSuggestionConfig) -> Result<usize> {
+    FullUser::fetch_by_id(db, cfg.user_id)
+        .context("The user which link suggestions are attributed to doesn't exist")?;
+
+    let glosses = fetch_all_glosses(db);
+    let known = fetch_known_pairs(db);
+    let tokens: Vec<HashSet<String>> = glosses.iter().map(|g| tokens(&g.english)).collect();
+
+    // Only glosses which share at least one token are compared
+    let mut index: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, gloss_tokens) in tokens.iter().enumerate() {
+        for token in gloss_tokens {
+            index.entry(token).or_default().push(i);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (i, gloss_tokens) in tokens.iter().enumerate() {
+        let others: HashSet<usize> = gloss_tokens
+            .iter()
+            .flat_map(|token| index[token.as_str()].iter().copied())
+            .filter(|&j| j > i)
+            .collect();
+
+        for j in others {
+            let (a, b) = (&glosses[i], &glosses[j]);
+            let pair = (a.word_id.min(b.word_id), a.word_id.max(b.word_id));
+
+            // Words spelled the same are already linked as confusable when they are accepted
+            if known.contains(&pair) || is_same_spelling(a, b) {
+                continue;
+            }
+
+            let overlap = gloss_overlap(gloss_tokens, &tokens[j]);
+            if overlap >= cfg.min_overlap {
+                candidates.push((overlap, pair));
+            }
+        }
+    }
+
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    candidates.truncate(cfg.max_per_run);
+
+    for &(overlap, (first, second)) in &candidates {
+        suggest_link(db, cfg.user_id, first, second, overlap);
+    }
+
+    Span::current().record("suggested", candidates.len());
+
+    Ok(candidates.len())
+}
+
+/// Suggest links periodically, if configured
+pub fn register(scheduler: &mut Scheduler, db: DbBase, cfg: Option<LinkSuggestionConfig>) {
+    let Some(cfg) = cfg else {
+        return;
+    };
+
+    let db = DbImpl(db.primary);
+    let schedule = Schedule::Every(Duration::from_secs(cfg.interval_hours * 60 * 60));
+
+    scheduler.register("link-suggestions", schedule, move || {
+        let db = db.clone();
+        async move {
+            let suggested = spawn_blocking_child(move || suggest_links(&db, &cfg)).await??;
+            info!(
+                suggested,
+                "Suggested links between words with overlapping glosses"
+            );
+            Ok(())
+        }
+    });
+}
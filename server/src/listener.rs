@@ -0,0 +1,175 @@
+//! Serving the site on accepted connections with hyper. warp's own server doesn't expose hyper's
+//! connection settings, so connections are served here instead, with the settings from
+//! [`ConnectionTuning`], and are given time to finish their requests when the server is stopped.
+
+use crate::config::ConnectionTuning;
+use crate::security_headers::CspNonce;
+use anyhow::Result;
+use futures::future::{self, BoxFuture, Either, FutureExt};
+use hyper::server::conn::Http;
+use hyper::service::{service_fn, Service};
+use hyper::{Body, Request};
+use std::convert::Infallible;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::pin;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{debug, info, warn};
+use warp::reply::Response;
+
+/// Sets up an accepted connection before requests are served on it, e.g a TLS handshake
+pub type Handshake<C> = BoxFuture<'static, io::Result<C>>;
+
+/// The address of the client. warp only knows the client's address when it accepts the connection
+/// itself, so it is passed along in the request's extensions instead.
+#[derive(Copy, Clone, Debug)]
+pub struct PeerAddr(pub SocketAddr);
+
+/// Somewhere which connections are accepted from
+pub trait Listener {
+    type Conn: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+
+    /// Accept the next connection, along with the client's address if it has one. Anything slow
+    /// belongs in the handshake, which is run separately for each connection, so that one slow
+    /// client doesn't hold up the others.
+    #[allow(clippy::type_complexity)]
+    fn accept(&mut self) -> BoxFuture<'_, io::Result<(Handshake<Self::Conn>, Option<SocketAddr>)>>;
+}
+
+impl Listener for TcpListener {
+    type Conn = TcpStream;
+
+    #[allow(clippy::type_complexity)]
+    fn accept(&mut self) -> BoxFuture<'_, io::Result<(Handshake<TcpStream>, Option<SocketAddr>)>> {
+        async move {
+            let (stream, addr) = TcpListener::accept(self).await?;
+            Ok((future::ok(stream).boxed(), Some(addr)))
+        }
+        .boxed()
+    }
+}
+
+impl Listener for UnixListener {
+    type Conn = UnixStream;
+
+    #[allow(clippy::type_complexity)]
+    fn accept(&mut self) -> BoxFuture<'_, io::Result<(Handshake<UnixStream>, Option<SocketAddr>)>> {
+        async move {
+            let (stream, _) = UnixListener::accept(self).await?;
+            Ok((future::ok(stream).boxed(), None))
+        }
+        .boxed()
+    }
+}
+
+fn http(tuning: &ConnectionTuning) -> Http {
+    let mut http = Http::new();
+    http.http1_keep_alive(tuning.keep_alive)
+        .http1_header_read_timeout(Duration::from_secs(tuning.header_read_timeout_secs))
+        .http2_keep_alive_interval(
+            tuning
+                .http2_keep_alive_interval_secs
+                .map(Duration::from_secs),
+        )
+        .http2_keep_alive_timeout(Duration::from_secs(tuning.http2_keep_alive_timeout_secs))
+        .http2_max_concurrent_streams(tuning.http2_max_concurrent_streams);
+    http
+}
+
+/// Serve connections from the listener until the server is stopped with SIGINT or SIGTERM. Open
+/// connections are then given the grace period to finish the requests they are handling.
+pub async fn serve<L, S>(mut listener: L, service: S, tuning: ConnectionTuning) -> Result<()>
+where
+    L: Listener,
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    let http = http(&tuning);
+    let shutdown = CancellationToken::new();
+    let connections = TaskTracker::new();
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut stop = pin!(async move {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = terminate.recv() => {},
+        }
+    });
+
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut stop => break,
+        };
+
+        let (handshake, addr) = match accepted {
+            Ok(conn) => conn,
+            Err(e) => {
+                // Most likely out of file descriptors, so give some connections time to close
+                warn!("Failed to accept connection: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let (http, service, shutdown) = (http.clone(), service.clone(), shutdown.clone());
+
+        connections.spawn(async move {
+            let stream = match handshake.await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    debug!(?addr, "Connection failed before its first request: {}", e);
+                    return;
+                }
+            };
+
+            let service = service_fn(move |mut req: Request<Body>| {
+                if let Some(addr) = addr {
+                    req.extensions_mut().insert(PeerAddr(addr));
+                }
+                req.extensions_mut().insert(CspNonce::generate());
+                service.clone().call(req)
+            });
+
+            let mut conn = pin!(http.serve_connection(stream, service).with_upgrades());
+            let res = match future::select(conn.as_mut(), pin!(shutdown.cancelled())).await {
+                Either::Left((res, _)) => res,
+                Either::Right(_) => {
+                    conn.as_mut().graceful_shutdown();
+                    conn.await
+                }
+            };
+
+            if let Err(e) = res {
+                debug!(?addr, "Error serving connection: {}", e);
+            }
+        });
+    }
+
+    info!(
+        "Shutting down, waiting up to {}s for {} open connections",
+        tuning.shutdown_grace_period_secs,
+        connections.len()
+    );
+
+    shutdown.cancel();
+    connections.close();
+
+    let grace_period = Duration::from_secs(tuning.shutdown_grace_period_secs);
+    if tokio::time::timeout(grace_period, connections.wait())
+        .await
+        .is_err()
+    {
+        warn!(
+            "{} connections were still open at the end of the grace period",
+            connections.len()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! Maintenance mode pauses submissions and edits from everyone but administrators, e.g while the
+//! database is being migrated. It is toggled from the admin panel and isn't persisted, so the
+//! site always starts with it off.
+
+use crate::auth::FullUser;
+use crate::i18n::I18nInfo;
+use isixhosa_common::auth::Permissions;
+use isixhosa_common::database::UserAccessDb;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::info;
+use warp::Rejection;
+
+static MAINTENANCE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// The site is in maintenance mode, so nothing can be submitted
+#[derive(Debug)]
+pub struct UnderMaintenance;
+
+impl warp::reject::Reject for UnderMaintenance {}
+
+pub fn is_enabled() -> bool {
+    MAINTENANCE_MODE.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    info!(enabled, "Maintenance mode toggled");
+    MAINTENANCE_MODE.store(enabled, Ordering::Relaxed);
+}
+
+/// Rejects with [`UnderMaintenance`] if maintenance mode is on and the user isn't an administrator
+pub async fn check_not_under_maintenance<D: UserAccessDb>(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: D,
+) -> Result<(FullUser, I18nInfo, D), Rejection> {
+    if is_enabled() && !user.permissions.contains(Permissions::Administrator) {
+        Err(warp::reject::custom(UnderMaintenance))
+    } else {
+        Ok((user, i18n_info, db))
+    }
+}
@@ -1,30 +1,54 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::auth::{with_moderator_auth, FullUser};
+use crate::auth::{reauthenticate_url, reply_reauthenticate, with_moderator_auth, FullUser};
+use crate::config::ChecklistItem;
+use crate::csrf::{csrf_protected_form, csrf_protected_form_with_fields};
 use crate::database::deletion::{
     ExampleDeletionSuggestion, LinkedWordDeletionSuggestion, WordDeletionSuggestion,
 };
+use crate::database::dependencies::{self, Dependencies};
+use crate::database::link_suggestions;
+use crate::database::moderation_actions;
+use crate::database::notifications::Notification;
+use crate::database::pending::{self, QueueState};
+use crate::database::provenance::Provenance;
+use crate::database::references;
+use crate::database::slugs;
+use crate::database::spam::{self, SpamReason};
+use crate::database::status::SuggestionStatus;
 use crate::database::submit::{submit_suggestion, WordSubmission};
 use crate::database::suggestion::{
     DatasetAttributionSuggestion, SuggestedExample, SuggestedLinkedWord, SuggestedWord,
 };
+use crate::database::trainees;
+use crate::details::{homographs, verb_derivation};
+use crate::edit_lock;
+use crate::error::{ServerError, ServerResult};
+use crate::flash;
 use crate::i18n::I18nInfo;
 use crate::i18n::SiteContext;
+use crate::queue_events::{queue_events, QueueEventsQuery, QueueWatcher};
 use crate::search::TantivyClient;
-use crate::serialization::qs_form;
-use crate::submit::edit_suggestion_page;
+use crate::serialization::FormFields;
+use crate::submit::{edit_suggestion_page, review_submission};
+use crate::Config;
 use crate::{spawn_blocking_child, DebugBoxedExt};
+use anyhow::Context;
 use askama::Template;
+use chrono::{DateTime, Utc};
 use isixhosa_click_macros::I18nTemplate;
 use isixhosa_common::auth::Auth;
 use isixhosa_common::database::WordId;
 use isixhosa_common::database::{DbBase, ModeratorAccessDb, WordOrSuggestionId};
+use isixhosa_common::flash::{FlashLinkText, FlashMessage};
 use isixhosa_common::format::DisplayHtml;
-use isixhosa_common::types::{ExistingWord, WordHit};
-use serde::Deserialize;
+use isixhosa_common::templates::{AcceptPreview, WordDetails, WordPreview};
+use isixhosa_common::types::{ExistingLinkedWord, ExistingWord, WordHit};
+use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
-use tracing::{error, instrument, Span};
+use tracing::{error, instrument, warn, Span};
+use warp::reply::Response;
 use warp::{body, Filter, Rejection, Reply};
 
 #[derive(Template, I18nTemplate, Debug)]
@@ -32,10 +56,27 @@ use warp::{body, Filter, Rejection, Reply};
 struct ModerationTemplate {
     auth: Auth,
     i18n_info: I18nInfo,
-    previous_success: Option<Success>,
     word_suggestions: Vec<SuggestedWord>,
     word_deletions: Vec<WordDeletionSuggestion>,
     word_associated_edits: Vec<(WordHit, WordAssociatedEdits)>,
+    /// What was in the queue when the page was loaded, so that it can tell when it changes
+    queue: QueueState,
+    /// The number of suggested words in the likely spam tab rather than the main queue
+    likely_spam_count: usize,
+    /// The number of references whose links are broken
+    broken_link_count: u64,
+    /// When each suggested word was submitted, by suggestion ID
+    submitted: HashMap<u64, DateTime<Utc>>,
+    /// Suggestions pending for at least this many days are highlighted
+    overdue_after_days: u64,
+    /// What must be ticked before accepting a suggested word. Empty unless the moderator is a
+    /// trainee.
+    checklist: Vec<ChecklistItem>,
+    /// Other suggested words which suggested words are linked to, by suggestion ID
+    dependencies: HashMap<u64, Dependencies>,
+    /// How much the English glosses overlap for links which were suggested automatically, by
+    /// suggestion ID
+    automatic_links: HashMap<u64, f64>,
 }
 
 impl ModerationTemplate {
@@ -45,19 +86,72 @@ impl ModerationTemplate {
             && self.word_associated_edits.is_empty()
     }
 
-    fn prev_action_method(&self) -> &'static str {
-        match &self.previous_success {
-            None => "other",
-            Some(success) => match success.method {
-                None => "other",
-                Some(Method::Edit) => "edit",
-                Some(Method::Accept) => "accept",
-                Some(Method::Reject) => "reject",
-            },
-        }
+    /// The number of whole days the suggestion has been pending for, if known
+    fn pending_days(&self, suggestion_id: u64) -> Option<i64> {
+        self.submitted
+            .get(&suggestion_id)
+            .map(|submitted| (Utc::now() - *submitted).num_days())
+    }
+
+    fn is_overdue(&self, suggestion_id: u64) -> bool {
+        self.pending_days(suggestion_id)
+            .is_some_and(|days| days >= self.overdue_after_days as i64)
+    }
+
+    /// The percentage by which the glosses overlap, if the link was suggested automatically
+    fn automatic_link_overlap(&self, suggestion_id: u64) -> Option<u32> {
+        self.automatic_links
+            .get(&suggestion_id)
+            .map(|overlap| (overlap * 100.0).round() as u32)
+    }
+
+    fn dependencies(&self, suggestion_id: u64) -> Option<&Dependencies> {
+        self.dependencies.get(&suggestion_id)
     }
 }
 
+/// Shows one suggested word at a time, so that the queue can be cleared from the keyboard
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "moderation_review.askama.html")]
+struct ModerationReviewTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    suggestion: Option<SuggestedWord>,
+    remaining: usize,
+    /// What must be ticked before accepting the word. Empty unless the moderator is a trainee.
+    checklist: Vec<ChecklistItem>,
+    /// Other suggested words which the suggestion is linked to
+    dependencies: Dependencies,
+}
+
+/// Suggested words which look like spam, kept apart from the main queue
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "moderation_spam.askama.html")]
+struct ModerationSpamTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    suggestions: Vec<(SuggestedWord, Vec<SpamReason>)>,
+}
+
+/// A single suggested word along with its context, so that it can be linked to directly
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "moderation_suggestion.askama.html")]
+struct ModerationSuggestionTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    suggestion: SuggestedWord,
+    /// The word as it currently is, if the suggestion edits an existing word
+    existing_word: Option<ExistingWord>,
+    /// Other pending suggestions which edit the same word
+    same_word_suggestions: Vec<SuggestedWord>,
+    /// Other pending suggestions by the same user
+    same_user_suggestions: Vec<SuggestedWord>,
+    /// What must be ticked before accepting the word. Empty unless the moderator is a trainee.
+    checklist: Vec<ChecklistItem>,
+    /// Other suggested words which the suggestion is linked to
+    dependencies: Dependencies,
+}
+
 /// Edits that are associated to a word but not of the word itself, e.g. examples
 #[derive(Default, Debug)]
 pub struct WordAssociatedEdits {
@@ -129,16 +223,21 @@ impl WordAssociatedEdits {
     fn linked_words_is_empty(&self) -> bool {
         self.linked_word_suggestions.is_empty() && self.linked_word_deletion_suggestions.is_empty()
     }
-}
 
-#[derive(Debug)]
-struct Success {
-    success: bool,
-    method: Option<Method>,
-    next_suggestion: Option<u32>,
+    /// Whether the only edits are links which were suggested automatically, which are less urgent
+    /// than edits suggested by users
+    fn is_only_automatic_links(&self, automatic_links: &HashMap<u64, f64>) -> bool {
+        self.examples_is_empty()
+            && self.linked_word_deletion_suggestions.is_empty()
+            && self.dataset_attribution_suggestions.is_empty()
+            && self
+                .linked_word_suggestions
+                .iter()
+                .all(|l| automatic_links.contains_key(&l.suggestion_id))
+    }
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 enum Method {
     Edit,
@@ -146,16 +245,67 @@ enum Method {
     Reject,
 }
 
+impl Method {
+    fn name(&self) -> &'static str {
+        match self {
+            Method::Edit => "edit",
+            Method::Accept => "accept",
+            Method::Reject => "reject",
+        }
+    }
+}
+
+/// The outcome of a moderation action, shown at the top of the moderation page after it
+fn action_outcome(success: bool, method: Option<&Method>) -> FlashMessage {
+    let method = method.map(Method::name).unwrap_or("other");
+
+    if success {
+        FlashMessage::success("moderation.action-success").with_arg("method", method)
+    } else {
+        FlashMessage::error("moderation.action-fail").with_arg("method", method)
+    }
+}
+
+/// An action taken from review mode, where the suggestion is always a suggested word
+#[derive(Deserialize, Debug)]
+struct ReviewAction {
+    method: Method,
+    #[serde(default)]
+    checklist: Vec<ChecklistItem>,
+}
+
+#[derive(Serialize, Debug)]
+struct ReviewActionResult {
+    success: bool,
+    /// Where to sign in again, if the moderator needs to before they can accept suggestions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reauthenticate: Option<String>,
+    /// Suggested words which are still pending but linked to the word which was just accepted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pending_dependencies: Vec<u64>,
+}
+
 #[derive(Deserialize, Debug)]
-struct Action {
+struct BanSubmitter {
+    user_id: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct Action {
     #[serde(flatten)]
     suggestion: ActionTarget,
     method: Method,
     suggestion_anchor_ord: u32,
+    /// Why a suggested word was rejected, which is shown to its submitter
+    #[serde(default)]
+    reason: Option<String>,
+    /// The checklist items ticked by a trainee moderator before accepting a suggested word
+    #[serde(default)]
+    checklist: Vec<ChecklistItem>,
 }
 
 #[serde_as]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "suggestion_type", content = "suggestion")]
 enum ActionTarget {
@@ -168,83 +318,463 @@ enum ActionTarget {
     DatasetAttribution(#[serde_as(as = "DisplayFromStr")] u64),
 }
 
+/// The parts of the config which affect the moderation pages
+#[derive(Debug)]
+struct ModerationSettings {
+    /// Suggestions pending for at least this many days are highlighted
+    overdue_after_days: u64,
+    /// What trainee moderators must tick before accepting a suggested word
+    trainee_checklist: Vec<ChecklistItem>,
+}
+
+impl ModerationSettings {
+    /// The checklist which the moderator must tick before accepting a suggested word, which is
+    /// empty unless they are a trainee
+    fn checklist_for(&self, db: &impl ModeratorAccessDb, moderator_id: u64) -> Vec<ChecklistItem> {
+        if !self.trainee_checklist.is_empty() && trainees::is_trainee(db, moderator_id) {
+            self.trainee_checklist.clone()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Check that the moderator ticked everything that they had to before accepting a suggested word
+async fn checklist_complete(
+    settings: Arc<ModerationSettings>,
+    db: &impl ModeratorAccessDb,
+    moderator_id: u64,
+    ticked: &[ChecklistItem],
+) -> ServerResult<bool> {
+    let db = db.clone();
+    let required = spawn_blocking_child(move || settings.checklist_for(&db, moderator_id)).await?;
+    Ok(required.iter().all(|item| ticked.contains(item)))
+}
+
 pub fn moderation(
     db: DbBase,
     tantivy: Arc<TantivyClient>,
     site_ctx: Arc<SiteContext>,
+    queue: Arc<QueueWatcher>,
+    cfg: &Config,
 ) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
     let with_tantivy = warp::any().map(move || tantivy.clone());
+    let settings = Arc::new(ModerationSettings {
+        overdue_after_days: cfg.moderation_overdue_days,
+        trainee_checklist: cfg.trainee_checklist.clone(),
+    });
+    let body_limits = cfg.body_limits;
+    let with_settings = warp::any().map(move || settings.clone());
 
     let show_all = warp::get()
-        .and(warp::any().map(|| None)) // previous_success is None
+        .and(with_settings.clone())
         .and(with_moderator_auth(db.clone(), site_ctx.clone()))
         .and_then(moderation_template);
 
     let process_one = warp::post()
+        .and(body::content_length_limit(body_limits.form))
+        .and(with_settings.clone())
         .and(with_tantivy.clone())
-        .and(body::form::<Action>())
+        .and(csrf_protected_form::<Action>())
         .and(with_moderator_auth(db.clone(), site_ctx.clone()))
         .and_then(process_one);
 
     let submit_edit = warp::post()
-        .and(body::content_length_limit(64 * 1024))
-        .and(with_tantivy)
-        .and(qs_form())
+        .and(body::content_length_limit(body_limits.submission))
+        .and(with_tantivy.clone())
+        .and(csrf_protected_form_with_fields())
         .and(with_moderator_auth(db.clone(), site_ctx.clone()))
         .and_then(edit_suggestion_form);
 
-    let edit_failed = warp::any()
-        .and(warp::any().map(|| {
-            Some(Success {
-                success: false,
-                method: Some(Method::Edit),
-                next_suggestion: None,
-            })
-        }))
-        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
-        .and_then(moderation_template);
-
     let other_failed = warp::any()
-        .and(warp::any().map(|| {
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .map(|_, _, _| {
             error!("Some action failed on moderation page");
-            Some(Success {
-                success: false,
-                method: None,
-                next_suggestion: None,
-            })
-        }))
+            flash::redirect("/moderation", vec![action_outcome(false, None)])
+        });
+
+    let events = warp::get()
+        .and(warp::path("events"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .map(move |query: QueueEventsQuery, _user, i18n_info, _db| {
+            let events = queue_events(&queue, query, i18n_info);
+            warp::sse::reply(warp::sse::keep_alive().stream(events))
+        });
+
+    let review_page = warp::get()
+        .and(warp::path::end())
+        .and(with_settings.clone())
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .and_then(review_template);
+
+    let review_action = warp::post()
+        .and(warp::path![u64])
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(with_settings.clone())
+        .and(with_tantivy.clone())
+        .and(csrf_protected_form::<ReviewAction>())
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .and_then(process_review_action);
+
+    let accept_preview = warp::get()
+        .and(warp::path!("suggestion" / u64 / "preview"))
+        .and(warp::path::end())
+        .and(with_settings.clone())
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .and_then(accept_preview_page);
+
+    let suggestion_page = warp::get()
+        .and(warp::path!("suggestion" / u64))
+        .and(warp::path::end())
+        .and(with_settings)
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .and_then(suggestion_template);
+
+    let spam_page = warp::get()
+        .and(warp::path::end())
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .and_then(spam_template);
+
+    let ban_submitter = warp::post()
+        .and(warp::path("ban"))
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(with_tantivy)
+        .and(csrf_protected_form::<BanSubmitter>())
         .and(with_moderator_auth(db, site_ctx.clone()))
-        .and_then(moderation_template);
+        .and_then(ban_submitter);
 
     let root = warp::path::end().and(show_all.or(process_one).or(other_failed));
-    let submit_edit = warp::path("edit")
-        .and(warp::path::end())
-        .and(submit_edit.or(edit_failed));
+    let submit_edit = warp::path("edit").and(warp::path::end()).and(submit_edit);
+    let review = warp::path("review").and(review_page.or(review_action));
+    let spam = warp::path("spam").and(spam_page.or(ban_submitter));
 
     warp::path("moderation")
-        .and(root.or(submit_edit))
+        .and(
+            root.or(submit_edit)
+                .or(events)
+                .or(review)
+                .or(suggestion_page)
+                .or(accept_preview)
+                .or(spam),
+        )
         .debug_boxed()
 }
 
 #[instrument(name = "Display moderation template", skip_all)]
 async fn moderation_template(
-    previous_success: Option<Success>,
+    settings: Arc<ModerationSettings>,
     user: FullUser,
     i18n_info: I18nInfo,
     db: impl ModeratorAccessDb,
 ) -> Result<impl Reply, Rejection> {
-    spawn_blocking_child(move || {
-        Ok(ModerationTemplate {
+    let template = spawn_blocking_child(move || {
+        let (likely_spam, word_suggestions) = partition_likely_spam(&db, &i18n_info);
+        let checklist = settings.checklist_for(&db, user.id.get());
+        let automatic_links = link_suggestions::fetch_pending(&db);
+
+        // Words with only automatically suggested links are listed last, since they are less urgent
+        let mut word_associated_edits = WordAssociatedEdits::fetch_all(&db, &i18n_info);
+        word_associated_edits
+            .sort_by_key(|(_, assoc)| assoc.is_only_automatic_links(&automatic_links));
+
+        ModerationTemplate {
             auth: user.into(),
             i18n_info: i18n_info.clone(),
-            previous_success,
-            word_suggestions: SuggestedWord::fetch_all_full(&db, &i18n_info),
+            word_suggestions,
             word_deletions: WordDeletionSuggestion::fetch_all(&db),
-            word_associated_edits: WordAssociatedEdits::fetch_all(&db, &i18n_info),
+            word_associated_edits,
+            queue: pending::fetch_queue_state(&db),
+            likely_spam_count: likely_spam.len(),
+            broken_link_count: references::count_broken_links(&db),
+            submitted: pending::fetch_all_submitted(&db),
+            overdue_after_days: settings.overdue_after_days,
+            checklist,
+            dependencies: dependencies::fetch_all(&db),
+            automatic_links,
+        }
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    Ok(template)
+}
+
+/// Split the suggested words into those which look like spam, along with why, and the rest
+fn partition_likely_spam(
+    db: &impl ModeratorAccessDb,
+    i18n_info: &I18nInfo,
+) -> (Vec<(SuggestedWord, Vec<SpamReason>)>, Vec<SuggestedWord>) {
+    let mut flagged = spam::fetch_flagged(db);
+    let (mut likely_spam, mut rest) = (Vec::new(), Vec::new());
+
+    for suggestion in SuggestedWord::fetch_all_full(db, i18n_info) {
+        match flagged.remove(&suggestion.suggestion_id) {
+            Some(reasons) => likely_spam.push((suggestion, reasons)),
+            None => rest.push(suggestion),
+        }
+    }
+
+    (likely_spam, rest)
+}
+
+#[instrument(name = "Display likely spam tab", skip_all)]
+async fn spam_template(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let template = spawn_blocking_child(move || ModerationSpamTemplate {
+        auth: user.into(),
+        i18n_info: i18n_info.clone(),
+        suggestions: partition_likely_spam(&db, &i18n_info).0,
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    Ok(template)
+}
+
+/// Lock the submitter's account and reject all of their suggestions which look like spam
+#[instrument(name = "Ban spam submitter", fields(user_id = ban.user_id), skip_all)]
+async fn ban_submitter(
+    tantivy: Arc<TantivyClient>,
+    ban: BanSubmitter,
+    _user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let success = spawn_blocking_child(move || {
+        if !FullUser::lock_spam_submitter(&db, ban.user_id) {
+            return false;
+        }
+
+        for (suggestion, _) in partition_likely_spam(&db, &i18n_info).0 {
+            if suggestion.suggesting_user.id.get() == ban.user_id {
+                SuggestionStatus::mark_rejected(&db, suggestion.suggestion_id, None);
+                dependencies::flag_dependents(&db, suggestion.suggestion_id);
+                SuggestedWord::delete(&db, tantivy.clone(), suggestion.suggestion_id);
+            }
+        }
+
+        true
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    let outcome = if success {
+        FlashMessage::success("moderation.ban-success")
+    } else {
+        FlashMessage::error("moderation.ban-fail")
+    };
+
+    Ok(flash::redirect("/moderation/spam", vec![outcome]))
+}
+
+#[instrument(name = "Display moderation review mode", skip_all)]
+async fn review_template(
+    settings: Arc<ModerationSettings>,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let template = spawn_blocking_child(move || {
+        let (_, suggestions) = partition_likely_spam(&db, &i18n_info);
+        let remaining = suggestions.len();
+        let checklist = settings.checklist_for(&db, user.id.get());
+        let suggestion = suggestions.into_iter().next();
+        let dependencies = suggestion
+            .as_ref()
+            .map(|s| dependencies::fetch_for_suggestion(&db, s.suggestion_id))
+            .unwrap_or_default();
+
+        ModerationReviewTemplate {
+            auth: user.into(),
+            i18n_info,
+            suggestion,
+            remaining,
+            checklist,
+            dependencies,
+        }
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    Ok(template)
+}
+
+#[instrument(
+    name = "Display single suggestion",
+    skip(settings, user, i18n_info, db)
+)]
+async fn suggestion_template(
+    suggestion_id: u64,
+    settings: Arc<ModerationSettings>,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let template = spawn_blocking_child(move || {
+        let suggestion = SuggestedWord::fetch_full(&db, &i18n_info, suggestion_id)?;
+        let existing_word = suggestion
+            .word_id
+            .and_then(|id| ExistingWord::fetch_full(&db, id));
+
+        let (mut same_word_suggestions, mut same_user_suggestions) = (Vec::new(), Vec::new());
+        for other in SuggestedWord::fetch_all_full(&db, &i18n_info) {
+            if other.suggestion_id == suggestion_id {
+                continue;
+            }
+
+            if suggestion.word_id.is_some() && other.word_id == suggestion.word_id {
+                same_word_suggestions.push(other);
+            } else if other.suggesting_user.id == suggestion.suggesting_user.id {
+                same_user_suggestions.push(other);
+            }
+        }
+
+        let checklist = settings.checklist_for(&db, user.id.get());
+        let dependencies = dependencies::fetch_for_suggestion(&db, suggestion_id);
+
+        Some(ModerationSuggestionTemplate {
+            auth: user.into(),
+            i18n_info,
+            suggestion,
+            existing_word,
+            same_word_suggestions,
+            same_user_suggestions,
+            checklist,
+            dependencies,
         })
     })
     .await
-    .unwrap()
+    .map_err(ServerError::from)?
+    .ok_or_else(warp::reject::not_found)?;
+
+    Ok(template)
+}
+
+/// Show the word page as it will look once the suggested word is accepted, without accepting it
+#[instrument(name = "Display accept preview", skip(settings, user, i18n_info, db))]
+async fn accept_preview_page(
+    suggestion_id: u64,
+    settings: Arc<ModerationSettings>,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<Response, Rejection> {
+    let (db_clone, i18n_clone) = (db.clone(), i18n_info.clone());
+    let user_id = user.id.get();
+    let (word, accept) = spawn_blocking_child(move || {
+        let db = db_clone;
+        let suggestion = SuggestedWord::fetch_full(&db, &i18n_clone, suggestion_id)?;
+        let (word, new_homograph_links) = suggestion.preview_accept(&db);
+
+        let accept = AcceptPreview {
+            suggestion_id,
+            slug: slugs::preview_slug(&db, suggestion.word_id, &word.xhosa),
+            search_hit: suggestion.accepted_document(word.word_id).into(),
+            new_homograph_links,
+            can_accept: settings.checklist_for(&db, user_id).is_empty(),
+        };
+
+        Some((word, accept))
+    })
+    .await
+    .map_err(ServerError::from)?
+    .ok_or_else(warp::reject::not_found)?;
+
+    let derivation = verb_derivation(&word, db.clone()).await?;
+    let homographs = homographs(&word, db).await?;
+
+    let fields = [
+        ("suggestion_type", "word".to_owned()),
+        ("suggestion", suggestion_id.to_string()),
+        ("suggestion_anchor_ord", "0".to_owned()),
+    ];
+
+    let details = WordDetails {
+        auth: user.into(),
+        i18n_info,
+        word,
+        views: None,
+        classroom: None,
+        preview: Some(WordPreview {
+            route: "/moderation".to_owned(),
+            fields: fields
+                .into_iter()
+                .map(|(field, value)| (field.to_owned(), value))
+                .collect(),
+            accept: Some(accept),
+        }),
+        derivation,
+        homographs,
+        neighbours: Default::default(),
+    };
+
+    Ok(details.into_response())
+}
+
+#[instrument(
+    name = "Process moderation review action",
+    skip(settings, tantivy, user, i18n_info, db)
+)]
+async fn process_review_action(
+    suggestion_id: u64,
+    settings: Arc<ModerationSettings>,
+    tantivy: Arc<TantivyClient>,
+    action: ReviewAction,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    if action.method == Method::Accept && !user.signed_in_recently() {
+        return Ok(warp::reply::json(&ReviewActionResult {
+            success: false,
+            reauthenticate: Some(reauthenticate_url("/moderation/review")),
+            pending_dependencies: Vec::new(),
+        }));
+    }
+
+    let dependencies = match action.method {
+        Method::Accept => pending_dependencies(&db, suggestion_id).await?,
+        _ => Vec::new(),
+    };
+
+    let success = match action.method {
+        Method::Accept
+            if !checklist_complete(settings, &db, user.id.get(), &action.checklist).await? =>
+        {
+            warn!("Trainee moderator tried to accept a suggestion without the checklist ticked");
+            false
+        }
+        Method::Accept => accept_suggested_word(&db, i18n_info, tantivy, suggestion_id).await?,
+        Method::Reject => reject_suggested_word(&db, tantivy, suggestion_id, None).await?,
+        Method::Edit => {
+            error!("Got request to edit a suggestion from review mode, but this goes via the form");
+            false
+        }
+    };
+
+    if success {
+        let accepted = action.method == Method::Accept;
+        record_action(&db, user.id.get(), accepted, action.checklist).await?;
+    }
+
+    let pending_dependencies = if success {
+        dependencies.into_iter().map(|hit| hit.id).collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(warp::reply::json(&ReviewActionResult {
+        success,
+        reauthenticate: None,
+        pending_dependencies,
+    }))
 }
 
 #[instrument(
@@ -257,24 +787,80 @@ async fn moderation_template(
 )]
 async fn edit_suggestion_form(
     tantivy: Arc<TantivyClient>,
-    submission: WordSubmission,
+    mut submission: WordSubmission,
+    fields: FormFields,
     user: FullUser,
     i18n_info: I18nInfo,
     db: impl ModeratorAccessDb,
-) -> Result<impl Reply, Rejection> {
+) -> Result<Response, Rejection> {
+    submission.canonicalize();
+
+    if let Some(page) = review_submission(&submission, fields, &user, &i18n_info, &db).await? {
+        return Ok(page);
+    }
+
     let next_suggestion = submission.suggestion_anchor_ord;
-    submit_suggestion(submission, tantivy, &user, &db, i18n_info.clone()).await;
-    moderation_template(
-        Some(Success {
-            success: true,
-            method: Some(Method::Edit),
-            next_suggestion,
-        }),
-        user,
-        i18n_info,
-        db,
+    let suggestion_id = submission.suggestion_id;
+    // The suggestion already exists, so it keeps its provenance
+    submit_suggestion(
+        submission,
+        tantivy,
+        &user,
+        &db,
+        i18n_info.clone(),
+        Provenance::WebForm,
     )
-    .await
+    .await?;
+
+    if let Some(suggestion_id) = suggestion_id {
+        edit_lock::unlock_suggestion(suggestion_id, user.id);
+    }
+
+    let edited = action_outcome(true, Some(&Method::Edit));
+    Ok(flash::redirect(
+        &moderation_path(next_suggestion),
+        vec![edited],
+    ))
+}
+
+/// The moderation page, scrolled to the given suggestion so that moderators can carry on from where
+/// they were
+fn moderation_path(next_suggestion: Option<u32>) -> String {
+    match next_suggestion {
+        Some(next) => format!("/moderation#suggestion_{next}"),
+        None => "/moderation".to_owned(),
+    }
+}
+
+/// Log the outcome of a moderation action, along with the checklist if one was ticked
+async fn record_action(
+    db: &impl ModeratorAccessDb,
+    moderator_id: u64,
+    accepted: bool,
+    checklist: Vec<ChecklistItem>,
+) -> ServerResult<()> {
+    let db = db.clone();
+    spawn_blocking_child(move || {
+        let action_id = moderation_actions::record(&db, moderator_id, accepted);
+
+        if accepted && !checklist.is_empty() {
+            moderation_actions::record_checklist(&db, action_id, &checklist);
+        }
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// The other suggested words which are still pending and linked to the given one
+async fn pending_dependencies(
+    db: &impl ModeratorAccessDb,
+    suggestion: u64,
+) -> ServerResult<Vec<WordHit>> {
+    let db = db.clone();
+    let dependencies =
+        spawn_blocking_child(move || dependencies::fetch_for_suggestion(&db, suggestion)).await?;
+    Ok(dependencies.pending)
 }
 
 async fn accept_suggested_word(
@@ -282,173 +868,209 @@ async fn accept_suggested_word(
     i18n_info: I18nInfo,
     tantivy: Arc<TantivyClient>,
     suggestion: u64,
-) -> bool {
+) -> ServerResult<bool> {
     let db = db.clone();
     spawn_blocking_child(move || {
         SuggestedWord::fetch_full(&db, &i18n_info, suggestion)
-            .unwrap()
-            .accept_whole_word_suggestion(&db, tantivy);
+            .context("Suggested word not found")
+            .map(|word| word.accept_whole_word_suggestion(&db, tantivy))
     })
-    .await
-    .unwrap();
+    .await??;
 
-    true
+    Ok(true)
 }
 
-async fn reject_suggested_word(
+/// Reject a suggested word, letting the contributor know why if a reason is given
+pub async fn reject_suggested_word(
     db: &impl ModeratorAccessDb,
     tantivy: Arc<TantivyClient>,
     suggestion_id: u64,
-) -> bool {
+    reason: Option<String>,
+) -> ServerResult<bool> {
     let db = db.clone();
-    spawn_blocking_child(move || SuggestedWord::delete(&db, tantivy, suggestion_id))
-        .await
-        .unwrap()
+    Ok(spawn_blocking_child(move || {
+        SuggestionStatus::mark_rejected(&db, suggestion_id, reason.as_deref());
+        Notification::suggestion_rejected(&db, suggestion_id, reason.as_deref());
+        dependencies::flag_dependents(&db, suggestion_id);
+        SuggestedWord::delete(&db, tantivy, suggestion_id)
+    })
+    .await?)
 }
 
 async fn accept_deletion(
     db: &impl ModeratorAccessDb,
     tantivy: Arc<TantivyClient>,
     suggestion: u64,
-) -> bool {
-    let word_id = WordDeletionSuggestion::fetch_word_id_for_suggestion(db, suggestion);
-    Span::current().record("word_id", word_id);
+) -> ServerResult<bool> {
     let db = db.clone();
-
-    spawn_blocking_child(move || ExistingWord::delete(&db, word_id))
-        .await
-        .unwrap();
+    let db_clone = db.clone();
+    let (word_id, linked) = spawn_blocking_child(move || {
+        let word_id = WordDeletionSuggestion::fetch_word_id_for_suggestion(&db, suggestion);
+        Span::current().record("word_id", word_id);
+        let linked = ExistingLinkedWord::delete_all_for_word(&db, word_id);
+        ExistingWord::delete(&db, word_id);
+        (word_id, linked)
+    })
+    .await?;
 
     tantivy
         .delete_word(WordOrSuggestionId::existing(word_id))
         .await;
+    tantivy.reindex_words(db_clone, linked).await;
 
-    true
+    Ok(true)
 }
 
-async fn reject_deletion(db: &impl ModeratorAccessDb, suggestion: u64) -> bool {
+async fn reject_deletion(db: &impl ModeratorAccessDb, suggestion: u64) -> ServerResult<bool> {
     let db = db.clone();
-    spawn_blocking_child(move || WordDeletionSuggestion::reject(&db, suggestion))
-        .await
-        .unwrap();
+    spawn_blocking_child(move || WordDeletionSuggestion::reject(&db, suggestion)).await?;
 
-    true
+    Ok(true)
 }
 
-async fn accept_suggested_example(db: &impl ModeratorAccessDb, suggestion: u64) -> bool {
+async fn accept_suggested_example(
+    db: &impl ModeratorAccessDb,
+    suggestion: u64,
+) -> ServerResult<bool> {
     let db = db.clone();
     spawn_blocking_child(move || {
         SuggestedExample::fetch(&db, suggestion)
-            .unwrap()
-            .accept(&db)
+            .context("Suggested example not found")
+            .map(|example| example.accept(&db))
     })
-    .await
-    .unwrap();
+    .await??;
 
-    true
+    Ok(true)
 }
 
-async fn reject_suggested_example(db: &impl ModeratorAccessDb, suggestion: u64) -> bool {
+async fn reject_suggested_example(
+    db: &impl ModeratorAccessDb,
+    suggestion: u64,
+) -> ServerResult<bool> {
     let db = db.clone();
-    spawn_blocking_child(move || SuggestedExample::delete(&db, suggestion))
-        .await
-        .unwrap()
+    Ok(spawn_blocking_child(move || SuggestedExample::delete(&db, suggestion)).await?)
 }
 
-async fn accept_example_deletion(db: &impl ModeratorAccessDb, suggestion: u64) -> bool {
+async fn accept_example_deletion(
+    db: &impl ModeratorAccessDb,
+    suggestion: u64,
+) -> ServerResult<bool> {
     let db = db.clone();
-    spawn_blocking_child(move || ExampleDeletionSuggestion::accept(&db, suggestion))
-        .await
-        .unwrap();
+    spawn_blocking_child(move || ExampleDeletionSuggestion::accept(&db, suggestion)).await?;
 
-    true
+    Ok(true)
 }
 
-async fn reject_example_deletion(db: &impl ModeratorAccessDb, suggestion: u64) -> bool {
+async fn reject_example_deletion(
+    db: &impl ModeratorAccessDb,
+    suggestion: u64,
+) -> ServerResult<bool> {
     let db = db.clone();
     spawn_blocking_child(move || ExampleDeletionSuggestion::delete_suggestion(&db, suggestion))
-        .await
-        .unwrap();
+        .await?;
 
-    true
+    Ok(true)
 }
 
 async fn accept_linked_word(
     db: &impl ModeratorAccessDb,
     i18n_info: I18nInfo,
     suggestion: u64,
-) -> bool {
+) -> ServerResult<bool> {
     let db = db.clone();
-    spawn_blocking_child(move || {
+    let accepted = spawn_blocking_child(move || {
         SuggestedLinkedWord::fetch(&db, i18n_info, suggestion).accept(&db)
     })
-    .await
-    .unwrap();
-    true
+    .await?;
+
+    match accepted {
+        Ok(_) => Ok(true),
+        Err(error) => {
+            warn!(suggestion, %error, "Could not accept suggested linked word");
+            Ok(false)
+        }
+    }
 }
 
-async fn reject_linked_word(db: &impl ModeratorAccessDb, suggestion: u64) -> bool {
+async fn reject_linked_word(db: &impl ModeratorAccessDb, suggestion: u64) -> ServerResult<bool> {
     let db = db.clone();
-    spawn_blocking_child(move || SuggestedLinkedWord::delete(&db, suggestion))
-        .await
-        .unwrap();
-    true
+    spawn_blocking_child(move || SuggestedLinkedWord::delete(&db, suggestion)).await?;
+
+    Ok(true)
 }
 
-async fn accept_linked_word_deletion(db: &impl ModeratorAccessDb, suggestion: u64) -> bool {
+async fn accept_linked_word_deletion(
+    db: &impl ModeratorAccessDb,
+    suggestion: u64,
+) -> ServerResult<bool> {
     let db = db.clone();
-    spawn_blocking_child(move || LinkedWordDeletionSuggestion::accept(&db, suggestion))
-        .await
-        .unwrap();
-    true
+    spawn_blocking_child(move || LinkedWordDeletionSuggestion::accept(&db, suggestion)).await?;
+
+    Ok(true)
 }
 
-async fn reject_linked_word_deletion(db: &impl ModeratorAccessDb, suggestion: u64) -> bool {
+async fn reject_linked_word_deletion(
+    db: &impl ModeratorAccessDb,
+    suggestion: u64,
+) -> ServerResult<bool> {
     let db = db.clone();
     spawn_blocking_child(move || LinkedWordDeletionSuggestion::delete_suggestion(&db, suggestion))
-        .await
-        .unwrap();
-    true
+        .await?;
+
+    Ok(true)
 }
 
 async fn accept_dataset_attribution_suggestion(
     db: &impl ModeratorAccessDb,
     suggestion: u64,
-) -> bool {
+) -> ServerResult<bool> {
     let db = db.clone();
     spawn_blocking_child(move || {
         DatasetAttributionSuggestion::fetch_by_id(&db, suggestion)
-            .unwrap()
-            .accept(&db)
+            .context("Dataset attribution suggestion not found")
+            .map(|attribution| attribution.accept(&db))
     })
-    .await
-    .unwrap();
-    true
+    .await??;
+
+    Ok(true)
 }
 
 async fn reject_dataset_attribution_suggestion(
     db: &impl ModeratorAccessDb,
     suggestion: u64,
-) -> bool {
+) -> ServerResult<bool> {
     let db = db.clone();
-    spawn_blocking_child(move || DatasetAttributionSuggestion::delete(&db, suggestion))
-        .await
-        .unwrap()
+    Ok(spawn_blocking_child(move || DatasetAttributionSuggestion::delete(&db, suggestion)).await?)
 }
 
-#[instrument(name = "Process moderation page action", skip(user, db, tantivy))]
+#[instrument(
+    name = "Process moderation page action",
+    skip(settings, user, db, tantivy)
+)]
 async fn process_one(
+    settings: Arc<ModerationSettings>,
     tantivy: Arc<TantivyClient>,
     params: Action,
     user: FullUser,
     i18n_info: I18nInfo,
     db: impl ModeratorAccessDb,
 ) -> Result<impl Reply, Rejection> {
-    let db_clone = db.clone();
+    if params.method == Method::Accept && !user.signed_in_recently() {
+        return Ok(reply_reauthenticate("/moderation"));
+    }
 
     let edit_unsupported = || {
         error!("Got request to edit word or example deletion suggestion, but this makes no sense!");
-        false
+        Ok(false)
+    };
+
+    // Fetched before accepting the word, since its links to other suggestions are moved over to the
+    // new word when it is accepted
+    let dependencies = match (&params.suggestion, &params.method) {
+        (ActionTarget::Word(suggestion), Method::Accept) => {
+            pending_dependencies(&db, *suggestion).await?
+        }
+        _ => Vec::new(),
     };
 
     let success = match params.suggestion {
@@ -469,10 +1091,22 @@ async fn process_one(
                 .await
                 .map(Reply::into_response)
             }
+            Method::Accept
+                if !checklist_complete(settings.clone(), &db, user.id.get(), &params.checklist)
+                    .await? =>
+            {
+                warn!(
+                    "Trainee moderator tried to accept a suggestion without the checklist ticked"
+                );
+                Ok(false)
+            }
             Method::Accept => {
                 accept_suggested_word(&db, i18n_info.clone(), tantivy, suggestion).await
             }
-            Method::Reject => reject_suggested_word(&db, tantivy, suggestion).await,
+            Method::Reject => {
+                let reason = params.reason.clone().filter(|r| !r.trim().is_empty());
+                reject_suggested_word(&db, tantivy, suggestion, reason).await
+            }
         },
         ActionTarget::Example(suggestion) => match params.method {
             Method::Edit => todo!("Example standalone editing"),
@@ -499,18 +1133,169 @@ async fn process_one(
             Method::Accept => accept_dataset_attribution_suggestion(&db, suggestion).await,
             Method::Reject => reject_dataset_attribution_suggestion(&db, suggestion).await,
         },
+    }?;
+
+    if success && params.method != Method::Edit {
+        let accepted = params.method == Method::Accept;
+        record_action(&db, user.id.get(), accepted, params.checklist).await?;
+    }
+
+    let mut messages = vec![action_outcome(success, Some(&params.method))];
+
+    // Suggested words which are still pending but linked to the word which was just accepted
+    if success && !dependencies.is_empty() {
+        let pending = dependencies.iter().fold(
+            FlashMessage::info("moderation.accept-dependencies"),
+            |message, hit| {
+                let text = hit.to_plaintext(&i18n_info).to_string();
+                message.with_link(
+                    format!("/moderation/suggestion/{}", hit.id),
+                    FlashLinkText::Literal(text),
+                )
+            },
+        );
+        messages.push(pending);
+    }
+
+    let next_suggestion = if success {
+        params.suggestion_anchor_ord.checked_sub(1)
+    } else {
+        None
     };
+    Ok(flash::redirect(&moderation_path(next_suggestion), messages))
+}
 
-    moderation_template(
-        Some(Success {
-            success,
-            method: Some(params.method),
-            next_suggestion: params.suggestion_anchor_ord.checked_sub(1),
-        }),
-        user,
-        i18n_info,
-        db_clone,
-    )
-    .await
-    .map(Reply::into_response)
+#[cfg(test)]
+mod tests {
+    use super::{Action, ActionTarget, Method};
+    use crate::config::ChecklistItem;
+    use crate::serialization::deserialize_form;
+    use crate::test_support::{assert_redirects, TestSite, HAMBA};
+    use isixhosa_common::auth::Permissions;
+    use proptest::prelude::*;
+    use url::form_urlencoded;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn accepted_suggestions_are_searchable() {
+        let site = TestSite::start().await;
+        let user = site.sign_in_as(Permissions::User);
+        let moderator = site.sign_in_as(Permissions::Moderator);
+
+        let submitted = site.post_form("/submit", &user, HAMBA).await;
+        assert!(submitted.status().is_redirection());
+
+        let suggestions = site.pending_suggestions();
+        assert_eq!(suggestions.len(), 1);
+        assert!(site.search("hamba").await.is_empty());
+
+        let accepted = site.moderate(&moderator, suggestions[0], "accept").await;
+        assert_redirects(&accepted, "/moderation");
+
+        assert!(site.pending_suggestions().is_empty());
+        let hits = site.search("hamba").await;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].english, "go");
+        assert_eq!(hits[0].xhosa, "hamba");
+        assert!(!hits[0].is_suggestion);
+    }
+
+    #[tokio::test]
+    async fn rejected_suggestions_are_not_searchable() {
+        let site = TestSite::start().await;
+        let user = site.sign_in_as(Permissions::User);
+        let moderator = site.sign_in_as(Permissions::Moderator);
+
+        site.post_form("/submit", &user, HAMBA).await;
+        let id = site.pending_suggestions()[0];
+        let rejected = site.moderate(&moderator, id, "reject").await;
+        assert_redirects(&rejected, "/moderation");
+
+        assert!(site.pending_suggestions().is_empty());
+        assert!(site.search("hamba").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn users_cannot_moderate() {
+        let site = TestSite::start().await;
+        let user = site.sign_in_as(Permissions::User);
+
+        site.post_form("/submit", &user, HAMBA).await;
+        let id = site.pending_suggestions()[0];
+        let refused = site.moderate(&user, id, "accept").await;
+        assert_eq!(refused.status(), StatusCode::FORBIDDEN);
+
+        assert_eq!(site.pending_suggestions().len(), 1);
+        assert!(site.search("hamba").await.is_empty());
+    }
+
+    fn action_target() -> impl Strategy<Value = (&'static str, u64)> {
+        let suggestion_type = proptest::sample::select(vec![
+            "word_deletion",
+            "word",
+            "example",
+            "example_deletion",
+            "linked_word",
+            "linked_word_deletion",
+            "dataset_attribution",
+        ]);
+        (suggestion_type, any::<u64>())
+    }
+
+    fn expected_target(suggestion_type: &str, id: u64) -> ActionTarget {
+        match suggestion_type {
+            "word_deletion" => ActionTarget::WordDeletion(id),
+            "word" => ActionTarget::Word(id),
+            "example" => ActionTarget::Example(id),
+            "example_deletion" => ActionTarget::ExampleDeletion(id),
+            "linked_word" => ActionTarget::LinkedWord(id),
+            "linked_word_deletion" => ActionTarget::LinkedWordDeletion(id),
+            "dataset_attribution" => ActionTarget::DatasetAttribution(id),
+            _ => unreachable!(),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn actions_round_trip(
+            (suggestion_type, id) in action_target(),
+            method in proptest::sample::select(vec![Method::Edit, Method::Accept, Method::Reject]),
+            suggestion_anchor_ord in any::<u32>(),
+            reason in proptest::option::of("[\\w ,.'!?-]{0,40}"),
+            checklist in proptest::collection::vec(
+                proptest::sample::select(vec![
+                    ChecklistItem::SpellingVerified,
+                    ChecklistItem::ClassVerified,
+                    ChecklistItem::NoDuplicate,
+                ]),
+                0..3,
+            ),
+        ) {
+            let mut form = form_urlencoded::Serializer::new(String::new());
+            form.append_pair("suggestion_type", suggestion_type)
+                .append_pair("suggestion", &id.to_string())
+                .append_pair("method", method.name())
+                .append_pair("suggestion_anchor_ord", &suggestion_anchor_ord.to_string());
+
+            if let Some(reason) = &reason {
+                form.append_pair("reason", reason);
+            }
+
+            for (i, item) in checklist.iter().enumerate() {
+                form.append_pair(&format!("checklist[{i}]"), item.name());
+            }
+
+            let action: Action = deserialize_form(form.finish().as_bytes()).unwrap();
+            prop_assert_eq!(action.suggestion, expected_target(suggestion_type, id));
+            prop_assert_eq!(action.method, method);
+            prop_assert_eq!(action.suggestion_anchor_ord, suggestion_anchor_ord);
+            prop_assert_eq!(action.reason, reason);
+            prop_assert_eq!(action.checklist, checklist);
+        }
+
+        #[test]
+        fn arbitrary_actions_do_not_panic(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let _ = deserialize_form::<Action>(&bytes);
+        }
+    }
 }
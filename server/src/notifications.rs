@@ -0,0 +1,109 @@
+use crate::auth::{with_user_auth, FullUser};
+use crate::config::BodyLimits;
+use crate::csrf::csrf_protected;
+use crate::database::notifications::{Notification, NotificationKind};
+use crate::error::ServerError;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, UserAccessDb};
+use std::sync::Arc;
+use tracing::instrument;
+use warp::http::Uri;
+use warp::{body, Filter, Rejection, Reply};
+
+/// Older notifications are kept but not shown
+const SHOWN_NOTIFICATIONS: u64 = 50;
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "notifications.askama.html")]
+struct NotificationsTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    notifications: Vec<Notification>,
+}
+
+pub fn notifications(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    body_limits: BodyLimits,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let page = warp::get()
+        .and(warp::path::end())
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and_then(notifications_page);
+
+    let read = warp::post()
+        .and(warp::path![u64 / "read"])
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(csrf_protected())
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and_then(mark_read);
+
+    let read_all = warp::post()
+        .and(warp::path!["read"])
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(csrf_protected())
+        .and(with_user_auth(db, site_ctx))
+        .and_then(mark_all_read);
+
+    warp::path("notifications")
+        .and(page.or(read).or(read_all))
+        .debug_boxed()
+}
+
+#[instrument(name = "Show notifications", skip_all)]
+async fn notifications_page(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let user_id = user.id.get();
+    let notifications =
+        spawn_blocking_child(move || Notification::fetch_recent(&db, user_id, SHOWN_NOTIFICATIONS))
+            .await
+            .map_err(ServerError::from)?;
+
+    Ok(NotificationsTemplate {
+        auth: user.into(),
+        i18n_info,
+        notifications,
+    })
+}
+
+#[instrument(name = "Mark notification read", skip(user, _i18n_info, db))]
+async fn mark_read(
+    notification_id: u64,
+    user: FullUser,
+    _i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let user_id = user.id.get();
+    spawn_blocking_child(move || Notification::mark_read(&db, user_id, notification_id))
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(warp::redirect::see_other(Uri::from_static(
+        "/notifications",
+    )))
+}
+
+#[instrument(name = "Mark all notifications read", skip_all)]
+async fn mark_all_read(
+    user: FullUser,
+    _i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let user_id = user.id.get();
+    spawn_blocking_child(move || Notification::mark_all_read(&db, user_id))
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(warp::redirect::see_other(Uri::from_static(
+        "/notifications",
+    )))
+}
@@ -0,0 +1,119 @@
+//! A privacy-friendly alternative to third-party CAPTCHAs. Before a new word is submitted, the
+//! browser fetches a random challenge and searches for a nonce such that the SHA-256 hash of
+//! `<challenge>:<nonce>` starts with a configured number of zero bits. This is cheap for someone
+//! submitting a word by hand, but adds up for anyone submitting in bulk. Each challenge can only be
+//! used once.
+
+use crate::auth::random_string_token;
+use crate::config::SubmissionChallenge;
+use crate::serialization::FormFields;
+use crate::DebugBoxedExt;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use sha2::Digest;
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument};
+use warp::http::StatusCode;
+use warp::{reply, Filter, Rejection, Reply};
+
+/// The form field holding the challenge which was solved
+pub const CHALLENGE_FIELD: &str = "pow_challenge";
+/// The form field holding the nonce which solves the challenge
+pub const NONCE_FIELD: &str = "pow_nonce";
+
+const CHALLENGE_LIFETIME: Duration = Duration::from_secs(10 * 60);
+/// Stops the unsolved challenges from using up unbounded memory
+const MAX_OUTSTANDING_CHALLENGES: usize = 10_000;
+
+lazy_static! {
+    static ref CHALLENGES: DashMap<String, Instant> = DashMap::new();
+}
+
+#[derive(Serialize)]
+struct Challenge {
+    challenge: String,
+    difficulty_bits: u32,
+}
+
+pub fn proof_of_work(
+    config: SubmissionChallenge,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    tokio::task::spawn(sweep_expired_challenges());
+
+    warp::get()
+        .and(warp::path!("proof-of-work" / "challenge"))
+        .and(warp::path::end())
+        .and_then(move || issue_challenge(config))
+        .debug_boxed()
+}
+
+async fn sweep_expired_challenges() {
+    loop {
+        tokio::time::sleep(CHALLENGE_LIFETIME).await;
+        CHALLENGES.retain(|_challenge, issued| issued.elapsed() < CHALLENGE_LIFETIME);
+    }
+}
+
+/// Replies with `null` if no challenge needs to be solved
+async fn issue_challenge(config: SubmissionChallenge) -> Result<reply::Response, Infallible> {
+    let difficulty_bits = match config {
+        SubmissionChallenge::None => return Ok(reply::json(&None::<Challenge>).into_response()),
+        SubmissionChallenge::ProofOfWork { difficulty_bits } => difficulty_bits,
+    };
+
+    if CHALLENGES.len() >= MAX_OUTSTANDING_CHALLENGES {
+        return Ok(StatusCode::TOO_MANY_REQUESTS.into_response());
+    }
+
+    let challenge = random_string_token();
+    CHALLENGES.insert(challenge.clone(), Instant::now());
+
+    Ok(reply::json(&Some(Challenge {
+        challenge,
+        difficulty_bits,
+    }))
+    .into_response())
+}
+
+/// Checks the solution to the challenge in the submitted form, if one is required. The challenge
+/// is used up either way.
+#[instrument(name = "Verify proof of work", skip(fields))]
+pub fn verify(config: SubmissionChallenge, fields: &FormFields) -> bool {
+    let difficulty_bits = match config {
+        SubmissionChallenge::None => return true,
+        SubmissionChallenge::ProofOfWork { difficulty_bits } => difficulty_bits,
+    };
+
+    let (Some(challenge), Some(nonce)) = (fields.get(CHALLENGE_FIELD), fields.get(NONCE_FIELD))
+    else {
+        debug!("No solution to the challenge given");
+        return false;
+    };
+
+    match CHALLENGES.remove(challenge) {
+        Some((_, issued)) if issued.elapsed() < CHALLENGE_LIFETIME => {}
+        _ => {
+            debug!("Unknown or expired challenge");
+            return false;
+        }
+    }
+
+    let hash = sha2::Sha256::digest(format!("{challenge}:{nonce}"));
+    leading_zero_bits(&hash) >= difficulty_bits
+}
+
+fn leading_zero_bits(bytes: &[u8]) -> u32 {
+    let mut bits = 0;
+
+    for byte in bytes {
+        bits += byte.leading_zeros();
+
+        if *byte != 0 {
+            break;
+        }
+    }
+
+    bits
+}
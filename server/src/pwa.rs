@@ -0,0 +1,214 @@
+//! Snapshots of the dictionary for offline lookup in the installed web app. A bundle holds the most
+//! viewed words along with every word from the datasets the app asks for, and is compressed on the
+//! way out like any other response.
+//!
+//! Bundles are versioned by a hash of the state of the words table and the bundle's contents. The
+//! app fetches `/pwa/manifest.json` to learn the current version, and then fetches the versioned
+//! bundle URL given in it. Responses for the current version's URL may be cached forever, so the
+//! app only needs to download a new bundle once the manifest gives a new version.
+
+use crate::database::offline_bundle::{
+    fetch_in_datasets, fetch_most_viewed, fetch_words_state, WordsState,
+};
+use crate::error::ServerError;
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use chrono::{DateTime, Utc};
+use isixhosa_common::database::{with_public_db, DbBase, PublicAccessDb};
+use isixhosa_common::types::{Dataset, WordHit};
+use serde::{Deserialize, Serialize};
+use serde_with::formats::CommaSeparator;
+use serde_with::{serde_as, StringWithSeparator};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use tracing::instrument;
+use warp::http::header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use warp::http::{HeaderValue, StatusCode};
+use warp::hyper::Body;
+use warp::reply::Response;
+use warp::{path, Filter, Rejection, Reply};
+
+/// Versioned bundles never change, so they can be cached for as long as the browser likes
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+#[serde_as]
+#[derive(Deserialize, Clone, Debug)]
+struct BundleQuery {
+    /// The IDs of the datasets whose words should be included in full, separated by commas
+    #[serde_as(as = "StringWithSeparator::<CommaSeparator, u64>")]
+    #[serde(default)]
+    datasets: Vec<u64>,
+    /// The version the app expects, as given in the manifest
+    version: Option<String>,
+}
+
+impl BundleQuery {
+    fn normalised_datasets(&self) -> Vec<u64> {
+        let mut datasets = self.datasets.clone();
+        datasets.sort_unstable();
+        datasets.dedup();
+        datasets
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct BundleManifest {
+    version: String,
+    /// The URL of the bundle at this version
+    bundle_url: String,
+    /// The datasets which can be asked for
+    datasets: Vec<Dataset>,
+}
+
+#[derive(Serialize, Debug)]
+struct Bundle {
+    version: String,
+    generated: DateTime<Utc>,
+    words: Vec<WordHit>,
+}
+
+/// What goes into each bundle, which is the same for every user of the site
+#[derive(Copy, Clone, Debug)]
+struct BundleSettings {
+    /// How many of the most viewed words to include
+    words: usize,
+    /// Whether offensive words are left out, as they are from search results
+    school_mode: bool,
+}
+
+pub fn pwa(
+    db: DbBase,
+    bundle_words: usize,
+    school_mode: bool,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let settings = BundleSettings {
+        words: bundle_words,
+        school_mode,
+    };
+    let with_settings = warp::any().map(move || settings);
+
+    let manifest = warp::path("manifest.json")
+        .and(path::end())
+        .and(warp::query())
+        .and(with_settings)
+        .and(with_public_db(db.clone()))
+        .and_then(bundle_manifest);
+
+    let bundle = warp::path("bundle")
+        .and(path::end())
+        .and(warp::query())
+        .and(warp::header::optional::<String>(IF_NONE_MATCH.as_str()))
+        .and(with_settings)
+        .and(with_public_db(db))
+        .and_then(serve_bundle);
+
+    warp::get()
+        .and(warp::path("pwa"))
+        .and(manifest.or(bundle))
+        .debug_boxed()
+}
+
+fn bundle_version(state: WordsState, settings: BundleSettings, datasets: &[u64]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{state:?} {settings:?} {datasets:?}"));
+    let hash = format!("{:x}", hasher.finalize());
+    hash[..16].to_owned()
+}
+
+fn bundle_url(version: &str, datasets: &[u64]) -> String {
+    let datasets = datasets
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!("/pwa/bundle?datasets={datasets}&version={version}")
+}
+
+#[instrument(name = "Fetch offline bundle manifest", skip(db))]
+async fn bundle_manifest(
+    query: BundleQuery,
+    settings: BundleSettings,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let (state, all_datasets) =
+        spawn_blocking_child(move || (fetch_words_state(&db), Dataset::fetch_all(&db)))
+            .await
+            .map_err(ServerError::from)?;
+
+    let datasets = query.normalised_datasets();
+    let version = bundle_version(state, settings, &datasets);
+
+    let manifest = BundleManifest {
+        bundle_url: bundle_url(&version, &datasets),
+        version,
+        datasets: all_datasets,
+    };
+
+    Ok(warp::reply::with_header(
+        warp::reply::json(&manifest),
+        CACHE_CONTROL,
+        "no-cache",
+    ))
+}
+
+#[instrument(name = "Fetch offline bundle", skip(if_none_match, db))]
+async fn serve_bundle(
+    query: BundleQuery,
+    if_none_match: Option<String>,
+    settings: BundleSettings,
+    db: impl PublicAccessDb,
+) -> Result<Response, Rejection> {
+    let datasets = query.normalised_datasets();
+    let db_clone = db.clone();
+    let state = spawn_blocking_child(move || fetch_words_state(&db_clone))
+        .await
+        .map_err(ServerError::from)?;
+
+    let version = bundle_version(state, settings, &datasets);
+    let etag = format!("\"{version}\"");
+
+    // Only the URL of the current version may be cached forever, since older URLs now serve it too
+    let cache_control = if query.version.as_deref() == Some(version.as_str()) {
+        IMMUTABLE
+    } else {
+        "no-cache"
+    };
+
+    let builder = Response::builder()
+        .header(ETAG, HeaderValue::from_str(&etag).unwrap())
+        .header(CACHE_CONTROL, cache_control);
+
+    if if_none_match.as_deref() == Some(etag.as_str()) {
+        return Ok(builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let words = spawn_blocking_child(move || {
+        let include_offensive = !settings.school_mode;
+        let mut words = fetch_most_viewed(&db, settings.words, include_offensive);
+        let mut included: HashSet<u64> = words.iter().map(|hit| hit.id).collect();
+
+        let from_datasets = fetch_in_datasets(&db, &datasets, include_offensive);
+        words.extend(
+            from_datasets
+                .into_iter()
+                .filter(|hit| included.insert(hit.id)),
+        );
+        words
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    let bundle = Bundle {
+        version,
+        generated: Utc::now(),
+        words,
+    };
+
+    Ok(builder
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&bundle).unwrap()))
+        .unwrap())
+}
@@ -0,0 +1,110 @@
+//! Pushes changes to the moderation queue to open moderation pages as server-sent events, so that
+//! moderators can see when new suggestions come in or someone else has already dealt with some,
+//! without reviewing a stale page. The queue is polled rather than every change being reported,
+//! since a slight delay doesn't matter here.
+
+use crate::database::pending::{fetch_queue_state, QueueState};
+use crate::i18n::I18nInfo;
+use crate::spawn_blocking_child;
+use futures::{stream, Stream};
+use isixhosa_common::database::db_impl::DbImpl;
+use isixhosa_common::database::DbBase;
+use isixhosa_common::i18n::TranslationKey;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use warp::sse::Event;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+pub struct QueueWatcher {
+    state: watch::Sender<QueueState>,
+}
+
+impl QueueWatcher {
+    pub fn start(db: DbBase) -> Arc<QueueWatcher> {
+        let (state, _) = watch::channel(QueueState::default());
+        let watcher = Arc::new(QueueWatcher { state });
+        let db = DbImpl(db.primary);
+
+        let watcher_clone = watcher.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+
+                // Only look at the queue while a moderation page is open
+                if watcher_clone.state.receiver_count() == 0 {
+                    continue;
+                }
+
+                let db = db.clone();
+                let current = spawn_blocking_child(move || fetch_queue_state(&db))
+                    .await
+                    .unwrap();
+
+                watcher_clone.state.send_if_modified(|state| {
+                    let changed = *state != current;
+                    *state = current;
+                    changed
+                });
+            }
+        });
+
+        watcher
+    }
+}
+
+/// What the moderation page showed when it was loaded
+#[derive(Deserialize, Copy, Clone, Debug)]
+pub struct QueueEventsQuery {
+    /// The newest suggested word in the main queue
+    since: u64,
+    /// The number of suggestions of any kind in the main queue
+    pending: u64,
+}
+
+#[derive(Serialize, Debug)]
+struct QueueChanged {
+    new: usize,
+    /// What to show on the page, if it is out of date
+    message: Option<String>,
+}
+
+impl QueueChanged {
+    fn new(state: &QueueState, query: QueueEventsQuery, i18n_info: &I18nInfo) -> QueueChanged {
+        let new = state.new_since(query.since);
+        let message = if new > 0 {
+            Some(i18n_info.translate_with(
+                &TranslationKey::new("moderation.new-suggestions"),
+                &crate::i18n_args!("count" => new),
+            ))
+        } else if state.pending != query.pending {
+            Some(i18n_info.translate(&TranslationKey::new("moderation.queue-changed")))
+        } else {
+            None
+        };
+
+        QueueChanged { new, message }
+    }
+}
+
+/// A stream of `queue` events, sent whenever the queue changes while the page is open
+pub fn queue_events(
+    watcher: &QueueWatcher,
+    query: QueueEventsQuery,
+    i18n_info: I18nInfo,
+) -> impl Stream<Item = Result<Event, warp::Error>> + Send + 'static {
+    let receiver = watcher.state.subscribe();
+
+    stream::unfold(receiver, move |mut receiver| {
+        let i18n_info = i18n_info.clone();
+        async move {
+            receiver.changed().await.ok()?;
+            let changed = QueueChanged::new(&receiver.borrow_and_update(), query, &i18n_info);
+            let event = Event::default().event("queue").json_data(changed);
+            Some((event, receiver))
+        }
+    })
+}
@@ -0,0 +1,131 @@
+use crate::auth::{with_moderator_auth, FullUser};
+use crate::config::BodyLimits;
+use crate::csrf::{csrf_protected, csrf_protected_form};
+use crate::database::references::{
+    add_reference, delete_reference, fetch_broken_links, BrokenLink, NewReference,
+};
+use crate::error::{DbError, ServerError, WordNotFound};
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, ModeratorAccessDb};
+use isixhosa_common::types::ExistingWord;
+use std::sync::Arc;
+use tracing::{instrument, warn};
+use warp::http::Uri;
+use warp::{body, Filter, Rejection, Reply};
+
+pub fn references(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    body_limits: BodyLimits,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let add = warp::post()
+        .and(warp::path![u64 / "references"])
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(csrf_protected_form())
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .and_then(add_reference_reply);
+
+    let delete = warp::post()
+        .and(warp::path![u64 / "references" / u64 / "delete"])
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(csrf_protected())
+        .and(with_moderator_auth(db.clone(), site_ctx.clone()))
+        .and_then(delete_reference_reply);
+
+    let broken_links = warp::get()
+        .and(warp::path!["moderation" / "broken_links"])
+        .and(warp::path::end())
+        .and(with_moderator_auth(db, site_ctx))
+        .and_then(broken_links_page);
+
+    warp::path("word")
+        .and(add.or(delete))
+        .or(broken_links)
+        .debug_boxed()
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "moderation_broken_links.askama.html")]
+struct BrokenLinksTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    links: Vec<BrokenLink>,
+}
+
+fn redirect_to_references(word_id: u64) -> impl Reply {
+    warp::redirect::see_other(
+        format!("/word/{word_id}#references")
+            .parse::<Uri>()
+            .unwrap(),
+    )
+}
+
+#[instrument(name = "Submit add reference form", skip(_user, _i18n_info, db))]
+async fn add_reference_reply(
+    word_id: u64,
+    reference: NewReference,
+    _user: FullUser,
+    _i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let added = spawn_blocking_child(move || {
+        if ExistingWord::fetch_alone(&db, word_id).is_none() {
+            return Ok(None);
+        }
+
+        if reference.is_valid() {
+            add_reference(&db, word_id, reference)?;
+        } else {
+            warn!("Invalid reference submitted");
+        }
+
+        Ok::<_, DbError>(Some(()))
+    })
+    .await
+    .map_err(ServerError::from)?
+    .map_err(ServerError::from)?;
+
+    match added {
+        Some(()) => Ok(redirect_to_references(word_id)),
+        None => Err(warp::reject::custom(WordNotFound(word_id))),
+    }
+}
+
+#[instrument(name = "Submit delete reference form", skip(_user, _i18n_info, db))]
+async fn delete_reference_reply(
+    word_id: u64,
+    reference_id: u64,
+    _user: FullUser,
+    _i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    spawn_blocking_child(move || delete_reference(&db, word_id, reference_id))
+        .await
+        .map_err(ServerError::from)?
+        .map_err(ServerError::from)?;
+
+    Ok(redirect_to_references(word_id))
+}
+
+#[instrument(name = "Show broken links", skip_all)]
+async fn broken_links_page(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl ModeratorAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let links = spawn_blocking_child(move || fetch_broken_links(&db))
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(BrokenLinksTemplate {
+        auth: user.into(),
+        i18n_info,
+        links,
+    })
+}
@@ -0,0 +1,135 @@
+//! The daily summary of the oldest pending suggestions which is sent to moderators, so that
+//! suggestions don't sit in the queue unnoticed.
+
+use crate::Config;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use fallible_iterator::FallibleIterator;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use rusqlite::{params, Connection};
+use std::fmt::Write;
+use tracing::{info, instrument, warn};
+
+struct PendingSuggestion {
+    suggestion_id: u64,
+    xhosa: String,
+    english: String,
+    submitted: DateTime<Utc>,
+}
+
+#[instrument(name = "Send pending suggestion summary", skip_all)]
+pub fn send_pending_summary(cfg: &Config, conn: &Connection) -> Result<()> {
+    let pending = fetch_oldest_pending(conn, cfg.moderation_summary_size)?;
+
+    if pending.is_empty() {
+        info!("No pending suggestions, so no summary was sent");
+        return Ok(());
+    }
+
+    let overdue = pending
+        .iter()
+        .filter(|s| (Utc::now() - s.submitted).num_days() >= cfg.moderation_overdue_days as i64)
+        .count();
+    let subject = format!("{} pending suggestions ({overdue} overdue)", pending.len());
+    let body = summary_body(cfg, &pending);
+
+    let Some(smtp) = &cfg.smtp else {
+        info!("No mail server configured, so the summary was not sent:\n{body}");
+        return Ok(());
+    };
+
+    let mailer = SmtpTransport::relay(&smtp.server)?
+        .credentials(Credentials::new(
+            smtp.username.clone(),
+            smtp.password.clone(),
+        ))
+        .build();
+    let from: Mailbox = smtp.from.parse()?;
+
+    for to in fetch_moderator_emails(conn)? {
+        let to: Mailbox = match to.parse() {
+            Ok(to) => to,
+            Err(e) => {
+                warn!("Invalid moderator email {to}: {e}");
+                continue;
+            }
+        };
+
+        let message = Message::builder()
+            .from(from.clone())
+            .to(to)
+            .subject(&subject)
+            .body(body.clone())?;
+
+        mailer.send(&message)?;
+    }
+
+    Ok(())
+}
+
+fn summary_body(cfg: &Config, pending: &[PendingSuggestion]) -> String {
+    let mut body = String::from("The oldest suggestions waiting for review are:\n\n");
+
+    for s in pending {
+        let days = (Utc::now() - s.submitted).num_days();
+        let overdue = if days >= cfg.moderation_overdue_days as i64 {
+            " (overdue)"
+        } else {
+            ""
+        };
+
+        writeln!(
+            body,
+            "- {} - {}, pending for {days} days{overdue}\n  https://{}/moderation/suggestion/{}",
+            s.xhosa, s.english, cfg.host, s.suggestion_id,
+        )
+        .unwrap();
+    }
+
+    body
+}
+
+fn fetch_oldest_pending(conn: &Connection, limit: usize) -> Result<Vec<PendingSuggestion>> {
+    const SELECT: &str = "
+        SELECT
+            word_suggestions.suggestion_id,
+            COALESCE(word_suggestions.xhosa, words.xhosa) AS xhosa,
+            COALESCE(word_suggestions.english, words.english) AS english,
+            word_suggestion_times.submitted
+        FROM word_suggestions
+        INNER JOIN word_suggestion_times
+            ON word_suggestion_times.suggestion_id = word_suggestions.suggestion_id
+        LEFT JOIN words ON words.word_id = word_suggestions.existing_word_id
+        WHERE word_suggestions.suggestion_id NOT IN (SELECT suggestion_id FROM likely_spam_suggestions)
+        ORDER BY word_suggestion_times.submitted ASC
+        LIMIT ?1;
+    ";
+
+    Ok(conn
+        .prepare(SELECT)?
+        .query(params![limit])?
+        .map(|row| {
+            Ok(PendingSuggestion {
+                suggestion_id: row.get("suggestion_id")?,
+                xhosa: row.get("xhosa")?,
+                english: row.get("english")?,
+                submitted: row.get("submitted")?,
+            })
+        })
+        .collect()?)
+}
+
+fn fetch_moderator_emails(conn: &Connection) -> Result<Vec<String>> {
+    const SELECT: &str = "
+        SELECT email FROM users
+            WHERE (is_moderator = 1 OR is_administrator = 1) AND locked = 0;
+    ";
+
+    Ok(conn
+        .prepare(SELECT)?
+        .query(params![])?
+        .map(|row| row.get("email"))
+        .collect()?)
+}
@@ -0,0 +1,99 @@
+//! Hooks for external replication and backup tools. The WAL is checkpointed so that the main
+//! database file is up to date, and then a consistent snapshot of it can be written and handed off.
+
+use crate::config::ReplicationConfig;
+use crate::scheduler::{Schedule, Scheduler};
+use crate::spawn_blocking_child;
+use anyhow::{bail, Result};
+use isixhosa_common::database::db_impl::DbImpl;
+use isixhosa_common::database::{AdministratorAccessDb, DbBase};
+use rusqlite::params;
+use std::process::Command;
+use std::time::Duration;
+use tracing::{info, instrument, Span};
+
+/// The outcome of a successful checkpoint
+#[derive(Copy, Clone, Debug)]
+pub struct Checkpoint {
+    /// The number of frames in the WAL before it was truncated
+    pub wal_frames: i64,
+    /// Whether a snapshot was written after checkpointing
+    pub snapshot_written: bool,
+}
+
+/// Checkpoint the WAL, then write a snapshot and run the snapshot hook if they are configured
+#[instrument(
+    name = "Checkpoint database",
+    fields(wal_frames, snapshot_written),
+    skip_all
+)]
+pub fn checkpoint_and_snapshot(
+    db: &impl AdministratorAccessDb,
+    cfg: &ReplicationConfig,
+) -> Result<Checkpoint> {
+    let conn = db.get()?;
+
+    // Returns (busy, frames in the WAL, frames checkpointed)
+    let (busy, wal_frames, checkpointed): (bool, i64, i64) =
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE);", params![], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+
+    if busy {
+        bail!(
+            "Checkpoint blocked by another connection ({checkpointed}/{wal_frames} frames written)"
+        );
+    }
+
+    Span::current().record("wal_frames", wal_frames);
+
+    let Some(path) = &cfg.snapshot_path else {
+        Span::current().record("snapshot_written", false);
+        return Ok(Checkpoint {
+            wal_frames,
+            snapshot_written: false,
+        });
+    };
+
+    // Write to a temporary file and then move it into place, so that the snapshot path never holds
+    // a half-written database
+    let temp_path = path.with_extension("tmp");
+    let _ = std::fs::remove_file(&temp_path);
+    conn.execute("VACUUM INTO ?1;", params![temp_path.to_string_lossy()])?;
+    std::fs::rename(&temp_path, path)?;
+
+    if let Some(hook) = &cfg.snapshot_hook {
+        let status = Command::new(hook).arg(path).status()?;
+
+        if !status.success() {
+            bail!("Snapshot hook {} exited with {status}", hook.display());
+        }
+    }
+
+    Span::current().record("snapshot_written", true);
+
+    Ok(Checkpoint {
+        wal_frames,
+        snapshot_written: true,
+    })
+}
+
+/// Checkpoint periodically, if an interval is configured
+pub fn register(scheduler: &mut Scheduler, db: DbBase, cfg: ReplicationConfig) {
+    let Some(mins) = cfg.checkpoint_interval_mins else {
+        return;
+    };
+
+    let db = DbImpl(db.primary);
+    let schedule = Schedule::Every(Duration::from_secs(mins * 60));
+
+    scheduler.register("database-checkpoint", schedule, move || {
+        let (db, cfg) = (db.clone(), cfg.clone());
+        async move {
+            let checkpoint =
+                spawn_blocking_child(move || checkpoint_and_snapshot(&db, &cfg)).await??;
+            info!(?checkpoint, "Scheduled checkpoint complete");
+            Ok(())
+        }
+    });
+}
@@ -0,0 +1,170 @@
+//! A small in-process scheduler for periodic jobs such as checkpoints and digests. Jobs are
+//! registered before the scheduler is started, and a job never runs twice at the same time - if it
+//! is still running when it is next due, that run is skipped.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveTime, Utc};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// When a job runs
+#[derive(Copy, Clone, Debug)]
+pub enum Schedule {
+    /// Every so often, starting one period after startup
+    Every(Duration),
+    /// Once a day at the given time (UTC)
+    Daily(NaiveTime),
+}
+
+impl Schedule {
+    fn next_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Schedule::Every(period) => now + chrono::Duration::from_std(*period).unwrap(),
+            Schedule::Daily(time) => {
+                let today = now.date_naive().and_time(*time).and_utc();
+
+                if today > now {
+                    today
+                } else {
+                    today + chrono::Duration::days(1)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct JobStatus {
+    pub running: bool,
+    pub last_started: Option<DateTime<Utc>>,
+    pub last_finished: Option<DateTime<Utc>>,
+    /// The error the last run failed with, if it did
+    pub last_error: Option<String>,
+    pub next_run: Option<DateTime<Utc>>,
+    /// The number of runs skipped because the job was still running
+    pub skipped: u64,
+}
+
+/// A registered job and its status, as shown on the jobs admin page
+pub struct JobInfo {
+    pub name: &'static str,
+    pub schedule: Schedule,
+    pub status: JobStatus,
+}
+
+struct Job {
+    name: &'static str,
+    schedule: Schedule,
+    run: Box<dyn Fn() -> JobFuture + Send + Sync>,
+    status: Mutex<JobStatus>,
+}
+
+impl Job {
+    /// Mark the job as running, unless it already is
+    fn try_begin(&self) -> bool {
+        let mut status = self.status.lock().unwrap();
+
+        if status.running {
+            status.skipped += 1;
+            false
+        } else {
+            status.running = true;
+            status.last_started = Some(Utc::now());
+            true
+        }
+    }
+
+    /// Run the job, which must have been begun with [`Job::try_begin`]
+    async fn run_to_completion(&self) {
+        let result = (self.run)().await;
+
+        match &result {
+            Ok(()) => info!(job = self.name, "Job complete"),
+            Err(error) => error!(job = self.name, ?error, "Job failed"),
+        }
+
+        let mut status = self.status.lock().unwrap();
+        status.running = false;
+        status.last_finished = Some(Utc::now());
+        status.last_error = result.err().map(|e| format!("{e:#}"));
+    }
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<Arc<Job>>,
+}
+
+impl Scheduler {
+    pub fn register<F, Fut>(&mut self, name: &'static str, schedule: Schedule, run: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.jobs.push(Arc::new(Job {
+            name,
+            schedule,
+            run: Box::new(move || Box::pin(run())),
+            status: Mutex::new(JobStatus::default()),
+        }));
+    }
+
+    /// Start running every registered job on its schedule
+    pub fn start(self) -> Arc<Scheduler> {
+        for job in &self.jobs {
+            tokio::task::spawn(run_on_schedule(job.clone()));
+        }
+
+        Arc::new(self)
+    }
+
+    pub fn jobs(&self) -> Vec<JobInfo> {
+        self.jobs
+            .iter()
+            .map(|job| JobInfo {
+                name: job.name,
+                schedule: job.schedule,
+                status: job.status.lock().unwrap().clone(),
+            })
+            .collect()
+    }
+
+    /// Run a job now rather than waiting for it to be due. Returns false if there is no such job or
+    /// it is already running.
+    pub fn run_now(&self, name: &str) -> bool {
+        let Some(job) = self.jobs.iter().find(|job| job.name == name).cloned() else {
+            return false;
+        };
+
+        if !job.try_begin() {
+            return false;
+        }
+
+        tokio::task::spawn(async move { job.run_to_completion().await });
+        true
+    }
+}
+
+async fn run_on_schedule(job: Arc<Job>) {
+    loop {
+        let next = job.schedule.next_after(Utc::now());
+        job.status.lock().unwrap().next_run = Some(next);
+
+        let wait = (next - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+
+        if job.try_begin() {
+            job.run_to_completion().await;
+        } else {
+            warn!(
+                job = job.name,
+                "Skipped run since the previous one is still going"
+            );
+        }
+    }
+}
@@ -1,52 +1,152 @@
+use crate::config::SearchReloadPolicy;
+use crate::error::SearchError;
+use crate::fragment_cache::{Fragment, FragmentCache};
 use crate::i18n::{FromWithI18n, I18nInfo};
-use crate::spawn_blocking_child;
+use crate::{spawn_blocking_child, Config};
 use anyhow::{Context, Result};
 use askama::Template;
+use dashmap::DashMap;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use isixhosa::noun::NounClass;
-use isixhosa_common::database::{GetWithSentinelExt, WordOrSuggestionId};
+use isixhosa_common::database::{GetWithSentinelExt, PublicAccessDb, WordOrSuggestionId};
 use isixhosa_common::format::DisplayHtml;
-use isixhosa_common::language::{NounClassExt, PartOfSpeech, Transitivity};
+use isixhosa_common::language::{NguniLanguage, NounClassExt, PartOfSpeech, Transitivity};
+use isixhosa_common::normalize;
+use isixhosa_common::search_direction::SearchDirection;
 use isixhosa_common::templates::AllWordsList;
-use isixhosa_common::types::WordHit;
+use isixhosa_common::types::{ExistingLinkedWord, WordHit};
 use num_enum::TryFromPrimitive;
 use ordered_float::OrderedFloat;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension, Row};
 use serde::Serialize;
 use std::cmp::{max, Ordering};
 use std::collections::HashSet;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::{Debug, Formatter};
+use std::io::{self, Read, Write};
 use std::marker::PhantomData;
 use std::num::NonZeroU64;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
-use tantivy::collector::{DocSetCollector, TopDocs};
+use std::time::{Duration, Instant};
+use tantivy::collector::{Count, DocSetCollector, TopDocs};
 use tantivy::directory::MmapDirectory;
-use tantivy::query::{AllQuery, BooleanQuery, FuzzyTermQuery, Query, TermQuery};
+use tantivy::query::{
+    AllQuery, BooleanQuery, BoostQuery, FuzzyTermQuery, Occur, Query, RegexQuery, TermQuery,
+};
 use tantivy::schema::{
     Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, INDEXED, STORED,
 };
 use tantivy::tokenizer::TextAnalyzer;
-use tantivy::tokenizer::{LowerCaser, SimpleTokenizer};
+use tantivy::tokenizer::{SimpleTokenizer, Token, TokenFilter, TokenStream, Tokenizer};
 use tantivy::{doc, Searcher};
-use tantivy::{Index, IndexReader, IndexWriter, TantivyDocument, Term};
-use tokio::sync::RwLock;
-use tracing::{debug_span, info, info_span, instrument, Span};
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+use tempdir::TempDir;
+use tracing::{debug_span, info, info_span, instrument, warn, Span};
+use utoipa::ToSchema;
 use xtra::prelude::*;
 
-const TANTIVY_WRITER_HEAP: usize = 128 * 1024 * 1024;
 const RESULTS: usize = 10;
+const PATTERN_RESULTS: usize = 100;
+/// How many documents matching a pattern's regex are loaded. The regex matches single tokens, so
+/// some of these are dropped once the whole word is checked against the pattern.
+const PATTERN_CANDIDATES: usize = PATTERN_RESULTS * 5;
+
+/// Stored as the payload of every commit, so that an index written with an older schema can be
+/// detected and rebuilt. Bump this whenever the schema or the way documents are indexed changes.
+const SCHEMA_VERSION: &str = "2";
+
+/// The file which lists the committed segments of the index
+const META_FILE: &str = "meta.json";
 
 pub struct TantivyClient {
     schema_info: SchemaInfo,
     tokenizer: TextAnalyzer,
     writer: Address<WriterActor>,
     searchers: Address<SearcherActor>,
-    /// Just the list portion of the 'all words' template (common/templates/all.askama.html)
-    all_words_html_cache: RwLock<Option<String>>,
+    /// Holds the list portion of the 'all words' template (common/templates/all.askama.html)
+    fragments: Arc<FragmentCache>,
+    search_cache: SearchCache,
+}
+
+/// Results of recent searches for accepted words only, since these are shared between all users
+/// and a few queries make up most searches. Results are only valid for the searcher generation
+/// that produced them, so any change to the index invalidates them. Results are ranked differently
+/// for each search direction, so they are cached separately for each. School mode leaves words
+/// out, so its results are cached separately too.
+struct SearchCache {
+    entries: DashMap<SearchCacheKey, CachedSearch>,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+#[derive(Hash, PartialEq, Eq)]
+struct SearchCacheKey {
+    query: String,
+    duplicate: bool,
+    direction: SearchDirection,
+    school_mode: bool,
+    limit: usize,
+}
+
+impl SearchCacheKey {
+    fn new(req: &SearchRequest<WordHit>) -> SearchCacheKey {
+        SearchCacheKey {
+            query: req.query.clone(),
+            duplicate: req.duplicate,
+            direction: req.i18n.search_direction,
+            school_mode: req.i18n.school_mode,
+            limit: req.limit,
+        }
+    }
+}
+
+struct CachedSearch {
+    generation: u64,
+    inserted: Instant,
+    hits: Vec<WordHit>,
+}
+
+impl SearchCache {
+    fn get(&self, key: &SearchCacheKey, generation: u64) -> Option<Vec<WordHit>> {
+        let entry = self.entries.get(key)?;
+
+        if entry.generation == generation && entry.inserted.elapsed() < self.ttl {
+            Some(entry.hits.clone())
+        } else {
+            None
+        }
+    }
+
+    fn insert(&self, key: SearchCacheKey, generation: u64, hits: &[WordHit]) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        if self.entries.len() >= self.max_entries {
+            self.entries.retain(|_, cached| {
+                cached.generation == generation && cached.inserted.elapsed() < self.ttl
+            });
+
+            // Keep the queries which were cached first rather than churning through entries
+            if self.entries.len() >= self.max_entries {
+                return;
+            }
+        }
+
+        self.entries.insert(
+            key,
+            CachedSearch {
+                generation,
+                inserted: Instant::now(),
+                hits: hits.to_vec(),
+            },
+        );
+    }
 }
 
 impl Debug for TantivyClient {
@@ -57,28 +157,55 @@ impl Debug for TantivyClient {
 
 impl TantivyClient {
     pub async fn start(
-        path: &Path,
+        cfg: &Config,
         db: Pool<SqliteConnectionManager>,
+        fragments: Arc<FragmentCache>,
     ) -> Result<Arc<TantivyClient>> {
+        let path = cfg.tantivy_path.as_path();
         let schema_info = Self::build_schema();
-        let dir = MmapDirectory::open(path)
-            .with_context(|| format!("Failed to open tantivy directory {:?}", path))?;
-        let reindex = !Index::exists(&dir)?;
+        let open_dir = || {
+            MmapDirectory::open(path)
+                .with_context(|| format!("Failed to open tantivy directory {:?}", path))
+        };
+        let mut dir = open_dir()?;
+        let mut reindex = !Index::exists(&dir)?;
+
+        if !reindex && !Self::schema_up_to_date(&dir, &schema_info.schema) {
+            info!("Tantivy index schema is out of date; deleting the index so it can be rebuilt");
+            Self::clear_index_dir(path)?;
+            dir = open_dir()?;
+            reindex = true;
+        }
+
         let index = Index::open_or_create(dir, schema_info.schema.clone())?;
 
-        let lowercaser = TextAnalyzer::builder(SimpleTokenizer::default())
-            .filter(LowerCaser)
+        let folding = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(FoldingFilter)
             .build();
-        index.tokenizers().register("lowercaser", lowercaser);
+        index.tokenizers().register("folding", folding);
 
-        let num_searchers = num_cpus::get();
-        let reader = index.reader_builder().try_into()?;
+        let num_searchers = cfg.tantivy_searchers.unwrap_or_else(num_cpus::get).max(1);
+        let reload_policy = match cfg.tantivy_reload_policy {
+            SearchReloadPolicy::OnCommit => ReloadPolicy::OnCommitWithDelay,
+            SearchReloadPolicy::Immediate => ReloadPolicy::Manual,
+        };
+        let reader = index
+            .reader_builder()
+            .reload_policy(reload_policy)
+            .try_into()?;
 
         let (searchers, mailbox) = Mailbox::bounded(32);
 
-        let writer = index.writer_with_num_threads(1, TANTIVY_WRITER_HEAP)?;
+        let writer = index.writer_with_num_threads(1, cfg.tantivy_writer_heap_bytes)?;
         let tokenizer = index.tokenizer_for_field(schema_info.english).unwrap();
-        let writer = WriterActor::new(writer, schema_info.clone());
+        let reload_after_commit =
+            (cfg.tantivy_reload_policy == SearchReloadPolicy::Immediate).then(|| reader.clone());
+        let writer = WriterActor::new(
+            writer,
+            schema_info.clone(),
+            reload_after_commit,
+            cfg.language.rules,
+        );
         let writer = xtra::spawn_tokio(writer, Mailbox::bounded(16));
 
         let client = TantivyClient {
@@ -86,7 +213,12 @@ impl TantivyClient {
             tokenizer,
             writer,
             searchers: searchers.clone(),
-            all_words_html_cache: RwLock::new(None),
+            fragments,
+            search_cache: SearchCache {
+                entries: DashMap::new(),
+                ttl: Duration::from_secs(cfg.search_cache_ttl_secs),
+                max_entries: cfg.search_cache_size,
+            },
         };
         let client = Arc::new(client);
 
@@ -105,14 +237,198 @@ impl TantivyClient {
             );
         }
 
+        // Any reindexing has been committed by now, so the whole index is warmed
+        let now = Instant::now();
+        Self::warm_up(&reader, &client.schema_info)?;
+        info!(
+            "Search index warmed up in {:.2}ms",
+            now.elapsed().as_secs_f64() * 1_000.0
+        );
+
         Ok(client)
     }
 
+    /// Load the parts of the index which searches use, so that the first search after startup
+    /// doesn't have to page them in from disk
+    fn warm_up(reader: &IndexReader, schema_info: &SchemaInfo) -> Result<()> {
+        reader.reload()?;
+        let searcher = reader.searcher();
+
+        for segment in searcher.segment_readers() {
+            for field in [
+                schema_info.english,
+                schema_info.xhosa,
+                schema_info.xhosa_stemmed,
+                schema_info.existing_id,
+                schema_info.suggestion_id,
+                schema_info.suggesting_user,
+            ] {
+                segment.inverted_index(field)?;
+            }
+
+            segment.get_store_reader(1)?;
+        }
+
+        searcher.search(&AllQuery, &Count)?;
+
+        Ok(())
+    }
+
+    /// Whether the existing index was written with the current schema version and schema
+    fn schema_up_to_date(dir: &MmapDirectory, schema: &Schema) -> bool {
+        let index = match Index::open(dir.clone()) {
+            Ok(index) => index,
+            Err(e) => {
+                warn!("Failed to open existing tantivy index: {e}");
+                return false;
+            }
+        };
+
+        let version = match index.load_metas() {
+            Ok(metas) => metas.payload,
+            Err(e) => {
+                warn!("Failed to load tantivy index metadata: {e}");
+                return false;
+            }
+        };
+
+        if version.as_deref() != Some(SCHEMA_VERSION) {
+            info!(
+                "Tantivy index has schema version {:?}, but {SCHEMA_VERSION} is required",
+                version
+            );
+            false
+        } else {
+            // Catch schema changes where the version was not bumped
+            index.schema() == *schema
+        }
+    }
+
+    fn clear_index_dir(path: &Path) -> Result<()> {
+        for entry in std::fs::read_dir(path)? {
+            let entry_path = entry?.path();
+
+            if entry_path.is_dir() {
+                std::fs::remove_dir_all(&entry_path)
+            } else {
+                std::fs::remove_file(&entry_path)
+            }
+            .with_context(|| format!("Failed to delete tantivy index file {:?}", entry_path))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the index as of its last commit to a gzipped tarball, so that another instance can
+    /// import it instead of reindexing its database. Only the files of the committed segments are
+    /// included, so this is safe to do while the server is running.
+    #[instrument(name = "Export search index snapshot", skip(out))]
+    pub fn export_snapshot(path: &Path, out: impl Write) -> Result<()> {
+        const ATTEMPTS: usize = 5;
+
+        let index = Index::open_in_dir(path)
+            .with_context(|| format!("Failed to open tantivy index {:?}", path))?;
+        let mut out = Some(out);
+
+        // A commit or merge may replace the segments while they are being read, in which case the
+        // snapshot is started again from the new metadata
+        for attempt in 1..=ATTEMPTS {
+            let meta = std::fs::read(path.join(META_FILE))?;
+            let segments = index.load_metas()?.segments;
+
+            let mut files = vec![];
+            for segment in &segments {
+                for file in segment.list_files() {
+                    match std::fs::read(path.join(&file)) {
+                        Ok(contents) => files.push((file, contents)),
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => break,
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+            }
+
+            let complete =
+                files.len() == segments.iter().map(|s| s.list_files().len()).sum::<usize>();
+            if !complete || std::fs::read(path.join(META_FILE))? != meta {
+                warn!("Search index changed during snapshot (attempt {attempt}/{ATTEMPTS})");
+                continue;
+            }
+
+            let encoder = GzEncoder::new(out.take().unwrap(), Compression::default());
+            let mut tar = tar::Builder::new(encoder);
+
+            for (name, contents) in files
+                .iter()
+                .map(|(file, contents)| (file.as_path(), contents))
+                .chain([(Path::new(META_FILE), &meta)])
+            {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(contents.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(chrono::Utc::now().timestamp() as u64);
+                tar.append_data(&mut header, name, contents.as_slice())?;
+            }
+
+            tar.into_inner()?.finish()?.flush()?;
+            info!("Exported {} search index segments", segments.len());
+
+            return Ok(());
+        }
+
+        anyhow::bail!("The search index kept changing while it was being exported")
+    }
+
+    /// Replace the index with a snapshot written by [`TantivyClient::export_snapshot`]. The
+    /// snapshot must have been made with the current schema. The server must not be running, since
+    /// it holds the index open.
+    #[instrument(name = "Import search index snapshot", skip(input))]
+    pub fn import_snapshot(path: &Path, input: impl Read) -> Result<()> {
+        std::fs::create_dir_all(path)?;
+
+        if Index::exists(&MmapDirectory::open(path)?)? {
+            // Taking the writer lock fails if the index is open in a running server
+            Index::open_in_dir(path)?
+                .writer::<TantivyDocument>(15_000_000)
+                .context("Failed to lock the search index - is the server still running?")?;
+        }
+
+        // Unpacked next to the index so that the files can be moved into it rather than copied
+        let parent = path.parent().unwrap_or(path);
+        let temp_dir = TempDir::new_in(parent, "isixhosa_click_search_snapshot")?;
+        tar::Archive::new(GzDecoder::new(input))
+            .unpack(temp_dir.path())
+            .context("Failed to unpack search index snapshot")?;
+
+        let unpacked = MmapDirectory::open(temp_dir.path())?;
+        if !Index::exists(&unpacked)? {
+            anyhow::bail!("The snapshot does not contain a search index");
+        }
+
+        if !Self::schema_up_to_date(&unpacked, &Self::build_schema().schema) {
+            anyhow::bail!(
+                "The snapshot was made with a different search index schema, so it can't be \
+                used by this version of the server"
+            );
+        }
+
+        Self::clear_index_dir(path)?;
+
+        for entry in std::fs::read_dir(temp_dir.path())? {
+            let entry = entry?;
+            std::fs::rename(entry.path(), path.join(entry.file_name()))
+                .with_context(|| format!("Failed to move {:?} into the index", entry.path()))?;
+        }
+
+        info!("Imported search index snapshot into {:?}", path);
+
+        Ok(())
+    }
+
     fn build_schema() -> SchemaInfo {
         let mut builder = Schema::builder();
 
         let text_options = TextOptions::default()
-            .set_indexing_options(TextFieldIndexing::default().set_tokenizer("lowercaser"))
+            .set_indexing_options(TextFieldIndexing::default().set_tokenizer("folding"))
             .set_stored();
 
         let english = builder.add_text_field("english", text_options.clone());
@@ -124,6 +440,7 @@ impl TantivyClient {
         let is_inchoative = builder.add_u64_field("is_inchoative", STORED);
         let transitivity = builder.add_u64_field("is_transitive", STORED);
         let noun_class = builder.add_u64_field("noun_class", STORED);
+        let is_offensive = builder.add_u64_field("is_offensive", INDEXED);
         let suggesting_user = builder.add_u64_field("is_suggestion", STORED | INDEXED);
         let existing_id = builder.add_u64_field("existing_id", STORED | INDEXED);
         let suggestion_id = builder.add_u64_field("suggestion_id", STORED | INDEXED);
@@ -139,6 +456,7 @@ impl TantivyClient {
             is_informal,
             transitivity,
             noun_class,
+            is_offensive,
             suggesting_user,
             existing_id,
             suggestion_id,
@@ -160,7 +478,7 @@ impl TantivyClient {
         include: IncludeResults,
         duplicate: bool,
         i18n: I18nInfo,
-    ) -> Result<Vec<Res>>
+    ) -> Result<Vec<Res>, SearchError>
     where
         Res: FromWithI18n<WordHit> + Send + Sync + 'static,
     {
@@ -169,39 +487,69 @@ impl TantivyClient {
                 query,
                 include,
                 duplicate,
+                limit: RESULTS,
                 i18n,
                 _phantom: PhantomData,
             })
             .await
-            .map_err(Into::into)
+            .map_err(SearchError::from)
     }
 
-    pub async fn get_all_words_html(&self, i18n_info: I18nInfo) -> Result<String> {
-        {
-            let all_words = self.all_words_html_cache.read().await;
-
-            if let Some(all_words) = &*all_words {
-                return Ok(all_words.clone());
-            }
-        }
+    /// Search for up to `limit` accepted words, best ranked first, for when more than the usual
+    /// handful of results is needed, e.g to filter them and split them into pages
+    #[instrument(
+        name = "Search for many words",
+        fields(query = %query, limit = limit),
+        skip_all,
+    )]
+    pub async fn search_many(
+        &self,
+        query: String,
+        limit: usize,
+        i18n: I18nInfo,
+    ) -> Result<Vec<WordHit>, SearchError> {
+        self.searchers
+            .send(SearchRequest::<WordHit> {
+                query,
+                include: IncludeResults::AcceptedOnly,
+                duplicate: false,
+                limit,
+                i18n,
+                _phantom: PhantomData,
+            })
+            .await
+            .map_err(SearchError::from)
+    }
 
-        let mut all_words = self.all_words_html_cache.write().await;
-        let words = self.searchers.send(GetAllWords).await?;
-        let rendered = AllWordsList { words, i18n_info }.render()?;
-        *all_words = Some(rendered.clone());
-        Ok(rendered)
+    /// Search for accepted words whose isiXhosa matches a crossword-style pattern. `_` and `?`
+    /// match exactly one letter, and `*` matches any number of letters, e.g `u_k_la`.
+    #[instrument(name = "Search for a pattern", fields(pattern = %pattern), skip_all)]
+    pub async fn search_pattern(&self, pattern: String, i18n: I18nInfo) -> Result<Vec<JsWordHit>> {
+        self.searchers
+            .send(PatternRequest { pattern, i18n })
+            .await
+            .map_err(SearchError::from)?
     }
 
-    async fn invalidate_all_words_cache(&self) {
-        *self.all_words_html_cache.write().await = None;
+    pub async fn get_all_words_html(&self, i18n_info: I18nInfo) -> Result<String> {
+        let lang = i18n_info.user_language.clone();
+        let school_mode = i18n_info.school_mode;
+
+        self.fragments
+            .get_or_render(Fragment::AllWords, &lang, school_mode, || async move {
+                let words = self.searchers.send(GetAllWords { school_mode }).await?;
+                Ok(AllWordsList { words, i18n_info }.render()?)
+            })
+            .await
     }
 
     #[instrument(name = "Reindex the database", skip_all)]
+    #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
     pub async fn reindex_database(&self, db: Pool<SqliteConnectionManager>) {
         const SELECT: &str = "
             SELECT
                 word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal, transitivity,
-                followed_by, noun_class
+                followed_by, noun_class, is_offensive
             FROM words
             ORDER BY word_id;
         ";
@@ -212,49 +560,145 @@ impl TantivyClient {
             let conn = db.get().unwrap();
             let mut stmt = conn.prepare(SELECT).unwrap();
 
-            stmt.query_map(params![], |row| {
-                Ok(WordDocument {
-                    id: WordOrSuggestionId::existing(row.get::<&str, i64>("word_id")? as u64),
-                    english: row.get("english")?,
-                    xhosa: row.get("xhosa")?,
-                    part_of_speech: row.get("part_of_speech")?,
-                    is_plural: row.get("is_plural")?,
-                    is_inchoative: row.get("is_inchoative")?,
-                    transitivity: row.get_with_sentinel("transitivity")?,
-                    suggesting_user: None,
-                    noun_class: row.get_with_sentinel("noun_class")?,
-                    is_informal: row.get("is_informal")?,
-                })
-            })
-            .unwrap()
-            .collect::<Result<Vec<WordDocument>, _>>()
-            .unwrap()
+            stmt.query_map(params![], |row| WordDocument::try_from(row))
+                .unwrap()
+                .collect::<Result<Vec<WordDocument>, _>>()
+                .unwrap()
         })
         .await
         .unwrap();
 
         self.writer.send(ReindexWords(docs)).await.unwrap();
-        self.invalidate_all_words_cache().await;
+        self.fragments.words_changed();
+    }
+
+    /// Reindex the given existing words, e.g after words which they were linked to were deleted
+    #[instrument(name = "Reindex words", fields(words = ids.len()), skip(self, db))]
+    #[allow(clippy::redundant_closure)] // "implementation of FnOnce is not general enough"
+    pub async fn reindex_words(&self, db: impl PublicAccessDb, ids: Vec<u64>) {
+        const SELECT: &str = "
+            SELECT
+                word_id, english, xhosa, part_of_speech, is_plural, is_inchoative, is_informal, transitivity,
+                followed_by, noun_class, is_offensive
+            FROM words
+            WHERE word_id = ?1;
+        ";
+
+        let docs = spawn_blocking_child(move || {
+            let conn = db.get().unwrap();
+            let mut stmt = conn.prepare_cached(SELECT).unwrap();
+
+            ids.into_iter()
+                .filter_map(|id| {
+                    stmt.query_row(params![id], |row| WordDocument::try_from(row))
+                        .optional()
+                        .unwrap()
+                })
+                .collect::<Vec<WordDocument>>()
+        })
+        .await
+        .unwrap();
+
+        for doc in docs {
+            self.edit_word(doc).await;
+        }
     }
 
     pub async fn add_new_word(&self, word: WordDocument) {
-        self.invalidate_all_words_cache().await;
-        self.writer.send(IndexWord(word)).await.unwrap()
+        self.writer.send(IndexWord(word)).await.unwrap();
+        self.fragments.words_changed();
     }
 
     pub async fn edit_word(&self, word: WordDocument) {
-        self.invalidate_all_words_cache().await;
-        self.writer.send(EditWord(word)).await.unwrap()
+        self.writer.send(EditWord(word)).await.unwrap();
+        self.fragments.words_changed();
     }
 
     pub async fn delete_word(&self, id: WordOrSuggestionId) {
-        self.invalidate_all_words_cache().await;
-        self.writer.send(DeleteWord(id)).await.unwrap()
+        self.writer.send(DeleteWord(id)).await.unwrap();
+        self.fragments.words_changed();
+    }
+}
+
+/// Folds each token with [`normalize::fold`], so that the index matches text the same way as the
+/// rest of the server compares it, whatever case, accents, or apostrophes it was written with
+#[derive(Clone)]
+struct FoldingFilter;
+
+impl TokenFilter for FoldingFilter {
+    type Tokenizer<T: Tokenizer> = FoldingFilterWrapper<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> FoldingFilterWrapper<T> {
+        FoldingFilterWrapper(tokenizer)
+    }
+}
+
+#[derive(Clone)]
+struct FoldingFilterWrapper<T>(T);
+
+impl<T: Tokenizer> Tokenizer for FoldingFilterWrapper<T> {
+    type TokenStream<'a> = FoldingTokenStream<T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        FoldingTokenStream(self.0.token_stream(text))
+    }
+}
+
+struct FoldingTokenStream<T>(T);
+
+impl<T: TokenStream> TokenStream for FoldingTokenStream<T> {
+    fn advance(&mut self) -> bool {
+        if !self.0.advance() {
+            return false;
+        }
+
+        let token = self.0.token_mut();
+        token.text = normalize::fold(&token.text);
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.0.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.0.token_mut()
+    }
+}
+
+/// Search results which share a part of speech
+#[derive(Debug)]
+pub struct HitGroup {
+    /// `None` for words whose part of speech isn't known
+    pub part_of_speech: Option<PartOfSpeech>,
+    pub hits: Vec<WordHit>,
+}
+
+impl HitGroup {
+    /// Group the hits by part of speech. Groups are in the order of the first hit in each, and the
+    /// hits in each group keep their order.
+    pub fn group(hits: Vec<WordHit>) -> Vec<HitGroup> {
+        let mut groups: Vec<HitGroup> = Vec::new();
+
+        for hit in hits {
+            match groups
+                .iter_mut()
+                .find(|group| group.part_of_speech == hit.part_of_speech)
+            {
+                Some(group) => group.hits.push(hit),
+                None => groups.push(HitGroup {
+                    part_of_speech: hit.part_of_speech,
+                    hits: vec![hit],
+                }),
+            }
+        }
+
+        groups
     }
 }
 
 /// A search result intended to be passed to the JS frontend
-#[derive(Serialize, Debug, Eq, PartialEq, Hash)]
+#[derive(Serialize, ToSchema, Debug, Eq, PartialEq, Hash)]
 pub struct JsWordHit {
     pub id: u64,
     pub html: String,
@@ -263,6 +707,12 @@ pub struct JsWordHit {
     pub is_suggestion: bool,
     pub english: String,
     pub xhosa: String,
+    /// Used to group results on the search page
+    pub part_of_speech: Option<PartOfSpeech>,
+
+    /// Words linked to this one, e.g its plural, shown below the result. Empty unless added by
+    /// [`JsWordHit::add_linked_words`].
+    pub linked_html: String,
 }
 
 impl FromWithI18n<WordHit> for JsWordHit {
@@ -273,6 +723,33 @@ impl FromWithI18n<WordHit> for JsWordHit {
             is_suggestion: hit.is_suggestion,
             english: hit.english,
             xhosa: hit.xhosa,
+            part_of_speech: hit.part_of_speech,
+            linked_html: String::new(),
+        }
+    }
+}
+
+impl JsWordHit {
+    pub async fn add_linked_words(
+        hits: &mut [JsWordHit],
+        db: impl PublicAccessDb,
+        i18n_info: &I18nInfo,
+    ) {
+        let word_ids: Vec<u64> = hits
+            .iter()
+            .filter(|hit| !hit.is_suggestion)
+            .map(|hit| hit.id)
+            .collect();
+        let mut snippets = spawn_blocking_child(move || {
+            ExistingLinkedWord::fetch_snippets_for_words(&db, &word_ids)
+        })
+        .await
+        .unwrap();
+
+        for hit in hits.iter_mut().filter(|hit| !hit.is_suggestion) {
+            if let Some(linked) = snippets.remove(&hit.id) {
+                hit.linked_html = linked.to_html(i18n_info).to_string();
+            }
         }
     }
 }
@@ -280,19 +757,44 @@ impl FromWithI18n<WordHit> for JsWordHit {
 pub struct WriterActor {
     writer: Arc<Mutex<IndexWriter>>,
     schema_info: Arc<SchemaInfo>,
+    /// Reloaded after every commit if searches should see writes immediately
+    reload_after_commit: Option<IndexReader>,
+    /// How words are stemmed for the site's language
+    rules: NguniLanguage,
 }
 
 impl WriterActor {
-    fn new(writer: IndexWriter, schema_info: SchemaInfo) -> Self {
+    fn new(
+        writer: IndexWriter,
+        schema_info: SchemaInfo,
+        reload_after_commit: Option<IndexReader>,
+        rules: NguniLanguage,
+    ) -> Self {
         WriterActor {
             writer: Arc::new(Mutex::new(writer)),
             schema_info: Arc::new(schema_info),
+            reload_after_commit,
+            rules,
+        }
+    }
+
+    /// Commit, recording the schema version the index was written with
+    fn commit(writer: &mut IndexWriter, reader: &Option<IndexReader>) -> tantivy::Result<u64> {
+        let mut commit = writer.prepare_commit()?;
+        commit.set_payload(SCHEMA_VERSION);
+        let opstamp = commit.commit()?;
+
+        if let Some(reader) = reader {
+            reader.reload()?;
         }
+
+        Ok(opstamp)
     }
 
     fn add_word(
         writer: &mut IndexWriter,
         schema_info: &SchemaInfo,
+        rules: NguniLanguage,
         doc: WordDocument,
     ) -> Result<()> {
         let stemmed = if doc.part_of_speech == Some(PartOfSpeech::Verb) {
@@ -300,8 +802,7 @@ impl WriterActor {
             doc.xhosa.trim_start_matches("(i)").to_owned()
         } else if doc.part_of_speech == Some(PartOfSpeech::Noun) || doc.part_of_speech.is_none() {
             // We just treat it as a noun for now.
-            // TODO(isizulu): better stemming
-            isixhosa::noun::guess_noun_base(&doc.xhosa, doc.noun_class)
+            rules.noun_base(&doc.xhosa, doc.noun_class)
         } else {
             doc.xhosa.to_owned()
         };
@@ -317,6 +818,7 @@ impl WriterActor {
             schema_info.is_informal => doc.is_informal as u64,
             schema_info.transitivity => doc.transitivity.map(|x| x as u64).unwrap_or(255),
             schema_info.noun_class => doc.noun_class.map(|x| x as u64).unwrap_or(255),
+            schema_info.is_offensive => doc.is_offensive as u64,
         );
 
         let (id_field, suggestion) = match doc.id {
@@ -359,16 +861,18 @@ impl Handler<ReindexWords> for WriterActor {
     async fn handle(&mut self, docs: ReindexWords, _ctx: &mut xtra::Context<Self>) {
         let writer = self.writer.clone();
         let schema_info = self.schema_info.clone();
+        let reader = self.reload_after_commit.clone();
+        let rules = self.rules;
 
         spawn_blocking_child(move || {
             let mut writer = writer.lock().unwrap();
             writer.delete_all_documents().unwrap();
 
             for doc in docs.0 {
-                Self::add_word(&mut writer, &schema_info, doc).unwrap();
+                Self::add_word(&mut writer, &schema_info, rules, doc).unwrap();
             }
 
-            writer.commit().unwrap();
+            Self::commit(&mut writer, &reader).unwrap();
         })
         .await
         .unwrap()
@@ -390,11 +894,13 @@ impl Handler<IndexWord> for WriterActor {
     async fn handle(&mut self, doc: IndexWord, _ctx: &mut xtra::Context<Self>) {
         let writer = self.writer.clone();
         let schema_info = self.schema_info.clone();
+        let reader = self.reload_after_commit.clone();
+        let rules = self.rules;
 
         spawn_blocking_child(move || {
             let mut writer = writer.lock().unwrap();
-            Self::add_word(&mut writer, &schema_info, doc.0).unwrap();
-            writer.commit().unwrap();
+            Self::add_word(&mut writer, &schema_info, rules, doc.0).unwrap();
+            Self::commit(&mut writer, &reader).unwrap();
         })
         .await
         .unwrap()
@@ -415,6 +921,8 @@ impl Handler<EditWord> for WriterActor {
     async fn handle(&mut self, edit: EditWord, _ctx: &mut xtra::Context<Self>) {
         let writer = self.writer.clone();
         let schema_info = self.schema_info.clone();
+        let reader = self.reload_after_commit.clone();
+        let rules = self.rules;
 
         spawn_blocking_child(move || {
             let mut writer = writer.lock().unwrap();
@@ -427,8 +935,8 @@ impl Handler<EditWord> for WriterActor {
                 }
             };
             writer.delete_term(term);
-            Self::add_word(&mut writer, &schema_info, edit.0).unwrap();
-            writer.commit().unwrap();
+            Self::add_word(&mut writer, &schema_info, rules, edit.0).unwrap();
+            Self::commit(&mut writer, &reader).unwrap();
         })
         .await
         .unwrap()
@@ -442,6 +950,7 @@ impl Handler<DeleteWord> for WriterActor {
     async fn handle(&mut self, delete: DeleteWord, _ctx: &mut xtra::Context<Self>) {
         let writer = self.writer.clone();
         let schema_info = self.schema_info.clone();
+        let reader = self.reload_after_commit.clone();
 
         spawn_blocking_child(move || {
             let mut writer = writer.lock().unwrap();
@@ -454,7 +963,7 @@ impl Handler<DeleteWord> for WriterActor {
                 }
             };
             writer.delete_term(term);
-            writer.commit().unwrap();
+            Self::commit(&mut writer, &reader).unwrap();
         })
         .await
         .unwrap()
@@ -482,6 +991,8 @@ pub struct SearchRequest<Res> {
     query: String,
     include: IncludeResults,
     duplicate: bool,
+    /// The most hits to return
+    limit: usize,
     // It isn't great that we have to pass this in. The reason for it is that with this,
     // we can avoid getting results just to map them and collect again. But this introduces
     // a coupling between I18n and searching that is not great. Really the best solution
@@ -498,6 +1009,7 @@ impl<Res> SearchRequest<Res> {
             query: self.query,
             include: self.include,
             duplicate: self.duplicate,
+            limit: self.limit,
             i18n: self.i18n,
             _phantom: PhantomData,
         }
@@ -512,7 +1024,88 @@ pub enum IncludeResults {
     AcceptedAndAllSuggestions,
 }
 
-pub struct GetAllWords;
+pub struct GetAllWords {
+    school_mode: bool,
+}
+
+pub struct PatternRequest {
+    pattern: String,
+    i18n: I18nInfo,
+}
+
+/// A single character of a crossword-style pattern
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PatternChar {
+    Letter(char),
+    AnyOne,
+    AnyMany,
+}
+
+/// Parse the pattern, folded in the same way as the indexed tokens are so that e.g `hámb*` still
+/// finds `hamba`
+fn parse_pattern(pattern: &str) -> Option<Vec<PatternChar>> {
+    normalize::fold(pattern)
+        .chars()
+        .map(|c| match c {
+            '_' | '?' => Some(PatternChar::AnyOne),
+            '*' => Some(PatternChar::AnyMany),
+            c if c.is_alphanumeric() => Some(PatternChar::Letter(c)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Convert the pattern to a regex over the indexed (folded) tokens of the `xhosa` field. Only
+/// alphanumeric characters and wildcards are allowed through, so nothing needs escaping.
+fn pattern_to_regex(pattern: &[PatternChar]) -> String {
+    pattern
+        .iter()
+        .map(|c| match c {
+            PatternChar::Letter(c) => c.to_string(),
+            PatternChar::AnyOne => ".".to_owned(),
+            PatternChar::AnyMany => ".*".to_owned(),
+        })
+        .collect()
+}
+
+/// Whether the whole word matches the pattern. This is the usual dynamic programming approach to
+/// wildcard matching, so it takes time proportional to the length of the pattern times the length
+/// of the word, however many `*`s there are.
+fn matches_pattern(pattern: &[PatternChar], word: &[char]) -> bool {
+    // matched[i] is whether the pattern so far matches the first i characters of the word
+    let mut matched = vec![false; word.len() + 1];
+    matched[0] = true;
+
+    for p in pattern {
+        match p {
+            PatternChar::AnyMany => {
+                for i in 1..=word.len() {
+                    matched[i] = matched[i] || matched[i - 1];
+                }
+            }
+            PatternChar::AnyOne | PatternChar::Letter(_) => {
+                for i in (1..=word.len()).rev() {
+                    let char_matches = match p {
+                        PatternChar::Letter(c) => word[i - 1] == *c,
+                        _ => true,
+                    };
+                    matched[i] = matched[i - 1] && char_matches;
+                }
+                matched[0] = false;
+            }
+        }
+    }
+
+    matched[word.len()]
+}
+
+/// Whether the whole of the word's isiXhosa matches the pattern, folded like the pattern is
+fn matches_word(pattern: &[PatternChar], xhosa: &str) -> bool {
+    let xhosa: Vec<char> = normalize::fold(xhosa.trim_start_matches("(i)"))
+        .chars()
+        .collect();
+    matches_pattern(pattern, &xhosa)
+}
 
 impl SearcherActor {
     #[instrument(
@@ -531,6 +1124,11 @@ impl SearcherActor {
         req: &SearchRequest<WordHit>,
         out: &mut HashSet<WordHit>,
     ) {
+        let (english_weight, xhosa_weight) = req.i18n.search_direction.weights();
+        let boost = |query: FuzzyTermQuery, weight: f32| -> Box<dyn Query + 'static> {
+            Box::new(BoostQuery::new(Box::new(query), weight))
+        };
+
         let mut tokenized = tokenizer.token_stream(&req.query);
         let mut queries: Vec<Box<dyn Query + 'static>> = Vec::with_capacity(3);
         tokenized.process(&mut |token| {
@@ -552,9 +1150,9 @@ impl SearcherActor {
             let query_xhosa_stemmed = FuzzyTermQuery::new_prefix(xhosa_stemmed, distance, true);
 
             let this_term: Vec<Box<dyn Query + 'static>> = vec![
-                Box::new(query_english),
-                Box::new(query_xhosa),
-                Box::new(query_xhosa_stemmed),
+                boost(query_english, english_weight),
+                boost(query_xhosa, xhosa_weight),
+                boost(query_xhosa_stemmed, xhosa_weight),
             ];
 
             queries.push(Box::new(BooleanQuery::union(this_term)));
@@ -583,10 +1181,17 @@ impl SearcherActor {
             IncludeResults::AcceptedOnly => not_suggestion(),
         };
 
+        // Duplicates are still looked for among offensive words, so that they aren't added again
+        let query = if req.i18n.school_mode && !req.duplicate {
+            client.schema_info.without_offensive(Box::new(query))
+        } else {
+            query
+        };
+
         let mut count = 0;
 
         let iter = searcher
-            .search(&query, &TopDocs::with_limit(RESULTS * 5))
+            .search(&query, &TopDocs::with_limit(req.limit * 5))
             .unwrap()
             .into_iter()
             .map(|(_, doc_address)| {
@@ -625,13 +1230,15 @@ where
         }
 
         impl WordHitWithScore {
-            fn new(hit: WordHit, query: &str) -> WordHitWithScore {
+            fn new(hit: WordHit, query: &str, direction: SearchDirection) -> WordHitWithScore {
                 let sim =
-                    |hit: &str| OrderedFloat(strsim::jaro_winkler(query, &hit.to_lowercase()));
-                let xh_sim = sim(hit.xhosa.trim_start_matches("(i)"));
-                let en_sim = sim(&hit.english);
+                    |hit: &str| OrderedFloat(strsim::jaro_winkler(query, &normalize::fold(hit)));
+                let (en_weight, xh_weight) = direction.weights();
+                let xh_sim = sim(hit.xhosa.trim_start_matches("(i)")) * f64::from(xh_weight);
+                let en_sim = sim(&hit.english) * f64::from(en_weight);
                 // Temporary fix for "become ___" ranking very low
-                let en_inchoative_sim = sim(hit.english.trim_start_matches("become "));
+                let en_inchoative_sim =
+                    sim(hit.english.trim_start_matches("become ")) * f64::from(en_weight);
                 let sim_score = max(xh_sim, max(en_sim, en_inchoative_sim));
                 // 1% penalty to any informal words to make them rank lower (they are usually less relevant)
                 let informal_penalty = if hit.is_informal { 0.99 } else { 1.0 };
@@ -661,15 +1268,28 @@ where
             }
         }
 
-        req.query = req.query.to_lowercase().replace(['(', ')'], "");
+        req.query = normalize::fold(&req.query).replace(['(', ')'], "");
         req.query.truncate(64);
 
         let mut searcher = self.reader.searcher();
+        let generation = searcher.generation().generation_id();
+        let cacheable = matches!(req.include, IncludeResults::AcceptedOnly);
+
+        if cacheable {
+            let cache = &self.client.search_cache;
+            if let Some(hits) = cache.get(&SearchCacheKey::new(&req), generation) {
+                return hits
+                    .into_iter()
+                    .map(|hit| Res::from_with_i18n(hit, &req.i18n))
+                    .collect();
+            }
+        }
+
         let client = self.client.clone();
         let mut tokenizer = self.client.tokenizer.clone();
         let mut results = HashSet::with_capacity(10);
 
-        spawn_blocking_child(move || {
+        let (req, hits) = spawn_blocking_child(move || {
             for level in 0..=2 {
                 SearcherActor::query_terms(
                     &mut searcher,
@@ -680,7 +1300,7 @@ where
                     &mut results,
                 );
 
-                if results.len() >= RESULTS {
+                if results.len() >= req.limit {
                     break;
                 }
             }
@@ -689,16 +1309,11 @@ where
                 let _g = debug_span!("Filtering for exact matches only").entered();
 
                 let exact = |hit: &WordHit| {
-                    hit.english.to_lowercase() == req.query.to_lowercase()
-                        || hit.xhosa.to_lowercase() == req.query.to_lowercase()
+                    normalize::matches(&hit.english, &req.query)
+                        || normalize::matches(&hit.xhosa, &req.query)
                 };
-                Ok::<_, anyhow::Error>(
-                    results
-                        .into_iter()
-                        .filter(exact)
-                        .map(|hit| Res::from_with_i18n(hit, &req.i18n))
-                        .collect(),
-                )
+                let hits: Vec<WordHit> = results.into_iter().filter(exact).collect();
+                Ok::<_, anyhow::Error>((req, hits))
             } else {
                 let _g =
                     info_span!("Sorting and ordering results", results = results.len()).entered();
@@ -707,36 +1322,52 @@ where
                     info_span!("Calculating string similarity").in_scope(|| {
                         results
                             .into_iter()
-                            .map(|hit| WordHitWithScore::new(hit, &req.query))
+                            .map(|hit| {
+                                WordHitWithScore::new(hit, &req.query, req.i18n.search_direction)
+                            })
                             .collect()
                     });
 
                 debug_span!("Sorting list based on score").in_scope(|| results.sort());
 
-                Ok(results
-                    .into_iter()
-                    .take(RESULTS)
-                    .map(|s| Res::from_with_i18n(s.hit, &req.i18n))
-                    .collect())
+                let hits: Vec<WordHit> =
+                    results.into_iter().take(req.limit).map(|s| s.hit).collect();
+                Ok((req, hits))
             }
         })
         .await
         .expect("Error executing search task")
-        .unwrap() // TODO(error handling)
+        .unwrap(); // TODO(error handling)
+
+        if cacheable {
+            self.client
+                .search_cache
+                .insert(SearchCacheKey::new(&req), generation, &hits);
+        }
+
+        hits.into_iter()
+            .map(|hit| Res::from_with_i18n(hit, &req.i18n))
+            .collect()
     }
 }
 
 impl Handler<GetAllWords> for SearcherActor {
     type Return = Vec<WordHit>;
 
-    async fn handle(&mut self, _msg: GetAllWords, _ctx: &mut xtra::Context<Self>) -> Vec<WordHit> {
+    async fn handle(&mut self, msg: GetAllWords, _ctx: &mut xtra::Context<Self>) -> Vec<WordHit> {
         let searcher = self.reader.searcher();
         let client = self.client.clone();
 
         // TODO(error handling)
         spawn_blocking_child(move || {
+            let query: Box<dyn Query> = if msg.school_mode {
+                Box::new(client.schema_info.without_offensive(Box::new(AllQuery)))
+            } else {
+                Box::new(AllQuery)
+            };
+
             let mut docs = searcher
-                .search(&AllQuery, &DocSetCollector)
+                .search(query.as_ref(), &DocSetCollector)
                 .unwrap()
                 .into_iter()
                 .map(|doc_address| {
@@ -755,6 +1386,63 @@ impl Handler<GetAllWords> for SearcherActor {
     }
 }
 
+impl Handler<PatternRequest> for SearcherActor {
+    type Return = Result<Vec<JsWordHit>>;
+
+    async fn handle(
+        &mut self,
+        req: PatternRequest,
+        _ctx: &mut xtra::Context<Self>,
+    ) -> Result<Vec<JsWordHit>> {
+        let pattern = match parse_pattern(&req.pattern) {
+            Some(pattern) if !pattern.is_empty() && pattern.len() <= 64 => pattern,
+            _ => return Ok(Vec::new()),
+        };
+
+        let searcher = self.reader.searcher();
+        let client = self.client.clone();
+
+        spawn_blocking_child(move || {
+            let regex = pattern_to_regex(&pattern);
+            let xhosa = RegexQuery::from_pattern(&regex, client.schema_info.xhosa)?;
+            let not_suggestion = Term::from_field_u64(client.schema_info.suggesting_user, 0);
+            let not_suggestion = TermQuery::new(not_suggestion, IndexRecordOption::Basic);
+            let query = BooleanQuery::intersection(vec![Box::new(xhosa), Box::new(not_suggestion)]);
+            let query = if req.i18n.school_mode {
+                client.schema_info.without_offensive(Box::new(query))
+            } else {
+                query
+            };
+
+            let hits = searcher
+                .search(&query, &TopDocs::with_limit(PATTERN_CANDIDATES))?
+                .into_iter()
+                .map(|(_, doc_address)| {
+                    searcher
+                        .doc(doc_address)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|doc| WordHit::try_deserialize(&client.schema_info, doc))
+                })
+                .collect::<Result<Vec<WordHit>>>()?;
+
+            // The regex matches individual tokens, so multi-word entries (and latent i verbs)
+            // need to be checked against the pattern as a whole.
+            let mut hits: Vec<WordHit> = hits
+                .into_iter()
+                .filter(|hit| matches_word(&pattern, &hit.xhosa))
+                .collect();
+
+            hits.sort_by_cached_key(|hit| (hit.xhosa.to_lowercase(), hit.id));
+            Ok(hits
+                .into_iter()
+                .take(PATTERN_RESULTS)
+                .map(|hit| JsWordHit::from_with_i18n(hit, &req.i18n))
+                .collect())
+        })
+        .await?
+    }
+}
+
 #[derive(Clone, Debug)]
 struct SchemaInfo {
     schema: Schema,
@@ -767,11 +1455,25 @@ struct SchemaInfo {
     is_informal: Field,
     transitivity: Field,
     noun_class: Field,
+    /// Only indexed, so that offensive words can be left out of results in school mode
+    is_offensive: Field,
     suggesting_user: Field,
     existing_id: Field,
     suggestion_id: Field,
 }
 
+impl SchemaInfo {
+    /// Leave offensive words out of the query's results
+    fn without_offensive(&self, query: Box<dyn Query>) -> BooleanQuery {
+        let offensive = Term::from_field_u64(self.is_offensive, 1);
+        let offensive = TermQuery::new(offensive, IndexRecordOption::Basic);
+        BooleanQuery::new(vec![
+            (Occur::Must, query),
+            (Occur::MustNot, Box::new(offensive)),
+        ])
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct WordDocument {
     pub id: WordOrSuggestionId,
@@ -785,6 +1487,27 @@ pub struct WordDocument {
     pub suggesting_user: Option<NonZeroU64>,
     pub noun_class: Option<NounClass>,
     pub is_informal: bool,
+    pub is_offensive: bool,
+}
+
+impl TryFrom<&Row<'_>> for WordDocument {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(WordDocument {
+            id: WordOrSuggestionId::existing(row.get::<&str, i64>("word_id")? as u64),
+            english: row.get("english")?,
+            xhosa: row.get("xhosa")?,
+            part_of_speech: row.get("part_of_speech")?,
+            is_plural: row.get("is_plural")?,
+            is_inchoative: row.get("is_inchoative")?,
+            transitivity: row.get_with_sentinel("transitivity")?,
+            suggesting_user: None,
+            noun_class: row.get_with_sentinel("noun_class")?,
+            is_informal: row.get("is_informal")?,
+            is_offensive: row.get("is_offensive")?,
+        })
+    }
 }
 
 trait WordHitExt {
@@ -882,3 +1605,28 @@ impl From<WordDocument> for WordHit {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match_whole_words() {
+        let pattern = parse_pattern("ham_a").unwrap();
+        assert!(matches_word(&pattern, "hamba"));
+        assert!(!matches_word(&pattern, "hambisa"));
+
+        let pattern = parse_pattern("uku*").unwrap();
+        assert!(matches_word(&pattern, "ukuhamba"));
+        assert!(!matches_word(&pattern, "hamba"));
+    }
+
+    #[test]
+    fn patterns_are_folded_like_the_index() {
+        let pattern = parse_pattern("Hámb*").unwrap();
+        assert_eq!(pattern, parse_pattern("hamb*").unwrap());
+        assert_eq!(pattern_to_regex(&pattern), "hamb.*");
+        assert!(matches_word(&pattern, "hamba"));
+        assert!(matches_word(&pattern, "(i)hambá"));
+    }
+}
@@ -0,0 +1,80 @@
+//! Security headers added to every response. Inline scripts are only allowed to run if they carry
+//! the nonce generated for the request, which templates are given in
+//! [`I18nInfo::csp_nonce`](crate::i18n::I18nInfo).
+
+use rand::Rng;
+use std::convert::Infallible;
+use warp::http::header::{
+    CONTENT_SECURITY_POLICY, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS,
+};
+use warp::http::HeaderValue;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+/// One year, as recommended for HSTS
+const HSTS_MAX_AGE: u64 = 60 * 60 * 24 * 365;
+
+/// The CSP nonce for a request. warp filters can't pass anything on to the filters after them, so
+/// this is generated when the request is accepted and carried in its extensions.
+#[derive(Clone, Debug)]
+pub struct CspNonce(pub String);
+
+impl CspNonce {
+    pub fn generate() -> CspNonce {
+        let mut bytes = [0u8; 16];
+        rand::thread_rng().fill(&mut bytes[..]);
+        CspNonce(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+}
+
+/// Extracts the request's CSP nonce, which is empty if it wasn't given one
+pub fn csp_nonce() -> impl Filter<Extract = (String,), Error = Infallible> + Clone {
+    warp::ext::optional::<CspNonce>()
+        .map(|nonce: Option<CspNonce>| nonce.map(|CspNonce(nonce)| nonce).unwrap_or_default())
+}
+
+fn content_security_policy(nonce: &str) -> String {
+    // WASM is needed for the wordle game
+    let script_src = if nonce.is_empty() {
+        "script-src 'self' 'wasm-unsafe-eval'".to_owned()
+    } else {
+        format!("script-src 'self' 'nonce-{nonce}' 'wasm-unsafe-eval'")
+    };
+
+    [
+        "default-src 'self'".to_owned(),
+        script_src,
+        "style-src 'self'".to_owned(),
+        "img-src 'self' data:".to_owned(),
+        "connect-src 'self'".to_owned(),
+        "object-src 'none'".to_owned(),
+        "base-uri 'none'".to_owned(),
+        "form-action 'self'".to_owned(),
+        "frame-ancestors 'none'".to_owned(),
+    ]
+    .join("; ")
+}
+
+pub async fn add_security_headers<R: Reply>(
+    reply: R,
+    nonce: String,
+) -> Result<Response, Rejection> {
+    let mut response = reply.into_response();
+
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_SECURITY_POLICY,
+        HeaderValue::from_str(&content_security_policy(&nonce)).unwrap(),
+    );
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(
+        REFERRER_POLICY,
+        HeaderValue::from_static("strict-origin-when-cross-origin"),
+    );
+    headers.insert(
+        STRICT_TRANSPORT_SECURITY,
+        HeaderValue::from_str(&format!("max-age={HSTS_MAX_AGE}; includeSubDomains")).unwrap(),
+    );
+
+    Ok(response)
+}
@@ -1,15 +1,23 @@
 use askama_warp::warp::http::header::CONTENT_TYPE;
+use futures::{StreamExt, TryStreamExt};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Deserializer};
 use std::fmt::Debug;
 use tracing::warn;
+use url::form_urlencoded;
 use warp::hyper::body::Bytes;
+use warp::multipart::{FormData, Part};
 use warp::{Buf, Filter, Rejection};
 
 pub fn false_fn() -> bool {
     false
 }
 
+/// For `skip_serializing_if`, to leave out fields which would be defaulted when deserializing
+pub fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
 pub fn deserialize_checkbox<'de, D>(deser: D) -> Result<bool, D::Error>
 where
     D: Deserializer<'de>,
@@ -24,28 +32,196 @@ where
     }
 }
 
+/// A form which could not be deserialized, rendered as a 400 page which names the field at fault
+/// if it is known
+#[derive(Debug)]
+pub struct InvalidForm {
+    pub field: Option<String>,
+    #[allow(dead_code)] // We DO want the Debug impl to count here
+    error: serde_qs::Error,
+}
+
+impl warp::reject::Reject for InvalidForm {}
+
+impl InvalidForm {
+    fn new(error: serde_qs::Error) -> InvalidForm {
+        // serde_derive names the field in backticks for missing, unknown and duplicate fields. Other
+        // errors (e.g an unparseable number) don't say which field they came from.
+        let message = error.to_string();
+        let field = message
+            .split_once("field `")
+            .and_then(|(_, rest)| rest.split_once('`'))
+            .map(|(field, _)| field.to_owned());
+
+        InvalidForm { field, error }
+    }
+}
+
 fn to_bytes<B: Buf>(mut b: B) -> Bytes {
     b.copy_to_bytes(b.remaining())
 }
 
-pub fn qs_form<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Copy
-{
+/// Extracts the raw body of a url-encoded form
+pub fn form_bytes() -> impl Filter<Extract = (Bytes,), Error = Rejection> + Copy {
     warp::header::exact(CONTENT_TYPE.as_ref(), "application/x-www-form-urlencoded")
         .and(warp::body::aggregate())
         .map(to_bytes)
-        .and_then(|bytes: Bytes| async move {
-            serde_qs::Config::new(5, false)
-                .deserialize_bytes(&bytes)
-                .map_err(|err| {
-                    #[allow(dead_code)] // We DO want the Debug impl to count here
-                    #[derive(Debug)]
-                    struct DeserErr(serde_qs::Error);
+}
+
+/// Deserializes a url-encoded form. Both flat fields and nested fields in the querystring style
+/// (`examples[0][english]=...`) are accepted.
+pub fn deserialize_form<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Rejection> {
+    serde_qs::Config::new(5, false)
+        .deserialize_bytes(bytes)
+        .map_err(|err| {
+            let err = InvalidForm::new(err);
+            warn!("Error deserializing form: {:?}", err);
+            warp::reject::custom(err)
+        })
+}
+
+/// The raw fields of a url-encoded form, in the order they were submitted, so that the form can be
+/// sent again as-is
+#[derive(Clone, Debug, Default)]
+pub struct FormFields(pub Vec<(String, String)>);
+
+impl FormFields {
+    pub fn parse(bytes: &[u8]) -> FormFields {
+        FormFields(form_urlencoded::parse(bytes).into_owned().collect())
+    }
+
+    pub fn to_urlencoded(&self) -> String {
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&self.0)
+            .finish()
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn without(self, excluded: &[&str]) -> FormFields {
+        FormFields(
+            self.0
+                .into_iter()
+                .filter(|(field, _)| !excluded.contains(&field.as_str()))
+                .collect(),
+        )
+    }
+}
 
-                    warn!("Error deserializing query-string: {:?}", err);
+/// A multipart form which could not be read, e.g because it was cut off or a text field wasn't
+/// valid UTF-8, rendered as a 400 page
+#[derive(Debug)]
+pub struct InvalidMultipart(pub String);
 
-                    impl warp::reject::Reject for DeserErr {}
+impl warp::reject::Reject for InvalidMultipart {}
 
-                    warp::reject::custom(DeserErr(err))
-                })
+/// A file uploaded through a multipart form
+#[derive(Clone, Debug)]
+pub struct UploadedFile {
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+impl UploadedFile {
+    /// Browsers still send a part for file inputs which were left empty, just with no contents
+    pub fn is_empty(&self) -> bool {
+        self.filename.is_empty() && self.bytes.is_empty()
+    }
+}
+
+/// A multipart form, with its text fields and uploaded files kept apart
+#[derive(Clone, Debug, Default)]
+pub struct MultipartForm {
+    pub fields: FormFields,
+    pub files: Vec<(String, UploadedFile)>,
+}
+
+impl MultipartForm {
+    async fn read(data: FormData) -> Result<MultipartForm, InvalidMultipart> {
+        let parts: Vec<Part> = data
+            .try_collect()
+            .await
+            .map_err(|err| InvalidMultipart(err.to_string()))?;
+
+        let mut form = MultipartForm::default();
+
+        for part in parts {
+            let name = part.name().to_owned();
+            let filename = part.filename().map(ToOwned::to_owned);
+            let content_type = part.content_type().map(ToOwned::to_owned);
+
+            let mut bytes = Vec::new();
+            let mut stream = part.stream();
+            while let Some(buf) = stream.next().await {
+                let buf = buf.map_err(|err| InvalidMultipart(err.to_string()))?;
+                bytes.extend_from_slice(&to_bytes(buf));
+            }
+
+            match filename {
+                Some(filename) => form.files.push((
+                    name,
+                    UploadedFile {
+                        filename,
+                        content_type,
+                        bytes,
+                    },
+                )),
+                None => {
+                    let value = String::from_utf8(bytes)
+                        .map_err(|_| InvalidMultipart(format!("Field {name} is not UTF-8")))?;
+                    form.fields.0.push((name, value));
+                }
+            }
+        }
+
+        Ok(form)
+    }
+
+    /// The file uploaded through the given field, unless it was left empty
+    pub fn file(&self, field: &str) -> Option<&UploadedFile> {
+        self.files
+            .iter()
+            .find(|(name, file)| name == field && !file.is_empty())
+            .map(|(_, file)| file)
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, Rejection> {
+        deserialize_form(self.fields.to_urlencoded().as_bytes())
+    }
+}
+
+/// Extracts a multipart form of at most `max_length` bytes. Larger bodies are rejected with
+/// [`PayloadTooLarge`](warp::reject::PayloadTooLarge).
+pub fn multipart_form(
+    max_length: u64,
+) -> impl Filter<Extract = (MultipartForm,), Error = Rejection> + Clone {
+    warp::multipart::form()
+        .max_length(Some(max_length))
+        .and_then(|data: FormData| async move {
+            MultipartForm::read(data).await.map_err(|err| {
+                warn!("Error reading multipart form: {:?}", err);
+                warp::reject::custom(err)
+            })
         })
 }
+
+/// Extracts a typed url-encoded form from the body. Forms which need CSRF protection should use
+/// [`csrf_protected_form`](crate::csrf::csrf_protected_form) instead.
+pub fn form<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Copy {
+    form_bytes().and_then(|bytes: Bytes| async move { deserialize_form(&bytes) })
+}
+
+/// Extracts a typed form from the query string, accepting nested fields like [`form`] does
+pub fn query_form<T: DeserializeOwned + Send>(
+) -> impl Filter<Extract = (T,), Error = Rejection> + Copy {
+    warp::query::raw()
+        .or(warp::any().map(String::new))
+        .unify()
+        .and_then(|query: String| async move { deserialize_form(query.as_bytes()) })
+}
@@ -3,25 +3,29 @@ use crate::search::{IncludeResults, JsWordHit, TantivyClient};
 use crate::spawn_send_interval;
 use futures::stream::SplitSink;
 use futures::SinkExt;
+use isixhosa_common::database::PublicAccessDb;
 use serde::{Deserialize, Serialize};
 use std::num::NonZeroU64;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tracing::warn;
 use warp::ws::{self, WebSocket};
 use xtra::prelude::*;
 
-pub struct LiveSearchSession {
+pub struct LiveSearchSession<D> {
     pub sender: SplitSink<WebSocket, ws::Message>,
     pub tantivy: Arc<TantivyClient>,
+    db: D,
     include: IncludeResults,
     heartbeat: Instant,
     i18n_info: I18nInfo,
 }
 
-impl LiveSearchSession {
+impl<D: PublicAccessDb> LiveSearchSession<D> {
     pub fn new(
         sender: SplitSink<WebSocket, ws::Message>,
         tantivy: Arc<TantivyClient>,
+        db: D,
         include_suggestions_from_user: Option<NonZeroU64>,
         is_moderator: bool,
         i18n_info: I18nInfo,
@@ -35,6 +39,7 @@ impl LiveSearchSession {
         LiveSearchSession {
             sender,
             tantivy,
+            db,
             include,
             heartbeat: Instant::now(),
             i18n_info,
@@ -42,7 +47,7 @@ impl LiveSearchSession {
     }
 }
 
-impl Actor for LiveSearchSession {
+impl<D: PublicAccessDb> Actor for LiveSearchSession<D> {
     type Stop = ();
 
     async fn started(&mut self, mailbox: &Mailbox<Self>) -> Result<(), ()> {
@@ -58,7 +63,7 @@ impl Actor for LiveSearchSession {
 #[derive(Copy, Clone, Default)]
 pub struct Heartbeat;
 
-impl Handler<Heartbeat> for LiveSearchSession {
+impl<D: PublicAccessDb> Handler<Heartbeat> for LiveSearchSession<D> {
     type Return = ();
 
     async fn handle(&mut self, _hb: Heartbeat, ctx: &mut Context<Self>) {
@@ -68,7 +73,7 @@ impl Handler<Heartbeat> for LiveSearchSession {
     }
 }
 
-impl Handler<Result<ws::Message, warp::Error>> for LiveSearchSession {
+impl<D: PublicAccessDb> Handler<Result<ws::Message, warp::Error>> for LiveSearchSession<D> {
     type Return = ();
 
     async fn handle(&mut self, message: Result<ws::Message, warp::Error>, ctx: &mut Context<Self>) {
@@ -102,12 +107,24 @@ impl Handler<Result<ws::Message, warp::Error>> for LiveSearchSession {
                         state: String,
                     }
 
+                    let search = self.tantivy.search(
+                        query.search,
+                        self.include,
+                        false,
+                        self.i18n_info.clone(),
+                    );
+                    let mut results: Vec<JsWordHit> = match search.await {
+                        Ok(results) => results,
+                        Err(err) => {
+                            warn!("Live search failed: {err}");
+                            return ctx.stop_self();
+                        }
+                    };
+                    JsWordHit::add_linked_words(&mut results, self.db.clone(), &self.i18n_info)
+                        .await;
+
                     let reply = Reply {
-                        results: self
-                            .tantivy
-                            .search(query.search, self.include, false, self.i18n_info.clone())
-                            .await
-                            .unwrap(),
+                        results,
                         state: query.state,
                     };
 
@@ -120,16 +137,19 @@ impl Handler<Result<ws::Message, warp::Error>> for LiveSearchSession {
                         return;
                     }
 
-                    let results: Vec<JsWordHit> = self
-                        .tantivy
-                        .search(
-                            query.to_owned(),
-                            IncludeResults::AcceptedOnly,
-                            false,
-                            self.i18n_info.clone(),
-                        )
-                        .await
-                        .unwrap();
+                    let search = self.tantivy.search(
+                        query.to_owned(),
+                        IncludeResults::AcceptedOnly,
+                        false,
+                        self.i18n_info.clone(),
+                    );
+                    let results: Vec<JsWordHit> = match search.await {
+                        Ok(results) => results,
+                        Err(err) => {
+                            warn!("Live search failed: {err}");
+                            return ctx.stop_self();
+                        }
+                    };
                     serde_json::to_string(&results).unwrap()
                 }
             };
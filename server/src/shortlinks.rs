@@ -0,0 +1,105 @@
+//! Shortlinks to searches, e.g `/s/x7Kq2mPa`, so that a search with its filters can be shared
+//! without a long URL. Anyone can follow a shortlink, but only signed in users can make one, so
+//! that the table of shortlinks can't be filled up anonymously.
+
+use crate::auth::{with_user_auth, FullUser};
+use crate::config::BodyLimits;
+use crate::csrf::csrf_protected_form;
+use crate::database::shortlinks::{fetch_query, shorten};
+use crate::error::ServerError;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::{spawn_blocking_child, DebugBoxedExt, SearchQuery};
+use isixhosa_common::database::{with_public_db, DbBase, PublicAccessDb, UserAccessDb};
+use std::sync::Arc;
+use tracing::instrument;
+use warp::http::{StatusCode, Uri};
+use warp::reply::Response;
+use warp::{body, Filter, Rejection, Reply};
+
+/// The longest search query which can be shared, which is also the longest the search box allows
+const MAX_QUERY_LEN: usize = 256;
+
+pub fn shortlinks(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    body_limits: BodyLimits,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let follow = warp::get()
+        .and(warp::path![String])
+        .and(warp::path::end())
+        .and(with_public_db(db.clone()))
+        .and_then(follow_shortlink);
+
+    let share = warp::post()
+        .and(warp::path::end())
+        .and(body::content_length_limit(body_limits.form))
+        .and(csrf_protected_form())
+        .and(with_user_auth(db, site_ctx))
+        .and_then(share_search);
+
+    warp::path("s").and(follow.or(share)).debug_boxed()
+}
+
+#[instrument(name = "Follow search shortlink", skip(db))]
+async fn follow_shortlink(short: String, db: impl PublicAccessDb) -> Result<impl Reply, Rejection> {
+    let query = spawn_blocking_child(move || fetch_query(&db, &short))
+        .await
+        .map_err(ServerError::from)?
+        .map_err(ServerError::from)?
+        .ok_or_else(warp::reject::not_found)?;
+
+    Ok(warp::redirect::see_other(
+        format!("/search?{query}").parse::<Uri>().unwrap(),
+    ))
+}
+
+/// Make a shortlink for the search and go back to it, where the shortlink is shown
+#[instrument(
+    name = "Share search",
+    fields(query = %search.query, user_id = %user.id),
+    skip_all
+)]
+async fn share_search(
+    search: SearchQuery,
+    user: FullUser,
+    _i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<Response, Rejection> {
+    if search.query.chars().count() > MAX_QUERY_LEN {
+        return Ok(StatusCode::BAD_REQUEST.into_response());
+    }
+
+    let query = search.to_query_string();
+    let query_clone = query.clone();
+    let short = spawn_blocking_child(move || shorten(&db, &query_clone))
+        .await
+        .map_err(ServerError::from)?
+        .map_err(ServerError::from)?;
+
+    Ok(warp::redirect::see_other(
+        format!("/search?{query}&shared={short}")
+            .parse::<Uri>()
+            .unwrap(),
+    )
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestSite;
+    use isixhosa_common::auth::Permissions;
+
+    #[tokio::test]
+    async fn long_queries_are_not_shortened() {
+        let site = TestSite::start().await;
+        let user = site.sign_in_as(Permissions::User);
+
+        let shared = site.post_form("/s", &user, &[("query", "hamba")]).await;
+        assert_eq!(shared.status(), StatusCode::SEE_OTHER);
+
+        let long = "a".repeat(MAX_QUERY_LEN + 1);
+        let refused = site.post_form("/s", &user, &[("query", &long)]).await;
+        assert_eq!(refused.status(), StatusCode::BAD_REQUEST);
+    }
+}
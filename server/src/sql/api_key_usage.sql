@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS api_key_usage (
+    key_id      INTEGER NOT NULL REFERENCES api_keys(key_id) ON DELETE CASCADE,
+    day         DATE NOT NULL,
+    requests    INTEGER NOT NULL,
+    PRIMARY KEY (key_id, day)
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS api_keys (
+    key_id      INTEGER PRIMARY KEY,
+    user_id     INTEGER NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    key_hash    TEXT NOT NULL,
+    name        TEXT NOT NULL,
+    daily_quota INTEGER NOT NULL,
+    created     TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_used   TIMESTAMP WITH TIME ZONE
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS automatic_link_suggestions (
+    first_word_id   INTEGER NOT NULL REFERENCES words(word_id) ON DELETE CASCADE,
+    second_word_id  INTEGER NOT NULL REFERENCES words(word_id) ON DELETE CASCADE,
+    -- Cleared once the suggestion is accepted or rejected. The pair is kept so that it isn't
+    -- suggested again.
+    suggestion_id   INTEGER REFERENCES linked_word_suggestions(suggestion_id) ON DELETE SET NULL,
+    -- How much the English glosses of the words overlap, from 0 to 1
+    overlap         REAL NOT NULL,
+    PRIMARY KEY (first_word_id, second_word_id)
+);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS bans (
+    ban_id     INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- Exactly one of user_id and ip_range is set
+    user_id    INTEGER REFERENCES users(user_id) ON DELETE CASCADE,
+    -- An IP address or CIDR range, e.g 192.0.2.0/24
+    ip_range   TEXT,
+    reason     TEXT NOT NULL,
+    banned_by  INTEGER NOT NULL REFERENCES users(user_id),
+    created    TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- Null if the ban never expires
+    expires    TIMESTAMP WITH TIME ZONE
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS drafts (
+    draft_id    INTEGER PRIMARY KEY,
+    user_id     INTEGER NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    english     TEXT NOT NULL,
+    xhosa       TEXT NOT NULL,
+    form        TEXT NOT NULL,
+    last_saved  TIMESTAMP WITH TIME ZONE NOT NULL
+);
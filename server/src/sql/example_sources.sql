@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS example_sources (
+    example_id  INTEGER PRIMARY KEY REFERENCES examples(example_id) ON DELETE CASCADE,
+    source      TEXT NOT NULL,
+    alignment   TEXT
+);
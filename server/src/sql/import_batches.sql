@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS import_batches (
+    batch_id     INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- The administrator who uploaded the file
+    user_id      INTEGER REFERENCES users(user_id) ON DELETE SET NULL,
+    filename     TEXT NOT NULL,
+    imported     TIMESTAMP WITH TIME ZONE NOT NULL,
+    rolled_back  TIMESTAMP WITH TIME ZONE
+);
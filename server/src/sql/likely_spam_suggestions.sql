@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS likely_spam_suggestions (
+    suggestion_id  INTEGER PRIMARY KEY REFERENCES word_suggestions(suggestion_id) ON DELETE CASCADE,
+    -- Comma-separated list of the heuristics which flagged the suggestion
+    reasons        TEXT NOT NULL
+);
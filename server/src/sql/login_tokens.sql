@@ -1,6 +1,10 @@
 CREATE TABLE IF NOT EXISTS login_tokens (
-    token_id    INTEGER PRIMARY KEY,
-    token_hash  TEXT NOT NULL,
-    user_id     INTEGER NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
-    last_used   TIMESTAMP WITH TIME ZONE NOT NULL
+    token_id                INTEGER PRIMARY KEY,
+    token_hash              TEXT NOT NULL,
+    user_id                 INTEGER NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    last_used               TIMESTAMP WITH TIME ZONE NOT NULL,
+    authenticated           TIMESTAMP WITH TIME ZONE NOT NULL,
+    refresh_hash            TEXT,
+    previous_refresh_hash   TEXT,
+    refreshed               TIMESTAMP WITH TIME ZONE
 );
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS missed_searches (
+    -- What was searched for, trimmed and in lowercase
+    query          TEXT PRIMARY KEY,
+    times          INTEGER NOT NULL,
+    last_searched  TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS moderation_actions (
+    action_id     INTEGER PRIMARY KEY AUTOINCREMENT,
+    moderator_id  INTEGER NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    -- Whether the suggestion was accepted, rather than rejected
+    accepted      BOOLEAN NOT NULL,
+    time          TIMESTAMP WITH TIME ZONE NOT NULL
+);
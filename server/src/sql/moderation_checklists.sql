@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS moderation_checklists (
+    action_id  INTEGER PRIMARY KEY REFERENCES moderation_actions(action_id) ON DELETE CASCADE,
+    -- The checklist items which were ticked, separated by commas
+    items      TEXT NOT NULL
+);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS notifications (
+    notification_id  INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id          INTEGER NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    -- 1 = suggestion accepted, 2 = suggestion rejected
+    kind             INTEGER NOT NULL,
+    english          TEXT NOT NULL,
+    xhosa            TEXT NOT NULL,
+    -- Set for accepted suggestions and rejected edits, unless the word has since been deleted
+    word_id          INTEGER REFERENCES words(word_id) ON DELETE SET NULL,
+    reason           TEXT,
+    created          TIMESTAMP WITH TIME ZONE NOT NULL,
+    read             BOOLEAN NOT NULL DEFAULT(FALSE)
+);
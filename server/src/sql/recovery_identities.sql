@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS recovery_identities (
+    user_id     INTEGER PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE,
+    issuer      TEXT NOT NULL,
+    oidc_id     TEXT NOT NULL,
+    email       TEXT,
+    linked      TIMESTAMP WITH TIME ZONE NOT NULL,
+    UNIQUE (issuer, oidc_id)
+);
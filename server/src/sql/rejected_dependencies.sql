@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS rejected_dependencies (
+    -- The pending suggested word which was linked to the rejected one
+    suggestion_id  INTEGER NOT NULL REFERENCES word_suggestions(suggestion_id) ON DELETE CASCADE,
+    english        TEXT NOT NULL,
+    xhosa          TEXT NOT NULL
+);
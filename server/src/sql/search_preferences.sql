@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS search_preferences (
+    user_id    INTEGER PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE,
+    -- 0 = both, 1 = English to isiXhosa, 2 = isiXhosa to English
+    direction  INTEGER NOT NULL DEFAULT(0)
+);
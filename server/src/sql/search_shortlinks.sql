@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS search_shortlinks (
+    short    TEXT PRIMARY KEY,
+    -- The search page's query string, e.g query=hamba&part_of_speech=verb
+    query    TEXT NOT NULL UNIQUE,
+    created  TIMESTAMP WITH TIME ZONE NOT NULL
+);
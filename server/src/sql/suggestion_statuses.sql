@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS suggestion_statuses (
+    -- Given to the contributor so that they can check on the suggestion without logging in
+    token          TEXT PRIMARY KEY,
+    -- Not a foreign key, since the suggestion is deleted once it has been reviewed
+    suggestion_id  INTEGER NOT NULL UNIQUE,
+    english        TEXT NOT NULL,
+    xhosa          TEXT NOT NULL,
+    -- 0 = pending, 1 = accepted, 2 = rejected
+    status         INTEGER NOT NULL DEFAULT 0,
+    -- The word which the suggestion was accepted as
+    word_id        INTEGER REFERENCES words(word_id) ON DELETE SET NULL,
+    -- Why the suggestion was rejected, if the moderator gave a reason
+    reason         TEXT,
+    updated        TIMESTAMP WITH TIME ZONE NOT NULL
+);
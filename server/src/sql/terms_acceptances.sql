@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS terms_acceptances (
+    user_id         INTEGER NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    -- The version of the terms of use and contributor license which was accepted
+    version         INTEGER NOT NULL,
+    accepted        TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, version)
+);
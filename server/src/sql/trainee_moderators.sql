@@ -0,0 +1,3 @@
+CREATE TABLE IF NOT EXISTS trainee_moderators (
+    user_id  INTEGER PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_id  INTEGER PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE,
+    -- 0 = follow the browser, 1 = light, 2 = dark
+    theme    INTEGER NOT NULL DEFAULT(0)
+);
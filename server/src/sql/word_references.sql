@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS word_references (
+    reference_id    INTEGER PRIMARY KEY AUTOINCREMENT,
+    word_id         INTEGER NOT NULL REFERENCES words(word_id) ON DELETE CASCADE,
+    kind            INTEGER NOT NULL,
+    author          TEXT NOT NULL,
+    title           TEXT NOT NULL,
+    year            INTEGER,
+    publisher       TEXT,
+    pages           TEXT,
+    url             TEXT,
+    link_checked    TIMESTAMP,
+    -- Checks failed in a row
+    link_failures   INTEGER NOT NULL DEFAULT 0,
+    link_broken_since TIMESTAMP,
+    link_error      TEXT
+);
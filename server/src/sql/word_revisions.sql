@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS word_revisions (
+    revision_id  INTEGER PRIMARY KEY AUTOINCREMENT,
+    word_id      INTEGER NOT NULL REFERENCES words(word_id) ON DELETE CASCADE,
+    -- The user whose suggestion was accepted in this revision
+    user_id      INTEGER REFERENCES users(user_id) ON DELETE SET NULL,
+    -- Whether the word was added by this revision, rather than edited
+    added        BOOLEAN NOT NULL,
+    time         TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- The import batch which the accepted suggestion came from, if any
+    import_batch INTEGER REFERENCES import_batches(batch_id),
+    -- The word as it was before an edit from an import batch, as JSON, so the edit can be undone
+    previous     TEXT
+);
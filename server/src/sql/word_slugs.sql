@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS word_slugs (
+    slug     TEXT PRIMARY KEY,
+    word_id  INTEGER NOT NULL REFERENCES words(word_id) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS word_suggestion_times (
+    suggestion_id  INTEGER PRIMARY KEY REFERENCES word_suggestions(suggestion_id) ON DELETE CASCADE,
+    -- When the suggestion was first submitted. Edits to the suggestion don't change this.
+    submitted      TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -18,5 +18,11 @@ CREATE TABLE IF NOT EXISTS word_suggestions (
     followed_by          TEXT,
     -- 255 is sentinel for "no noun class" as opposed to null which is noun class not changed
     noun_class           INTEGER,
-    note                 TEXT
+    note                 TEXT,
+    is_offensive         BOOLEAN,
+
+    -- How the suggestion arrived: 0 = web form, 1 = import, 2 = API, 3 = anonymous
+    source               INTEGER NOT NULL DEFAULT 0,
+    import_batch         INTEGER REFERENCES import_batches(batch_id),
+    api_key_id           INTEGER REFERENCES api_keys(key_id) ON DELETE SET NULL
 );
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS word_views (
+    word_id  INTEGER NOT NULL REFERENCES words(word_id) ON DELETE CASCADE,
+    day      DATE NOT NULL,
+    views    INTEGER NOT NULL,
+    PRIMARY KEY (word_id, day)
+);
@@ -12,5 +12,6 @@ CREATE TABLE IF NOT EXISTS words (
     transitivity         INTEGER,
     followed_by          TEXT NOT NULL,
     noun_class           INTEGER,
-    note                 TEXT NOT NULL
+    note                 TEXT NOT NULL,
+    is_offensive         BOOLEAN NOT NULL DEFAULT FALSE
 );
@@ -0,0 +1,2 @@
+-- Orders headwords alphabetically, for browsing from a word to its neighbours
+CREATE INDEX IF NOT EXISTS words_alphabetical ON words (xhosa COLLATE NOCASE, word_id);
@@ -1,37 +1,77 @@
-use crate::auth::{with_user_auth, FullUser};
+use crate::auth::FullUser;
+use crate::bans::with_unbanned_user_auth;
+use crate::config::{BodyLimits, SubmissionChallenge};
+use crate::csrf::{csrf_protected_form_with_fields, CSRF_FIELD};
+use crate::database::drafts::Draft;
+use crate::database::provenance::Provenance;
 use crate::database::submit;
-use crate::database::submit::{WordFormTemplate, WordSubmission};
+use crate::database::submit::{SubmissionAction, WordFormTemplate, WordSubmission};
 use crate::database::suggestion::SuggestedWord;
+use crate::details::{homographs, verb_derivation};
+use crate::edit_lock;
+use crate::error::ServerError;
+use crate::flash;
 use crate::i18n::I18nInfo;
 use crate::i18n::SiteContext;
+use crate::proof_of_work;
 use crate::search::TantivyClient;
-use crate::serialization::qs_form;
+use crate::serialization::FormFields;
+use crate::terms::with_terms_accepted_auth;
+use crate::validation::{FieldError, ValidationErrors};
 use crate::{spawn_blocking_child, DebugBoxedExt};
 use askama::Template;
 use isixhosa_click_macros::I18nTemplate;
 use isixhosa_common::auth::Auth;
 use isixhosa_common::database::{DbBase, UserAccessDb};
+use isixhosa_common::flash::{FlashLinkText, FlashMessage};
 use isixhosa_common::format::DisplayHtml;
-use isixhosa_common::language::{NounClassExt, Transitivity};
+use isixhosa_common::language::{NounClassExt, PartOfSpeech, Transitivity};
+use isixhosa_common::templates::{WordDetails, WordPreview};
 use isixhosa_common::types::Dataset;
 use serde::Deserialize;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::sync::Arc;
 use tracing::instrument;
-use warp::{body, path, Filter, Rejection, Reply};
+use warp::http::StatusCode;
+use warp::reply::Response;
+use warp::{body, path, reply, Filter, Rejection, Reply};
 
 #[derive(Template, I18nTemplate, Debug)]
 #[template(path = "submit.askama.html")]
 struct SubmitTemplate {
     auth: Auth,
     i18n_info: I18nInfo,
-    previous_success: Option<bool>,
     action: SubmitFormAction,
     word: WordFormTemplate,
     datasets: Vec<Dataset>,
+    errors: ValidationErrors,
+    /// The moderator already editing the suggestion, if it's locked by someone else
+    edit_locked_by: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PluralOfQuery {
+    plural_of: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct PrefillQuery {
+    english: Option<String>,
+    xhosa: Option<String>,
 }
 
 impl SubmitTemplate {
+    fn field_error(&self, field: &str) -> Option<String> {
+        self.errors.get(field).map(|error| match error {
+            FieldError::Required => self.t("submit.field-required"),
+            FieldError::TooLong { max } => {
+                self.t_with("submit.field-too-long", &crate::i18n_args!("max" => max))
+            }
+            FieldError::ChallengeFailed => self.t("submit.challenge-failed"),
+            FieldError::Spelling => self.t("submit.field-spelling"),
+        })
+    }
+
     fn this_word_id_js(&self) -> String {
         match self.action {
             SubmitFormAction::EditExisting(existing) => existing.to_string(),
@@ -53,6 +93,37 @@ enum SubmitFormAction {
     EditExisting(u64),
 }
 
+impl SubmitFormAction {
+    fn from_submission(submission: &WordSubmission) -> SubmitFormAction {
+        match *submission {
+            WordSubmission {
+                suggestion_id: Some(suggestion_id),
+                suggestion_anchor_ord: Some(suggestion_anchor_ord),
+                existing_id,
+                ..
+            } => SubmitFormAction::EditSuggestion {
+                suggestion_id,
+                existing_id,
+                suggestion_anchor_ord,
+            },
+            WordSubmission {
+                existing_id: Some(existing_id),
+                ..
+            } => SubmitFormAction::EditExisting(existing_id),
+            _ => SubmitFormAction::SubmitNewWord,
+        }
+    }
+
+    /// The route which the form is submitted to
+    fn route(&self) -> String {
+        match self {
+            SubmitFormAction::EditSuggestion { .. } => "/moderation/edit".to_owned(),
+            SubmitFormAction::SubmitNewWord => "/submit".to_owned(),
+            SubmitFormAction::EditExisting(id) => format!("/word/{id}"),
+        }
+    }
+}
+
 impl Display for SubmitFormAction {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
@@ -63,26 +134,32 @@ pub fn submit(
     db: DbBase,
     tantivy: Arc<TantivyClient>,
     site_ctx: Arc<SiteContext>,
+    challenge: SubmissionChallenge,
+    body_limits: BodyLimits,
 ) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let plural_page = warp::get()
+        .and(warp::query())
+        .and(with_terms_accepted_auth(db.clone(), site_ctx.clone()))
+        .and_then(submit_plural_page);
+
+    let prefill_page = warp::get()
+        .and(warp::query())
+        .and(with_terms_accepted_auth(db.clone(), site_ctx.clone()))
+        .and_then(submit_prefilled_page);
+
     let submit_page = warp::get()
-        .and(warp::any().map(|| None)) // previous_success is none
         .and(warp::any().map(SubmitFormAction::default))
-        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and(with_terms_accepted_auth(db.clone(), site_ctx.clone()))
         .and_then(submit_word_page);
 
-    let submit_form = body::content_length_limit(64 * 1024)
-        .and(warp::any().map(move || tantivy.clone()))
-        .and(qs_form())
-        .and(with_user_auth(db.clone(), site_ctx.clone()))
+    let submit_form = body::content_length_limit(body_limits.submission)
+        .and(warp::any().map(move || (tantivy.clone(), challenge)))
+        .untuple_one()
+        .and(csrf_protected_form_with_fields())
+        .and(with_unbanned_user_auth(db, site_ctx))
         .and_then(submit_new_word_form);
 
-    let failed_to_submit = warp::any()
-        .and(warp::any().map(|| Some(false))) // previous_success is Some(false)
-        .and(warp::any().map(SubmitFormAction::default))
-        .and(with_user_auth(db, site_ctx.clone()))
-        .and_then(submit_word_page);
-
-    let submit_routes = submit_page.or(submit_form).or(failed_to_submit);
+    let submit_routes = plural_page.or(prefill_page).or(submit_page).or(submit_form);
 
     warp::path("submit")
         .and(path::end())
@@ -103,10 +180,11 @@ pub async fn edit_suggestion_page(
         SuggestedWord::fetch_existing_id_for_suggestion(&db_clone, suggestion_id)
     })
     .await
-    .unwrap();
+    .map_err(ServerError::from)?;
 
-    submit_word_page(
-        None,
+    let edit_locked_by = edit_lock::lock_suggestion(suggestion_id, &user);
+
+    let mut template = submit_word_page(
         SubmitFormAction::EditSuggestion {
             suggestion_id,
             existing_id,
@@ -116,36 +194,29 @@ pub async fn edit_suggestion_page(
         i18n_info,
         db,
     )
-    .await
+    .await?;
+
+    template.edit_locked_by = edit_locked_by;
+    Ok(template)
 }
 
-#[instrument(name = "Display edit word page", skip(user, db, previous_success))]
+#[instrument(name = "Display edit word page", skip(user, db))]
 pub async fn edit_word_page(
-    previous_success: Option<bool>,
     id: u64,
     user: FullUser,
     i18n_info: I18nInfo,
     db: impl UserAccessDb,
 ) -> Result<impl Reply, Rejection> {
-    submit_word_page(
-        previous_success,
-        SubmitFormAction::EditExisting(id),
-        user,
-        i18n_info,
-        db,
-    )
-    .await
+    submit_word_page(SubmitFormAction::EditExisting(id), user, i18n_info, db).await
 }
 
-// TODO(form validation): server side form validation
 #[instrument(name = "Display submit word page", skip_all)]
 async fn submit_word_page(
-    previous_success: Option<bool>,
     action: SubmitFormAction,
     user: FullUser,
     i18n_info: I18nInfo,
     db: impl UserAccessDb,
-) -> Result<impl Reply, Rejection> {
+) -> Result<SubmitTemplate, Rejection> {
     let i18n_clone = i18n_info.clone();
     let db = db.clone();
     let (word, datasets) = spawn_blocking_child(move || {
@@ -166,33 +237,273 @@ async fn submit_word_page(
         (template, datasets)
     })
     .await
-    .unwrap();
+    .map_err(ServerError::from)?;
 
     Ok(SubmitTemplate {
         auth: user.into(),
         i18n_info: i18n_clone,
-        previous_success,
         action,
         word,
         datasets,
+        errors: ValidationErrors::default(),
+        edit_locked_by: None,
+    })
+}
+
+/// The submit page pre-filled with the plural of a singular noun the user just submitted
+#[instrument(name = "Display submit plural page", skip(user, i18n_info, db))]
+async fn submit_plural_page(
+    query: PluralOfQuery,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<SubmitTemplate, Rejection> {
+    let (i18n_clone, user_id) = (i18n_info.clone(), user.id);
+    let (word, datasets) = spawn_blocking_child(move || {
+        let template = WordFormTemplate::plural_of(&db, &i18n_info, query.plural_of, user_id);
+        (template, Dataset::fetch_all(&db))
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    Ok(SubmitTemplate {
+        auth: user.into(),
+        i18n_info: i18n_clone,
+        action: SubmitFormAction::SubmitNewWord,
+        word: word.ok_or_else(warp::reject::not_found)?,
+        datasets,
+        errors: ValidationErrors::default(),
+        edit_locked_by: None,
+    })
+}
+
+/// The submit page pre-filled with a word which was searched for but not found, linked to from the
+/// contributor tasks page
+#[instrument(name = "Display pre-filled submit page", skip(user, i18n_info, db))]
+async fn submit_prefilled_page(
+    query: PrefillQuery,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<SubmitTemplate, Rejection> {
+    if query.english.is_none() && query.xhosa.is_none() {
+        return Err(warp::reject::not_found());
+    }
+
+    let datasets = spawn_blocking_child(move || Dataset::fetch_all(&db))
+        .await
+        .map_err(ServerError::from)?;
+
+    Ok(SubmitTemplate {
+        auth: user.into(),
+        i18n_info,
+        action: SubmitFormAction::SubmitNewWord,
+        word: WordFormTemplate {
+            english: query.english.unwrap_or_default(),
+            xhosa: query.xhosa.unwrap_or_default(),
+            ..Default::default()
+        },
+        datasets,
+        errors: ValidationErrors::default(),
+        edit_locked_by: None,
     })
 }
 
+/// Checks a submitted word form before it is saved. If the submission is invalid, or the user asked
+/// to preview it, save it as a draft, or go back to editing it, the page to show instead is
+/// returned.
+pub async fn review_submission(
+    submission: &WordSubmission,
+    fields: FormFields,
+    user: &FullUser,
+    i18n_info: &I18nInfo,
+    db: &impl UserAccessDb,
+) -> Result<Option<Response>, Rejection> {
+    let (submission, user, i18n_info, db) = (
+        submission.clone(),
+        user.clone(),
+        i18n_info.clone(),
+        db.clone(),
+    );
+
+    let errors = match (
+        submission.action,
+        submission.validate(&i18n_info.ctx.language),
+    ) {
+        (SubmissionAction::Draft, _) => {
+            return save_draft(submission, fields, user, db).await.map(Some);
+        }
+        (SubmissionAction::Submit, Ok(())) => return Ok(None),
+        (SubmissionAction::Preview, Ok(())) => {
+            return preview_page(submission, fields, user, i18n_info, db)
+                .await
+                .map(Some);
+        }
+        (SubmissionAction::Edit, Ok(())) => ValidationErrors::default(),
+        (_, Err(errors)) => errors,
+    };
+
+    submission_form_page(submission, errors, user, i18n_info, db)
+        .await
+        .map(Some)
+}
+
+#[instrument(name = "Save word form as draft", skip_all)]
+async fn save_draft(
+    submission: WordSubmission,
+    fields: FormFields,
+    user: FullUser,
+    db: impl UserAccessDb,
+) -> Result<Response, Rejection> {
+    let form = fields
+        .without(&[CSRF_FIELD, "action", "draft_id"])
+        .to_urlencoded();
+    let (user_id, draft_id) = (user.id.get(), submission.draft_id);
+    let (english, xhosa) = (submission.english, submission.xhosa);
+
+    let draft_id =
+        spawn_blocking_child(move || Draft::save(&db, user_id, draft_id, &english, &xhosa, &form))
+            .await
+            .map_err(ServerError::from)?;
+
+    let saved = FlashMessage::info("submit.draft-saved");
+    Ok(flash::redirect(&format!("/drafts/{draft_id}"), vec![saved]))
+}
+
+/// Show the form again with the user's input and anything they need to fix
+#[instrument(name = "Display word form from submission", skip_all)]
+pub async fn submission_form_page(
+    submission: WordSubmission,
+    errors: ValidationErrors,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<Response, Rejection> {
+    let action = SubmitFormAction::from_submission(&submission);
+
+    let i18n_clone = i18n_info.clone();
+    let (word, datasets) = spawn_blocking_child(move || {
+        let template = WordFormTemplate::from_submission(&db, &i18n_info, submission);
+        let datasets = Dataset::fetch_all(&db);
+        (template, datasets)
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    let status = if errors.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+
+    let template = SubmitTemplate {
+        auth: user.into(),
+        i18n_info: i18n_clone,
+        action,
+        word,
+        datasets,
+        errors,
+        edit_locked_by: None,
+    };
+
+    Ok(reply::with_status(template, status).into_response())
+}
+
+#[instrument(name = "Display word submission preview", skip_all)]
+async fn preview_page(
+    submission: WordSubmission,
+    fields: FormFields,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<Response, Rejection> {
+    let route = SubmitFormAction::from_submission(&submission).route();
+
+    let (user_clone, db_clone) = (user.clone(), db.clone());
+    let word = spawn_blocking_child(move || submission.into_preview(&db_clone, &user_clone))
+        .await
+        .map_err(ServerError::from)?;
+    let derivation = verb_derivation(&word, db.clone()).await?;
+    let homographs = homographs(&word, db).await?;
+
+    let details = WordDetails {
+        auth: user.into(),
+        i18n_info,
+        word,
+        views: None,
+        classroom: None,
+        preview: Some(WordPreview {
+            route,
+            fields: fields.without(&[CSRF_FIELD, "action"]).0,
+            accept: None,
+        }),
+        derivation,
+        homographs,
+        neighbours: Default::default(),
+    };
+
+    Ok(details.into_response())
+}
+
 #[instrument(name = "Submit word form", skip_all)]
 async fn submit_new_word_form(
     tantivy: Arc<TantivyClient>,
-    word: WordSubmission,
+    challenge: SubmissionChallenge,
+    mut word: WordSubmission,
+    fields: FormFields,
     user: FullUser,
     i18n_info: I18nInfo,
     db: impl UserAccessDb,
-) -> Result<impl Reply, Rejection> {
-    submit::submit_suggestion(word, tantivy, &user, &db, i18n_info.clone()).await;
-    submit_word_page(
-        Some(true),
-        SubmitFormAction::SubmitNewWord,
-        user,
-        i18n_info,
-        db,
+) -> Result<Response, Rejection> {
+    word.canonicalize();
+
+    // Drafts and previews don't need the challenge to be solved, only the final submission. It can
+    // only be used once, so it isn't checked until the form is otherwise valid, so that fixing a
+    // mistake in the form doesn't mean solving a new one.
+    if let Some(page) = review_submission(&word, fields.clone(), &user, &i18n_info, &db).await? {
+        return Ok(page);
+    }
+
+    if !proof_of_work::verify(challenge, &fields) {
+        let mut errors = ValidationErrors::default();
+        errors.add(proof_of_work::NONCE_FIELD, FieldError::ChallengeFailed);
+        return submission_form_page(word, errors, user, i18n_info, db).await;
+    }
+
+    let is_new_singular_noun = word.existing_id.is_none()
+        && word.suggestion_id.is_none()
+        && word.part_of_speech == Some(PartOfSpeech::Noun)
+        && !word.is_plural;
+    let plural = word
+        .noun_class
+        .filter(|_| is_new_singular_noun)
+        .and_then(|class| i18n_info.ctx.language.rules.pluralize(class, &word.xhosa));
+
+    let submitted = submit::submit_suggestion(
+        word,
+        tantivy,
+        &user,
+        &db,
+        i18n_info.clone(),
+        Provenance::WebForm,
     )
-    .await
+    .await?;
+    let mut messages = vec![FlashMessage::success("submit.submit-success")];
+
+    if let Some(submitted) = submitted {
+        messages.push(flash::status_link(&submitted.status_token, &i18n_info));
+
+        // The plural's form is pre-filled from the singular noun's suggestion
+        if let Some(plural) = plural {
+            let companion = FlashMessage::info("submit.plural-companion")
+                .with_arg("plural", plural)
+                .with_link(
+                    format!("/submit?plural_of={}", submitted.suggestion_id),
+                    FlashLinkText::Key("submit.plural-companion-link".to_owned()),
+                );
+            messages.push(companion);
+        }
+    }
+
+    Ok(flash::redirect("/submit", messages))
 }
@@ -0,0 +1,53 @@
+use crate::auth::with_any_auth;
+use crate::database::status::{ReviewState, SuggestionStatus};
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, PublicAccessDb};
+use std::sync::Arc;
+use tracing::instrument;
+use warp::{Filter, Rejection, Reply};
+
+/// Lets contributors see what happened to their suggestion, without needing to log in
+#[derive(Template, I18nTemplate, Debug)]
+#[template(path = "suggestion_status.askama.html")]
+struct SuggestionStatusTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    status: SuggestionStatus,
+}
+
+pub fn suggestion_status(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    warp::path!["suggestion-status" / String]
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_any_auth(db, site_ctx))
+        .and_then(suggestion_status_page)
+        .debug_boxed()
+}
+
+#[instrument(name = "Display suggestion status page", skip_all)]
+async fn suggestion_status_page(
+    token: String,
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let status = spawn_blocking_child(move || SuggestionStatus::fetch(&db, &token))
+        .await
+        .unwrap();
+
+    match status {
+        Some(status) => Ok(SuggestionStatusTemplate {
+            auth,
+            i18n_info,
+            status,
+        }),
+        None => Err(warp::reject::not_found()),
+    }
+}
@@ -0,0 +1,75 @@
+//! Lists small, specific tasks for contributors, each linking straight to the form where it can be
+//! done: filling in what's missing from existing words, and adding words which were searched for
+//! but not found
+
+use crate::auth::with_any_auth;
+use crate::database::tasks::{fetch_missed_searches, fetch_word_tasks, MissedSearch, WordTask};
+use crate::error::ServerError;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::{spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, PublicAccessDb};
+use isixhosa_common::types::WordHit;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use std::sync::Arc;
+use tracing::instrument;
+use warp::{Filter, Rejection, Reply};
+
+/// How many of each kind of task to list
+const TASKS_PER_KIND: u64 = 20;
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "contribute_tasks.askama.html")]
+struct TasksTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    word_tasks: Vec<(WordTask, Vec<WordHit>)>,
+    missed_searches: Vec<MissedSearch>,
+}
+
+impl TasksTemplate {
+    fn submit_link(&self, field: &str, query: &str) -> String {
+        format!(
+            "/submit?{field}={}",
+            utf8_percent_encode(query, NON_ALPHANUMERIC)
+        )
+    }
+}
+
+pub fn tasks(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    warp::path!["contribute" / "tasks"]
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_any_auth(db, site_ctx))
+        .and_then(tasks_page)
+        .debug_boxed()
+}
+
+#[instrument(name = "Show contributor tasks", skip_all)]
+async fn tasks_page(
+    auth: Auth,
+    i18n_info: I18nInfo,
+    db: impl PublicAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let (word_tasks, missed_searches) = spawn_blocking_child(move || {
+        let word_tasks = WordTask::ALL
+            .into_iter()
+            .map(|task| (task, fetch_word_tasks(&db, task, TASKS_PER_KIND)))
+            .collect();
+        (word_tasks, fetch_missed_searches(&db, TASKS_PER_KIND))
+    })
+    .await
+    .map_err(ServerError::from)?;
+
+    Ok(TasksTemplate {
+        auth,
+        i18n_info,
+        word_tasks,
+        missed_searches,
+    })
+}
@@ -0,0 +1,205 @@
+//! Contributors must accept the terms of use and the license which their contributions are made
+//! under before submitting anything. Which version of the terms each user accepted is recorded, so
+//! that when the terms change, everyone is asked to accept them again before submitting more.
+
+use crate::auth::{with_user_auth, FullUser};
+use crate::config::BodyLimits;
+use crate::csrf::csrf_protected_form;
+use crate::database::terms::{has_accepted, record_acceptance};
+use crate::error::ServerError;
+use crate::i18n::{I18nInfo, SiteContext};
+use crate::serialization::{deserialize_checkbox, false_fn, query_form};
+use crate::{flash, spawn_blocking_child, DebugBoxedExt};
+use askama::Template;
+use isixhosa_click_macros::I18nTemplate;
+use isixhosa_common::auth::Auth;
+use isixhosa_common::database::{DbBase, UserAccessDb};
+use isixhosa_common::flash::FlashMessage;
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+use warp::http::Method;
+use warp::path::FullPath;
+use warp::{body, Filter, Rejection, Reply};
+
+/// The version of the terms of use and contributor license. Bump this whenever either changes, so
+/// that contributors are asked to accept the new terms.
+pub const TERMS_VERSION: u32 = 1;
+
+/// The user hasn't accepted the current terms, so can't submit anything until they do
+#[derive(Debug)]
+pub struct TermsNotAccepted {
+    /// Where to go back to once the terms are accepted
+    pub redirect: String,
+}
+
+impl warp::reject::Reject for TermsNotAccepted {}
+
+impl TermsNotAccepted {
+    pub fn accept_url(&self) -> String {
+        format!(
+            "/terms_of_use/accept?redirect={}",
+            percent_encoding::utf8_percent_encode(
+                &self.redirect,
+                percent_encoding::NON_ALPHANUMERIC
+            )
+        )
+    }
+}
+
+/// The page to go back to after accepting the terms. Forms can't be submitted again by redirecting
+/// to them, so only pages which were being viewed are returned to.
+fn return_to() -> impl Filter<Extract = (String,), Error = Infallible> + Clone {
+    let query = warp::query::raw().or(warp::any().map(String::new)).unify();
+
+    warp::method().and(warp::path::full()).and(query).map(
+        |method: Method, path: FullPath, query: String| {
+            if method != Method::GET {
+                String::new()
+            } else if query.is_empty() {
+                path.as_str().to_owned()
+            } else {
+                format!("{}?{query}", path.as_str())
+            }
+        },
+    )
+}
+
+/// Like [`with_user_auth`], but rejects with [`TermsNotAccepted`] if the user hasn't accepted the
+/// current version of the terms. Used on the pages for submitting, as well as by
+/// [`with_unbanned_user_auth`](crate::bans::with_unbanned_user_auth) on every route which writes.
+pub fn with_terms_accepted_auth(
+    db: DbBase,
+    ctx: Arc<SiteContext>,
+) -> impl Filter<Extract = (FullUser, I18nInfo, impl UserAccessDb), Error = Rejection> + Clone {
+    with_user_auth(db, ctx)
+        .and(return_to())
+        .and_then(check_terms_accepted)
+        .untuple_one()
+}
+
+#[instrument(name = "Check whether user accepted terms", fields(user_id = %user.id), skip_all)]
+async fn check_terms_accepted<D: UserAccessDb>(
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: D,
+    redirect: String,
+) -> Result<(FullUser, I18nInfo, D), Rejection> {
+    let (db_clone, user_id) = (db.clone(), user.id.get());
+    let accepted = spawn_blocking_child(move || has_accepted(&db_clone, user_id, TERMS_VERSION))
+        .await
+        .map_err(ServerError::from)?
+        .map_err(ServerError::from)?;
+
+    if accepted {
+        Ok((user, i18n_info, db))
+    } else {
+        debug!("User hasn't accepted the current terms");
+        Err(warp::reject::custom(TermsNotAccepted { redirect }))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct AcceptQuery {
+    #[serde(default)]
+    redirect: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct AcceptForm {
+    #[serde(default)]
+    redirect: String,
+    #[serde(default = "false_fn")]
+    #[serde(deserialize_with = "deserialize_checkbox")]
+    license_agree: bool,
+    #[serde(default = "false_fn")]
+    #[serde(deserialize_with = "deserialize_checkbox")]
+    tou_agree: bool,
+}
+
+#[derive(Template, I18nTemplate)]
+#[template(path = "terms_accept.askama.html")]
+struct AcceptTermsTemplate {
+    auth: Auth,
+    i18n_info: I18nInfo,
+    redirect: String,
+    did_not_agree: bool,
+}
+
+pub fn terms(
+    db: DbBase,
+    site_ctx: Arc<SiteContext>,
+    body_limits: BodyLimits,
+) -> impl Filter<Error = Rejection, Extract = impl Reply> + Clone {
+    let page = warp::get()
+        .and(query_form())
+        .and(with_user_auth(db.clone(), site_ctx.clone()))
+        .and_then(accept_terms_page);
+
+    let submit = warp::post()
+        .and(body::content_length_limit(body_limits.form))
+        .and(csrf_protected_form())
+        .and(with_user_auth(db, site_ctx))
+        .and_then(accept_terms_form);
+
+    warp::path!["terms_of_use" / "accept"]
+        .and(warp::path::end())
+        .and(page.or(submit))
+        .debug_boxed()
+}
+
+/// Only return to pages on this site
+fn local_redirect(redirect: &str) -> &str {
+    if redirect.starts_with('/') && !redirect.starts_with("//") {
+        redirect
+    } else {
+        "/"
+    }
+}
+
+#[instrument(name = "Show accept terms page", skip_all)]
+async fn accept_terms_page(
+    query: AcceptQuery,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    _db: impl UserAccessDb,
+) -> Result<impl Reply, Rejection> {
+    Ok(AcceptTermsTemplate {
+        auth: user.into(),
+        i18n_info,
+        redirect: local_redirect(&query.redirect).to_owned(),
+        did_not_agree: false,
+    })
+}
+
+#[instrument(name = "Submit accept terms form", fields(user_id = %user.id), skip_all)]
+async fn accept_terms_form(
+    form: AcceptForm,
+    user: FullUser,
+    i18n_info: I18nInfo,
+    db: impl UserAccessDb,
+) -> Result<impl Reply, Rejection> {
+    let redirect = local_redirect(&form.redirect).to_owned();
+
+    if !form.license_agree || !form.tou_agree {
+        return Ok(AcceptTermsTemplate {
+            auth: user.into(),
+            i18n_info,
+            redirect,
+            did_not_agree: true,
+        }
+        .into_response());
+    }
+
+    let user_id = user.id.get();
+    spawn_blocking_child(move || record_acceptance(&db, user_id, TERMS_VERSION))
+        .await
+        .map_err(ServerError::from)?
+        .map_err(ServerError::from)?;
+
+    Ok(flash::redirect(
+        &redirect,
+        vec![FlashMessage::success("terms-acceptance.accepted")],
+    ))
+}
@@ -0,0 +1,283 @@
+//! Serves the site's routes from a throwaway database and search index, so that whole flows such as
+//! suggesting, moderating, and searching for a word can be tested through the same requests that a
+//! browser would make.
+
+use crate::auth::{random_string_token, StaySignedInToken, STAY_LOGGED_IN_COOKIE};
+use crate::config::SearchReloadPolicy;
+use crate::csrf::{CsrfToken, CSRF_FIELD};
+use crate::database::terms::record_acceptance;
+use crate::fragment_cache::FragmentCache;
+use crate::i18n::SiteContext;
+use crate::scheduler::Scheduler;
+use crate::search::TantivyClient;
+use crate::terms::TERMS_VERSION;
+use crate::{i18n, open_db_pool, routes, set_up_db, Config};
+use isixhosa_common::auth::Permissions;
+use isixhosa_common::database::db_impl::DbImpl;
+use isixhosa_common::database::DbBase;
+use isixhosa_common::types::WordHit;
+use rusqlite::{params, OptionalExtension};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use tempdir::TempDir;
+use url::form_urlencoded;
+use warp::filters::BoxedFilter;
+use warp::http::header::{CONTENT_TYPE, COOKIE, LOCATION};
+use warp::http::StatusCode;
+use warp::hyper::body::Bytes;
+use warp::reply::Response;
+use warp::{Filter, Rejection};
+
+const SITE: &str = "isixhosa";
+
+/// The word form's fields for suggesting `hamba` (to go)
+pub const HAMBA: &[(&str, &str)] = &[
+    ("english", "go"),
+    ("xhosa", "hamba"),
+    ("part_of_speech", "verb"),
+    ("note", ""),
+    ("xhosa_tone_markings", ""),
+    ("infinitive", ""),
+    ("transitivity", ""),
+    ("followed_by", ""),
+];
+
+/// Check that the response redirects to the path, as forms which were handled successfully do
+pub fn assert_redirects(response: &warp::http::Response<Bytes>, to: &str) {
+    assert_eq!(response.status(), StatusCode::SEE_OTHER, "{response:?}");
+    assert_eq!(response.headers()[LOCATION], to);
+}
+
+/// The site context can only be loaded once per process, so it's shared by every test
+fn site_ctx() -> Arc<SiteContext> {
+    static SITE_CTX: OnceLock<Arc<SiteContext>> = OnceLock::new();
+
+    SITE_CTX
+        .get_or_init(|| Arc::new(i18n::load(SITE.to_owned(), &Config::default())))
+        .clone()
+}
+
+pub struct TestSite {
+    pub db: DbBase,
+    routes: BoxedFilter<(Response,)>,
+    // Deleted once the site is dropped
+    _dir: TempDir,
+}
+
+/// A signed in user, whose cookie and CSRF token are sent along with each request made as them
+pub struct Session {
+    cookie: String,
+    csrf_token: String,
+}
+
+impl TestSite {
+    /// Start the site with an empty database and search index. Signing in through the OIDC provider
+    /// isn't possible, so users are signed in with [`TestSite::sign_in_as`] instead.
+    pub async fn start() -> TestSite {
+        let dir = TempDir::new("isixhosa_click_test").unwrap();
+        let cfg = Config {
+            database_path: dir.path().join("isixhosa_click.db"),
+            tantivy_path: dir.path().join("tantivy_data"),
+            // Otherwise words wouldn't be searchable straight after they're accepted
+            tantivy_reload_policy: SearchReloadPolicy::Immediate,
+            search_cache_size: 0,
+            server_source_path: PathBuf::from(env!("CARGO_MANIFEST_DIR")),
+            cert_path: None,
+            key_path: None,
+            ..Config::default()
+        };
+        std::fs::create_dir(&cfg.tantivy_path).unwrap();
+
+        let pool = open_db_pool(&cfg).unwrap();
+        set_up_db(&pool.get().unwrap()).unwrap();
+
+        let fragments = Arc::new(FragmentCache::default());
+        let tantivy = TantivyClient::start(&cfg, pool.clone(), fragments.clone())
+            .await
+            .unwrap();
+        let db = DbBase::new(pool);
+
+        let sign_in = warp::any()
+            .and_then(|| async { Err::<Response, Rejection>(warp::reject::not_found()) });
+        let routes = routes(
+            &cfg,
+            SITE,
+            db.clone(),
+            tantivy,
+            fragments,
+            site_ctx(),
+            Scheduler::default().start(),
+            sign_in,
+        )
+        .await
+        .unwrap();
+
+        TestSite {
+            db,
+            routes: routes.boxed(),
+            _dir: dir,
+        }
+    }
+
+    /// Create a user with the given role who has accepted the terms of use, and sign them in
+    pub fn sign_in_as(&self, permissions: Permissions) -> Session {
+        const INSERT: &str = "
+            INSERT INTO users
+                (oidc_id, username, display_name, email, is_moderator, is_administrator, locked)
+            VALUES (?1, ?2, TRUE, ?3, ?4, ?5, FALSE) RETURNING user_id;
+        ";
+
+        let db = DbImpl(self.db.primary.clone());
+        let username = format!("test_{}", permissions.name());
+        let user_id: u64 = self
+            .db
+            .primary
+            .get()
+            .unwrap()
+            .query_row(
+                INSERT,
+                params![
+                    random_string_token(),
+                    username,
+                    format!("{username}@example.com"),
+                    permissions.contains(Permissions::Moderator),
+                    permissions.contains(Permissions::Administrator),
+                ],
+                |row| row.get("user_id"),
+            )
+            .unwrap();
+        record_acceptance(&db, user_id, TERMS_VERSION).unwrap();
+
+        let token = StaySignedInToken::new(&db, user_id);
+        let value = serde_json::to_string(&(&token.token, token.token_id)).unwrap();
+
+        Session {
+            cookie: format!("{STAY_LOGGED_IN_COOKIE}={value}"),
+            csrf_token: CsrfToken::for_session(&token).into(),
+        }
+    }
+
+    pub async fn get(&self, path: &str, session: Option<&Session>) -> warp::http::Response<Bytes> {
+        let mut request = warp::test::request().method("GET").path(path);
+
+        if let Some(session) = session {
+            request = request.header(COOKIE, &session.cookie);
+        }
+
+        request.reply(&self.routes).await
+    }
+
+    /// Submit a url-encoded form as the user, along with their CSRF token
+    pub async fn post_form(
+        &self,
+        path: &str,
+        session: &Session,
+        fields: &[(&str, &str)],
+    ) -> warp::http::Response<Bytes> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .append_pair(CSRF_FIELD, &session.csrf_token)
+            .finish();
+
+        warp::test::request()
+            .method("POST")
+            .path(path)
+            .header(COOKIE, &session.cookie)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body)
+            .reply(&self.routes)
+            .await
+    }
+
+    /// Accept or reject the suggested word as the user, with the method as on the moderation page
+    pub async fn moderate(
+        &self,
+        session: &Session,
+        suggestion_id: u64,
+        method: &str,
+    ) -> warp::http::Response<Bytes> {
+        let suggestion_id = suggestion_id.to_string();
+        let fields = [
+            ("suggestion_type", "word"),
+            ("suggestion", suggestion_id.as_str()),
+            ("method", method),
+            ("suggestion_anchor_ord", "0"),
+        ];
+
+        self.post_form("/moderation", session, &fields).await
+    }
+
+    /// Upload a file through a multipart form as the user, along with their CSRF token
+    pub async fn upload(
+        &self,
+        path: &str,
+        session: &Session,
+        field: &str,
+        filename: &str,
+        contents: &str,
+    ) -> warp::http::Response<Bytes> {
+        const BOUNDARY: &str = "isixhosa-click-test-boundary";
+
+        let body = format!(
+            "--{BOUNDARY}\r\n\
+             Content-Disposition: form-data; name=\"{CSRF_FIELD}\"\r\n\r\n\
+             {csrf_token}\r\n\
+             --{BOUNDARY}\r\n\
+             Content-Disposition: form-data; name=\"{field}\"; filename=\"{filename}\"\r\n\
+             Content-Type: text/csv\r\n\r\n\
+             {contents}\r\n\
+             --{BOUNDARY}--\r\n",
+            csrf_token = session.csrf_token,
+        );
+
+        warp::test::request()
+            .method("POST")
+            .path(path)
+            .header(COOKIE, &session.cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(body)
+            .reply(&self.routes)
+            .await
+    }
+
+    /// The accepted words which a search for the query finds
+    pub async fn search(&self, query: &str) -> Vec<WordHit> {
+        let query: String = form_urlencoded::byte_serialize(query.as_bytes()).collect();
+        let response = self
+            .get(&format!("/search?query={query}&raw=true"), None)
+            .await;
+        serde_json::from_slice(response.body()).unwrap()
+    }
+
+    /// The IDs of the suggested words which are waiting to be moderated
+    pub fn pending_suggestions(&self) -> Vec<u64> {
+        let conn = self.db.primary.get().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT suggestion_id FROM word_suggestions ORDER BY suggestion_id;")
+            .unwrap();
+        let ids = stmt
+            .query_map(params![], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        ids
+    }
+
+    /// The ID of the accepted word with the given isiXhosa, if there is one
+    pub fn word_id(&self, xhosa: &str) -> Option<u64> {
+        self.db
+            .primary
+            .get()
+            .unwrap()
+            .query_row(
+                "SELECT word_id FROM words WHERE xhosa = ?1;",
+                params![xhosa],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap()
+    }
+}
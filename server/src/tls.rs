@@ -0,0 +1,166 @@
+//! Serving HTTPS directly, for deployments without a reverse proxy. Unlike warp's own TLS server,
+//! the certificate is reloaded when its files change, so that a renewed certificate is picked up
+//! without restarting the server.
+
+use crate::listener::{Handshake, Listener};
+use crate::scheduler::{Schedule, Scheduler};
+use anyhow::{anyhow, Context, Result};
+use futures::future::{BoxFuture, FutureExt};
+use std::fmt::{self, Debug, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tokio_rustls::rustls::{crypto, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tracing::info;
+
+/// Clients which haven't finished the handshake by now are disconnected
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Hands out the most recently loaded certificate to each handshake
+pub struct CertResolver {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    current: RwLock<Arc<CertifiedKey>>,
+    /// When the certificate and key files were last modified, as of the last load
+    modified: Mutex<(SystemTime, SystemTime)>,
+}
+
+impl Debug for CertResolver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CertResolver")
+            .field("cert_path", &self.cert_path)
+            .field("key_path", &self.key_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+impl CertResolver {
+    pub fn load(cert_path: &Path, key_path: &Path) -> Result<CertResolver> {
+        let modified = (modified(cert_path)?, modified(key_path)?);
+
+        Ok(CertResolver {
+            cert_path: cert_path.to_owned(),
+            key_path: key_path.to_owned(),
+            current: RwLock::new(Arc::new(load_certified_key(cert_path, key_path)?)),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    /// Load the certificate again if either of its files has changed since it was last loaded.
+    /// If the new files can't be loaded, e.g because only one of them has been replaced so far,
+    /// the old certificate is kept and loading is tried again next time.
+    fn reload_if_changed(&self) -> Result<()> {
+        let modified = (modified(&self.cert_path)?, modified(&self.key_path)?);
+
+        if *self.modified.lock().unwrap() == modified {
+            return Ok(());
+        }
+
+        let key = load_certified_key(&self.cert_path, &self.key_path)?;
+        *self.current.write().unwrap() = Arc::new(key);
+        *self.modified.lock().unwrap() = modified;
+
+        info!("Reloaded TLS certificate from {:?}", self.cert_path);
+        Ok(())
+    }
+}
+
+fn modified(path: &Path) -> Result<SystemTime> {
+    let metadata = std::fs::metadata(path).with_context(|| format!("Couldn't read {:?}", path))?;
+    Ok(metadata.modified()?)
+}
+
+fn load_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey> {
+    let mut certs = BufReader::new(
+        File::open(cert_path).with_context(|| format!("Couldn't open {:?}", cert_path))?,
+    );
+    let certs = rustls_pemfile::certs(&mut certs)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Invalid certificate in {:?}", cert_path))?;
+
+    if certs.is_empty() {
+        return Err(anyhow!("No certificates found in {:?}", cert_path));
+    }
+
+    let mut key = BufReader::new(
+        File::open(key_path).with_context(|| format!("Couldn't open {:?}", key_path))?,
+    );
+    let key = rustls_pemfile::private_key(&mut key)
+        .with_context(|| format!("Invalid private key in {:?}", key_path))?
+        .ok_or_else(|| anyhow!("No private key found in {:?}", key_path))?;
+    let key = crypto::ring::sign::any_supported_type(&key)
+        .with_context(|| format!("Unsupported private key in {:?}", key_path))?;
+
+    Ok(CertifiedKey::new(certs, key))
+}
+
+/// Check for a renewed certificate every `interval_mins` minutes
+pub fn register(scheduler: &mut Scheduler, resolver: Arc<CertResolver>, interval_mins: u64) {
+    let schedule = Schedule::Every(Duration::from_secs(interval_mins * 60));
+
+    scheduler.register("tls-certificate-reload", schedule, move || {
+        let resolver = resolver.clone();
+        async move { tokio::task::spawn_blocking(move || resolver.reload_if_changed()).await? }
+    });
+}
+
+/// Accepts TLS connections, using whichever certificate the resolver last loaded
+pub struct TlsListener {
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+}
+
+impl TlsListener {
+    pub async fn bind(port: u16, resolver: Arc<CertResolver>) -> Result<TlsListener> {
+        let mut config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_cert_resolver(resolver);
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        let listener = TcpListener::bind(([0, 0, 0, 0], port))
+            .await
+            .with_context(|| format!("Failed to bind HTTPS port {}", port))?;
+
+        Ok(TlsListener {
+            listener,
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+}
+
+impl Listener for TlsListener {
+    type Conn = TlsStream<TcpStream>;
+
+    #[allow(clippy::type_complexity)]
+    fn accept(&mut self) -> BoxFuture<'_, io::Result<(Handshake<Self::Conn>, Option<SocketAddr>)>> {
+        async move {
+            let (stream, addr) = self.listener.accept().await?;
+            let accept = self.acceptor.accept(stream);
+
+            let handshake = async move {
+                tokio::time::timeout(HANDSHAKE_TIMEOUT, accept)
+                    .await
+                    .map_err(|_| {
+                        io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out")
+                    })?
+            };
+
+            Ok((handshake.boxed(), Some(addr)))
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,46 @@
+//! Serving plain HTTP on a Unix domain socket rather than a TCP port, for deployments where the
+//! reverse proxy runs on the same host.
+
+use crate::config::UnixSocketConfig;
+use anyhow::{bail, Context, Result};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::Path;
+use tokio::net::UnixListener;
+use tracing::info;
+
+/// Bind the socket, replacing a socket file left behind by a previous run
+pub fn bind(cfg: &UnixSocketConfig) -> Result<UnixListener> {
+    remove_stale_socket(&cfg.path)?;
+
+    let listener = UnixListener::bind(&cfg.path)
+        .with_context(|| format!("Failed to bind Unix socket {:?}", cfg.path))?;
+
+    std::fs::set_permissions(&cfg.path, std::fs::Permissions::from_mode(cfg.mode))
+        .with_context(|| format!("Failed to set permissions of Unix socket {:?}", cfg.path))?;
+
+    info!("Listening on Unix socket {:?}", cfg.path);
+
+    Ok(listener)
+}
+
+/// The socket file isn't removed when the server stops, so it is removed before binding instead.
+/// Anything at the path which isn't a socket, or a socket which another process is still listening
+/// on, is left alone.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    if !metadata.file_type().is_socket() {
+        bail!("{:?} already exists and is not a socket", path);
+    }
+
+    if std::os::unix::net::UnixStream::connect(path).is_ok() {
+        bail!("Another process is already listening on {:?}", path);
+    }
+
+    info!("Removing stale Unix socket {:?}", path);
+    std::fs::remove_file(path).with_context(|| format!("Failed to remove {:?}", path))
+}
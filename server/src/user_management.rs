@@ -1,13 +1,10 @@
 use crate::auth::{FullUser, StaySignedInToken};
-use crate::{set_up_db, Config, UserCommand};
+use crate::{open_db_pool, set_up_db, Config, UserCommand};
 use isixhosa_common::database::db_impl::DbImpl;
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
 use tabled::Table;
 
 pub fn run_command(cfg: Config, command: UserCommand) -> anyhow::Result<()> {
-    let manager = SqliteConnectionManager::file(cfg.database_path);
-    let pool = Pool::new(manager)?;
+    let pool = open_db_pool(&cfg)?;
     set_up_db(&*pool.get()?)?;
     let db = DbImpl(pool);
 
@@ -0,0 +1,64 @@
+//! Validation of submitted forms. Rather than failing the whole submission, each invalid field is
+//! reported back to the user alongside the input they gave.
+
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FieldError {
+    Required,
+    TooLong {
+        max: usize,
+    },
+    /// The anti-abuse challenge wasn't solved
+    ChallengeFailed,
+    /// Written with letters which the language's orthography doesn't use
+    Spelling,
+}
+
+/// Errors for each invalid field, keyed by the field's name in the form
+#[derive(Clone, Debug, Default)]
+pub struct ValidationErrors(HashMap<&'static str, FieldError>);
+
+impl ValidationErrors {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, field: &str) -> Option<FieldError> {
+        self.0.get(field).copied()
+    }
+
+    /// The names of the invalid fields, in no particular order
+    pub fn fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.0.keys().copied()
+    }
+
+    pub fn add(&mut self, field: &'static str, error: FieldError) {
+        // Keep the first error for each field, as it's usually the most relevant
+        self.0.entry(field).or_insert(error);
+    }
+
+    pub fn require(&mut self, field: &'static str, present: bool) {
+        if !present {
+            self.add(field, FieldError::Required);
+        }
+    }
+
+    pub fn require_text(&mut self, field: &'static str, value: &str) {
+        self.require(field, !value.trim().is_empty());
+    }
+
+    pub fn max_length(&mut self, field: &'static str, value: &str, max: usize) {
+        if value.chars().count() > max {
+            self.add(field, FieldError::TooLong { max });
+        }
+    }
+
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
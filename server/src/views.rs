@@ -0,0 +1,116 @@
+//! Word page view counting. Views are buffered in memory and written to the database in batches
+//! so as to not issue a write for every single page load.
+
+use crate::database::views::record_views;
+use crate::fragment_cache::FragmentCache;
+use crate::{spawn_blocking_child, spawn_send_interval};
+use futures::FutureExt;
+use isixhosa_common::database::db_impl::DbImpl;
+use isixhosa_common::database::DbBase;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use warp::Filter;
+use xtra::prelude::*;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Substrings of user agents which are considered to be bots and are not counted
+const BOT_USER_AGENTS: [&str; 8] = [
+    "bot", "crawl", "spider", "slurp", "curl", "wget", "python", "headless",
+];
+
+pub struct ViewCounter {
+    db: DbImpl,
+    pending: HashMap<u64, u64>,
+    /// Trending words are cached, so they need to be rebuilt once new views are written
+    fragments: Arc<FragmentCache>,
+}
+
+impl ViewCounter {
+    pub fn start(db: DbBase, fragments: Arc<FragmentCache>) -> Address<ViewCounter> {
+        let counter = ViewCounter {
+            db: DbImpl(db.primary),
+            pending: HashMap::new(),
+            fragments,
+        };
+
+        xtra::spawn_tokio(counter, Mailbox::bounded(256))
+    }
+}
+
+impl Actor for ViewCounter {
+    type Stop = ();
+
+    async fn started(&mut self, mailbox: &Mailbox<Self>) -> Result<(), ()> {
+        spawn_send_interval(mailbox.address(), FLUSH_INTERVAL, Flush);
+        Ok(())
+    }
+
+    async fn stopped(mut self) {
+        self.flush().await;
+    }
+}
+
+impl ViewCounter {
+    async fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let views = std::mem::take(&mut self.pending);
+        let db = self.db.clone();
+        spawn_blocking_child(move || record_views(&db, views))
+            .await
+            .unwrap();
+        self.fragments.views_changed();
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct RecordView(pub u64);
+
+/// Count a view of an existing word without waiting for the counter. If its mailbox is full, e.g
+/// while views are being written to the database, the view is dropped rather than holding up the
+/// page.
+pub fn record_view(views: &Address<ViewCounter>, word_id: u64) {
+    let _ = views.send(RecordView(word_id)).detach().now_or_never();
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Flush;
+
+impl Handler<RecordView> for ViewCounter {
+    type Return = ();
+
+    async fn handle(&mut self, view: RecordView, _ctx: &mut Context<Self>) {
+        *self.pending.entry(view.0).or_default() += 1;
+    }
+}
+
+impl Handler<Flush> for ViewCounter {
+    type Return = ();
+
+    async fn handle(&mut self, _flush: Flush, _ctx: &mut Context<Self>) {
+        self.flush().await;
+    }
+}
+
+fn is_probably_bot(user_agent: Option<&str>) -> bool {
+    match user_agent {
+        Some(user_agent) => {
+            let user_agent = user_agent.to_lowercase();
+            BOT_USER_AGENTS.iter().any(|bot| user_agent.contains(bot))
+        }
+        None => true,
+    }
+}
+
+/// Extracts whether the request should count towards a word's views
+pub fn counts_as_view() -> impl Filter<Extract = (bool,), Error = Infallible> + Clone {
+    warp::header::optional::<String>("user-agent")
+        .map(|user_agent: Option<String>| !is_probably_bot(user_agent.as_deref()))
+        .or(warp::any().map(|| false))
+        .unify()
+}
@@ -13,7 +13,8 @@ export class LiveSearch {
         create_item_container,
         filter_fn,
         include_own_suggestions,
-        translations
+        translations,
+        create_group = null
     ) {
         this.input = input;
         this.last_value = "";
@@ -24,6 +25,7 @@ export class LiveSearch {
         this.create_item_container = create_item_container;
         this.filter_fn = filter_fn;
         this.translations = translations
+        this.create_group = create_group;
 
         this.id = next_id;
         next_id++;
@@ -99,41 +101,76 @@ export class LiveSearch {
 
             searcher.hits.appendChild(p);
         } else {
-            let container = searcher.create_container();
-
-            results.forEach(function (result) {
-                let item = searcher.create_item(formatResult(searcher.translations, result), result.id, result.is_suggestion);
-                formatResult(searcher.translations, result, item);
+            if (searcher.create_group != null) {
+                groupByPartOfSpeech(results).forEach(function ([part_of_speech, group]) {
+                    let [section, inner] = searcher.create_group(part_of_speech, group.length);
+                    searcher.appendResults(group, inner);
+                    searcher.hits.appendChild(section);
+                });
+            } else {
+                searcher.appendResults(results, searcher.hits);
+            }
 
-                let [item_container_parent, item_container_inner] = searcher.create_item_container(result.id, result.is_suggestion);
-                let append = item;
+            searcher.input.classList.add("has_results");
+        }
+    }
 
-                if (item_container_parent != null) {
-                    item_container_inner.appendChild(item);
-                    append = item_container_parent;
-                }
+    appendResults(results, parent) {
+        let searcher = this;
+        let container = searcher.create_container();
 
-                if (container != null) {
-                    container.appendChild(append);
-                } else {
-                    searcher.hits.appendChild(append);
-                }
+        results.forEach(function (result) {
+            let item = searcher.create_item(formatResult(searcher.translations, result), result.id, result.is_suggestion);
+            formatResult(searcher.translations, result, item);
 
-                searcher.post_create_item(item);
-            });
+            let [item_container_parent, item_container_inner] = searcher.create_item_container(result.id, result.is_suggestion);
+            let append = item;
 
-            searcher.input.classList.add("has_results");
+            if (item_container_parent != null) {
+                item_container_inner.appendChild(item);
+                append = item_container_parent;
+            }
 
             if (container != null) {
-                searcher.hits.appendChild(container);
+                container.appendChild(append);
+            } else {
+                parent.appendChild(append);
             }
+
+            searcher.post_create_item(item);
+        });
+
+        if (container != null) {
+            parent.appendChild(container);
         }
     }
 }
 
+// Groups are in the order of the best ranked result in each, and results keep their order within
+// each group
+function groupByPartOfSpeech(results) {
+    let groups = new Map();
+
+    for (let result of results) {
+        let part_of_speech = result.part_of_speech ?? null;
+
+        if (!groups.has(part_of_speech)) {
+            groups.set(part_of_speech, []);
+        }
+
+        groups.get(part_of_speech).push(result);
+    }
+
+    return Array.from(groups.entries());
+}
+
 export function formatResult(translations, result, elt) {
     if (elt != null) {
-        elt.innerHTML = result.html;
+        if (result.linked_html) {
+            elt.innerHTML = `<span>${result.html}${result.linked_html}</span>`;
+        } else {
+            elt.innerHTML = result.html;
+        }
     } else {
         let div = document.createElement("div");
         div.innerHTML = result.html;
@@ -0,0 +1,77 @@
+// Solves the server's proof of work challenge before a form is submitted. See proof_of_work.rs.
+
+async function leadingZeroBits(text) {
+    let hash = new Uint8Array(await crypto.subtle.digest("SHA-256", new TextEncoder().encode(text)));
+    let bits = 0;
+
+    for (let byte of hash) {
+        if (byte === 0) {
+            bits += 8;
+        } else {
+            bits += Math.clz32(byte) - 24;
+            break;
+        }
+    }
+
+    return bits;
+}
+
+async function solve(challenge, difficultyBits) {
+    for (let nonce = 0; ; nonce++) {
+        if (await leadingZeroBits(`${challenge}:${nonce}`) >= difficultyBits) {
+            return nonce;
+        }
+    }
+}
+
+function setHiddenField(form, name, value) {
+    let input = form.querySelector(`input[name="${name}"]`);
+
+    if (input === null) {
+        input = document.createElement("input");
+        input.type = "hidden";
+        input.name = name;
+        form.appendChild(input);
+    }
+
+    input.value = value;
+}
+
+export function setupProofOfWork(form) {
+    let solving = false;
+
+    form.addEventListener("submit", async function(event) {
+        let submitter = event.submitter;
+
+        // Only the final submission needs a solution, not drafts or previews
+        if (form.dataset.solved === "true" || (submitter && submitter.name === "action" && submitter.value !== "submit")) {
+            return;
+        }
+
+        event.preventDefault();
+
+        if (solving) {
+            return;
+        }
+
+        solving = true;
+
+        try {
+            let response = await fetch("/proof-of-work/challenge");
+            let challenge = await response.json();
+
+            if (challenge !== null) {
+                let nonce = await solve(challenge.challenge, challenge.difficulty_bits);
+                setHiddenField(form, "pow_challenge", challenge.challenge);
+                setHiddenField(form, "pow_nonce", nonce);
+            }
+        } catch (error) {
+            // Submit anyway, and the server will explain what went wrong
+            console.log("Failed to solve challenge: ", error);
+        }
+
+        solving = false;
+        form.dataset.solved = "true";
+        form.requestSubmit(submitter);
+    });
+}
@@ -47,3 +47,57 @@ export function addDuplicateSearchFor(translations, input_id, this_word_id) {
         translations
     );
 }
+
+/* Point out existing nouns which the isiXhosa may be the singular or plural of, which the word should
+   perhaps be linked to rather than submitted as a new entry */
+export function addOtherNumberSearch(this_word_id) {
+    let input = document.getElementById("xhosa");
+    let partOfSpeech = document.getElementById("part_of_speech");
+    let nounClass = document.getElementById("noun_class");
+    let notice = document.getElementById("other_number");
+    let words = notice.querySelector(".other_number_words");
+    let timeout = null;
+
+    function search() {
+        if (partOfSpeech.value !== "noun" || input.value.trim() === "") {
+            notice.hidden = true;
+            return;
+        }
+
+        let params = new URLSearchParams({ xhosa: input.value, noun_class: nounClass.value });
+        fetch(`/search/other_number?${params}`)
+            .then(response => response.json())
+            .then(results => {
+                /* noinspection EqualityComparisonWithCoercionJS -- this is done intentionally for string to number eq */
+                results = results.filter(r => r.is_suggestion || r.id != this_word_id);
+
+                words.replaceChildren(...results.map((result, i) => {
+                    let word;
+                    if (!result.is_suggestion) {
+                        word = document.createElement("a");
+                        word.href = `/word/${result.id}`;
+                        word.rel = "noopener noreferrer";
+                        word.target = "_blank";
+                    } else {
+                        word = document.createElement("span");
+                    }
+
+                    word.innerText = i === 0 ? result.xhosa : `, ${result.xhosa}`;
+                    return word;
+                }));
+
+                notice.hidden = results.length === 0;
+            })
+            .catch(e => console.error(e));
+    }
+
+    function searchSoon() {
+        clearTimeout(timeout);
+        timeout = setTimeout(search, 300);
+    }
+
+    input.addEventListener("input", searchSoon);
+    partOfSpeech.addEventListener("change", searchSoon);
+    nounClass.addEventListener("change", searchSoon);
+    search();
+}
@@ -16,6 +16,194 @@ settings = Settings
     .failure = There was an error saving settings.
     .unsaved = You have unsaved changes.
 
+appearance = Appearance
+    .change = Change the site's appearance and how searches are ranked
+
+sessions = Signed in devices
+    .change = Manage the devices you are signed in on
+    .this-device = This device
+    .is-remembered = You will stay signed in on this device after closing your browser.
+    .is-not-remembered = You will be signed out of this device when you close your browser.
+    .remember = Stay signed in on this device
+    .forget = Sign out when I close my browser
+    .remembered = You will now stay signed in on this device.
+    .forgotten = You will now be signed out when you close your browser.
+    .other-devices = Other devices
+    .other-devices-explanation =
+        If you have signed in on a device which you no longer use or which someone else has access to, you can sign
+        out of it from here.
+    .sign-out-others = Sign out of all other devices
+    .confirm-sign-out-others = Are you sure you want to sign out of all your other devices?
+    .signed-out-others = { $count ->
+        [0] You were not signed in on any other devices.
+        [one] Signed out of one other device.
+        *[other] Signed out of { $count } other devices.
+    }
+
+recovery = Account recovery
+    .change = Set up account recovery
+    .explanation =
+        If you lose access to your Google account, you can still sign in to { site.short-name } with a { $provider }
+        account which you have linked to your account.
+    .sign-in = Sign in with { $provider }
+    .none-linked = You have not linked a { $provider } account yet.
+    .linked = Your account has been linked to a { $provider } account since { $date }.
+    .linked-with-email = Your account has been linked to the { $provider } account { $email } since { $date }.
+    .link = Link a { $provider } account
+    .link-different = Link a different { $provider } account
+    .unlink = Unlink
+    .confirm-unlink = Are you sure you want to unlink this account? You will only be able to sign in with Google.
+    .message = {$message ->
+        [linked] Successfully linked your { $provider } account.
+        [unlinked] Successfully unlinked your { $provider } account.
+        [already-linked] That { $provider } account is already linked to another { site.short-name } account.
+        *[not-linked]
+            That { $provider } account is not linked to any { site.short-name } account. Sign in with Google and
+            link it from your settings first.
+    }
+
+api-keys = API keys
+    .change = Manage your API keys
+    .explanation =
+        API keys let your programs use the high-volume parts of the { site.short-name } API, such as pattern search.
+        Send a key in the X-Api-Key header of each request. Each key can make { $quota } requests per day.
+    .docs = Read the API documentation
+    .none = You have no API keys yet.
+    .name = Name
+    .name-placeholder = What the key is for
+    .created = Created
+    .last-used = Last used
+    .never-used = Never
+    .used-today = Used today
+    .used-total = Used in total
+    .create = Create a key
+    .revoke = Revoke
+    .confirm-revoke = Are you sure you want to revoke this key? Programs using it will stop working.
+    .copy-now = Copy your new key now. It will not be shown again.
+    .at-limit = You can hold at most { $max } keys. Revoke one to create another.
+    .message = {$message ->
+        [created] Your API key was created.
+        [revoked] The API key was revoked.
+        [limit-reached] You can hold at most { $max } keys. Revoke one to create another.
+        *[invalid-name] Give the key a name of at most 64 characters.
+    }
+
+theme = Theme
+    .system = Same as my device
+    .light = Light
+    .dark = Dark
+    .saved-in-browser = Your settings are saved in this browser. Sign in to keep them across devices.
+
+search-direction = Search direction
+    .description = Which language you usually search in. Words matching in that language are shown first.
+    .both = Both { source-language } and { target-language }
+    .english_to_xhosa = { source-language } to { target-language }
+    .xhosa_to_english = { target-language } to { source-language }
+
+school-mode = School mode
+    .description = Hides vulgar and offensive words from searches, word lists, and random words, for using the site with young learners.
+    .enable = Turn on school mode
+    .for-session = School mode stays on until you close your browser.
+    .site-wide = School mode is always on for this site.
+
+notifications = Notifications
+    .none = You have no notifications.
+    .unread = { $count ->
+        [0] Notifications
+        [one] Notifications (one unread)
+        *[other] Notifications ({ $count } unread)
+    }
+    .mark-read = Mark as read
+    .mark-all-read = Mark all as read
+    .suggestion-accepted = Your suggestion { $word } was accepted.
+    .suggestion-rejected = Your suggestion { $word } was rejected.
+
+changes = Recent changes
+    .description = Words recently added to and edited in the { site.short-name } dictionary.
+    .none = There are no changes to show.
+    .filter-all = All changes
+    .filter-added = Added words
+    .filter-edited = Edited words
+    .added = { $word } was added
+    .edited = { $word } was edited
+    .by = by { $user }
+    .anonymous = an anonymous contributor
+    .previous = Previous page
+    .next = Next page
+
+drafts = Drafts
+    .none = You have no saved drafts.
+    .untitled = Untitled draft
+    .last-saved = Last saved { $date }
+    .delete = Delete
+    .confirm-delete = Are you sure you want to delete this draft?
+
+admin = Admin panel
+    .maintenance-on = The site is in maintenance mode. Only administrators can submit or edit words.
+    .maintenance-off = The site is running normally.
+    .enable-maintenance = Enable maintenance mode
+    .disable-maintenance = Disable maintenance mode
+    .maintenance-toggled = Maintenance mode was updated.
+    .search-index = Search index
+    .reindex-text = Rebuild the search index from the database. Searches may be incomplete until this finishes.
+    .reindex = Rebuild search index
+    .reindex-started = The search index is being rebuilt in the background.
+    .search-index-snapshot-text = A snapshot of the search index can be imported into another instance with the import-search-index command while it is stopped, so that it doesn't have to rebuild its index. The other instance's database should be a copy of this one's.
+    .download-search-index-snapshot = Download search index snapshot
+    .config = Configuration
+
+users = Users
+    .search = Search by username or email address
+    .search-button = Search
+    .none = No users found.
+    .id = ID
+    .username = Username
+    .email = Email address
+    .role = Role
+    .locked = Locked
+    .yes = Yes
+    .no = No
+    .contributions = Contributions
+    .attributed-words = Words credited to them
+    .words-added = Suggested words which were added
+    .words-edited = Suggested edits which were accepted
+    .pending-suggestions = Pending word suggestions
+    .suggestions-rejected = Rejected word suggestions
+    .moderation = Moderation
+    .moderation-accepted = Suggestions accepted
+    .moderation-rejected = Suggestions rejected
+    .account = Account
+    .is-self = You can't change your own role or lock your own account.
+    .set-role = Change role
+    .lock = Lock account
+    .unlock = Unlock account
+    .trainee = Trainee moderator
+    .trainee-explanation =
+        Trainee moderators must tick off a checklist before they can accept a suggested word.
+    .make-trainee = Make trainee
+    .end-training = End training
+    .signed-in-devices = { $count ->
+        [0] They aren't staying signed in on any devices.
+        [one] They are staying signed in on one device.
+        *[other] They are staying signed in on { $count } devices.
+    }
+    .sign-out = Sign out on all devices
+    .role-success = Their role was changed.
+    .lock-success = Their account was updated.
+    .trainee-success = Their trainee status was changed.
+    .sign-out-success = They were signed out on all devices.
+    .no-api-keys = They have no API keys.
+    .api-key-quota = Daily quota
+    .set-api-key-quota = Set quota
+    .api-key-quota-success = The key's quota was changed.
+    .api-key-revoke-success = The key was revoked.
+    .update-fail = The user could not be updated.
+
+role = Role
+    .user = User
+    .moderator = Moderator
+    .administrator = Administrator
+
 site-settings = Site settings
     .datasets = Manage datasets
     .datasets-text =
@@ -23,6 +211,131 @@ site-settings = Site settings
         dataset attributions, you can attribute entries to their source properly and beautifully. The datasets that have
         been included in the dictionary can be managed below.
     .no-datasets = There are no datasets. Add one to get started.
+    .bans = Manage bans
+    .imports = Import suggestions
+    .database = Database
+    .checkpoint-text =
+        Write all recent changes into the main database file and, if configured, take a snapshot of it for
+        replication or backup tools. This also happens automatically if a checkpoint interval is configured.
+    .checkpoint = Checkpoint now
+    .checkpoint-success = The database was checkpointed successfully.
+    .checkpoint-fail = The database could not be checkpointed. Check the logs for details.
+    .jobs = Scheduled jobs
+
+provenance = How the suggestion arrived
+    .web-form = Submission form
+    .import = CSV import
+    .api = API
+    .anonymous = Anonymous submission
+
+imports = Import suggestions
+    .explanation =
+        Upload a CSV file to add each of its rows as a suggestion for moderators to review. The first row names the
+        columns, which are the fields of the submission form, e.g. english, xhosa, part_of_speech, noun_class,
+        xhosa_tone_markings, infinitive and note. Rows can edit an existing word by giving its ID in an existing_id
+        column. The suggestions from each file are kept together as a batch.
+    .file = CSV file
+    .upload = Import
+    .imported = { $count ->
+        [one] Imported one suggestion as batch #{ $batch }.
+        *[other] Imported { $count } suggestions as batch #{ $batch }.
+    }
+    .nothing-imported = No suggestions were imported.
+    .skipped = { $count ->
+        [one] One row was skipped:
+        *[other] { $count } rows were skipped:
+    }
+    .unreadable = Line { $line } couldn't be read.
+    .unreadable-field = Line { $line } couldn't be read, because of the { $field } column.
+    .invalid = Line { $line } has invalid fields: { $fields }
+    .batches = Import batches
+    .none = Nothing has been imported yet.
+    .batch = Batch
+    .filename = File
+    .imported-by = Imported by
+    .deleted-user = Deleted user
+    .date = Imported on
+    .pending = Pending
+    .accepted = Accepted
+    .roll-back = Roll back
+    .rolled-back = Rolled back on { $date }
+
+import-rollback = Roll back import
+    .title = Roll back import batch #{ $batch }
+    .batch = Imported from { $filename } on { $date }.
+    .explanation =
+        Rolling back the batch will make the changes listed below. Nothing has been changed yet. Check them, then confirm
+        at the bottom of the page.
+    .nothing = None of the suggestions from this batch are pending or have been accepted, so there is nothing to roll back.
+    .rolled-back-on = This batch was rolled back on { $date }.
+    .pending = { $count ->
+        [one] One pending suggestion will be rejected
+        *[other] { $count } pending suggestions will be rejected
+    }
+    .added = { $count ->
+        [one] One word which the batch added will be deleted
+        *[other] { $count } words which the batch added will be deleted
+    }
+    .edited = { $count ->
+        [one] One word which the batch edited will be put back as it was
+        *[other] { $count } words which the batch edited will be put back as they were
+    }
+    .restored-to = (will be { $xhosa } again)
+    .conflicts-explanation =
+        Some words have been edited since the batch changed them, or how they were before isn't known. They will be
+        left alone, so that later work isn't lost. Fix them by hand if needed.
+    .conflicts = { $count ->
+        [one] One word will be left alone
+        *[other] { $count } words will be left alone
+    }
+    .confirm = Roll back batch
+    .success = Rolled back import batch #{ $batch }.
+    .skipped = Words left alone because they were edited since: { $count }
+    .already-rolled-back = This batch has already been rolled back.
+
+bans = Bans
+    .explanation =
+        Banned users, and anyone using an IP address in a banned range, can't submit or edit words until
+        their ban expires or is lifted.
+    .none = There are no active bans.
+    .target = User ID or IP range
+    .target-placeholder = e.g. 42 or 192.0.2.0/24
+    .reason = Reason
+    .banned-by = Banned by
+    .created = Banned on
+    .expires = Expires
+    .never = Never
+    .lift = Lift ban
+    .add = Add ban
+    .add-success = Successfully added ban.
+    .add-fail = The ban could not be added. Check that the user ID or IP range is valid and that a reason is given.
+    .lift-success = Successfully lifted ban.
+    .lift-fail = The ban could not be lifted. It may have already been lifted.
+
+jobs = Scheduled jobs
+    .none = There are no scheduled jobs. Jobs are only registered once they are enabled in the configuration.
+    .name = Job
+    .schedule = Schedule
+    .every = { $minutes ->
+        [one] Every minute
+        *[other] Every { $minutes } minutes
+    }
+    .daily = Daily at { $time } UTC
+    .last-run = Last run
+    .next-run = Next run
+    .never = Never
+    .status = Status
+    .running = Running
+    .not-run = Not run yet
+    .succeeded = Succeeded
+    .failed = Failed
+    .skipped = { $count ->
+        [one] (one run skipped while still running)
+        *[other] ({ $count } runs skipped while still running)
+    }
+    .run-now = Run now
+    .run-success = The job was started.
+    .run-fail = The job could not be started. It may already be running.
 
 dataset = Dataset
     .datasets = Datasets
@@ -210,15 +523,48 @@ search = Search
     .description = Search for a word in the free, open { site.short-name } dictionary for { target-language } and { source-language }.
     .prompt = Type {{ source-language.indef-article }} or {{ target-language }} word
     .no-results = No results.
+    .external = From other dictionaries
+    .external-explanation = Nothing was found in this dictionary, but these words were found in others. They aren't part of { site.short-name }, and open on the site they come from.
+    .external-source = From { $source }
+    .by-morphemes = Break the word into parts and search for its root
+    .morphemes = The word { $query } is probably:
+    .no-morphemes = The word couldn't be broken into parts with a root in the dictionary, so it was searched for as-is.
+    .random-word = Surprise me with a random word
+    .trending = Trending this week
+    .group = { $part-of-speech } ({ $count })
+    .no-part-of-speech = Other
+    .any-part-of-speech = Any
+    .sort = Sort by
+    .sort-relevance = Best match
+    .sort-xhosa = { target-language } (A-Z)
+    .sort-english = { source-language } (A-Z)
+    .apply-filters = Apply
+    .previous = Previous page
+    .next = Next page
+    .share = Get a short link to this search
+    .shortlink = Short link to this search:
+
+homepage = Home
+    .featured = Featured word
+    .recently-added = Recently added
+    .all-changes = See all recent changes
+    .word-count = { $count ->
+        [one] One word in the dictionary
+        *[other] { $count } words in the dictionary
+    }
+    .no-part-of-speech = No part of speech
 
 submit = Submit a word
     .description = Submit a word to the free, open, online { site.short-name } dictionary for { target-language } and { source-language }.
     .submit-success = Word successfully submitted!
     .submit-fail = There was an error submitting the word.
+    .plural-companion = The plural is probably { $plural }.
+    .plural-companion-link = Submit the plural too
     .check-style = Take a look at the <a href="{ -style-guide-url }">style guide</a> before submitting a word.
     .required-field = Required fields are marked with a <span class="required">*</span>.
     .translation = Translation
     .possible-duplicates = Possible duplicates (hover)
+    .possible-other-number = This may be the singular or plural of an existing word. Consider linking them as plural/singular instead of adding a new entry:
     .select-plural = Plural?
     .select-inchoative = Inchoative?
     .add-example = Add another
@@ -226,6 +572,93 @@ submit = Submit a word
     .submit-new = Suggest word
     .submit-edit-suggestion = Submit edit to suggestion
     .submit-edit = Suggest edit to word
+    .classroom = Classroom
+    .description = Teach with the dictionary: students follow along as the teacher opens word pages.
+    .join = Join a session
+    .code = Session code
+    .start = Start a session
+    .start-explanation = Start a session and give your class its code. Every word page you open will be shown to everyone who joined.
+    .sign-in-to-start = Sign in to start a session of your own.
+    .too-many = There are too many sessions running at the moment. Please try again later.
+    .not-found = There is no session running with the code { $code }.
+    .session-code = Session code: { $code }
+    .participants = { $count ->
+        [one] One student is following along.
+        *[other] { $count } students are following along.
+    }
+    .teacher-instructions = Open any word page, e.g. from a search, and it will be shown to your students.
+    .waiting = Waiting for the teacher to open a word page.
+    .following = Following session { $code }.
+    .leave = Leave
+    .left = You left the session.
+    .session-ended = The teacher has ended this session.
+    .end = End session
+    .confirm-end = Are you sure you want to end this session?
+    .ended = The session has ended.
+
+completeness = Completeness
+    .score = This entry is { $score }% complete.
+    .missing = It's missing:
+    .example = an example
+    .link = a linked word
+    .noun-class = a noun class
+    .improve = Improve this entry
+    .more = Find more entries which need work
+
+needs-improvement = Entries which need work
+    .description = Entries in the dictionary which are missing examples, linked words, or noun classes.
+    .explanation = These entries are missing some parts, with the least complete first. If you'd like to help but don't know where to start, pick one and suggest what it's missing.
+    .none = Every entry is complete!
+    .tasks = See more things to do
+
+tasks = Things to do
+    .description = Small tasks which help improve the dictionary, such as adding words which people couldn't find.
+    .explanation = Each of these is a small, specific thing which would make the dictionary better. Pick one and follow its link to the form where it can be done.
+    .none = There's nothing here right now.
+    .fix = Fill it in
+    .missed-searches = Words people searched for but couldn't find
+    .missed-searches-explanation = Nothing was found when these were searched for. If you know what they mean, please add them.
+    .times-searched = { $count ->
+        [one] (searched once)
+        *[other] (searched { $count } times)
+    }
+    .add-as-english = Add as { source-language }
+    .add-as-target = Add as { target-language }
+    .missing-example = Words with no examples
+    .noun-missing-class = Nouns with no noun class
+    .verb-missing-transitivity = Verbs with no transitivity
+
+preview = Preview
+    .save-draft = Save draft
+    .edit-locked = { $username } is already editing this suggestion. If you submit changes too, one of you may overwrite the other's edits.
+    .draft-saved = Draft saved. You can finish it later from your <a href="/settings">settings</a>.
+    .field-required = This field is required.
+    .field-too-long = This must be at most { $max } characters long.
+    .field-spelling = Only the letters a to z, spaces, hyphens, apostrophes, and brackets can be used here.
+    .challenge-failed = Your browser couldn't confirm this submission. Please make sure JavaScript is enabled and try submitting again.
+
+suggestion-status = Suggestion status
+    .description = Check whether a suggestion to the dictionary has been reviewed yet.
+    .link = You can check whether your suggestion has been accepted at any time with this link:
+    .pending = This suggestion is still waiting to be reviewed by a moderator.
+    .accepted = This suggestion was accepted.
+    .view-word = View the word
+    .accepted-then-deleted = This suggestion was accepted, but the word has since been deleted.
+    .rejected = This suggestion was rejected.
+    .reason = Reason
+    .updated = Last updated { $date }.
+
+preview = Preview
+    .explanation = This is a preview of how the word will look once it has been accepted. It has not been submitted yet.
+    .edit = Keep editing
+    .confirm = Confirm and submit
+    .accept-explanation = This is a preview of how the word will look once you accept the suggestion. Nothing has been changed yet.
+    .address = Address
+    .search-result = In search results
+    .new-homograph-links = These words are spelled the same and will be linked to it as easily confused:
+    .back-to-suggestion = Back to the suggestion
+    .accept = Accept
+    .checklist-required = Go back to the suggestion to tick the checklist before accepting it.
 
 changes = Changes made and why
     .explanation = Briefly explain the changes you made and why.
@@ -246,6 +679,7 @@ wordle = Wordle
 
 moderation = Moderation
     .possible-duplicates = Possible duplicates
+    .possible-other-number = Possible singular or plural, which it could be linked to instead
     .suggestions = Suggestions
     .suggested-words = Suggested words
     .dataset-attributions = Dataset attributions
@@ -257,6 +691,7 @@ moderation = Moderation
     .change-type = Change type
     .word-deleted = Word deleted
     .suggestor = Suggested by
+    .provenance = Submitted through
     .suggested-by = <strong>Suggested by</strong> { $username }
     .changes-summary = Changes summary
     .selected-class = Selected noun class
@@ -267,6 +702,57 @@ moderation = Moderation
     .reject-deletion = Reject deletion
     .edited-examples-and-links = Edited examples and linked words
     .no-suggestions = There are no suggestions to review at this time.
+    .review = Review suggestions
+    .review-remaining = { $count ->
+        [one] One suggested word left to review.
+        *[other] { $count } suggested words left to review.
+    }
+    .review-show-all = Show all suggestions
+    .review-mode = Review suggested words one at a time
+    .review-failed = Something went wrong. Please try again.
+    .suggestion = Suggestion #{ $id }
+    .suggestion-link = Link to this suggestion
+    .preview-accept = Preview accepted word
+    .current-word = Current word
+    .same-word-suggestions = Other suggestions for this word
+    .same-user-suggestions = Other suggestions by this user
+    .pending-dependencies = This suggestion is linked to other suggested words which are still pending. The links will only be added once both words are accepted:
+    .rejected-dependencies = This suggestion was linked to suggested words which have since been rejected, so those links were removed:
+    .accept-dependencies = The accepted word is linked to other suggested words which are still pending. Review them next:
+    .review-dependencies = The accepted word is linked to other suggested words which are still pending. Review them now?
+    .automatic-link = Suggested automatically because the { source-language } glosses overlap by { $percent }%. Check that the words really are related before accepting.
+    .likely-spam = Likely spam ({ $count })
+    .likely-spam-description =
+        These suggestions were flagged as likely spam when they were submitted, so they are kept
+        out of the main queue.
+    .no-likely-spam = There are no suggestions flagged as likely spam.
+    .new-suggestions = { $count ->
+        [one] One new suggestion since this page was loaded.
+        *[other] { $count } new suggestions since this page was loaded.
+    }
+    .queue-changed = Some suggestions have been dealt with since this page was loaded.
+    .refresh = Refresh
+    .spam-reasons = Flagged because
+    .spam-reason-url = Contains a link
+    .spam-reason-repeated = Repeats an earlier submission
+    .spam-reason-nonsense = Looks like nonsense
+    .ban-submitter = Ban submitter
+    .confirm-ban =
+        Are you sure you want to ban { $username }? Their account will be locked and all of their
+        suggestions flagged as likely spam will be rejected.
+    .ban-success = Successfully banned the submitter.
+    .ban-fail = The submitter could not be banned. Moderators can't be banned this way.
+    .checklist = Before accepting, confirm that
+    .checklist-spelling = The { source-language } and { target-language } spellings have been checked
+    .checklist-class = The part of speech and noun class are correct
+    .checklist-duplicate = The word is not already in the dictionary
+    .rejection-reason = Reason for rejecting (optional, shown to the submitter)
+    .pending-for = { $days ->
+        [0] Submitted today
+        [one] Pending for one day
+        *[other] Pending for { $days } days
+    }
+    .overdue = Overdue
     .action-success =
         {$method ->
             [accept] Successfully accepted suggestion.
@@ -303,6 +789,7 @@ word = Word details page
     .suggest-edit = Suggest edit
     .suggest-delete = Suggest deletion
     .confirm-delete = Are you sure you want to suggest this word be deleted?
+    .views = Page views
     .success-message =
         Successfully {$action ->
             [edit] suggested edit
@@ -310,6 +797,16 @@ word = Word details page
         }.
 
         It will be reviewed by moderators shortly, thank you!
+    .homographs = { $count ->
+        [one] There is another entry spelled { $xhosa } with a different meaning:
+       *[other] There are other entries spelled { $xhosa } with different meanings:
+    }
+    .alphabetical-neighbours = Previous and next words alphabetically
+
+spelling = Spelling
+    .header = Words spelled { $xhosa }
+    .description = Every entry spelled this way in the free, open { site.short-name } dictionary.
+    .explanation = { $count } entries are spelled { $xhosa }, each with a different meaning:
 
 transitivity = Transitivity
     .explanation = Whether the verb can take a direct object or not.
@@ -388,6 +885,11 @@ informal = Informal or slang?
     .in-word-result = informal
     .non = non-informal
 
+offensive = Vulgar or offensive?
+    .in-word-result = offensive
+    .non = non-offensive
+    .hidden = This entry is vulgar or offensive. Show it
+
 yes = yes
     .capital = Yes
 no = no
@@ -404,6 +906,38 @@ noun-class = Noun class
 infinitive = Infinitive
     .form = Infinitive form
 
+concords = Concords
+    .explanation =
+        The prefixes and words which agree with a noun of this class, such as the subject concord on a verb whose
+        subject is this noun.
+    .subject = Subject concord
+    .object = Object concord
+    .possessive = Possessive concord
+    .this = This
+    .that = That
+    .yonder = That over there
+
+verb-root = Probable root
+    .explanation =
+        The verb this one is probably derived from, by adding an extension. This is guessed from the shape of the
+        word, so it may be wrong.
+
+verb-extension = Verb extension
+    .causative = causative
+    .applicative = applicative
+    .passive = passive
+    .reciprocal = reciprocal
+
+morpheme = Part of a word
+    .infinitive = infinitive
+    .negative = negative
+    .subject-concord = subject concord
+    .remote-past = subject concord, remote past
+    .tense-marker = tense marker
+    .object-concord = object concord
+    .root = root
+    .ending = ending
+
 inchoative = Inchoative
     .explanation =
         An inchoative (stative) verb takes the perfect tense for present tense meaning. For example, "ndilambile"
@@ -442,13 +976,71 @@ boundmorpheme = bound morpheme
 examples = Example sentences
     .source = { source-language } example
     .target = { target-language } example
+    .from-corpus = From { $corpus }
+    .find-in-corpus = Find examples in the corpus
+
+corpus-examples = Examples from the corpus
+    .description = Sentences from { $corpus } which use { $word }. Attaching one adds it to the word's examples straight away, attributed to the corpus.
+    .not-configured = No example corpus has been set up.
+    .none = No sentences in the corpus use this word.
+    .attach = Attach
+    .back = Back to the word
 
 delete = Delete
 
 contributors = Contributors
 
+reference = Reference
+    .references = References
+    .kind = Kind of work
+    .kind-dictionary = Dictionary
+    .kind-textbook = Textbook
+    .kind-corpus = Corpus
+    .kind-other = Other
+    .author = Author
+    .title = Title
+    .year = Year
+    .publisher = Publisher
+    .pages = Pages
+    .url = URL
+    .no-date = n.d.
+    .page = p. { $pages }
+    .page-range = pp. { $pages }
+    .add = Add reference
+    .delete = Delete reference
+    .confirm-delete = Are you sure that you want to delete this reference?
+    .link-broken = (link broken)
+
+broken-links = Broken links
+    .description = The links given by these references have stopped working. Find a new link for each, or delete the reference if the work can't be found online any more.
+    .count = Broken links in references ({ $count })
+    .none = All of the links given by references are working.
+    .word = Word
+    .since = Broken since
+    .error = Last error
+
 not-found = Page not found
     .sorry = This page was not found. Sorry!
+    .word = There is no word with the ID { $word_id }. It may have been deleted.
+
+banned = Banned
+    .explanation = You have been banned from submitting to the dictionary.
+    .reason = Reason
+    .expires = Your ban expires on { $date }.
+    .permanent = Your ban does not expire.
+
+maintenance = Down for maintenance
+    .explanation =
+        The dictionary is undergoing maintenance, so submissions and edits are paused for now. You can still
+        search and browse words. Please try again later.
+
+bad-request = Invalid request
+    .sorry = The form or link you followed was not valid. Please go back and try again.
+    .invalid-field = The field "{ $field }" was missing or not valid.
+
+server-error = Something went wrong
+    .sorry = An unexpected error occurred while loading this page. Please try again later. Sorry!
+    .reference-code = If you report this, please include the reference code { $code }.
 
 offline = Offline
     .header = You are currently offline
@@ -490,6 +1082,14 @@ license-agreement =
      the <a href="{ -tou-url }">terms of use</a> and under the
      <a href="{ site.license-url }">{ site.license-full }</a>.
 
+terms-acceptance = Accept the terms
+    .description =
+        The terms of use or the license which contributions to { site.short-name } are made under have changed since
+        you last agreed to them. Please read them and agree to continue submitting.
+    .did-not-agree = You must agree to the terms of use and the license to submit to { site.short-name }.
+    .continue = Continue
+    .accepted = Thank you for accepting the terms.
+
 username = Display name
     .explanation = This is the name that others will see you by, and what you will be credited with for your submissions.
     .placeholder = John Doe
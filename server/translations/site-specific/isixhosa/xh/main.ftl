@@ -1,5 +1,8 @@
-site = IsiXhosa.click Isichazi-Magama
-    .short-name = IsiXhosa.click
+-site-name = IsiXhosa.click Isichazi-Magama
+-site-short-name = IsiXhosa.click
+
+site = { -site-name }
+    .short-name = { -site-short-name }
     .license-full = Creative Commons 4.0 Attribution ShareAlike License
     .license-short = CC BY-SA 4.0
     .license-url = https://creativecommons.org/licenses/by-sa/4.0/
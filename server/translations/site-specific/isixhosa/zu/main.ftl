@@ -1,5 +1,8 @@
-site = Isichazamazwi IsiXhosa.click
-    .short-name = IsiXhosa.click
+-site-name = Isichazamazwi IsiXhosa.click
+-site-short-name = IsiXhosa.click
+
+site = { -site-name }
+    .short-name = { -site-short-name }
     .license-full = I-Creative Commons 4.0 Attribution ShareAlike License
     .license-short = CC BY-SA 4.0
     .license-url = https://creativecommons.org/licenses/by-sa/4.0/
@@ -1,5 +1,8 @@
-site = IsiZulu on isiXhosa.click
-    .short-name = IsiZulu on IsiXhosa.click
+-site-name = IsiZulu on isiXhosa.click
+-site-short-name = IsiZulu on IsiXhosa.click
+
+site = { -site-name }
+    .short-name = { -site-short-name }
     .license-full = Creative Commons Attribution-NonCommercial-ShareAlike 4.0 International License
     .license-short = CC BY-NC-SA 4.0
     .license-url = https://creativecommons.org/licenses/by-nc-sa/4.0/
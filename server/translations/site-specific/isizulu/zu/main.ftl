@@ -1,5 +1,8 @@
-site = Isichazamazwi samagama ocwaningozilimi kanye nobucikomagama
-    .short-name = Isichazamazwi samagama ocwaningozilimi kanye nobucikomagama
+-site-name = Isichazamazwi samagama ocwaningozilimi kanye nobucikomagama
+-site-short-name = Isichazamazwi samagama ocwaningozilimi kanye nobucikomagama
+
+site = { -site-name }
+    .short-name = { -site-short-name }
     .license-full = I-Creative Commons Attribution-NonCommercial-ShareAlike 4.0 International License
     .license-short = CC BY-NC-SA 4.0
     .license-url = https://creativecommons.org/licenses/by-nc-sa/4.0/
@@ -7,6 +7,9 @@ use gloo::events::EventListener;
 use isixhosa_common::format::{DisplayHtml, HtmlFormatter};
 use isixhosa_common::i18n::{I18nInfo, SiteContext, TranslationKey};
 use isixhosa_common::i18n_args;
+use isixhosa_common::language::LanguageProfile;
+use isixhosa_common::search_direction::SearchDirection;
+use isixhosa_common::theme::Theme;
 use rand::prelude::*;
 use serde::Deserialize;
 use std::fmt;
@@ -552,10 +555,17 @@ pub async fn start_wordle(host: String, lang: &str) {
 
     let i18n_info = I18nInfo {
         user_language: lang.parse().expect("Invalid locale"),
+        theme: Theme::default(),
+        search_direction: SearchDirection::default(),
+        school_mode: false,
+        flashes: Vec::new(),
+        csp_nonce: String::new(),
         ctx: Arc::new(SiteContext {
             site_i18n: loader,
             supported_langs: &[],
             host: host.to_string(),
+            language: LanguageProfile::default(),
+            school_mode: false,
         }),
     };
 